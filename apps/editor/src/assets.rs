@@ -0,0 +1,579 @@
+use crate::{
+	history::{Edit, EntitySnapshot, History},
+	prefab::{self, Prefab, PREFAB_EXTENSION},
+};
+use hourglass::{
+	app::{log, Context, JobOutput, LoadedAssets, StateResult},
+	assets::{Asset, AssetError, AssetEvent, AssetId, AssetServer, Handle, LoadContext},
+	ecs::{
+		reflect::{Field, Reflect, Value},
+		world::{Entity, World},
+	},
+	math::Transform,
+};
+use std::{
+	collections::HashMap,
+	fs, io,
+	path::{Path, PathBuf},
+	sync::Arc,
+	time::{Duration, Instant, SystemTime},
+};
+
+pub const ASSET_DIR: &str = "assets";
+pub const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+const THUMBNAIL_SIZE: u32 = 64;
+const TILE_SIZE: egui::Vec2 = egui::vec2(80.0, 96.0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetKind {
+	Image,
+	Scene,
+	Prefab,
+	Audio,
+	Other,
+}
+
+impl AssetKind {
+	pub fn from_path(path: &Path) -> Self {
+		let extension = path
+			.extension()
+			.and_then(|extension| extension.to_str())
+			.map(str::to_ascii_lowercase);
+		match extension.as_deref() {
+			Some("png" | "jpg" | "jpeg" | "bmp" | "gif" | "tga") => Self::Image,
+			Some("ron") => Self::Scene,
+			Some(PREFAB_EXTENSION) => Self::Prefab,
+			Some("wav" | "ogg" | "mp3" | "flac") => Self::Audio,
+			_ => Self::Other,
+		}
+	}
+
+	fn title(self) -> &'static str {
+		match self {
+			Self::Image => "Image",
+			Self::Scene => "Scene",
+			Self::Prefab => "Prefab",
+			Self::Audio => "Audio",
+			Self::Other => "File",
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssetEntry {
+	pub path: PathBuf,
+	pub kind: AssetKind,
+	pub size: u64,
+	pub modified: Option<SystemTime>,
+}
+
+impl AssetEntry {
+	fn name(&self) -> String {
+		self.path
+			.file_name()
+			.map_or_else(String::new, |name| name.to_string_lossy().into_owned())
+	}
+}
+
+/// The drag and drop payload of an asset dragged out of the browser
+pub struct AssetPath(pub PathBuf);
+
+// What the asset server loads for the browser
+struct Import {
+	asset: JobOutput,
+	details: String,
+	thumbnail: Option<egui::ColorImage>,
+}
+
+impl Asset for Import {
+	fn load(bytes: Vec<u8>, context: &mut LoadContext) -> Result<Self, AssetError> {
+		import(Path::new(context.path()), bytes).map_err(AssetError::invalid)
+	}
+}
+
+enum ImportStatus {
+	Importing,
+	Imported(String),
+	Failed(String),
+}
+
+/// Lists the files in the asset directory, rescanning it every second and importing
+/// new files into `LoadedAssets` with the app's `AssetServer`, which hot reloads them
+pub struct AssetBrowser {
+	root: PathBuf,
+	entries: Vec<AssetEntry>,
+	selected: Option<PathBuf>,
+	status: HashMap<PathBuf, ImportStatus>,
+	thumbnails: HashMap<PathBuf, egui::TextureHandle>,
+	handles: HashMap<PathBuf, Handle<Import>>,
+	server: Option<AssetServer>,
+	last_scan: Option<Instant>,
+	reimports: Vec<PathBuf>,
+	reloaded: Vec<PathBuf>,
+}
+
+impl Default for AssetBrowser {
+	fn default() -> Self {
+		Self {
+			root: PathBuf::from(ASSET_DIR),
+			entries: Vec::new(),
+			selected: None,
+			status: HashMap::new(),
+			thumbnails: HashMap::new(),
+			handles: HashMap::new(),
+			server: None,
+			last_scan: None,
+			reimports: Vec::new(),
+			reloaded: Vec::new(),
+		}
+	}
+}
+
+impl AssetBrowser {
+	/// Browses another directory, such as when a project is opened
+	pub fn set_root(&mut self, root: PathBuf) {
+		*self = Self {
+			root,
+			..Self::default()
+		};
+	}
+
+	/// Takes the server's events and rescans the asset directory when it is due,
+	/// called every update
+	pub fn watch(&mut self, context: &mut Context, gui: &egui::Context) {
+		if self.server.is_none() {
+			self.server = context.resources.get::<AssetServer>().cloned();
+		}
+		let Some(server) = self.server.clone() else {
+			return;
+		};
+		for path in std::mem::take(&mut self.reimports) {
+			server.reload(&asset_key(&path));
+			self.status.insert(path, ImportStatus::Importing);
+		}
+		for event in server.take_events() {
+			self.complete(context, gui, event);
+		}
+		if self
+			.last_scan
+			.is_some_and(|last_scan| last_scan.elapsed() < WATCH_INTERVAL)
+		{
+			return;
+		}
+		self.last_scan = Some(Instant::now());
+		let entries = match scan(&self.root) {
+			Ok(entries) => entries,
+			Err(error) if error.kind() == io::ErrorKind::NotFound => Vec::new(),
+			Err(error) => {
+				log::warn!("Failed to scan {}: {error}", self.root.display());
+				return;
+			}
+		};
+		let removed = self
+			.entries
+			.iter()
+			.filter(|entry| !entries.iter().any(|new| new.path == entry.path))
+			.map(|entry| entry.path.clone())
+			.collect::<Vec<_>>();
+		for path in removed {
+			self.forget(context, &path);
+		}
+		let changed = changed(&self.entries, &entries)
+			.map(|entry| entry.path.clone())
+			.collect::<Vec<_>>();
+		self.entries = entries;
+		// Modified files are reloaded by the server
+		for path in changed {
+			self.handles
+				.entry(path.clone())
+				.or_insert_with(|| server.load(&asset_key(&path)));
+			self.status.entry(path).or_insert(ImportStatus::Importing);
+		}
+	}
+
+	fn complete(&mut self, context: &mut Context, gui: &egui::Context, event: AssetEvent) {
+		let (id, reloaded) = match event {
+			AssetEvent::Loaded { id, .. } => (id, false),
+			AssetEvent::Reloaded { id, .. } => (id, true),
+			AssetEvent::Failed { id, path, error } => {
+				log::warn!("Failed to import {path}: {error}");
+				if let Some(path) = self.path_of(id) {
+					self.status.insert(path, ImportStatus::Failed(error));
+				}
+				return;
+			}
+			AssetEvent::Unloaded { .. } => return,
+		};
+		let (Some(path), Some(server)) = (self.path_of(id), &self.server) else {
+			return;
+		};
+		let Some(import) = server.get(&self.handles[&path]) else {
+			return;
+		};
+		if context.resources.get::<LoadedAssets>().is_none() {
+			context.resources.insert(LoadedAssets::default());
+		}
+		if let Some(loaded) = context.resources.get_mut::<LoadedAssets>() {
+			let key = asset_key(&path);
+			if reloaded {
+				log::info!("Reloaded {key}");
+				self.reloaded.push(path.clone());
+			}
+			loaded.insert(key, import.asset.clone());
+		}
+		match &import.thumbnail {
+			Some(thumbnail) => {
+				let texture =
+					gui.load_texture(asset_key(&path), thumbnail.clone(), Default::default());
+				self.thumbnails.insert(path.clone(), texture);
+			}
+			None => {
+				self.thumbnails.remove(&path);
+			}
+		}
+		self.status
+			.insert(path, ImportStatus::Imported(import.details.clone()));
+	}
+
+	fn path_of(&self, id: AssetId) -> Option<PathBuf> {
+		self.handles
+			.iter()
+			.find(|(_, handle)| handle.id() == id)
+			.map(|(path, _)| path.clone())
+	}
+
+	/// Assets imported again since the last call after changing on disk
+	pub fn take_reloaded(&mut self) -> Vec<PathBuf> {
+		std::mem::take(&mut self.reloaded)
+	}
+
+	/// The imported images by path, refreshed when they change on disk
+	pub fn textures(&self) -> &HashMap<PathBuf, egui::TextureHandle> {
+		&self.thumbnails
+	}
+
+	pub fn show(&mut self, ui: &mut egui::Ui) {
+		ui.horizontal(|ui| {
+			ui.label(self.root.display().to_string());
+			if ui.button("Refresh").clicked() {
+				self.last_scan = None;
+			}
+		});
+		ui.separator();
+		if !self.root.exists() {
+			ui.weak("The asset directory doesn't exist");
+			if ui.button("Create it").clicked() {
+				if let Err(error) = fs::create_dir_all(&self.root) {
+					log::warn!("Failed to create {}: {error}", self.root.display());
+				}
+				self.last_scan = None;
+			}
+			return;
+		}
+		egui::Panel::bottom("asset_details")
+			.resizable(false)
+			.show(ui, |ui| self.details(ui));
+		let entries = std::mem::take(&mut self.entries);
+		egui::ScrollArea::vertical().show(ui, |ui| {
+			ui.horizontal_wrapped(|ui| {
+				for entry in &entries {
+					self.tile(ui, entry);
+				}
+			});
+		});
+		self.entries = entries;
+	}
+
+	fn tile(&mut self, ui: &mut egui::Ui, entry: &AssetEntry) {
+		let (rect, response) = ui.allocate_exact_size(TILE_SIZE, egui::Sense::click_and_drag());
+		let selected = self.selected.as_ref() == Some(&entry.path);
+		if selected || response.hovered() {
+			let fill = if selected {
+				ui.visuals().selection.bg_fill
+			} else {
+				ui.visuals().widgets.hovered.bg_fill
+			};
+			ui.painter().rect_filled(rect, 4.0, fill);
+		}
+		let image = egui::Rect::from_center_size(
+			egui::pos2(
+				rect.center().x,
+				rect.top() + 4.0 + THUMBNAIL_SIZE as f32 / 2.0,
+			),
+			egui::Vec2::splat(THUMBNAIL_SIZE as f32),
+		);
+		match self.thumbnails.get(&entry.path) {
+			Some(texture) => egui::Image::new(texture).paint_at(ui, image),
+			None => {
+				ui.painter()
+					.rect_filled(image, 4.0, ui.visuals().extreme_bg_color);
+				ui.painter().text(
+					image.center(),
+					egui::Align2::CENTER_CENTER,
+					entry.kind.title(),
+					egui::FontId::proportional(12.0),
+					ui.visuals().weak_text_color(),
+				);
+			}
+		}
+		let name = ui.painter().layout(
+			entry.name(),
+			egui::FontId::proportional(12.0),
+			ui.visuals().text_color(),
+			TILE_SIZE.x - 4.0,
+		);
+		ui.painter().galley(
+			egui::pos2(rect.center().x - name.size().x / 2.0, image.bottom() + 4.0),
+			name,
+			ui.visuals().text_color(),
+		);
+		response.dnd_set_drag_payload(AssetPath(entry.path.clone()));
+		if response.clicked() || response.drag_started() {
+			self.selected = Some(entry.path.clone());
+		}
+		response.context_menu(|ui| {
+			if ui.button("Reimport").clicked() {
+				self.reimports.push(entry.path.clone());
+			}
+		});
+	}
+
+	fn details(&mut self, ui: &mut egui::Ui) {
+		let Some(entry) = self
+			.selected
+			.as_ref()
+			.and_then(|selected| self.entries.iter().find(|entry| &entry.path == selected))
+		else {
+			ui.weak("Select an asset, or drag it into the viewport");
+			return;
+		};
+		egui::Grid::new("asset_metadata")
+			.num_columns(2)
+			.show(ui, |ui| {
+				ui.label("Path");
+				ui.label(entry.path.display().to_string());
+				ui.end_row();
+				ui.label("Kind");
+				ui.label(entry.kind.title());
+				ui.end_row();
+				ui.label("Size");
+				ui.label(format_size(entry.size));
+				ui.end_row();
+				ui.label("Modified");
+				ui.label(entry.modified.map_or_else(
+					|| "Unknown".to_string(),
+					|modified| {
+						let age = modified.elapsed().unwrap_or_default().as_secs();
+						format!("{age} s ago")
+					},
+				));
+				ui.end_row();
+				ui.label("Import");
+				match self.status.get(&entry.path) {
+					Some(ImportStatus::Importing) | None => ui.label("Importing"),
+					Some(ImportStatus::Imported(details)) => ui.label(details),
+					Some(ImportStatus::Failed(error)) => {
+						ui.colored_label(ui.visuals().error_fg_color, error)
+					}
+				};
+				ui.end_row();
+			});
+		if ui.button("Reimport").clicked() {
+			self.reimports.push(entry.path.clone());
+		}
+	}
+
+	// Dropping the handle lets the server unload it
+	fn forget(&mut self, context: &mut Context, path: &Path) {
+		self.handles.remove(path);
+		self.status.remove(path);
+		self.thumbnails.remove(path);
+		if self.selected.as_deref() == Some(path) {
+			self.selected = None;
+		}
+		if let Some(loaded) = context.resources.get_mut::<LoadedAssets>() {
+			loaded.remove(&asset_key(path));
+		}
+	}
+}
+
+/// Creates an entity for the asset with the transform as an undoable edit.
+/// Images are given a `Sprite` showing them and prefabs create an instance.
+pub fn instantiate(
+	world: &mut World,
+	history: &mut History,
+	path: &Path,
+	transform: &Transform,
+) -> Option<Entity> {
+	let created = match AssetKind::from_path(path) {
+		AssetKind::Prefab => prefab::instantiate(world, path, transform),
+		_ => Ok(create_entity(world, path, transform)),
+	};
+	let result = created.and_then(|(entity, edit)| {
+		history.apply(world, edit)?;
+		Ok(entity)
+	});
+	match result {
+		Ok(entity) => Some(entity),
+		Err(error) => {
+			log::warn!("Failed to instantiate {}: {error}", path.display());
+			None
+		}
+	}
+}
+
+fn create_entity(world: &mut World, path: &Path, transform: &Transform) -> (Entity, Edit) {
+	let entity = world.create_entity();
+	let mut components = vec![("Transform", transform.fields())];
+	if AssetKind::from_path(path) == AssetKind::Image {
+		components.push((
+			"Sprite",
+			vec![Field {
+				name: "path",
+				value: Value::Text(asset_key(path)),
+			}],
+		));
+	}
+	let edit = Edit::Create(EntitySnapshot {
+		entity,
+		name: path
+			.file_stem()
+			.map(|stem| stem.to_string_lossy().into_owned()),
+		parent: None,
+		components,
+	});
+	(entity, edit)
+}
+
+// Assets are keyed in `LoadedAssets` by their path with forward slashes
+pub fn asset_key(path: &Path) -> String {
+	path.to_string_lossy().replace('\\', "/")
+}
+
+fn format_size(size: u64) -> String {
+	match size {
+		0..1024 => format!("{size} B"),
+		1024..1_048_576 => format!("{:.1} KiB", size as f64 / 1024.0),
+		_ => format!("{:.1} MiB", size as f64 / 1_048_576.0),
+	}
+}
+
+/// Every file under the directory, sorted by path and skipping hidden files
+pub fn scan(root: &Path) -> io::Result<Vec<AssetEntry>> {
+	let mut entries = Vec::new();
+	let mut directories = vec![root.to_path_buf()];
+	while let Some(directory) = directories.pop() {
+		for item in fs::read_dir(directory)? {
+			let item = item?;
+			if item.file_name().to_string_lossy().starts_with('.') {
+				continue;
+			}
+			let metadata = item.metadata()?;
+			let path = item.path();
+			if metadata.is_dir() {
+				directories.push(path);
+			} else {
+				entries.push(AssetEntry {
+					kind: AssetKind::from_path(&path),
+					size: metadata.len(),
+					modified: metadata.modified().ok(),
+					path,
+				});
+			}
+		}
+	}
+	entries.sort_by(|a, b| a.path.cmp(&b.path));
+	Ok(entries)
+}
+
+// Entries that are new or modified since the previous scan
+fn changed<'a>(
+	previous: &'a [AssetEntry],
+	entries: &'a [AssetEntry],
+) -> impl Iterator<Item = &'a AssetEntry> {
+	entries.iter().filter(|entry| !previous.contains(entry))
+}
+
+// Images are decoded with a thumbnail, scenes are checked to be valid RON,
+// prefabs are parsed and anything else is kept as bytes
+fn import(path: &Path, bytes: Vec<u8>) -> StateResult<Import> {
+	Ok(match AssetKind::from_path(path) {
+		AssetKind::Image => {
+			let image = image::load_from_memory(&bytes)?;
+			let thumbnail = image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgba8();
+			let image = image.to_rgba8();
+			Import {
+				details: format!("{} × {} image", image.width(), image.height()),
+				thumbnail: Some(egui::ColorImage::from_rgba_unmultiplied(
+					[thumbnail.width() as usize, thumbnail.height() as usize],
+					thumbnail.as_raw(),
+				)),
+				asset: Arc::new(image),
+			}
+		}
+		AssetKind::Scene => {
+			let text = String::from_utf8(bytes)?;
+			ron::from_str::<ron::Value>(&text)?;
+			Import {
+				details: "RON data".to_string(),
+				thumbnail: None,
+				asset: Arc::new(text),
+			}
+		}
+		AssetKind::Prefab => {
+			let text = String::from_utf8(bytes)?;
+			let prefab = ron::from_str::<Prefab>(&text)?;
+			Import {
+				details: format!("Prefab of {} entities", prefab.nodes.len()),
+				thumbnail: None,
+				asset: Arc::new(prefab),
+			}
+		}
+		AssetKind::Audio | AssetKind::Other => Import {
+			details: format!("{} raw", format_size(bytes.len() as u64)),
+			thumbnail: None,
+			asset: Arc::new(bytes),
+		},
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn scans_and_imports_assets() -> StateResult<()> {
+		let root = std::env::temp_dir().join("hourglass_editor_assets");
+		let _ = fs::remove_dir_all(&root);
+		fs::create_dir_all(root.join("sprites"))?;
+		image::RgbaImage::new(128, 32).save(root.join("sprites/player.png"))?;
+		fs::write(root.join("level.ron"), "(enemies: 3)")?;
+		fs::write(root.join(".hidden"), "")?;
+
+		let entries = scan(&root)?;
+		let kinds = entries
+			.iter()
+			.map(|entry| (entry.name(), entry.kind))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			kinds,
+			[
+				("level.ron".to_string(), AssetKind::Scene),
+				("player.png".to_string(), AssetKind::Image)
+			]
+		);
+		assert_eq!(changed(&[], &entries).count(), 2);
+		assert_eq!(changed(&entries, &entries).count(), 0);
+
+		let player_path = root.join("sprites/player.png");
+		let player = import(&player_path, fs::read(&player_path)?)?;
+		assert_eq!(player.details, "128 × 32 image");
+		assert_eq!(
+			player.thumbnail.map(|thumbnail| thumbnail.size),
+			Some([64, 16])
+		);
+		assert!(import(&root.join("level.ron"), b"(enemies:".to_vec()).is_err());
+		fs::remove_dir_all(root)?;
+		Ok(())
+	}
+}
@@ -0,0 +1,260 @@
+use crate::viewport::View;
+use hourglass::ecs::world::World;
+
+const ZOOM_SPEED: f32 = 0.002;
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 10.0;
+/// Degrees turned per pixel the pointer moves
+const TURN_SPEED: f32 = 0.3;
+/// World units per second when zoomed to 1
+const FLY_SPEED: f32 = 8.0;
+const FAST_FLY: f32 = 3.0;
+const FLY_KEYS: [egui::Key; 4] = [egui::Key::W, egui::Key::A, egui::Key::S, egui::Key::D];
+const BOOKMARK_KEYS: [egui::Key; 4] = [
+	egui::Key::Num1,
+	egui::Key::Num2,
+	egui::Key::Num3,
+	egui::Key::Num4,
+];
+
+/// Where the viewport looks, and how far it is zoomed and turned
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EditorCamera {
+	/// The world point at the viewport's center
+	pub center: egui::Vec2,
+	pub zoom: f32,
+	/// Counterclockwise, in degrees
+	pub rotation: f32,
+}
+
+impl Default for EditorCamera {
+	fn default() -> Self {
+		Self {
+			center: egui::Vec2::ZERO,
+			zoom: 1.0,
+			rotation: 0.0,
+		}
+	}
+}
+
+impl EditorCamera {
+	/// Turns the view around a world point, which stays put on screen
+	pub fn orbit(&mut self, pivot: egui::Vec2, degrees: f32) {
+		self.center = pivot + rotate(self.center - pivot, degrees);
+		self.rotation += degrees;
+	}
+
+	/// Zooms by a factor, keeping the world point under a screen point where it is
+	pub fn zoom_at(&mut self, view_origin: egui::Pos2, point: egui::Pos2, factor: f32) {
+		let before = self.view(view_origin).to_world(point);
+		self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+		self.center += before - self.view(view_origin).to_world(point);
+	}
+
+	/// Moves by an offset on screen, in pixels
+	pub fn pan(&mut self, view_origin: egui::Pos2, delta: egui::Vec2) {
+		let view = self.view(view_origin);
+		self.center += view.to_world(view_origin) - view.to_world(view_origin + delta);
+	}
+
+	pub fn view(&self, origin: egui::Pos2) -> View {
+		View {
+			origin,
+			camera: *self,
+		}
+	}
+}
+
+/// Rotates a vector counterclockwise, with y pointing up
+pub fn rotate(vector: egui::Vec2, degrees: f32) -> egui::Vec2 {
+	let (sin, cos) = degrees.to_radians().sin_cos();
+	egui::vec2(
+		vector.x * cos - vector.y * sin,
+		vector.x * sin + vector.y * cos,
+	)
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CameraMode {
+	/// The middle button pans and the right button turns around the pointer
+	#[default]
+	Orbit,
+	/// While the right button is held, WASD moves and the pointer turns
+	Fly,
+}
+
+/// Saved cameras, recalled with 1 to 4 and saved with Ctrl held.
+/// Kept as a world resource so each scene has its own.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CameraBookmarks(pub [Option<EditorCamera>; BOOKMARK_KEYS.len()]);
+
+/// Moves the viewport's camera with the mouse and keyboard
+#[derive(Default)]
+pub struct CameraController {
+	pub camera: EditorCamera,
+	pub mode: CameraMode,
+	flying: bool,
+	pivot: Option<egui::Vec2>,
+}
+
+impl CameraController {
+	pub fn toolbar(&mut self, ui: &mut egui::Ui, world: &World) {
+		// Keep the movement keys from switching the gizmo mode while flying
+		if self.flying {
+			ui.input_mut(|input| {
+				for key in FLY_KEYS {
+					input.consume_key(egui::Modifiers::NONE, key);
+					input.consume_key(egui::Modifiers::SHIFT, key);
+				}
+			});
+		}
+		ui.selectable_value(&mut self.mode, CameraMode::Orbit, "Orbit")
+			.on_hover_text("Middle drag pans, right drag orbits the pointer");
+		ui.selectable_value(&mut self.mode, CameraMode::Fly, "Fly")
+			.on_hover_text("Hold the right button to look, and WASD to fly");
+		if ui
+			.button("Reset")
+			.on_hover_text("Reset the camera")
+			.clicked()
+		{
+			self.camera = EditorCamera::default();
+		}
+		ui.separator();
+
+		let mut bookmarks = bookmarks(world);
+		let keyboard = !ui.ctx().egui_wants_keyboard_input();
+		for (slot, key) in BOOKMARK_KEYS.into_iter().enumerate() {
+			let saved = bookmarks.0[slot];
+			let label = egui::RichText::new(format!("{}", slot + 1));
+			let response = ui
+				.add(egui::Button::new(if saved.is_some() {
+					label
+				} else {
+					label.weak()
+				}))
+				.on_hover_text(format!(
+					"Recall bookmark {0}, or save it with Ctrl held ({0})",
+					slot + 1
+				));
+			let command = ui.input(|input| input.modifiers.command);
+			let pressed = keyboard && ui.input(|input| input.key_pressed(key));
+			if response.clicked() || pressed {
+				if command {
+					bookmarks.0[slot] = Some(self.camera);
+					world.resources().borrow_mut().insert(bookmarks.clone());
+				} else if let Some(camera) = saved {
+					self.camera = camera;
+				}
+			}
+		}
+	}
+
+	/// Pans, zooms, orbits and flies with the viewport's response
+	pub fn interact(&mut self, ui: &egui::Ui, response: &egui::Response) {
+		let origin = response.rect.center();
+		if let Some(pointer) = response.hover_pos() {
+			let scroll = ui.input(|input| input.smooth_scroll_delta.y);
+			if scroll != 0.0 {
+				self.camera
+					.zoom_at(origin, pointer, (scroll * ZOOM_SPEED).exp());
+			}
+		}
+		if response.dragged_by(egui::PointerButton::Middle) {
+			self.camera.pan(origin, response.drag_delta());
+		}
+
+		let turning = response.dragged_by(egui::PointerButton::Secondary);
+		let turn = -response.drag_delta().x * TURN_SPEED;
+		match self.mode {
+			CameraMode::Orbit => {
+				self.flying = false;
+				if response.drag_started_by(egui::PointerButton::Secondary) {
+					self.pivot = response
+						.interact_pointer_pos()
+						.map(|pointer| self.camera.view(origin).to_world(pointer));
+				}
+				if !turning {
+					self.pivot = None;
+				}
+				if let Some(pivot) = self.pivot {
+					self.camera.orbit(pivot, turn);
+				}
+			}
+			CameraMode::Fly => {
+				self.flying = turning;
+				if !turning {
+					return;
+				}
+				self.camera.rotation += turn;
+				let (direction, fast, delta) = ui.input(|input| {
+					let axis = |positive, negative| {
+						f32::from(u8::from(input.key_down(positive)))
+							- f32::from(u8::from(input.key_down(negative)))
+					};
+					(
+						egui::vec2(
+							axis(egui::Key::D, egui::Key::A),
+							axis(egui::Key::W, egui::Key::S),
+						),
+						input.modifiers.shift,
+						input.stable_dt,
+					)
+				});
+				let speed = FLY_SPEED / self.camera.zoom * if fast { FAST_FLY } else { 1.0 };
+				self.camera.center += rotate(direction, self.camera.rotation) * speed * delta;
+				ui.ctx().request_repaint();
+			}
+		}
+	}
+
+	/// Centers the view on a world point
+	pub fn focus(&mut self, point: egui::Vec2) {
+		self.camera.center = point;
+	}
+}
+
+fn bookmarks(world: &World) -> CameraBookmarks {
+	world
+		.resources()
+		.borrow()
+		.get::<CameraBookmarks>()
+		.cloned()
+		.unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use hourglass::math::Transform;
+
+	#[test]
+	fn orbiting_and_zooming_keep_their_point_on_screen() {
+		let origin = egui::pos2(200.0, 100.0);
+		let mut camera = EditorCamera {
+			center: egui::vec2(3.0, -1.0),
+			..EditorCamera::default()
+		};
+		let pivot = Transform::from_xyz(5.0, 2.0, 0.0);
+		let before = camera.view(origin).to_screen(&pivot);
+		camera.orbit(egui::vec2(5.0, 2.0), 90.0);
+		let after = camera.view(origin).to_screen(&pivot);
+		assert!(before.distance(after) < 1e-3);
+
+		let pointer = egui::pos2(260.0, 40.0);
+		let under = camera.view(origin).to_world(pointer);
+		camera.zoom_at(origin, pointer, 2.0);
+		assert_eq!(camera.zoom, 2.0);
+		assert!((camera.view(origin).to_world(pointer) - under).length() < 1e-3);
+
+		camera.zoom_at(origin, pointer, 1000.0);
+		assert_eq!(camera.zoom, MAX_ZOOM);
+
+		// Panning drags the world along with the pointer
+		let grabbed = camera.view(origin).to_world(pointer);
+		camera.pan(origin, egui::vec2(10.0, -5.0));
+		let moved = camera
+			.view(origin)
+			.to_world(pointer + egui::vec2(10.0, -5.0));
+		assert!((moved - grabbed).length() < 1e-3);
+	}
+}
@@ -0,0 +1,117 @@
+use hourglass::app::{
+	log::{Level, LevelFilter},
+	LogBuffer, LogRecord,
+};
+
+/// The app's most recent log records, filtered by level and searched by text.
+/// Clicking a record copies it.
+pub struct Console {
+	logs: LogBuffer,
+	level: LevelFilter,
+	search: String,
+}
+
+impl Default for Console {
+	fn default() -> Self {
+		Self::new(LogBuffer::default())
+	}
+}
+
+impl Console {
+	pub fn new(logs: LogBuffer) -> Self {
+		Self {
+			logs,
+			level: LevelFilter::Trace,
+			search: String::new(),
+		}
+	}
+
+	pub fn show(&mut self, ui: &mut egui::Ui) {
+		ui.horizontal(|ui| {
+			egui::ComboBox::from_id_salt("console_level")
+				.selected_text(self.level.to_string())
+				.show_ui(ui, |ui| {
+					for level in LevelFilter::iter() {
+						ui.selectable_value(&mut self.level, level, level.to_string());
+					}
+				});
+			ui.add(egui::TextEdit::singleline(&mut self.search).hint_text("Search"));
+			if ui.button("Clear").clicked() {
+				self.logs.clear();
+			}
+		});
+		ui.separator();
+
+		egui::ScrollArea::vertical()
+			.auto_shrink(false)
+			.stick_to_bottom(true)
+			.show(ui, |ui| {
+				for record in self.logs.items() {
+					if self.matches(&record) {
+						record_line(ui, &record);
+					}
+				}
+			});
+	}
+
+	fn matches(&self, record: &LogRecord) -> bool {
+		let search = self.search.to_lowercase();
+		record.level <= self.level
+			&& (record.message.to_lowercase().contains(&search)
+				|| record.target.to_lowercase().contains(&search))
+	}
+}
+
+/// A record colored by its level, copied when clicked
+pub fn record_line(ui: &mut egui::Ui, record: &LogRecord) {
+	let color = match record.level {
+		Level::Error => ui.visuals().error_fg_color,
+		Level::Warn => ui.visuals().warn_fg_color,
+		_ => ui.visuals().text_color(),
+	};
+	let line = format!(
+		"{:>8.3} {:<5} {}: {}",
+		record.elapsed.as_secs_f64(),
+		record.level,
+		record.target,
+		record.message
+	);
+	let text = egui::RichText::new(&line).color(color).monospace();
+	let response = ui
+		.add(egui::Label::new(text).sense(egui::Sense::click()))
+		.on_hover_text("Click to copy");
+	if response.clicked() {
+		ui.ctx().copy_text(line);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+
+	fn record(level: Level, message: &str) -> LogRecord {
+		LogRecord {
+			level,
+			target: "editor".to_string(),
+			message: message.to_string(),
+			elapsed: Duration::ZERO,
+		}
+	}
+
+	#[test]
+	fn records_are_filtered_by_level_and_search() {
+		let mut console = Console {
+			level: LevelFilter::Warn,
+			..Default::default()
+		};
+		assert!(console.matches(&record(Level::Error, "Failed to load")));
+		assert!(!console.matches(&record(Level::Info, "Loaded")));
+
+		console.search = "LOAD".to_string();
+		assert!(console.matches(&record(Level::Warn, "Slow load")));
+		assert!(!console.matches(&record(Level::Warn, "Slow frame")));
+		console.search = "edit".to_string();
+		assert!(console.matches(&record(Level::Warn, "Slow frame")));
+	}
+}
@@ -1,35 +1,375 @@
-use hourglass::app::{
-	async_trait::async_trait, log, AppEvent, Context, State, StateResult, Transition, WorkerRequest,
+use crate::{
+	assets::{self, AssetBrowser, AssetKind},
+	console::Console,
+	gui::Gui,
+	hierarchy::Hierarchy,
+	history::History,
+	inspector::Inspector,
+	layout::{Layout, Tab},
+	play::{FixedDelta, PlayState, Simulation},
+	prefab,
+	profiler::Profiler,
+	project::{Project, ProjectSettings, RecentProjects, StartScreen},
+	remote::Remote,
+	script::Script,
+	settings::{Keybindings, Preferences, Settings},
+	viewport::Viewport,
 };
+use hourglass::{
+	app::{
+		async_trait::async_trait, log, AppEvent, Context, Frame, State, StateResult, Transition,
+		WindowId,
+	},
+	ecs::world::World,
+};
+use std::time::{Duration, Instant};
+
+/// Redoes alongside the redo keybinding
+const REDO_SHIFT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(
+	egui::Modifiers::COMMAND.plus(egui::Modifiers::SHIFT),
+	egui::Key::Z,
+);
+
+/// Shows the start screen until a project is opened, then the docked panels
+#[derive(Default)]
+pub struct Editor {
+	gui: Gui,
+	settings: Settings,
+	panels: Panels,
+	start: StartScreen,
+	autosaved: Option<Instant>,
+}
 
+/// The contents of each tab, drawn on the world thread
 #[derive(Default)]
-pub struct Editor;
+pub struct Panels {
+	viewport: Viewport,
+	hierarchy: Hierarchy,
+	inspector: Inspector,
+	console: Console,
+	history: History,
+	simulation: Simulation,
+	profiler: Profiler,
+	assets: AssetBrowser,
+	remote: Remote,
+	project: ProjectSettings,
+	script: Script,
+	preferences: Preferences,
+	keybindings: Keybindings,
+}
+
+impl Panels {
+	pub fn show(&mut self, ui: &mut egui::Ui, world: &mut World, tab: Tab) {
+		match tab {
+			Tab::Viewport => {
+				self.viewport
+					.selection
+					.clone_from(&self.hierarchy.selection);
+				self.viewport.textures.clone_from(self.assets.textures());
+				self.viewport.keybindings = self.keybindings;
+				self.viewport.show(ui, world, &mut self.history);
+				self.hierarchy
+					.selection
+					.clone_from(&self.viewport.selection);
+				if let Some((path, transform)) = self.viewport.dropped.take() {
+					if let Some(created) =
+						assets::instantiate(world, &mut self.history, &path, &transform)
+					{
+						self.hierarchy.selection.select(created);
+					}
+				}
+			}
+			Tab::Hierarchy => self.hierarchy.show(ui, world, &mut self.history),
+			Tab::Inspector => {
+				self.inspector
+					.selection
+					.clone_from(&self.hierarchy.selection);
+				self.inspector.show(ui, world, &mut self.history);
+			}
+			Tab::Console => self.console.show(ui),
+			Tab::Script => {
+				self.script.show(ui, world, &mut self.history);
+				if let Some(selected) = self.script.selected.take() {
+					self.hierarchy.selection.set(selected);
+				}
+			}
+			Tab::History => {
+				if let Err(error) = self.history.show(ui, world) {
+					log::warn!("Failed to move through the history: {error}");
+				}
+			}
+			Tab::Profiler => self.profiler.show(ui),
+			Tab::Assets => self.assets.show(ui),
+			Tab::Remote => self.remote.show(ui),
+			Tab::Project => {
+				if let Some(project) = self.project.show(ui) {
+					self.assets.set_root(project.asset_root.clone());
+					self.hierarchy.asset_root = project.asset_root.clone();
+				}
+			}
+		}
+	}
+
+	// Textures are refreshed by the asset browser, while changed prefabs update their instances
+	fn reload_assets(&mut self, world: &mut World) {
+		for path in self.assets.take_reloaded() {
+			if AssetKind::from_path(&path) != AssetKind::Prefab {
+				continue;
+			}
+			let result = prefab::reload(world, &path)
+				.and_then(|edit| edit.map_or(Ok(()), |edit| self.history.apply(world, edit)));
+			if let Err(error) = result {
+				log::warn!("Failed to reload {}: {error}", path.display());
+			}
+		}
+	}
+
+	pub fn undo(&mut self, world: &mut World) {
+		if let Err(error) = self.history.undo(world) {
+			log::warn!("Failed to undo: {error}");
+		}
+	}
+
+	pub fn redo(&mut self, world: &mut World) {
+		if let Err(error) = self.history.redo(world) {
+			log::warn!("Failed to redo: {error}");
+		}
+	}
+
+	// Focused text fields keep the shortcuts for their own undo
+	fn shortcuts(&mut self, ui: &mut egui::Ui, world: &mut World) {
+		if ui.ctx().egui_wants_keyboard_input() || self.preferences.is_rebinding() {
+			return;
+		}
+		let Keybindings { undo, redo, .. } = self.keybindings;
+		if ui
+			.input_mut(|input| input.consume_shortcut(&REDO_SHIFT) || input.consume_shortcut(&redo))
+		{
+			self.redo(world);
+		} else if ui.input_mut(|input| input.consume_shortcut(&undo)) {
+			self.undo(world);
+		}
+	}
+}
+
+fn menu_bar(ui: &mut egui::Ui, settings: &mut Settings, panels: &mut Panels, world: &mut World) {
+	egui::MenuBar::new().ui(ui, |ui| {
+		ui.menu_button("File", |ui| {
+			if ui.button("Project settings").clicked() {
+				settings.layout.open(Tab::Project);
+			}
+			if ui.button("Preferences").clicked() {
+				panels.preferences.open = true;
+			}
+			if ui.button("Close project").clicked() {
+				save_settings(settings);
+				panels.project.project = None;
+			}
+		});
+		ui.menu_button("Edit", |ui| {
+			let Keybindings { undo, redo, .. } = panels.keybindings;
+			let undo = egui::Button::new("Undo").shortcut_text(ui.ctx().format_shortcut(&undo));
+			if ui.add_enabled(panels.history.can_undo(), undo).clicked() {
+				panels.undo(world);
+			}
+			let redo = egui::Button::new("Redo").shortcut_text(ui.ctx().format_shortcut(&redo));
+			if ui.add_enabled(panels.history.can_redo(), redo).clicked() {
+				panels.redo(world);
+			}
+		});
+		ui.menu_button("View", |ui| {
+			for tab in Tab::ALL {
+				if ui.button(tab.title()).clicked() {
+					settings.layout.open(tab);
+				}
+			}
+			ui.separator();
+			if ui.button("Reset layout").clicked() {
+				settings.layout = Layout::default();
+			}
+		});
+		ui.separator();
+		panels.simulation.toolbar(ui, world, &mut panels.history);
+		if panels.simulation.state() != PlayState::Editing {
+			ui.colored_label(
+				ui.visuals().warn_fg_color,
+				"Changes made while playing are undone on stop",
+			);
+		}
+	});
+}
+
+fn save_settings(settings: &Settings) {
+	if let Err(error) = settings.save() {
+		log::warn!("Failed to save the editor settings: {error}");
+	}
+}
+
+impl Editor {
+	// Saves the open project and the settings, including the layout, every few minutes
+	fn autosave(&mut self) {
+		let interval = Duration::from_secs(u64::from(self.settings.autosave_minutes) * 60);
+		if interval.is_zero()
+			|| self
+				.autosaved
+				.is_some_and(|autosaved| autosaved.elapsed() < interval)
+		{
+			return;
+		}
+		self.autosaved = Some(Instant::now());
+		if let Some(project) = &self.panels.project.project {
+			if let Err(error) = project.save() {
+				log::warn!("Failed to autosave the project: {error}");
+			}
+		}
+		save_settings(&self.settings);
+		log::debug!("Autosaved");
+	}
+
+	fn show_start_screen(&mut self, context: &mut Context) -> StateResult<()> {
+		let input = self.gui.take_input();
+		let mut opened = None;
+		let output = self.gui.context().run_ui(input, |ui| {
+			egui::CentralPanel::default().show(ui, |ui| opened = self.start.show(ui));
+		});
+		self.gui.render(output)?;
+		if let Some(project) = opened {
+			self.open_project(context, project);
+		}
+		Ok(())
+	}
+
+	// Paths in the editor are relative to the project, which it works from while open
+	fn open_project(&mut self, context: &mut Context, project: Project) {
+		if let Err(error) = std::env::set_current_dir(project.directory()) {
+			log::warn!("Failed to open {}: {error}", project.directory().display());
+			return;
+		}
+		self.start.recent.add(project.directory());
+		if let Err(error) = self.start.recent.save() {
+			log::warn!("Failed to save the recent projects: {error}");
+		}
+		self.autosaved = Some(Instant::now());
+		self.panels.assets.set_root(project.asset_root.clone());
+		self.panels.hierarchy.asset_root = project.asset_root.clone();
+		if let Err(error) = context.set_title(
+			WindowId::PRIMARY,
+			format!("{} - Hourglass Editor", project.name),
+		) {
+			log::warn!("Failed to set the window title: {error}");
+		}
+		log::info!("Opened the project {}", project.directory().display());
+		self.panels.project.open(project);
+	}
+}
 
 #[async_trait]
 impl State<Context, AppEvent> for Editor {
+	fn label(&self) -> String {
+		"Editor".to_string()
+	}
+
+	async fn on_start(&mut self, context: &mut Context) -> StateResult<()> {
+		self.panels.console = Console::new(context.logs().clone());
+		self.start.recent = RecentProjects::load();
+		self.settings = Settings::load();
+		Ok(())
+	}
+
+	async fn on_stop(&mut self, _context: &mut Context) -> StateResult<()> {
+		self.settings.save()
+	}
+
+	async fn on_resume_app(&mut self, context: &mut Context) -> StateResult<()> {
+		if let Some(window) = context.window(WindowId::PRIMARY) {
+			self.gui.attach(window, context.present_mode()).await?;
+		}
+		Ok(())
+	}
+
+	async fn on_suspend_app(&mut self, _context: &mut Context) -> StateResult<()> {
+		self.gui.detach();
+		Ok(())
+	}
+
+	async fn on_render(&mut self, context: &mut Context, _frame: Frame) -> StateResult<()> {
+		self.panels.profiler.record(context);
+		self.gui.set_present_mode(context.present_mode());
+		let gui = self.gui.context().clone();
+		if gui.options(|options| options.theme_preference) != self.settings.theme {
+			gui.set_theme(self.settings.theme);
+		}
+		if self.panels.project.project.is_none() {
+			return self.show_start_screen(context);
+		}
+		let Some(world) = context.world().cloned() else {
+			return Ok(());
+		};
+		// The world lives on its own thread, so the panels are drawn there
+		let input = self.gui.take_input();
+		let mut settings = std::mem::take(&mut self.settings);
+		let mut panels = std::mem::take(&mut self.panels);
+		panels.keybindings = settings.keybindings;
+		let (settings, panels, output) = world
+			.with(move |world| {
+				panels.reload_assets(world);
+				let output = gui.run_ui(input, |ui| {
+					panels.shortcuts(ui, world);
+					egui::Panel::top("menu")
+						.show(ui, |ui| menu_bar(ui, &mut settings, &mut panels, world));
+					if panels.preferences.show(ui.ctx(), &mut settings) {
+						save_settings(&settings);
+					}
+					egui::CentralPanel::no_frame()
+						.show(ui, |ui| settings.layout.show(ui, &mut panels, world));
+				});
+				(settings, panels, output)
+			})
+			.await?;
+		self.settings = settings;
+		self.panels = panels;
+		self.gui.render(output)
+	}
+
+	async fn on_fixed_update(
+		&mut self,
+		context: &mut Context,
+		delta: f64,
+	) -> StateResult<Transition<Context, AppEvent>> {
+		let Some(world) = context.world().cloned() else {
+			return Ok(Transition::None);
+		};
+		if !self.panels.simulation.tick() {
+			return Ok(Transition::None);
+		}
+		world
+			.with(move |world| world.resources().borrow_mut().insert(FixedDelta(delta)))
+			.await?;
+		if let Err(error) = context.run_world_systems().await {
+			log::error!("A system failed, pausing the simulation: {error}");
+			self.panels.simulation.pause();
+		}
+		Ok(Transition::None)
+	}
+
 	async fn update(
 		&mut self,
-		_context: &mut Context,
+		context: &mut Context,
 	) -> StateResult<Transition<Context, AppEvent>> {
-		log::info!("Hi!");
+		if self.panels.project.project.is_some() {
+			self.panels.assets.watch(context, self.gui.context());
+			self.autosave();
+		}
+		self.panels.remote.poll();
 		Ok(Transition::None)
 	}
 
 	async fn on_event(
 		&mut self,
-		context: &mut Context,
+		_context: &mut Context,
 		event: &mut AppEvent,
 	) -> StateResult<Transition<Context, AppEvent>> {
-		match event {
-			AppEvent::Resized { width, height } => {
-				log::info!("width: {width} height: {height}");
-				Ok(Transition::None)
-			}
-			AppEvent::Exit => {
-				log::info!("Finalizing...");
-				context.app_proxy.send_event(WorkerRequest::Exit)?;
-				Ok(Transition::None)
-			}
-		}
+		self.gui.handle_event(event);
+		Ok(Transition::None)
 	}
 }
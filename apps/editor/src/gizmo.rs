@@ -0,0 +1,388 @@
+use crate::{settings::Keybindings, viewport::View};
+use hourglass::math::{Quat, Transform, Vec3};
+
+const HANDLE_LENGTH: f32 = 64.0;
+const HANDLE_SIZE: f32 = 8.0;
+const RING_RADIUS: f32 = 48.0;
+const PICK_DISTANCE: f32 = 6.0;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GizmoMode {
+	#[default]
+	Translate,
+	Rotate,
+	Scale,
+}
+
+/// Which axes translation follows. Scaling always follows the entity's own axes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Space {
+	#[default]
+	World,
+	Local,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Snapping {
+	pub enabled: bool,
+	/// In world units
+	pub translate: f32,
+	/// In degrees
+	pub rotate: f32,
+	pub scale: f32,
+}
+
+impl Default for Snapping {
+	fn default() -> Self {
+		Self {
+			enabled: false,
+			translate: 0.5,
+			rotate: 15.0,
+			scale: 0.25,
+		}
+	}
+}
+
+impl Snapping {
+	fn snap(&self, value: f32, step: f32) -> f32 {
+		if self.enabled && step > 0.0 {
+			(value / step).round() * step
+		} else {
+			value
+		}
+	}
+}
+
+/// The selected entity's transform and its parent's world transform
+#[derive(Clone, Copy)]
+pub struct Target {
+	pub parent: Transform,
+	pub local: Transform,
+}
+
+impl Target {
+	fn world(&self) -> Transform {
+		self.parent.mul_transform(&self.local)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Handle {
+	X,
+	Y,
+	Center,
+	Ring,
+}
+
+// The target and pointer as they were when the drag started
+struct Drag {
+	handle: Handle,
+	target: Target,
+	pointer: egui::Pos2,
+}
+
+/// Handles in the viewport that translate, rotate or scale the selected entity
+#[derive(Default)]
+pub struct Gizmo {
+	pub mode: GizmoMode,
+	pub space: Space,
+	pub snapping: Snapping,
+	drag: Option<Drag>,
+}
+
+impl Gizmo {
+	pub fn is_dragging(&self) -> bool {
+		self.drag.is_some()
+	}
+
+	pub fn toolbar(&mut self, ui: &mut egui::Ui, keybindings: &Keybindings) {
+		for (mode, label, shortcut) in [
+			(GizmoMode::Translate, "Move", keybindings.translate),
+			(GizmoMode::Rotate, "Rotate", keybindings.rotate),
+			(GizmoMode::Scale, "Scale", keybindings.scale),
+		] {
+			ui.selectable_value(&mut self.mode, mode, label)
+				.on_hover_text(ui.ctx().format_shortcut(&shortcut));
+			if !ui.ctx().egui_wants_keyboard_input()
+				&& ui.input_mut(|input| input.consume_shortcut(&shortcut))
+			{
+				self.mode = mode;
+			}
+		}
+		ui.separator();
+		let space = match self.space {
+			Space::World => "World",
+			Space::Local => "Local",
+		};
+		if ui.button(space).on_hover_text("Toggle the space").clicked() {
+			self.space = match self.space {
+				Space::World => Space::Local,
+				Space::Local => Space::World,
+			};
+		}
+		ui.separator();
+		ui.checkbox(&mut self.snapping.enabled, "Snap");
+		ui.add_enabled_ui(self.snapping.enabled, |ui| {
+			let snapping = &mut self.snapping;
+			ui.add(
+				egui::DragValue::new(&mut snapping.translate)
+					.speed(0.05)
+					.range(0.0..=100.0),
+			)
+			.on_hover_text("Translation step");
+			ui.add(
+				egui::DragValue::new(&mut snapping.rotate)
+					.range(0.0..=180.0)
+					.suffix("°"),
+			)
+			.on_hover_text("Rotation step");
+			ui.add(
+				egui::DragValue::new(&mut snapping.scale)
+					.speed(0.01)
+					.range(0.0..=10.0),
+			)
+			.on_hover_text("Scale step");
+		});
+	}
+
+	/// Starts, continues or ends a drag on a handle with the viewport's response,
+	/// returning the target's new local transform while dragging
+	pub fn interact(
+		&mut self,
+		response: &egui::Response,
+		view: &View,
+		target: &Target,
+	) -> Option<Transform> {
+		if response.drag_started() {
+			// Dragging starts once the pointer has moved, so handles are picked where it was pressed
+			let pointer = response
+				.ctx
+				.input(|input| input.pointer.press_origin())
+				.or(response.interact_pointer_pos())?;
+			self.drag = self.pick(view, target, pointer).map(|handle| Drag {
+				handle,
+				target: *target,
+				pointer,
+			});
+		}
+		if response.drag_stopped() || !response.dragged() {
+			self.drag = None;
+			return None;
+		}
+		let pointer = response.interact_pointer_pos()?;
+		Some(self.drag_to(self.drag.as_ref()?, view, pointer))
+	}
+
+	pub fn paint(&self, painter: &egui::Painter, view: &View, target: &Target) {
+		let center = view.to_screen(&target.world());
+		let active = self.drag.as_ref().map(|drag| drag.handle);
+		let color = |handle, color: egui::Color32| {
+			if active == Some(handle) {
+				egui::Color32::YELLOW
+			} else {
+				color
+			}
+		};
+		let x_color = color(Handle::X, egui::Color32::from_rgb(230, 80, 80));
+		let y_color = color(Handle::Y, egui::Color32::from_rgb(80, 200, 80));
+		let center_color = color(Handle::Center, egui::Color32::from_rgb(90, 140, 240));
+		match self.mode {
+			GizmoMode::Rotate => {
+				painter.circle_stroke(
+					center,
+					RING_RADIUS,
+					egui::Stroke::new(2.0, color(Handle::Ring, center_color)),
+				);
+				let (x, _) = self.screen_axes(view, target, Space::Local);
+				painter.line_segment(
+					[center, center + x * RING_RADIUS],
+					egui::Stroke::new(1.0, x_color),
+				);
+			}
+			GizmoMode::Translate | GizmoMode::Scale => {
+				let (x, y) = self.screen_axes(view, target, self.axes_space());
+				for (axis, color) in [(x, x_color), (y, y_color)] {
+					let end = center + axis * HANDLE_LENGTH;
+					painter.line_segment([center, end], egui::Stroke::new(2.0, color));
+					if self.mode == GizmoMode::Translate {
+						painter.circle_filled(end, HANDLE_SIZE / 2.0 + 1.0, color);
+					} else {
+						painter.rect_filled(
+							egui::Rect::from_center_size(end, egui::Vec2::splat(HANDLE_SIZE)),
+							0.0,
+							color,
+						);
+					}
+				}
+				painter.rect_filled(
+					egui::Rect::from_center_size(center, egui::Vec2::splat(HANDLE_SIZE)),
+					0.0,
+					center_color,
+				);
+			}
+		}
+	}
+
+	fn axes_space(&self) -> Space {
+		match self.mode {
+			GizmoMode::Scale => Space::Local,
+			_ => self.space,
+		}
+	}
+
+	// Unit axes on screen, where y points down
+	fn screen_axes(&self, view: &View, target: &Target, space: Space) -> (egui::Vec2, egui::Vec2) {
+		let (x, y) = world_axes(target, space);
+		(view.to_screen_direction(x), view.to_screen_direction(y))
+	}
+
+	fn pick(&self, view: &View, target: &Target, pointer: egui::Pos2) -> Option<Handle> {
+		let center = view.to_screen(&target.world());
+		if self.mode == GizmoMode::Rotate {
+			let distance = (pointer.distance(center) - RING_RADIUS).abs();
+			return (distance <= PICK_DISTANCE).then_some(Handle::Ring);
+		}
+		if egui::Rect::from_center_size(center, egui::Vec2::splat(HANDLE_SIZE + PICK_DISTANCE))
+			.contains(pointer)
+		{
+			return Some(Handle::Center);
+		}
+		let (x, y) = self.screen_axes(view, target, self.axes_space());
+		[(Handle::X, x), (Handle::Y, y)]
+			.into_iter()
+			.find(|(_, axis)| {
+				let along = (pointer - center).dot(*axis).clamp(0.0, HANDLE_LENGTH);
+				pointer.distance(center + *axis * along) <= PICK_DISTANCE
+			})
+			.map(|(handle, _)| handle)
+	}
+
+	// Measured from where the drag started, so snapping doesn't drift
+	fn drag_to(&self, drag: &Drag, view: &View, pointer: egui::Pos2) -> Transform {
+		let snapping = &self.snapping;
+		let target = &drag.target;
+		let start = target.world();
+		let mut local = target.local;
+		match self.mode {
+			GizmoMode::Translate => {
+				let delta = view.to_world(pointer) - view.to_world(drag.pointer);
+				let (x_axis, y_axis) = world_axes(target, self.space);
+				let along_x = match drag.handle {
+					Handle::Y => 0.0,
+					_ => snapping.snap(delta.dot(x_axis), snapping.translate),
+				};
+				let along_y = match drag.handle {
+					Handle::X => 0.0,
+					_ => snapping.snap(delta.dot(y_axis), snapping.translate),
+				};
+				let moved = egui::vec2(start.translation.x, start.translation.y)
+					+ x_axis * along_x
+					+ y_axis * along_y;
+				local.translation = target.parent.inverse_transform_point(Vec3::new(
+					moved.x,
+					moved.y,
+					start.translation.z,
+				));
+			}
+			GizmoMode::Rotate => {
+				let center = view.to_screen(&start);
+				let angle = |point: egui::Pos2| {
+					let offset = point - center;
+					(-offset.y).atan2(offset.x).to_degrees()
+				};
+				let turned =
+					(angle(pointer) - angle(drag.pointer) + 180.0).rem_euclid(360.0) - 180.0;
+				let turned = snapping.snap(turned, snapping.rotate).to_radians();
+				local.rotate(Quat::from_rotation_z(turned));
+			}
+			GizmoMode::Scale => {
+				let delta = (pointer - drag.pointer) / HANDLE_LENGTH;
+				let (x_axis, y_axis) = self.screen_axes(view, target, Space::Local);
+				let (scale_x, scale_y) = match drag.handle {
+					Handle::X => (Some(delta.dot(x_axis)), None),
+					Handle::Y => (None, Some(delta.dot(y_axis))),
+					_ => {
+						let uniform = delta.dot((x_axis + y_axis).normalized());
+						(Some(uniform), Some(uniform))
+					}
+				};
+				let scale =
+					|scale: f32, amount: f32| snapping.snap(scale * (1.0 + amount), snapping.scale);
+				if let Some(amount) = scale_x {
+					local.scale.x = scale(local.scale.x, amount);
+				}
+				if let Some(amount) = scale_y {
+					local.scale.y = scale(local.scale.y, amount);
+				}
+			}
+		}
+		local
+	}
+}
+
+// Unit axes in world space, where y points up
+fn world_axes(target: &Target, space: Space) -> (egui::Vec2, egui::Vec2) {
+	let rotation = match space {
+		Space::World => 0.0,
+		Space::Local => target.world().angle_z(),
+	};
+	let (sin, cos) = rotation.sin_cos();
+	(egui::vec2(cos, sin), egui::vec2(-sin, cos))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::camera::EditorCamera;
+
+	fn drag(gizmo: &Gizmo, handle: Handle, target: &Target, to: egui::Vec2) -> Transform {
+		let view = View {
+			origin: egui::Pos2::ZERO,
+			camera: EditorCamera::default(),
+		};
+		let center = view.to_screen(&target.world());
+		let drag = Drag {
+			handle,
+			target: *target,
+			pointer: center + egui::vec2(RING_RADIUS, 0.0),
+		};
+		gizmo.drag_to(&drag, &view, drag.pointer + to)
+	}
+
+	#[test]
+	fn handles_edit_the_local_transform() {
+		// The parent is turned a quarter counterclockwise, so its local x points up
+		let target = Target {
+			parent: Transform::from_xyz(10.0, 0.0, 0.0)
+				.with_rotation(Quat::from_rotation_z(90_f32.to_radians())),
+			local: Transform::default(),
+		};
+		let mut gizmo = Gizmo::default();
+
+		// Dragging right in world space moves along the parent's negative y
+		let moved = drag(&gizmo, Handle::X, &target, egui::vec2(40.0, 25.0));
+		let moved = moved.translation;
+		assert!(moved.x.abs() < 1e-4 && (moved.y + 1.25).abs() < 1e-4);
+
+		gizmo.space = Space::Local;
+		gizmo.snapping.enabled = true;
+		// The local x axis points up on screen, and 1.25 units snaps to 1.5
+		let moved = drag(&gizmo, Handle::X, &target, egui::vec2(0.0, -40.0));
+		let moved = moved.translation;
+		assert!((moved.x - 1.5).abs() < 1e-4 && moved.y.abs() < 1e-4);
+
+		gizmo.mode = GizmoMode::Rotate;
+		let turned = drag(
+			&gizmo,
+			Handle::Ring,
+			&target,
+			egui::vec2(-RING_RADIUS, -RING_RADIUS),
+		);
+		assert!((turned.angle_z().to_degrees() - 90.0).abs() < 1e-4);
+
+		gizmo.mode = GizmoMode::Scale;
+		// The local y axis points left on screen
+		let scaled = drag(&gizmo, Handle::Y, &target, egui::vec2(-HANDLE_LENGTH, 0.0));
+		assert_eq!((scaled.scale.x, scaled.scale.y), (1.0, 2.0));
+	}
+}
@@ -0,0 +1,399 @@
+use hourglass::app::{
+	log,
+	winit::{
+		event::{ElementState, MouseButton, MouseScrollDelta},
+		keyboard::KeyCode,
+		window::Window,
+	},
+	AppEvent, PresentMode, StateResult, TextComposition,
+};
+use std::{sync::Arc, time::Instant};
+
+/// Draws egui on a window with wgpu, with input translated from `AppEvent`s
+pub struct Gui {
+	context: egui::Context,
+	events: Vec<egui::Event>,
+	modifiers: egui::Modifiers,
+	pointer: egui::Pos2,
+	started: Instant,
+	renderer: Option<Renderer>,
+	clipboard: Option<arboard::Clipboard>,
+}
+
+impl Default for Gui {
+	fn default() -> Self {
+		Self {
+			context: egui::Context::default(),
+			events: Vec::new(),
+			modifiers: egui::Modifiers::default(),
+			pointer: egui::Pos2::ZERO,
+			started: Instant::now(),
+			renderer: None,
+			clipboard: None,
+		}
+	}
+}
+
+impl Gui {
+	pub fn context(&self) -> &egui::Context {
+		&self.context
+	}
+
+	/// Creates the surface for the window, called when the app resumes
+	pub async fn attach(
+		&mut self,
+		window: Arc<Window>,
+		present_mode: PresentMode,
+	) -> StateResult<()> {
+		self.renderer = Some(Renderer::new(window, present_mode).await?);
+		Ok(())
+	}
+
+	/// Reconfigures the surface when the present mode changes
+	pub fn set_present_mode(&mut self, present_mode: PresentMode) {
+		if let Some(renderer) = self.renderer.as_mut() {
+			renderer.set_present_mode(present_mode);
+		}
+	}
+
+	/// Drops the surface, called when the app is suspended
+	pub fn detach(&mut self) {
+		self.renderer = None;
+	}
+
+	pub fn handle_event(&mut self, event: &AppEvent) {
+		let modifiers = self.modifiers;
+		let event = match event {
+			AppEvent::Resized { width, height, .. } => {
+				if let Some(renderer) = self.renderer.as_mut() {
+					renderer.resize(*width, *height);
+				}
+				return;
+			}
+			AppEvent::FocusChanged { focused, .. } => egui::Event::WindowFocused(*focused),
+			AppEvent::CursorMoved {
+				logical_x,
+				logical_y,
+				..
+			} => {
+				self.pointer = egui::pos2(*logical_x as f32, *logical_y as f32);
+				egui::Event::PointerMoved(self.pointer)
+			}
+			AppEvent::MouseButton { button, state, .. } => {
+				let Some(button) = pointer_button(*button) else {
+					return;
+				};
+				egui::Event::PointerButton {
+					pos: self.pointer,
+					button,
+					pressed: *state == ElementState::Pressed,
+					modifiers,
+				}
+			}
+			AppEvent::MouseWheel { delta, .. } => {
+				let (unit, delta) = match delta {
+					MouseScrollDelta::LineDelta(x, y) => {
+						(egui::MouseWheelUnit::Line, egui::vec2(*x, *y))
+					}
+					MouseScrollDelta::PixelDelta(position) => (
+						egui::MouseWheelUnit::Point,
+						egui::vec2(position.x as f32, position.y as f32) / self.pixels_per_point(),
+					),
+				};
+				egui::Event::MouseWheel {
+					unit,
+					delta,
+					phase: egui::TouchPhase::Move,
+					modifiers,
+				}
+			}
+			AppEvent::KeyboardInput {
+				keycode: Some(keycode),
+				state,
+				..
+			} => {
+				let pressed = *state == ElementState::Pressed;
+				if self.update_modifiers(*keycode, pressed) {
+					egui::Event::ModifiersChanged(self.modifiers)
+				} else {
+					let Some(key) = key(*keycode) else {
+						return;
+					};
+					if pressed && self.modifiers.command {
+						if let Some(event) = self.clipboard_event(key) {
+							self.events.push(event);
+							return;
+						}
+					}
+					egui::Event::Key {
+						key,
+						physical_key: Some(key),
+						pressed,
+						repeat: false,
+						modifiers,
+					}
+				}
+			}
+			AppEvent::TextInput {
+				text,
+				composition: TextComposition::Committed,
+				..
+			} => egui::Event::Text(text.clone()),
+			_ => return,
+		};
+		self.events.push(event);
+	}
+
+	/// The input gathered since the last frame, for `egui::Context::run_ui`
+	pub fn take_input(&mut self) -> egui::RawInput {
+		let pixels_per_point = self.pixels_per_point();
+		let size = self
+			.renderer
+			.as_ref()
+			.map_or(egui::Vec2::ZERO, Renderer::size);
+		let mut input = egui::RawInput {
+			screen_rect: Some(egui::Rect::from_min_size(
+				egui::Pos2::ZERO,
+				size / pixels_per_point,
+			)),
+			time: Some(self.started.elapsed().as_secs_f64()),
+			events: std::mem::take(&mut self.events),
+			focused: true,
+			..Default::default()
+		};
+		input
+			.viewports
+			.entry(egui::ViewportId::ROOT)
+			.or_default()
+			.native_pixels_per_point = Some(pixels_per_point);
+		input
+	}
+
+	pub fn render(&mut self, output: egui::FullOutput) -> StateResult<()> {
+		for command in &output.platform_output.commands {
+			if let egui::OutputCommand::CopyText(text) = command {
+				let copied = self.clipboard().map(|clipboard| clipboard.set_text(text));
+				if let Some(Err(error)) = copied {
+					log::warn!("Failed to copy to the clipboard: {error}");
+				}
+			}
+		}
+		let Some(renderer) = self.renderer.as_mut() else {
+			return Ok(());
+		};
+		let paint_jobs = self
+			.context
+			.tessellate(output.shapes, output.pixels_per_point);
+		renderer.render(&paint_jobs, &output.textures_delta, output.pixels_per_point)
+	}
+
+	fn clipboard_event(&mut self, key: egui::Key) -> Option<egui::Event> {
+		match key {
+			egui::Key::C => Some(egui::Event::Copy),
+			egui::Key::X => Some(egui::Event::Cut),
+			egui::Key::V => self.clipboard()?.get_text().ok().map(egui::Event::Paste),
+			_ => None,
+		}
+	}
+
+	// Opened on first use, since it may not be available
+	fn clipboard(&mut self) -> Option<&mut arboard::Clipboard> {
+		if self.clipboard.is_none() {
+			self.clipboard = arboard::Clipboard::new()
+				.map_err(|error| log::warn!("Failed to open the clipboard: {error}"))
+				.ok();
+		}
+		self.clipboard.as_mut()
+	}
+
+	fn pixels_per_point(&self) -> f32 {
+		self.renderer
+			.as_ref()
+			.map_or(1.0, |renderer| renderer.window.scale_factor() as f32)
+	}
+
+	// Returns whether the key was a modifier
+	fn update_modifiers(&mut self, keycode: KeyCode, pressed: bool) -> bool {
+		let modifier = match keycode {
+			KeyCode::ShiftLeft | KeyCode::ShiftRight => &mut self.modifiers.shift,
+			KeyCode::ControlLeft | KeyCode::ControlRight => &mut self.modifiers.ctrl,
+			KeyCode::AltLeft | KeyCode::AltRight => &mut self.modifiers.alt,
+			KeyCode::SuperLeft | KeyCode::SuperRight => &mut self.modifiers.mac_cmd,
+			_ => return false,
+		};
+		*modifier = pressed;
+		self.modifiers.command = if cfg!(target_os = "macos") {
+			self.modifiers.mac_cmd
+		} else {
+			self.modifiers.ctrl
+		};
+		true
+	}
+}
+
+fn pointer_button(button: MouseButton) -> Option<egui::PointerButton> {
+	Some(match button {
+		MouseButton::Left => egui::PointerButton::Primary,
+		MouseButton::Right => egui::PointerButton::Secondary,
+		MouseButton::Middle => egui::PointerButton::Middle,
+		MouseButton::Back => egui::PointerButton::Extra1,
+		MouseButton::Forward => egui::PointerButton::Extra2,
+		MouseButton::Other(_) => return None,
+	})
+}
+
+// winit names keys such as `KeyA` and `Digit1` where egui uses `A` and `1`
+fn key(keycode: KeyCode) -> Option<egui::Key> {
+	let name = format!("{keycode:?}");
+	let name = name
+		.strip_prefix("Key")
+		.or_else(|| name.strip_prefix("Digit"))
+		.unwrap_or(&name);
+	egui::Key::from_name(name)
+}
+
+struct Renderer {
+	window: Arc<Window>,
+	surface: wgpu::Surface<'static>,
+	device: wgpu::Device,
+	queue: wgpu::Queue,
+	config: wgpu::SurfaceConfiguration,
+	present_mode: PresentMode,
+	supported_present_modes: Vec<wgpu::PresentMode>,
+	egui: egui_wgpu::Renderer,
+}
+
+impl Renderer {
+	async fn new(window: Arc<Window>, present_mode: PresentMode) -> StateResult<Self> {
+		let instance = wgpu::Instance::default();
+		let surface = instance.create_surface(window.clone())?;
+		let adapter = instance
+			.request_adapter(&wgpu::RequestAdapterOptions {
+				compatible_surface: Some(&surface),
+				..Default::default()
+			})
+			.await?;
+		let (device, queue) = adapter
+			.request_device(&wgpu::DeviceDescriptor::default())
+			.await?;
+		let size = window.inner_size();
+		let mut config = surface
+			.get_default_config(&adapter, size.width.max(1), size.height.max(1))
+			.ok_or("The window surface isn't supported by the adapter")?;
+		let supported_present_modes = surface.get_capabilities(&adapter).present_modes;
+		config.present_mode = surface_present_mode(present_mode, &supported_present_modes);
+		surface.configure(&device, &config);
+		let egui = egui_wgpu::Renderer::new(&device, config.format, Default::default());
+		Ok(Self {
+			window,
+			surface,
+			device,
+			queue,
+			config,
+			present_mode,
+			supported_present_modes,
+			egui,
+		})
+	}
+
+	fn set_present_mode(&mut self, present_mode: PresentMode) {
+		if present_mode == self.present_mode {
+			return;
+		}
+		self.present_mode = present_mode;
+		self.config.present_mode =
+			surface_present_mode(present_mode, &self.supported_present_modes);
+		self.surface.configure(&self.device, &self.config);
+	}
+
+	fn size(&self) -> egui::Vec2 {
+		egui::vec2(self.config.width as f32, self.config.height as f32)
+	}
+
+	fn resize(&mut self, width: u32, height: u32) {
+		if width == 0 || height == 0 {
+			return;
+		}
+		self.config.width = width;
+		self.config.height = height;
+		self.surface.configure(&self.device, &self.config);
+	}
+
+	fn render(
+		&mut self,
+		paint_jobs: &[egui::ClippedPrimitive],
+		textures_delta: &egui::TexturesDelta,
+		pixels_per_point: f32,
+	) -> StateResult<()> {
+		for (id, deltas) in &textures_delta.set {
+			for delta in deltas {
+				self.egui
+					.update_texture(&self.device, &self.queue, *id, delta);
+			}
+		}
+
+		let frame = match self.surface.get_current_texture() {
+			wgpu::CurrentSurfaceTexture::Success(frame)
+			| wgpu::CurrentSurfaceTexture::Suboptimal(frame) => frame,
+			wgpu::CurrentSurfaceTexture::Outdated | wgpu::CurrentSurfaceTexture::Lost => {
+				self.surface.configure(&self.device, &self.config);
+				return Ok(());
+			}
+			_ => return Ok(()),
+		};
+		let view = frame.texture.create_view(&Default::default());
+		let screen = egui_wgpu::ScreenDescriptor {
+			size_in_pixels: [self.config.width, self.config.height],
+			pixels_per_point,
+		};
+		let mut encoder = self.device.create_command_encoder(&Default::default());
+		let commands =
+			self.egui
+				.update_buffers(&self.device, &self.queue, &mut encoder, paint_jobs, &screen);
+		{
+			let mut pass = encoder
+				.begin_render_pass(&wgpu::RenderPassDescriptor {
+					label: Some("gui"),
+					color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+						view: &view,
+						depth_slice: None,
+						resolve_target: None,
+						ops: wgpu::Operations {
+							load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+							store: wgpu::StoreOp::Store,
+						},
+					})],
+					..Default::default()
+				})
+				.forget_lifetime();
+			self.egui.render(&mut pass, paint_jobs, &screen);
+		}
+		self.queue
+			.submit(commands.into_iter().chain([encoder.finish()]));
+		self.queue.present(frame);
+
+		for id in &textures_delta.free {
+			self.egui.free_texture(id);
+		}
+		Ok(())
+	}
+}
+
+// The surface's present mode, falling back to vsync when the surface doesn't support it
+fn surface_present_mode(
+	present_mode: PresentMode,
+	supported: &[wgpu::PresentMode],
+) -> wgpu::PresentMode {
+	let present_mode = match present_mode {
+		PresentMode::AutoVsync => return wgpu::PresentMode::AutoVsync,
+		PresentMode::AutoNoVsync => return wgpu::PresentMode::AutoNoVsync,
+		PresentMode::Fifo => wgpu::PresentMode::Fifo,
+		PresentMode::Mailbox => wgpu::PresentMode::Mailbox,
+		PresentMode::Immediate => wgpu::PresentMode::Immediate,
+	};
+	if supported.contains(&present_mode) {
+		present_mode
+	} else {
+		wgpu::PresentMode::AutoVsync
+	}
+}
@@ -0,0 +1,255 @@
+use crate::{
+	assets::ASSET_DIR,
+	history::{registry, Edit, EntitySnapshot, History},
+	prefab,
+	search::EntityQuery,
+	selection::Selection,
+};
+use hourglass::{
+	app::log,
+	ecs::{
+		hierarchy,
+		world::{Entity, World},
+	},
+};
+use std::{collections::HashSet, path::PathBuf};
+
+enum Action {
+	Create(Option<Entity>),
+	Delete(Entity),
+	Reparent(Entity, Option<Entity>),
+	SavePrefab(Entity),
+}
+
+// The entities matching the search, and those shown for them with their ancestors
+struct Found {
+	matches: HashSet<Entity>,
+	shown: HashSet<Entity>,
+}
+
+/// A tree of the world's entities. Entities are selected by clicking, with Ctrl
+/// held to select several, reparented by dragging, and created, deleted or saved
+/// as prefabs from the context menu. Dragging or deleting a selected entity takes
+/// the rest of the selection along. Prefab instances are shown in the hyperlink color.
+/// While searching, only matches and their ancestors are shown.
+pub struct Hierarchy {
+	pub selection: Selection,
+	/// Where prefabs are saved, under `prefabs`
+	pub asset_root: PathBuf,
+	query: EntityQuery,
+	found: Option<Found>,
+	actions: Vec<Action>,
+}
+
+impl Default for Hierarchy {
+	fn default() -> Self {
+		Self {
+			selection: Selection::default(),
+			asset_root: PathBuf::from(ASSET_DIR),
+			query: EntityQuery::default(),
+			found: None,
+			actions: Vec::new(),
+		}
+	}
+}
+
+impl Hierarchy {
+	pub fn show(&mut self, ui: &mut egui::Ui, world: &mut World, history: &mut History) {
+		self.search(ui, world);
+		ui.separator();
+
+		egui::ScrollArea::vertical().show(ui, |ui| {
+			for root in hierarchy::roots(world) {
+				self.entity_node(ui, world, root);
+			}
+
+			// The space below the tree creates root entities and takes drops to unparent
+			let response = ui.allocate_response(ui.available_size(), egui::Sense::click());
+			if let Some(dragged) = response.dnd_release_payload::<Entity>() {
+				self.actions.push(Action::Reparent(*dragged, None));
+			}
+			response.context_menu(|ui| {
+				if ui.button("Create entity").clicked() {
+					self.actions.push(Action::Create(None));
+				}
+			});
+		});
+
+		for action in std::mem::take(&mut self.actions) {
+			self.apply(action, world, history);
+		}
+	}
+
+	fn search(&mut self, ui: &mut egui::Ui, world: &World) {
+		let registry = registry(world);
+		self.query.show(ui, &registry);
+		if self.query.is_empty() {
+			self.found = None;
+			return;
+		}
+		let results = self.query.results(world, &registry);
+		ui.horizontal(|ui| {
+			ui.weak(format!("{} found", results.len()));
+			if ui
+				.add_enabled(!results.is_empty(), egui::Button::new("Select all"))
+				.clicked()
+			{
+				self.selection.set(results.iter().copied());
+			}
+		});
+		let mut shown = HashSet::new();
+		for result in &results {
+			let mut entity = Some(*result);
+			while let Some(current) = entity.filter(|current| shown.insert(*current)) {
+				entity = hierarchy::parent(world, current);
+			}
+		}
+		self.found = Some(Found {
+			matches: results.into_iter().collect(),
+			shown,
+		});
+	}
+
+	fn is_shown(&self, entity: Entity) -> bool {
+		self.found
+			.as_ref()
+			.is_none_or(|found| found.shown.contains(&entity))
+	}
+
+	fn entity_node(&mut self, ui: &mut egui::Ui, world: &World, entity: Entity) {
+		if !self.is_shown(entity) {
+			return;
+		}
+		let mut children = hierarchy::children(world, entity);
+		children.retain(|child| self.is_shown(*child));
+		if children.is_empty() {
+			ui.horizontal(|ui| {
+				ui.add_space(ui.spacing().indent);
+				self.entity_label(ui, world, entity);
+			});
+			return;
+		}
+		let id = ui.make_persistent_id(("hierarchy", entity));
+		egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, true)
+			.show_header(ui, |ui| self.entity_label(ui, world, entity))
+			.body(|ui| {
+				for child in children {
+					self.entity_node(ui, world, child);
+				}
+			});
+	}
+
+	fn entity_label(&mut self, ui: &mut egui::Ui, world: &World, entity: Entity) {
+		let mut label = egui::RichText::new(entity_label(world, entity));
+		if prefab::instance_of(world, entity).is_some() {
+			label = label.color(ui.visuals().hyperlink_color);
+		}
+		if self
+			.found
+			.as_ref()
+			.is_some_and(|found| !found.matches.contains(&entity))
+		{
+			label = label.weak();
+		}
+		let response = ui
+			.selectable_label(self.selection.contains(entity), label)
+			.interact(egui::Sense::drag());
+		if response.clicked() {
+			self.selection
+				.click(entity, ui.input(|input| input.modifiers));
+		}
+		response.dnd_set_drag_payload(entity);
+		if let Some(dragged) = response.dnd_release_payload::<Entity>() {
+			self.actions.push(Action::Reparent(*dragged, Some(entity)));
+		}
+		response.context_menu(|ui| {
+			if ui.button("Create child").clicked() {
+				self.actions.push(Action::Create(Some(entity)));
+			}
+			if hierarchy::parent(world, entity).is_some() && ui.button("Unparent").clicked() {
+				self.actions.push(Action::Reparent(entity, None));
+			}
+			if ui.button("Save as prefab").clicked() {
+				self.actions.push(Action::SavePrefab(entity));
+			}
+			if ui.button("Delete").clicked() {
+				self.actions.push(Action::Delete(entity));
+			}
+		});
+	}
+
+	fn apply(&mut self, action: Action, world: &mut World, history: &mut History) {
+		let edit = match action {
+			Action::Create(parent) => {
+				let entity = world.create_entity();
+				self.selection.select(entity);
+				Edit::Create(EntitySnapshot {
+					entity,
+					name: Some(format!("Entity {}", entity.index())),
+					parent,
+					components: Vec::new(),
+				})
+			}
+			Action::Delete(entity) => {
+				let entities = self.affected(world, entity);
+				let edits = entities
+					.iter()
+					.map(|entity| Edit::delete(world, *entity))
+					.collect();
+				Edit::batch(format!("Delete {} entities", entities.len()), edits)
+			}
+			// Entities can't move under themselves or their descendants
+			Action::Reparent(entity, new) => {
+				let edits = self
+					.affected(world, entity)
+					.into_iter()
+					.filter(|entity| {
+						new.is_none_or(|parent| {
+							parent != *entity && !hierarchy::is_descendant(world, parent, *entity)
+						})
+					})
+					.map(|entity| Edit::Reparent {
+						entity,
+						old: hierarchy::parent(world, entity),
+						new,
+					})
+					.collect::<Vec<_>>();
+				if edits.is_empty() {
+					return;
+				}
+				Edit::batch(format!("Reparent {} entities", edits.len()), edits)
+			}
+			Action::SavePrefab(entity) => {
+				let path = prefab::new_prefab_path(&self.asset_root, world, entity);
+				match prefab::create(world, entity, &path) {
+					Ok(edit) => {
+						log::info!("Saved the prefab {}", path.display());
+						edit
+					}
+					Err(error) => {
+						log::warn!("Failed to save the prefab {}: {error}", path.display());
+						return;
+					}
+				}
+			}
+		};
+		if let Err(error) = history.apply(world, edit) {
+			log::warn!("Failed to edit the hierarchy: {error}");
+		}
+		self.selection.retain_existing(world);
+	}
+
+	// The whole selection when the entity is part of it
+	fn affected(&self, world: &World, entity: Entity) -> Vec<Entity> {
+		if self.selection.contains(entity) {
+			self.selection.roots(world)
+		} else {
+			vec![entity]
+		}
+	}
+}
+
+/// The entity's name, or its index when it has none
+pub fn entity_label(world: &World, entity: Entity) -> String {
+	hierarchy::name(world, entity).unwrap_or_else(|| format!("Entity {}", entity.index()))
+}
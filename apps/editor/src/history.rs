@@ -0,0 +1,555 @@
+use hourglass::ecs::{
+	error::Result,
+	hierarchy::{self, Name, Parent},
+	reflect::{ComponentInfo, ComponentRegistry, Field},
+	world::{Entity, World},
+};
+
+/// A reversible change to the world made from the editor
+#[derive(Debug, Clone)]
+pub enum Edit {
+	SetField {
+		entity: Entity,
+		component: &'static str,
+		old: Field,
+		new: Field,
+	},
+	/// Sets several fields of a component at once, such as a gizmo moving a transform
+	SetComponent {
+		entity: Entity,
+		component: &'static str,
+		old: Vec<Field>,
+		new: Vec<Field>,
+	},
+	/// Adds a component with its default values
+	AddComponent {
+		entity: Entity,
+		component: &'static str,
+	},
+	/// Keeps the component's fields to put them back on undo
+	RemoveComponent {
+		entity: Entity,
+		component: &'static str,
+		fields: Vec<Field>,
+	},
+	Rename {
+		entity: Entity,
+		old: String,
+		new: String,
+	},
+	/// Created entities are restored from the snapshot on redo
+	Create(EntitySnapshot),
+	/// Parents come before their children
+	Delete(Vec<EntitySnapshot>),
+	Reparent {
+		entity: Entity,
+		old: Option<Entity>,
+		new: Option<Entity>,
+	},
+	/// Edits applied in order and undone in reverse, as one step
+	Batch { label: String, edits: Vec<Edit> },
+}
+
+impl Edit {
+	/// Deletes the entity along with its descendants
+	pub fn delete(world: &World, entity: Entity) -> Self {
+		let registry = registry(world);
+		let mut snapshots = Vec::new();
+		let mut pending = vec![entity];
+		while let Some(entity) = pending.pop() {
+			snapshots.push(EntitySnapshot::take(world, &registry, entity));
+			pending.extend(hierarchy::children(world, entity));
+		}
+		Self::Delete(snapshots)
+	}
+
+	/// The edits as one step, or the edit itself when there is only one
+	pub fn batch(label: impl Into<String>, mut edits: Vec<Self>) -> Self {
+		if edits.len() == 1 {
+			return edits.remove(0);
+		}
+		Self::Batch {
+			label: label.into(),
+			edits,
+		}
+	}
+
+	pub fn remove_component(world: &World, entity: Entity, info: &ComponentInfo) -> Self {
+		Self::RemoveComponent {
+			entity,
+			component: info.name,
+			fields: info.fields(world, entity).unwrap_or_default(),
+		}
+	}
+
+	pub fn label(&self) -> String {
+		match self {
+			Self::SetField { component, new, .. } => format!("Set {component}.{}", new.name),
+			Self::SetComponent { component, .. } => format!("Set {component}"),
+			Self::AddComponent { component, .. } => format!("Add {component}"),
+			Self::RemoveComponent { component, .. } => format!("Remove {component}"),
+			Self::Rename { new, .. } => format!("Rename to '{new}'"),
+			Self::Create(snapshot) => format!("Create {}", snapshot.label()),
+			Self::Delete(snapshots) => match snapshots.as_slice() {
+				[snapshot] => format!("Delete {}", snapshot.label()),
+				snapshots => format!("Delete {} entities", snapshots.len()),
+			},
+			Self::Reparent { .. } => "Reparent".to_string(),
+			Self::Batch { label, .. } => label.clone(),
+		}
+	}
+
+	pub fn redo(&self, world: &mut World) -> Result<()> {
+		match self {
+			Self::SetField {
+				entity,
+				component,
+				new,
+				..
+			} => set_field(world, *entity, component, new.clone()),
+			Self::SetComponent {
+				entity,
+				component,
+				new,
+				..
+			} => set_fields(world, *entity, component, new),
+			Self::AddComponent { entity, component } => {
+				component_info(world, component)?.add_default(world, *entity)
+			}
+			Self::RemoveComponent {
+				entity, component, ..
+			} => component_info(world, component)?.remove(world, *entity),
+			Self::Rename { entity, new, .. } => rename(world, *entity, new),
+			Self::Create(snapshot) => snapshot.restore(world, &registry(world)),
+			Self::Delete(snapshots) => {
+				for snapshot in snapshots.iter().rev() {
+					world.remove_entity(snapshot.entity);
+				}
+				Ok(())
+			}
+			Self::Reparent { entity, new, .. } => hierarchy::set_parent(world, *entity, *new),
+			Self::Batch { edits, .. } => edits.iter().try_for_each(|edit| edit.redo(world)),
+		}
+	}
+
+	fn undo(&self, world: &mut World) -> Result<()> {
+		match self {
+			Self::SetField {
+				entity,
+				component,
+				old,
+				..
+			} => set_field(world, *entity, component, old.clone()),
+			Self::SetComponent {
+				entity,
+				component,
+				old,
+				..
+			} => set_fields(world, *entity, component, old),
+			Self::AddComponent { entity, component } => {
+				component_info(world, component)?.remove(world, *entity)
+			}
+			Self::RemoveComponent {
+				entity,
+				component,
+				fields,
+			} => {
+				component_info(world, component)?.add_default(world, *entity)?;
+				set_fields(world, *entity, component, fields)
+			}
+			Self::Rename { entity, old, .. } => rename(world, *entity, old),
+			Self::Create(snapshot) => {
+				world.remove_entity(snapshot.entity);
+				Ok(())
+			}
+			Self::Delete(snapshots) => {
+				let registry = registry(world);
+				snapshots
+					.iter()
+					.try_for_each(|snapshot| snapshot.restore(world, &registry))
+			}
+			Self::Reparent { entity, old, .. } => hierarchy::set_parent(world, *entity, *old),
+			Self::Batch { edits, .. } => edits.iter().rev().try_for_each(|edit| edit.undo(world)),
+		}
+	}
+
+	// Whether a later edit can be folded into this one
+	fn merges_with(&self, other: &Self) -> bool {
+		match (self, other) {
+			(
+				Self::SetField {
+					entity,
+					component,
+					new,
+					..
+				},
+				Self::SetField {
+					entity: other_entity,
+					component: other_component,
+					new: other_new,
+					..
+				},
+			) => {
+				entity == other_entity && component == other_component && new.name == other_new.name
+			}
+			(
+				Self::SetComponent {
+					entity, component, ..
+				},
+				Self::SetComponent {
+					entity: other_entity,
+					component: other_component,
+					..
+				},
+			) => entity == other_entity && component == other_component,
+			(Self::Rename { entity, .. }, Self::Rename { entity: other, .. }) => entity == other,
+			// Such as a field dragged while several entities are selected
+			(Self::Batch { edits, .. }, Self::Batch { edits: others, .. }) => {
+				edits.len() == others.len()
+					&& edits
+						.iter()
+						.zip(others)
+						.all(|(edit, other)| edit.merges_with(other))
+			}
+			_ => false,
+		}
+	}
+
+	fn merge(&mut self, other: Self) {
+		match (self, other) {
+			(Self::SetField { new, .. }, Self::SetField { new: other, .. }) => *new = other,
+			(Self::SetComponent { new, .. }, Self::SetComponent { new: other, .. }) => *new = other,
+			(Self::Rename { new, .. }, Self::Rename { new: other, .. }) => *new = other,
+			(Self::Batch { edits, .. }, Self::Batch { edits: others, .. }) => {
+				for (edit, other) in edits.iter_mut().zip(others) {
+					edit.merge(other);
+				}
+			}
+			_ => {}
+		}
+	}
+}
+
+/// An entity's name, parent, and registered components, enough to bring it back
+#[derive(Debug, Clone)]
+pub struct EntitySnapshot {
+	pub entity: Entity,
+	pub name: Option<String>,
+	pub parent: Option<Entity>,
+	pub components: Vec<(&'static str, Vec<Field>)>,
+}
+
+impl EntitySnapshot {
+	pub fn take(world: &World, registry: &ComponentRegistry, entity: Entity) -> Self {
+		Self {
+			entity,
+			name: hierarchy::name(world, entity),
+			parent: hierarchy::parent(world, entity),
+			components: registry
+				.components_of(world, entity)
+				.map(|info| (info.name, info.fields(world, entity).unwrap_or_default()))
+				.collect(),
+		}
+	}
+
+	fn label(&self) -> String {
+		self.name
+			.clone()
+			.unwrap_or_else(|| format!("Entity {}", self.entity.index()))
+	}
+
+	/// Components that aren't in the registry can't be restored
+	pub fn restore(&self, world: &mut World, registry: &ComponentRegistry) -> Result<()> {
+		if !world.entity_exists(self.entity) {
+			world.restore_entity(self.entity)?;
+		}
+		if let Some(name) = &self.name {
+			world.add_component(self.entity, Name(name.clone()))?;
+		}
+		if let Some(parent) = self.parent {
+			world.add_component(self.entity, Parent(parent))?;
+		}
+		for (component, fields) in &self.components {
+			let info = registry
+				.get(component)
+				.ok_or_else(|| unregistered(component))?;
+			info.add_default(world, self.entity)?;
+			for field in fields {
+				info.set_field(world, self.entity, field.clone())?;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// The world's component registry, or an empty one
+pub fn registry(world: &World) -> ComponentRegistry {
+	world
+		.resources()
+		.borrow()
+		.get::<ComponentRegistry>()
+		.cloned()
+		.unwrap_or_default()
+}
+
+fn component_info(world: &World, component: &str) -> Result<ComponentInfo> {
+	registry(world)
+		.get(component)
+		.copied()
+		.ok_or_else(|| unregistered(component).into())
+}
+
+fn unregistered(component: &str) -> String {
+	format!("No component named '{component}' is registered")
+}
+
+fn set_field(world: &World, entity: Entity, component: &str, field: Field) -> Result<()> {
+	Ok(component_info(world, component)?.set_field(world, entity, field)?)
+}
+
+fn set_fields(world: &World, entity: Entity, component: &str, fields: &[Field]) -> Result<()> {
+	fields
+		.iter()
+		.try_for_each(|field| set_field(world, entity, component, field.clone()))
+}
+
+fn rename(world: &mut World, entity: Entity, name: &str) -> Result<()> {
+	world.add_component(entity, Name(name.to_string()))
+}
+
+/// The edits made in the editor, for undo and redo
+#[derive(Default, Clone)]
+pub struct History {
+	done: Vec<Edit>,
+	undone: Vec<Edit>,
+	// Whether the last edit can still take merges
+	open: bool,
+}
+
+impl History {
+	pub fn apply(&mut self, world: &mut World, edit: Edit) -> Result<()> {
+		self.push(world, edit, false)
+	}
+
+	/// Applies the edit, folding it into the last edit if both change the same
+	/// thing, such as while a slider is dragged. Call `seal` when the drag ends.
+	pub fn merge(&mut self, world: &mut World, edit: Edit) -> Result<()> {
+		self.push(world, edit, true)
+	}
+
+	/// Keeps an edit that was already made to the world, so it can be undone
+	pub fn record(&mut self, edit: Edit) {
+		self.undone.clear();
+		self.done.push(edit);
+		self.open = false;
+	}
+
+	/// Keeps the next edit from merging into the last one
+	pub fn seal(&mut self) {
+		self.open = false;
+	}
+
+	fn push(&mut self, world: &mut World, edit: Edit, merge: bool) -> Result<()> {
+		edit.redo(world)?;
+		self.undone.clear();
+		match self.done.last_mut() {
+			Some(last) if merge && self.open && last.merges_with(&edit) => last.merge(edit),
+			_ => self.done.push(edit),
+		}
+		self.open = merge;
+		Ok(())
+	}
+
+	pub fn undo(&mut self, world: &mut World) -> Result<()> {
+		self.open = false;
+		let Some(edit) = self.done.pop() else {
+			return Ok(());
+		};
+		let result = edit.undo(world);
+		self.undone.push(edit);
+		result
+	}
+
+	pub fn redo(&mut self, world: &mut World) -> Result<()> {
+		self.open = false;
+		let Some(edit) = self.undone.pop() else {
+			return Ok(());
+		};
+		let result = edit.redo(world);
+		self.done.push(edit);
+		result
+	}
+
+	pub fn can_undo(&self) -> bool {
+		!self.done.is_empty()
+	}
+
+	pub fn can_redo(&self) -> bool {
+		!self.undone.is_empty()
+	}
+
+	/// Lists every edit, and undoes or redoes up to the one that is clicked
+	pub fn show(&mut self, ui: &mut egui::Ui, world: &mut World) -> Result<()> {
+		let mut target = None;
+		egui::ScrollArea::vertical()
+			.auto_shrink(false)
+			.show(ui, |ui| {
+				if ui.selectable_label(self.done.is_empty(), "Start").clicked() {
+					target = Some(0);
+				}
+				for (index, edit) in self.done.iter().enumerate() {
+					let current = index + 1 == self.done.len();
+					if ui.selectable_label(current, edit.label()).clicked() {
+						target = Some(index + 1);
+					}
+				}
+				for (index, edit) in self.undone.iter().rev().enumerate() {
+					let label = egui::RichText::new(edit.label()).weak();
+					if ui.selectable_label(false, label).clicked() {
+						target = Some(self.done.len() + index + 1);
+					}
+				}
+			});
+		let Some(target) = target else {
+			return Ok(());
+		};
+		while self.done.len() > target {
+			self.undo(world)?;
+		}
+		while self.done.len() < target && self.can_redo() {
+			self.redo(world)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::scene::{create_world, Sprite};
+	use hourglass::ecs::reflect::Value;
+	use hourglass::math::Transform;
+
+	fn set_x(entity: Entity, old: f64, new: f64) -> Edit {
+		let field = |value| Field {
+			name: "x",
+			value: Value::Float(value),
+		};
+		Edit::SetField {
+			entity,
+			component: "Transform",
+			old: field(old),
+			new: field(new),
+		}
+	}
+
+	fn x(world: &World, entity: Entity) -> f32 {
+		world
+			.get_component::<Transform>(entity)
+			.unwrap()
+			.translation
+			.x
+	}
+
+	#[test]
+	fn merged_edits_undo_together() -> Result<()> {
+		let mut world = create_world();
+		let entity = hierarchy::roots(&world)[0];
+		let mut history = History::default();
+
+		history.merge(&mut world, set_x(entity, 0.0, 1.0))?;
+		history.merge(&mut world, set_x(entity, 1.0, 2.0))?;
+		history.seal();
+		history.merge(&mut world, set_x(entity, 2.0, 3.0))?;
+		assert_eq!(x(&world, entity), 3.0);
+
+		history.undo(&mut world)?;
+		assert_eq!(x(&world, entity), 2.0);
+		history.undo(&mut world)?;
+		assert_eq!(x(&world, entity), 0.0);
+		assert!(!history.can_undo());
+		history.redo(&mut world)?;
+		assert_eq!(x(&world, entity), 2.0);
+		Ok(())
+	}
+
+	#[test]
+	fn batches_over_a_selection_merge() -> Result<()> {
+		let mut world = create_world();
+		let roots = hierarchy::roots(&world);
+		let (first, second) = (roots[1], roots[2]);
+		let (first_x, second_x) = (x(&world, first), x(&world, second));
+		let mut history = History::default();
+
+		let batch = |world: &World, new: f64| {
+			let edits = [first, second]
+				.map(|entity| set_x(entity, f64::from(x(world, entity)), new))
+				.to_vec();
+			Edit::batch("Set Transform.x", edits)
+		};
+		for new in [7.0, 9.0] {
+			let edit = batch(&world, new);
+			history.merge(&mut world, edit)?;
+		}
+		assert_eq!((x(&world, first), x(&world, second)), (9.0, 9.0));
+		history.undo(&mut world)?;
+		assert_eq!((x(&world, first), x(&world, second)), (first_x, second_x));
+		assert!(!history.can_undo());
+		Ok(())
+	}
+
+	#[test]
+	fn deleted_entities_are_restored() -> Result<()> {
+		let mut world = create_world();
+		let parent = hierarchy::roots(&world)[0];
+		let child = hierarchy::children(&world, parent)[0];
+		world
+			.get_component_mut::<Transform>(parent)
+			.unwrap()
+			.translation
+			.x = 5.0;
+		let mut history = History::default();
+
+		let delete = Edit::delete(&world, parent);
+		history.apply(&mut world, delete)?;
+		assert!(!world.entity_exists(parent));
+		assert!(!world.entity_exists(child));
+
+		history.undo(&mut world)?;
+		assert_eq!(x(&world, parent), 5.0);
+		assert_eq!(hierarchy::parent(&world, child), Some(parent));
+		assert_eq!(hierarchy::name(&world, child), Some("Weapon".to_string()));
+		Ok(())
+	}
+
+	#[test]
+	fn removed_components_come_back() -> Result<()> {
+		let mut world = create_world();
+		let entity = hierarchy::roots(&world)[0];
+		world
+			.get_component_mut::<Transform>(entity)
+			.unwrap()
+			.translation
+			.x = 5.0;
+		let registry = registry(&world);
+		let mut history = History::default();
+
+		let remove = Edit::remove_component(&world, entity, registry.get("Transform").unwrap());
+		history.apply(&mut world, remove)?;
+		assert!(world.get_component::<Transform>(entity).is_none());
+		history.undo(&mut world)?;
+		assert_eq!(x(&world, entity), 5.0);
+
+		history.apply(
+			&mut world,
+			Edit::AddComponent {
+				entity,
+				component: "Sprite",
+			},
+		)?;
+		assert!(world.get_component::<Sprite>(entity).is_some());
+		history.undo(&mut world)?;
+		assert!(world.get_component::<Sprite>(entity).is_none());
+		Ok(())
+	}
+}
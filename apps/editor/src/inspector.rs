@@ -0,0 +1,253 @@
+use crate::{
+	hierarchy::entity_label,
+	history::{Edit, History},
+	prefab::{self, PrefabInstance},
+	selection::Selection,
+};
+use hourglass::{
+	app::log,
+	ecs::{
+		hierarchy::Name,
+		reflect::{ComponentInfo, ComponentRegistry, Field, Value},
+		world::{Entity, World},
+	},
+};
+
+/// Edits the selected entity's name and the fields of its registered components.
+/// Fields of prefab instances that override the prefab are highlighted.
+/// With several entities selected, the components they share are shown with the
+/// primary selection's values, and each edit is made to all of them as one step.
+#[derive(Default)]
+pub struct Inspector {
+	pub selection: Selection,
+}
+
+impl Inspector {
+	pub fn show(&mut self, ui: &mut egui::Ui, world: &mut World, history: &mut History) {
+		let Some(registry) = world
+			.resources()
+			.borrow()
+			.get::<ComponentRegistry>()
+			.cloned()
+		else {
+			ui.label("The world has no component registry");
+			return;
+		};
+
+		let entities = self
+			.selection
+			.entities()
+			.iter()
+			.copied()
+			.filter(|entity| world.entity_exists(*entity))
+			.collect::<Vec<_>>();
+		let Some(&entity) = entities.last() else {
+			ui.label("Nothing selected");
+			return;
+		};
+		let several = entities.len() > 1;
+		if several {
+			ui.heading(format!("{} entities", entities.len()));
+		} else {
+			edit_name(ui, world, entity, history);
+			if let Some(instance) = prefab::instance_of(world, entity) {
+				prefab_bar(ui, world, entity, (&instance, history));
+			}
+		}
+		ui.separator();
+
+		// The prefab tag is edited through the prefab bar
+		let components = registry
+			.components_of(world, entity)
+			.filter(|info| !is_prefab_tag(info) && on_all(world, &entities, info))
+			.copied()
+			.collect::<Vec<ComponentInfo>>();
+		let overrides = if several {
+			Vec::new()
+		} else {
+			prefab::overrides(world, entity)
+		};
+		let mut removed = None;
+		for info in &components {
+			let id = ui.make_persistent_id(info.name);
+			egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, true)
+				.show_header(ui, |ui| {
+					ui.strong(info.name);
+					ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+						if ui.small_button("Remove").clicked() {
+							removed = Some(*info);
+						}
+					});
+				})
+				.body(|ui| {
+					for field in info.fields(world, entity).unwrap_or_default() {
+						let mut value = field.value.clone();
+						let overridden = overrides.contains(&(info.name, field.name));
+						let response = edit_value(ui, field.name, &mut value, overridden);
+						if response.changed() {
+							let new = Field {
+								name: field.name,
+								value,
+							};
+							let edit = set_field(world, &entities, info, new);
+							record(history, world, &response, edit);
+						}
+					}
+				});
+		}
+		if let Some(info) = removed {
+			let edits = entities
+				.iter()
+				.map(|entity| Edit::remove_component(world, *entity, &info))
+				.collect();
+			let edit = Edit::batch(format!("Remove {}", info.name), edits);
+			if let Err(error) = history.apply(world, edit) {
+				log::warn!("Failed to remove {}: {error}", info.name);
+			}
+		}
+
+		ui.separator();
+		let missing = registry
+			.iter()
+			.filter(|info| !on_all(world, &entities, info) && !is_prefab_tag(info))
+			.copied()
+			.collect::<Vec<_>>();
+		ui.add_enabled_ui(!missing.is_empty(), |ui| {
+			ui.menu_button("Add component", |ui| {
+				for info in missing {
+					if ui.button(info.name).clicked() {
+						let component = info.name;
+						let edits = entities
+							.iter()
+							.filter(|entity| !info.is_on(world, **entity))
+							.map(|entity| Edit::AddComponent {
+								entity: *entity,
+								component,
+							})
+							.collect();
+						let edit = Edit::batch(format!("Add {component}"), edits);
+						if let Err(error) = history.apply(world, edit) {
+							log::warn!("Failed to add {component}: {error}");
+						}
+					}
+				}
+			});
+		});
+	}
+}
+
+fn on_all(world: &World, entities: &[Entity], info: &ComponentInfo) -> bool {
+	entities.iter().all(|entity| info.is_on(world, *entity))
+}
+
+// Sets the field on each entity, keeping each one's old value for undo
+fn set_field(world: &World, entities: &[Entity], info: &ComponentInfo, new: Field) -> Edit {
+	let edits = entities
+		.iter()
+		.filter_map(|entity| {
+			let old = info
+				.fields(world, *entity)?
+				.into_iter()
+				.find(|old| old.name == new.name)?;
+			Some(Edit::SetField {
+				entity: *entity,
+				component: info.name,
+				old,
+				new: new.clone(),
+			})
+		})
+		.collect();
+	Edit::batch(format!("Set {}.{}", info.name, new.name), edits)
+}
+
+fn is_prefab_tag(info: &ComponentInfo) -> bool {
+	info.type_id == std::any::TypeId::of::<PrefabInstance>()
+}
+
+fn prefab_bar(
+	ui: &mut egui::Ui,
+	world: &mut World,
+	entity: Entity,
+	(instance, history): (&PrefabInstance, &mut History),
+) {
+	ui.horizontal(|ui| {
+		ui.label("Prefab");
+		ui.monospace(&instance.path);
+		if ui.button("Apply to prefab").clicked() {
+			match prefab::apply(world, entity) {
+				Ok(edit) => {
+					if let Err(error) = history.apply(world, edit) {
+						log::warn!("Failed to update the prefab instances: {error}");
+					}
+				}
+				Err(error) => log::warn!("Failed to apply to {}: {error}", instance.path),
+			}
+		}
+		if ui.button("Revert").clicked() {
+			if let Some(edit) = prefab::revert(world, entity) {
+				if let Err(error) = history.apply(world, edit) {
+					log::warn!("Failed to revert to {}: {error}", instance.path);
+				}
+			}
+		}
+	});
+}
+
+fn edit_name(ui: &mut egui::Ui, world: &mut World, entity: Entity, history: &mut History) {
+	let Some(old) = world
+		.get_component::<Name>(entity)
+		.map(|name| name.0.clone())
+	else {
+		ui.heading(entity_label(world, entity));
+		return;
+	};
+	let mut new = old.clone();
+	let response = ui
+		.horizontal(|ui| {
+			ui.label("Name");
+			ui.text_edit_singleline(&mut new)
+		})
+		.inner;
+	if response.changed() {
+		record(history, world, &response, Edit::Rename { entity, old, new });
+	}
+}
+
+// Edits made during one drag or while a text field has focus are undone together
+fn record(history: &mut History, world: &mut World, response: &egui::Response, edit: Edit) {
+	let result = if response.dragged() || response.has_focus() {
+		history.merge(world, edit)
+	} else {
+		history.apply(world, edit)
+	};
+	if let Err(error) = result {
+		log::warn!("Failed to edit the entity: {error}");
+	}
+	if response.drag_stopped() || response.lost_focus() {
+		history.seal();
+	}
+}
+
+pub fn edit_value(
+	ui: &mut egui::Ui,
+	name: &str,
+	value: &mut Value,
+	overridden: bool,
+) -> egui::Response {
+	ui.horizontal(|ui| {
+		if overridden {
+			ui.strong(name)
+				.on_hover_text("Overrides the prefab's value");
+		} else {
+			ui.label(name);
+		}
+		match value {
+			Value::Bool(value) => ui.checkbox(value, ""),
+			Value::Integer(value) => ui.add(egui::DragValue::new(value)),
+			Value::Unsigned(value) => ui.add(egui::DragValue::new(value)),
+			Value::Float(value) => ui.add(egui::DragValue::new(value).speed(0.1)),
+			Value::Text(value) => ui.text_edit_singleline(value),
+		}
+	})
+	.inner
+}
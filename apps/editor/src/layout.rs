@@ -0,0 +1,137 @@
+use crate::editor::Panels;
+use egui_dock::{DockArea, DockState, NodeIndex, Style, TabViewer};
+use hourglass::ecs::world::World;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Tab {
+	Viewport,
+	Hierarchy,
+	Inspector,
+	Console,
+	History,
+	Profiler,
+	Assets,
+	Remote,
+	Project,
+	Script,
+}
+
+impl Tab {
+	pub const ALL: [Self; 10] = [
+		Self::Viewport,
+		Self::Hierarchy,
+		Self::Inspector,
+		Self::Console,
+		Self::History,
+		Self::Profiler,
+		Self::Assets,
+		Self::Remote,
+		Self::Project,
+		Self::Script,
+	];
+
+	pub fn title(self) -> &'static str {
+		match self {
+			Self::Viewport => "Viewport",
+			Self::Hierarchy => "Hierarchy",
+			Self::Inspector => "Inspector",
+			Self::Console => "Console",
+			Self::History => "History",
+			Self::Profiler => "Profiler",
+			Self::Assets => "Assets",
+			Self::Remote => "Remote",
+			Self::Project => "Project",
+			Self::Script => "Script",
+		}
+	}
+}
+
+/// How the editor's tabs are docked, saved with the editor's settings
+#[derive(Serialize, Deserialize)]
+pub struct Layout {
+	dock: DockState<Tab>,
+}
+
+impl Default for Layout {
+	fn default() -> Self {
+		let mut dock = DockState::new(vec![Tab::Viewport]);
+		let surface = dock.main_surface_mut();
+		let [viewport, _] = surface.split_left(NodeIndex::root(), 0.2, vec![Tab::Hierarchy]);
+		let [viewport, _] = surface.split_right(viewport, 0.75, vec![Tab::Inspector, Tab::Project]);
+		surface.split_below(
+			viewport,
+			0.7,
+			vec![
+				Tab::Assets,
+				Tab::Console,
+				Tab::Script,
+				Tab::History,
+				Tab::Profiler,
+				Tab::Remote,
+			],
+		);
+		Self { dock }
+	}
+}
+
+impl Layout {
+	/// Reopens a tab that was closed, or focuses it if it is already docked
+	pub fn open(&mut self, tab: Tab) {
+		match self.dock.find_tab(&tab) {
+			Some(path) => {
+				let _ = self.dock.set_active_tab(path);
+			}
+			None => self.dock.push_to_focused_leaf(tab),
+		}
+	}
+
+	pub fn show(&mut self, ui: &mut egui::Ui, panels: &mut Panels, world: &mut World) {
+		DockArea::new(&mut self.dock)
+			.style(Style::from_egui(ui.style()))
+			.show_inside(ui, &mut Tabs { panels, world });
+	}
+}
+
+struct Tabs<'a> {
+	panels: &'a mut Panels,
+	world: &'a mut World,
+}
+
+impl TabViewer for Tabs<'_> {
+	type Tab = Tab;
+
+	fn id(&mut self, tab: &mut Tab) -> egui::Id {
+		egui::Id::new(*tab)
+	}
+
+	fn title(&mut self, tab: &mut Tab) -> egui::WidgetText {
+		tab.title().into()
+	}
+
+	fn ui(&mut self, ui: &mut egui::Ui, tab: &mut Tab) {
+		self.panels.show(ui, self.world, *tab);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use hourglass::app::{StateError, StateResult};
+
+	#[test]
+	fn layout_round_trips() -> StateResult<()> {
+		let mut layout = Layout::default();
+		layout
+			.dock
+			.remove_tab(layout.dock.find_tab(&Tab::Console).unwrap());
+		let contents = ron::to_string(&layout).map_err(Box::<StateError>::from)?;
+
+		let mut loaded = ron::from_str::<Layout>(&contents)?;
+		assert!(loaded.dock.find_tab(&Tab::Inspector).is_some());
+		assert!(loaded.dock.find_tab(&Tab::Console).is_none());
+		loaded.open(Tab::Console);
+		assert!(loaded.dock.find_tab(&Tab::Console).is_some());
+		Ok(())
+	}
+}
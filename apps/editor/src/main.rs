@@ -1,15 +1,45 @@
 #![forbid(unsafe_code)]
 
+mod assets;
+mod camera;
+mod console;
 mod editor;
+mod gizmo;
+mod gui;
+mod hierarchy;
+mod history;
+mod inspector;
+mod layout;
+mod play;
+mod prefab;
+mod profiler;
+mod project;
+mod remote;
+mod scene;
+mod script;
+mod search;
+mod selection;
+mod settings;
+mod viewport;
 
 use editor::Editor;
-use hourglass::app::{tokio, App, AppConfig};
+use hourglass::{
+	app::{tokio, App, AppConfig},
+	assets::{AssetServer, FileSource},
+	diagnostics,
+};
 use std::error::Error;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-	env_logger::init();
-	let app = App::new(&AppConfig::default())?;
-	app.run(Editor);
+	// Recorded for the profiler panel
+	diagnostics::enable(true);
+	let mut builder = App::builder(AppConfig::from_args()?);
+	builder
+		.with_world(scene::create_world)
+		.add_system(scene::movement_system)
+		.with_assets(AssetServer::new(FileSource::new(".")).with_hot_reload(assets::WATCH_INTERVAL))
+		.publish_metrics("metrics")?;
+	builder.build()?.run(Editor::default());
 	Ok(())
 }
@@ -0,0 +1,223 @@
+use crate::history::{registry, EntitySnapshot, History};
+use hourglass::{
+	app::log,
+	ecs::{
+		error::Result,
+		hierarchy::{Name, Parent},
+		reflect::ComponentRegistry,
+		world::World,
+	},
+};
+use std::{any::TypeId, collections::BTreeSet};
+
+/// The fixed timestep in seconds, added to the world's resources before each tick
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedDelta(pub f64);
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PlayState {
+	#[default]
+	Editing,
+	Playing,
+	Paused,
+}
+
+/// Runs the app's systems on the world from the editor. Playing snapshots the world
+/// and the undo history, and stopping puts both back. Snapshots keep names, parents
+/// and registered components, so playing fails while the world has any other component.
+#[derive(Default)]
+pub struct Simulation {
+	state: PlayState,
+	snapshot: Option<(Vec<EntitySnapshot>, History)>,
+	steps: usize,
+}
+
+impl Simulation {
+	pub fn state(&self) -> PlayState {
+		self.state
+	}
+
+	pub fn play(&mut self, world: &World, history: &History) -> Result<()> {
+		if self.state == PlayState::Editing {
+			let registry = registry(world);
+			let unsupported = unsupported_components(world, &registry);
+			if !unsupported.is_empty() {
+				let names = unsupported.into_iter().collect::<Vec<_>>().join(", ");
+				return Err(format!(
+					"Can't play, since stopping couldn't restore these components that \
+					 aren't in the ComponentRegistry: {names}"
+				)
+				.into());
+			}
+			let entities = world
+				.entities()
+				.into_iter()
+				.map(|entity| EntitySnapshot::take(world, &registry, entity))
+				.collect();
+			self.snapshot = Some((entities, history.clone()));
+		}
+		self.state = PlayState::Playing;
+		Ok(())
+	}
+
+	pub fn pause(&mut self) {
+		if self.state == PlayState::Playing {
+			self.state = PlayState::Paused;
+		}
+	}
+
+	/// Runs a single fixed tick, pausing first if needed
+	pub fn step(&mut self, world: &World, history: &History) -> Result<()> {
+		self.play(world, history)?;
+		self.state = PlayState::Paused;
+		self.steps += 1;
+		Ok(())
+	}
+
+	pub fn stop(&mut self, world: &mut World, history: &mut History) -> Result<()> {
+		self.state = PlayState::Editing;
+		self.steps = 0;
+		let Some((entities, saved)) = self.snapshot.take() else {
+			return Ok(());
+		};
+		*history = saved;
+		world.remove_entities(&world.entities());
+		let registry = registry(world);
+		entities
+			.iter()
+			.try_for_each(|snapshot| snapshot.restore(world, &registry))
+	}
+
+	/// Whether the systems run this fixed tick
+	pub fn tick(&mut self) -> bool {
+		match self.state {
+			PlayState::Playing => true,
+			PlayState::Paused if self.steps > 0 => {
+				self.steps -= 1;
+				true
+			}
+			_ => false,
+		}
+	}
+
+	pub fn toolbar(&mut self, ui: &mut egui::Ui, world: &mut World, history: &mut History) {
+		let playing = self.state == PlayState::Playing;
+		let label = if self.state == PlayState::Paused {
+			"Resume"
+		} else {
+			"Play"
+		};
+		if ui.add_enabled(!playing, egui::Button::new(label)).clicked() {
+			if let Err(error) = self.play(world, history) {
+				log::error!("{error}");
+			}
+		}
+		if ui
+			.add_enabled(playing, egui::Button::new("Pause"))
+			.clicked()
+		{
+			self.pause();
+		}
+		if ui
+			.add_enabled(!playing, egui::Button::new("Step"))
+			.clicked()
+		{
+			if let Err(error) = self.step(world, history) {
+				log::error!("{error}");
+			}
+		}
+		let editing = self.state == PlayState::Editing;
+		if ui
+			.add_enabled(!editing, egui::Button::new("Stop"))
+			.clicked()
+		{
+			if let Err(error) = self.stop(world, history) {
+				log::warn!("Failed to restore the world from before playing: {error}");
+			}
+		}
+	}
+}
+
+// The type names of components in the world that a snapshot would lose
+fn unsupported_components(world: &World, registry: &ComponentRegistry) -> BTreeSet<&'static str> {
+	let supported = registry
+		.iter()
+		.map(|info| info.type_id)
+		.chain([TypeId::of::<Name>(), TypeId::of::<Parent>()])
+		.collect::<Vec<_>>();
+	world
+		.entities()
+		.into_iter()
+		.flat_map(|entity| world.component_types(entity))
+		.filter(|(type_id, _)| !supported.contains(type_id))
+		.map(|(_, name)| name)
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::scene::{create_world, movement_system};
+	use hourglass::ecs::hierarchy;
+	use hourglass::math::Transform;
+
+	#[test]
+	fn stop_restores_the_world() -> Result<()> {
+		let mut world = create_world();
+		let mut history = History::default();
+		let player = hierarchy::roots(&world)[0];
+		let mut simulation = Simulation::default();
+
+		simulation.step(&world, &history)?;
+		assert_eq!(simulation.state(), PlayState::Paused);
+		assert!(simulation.tick());
+		assert!(!simulation.tick());
+
+		world.resources().borrow_mut().insert(FixedDelta(1.0));
+		movement_system(&mut world)?;
+		world.remove_entity(hierarchy::children(&world, player)[0]);
+		world.create_entity();
+		assert_ne!(
+			world
+				.get_component::<Transform>(player)
+				.unwrap()
+				.translation
+				.x,
+			0.0
+		);
+
+		let count = world.entities().len();
+		simulation.stop(&mut world, &mut history)?;
+		assert_eq!(
+			world
+				.get_component::<Transform>(player)
+				.unwrap()
+				.translation
+				.x,
+			0.0
+		);
+		assert_eq!(world.entities().len(), count);
+		assert_eq!(hierarchy::children(&world, player).len(), 1);
+		Ok(())
+	}
+
+	struct Unregistered;
+
+	#[test]
+	fn playing_fails_on_components_the_snapshot_would_lose() -> Result<()> {
+		let mut world = create_world();
+		let history = History::default();
+		let player = hierarchy::roots(&world)[0];
+		world.add_component(player, Unregistered)?;
+		let mut simulation = Simulation::default();
+
+		let error = simulation.play(&world, &history).unwrap_err();
+		assert!(error.to_string().ends_with("play::tests::Unregistered"));
+		assert_eq!(simulation.state(), PlayState::Editing);
+
+		world.remove_component::<Unregistered>(player)?;
+		simulation.play(&world, &history)?;
+		assert_eq!(simulation.state(), PlayState::Playing);
+		Ok(())
+	}
+}
@@ -0,0 +1,727 @@
+use crate::{
+	assets::asset_key,
+	hierarchy::entity_label,
+	history::{registry, Edit, EntitySnapshot},
+};
+use hourglass::{
+	ecs::{
+		error::Result,
+		hierarchy, reflect,
+		reflect::{ComponentInfo, ComponentRegistry, Field, Reflect, Value},
+		world::{Entity, World},
+	},
+	math::Transform,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::HashMap,
+	fs,
+	path::{Path, PathBuf},
+};
+
+pub const PREFAB_EXTENSION: &str = "prefab";
+const TAG: &str = "PrefabInstance";
+
+/// Marks an entity as made from a node of the prefab at the path
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PrefabInstance {
+	pub path: String,
+	pub node: u32,
+}
+
+reflect!(PrefabInstance { path, node });
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PrefabValue {
+	Bool(bool),
+	Integer(i64),
+	Unsigned(u64),
+	Float(f64),
+	Text(String),
+}
+
+impl From<&Value> for PrefabValue {
+	fn from(value: &Value) -> Self {
+		match value {
+			Value::Bool(value) => Self::Bool(*value),
+			Value::Integer(value) => Self::Integer(*value),
+			Value::Unsigned(value) => Self::Unsigned(*value),
+			Value::Float(value) => Self::Float(*value),
+			Value::Text(value) => Self::Text(value.clone()),
+		}
+	}
+}
+
+impl From<&PrefabValue> for Value {
+	fn from(value: &PrefabValue) -> Self {
+		match value {
+			PrefabValue::Bool(value) => Self::Bool(*value),
+			PrefabValue::Integer(value) => Self::Integer(*value),
+			PrefabValue::Unsigned(value) => Self::Unsigned(*value),
+			PrefabValue::Float(value) => Self::Float(*value),
+			PrefabValue::Text(value) => Self::Text(value.clone()),
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrefabComponent {
+	pub name: String,
+	pub fields: Vec<(String, PrefabValue)>,
+}
+
+impl PrefabComponent {
+	fn value(&self, field: &str) -> Option<&PrefabValue> {
+		self.fields
+			.iter()
+			.find(|(name, _)| name == field)
+			.map(|(_, value)| value)
+	}
+
+	// Matches the saved fields to the registered component's, skipping any it no longer has
+	fn resolve(&self, info: &ComponentInfo) -> Vec<Field> {
+		info.default_fields()
+			.into_iter()
+			.filter_map(|field| {
+				Some(Field {
+					name: field.name,
+					value: self.value(field.name)?.into(),
+				})
+			})
+			.collect()
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrefabNode {
+	/// Stays the same as the prefab is edited, so instances can be matched to it
+	pub id: u32,
+	pub parent: Option<u32>,
+	pub name: Option<String>,
+	pub components: Vec<PrefabComponent>,
+}
+
+impl PrefabNode {
+	fn component(&self, name: &str) -> Option<&PrefabComponent> {
+		self.components
+			.iter()
+			.find(|component| component.name == name)
+	}
+}
+
+/// Entities saved from the editor to be instantiated again, with parents before children
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Prefab {
+	pub nodes: Vec<PrefabNode>,
+}
+
+impl Prefab {
+	pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+		Ok(ron::from_str(&fs::read_to_string(path)?)?)
+	}
+
+	pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+		let path = path.as_ref();
+		if let Some(directory) = path.parent() {
+			fs::create_dir_all(directory)?;
+		}
+		let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+		Ok(fs::write(path, contents)?)
+	}
+
+	fn root(&self) -> Option<u32> {
+		self.nodes.first().map(|node| node.id)
+	}
+
+	fn node(&self, id: u32) -> Option<&PrefabNode> {
+		self.nodes.iter().find(|node| node.id == id)
+	}
+}
+
+// The prefabs the editor has loaded or saved, kept as a world resource by path.
+// Instances are compared to these to tell their overrides apart.
+#[derive(Default)]
+struct PrefabCache(HashMap<String, Prefab>);
+
+fn cached(world: &World, path: &str) -> Result<Prefab> {
+	match peek(world, path) {
+		Some(prefab) => Ok(prefab),
+		None => {
+			let prefab = Prefab::load(path)?;
+			cache(world, path, prefab.clone());
+			Ok(prefab)
+		}
+	}
+}
+
+fn peek(world: &World, path: &str) -> Option<Prefab> {
+	world
+		.resources()
+		.borrow()
+		.get::<PrefabCache>()
+		.and_then(|cache| cache.0.get(path).cloned())
+}
+
+fn cache(world: &World, path: &str, prefab: Prefab) {
+	let mut resources = world.resources().borrow_mut();
+	if resources.get::<PrefabCache>().is_none() {
+		resources.insert(PrefabCache::default());
+	}
+	if let Some(cache) = resources.get_mut::<PrefabCache>() {
+		cache.0.insert(path.to_string(), prefab);
+	}
+}
+
+pub fn instance_of(world: &World, entity: Entity) -> Option<PrefabInstance> {
+	world
+		.get_component::<PrefabInstance>(entity)
+		.map(|instance| instance.clone())
+}
+
+/// A free path in the asset root's prefab folder, named after the entity
+pub fn new_prefab_path(asset_root: &Path, world: &World, entity: Entity) -> PathBuf {
+	let directory = asset_root.join("prefabs");
+	let name = entity_label(world, entity)
+		.chars()
+		.filter(|character| character.is_alphanumeric() || " -_".contains(*character))
+		.collect::<String>();
+	let mut path = directory.join(format!("{name}.{PREFAB_EXTENSION}"));
+	let mut copy = 1;
+	while path.exists() {
+		copy += 1;
+		path = directory.join(format!("{name} {copy}.{PREFAB_EXTENSION}"));
+	}
+	path
+}
+
+/// Saves the entity and its descendants as a new prefab, returning the edit
+/// that makes them its first instance
+pub fn create(world: &World, root: Entity, path: &Path) -> Result<Edit> {
+	let key = asset_key(path);
+	let (prefab, tags) = capture(world, root, &key, None);
+	prefab.save(path)?;
+	cache(world, &key, prefab);
+	Ok(Edit::Batch {
+		label: format!("Save prefab {key}"),
+		edits: tags,
+	})
+}
+
+/// Creates an instance of the prefab with its root at the transform
+pub fn instantiate(
+	world: &mut World,
+	path: &Path,
+	transform: &Transform,
+) -> Result<(Entity, Edit)> {
+	let key = asset_key(path);
+	let prefab = cached(world, &key)?;
+	let registry = registry(world);
+	let mut entities = HashMap::new();
+	let mut edits = Vec::new();
+	for node in &prefab.nodes {
+		let entity = world.create_entity();
+		let mut snapshot = node_snapshot(&registry, node, entity, &key);
+		snapshot.parent = node
+			.parent
+			.and_then(|parent| entities.get(&parent).copied());
+		if Some(node.id) == prefab.root() {
+			snapshot
+				.components
+				.retain(|(component, _)| *component != "Transform");
+			snapshot.components.push(("Transform", transform.fields()));
+		}
+		entities.insert(node.id, entity);
+		edits.push(Edit::Create(snapshot));
+	}
+	let root = prefab
+		.root()
+		.and_then(|root| entities.get(&root).copied())
+		.ok_or("The prefab is empty")?;
+	let edit = Edit::Batch {
+		label: format!("Instantiate {key}"),
+		edits,
+	};
+	Ok((root, edit))
+}
+
+/// Saves the changes made to an instance in its prefab, then brings the other
+/// instances up to date, except for the fields they override and the entities
+/// and components they have added or removed
+pub fn apply(world: &mut World, entity: Entity) -> Result<Edit> {
+	let tag = instance_of(world, entity).ok_or("The entity isn't part of a prefab")?;
+	let old = cached(world, &tag.path)?;
+	let root = instance_root(world, entity, &old).ok_or("The prefab instance has no root")?;
+	let (new, mut edits) = capture(world, root, &tag.path, Some(&old));
+	new.save(&tag.path)?;
+	cache(world, &tag.path, new.clone());
+
+	let change = Change {
+		path: &tag.path,
+		old: &old,
+		new: &new,
+	};
+	for instance in instances(world, &tag.path, &old) {
+		if instance.root != root {
+			edits.extend(update(world, &change, instance));
+		}
+	}
+	Ok(Edit::Batch {
+		label: format!("Apply prefab {}", tag.path),
+		edits,
+	})
+}
+
+/// Brings the instances up to date after the prefab's file changed on disk, as `apply` does.
+/// Returns `None` when the prefab wasn't in use or is unchanged, such as after it was applied.
+pub fn reload(world: &mut World, path: &Path) -> Result<Option<Edit>> {
+	let key = asset_key(path);
+	let Some(old) = peek(world, &key) else {
+		return Ok(None);
+	};
+	let new = Prefab::load(path)?;
+	if new == old {
+		return Ok(None);
+	}
+	cache(world, &key, new.clone());
+	let change = Change {
+		path: &key,
+		old: &old,
+		new: &new,
+	};
+	let mut edits = Vec::new();
+	for instance in instances(world, &key, &old) {
+		edits.extend(update(world, &change, instance));
+	}
+	Ok(Some(Edit::Batch {
+		label: format!("Reload {key}"),
+		edits,
+	}))
+}
+
+/// Sets the entity's name and components back to its prefab's
+pub fn revert(world: &World, entity: Entity) -> Option<Edit> {
+	let saved = prefab_node(world, entity)?;
+	let current = capture_node(world, &registry(world), entity, saved.id);
+	let edits = update_entity(world, entity, (Some(&current), &saved));
+	(!edits.is_empty()).then(|| Edit::Batch {
+		label: format!("Revert {}", entity_label(world, entity)),
+		edits,
+	})
+}
+
+/// The component fields of an instance entity that differ from its prefab
+pub fn overrides(world: &World, entity: Entity) -> Vec<(&'static str, &'static str)> {
+	let Some(node) = prefab_node(world, entity) else {
+		return Vec::new();
+	};
+	let registry = registry(world);
+	let mut overridden = Vec::new();
+	for info in registry.components_of(world, entity) {
+		let Some(saved) = node
+			.component(info.name)
+			.filter(|_| !placement(&node, info.name))
+		else {
+			continue;
+		};
+		for field in info.fields(world, entity).unwrap_or_default() {
+			if saved
+				.value(field.name)
+				.is_some_and(|value| *value != PrefabValue::from(&field.value))
+			{
+				overridden.push((info.name, field.name));
+			}
+		}
+	}
+	overridden
+}
+
+// Where each instance is placed isn't an override and isn't changed by the prefab
+fn placement(node: &PrefabNode, component: &str) -> bool {
+	node.parent.is_none() && component == "Transform"
+}
+
+fn prefab_node(world: &World, entity: Entity) -> Option<PrefabNode> {
+	let tag = instance_of(world, entity)?;
+	cached(world, &tag.path).ok()?.node(tag.node).cloned()
+}
+
+fn instance_root(world: &World, entity: Entity, prefab: &Prefab) -> Option<Entity> {
+	let mut current = entity;
+	loop {
+		let tag = instance_of(world, current)?;
+		if Some(tag.node) == prefab.root() {
+			return Some(current);
+		}
+		current = hierarchy::parent(world, current)?;
+	}
+}
+
+// Saves the subtree. Entities already in an instance of the prefab keep their
+// node ids and the rest are given new ones, along with the edits that tag them.
+fn capture(
+	world: &World,
+	root: Entity,
+	path: &str,
+	previous: Option<&Prefab>,
+) -> (Prefab, Vec<Edit>) {
+	let registry = registry(world);
+	let mut next_id = previous
+		.and_then(|prefab| prefab.nodes.iter().map(|node| node.id).max())
+		.map_or(0, |id| id + 1);
+	let mut nodes = Vec::new();
+	let mut tags = Vec::new();
+	let mut pending = vec![(root, None)];
+	while let Some((entity, parent)) = pending.pop() {
+		let existing = instance_of(world, entity)
+			.filter(|tag| previous.is_some() && tag.path == path)
+			.map(|tag| tag.node);
+		let id = existing.unwrap_or_else(|| {
+			let id = next_id;
+			next_id += 1;
+			let tag = PrefabInstance {
+				path: path.to_string(),
+				node: id,
+			};
+			tags.extend(tag_edits(world, entity, &tag));
+			id
+		});
+		let mut node = capture_node(world, &registry, entity, id);
+		node.parent = parent;
+		nodes.push(node);
+		for child in hierarchy::children(world, entity).into_iter().rev() {
+			pending.push((child, Some(id)));
+		}
+	}
+	(Prefab { nodes }, tags)
+}
+
+fn capture_node(
+	world: &World,
+	registry: &ComponentRegistry,
+	entity: Entity,
+	id: u32,
+) -> PrefabNode {
+	PrefabNode {
+		id,
+		parent: None,
+		name: hierarchy::name(world, entity),
+		components: registry
+			.components_of(world, entity)
+			.filter(|info| info.name != TAG)
+			.map(|info| PrefabComponent {
+				name: info.name.to_string(),
+				fields: info
+					.fields(world, entity)
+					.unwrap_or_default()
+					.iter()
+					.map(|field| (field.name.to_string(), (&field.value).into()))
+					.collect(),
+			})
+			.collect(),
+	}
+}
+
+fn tag_edits(world: &World, entity: Entity, tag: &PrefabInstance) -> Vec<Edit> {
+	let old = world
+		.get_component::<PrefabInstance>(entity)
+		.map(|old| old.fields());
+	let set = |old| Edit::SetComponent {
+		entity,
+		component: TAG,
+		old,
+		new: tag.fields(),
+	};
+	match old {
+		Some(old) => vec![set(old)],
+		None => vec![
+			Edit::AddComponent {
+				entity,
+				component: TAG,
+			},
+			set(PrefabInstance::default().fields()),
+		],
+	}
+}
+
+// Components that are no longer registered are left out
+fn node_snapshot(
+	registry: &ComponentRegistry,
+	node: &PrefabNode,
+	entity: Entity,
+	path: &str,
+) -> EntitySnapshot {
+	let tag = PrefabInstance {
+		path: path.to_string(),
+		node: node.id,
+	};
+	let mut components = node
+		.components
+		.iter()
+		.filter_map(|component| {
+			let info = registry.get(&component.name)?;
+			Some((info.name, component.resolve(info)))
+		})
+		.collect::<Vec<_>>();
+	components.push((TAG, tag.fields()));
+	EntitySnapshot {
+		entity,
+		name: node.name.clone(),
+		parent: None,
+		components,
+	}
+}
+
+struct Change<'a> {
+	path: &'a str,
+	old: &'a Prefab,
+	new: &'a Prefab,
+}
+
+// The entities of one instance by node id
+struct Instance {
+	root: Entity,
+	nodes: HashMap<u32, Entity>,
+}
+
+// Entities added under an instance that aren't tagged are left out, along with their children
+fn instances(world: &World, path: &str, prefab: &Prefab) -> Vec<Instance> {
+	world
+		.entities()
+		.into_iter()
+		.filter(|entity| {
+			instance_of(world, *entity)
+				.is_some_and(|tag| tag.path == path && Some(tag.node) == prefab.root())
+		})
+		.map(|root| {
+			let mut nodes = HashMap::new();
+			let mut pending = vec![root];
+			while let Some(entity) = pending.pop() {
+				if let Some(tag) = instance_of(world, entity).filter(|tag| tag.path == path) {
+					nodes.insert(tag.node, entity);
+					pending.extend(hierarchy::children(world, entity));
+				}
+			}
+			Instance { root, nodes }
+		})
+		.collect()
+}
+
+// The edits that bring an instance from the old prefab to the new one
+fn update(world: &mut World, change: &Change, mut instance: Instance) -> Vec<Edit> {
+	let registry = registry(world);
+	let mut edits = Vec::new();
+	for node in &change.new.nodes {
+		let old = change.old.node(node.id);
+		if let Some(entity) = instance.nodes.get(&node.id).copied() {
+			edits.extend(update_entity(world, entity, (old, node)));
+			continue;
+		}
+		// Nodes the instance deleted stay deleted
+		let parent = node
+			.parent
+			.and_then(|parent| instance.nodes.get(&parent).copied());
+		if let (None, Some(parent)) = (old, parent) {
+			let entity = world.create_entity();
+			let mut snapshot = node_snapshot(&registry, node, entity, change.path);
+			snapshot.parent = Some(parent);
+			instance.nodes.insert(node.id, entity);
+			edits.push(Edit::Create(snapshot));
+		}
+	}
+	for node in &change.old.nodes {
+		let removed = |id| change.new.node(id).is_none();
+		// Deleting the parent deletes its children
+		if removed(node.id) && !node.parent.is_some_and(removed) {
+			if let Some(entity) = instance.nodes.get(&node.id) {
+				edits.push(Edit::delete(world, *entity));
+			}
+		}
+	}
+	edits
+}
+
+// Fields still matching the old node are overwritten, while overridden fields
+// and components the instance added or removed are kept
+fn update_entity(
+	world: &World,
+	entity: Entity,
+	(old, new): (Option<&PrefabNode>, &PrefabNode),
+) -> Vec<Edit> {
+	let registry = registry(world);
+	let mut edits = Vec::new();
+	let name = hierarchy::name(world, entity);
+	if let Some(new_name) = &new.name {
+		if name == old.and_then(|old| old.name.clone()) && name.as_ref() != Some(new_name) {
+			edits.push(Edit::Rename {
+				entity,
+				old: name.unwrap_or_default(),
+				new: new_name.clone(),
+			});
+		}
+	}
+	for component in &new.components {
+		let Some(info) = registry.get(&component.name) else {
+			continue;
+		};
+		if placement(new, info.name) && info.is_on(world, entity) {
+			continue;
+		}
+		let saved = old.and_then(|old| old.component(&component.name));
+		match info.fields(world, entity) {
+			Some(current) => {
+				let (old_fields, new_fields) = current
+					.into_iter()
+					.filter_map(|field| {
+						let value = component.value(field.name)?;
+						let current = PrefabValue::from(&field.value);
+						let kept =
+							saved.and_then(|saved| saved.value(field.name)) == Some(&current);
+						(kept && *value != current).then(|| {
+							let new = Field {
+								name: field.name,
+								value: value.into(),
+							};
+							(field, new)
+						})
+					})
+					.unzip::<_, _, Vec<_>, Vec<_>>();
+				if !new_fields.is_empty() {
+					edits.push(Edit::SetComponent {
+						entity,
+						component: info.name,
+						old: old_fields,
+						new: new_fields,
+					});
+				}
+			}
+			None if saved.is_none() => {
+				edits.push(Edit::AddComponent {
+					entity,
+					component: info.name,
+				});
+				edits.push(Edit::SetComponent {
+					entity,
+					component: info.name,
+					old: info.default_fields(),
+					new: component.resolve(info),
+				});
+			}
+			None => {}
+		}
+	}
+	for component in old.iter().flat_map(|old| &old.components) {
+		if new.component(&component.name).is_some() {
+			continue;
+		}
+		if let Some(info) = registry.get(&component.name) {
+			if info.is_on(world, entity) {
+				edits.push(Edit::remove_component(world, entity, info));
+			}
+		}
+	}
+	edits
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		history::History,
+		scene::{create_world, Health, Velocity},
+	};
+	use hourglass::ecs::hierarchy::{Name, Parent};
+
+	#[test]
+	fn edits_reach_instances_except_overrides() -> Result<()> {
+		let mut world = create_world();
+		let player = hierarchy::roots(&world)[0];
+		let path = std::env::temp_dir().join("hourglass_editor_player.prefab");
+		let mut history = History::default();
+
+		let edit = create(&world, player, &path)?;
+		history.apply(&mut world, edit)?;
+		let (copy, edit) = instantiate(&mut world, &path, &Transform::from_xyz(5.0, 0.0, 0.0))?;
+		history.apply(&mut world, edit)?;
+		assert_eq!(
+			world
+				.get_component::<Transform>(copy)
+				.unwrap()
+				.translation
+				.x,
+			5.0
+		);
+		assert_eq!(hierarchy::children(&world, copy).len(), 1);
+
+		world.get_component_mut::<Health>(copy).unwrap().value = 10;
+		world.get_component_mut::<Health>(player).unwrap().value = 50;
+		world.get_component_mut::<Velocity>(player).unwrap().x = 2.0;
+		let shield = world.create_entity();
+		world.add_component(shield, Name("Shield".to_string()))?;
+		world.add_component(shield, Parent(player))?;
+		let edit = apply(&mut world, player)?;
+		history.apply(&mut world, edit)?;
+
+		assert_eq!(world.get_component::<Health>(copy).unwrap().value, 10);
+		assert_eq!(world.get_component::<Velocity>(copy).unwrap().x, 2.0);
+		let names = hierarchy::children(&world, copy)
+			.into_iter()
+			.filter_map(|child| hierarchy::name(&world, child))
+			.collect::<Vec<_>>();
+		assert!(names.contains(&"Shield".to_string()));
+		assert_eq!(overrides(&world, copy), [("Health", "value")]);
+
+		let edit = revert(&world, copy).unwrap();
+		history.apply(&mut world, edit)?;
+		assert_eq!(world.get_component::<Health>(copy).unwrap().value, 50);
+		assert_eq!(
+			world
+				.get_component::<Transform>(copy)
+				.unwrap()
+				.translation
+				.x,
+			5.0
+		);
+		history.undo(&mut world)?;
+		history.undo(&mut world)?;
+		assert_eq!(world.get_component::<Velocity>(copy).unwrap().x, 1.0);
+		fs::remove_file(path)?;
+		Ok(())
+	}
+
+	#[test]
+	fn instances_reload_when_the_file_changes() -> Result<()> {
+		let mut world = create_world();
+		let player = hierarchy::roots(&world)[0];
+		let path = std::env::temp_dir().join("hourglass_editor_reload.prefab");
+		let mut history = History::default();
+		let edit = create(&world, player, &path)?;
+		history.apply(&mut world, edit)?;
+		let (copy, edit) = instantiate(&mut world, &path, &Transform::from_xyz(5.0, 0.0, 0.0))?;
+		history.apply(&mut world, edit)?;
+		assert!(reload(&mut world, &path)?.is_none());
+
+		let mut prefab = Prefab::load(&path)?;
+		let velocity = prefab.nodes[0]
+			.components
+			.iter_mut()
+			.find(|component| component.name == "Velocity")
+			.unwrap();
+		velocity.fields[0].1 = PrefabValue::Float(3.0);
+		prefab.save(&path)?;
+		let edit = reload(&mut world, &path)?.unwrap();
+		history.apply(&mut world, edit)?;
+		for entity in [player, copy] {
+			assert_eq!(world.get_component::<Velocity>(entity).unwrap().x, 3.0);
+		}
+		assert_eq!(
+			world
+				.get_component::<Transform>(copy)
+				.unwrap()
+				.translation
+				.x,
+			5.0
+		);
+		fs::remove_file(path)?;
+		Ok(())
+	}
+}
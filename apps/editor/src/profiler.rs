@@ -0,0 +1,237 @@
+use hourglass::{
+	app::{BusTraffic, Context, SystemTiming},
+	diagnostics::{self, Frame},
+};
+use std::collections::VecDeque;
+
+const SAMPLES: usize = 300;
+const GRAPH_HEIGHT: f32 = 48.0;
+const SPAN_INDENT: f32 = 12.0;
+const THROUGHPUT_PERIOD: f64 = 1.0;
+
+/// Live graphs of the frame time, the time each system takes and how busy the event buses are,
+/// with the spans and counters of the latest frame
+#[derive(Default)]
+pub struct Profiler {
+	paused: bool,
+	frame: Graph,
+	latest: Option<Frame>,
+	systems: Vec<SystemGraph>,
+	buses: Vec<BusGraph>,
+	since_throughput: f64,
+}
+
+struct SystemGraph {
+	name: &'static str,
+	runs: u64,
+	graph: Graph,
+}
+
+struct BusGraph {
+	name: &'static str,
+	published: u64,
+	graph: Graph,
+}
+
+impl Profiler {
+	/// Samples the context, called once per frame
+	pub fn record(&mut self, context: &Context) {
+		if self.paused {
+			return;
+		}
+		let delta = context.timing.delta_seconds();
+		self.frame.push(delta * 1000.0);
+		self.latest = diagnostics::latest_frame();
+		self.record_systems(&context.system_timings());
+		self.since_throughput += delta;
+		if self.since_throughput >= THROUGHPUT_PERIOD {
+			self.record_throughput(&context.bus_traffic(), self.since_throughput);
+			self.since_throughput = 0.0;
+		}
+	}
+
+	pub fn show(&mut self, ui: &mut egui::Ui) {
+		ui.horizontal(|ui| {
+			ui.toggle_value(&mut self.paused, "Pause");
+			if ui.button("Clear").clicked() {
+				*self = Self {
+					paused: self.paused,
+					..Self::default()
+				};
+			}
+		});
+		ui.separator();
+		egui::ScrollArea::vertical().show(ui, |ui| {
+			ui.heading("Frame");
+			self.frame.show(ui, "Frame time", "ms");
+			ui.heading("Spans");
+			self.show_latest(ui);
+			ui.heading("Systems");
+			if self.systems.is_empty() {
+				ui.weak("No systems have run, press play to run them");
+			}
+			for system in &self.systems {
+				system.graph.show(ui, system.name, "ms");
+			}
+			ui.heading("Event buses");
+			if self.buses.is_empty() {
+				ui.weak("No event buses");
+			}
+			for bus in &self.buses {
+				bus.graph.show(ui, bus.name, "messages/s");
+			}
+		});
+	}
+
+	fn show_latest(&self, ui: &mut egui::Ui) {
+		let Some(frame) = self.latest.as_ref().filter(|frame| !frame.spans.is_empty()) else {
+			ui.weak("No spans recorded");
+			return;
+		};
+		let mut thread = None;
+		for span in &frame.spans {
+			if thread != Some(span.thread) {
+				thread = Some(span.thread);
+				ui.label(format!("Thread {}", span.thread));
+			}
+			ui.horizontal(|ui| {
+				ui.add_space(SPAN_INDENT * (span.depth + 1) as f32);
+				ui.label(format!(
+					"{}: {:.3} ms",
+					span.name,
+					span.duration.as_secs_f64() * 1000.0
+				));
+			});
+		}
+		for (name, amount) in &frame.counters {
+			ui.label(format!("{name}: {amount}"));
+		}
+	}
+
+	// Systems only run while playing, so a sample is taken for each new run
+	fn record_systems(&mut self, timings: &[SystemTiming]) {
+		for (index, timing) in timings.iter().enumerate() {
+			if self.systems.len() <= index {
+				self.systems.push(SystemGraph {
+					name: timing.name,
+					runs: 0,
+					graph: Graph::default(),
+				});
+			}
+			let system = &mut self.systems[index];
+			if timing.runs > system.runs {
+				system.runs = timing.runs;
+				system.graph.push(timing.last.as_secs_f64() * 1000.0);
+			}
+		}
+	}
+
+	fn record_throughput(&mut self, traffic: &[BusTraffic], elapsed: f64) {
+		for (index, bus) in traffic.iter().enumerate() {
+			if self.buses.len() <= index {
+				self.buses.push(BusGraph {
+					name: bus.name,
+					published: bus.published,
+					graph: Graph::default(),
+				});
+				continue;
+			}
+			let graph = &mut self.buses[index];
+			let published = bus.published.saturating_sub(graph.published);
+			graph.published = bus.published;
+			graph.graph.push(published as f64 / elapsed);
+		}
+	}
+}
+
+/// The most recent samples, drawn as a line scaled to the largest one
+#[derive(Default)]
+struct Graph {
+	samples: VecDeque<f32>,
+}
+
+impl Graph {
+	fn push(&mut self, sample: f64) {
+		if self.samples.len() == SAMPLES {
+			self.samples.pop_front();
+		}
+		self.samples.push_back(sample as f32);
+	}
+
+	fn latest(&self) -> f32 {
+		self.samples.back().copied().unwrap_or_default()
+	}
+
+	fn max(&self) -> f32 {
+		self.samples.iter().copied().fold(0.0, f32::max)
+	}
+
+	fn show(&self, ui: &mut egui::Ui, label: &str, unit: &str) {
+		let max = self.max();
+		ui.label(format!(
+			"{label}: {:.2} {unit} (max {max:.2})",
+			self.latest()
+		));
+		let (response, painter) = ui.allocate_painter(
+			egui::vec2(ui.available_width(), GRAPH_HEIGHT),
+			egui::Sense::hover(),
+		);
+		let rect = response.rect;
+		painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+		if self.samples.len() < 2 || max <= 0.0 {
+			return;
+		}
+		let step = rect.width() / (SAMPLES - 1) as f32;
+		let start = rect.right() - step * (self.samples.len() - 1) as f32;
+		let points = self
+			.samples
+			.iter()
+			.enumerate()
+			.map(|(index, sample)| {
+				egui::pos2(
+					start + step * index as f32,
+					rect.bottom() - rect.height() * sample / max,
+				)
+			})
+			.collect();
+		painter.add(egui::Shape::line(
+			points,
+			egui::Stroke::new(1.5, ui.visuals().selection.bg_fill),
+		));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+
+	#[test]
+	fn samples_new_runs_and_throughput() {
+		let mut profiler = Profiler::default();
+		let timing = SystemTiming {
+			name: "movement_system",
+			last: Duration::from_millis(2),
+			total: Duration::from_millis(2),
+			runs: 1,
+		};
+		let timings = [timing];
+		profiler.record_systems(&timings);
+		profiler.record_systems(&timings);
+		assert_eq!(profiler.systems[0].graph.samples, [2.0]);
+
+		let traffic = |published| BusTraffic {
+			name: "AppMetrics",
+			published,
+		};
+		profiler.record_throughput(&[traffic(10)], 1.0);
+		profiler.record_throughput(&[traffic(30)], 2.0);
+		assert_eq!(profiler.buses[0].graph.samples, [10.0]);
+
+		for sample in 0..SAMPLES + 5 {
+			profiler.frame.push(sample as f64);
+		}
+		assert_eq!(profiler.frame.samples.len(), SAMPLES);
+		assert_eq!(profiler.frame.max(), (SAMPLES + 4) as f32);
+	}
+}
@@ -0,0 +1,351 @@
+use crate::{assets::ASSET_DIR, settings::config_path};
+use hourglass::app::{log, StateError, StateResult};
+use serde::{Deserialize, Serialize};
+use std::{
+	fs,
+	path::{Path, PathBuf},
+};
+
+/// The file in a project's directory describing it
+pub const PROJECT_FILE: &str = "project.ron";
+const RECENT_FILE: &str = "recent_projects.ron";
+const MAX_RECENT: usize = 10;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BuildProfile {
+	#[default]
+	Debug,
+	Release,
+}
+
+/// How the game is built from the project
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildSettings {
+	pub profile: BuildProfile,
+	/// The target triple, or the host's when empty
+	pub target: String,
+	pub features: Vec<String>,
+	/// Relative to the project directory
+	pub output_dir: PathBuf,
+}
+
+impl Default for BuildSettings {
+	fn default() -> Self {
+		Self {
+			profile: BuildProfile::default(),
+			target: String::new(),
+			features: Vec::new(),
+			output_dir: PathBuf::from("build"),
+		}
+	}
+}
+
+impl BuildSettings {
+	pub fn cargo_args(&self) -> Vec<String> {
+		let mut args = vec!["build".to_string()];
+		if self.profile == BuildProfile::Release {
+			args.push("--release".to_string());
+		}
+		if !self.target.is_empty() {
+			args.extend(["--target".to_string(), self.target.clone()]);
+		}
+		if !self.features.is_empty() {
+			args.extend(["--features".to_string(), self.features.join(",")]);
+		}
+		args.push("--target-dir".to_string());
+		args.push(self.output_dir.to_string_lossy().into_owned());
+		args
+	}
+}
+
+/// A game being edited, saved as `PROJECT_FILE` in its directory.
+/// The editor works from the project's directory while it is open.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Project {
+	pub name: String,
+	/// Relative to the project directory
+	pub asset_root: PathBuf,
+	pub build: BuildSettings,
+	#[serde(skip)]
+	directory: PathBuf,
+}
+
+impl Project {
+	/// Creates the project's directory with an empty asset root
+	pub fn create(directory: impl Into<PathBuf>, name: &str) -> StateResult<Self> {
+		let project = Self {
+			name: name.to_string(),
+			asset_root: PathBuf::from(ASSET_DIR),
+			build: BuildSettings::default(),
+			directory: directory.into(),
+		};
+		if project.file().exists() {
+			return Err(format!("{} already has a project", project.directory.display()).into());
+		}
+		fs::create_dir_all(project.directory.join(&project.asset_root))?;
+		project.save()?;
+		Self::load(project.directory)
+	}
+
+	pub fn load(directory: impl Into<PathBuf>) -> StateResult<Self> {
+		let directory = fs::canonicalize(directory.into())?;
+		let contents = fs::read_to_string(directory.join(PROJECT_FILE))?;
+		Ok(Self {
+			directory,
+			..ron::from_str(&contents)?
+		})
+	}
+
+	pub fn save(&self) -> StateResult<()> {
+		let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+			.map_err(Box::<StateError>::from)?;
+		Ok(fs::write(self.file(), contents)?)
+	}
+
+	pub fn directory(&self) -> &Path {
+		&self.directory
+	}
+
+	fn file(&self) -> PathBuf {
+		self.directory.join(PROJECT_FILE)
+	}
+}
+
+/// The most recently opened project directories, newest first, kept in the user's config directory
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecentProjects(pub Vec<PathBuf>);
+
+impl RecentProjects {
+	pub fn load() -> Self {
+		let Some(path) = config_path(RECENT_FILE) else {
+			return Self::default();
+		};
+		let Ok(contents) = fs::read_to_string(&path) else {
+			return Self::default();
+		};
+		ron::from_str(&contents).unwrap_or_else(|error| {
+			log::warn!("Failed to read {}: {error}", path.display());
+			Self::default()
+		})
+	}
+
+	pub fn save(&self) -> StateResult<()> {
+		let path = config_path(RECENT_FILE).ok_or("There is no config directory")?;
+		if let Some(directory) = path.parent() {
+			fs::create_dir_all(directory)?;
+		}
+		let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+			.map_err(Box::<StateError>::from)?;
+		Ok(fs::write(path, contents)?)
+	}
+
+	pub fn add(&mut self, directory: &Path) {
+		let directory = fs::canonicalize(directory).unwrap_or_else(|_| directory.to_path_buf());
+		self.0.retain(|recent| *recent != directory);
+		self.0.insert(0, directory);
+		self.0.truncate(MAX_RECENT);
+	}
+}
+
+/// Shown until a project is open, to create one or open an existing or recent one
+pub struct StartScreen {
+	pub recent: RecentProjects,
+	name: String,
+	location: String,
+	open: String,
+	error: Option<String>,
+}
+
+impl Default for StartScreen {
+	fn default() -> Self {
+		Self {
+			recent: RecentProjects::default(),
+			name: "Game".to_string(),
+			location: dirs::home_dir()
+				.map(|home| home.to_string_lossy().into_owned())
+				.unwrap_or_default(),
+			open: String::new(),
+			error: None,
+		}
+	}
+}
+
+impl StartScreen {
+	/// Returns the project to open once one is picked
+	pub fn show(&mut self, ui: &mut egui::Ui) -> Option<Project> {
+		let mut opened = None;
+		ui.heading("Hourglass Editor");
+		ui.separator();
+
+		ui.strong("Recent projects");
+		if self.recent.0.is_empty() {
+			ui.weak("No recent projects");
+		}
+		for directory in &self.recent.0 {
+			let missing = !directory.join(PROJECT_FILE).exists();
+			let response = ui
+				.add_enabled(
+					!missing,
+					egui::Button::new(directory.display().to_string()).frame(false),
+				)
+				.on_disabled_hover_text("The project no longer exists");
+			if response.clicked() {
+				opened = Some(Project::load(directory));
+			}
+		}
+		ui.separator();
+
+		ui.strong("New project");
+		egui::Grid::new("new_project").show(ui, |ui| {
+			ui.label("Name");
+			ui.text_edit_singleline(&mut self.name);
+			ui.end_row();
+			ui.label("Location");
+			ui.text_edit_singleline(&mut self.location);
+			ui.end_row();
+		});
+		if ui.button("Create").clicked() {
+			let directory = Path::new(&self.location).join(&self.name);
+			opened = Some(Project::create(directory, &self.name));
+		}
+		ui.separator();
+
+		ui.strong("Open project");
+		ui.horizontal(|ui| {
+			ui.add(egui::TextEdit::singleline(&mut self.open).hint_text("Project directory"));
+			if ui.button("Open").clicked() {
+				opened = Some(Project::load(&self.open));
+			}
+		});
+
+		if let Some(error) = &self.error {
+			ui.colored_label(ui.visuals().error_fg_color, error);
+		}
+		match opened? {
+			Ok(project) => {
+				self.error = None;
+				Some(project)
+			}
+			Err(error) => {
+				self.error = Some(format!("Failed to open the project: {error}"));
+				None
+			}
+		}
+	}
+}
+
+/// Edits the open project's settings, which take effect when saved
+#[derive(Default)]
+pub struct ProjectSettings {
+	pub project: Option<Project>,
+	features: String,
+}
+
+impl ProjectSettings {
+	pub fn open(&mut self, project: Project) {
+		self.features = project.build.features.join(", ");
+		self.project = Some(project);
+	}
+
+	/// Returns the project once it has been saved
+	pub fn show(&mut self, ui: &mut egui::Ui) -> Option<&Project> {
+		let project = self.project.as_mut()?;
+		let mut saved = false;
+		egui::Grid::new("project_settings").show(ui, |ui| {
+			ui.label("Directory");
+			ui.label(project.directory.display().to_string());
+			ui.end_row();
+			ui.label("Name");
+			ui.text_edit_singleline(&mut project.name);
+			ui.end_row();
+			ui.label("Asset root");
+			let mut asset_root = project.asset_root.to_string_lossy().into_owned();
+			if ui.text_edit_singleline(&mut asset_root).changed() {
+				project.asset_root = PathBuf::from(asset_root);
+			}
+			ui.end_row();
+		});
+		ui.separator();
+		ui.strong("Build");
+		let build = &mut project.build;
+		egui::Grid::new("build_settings").show(ui, |ui| {
+			ui.label("Profile");
+			ui.horizontal(|ui| {
+				ui.selectable_value(&mut build.profile, BuildProfile::Debug, "Debug");
+				ui.selectable_value(&mut build.profile, BuildProfile::Release, "Release");
+			});
+			ui.end_row();
+			ui.label("Target");
+			ui.add(egui::TextEdit::singleline(&mut build.target).hint_text("Host"));
+			ui.end_row();
+			ui.label("Features");
+			if ui.text_edit_singleline(&mut self.features).changed() {
+				build.features = self
+					.features
+					.split(',')
+					.map(str::trim)
+					.filter(|feature| !feature.is_empty())
+					.map(str::to_string)
+					.collect();
+			}
+			ui.end_row();
+			ui.label("Output directory");
+			let mut output_dir = build.output_dir.to_string_lossy().into_owned();
+			if ui.text_edit_singleline(&mut output_dir).changed() {
+				build.output_dir = PathBuf::from(output_dir);
+			}
+			ui.end_row();
+		});
+		ui.monospace(format!("cargo {}", build.cargo_args().join(" ")));
+		ui.separator();
+		if ui.button("Save").clicked() {
+			match project.save() {
+				Ok(()) => saved = true,
+				Err(error) => log::warn!("Failed to save the project: {error}"),
+			}
+		}
+		saved.then_some(&*project)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn projects_are_created_and_reopened() -> StateResult<()> {
+		let directory = std::env::temp_dir().join("hourglass_editor_project");
+		let _ = fs::remove_dir_all(&directory);
+		let mut project = Project::create(&directory, "Game")?;
+		assert!(directory.join(ASSET_DIR).is_dir());
+		assert!(Project::create(&directory, "Game").is_err());
+
+		project.build.profile = BuildProfile::Release;
+		project.build.features = vec!["gamepad".to_string(), "tray".to_string()];
+		project.save()?;
+		let reopened = Project::load(&directory)?;
+		assert_eq!(reopened, project);
+		assert_eq!(
+			reopened.build.cargo_args(),
+			[
+				"build",
+				"--release",
+				"--features",
+				"gamepad,tray",
+				"--target-dir",
+				"build"
+			]
+		);
+
+		let mut recent = RecentProjects::default();
+		recent.add(Path::new("/games/a"));
+		recent.add(Path::new("/games/b"));
+		recent.add(Path::new("/games/a"));
+		assert_eq!(
+			recent.0,
+			[PathBuf::from("/games/a"), PathBuf::from("/games/b")]
+		);
+		fs::remove_dir_all(directory)?;
+		Ok(())
+	}
+}
@@ -0,0 +1,269 @@
+use crate::{console::record_line, inspector::edit_value};
+use hourglass::app::{
+	DebugClient, DebugMessage, LogRecord, RemoteEntity, RemoteEntityId, RemoteTiming,
+};
+use std::{
+	collections::VecDeque,
+	time::{Duration, Instant},
+};
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:7777";
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_LOGS: usize = 1000;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Page {
+	#[default]
+	Entities,
+	Logs,
+	Timings,
+}
+
+/// Attaches to a running app served with `AppBuilder::serve_debugger`. Its entities are
+/// mirrored once a second for inspecting and editing, and its logs and timings streamed.
+pub struct Remote {
+	address: String,
+	client: Option<DebugClient>,
+	status: String,
+	page: Page,
+	entities: Vec<RemoteEntity>,
+	selected: Option<RemoteEntityId>,
+	logs: VecDeque<LogRecord>,
+	timings: Vec<RemoteTiming>,
+	refreshed: Option<Instant>,
+}
+
+impl Default for Remote {
+	fn default() -> Self {
+		Self {
+			address: DEFAULT_ADDRESS.to_string(),
+			client: None,
+			status: "Detached".to_string(),
+			page: Page::default(),
+			entities: Vec::new(),
+			selected: None,
+			logs: VecDeque::new(),
+			timings: Vec::new(),
+			refreshed: None,
+		}
+	}
+}
+
+impl Remote {
+	/// Takes the messages the app sent and asks for its entities again, called every update
+	pub fn poll(&mut self) {
+		let Some(client) = &self.client else {
+			return;
+		};
+		if let Some(reason) = client.closed() {
+			self.status = reason;
+			self.client = None;
+			return;
+		}
+		let messages = std::iter::from_fn(|| client.try_recv()).collect::<Vec<_>>();
+		if self
+			.refreshed
+			.is_none_or(|refreshed| refreshed.elapsed() >= REFRESH_INTERVAL)
+		{
+			client.send(DebugMessage::Inspect);
+			self.refreshed = Some(Instant::now());
+		}
+		for message in messages {
+			self.receive(message);
+		}
+	}
+
+	fn receive(&mut self, message: DebugMessage) {
+		match message {
+			DebugMessage::World(entities) => self.entities = entities,
+			DebugMessage::Logs(logs) => {
+				self.logs.extend(logs);
+				let excess = self.logs.len().saturating_sub(MAX_LOGS);
+				self.logs.drain(..excess);
+			}
+			DebugMessage::Timings(timings) => self.timings = timings,
+			DebugMessage::Inspect | DebugMessage::SetField { .. } => {}
+		}
+	}
+
+	pub fn show(&mut self, ui: &mut egui::Ui) {
+		ui.horizontal(|ui| {
+			ui.label("Address");
+			ui.add_enabled(
+				self.client.is_none(),
+				egui::TextEdit::singleline(&mut self.address).desired_width(140.0),
+			);
+			if self.client.is_some() {
+				if ui.button("Detach").clicked() {
+					self.client = None;
+					self.status = "Detached".to_string();
+				}
+			} else if ui.button("Attach").clicked() {
+				self.attach();
+			}
+			ui.weak(&self.status);
+		});
+		ui.horizontal(|ui| {
+			ui.selectable_value(&mut self.page, Page::Entities, "Entities");
+			ui.selectable_value(&mut self.page, Page::Logs, "Logs");
+			ui.selectable_value(&mut self.page, Page::Timings, "Timings");
+		});
+		ui.separator();
+		match self.page {
+			Page::Entities => self.show_entities(ui),
+			Page::Logs => {
+				egui::ScrollArea::vertical()
+					.auto_shrink(false)
+					.stick_to_bottom(true)
+					.show(ui, |ui| {
+						for record in &self.logs {
+							record_line(ui, record);
+						}
+					});
+			}
+			Page::Timings => {
+				egui::Grid::new("remote_timings")
+					.striped(true)
+					.show(ui, |ui| {
+						ui.strong("System");
+						ui.strong("Last");
+						ui.strong("Average");
+						ui.strong("Runs");
+						ui.end_row();
+						for timing in &self.timings {
+							ui.label(&timing.name);
+							ui.label(format!("{:.3} ms", timing.last.as_secs_f64() * 1000.0));
+							ui.label(format!("{:.3} ms", timing.average.as_secs_f64() * 1000.0));
+							ui.label(timing.runs.to_string());
+							ui.end_row();
+						}
+					});
+			}
+		}
+	}
+
+	fn attach(&mut self) {
+		*self = Self {
+			address: self.address.clone(),
+			..Self::default()
+		};
+		match DebugClient::connect(self.address.clone()) {
+			Ok(client) => {
+				self.client = Some(client);
+				self.status = format!("Attached to {}", self.address);
+			}
+			Err(error) => self.status = error.to_string(),
+		}
+	}
+
+	// Edits are sent to the app and made to the mirror so they show before the next refresh
+	fn show_entities(&mut self, ui: &mut egui::Ui) {
+		let mut edit = None;
+		ui.columns(2, |columns| {
+			egui::ScrollArea::vertical()
+				.id_salt("remote_hierarchy")
+				.show(&mut columns[0], |ui| {
+					let ids = self
+						.entities
+						.iter()
+						.map(|entity| entity.id)
+						.collect::<Vec<_>>();
+					for entity in &self.entities {
+						if entity.parent.is_none_or(|parent| !ids.contains(&parent)) {
+							entity_node(ui, &self.entities, entity, &mut self.selected);
+						}
+					}
+				});
+			let ui = &mut columns[1];
+			let Some(entity) = self
+				.entities
+				.iter_mut()
+				.find(|entity| Some(entity.id) == self.selected)
+			else {
+				ui.label("Nothing selected");
+				return;
+			};
+			ui.heading(remote_label(entity));
+			for component in &mut entity.components {
+				ui.collapsing(&component.name, |ui| {
+					for (field, value) in &mut component.fields {
+						if edit_value(ui, field, value, false).changed() {
+							edit = Some(DebugMessage::SetField {
+								entity: entity.id,
+								component: component.name.clone(),
+								field: field.clone(),
+								value: value.clone(),
+							});
+						}
+					}
+				});
+			}
+		});
+		if let (Some(edit), Some(client)) = (edit, &self.client) {
+			client.send(edit);
+		}
+	}
+}
+
+fn entity_node(
+	ui: &mut egui::Ui,
+	entities: &[RemoteEntity],
+	entity: &RemoteEntity,
+	selected: &mut Option<RemoteEntityId>,
+) {
+	let children = entities
+		.iter()
+		.filter(|child| child.parent == Some(entity.id))
+		.collect::<Vec<_>>();
+	let mut label = |ui: &mut egui::Ui| {
+		if ui
+			.selectable_label(*selected == Some(entity.id), remote_label(entity))
+			.clicked()
+		{
+			*selected = Some(entity.id);
+		}
+	};
+	if children.is_empty() {
+		ui.horizontal(|ui| {
+			ui.add_space(ui.spacing().indent);
+			label(ui);
+		});
+		return;
+	}
+	let id = ui.make_persistent_id(("remote_hierarchy", entity.id));
+	egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, true)
+		.show_header(ui, label)
+		.body(|ui| {
+			for child in children {
+				entity_node(ui, entities, child, selected);
+			}
+		});
+}
+
+fn remote_label(entity: &RemoteEntity) -> String {
+	entity
+		.name
+		.clone()
+		.unwrap_or_else(|| format!("Entity {}", entity.id.index))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use hourglass::app::log::Level;
+
+	#[test]
+	fn received_logs_are_capped() {
+		let mut remote = Remote::default();
+		let record = LogRecord {
+			level: Level::Info,
+			target: "game".to_string(),
+			message: "Frame".to_string(),
+			elapsed: Duration::ZERO,
+		};
+		remote.receive(DebugMessage::Logs(vec![record; MAX_LOGS + 10]));
+		assert_eq!(remote.logs.len(), MAX_LOGS);
+		remote.receive(DebugMessage::World(Vec::new()));
+		assert!(remote.entities.is_empty());
+	}
+}
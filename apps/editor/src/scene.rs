@@ -0,0 +1,96 @@
+use crate::{play::FixedDelta, prefab::PrefabInstance};
+use hourglass::{
+	app::log,
+	ecs::{
+		error::Result,
+		hierarchy::{self, Name, Parent},
+		izip, reflect,
+		reflect::ComponentRegistry,
+		system,
+		world::{Entity, World},
+	},
+	math::Transform,
+};
+
+/// The entity's transform relative to the world, composed through its parents.
+/// Entities without a `Transform` count as the identity.
+pub fn world_transform(world: &World, entity: Entity) -> Transform {
+	let local = world
+		.get_component::<Transform>(entity)
+		.map_or_else(Transform::default, |transform| *transform);
+	match hierarchy::parent(world, entity) {
+		Some(parent) => world_transform(world, parent).mul_transform(&local),
+		None => local,
+	}
+}
+
+#[derive(Debug, Default)]
+pub struct Velocity {
+	pub x: f32,
+	pub y: f32,
+}
+
+reflect!(Velocity { x, y });
+
+#[derive(Debug, Default)]
+pub struct Health {
+	pub value: u32,
+}
+
+reflect!(Health { value });
+
+/// Draws an image asset, keyed by its path in `LoadedAssets`
+#[derive(Debug, Default)]
+pub struct Sprite {
+	pub path: String,
+}
+
+reflect!(Sprite { path });
+
+system!(movement_system, [resources, _entity], (), (transform: Transform, velocity: Velocity) -> Result<()> {
+	let delta = resources.borrow().get::<FixedDelta>().map_or(0.0, |delta| delta.0) as f32;
+	transform.translation.x += velocity.x * delta;
+	transform.translation.y += velocity.y * delta;
+	Ok(())
+});
+
+/// The world the editor opens with, with the editable components registered
+pub fn create_world() -> World {
+	let mut world = World::new();
+	let mut registry = ComponentRegistry::new();
+	registry
+		.register::<Transform>("Transform")
+		.register::<Velocity>("Velocity")
+		.register::<Health>("Health")
+		.register::<Sprite>("Sprite")
+		.register::<PrefabInstance>("PrefabInstance");
+	world.resources().borrow_mut().insert(registry);
+	if let Err(error) = populate(&mut world) {
+		log::error!("Failed to create the starting scene: {error}");
+	}
+	world
+}
+
+fn populate(world: &mut World) -> Result<()> {
+	let player = named(world, "Player")?;
+	world.add_component(player, Transform::default())?;
+	world.add_component(player, Velocity { x: 1.0, y: 0.5 })?;
+	world.add_component(player, Health { value: 100 })?;
+
+	for index in 0..3 {
+		let entity = named(world, &format!("Enemy {index}"))?;
+		world.add_component(entity, Transform::from_xyz(index as f32 * 2.0, 4.0, 0.0))?;
+		world.add_component(entity, Health { value: 100 })?;
+	}
+
+	let weapon = named(world, "Weapon")?;
+	world.add_component(weapon, Transform::from_xyz(0.75, 0.0, 0.0))?;
+	world.add_component(weapon, Parent(player))?;
+	Ok(())
+}
+
+fn named(world: &mut World, name: &str) -> Result<Entity> {
+	let entity = world.create_entity();
+	world.add_component(entity, Name(name.to_string()))?;
+	Ok(entity)
+}
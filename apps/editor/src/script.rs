@@ -0,0 +1,393 @@
+use crate::{
+	hierarchy::entity_label,
+	history::{registry, Edit, EntitySnapshot, History},
+	search::EntityQuery,
+};
+use hourglass::{
+	ecs::{
+		error::Result,
+		reflect::{ComponentInfo, ComponentRegistry, Field},
+		world::{Entity, World},
+	},
+	scripting::{evaluate, ScriptBindings, ScriptError, ScriptValue},
+};
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+type Shared = Rc<RefCell<Changes>>;
+
+const MAX_LINES: usize = 500;
+const HELP: &str = "\
+Lines are scripts run against the world, with the functions game scripts have and:
+  entities() | find(\"name\") | name(entity) | select(entities)
+  add(entity, \"Component\") | set(entity, \"Component\", #{ field: value })
+Changes a line makes are undone together. Such as:
+  for entity in find(\"Enemy\") { if get(entity, \"Health\").value == 0 { despawn(entity) } }";
+
+/// What running a line printed, and the entities it selected
+#[derive(Debug, Default, PartialEq)]
+pub struct Output {
+	pub text: String,
+	pub selected: Option<Vec<Entity>>,
+}
+
+// What the functions a line called did, gathered while it runs
+#[derive(Default)]
+struct Changes {
+	edits: Vec<Edit>,
+	selected: Option<Vec<Entity>>,
+}
+
+/// Runs a line of script against the world. Changes are made through the history, as one
+/// step, so they can be undone.
+pub fn run(world: &mut World, history: &mut History, line: &str) -> Result<Output> {
+	if matches!(line.trim(), "" | "help") {
+		return Ok(Output {
+			text: HELP.to_string(),
+			selected: None,
+		});
+	}
+	let changes = Shared::default();
+	let value = evaluate(world, &bindings(&changes), line);
+	let Changes { edits, selected } = changes.take();
+	// Changes made before an error are kept, so they can still be undone
+	let changed = edits.len();
+	if !edits.is_empty() {
+		history.record(Edit::batch(line.trim(), edits));
+	}
+	let text = match value? {
+		ScriptValue::Unit if changed > 0 => format!("Changed {changed}"),
+		ScriptValue::Unit => String::new(),
+		value => value.to_string(),
+	};
+	Ok(Output { text, selected })
+}
+
+// Functions that change the world replace the built in ones, so they go through the history
+fn bindings(changes: &Shared) -> ScriptBindings {
+	let mut bindings = ScriptBindings::new();
+	bindings.register_function("entities", 0, |world, _, _| Ok(entities(world.entities())));
+	bindings.register_function("find", 1, |world, _, arguments| {
+		let query = EntityQuery {
+			name: argument("find", arguments, 0, ScriptValue::as_text)?.to_string(),
+			..EntityQuery::default()
+		};
+		Ok(entities(query.results(world, &registry(world))))
+	});
+	bindings.register_function("name", 1, |world, _, arguments| {
+		let entity = argument("name", arguments, 0, ScriptValue::as_entity)?;
+		Ok(ScriptValue::Text(entity_label(world, entity)))
+	});
+	let selection = changes.clone();
+	bindings.register_function("select", 1, move |_, _, arguments| {
+		let selected = match &arguments[0] {
+			ScriptValue::Array(items) => items.iter().filter_map(ScriptValue::as_entity).collect(),
+			value => value.as_entity().into_iter().collect(),
+		};
+		selection.borrow_mut().selected = Some(selected);
+		Ok(ScriptValue::Unit)
+	});
+
+	let created = changes.clone();
+	bindings.register_function("spawn_entity", 0, move |world, _, _| {
+		let entity = world.create_entity();
+		let snapshot = EntitySnapshot {
+			entity,
+			name: None,
+			parent: None,
+			components: Vec::new(),
+		};
+		record(world, &created, Edit::Create(snapshot))?;
+		Ok(ScriptValue::Entity(entity))
+	});
+	let deleted = changes.clone();
+	bindings.register_function("despawn", 1, move |world, _, arguments| {
+		let entity = argument("despawn", arguments, 0, ScriptValue::as_entity)?;
+		// Already gone with an ancestor despawned earlier in the line
+		if world.entity_exists(entity) {
+			record(world, &deleted, Edit::delete(world, entity))?;
+		}
+		Ok(ScriptValue::Unit)
+	});
+	let added = changes.clone();
+	bindings.register_function("add", 2, move |world, _, arguments| {
+		let (entity, info) = component("add", world, arguments)?;
+		if !info.is_on(world, entity) {
+			let edit = Edit::AddComponent {
+				entity,
+				component: info.name,
+			};
+			record(world, &added, edit)?;
+		}
+		Ok(ScriptValue::Unit)
+	});
+	let removed = changes.clone();
+	bindings.register_function("remove", 2, move |world, _, arguments| {
+		let (entity, info) = component("remove", world, arguments)?;
+		if info.is_on(world, entity) {
+			record(
+				world,
+				&removed,
+				Edit::remove_component(world, entity, &info),
+			)?;
+		}
+		Ok(ScriptValue::Unit)
+	});
+	let set = changes.clone();
+	bindings.register_function("set", 3, move |world, _, arguments| {
+		set_fields(world, &set, arguments)?;
+		Ok(ScriptValue::Unit)
+	});
+	bindings
+}
+
+// Adds the component if it's missing, then sets each field in the map
+fn set_fields(
+	world: &mut World,
+	changes: &Shared,
+	arguments: &[ScriptValue],
+) -> Result<(), ScriptError> {
+	let (entity, info) = component("set", world, arguments)?;
+	let Some(ScriptValue::Map(values)) = arguments.get(2) else {
+		return Err(ScriptError::Arguments("set".to_string()));
+	};
+	let fields = match info.fields(world, entity) {
+		Some(fields) => fields,
+		None => {
+			let edit = Edit::AddComponent {
+				entity,
+				component: info.name,
+			};
+			record(world, changes, edit)?;
+			info.default_fields()
+		}
+	};
+	for (name, value) in values {
+		let old = fields
+			.iter()
+			.find(|field| field.name == name)
+			.ok_or_else(|| {
+				ScriptError::Runtime(format!("{} has no field named '{name}'", info.name))
+			})?;
+		let value = value.to_field(&old.value).ok_or_else(|| {
+			ScriptError::Runtime(format!(
+				"Can't set {}.{name} to a {}",
+				info.name,
+				value.type_name()
+			))
+		})?;
+		let edit = Edit::SetField {
+			entity,
+			component: info.name,
+			old: old.clone(),
+			new: Field {
+				name: old.name,
+				value,
+			},
+		};
+		record(world, changes, edit)?;
+	}
+	Ok(())
+}
+
+// Made straight away so the rest of the line sees it, and kept for the history
+fn record(world: &mut World, changes: &Shared, edit: Edit) -> Result<(), ScriptError> {
+	edit.redo(world)
+		.map_err(|error| ScriptError::Runtime(error.to_string()))?;
+	changes.borrow_mut().edits.push(edit);
+	Ok(())
+}
+
+fn entities(entities: Vec<Entity>) -> ScriptValue {
+	ScriptValue::Array(entities.into_iter().map(ScriptValue::Entity).collect())
+}
+
+fn argument<'a, T>(
+	function: &str,
+	arguments: &'a [ScriptValue],
+	index: usize,
+	convert: impl Fn(&'a ScriptValue) -> Option<T>,
+) -> Result<T, ScriptError> {
+	arguments
+		.get(index)
+		.and_then(convert)
+		.ok_or_else(|| ScriptError::Arguments(function.to_string()))
+}
+
+// The entity and registered component named by the first two arguments
+fn component(
+	function: &str,
+	world: &World,
+	arguments: &[ScriptValue],
+) -> Result<(Entity, ComponentInfo), ScriptError> {
+	let entity = argument(function, arguments, 0, ScriptValue::as_entity)?;
+	let name = argument(function, arguments, 1, ScriptValue::as_text)?;
+	let registry: ComponentRegistry = registry(world);
+	let info = registry
+		.get(name)
+		.cloned()
+		.ok_or_else(|| ScriptError::UnknownComponent(name.to_string()))?;
+	Ok((entity, info))
+}
+
+enum Line {
+	Input(String),
+	Output(String),
+	Error(String),
+}
+
+/// Runs commands that query and change the world, with the previous lines recalled by arrow keys
+#[derive(Default)]
+pub struct Script {
+	input: String,
+	lines: VecDeque<Line>,
+	entered: Vec<String>,
+	recalled: Option<usize>,
+	/// Entities picked by the last `select`, for the hierarchy to take
+	pub selected: Option<Vec<Entity>>,
+}
+
+impl Script {
+	pub fn show(&mut self, ui: &mut egui::Ui, world: &mut World, history: &mut History) {
+		let input_height = ui.spacing().interact_size.y + ui.spacing().item_spacing.y * 2.0;
+		egui::ScrollArea::vertical()
+			.auto_shrink(false)
+			.stick_to_bottom(true)
+			.max_height(ui.available_height() - input_height)
+			.show(ui, |ui| {
+				for line in &self.lines {
+					match line {
+						Line::Input(text) => ui.monospace(format!("> {text}")),
+						Line::Output(text) => ui.label(egui::RichText::new(text).monospace()),
+						Line::Error(text) => ui.colored_label(ui.visuals().error_fg_color, text),
+					};
+				}
+			});
+		ui.separator();
+
+		let response = ui.add(
+			egui::TextEdit::singleline(&mut self.input)
+				.hint_text("Type 'help' for commands")
+				.font(egui::TextStyle::Monospace)
+				.desired_width(f32::INFINITY),
+		);
+		if response.has_focus() {
+			self.recall(ui);
+		}
+		if response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter)) {
+			let line = std::mem::take(&mut self.input);
+			self.enter(world, history, &line);
+			response.request_focus();
+		}
+	}
+
+	fn recall(&mut self, ui: &egui::Ui) {
+		let (up, down) = ui.input(|input| {
+			(
+				input.key_pressed(egui::Key::ArrowUp),
+				input.key_pressed(egui::Key::ArrowDown),
+			)
+		});
+		let last = self.entered.len().checked_sub(1);
+		self.recalled = match (up, down, self.recalled) {
+			(true, _, None) => last,
+			(true, _, Some(index)) => Some(index.saturating_sub(1)),
+			(_, true, Some(index)) => Some(index + 1).filter(|index| *index < self.entered.len()),
+			_ => return,
+		};
+		self.input = self
+			.recalled
+			.map(|index| self.entered[index].clone())
+			.unwrap_or_default();
+	}
+
+	fn enter(&mut self, world: &mut World, history: &mut History, line: &str) {
+		if line.trim().is_empty() {
+			return;
+		}
+		self.push(Line::Input(line.to_string()));
+		match run(world, history, line) {
+			Ok(output) => {
+				if !output.text.is_empty() {
+					self.push(Line::Output(output.text));
+				}
+				self.selected = output.selected.or(self.selected.take());
+			}
+			Err(error) => self.push(Line::Error(error.to_string())),
+		}
+		self.entered.push(line.to_string());
+		self.recalled = None;
+	}
+
+	fn push(&mut self, line: Line) {
+		self.lines.push_back(line);
+		if self.lines.len() > MAX_LINES {
+			self.lines.pop_front();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::scene::{create_world, Health};
+	use hourglass::ecs::hierarchy;
+
+	#[test]
+	fn lines_query_and_change_the_world() -> Result<()> {
+		let mut world = create_world();
+		let mut history = History::default();
+		let names = |world: &World, entities: &[Entity]| {
+			entities
+				.iter()
+				.filter_map(|entity| hierarchy::name(world, *entity))
+				.collect::<Vec<_>>()
+		};
+
+		let output = run(
+			&mut world,
+			&mut history,
+			"for entity in find(\"Enemy \") { \
+				if get(entity, \"Transform\").x < 3 { set(entity, \"Health\", #{ value: 0 }) } }",
+		)?;
+		assert_eq!(output.text, "Changed 2");
+		let dead = "entities().filter(|entity| has(entity, \"Health\") \
+			&& get(entity, \"Health\").value == 0)";
+		let output = run(&mut world, &mut history, &format!("select({dead})"))?;
+		let selected = output.selected.unwrap_or_default();
+		assert_eq!(names(&world, &selected), ["Enemy 0", "Enemy 1"]);
+		assert!(world.get_component::<Health>(selected[0]).is_some());
+
+		run(
+			&mut world,
+			&mut history,
+			&format!("for entity in {dead} {{ despawn(entity) }}"),
+		)?;
+		assert_eq!(run(&mut world, &mut history, "entities().len()")?.text, "3");
+		history.undo(&mut world)?;
+		assert_eq!(
+			run(
+				&mut world,
+				&mut history,
+				"entities().filter(|entity| has(entity, \"Health\")).len()"
+			)?
+			.text,
+			"4"
+		);
+
+		let enemy = "find(\"Enemy 0\")[0]";
+		assert!(run(
+			&mut world,
+			&mut history,
+			&format!("set({enemy}, \"Health\", #{{ mana: 1 }})")
+		)
+		.is_err());
+		assert!(run(
+			&mut world,
+			&mut history,
+			&format!("add({enemy}, \"Nothing\")")
+		)
+		.is_err());
+		assert!(run(&mut world, &mut history, "find(\"Enemy)").is_err());
+		Ok(())
+	}
+}
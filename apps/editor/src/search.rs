@@ -0,0 +1,269 @@
+use hourglass::ecs::{
+	hierarchy,
+	reflect::{ComponentRegistry, Value},
+	world::{Entity, World},
+};
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+	Equal,
+	NotEqual,
+	Less,
+	Greater,
+	Contains,
+}
+
+impl Comparison {
+	const ALL: [Self; 5] = [
+		Self::Equal,
+		Self::NotEqual,
+		Self::Less,
+		Self::Greater,
+		Self::Contains,
+	];
+
+	fn symbol(self) -> &'static str {
+		match self {
+			Self::Equal => "=",
+			Self::NotEqual => "≠",
+			Self::Less => "<",
+			Self::Greater => ">",
+			Self::Contains => "contains",
+		}
+	}
+
+	// The text is read as the field's type, so `1` equals a float field of `1.0`
+	fn compare(self, value: &Value, text: &str) -> bool {
+		if self == Self::Contains {
+			return display(value).to_lowercase().contains(&text.to_lowercase());
+		}
+		let ordering = match value {
+			Value::Bool(value) => text.parse::<bool>().ok().map(|text| value.cmp(&text)),
+			Value::Integer(value) => text.parse::<i64>().ok().map(|text| value.cmp(&text)),
+			Value::Unsigned(value) => text.parse::<u64>().ok().map(|text| value.cmp(&text)),
+			Value::Float(value) => text
+				.parse::<f64>()
+				.ok()
+				.and_then(|text| value.partial_cmp(&text)),
+			Value::Text(value) => Some(value.as_str().cmp(text)),
+		};
+		match (self, ordering) {
+			(Self::NotEqual, None) => true,
+			(_, None) => false,
+			(Self::Equal, Some(ordering)) => ordering == Ordering::Equal,
+			(Self::NotEqual, Some(ordering)) => ordering != Ordering::Equal,
+			(Self::Less, Some(ordering)) => ordering == Ordering::Less,
+			(Self::Greater, Some(ordering)) => ordering == Ordering::Greater,
+			(Self::Contains, Some(_)) => unreachable!("Contains is compared as text"),
+		}
+	}
+}
+
+fn display(value: &Value) -> String {
+	match value {
+		Value::Bool(value) => value.to_string(),
+		Value::Integer(value) => value.to_string(),
+		Value::Unsigned(value) => value.to_string(),
+		Value::Float(value) => value.to_string(),
+		Value::Text(value) => value.clone(),
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+	Has(&'static str),
+	Field {
+		component: &'static str,
+		field: &'static str,
+		comparison: Comparison,
+		value: String,
+	},
+}
+
+impl Filter {
+	fn matches(&self, world: &World, registry: &ComponentRegistry, entity: Entity) -> bool {
+		match self {
+			Self::Has(component) => registry
+				.get(component)
+				.is_some_and(|info| info.is_on(world, entity)),
+			Self::Field {
+				component,
+				field,
+				comparison,
+				value,
+			} => registry
+				.get(component)
+				.and_then(|info| info.fields(world, entity))
+				.and_then(|fields| fields.into_iter().find(|current| current.name == *field))
+				.is_some_and(|current| comparison.compare(&current.value, value)),
+		}
+	}
+}
+
+/// Finds entities by name and by the components and field values they have.
+/// An entity matches when its name contains the text and every filter matches.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct EntityQuery {
+	pub name: String,
+	pub filters: Vec<Filter>,
+}
+
+impl EntityQuery {
+	pub fn is_empty(&self) -> bool {
+		self.name.is_empty() && self.filters.is_empty()
+	}
+
+	pub fn matches(&self, world: &World, registry: &ComponentRegistry, entity: Entity) -> bool {
+		let name = hierarchy::name(world, entity).unwrap_or_default();
+		name.to_lowercase().contains(&self.name.to_lowercase())
+			&& self
+				.filters
+				.iter()
+				.all(|filter| filter.matches(world, registry, entity))
+	}
+
+	pub fn results(&self, world: &World, registry: &ComponentRegistry) -> Vec<Entity> {
+		world
+			.entities()
+			.into_iter()
+			.filter(|entity| self.matches(world, registry, *entity))
+			.collect()
+	}
+
+	/// The search box and a row for each filter
+	pub fn show(&mut self, ui: &mut egui::Ui, registry: &ComponentRegistry) {
+		let components = registry.iter().map(|info| info.name).collect::<Vec<_>>();
+		ui.horizontal(|ui| {
+			ui.add(
+				egui::TextEdit::singleline(&mut self.name)
+					.hint_text("Search")
+					.desired_width(120.0),
+			);
+			ui.add_enabled_ui(!components.is_empty(), |ui| {
+				ui.menu_button("Filter", |ui| {
+					if ui.button("Has component").clicked() {
+						self.filters.push(Filter::Has(components[0]));
+					}
+					if ui.button("Field value").clicked() {
+						let info = registry.iter().next().expect("Checked for components");
+						self.filters.push(Filter::Field {
+							component: info.name,
+							field: info.default_fields().first().map_or("", |field| field.name),
+							comparison: Comparison::Equal,
+							value: String::new(),
+						});
+					}
+				});
+			});
+			if !self.is_empty() && ui.button("Clear").clicked() {
+				*self = Self::default();
+			}
+		});
+
+		let mut removed = None;
+		for (index, filter) in self.filters.iter_mut().enumerate() {
+			ui.horizontal(|ui| {
+				if ui.small_button("✖").clicked() {
+					removed = Some(index);
+				}
+				match filter {
+					Filter::Has(component) => {
+						ui.label("Has");
+						pick(ui, ("filter_has", index), component, &components);
+					}
+					Filter::Field {
+						component,
+						field,
+						comparison,
+						value,
+					} => {
+						if pick(ui, ("filter_component", index), component, &components) {
+							*field = "";
+						}
+						let fields = registry
+							.get(component)
+							.map(|info| info.default_fields())
+							.unwrap_or_default()
+							.into_iter()
+							.map(|field| field.name)
+							.collect::<Vec<_>>();
+						if field.is_empty() {
+							*field = fields.first().copied().unwrap_or_default();
+						}
+						pick(ui, ("filter_field", index), field, &fields);
+						egui::ComboBox::from_id_salt(("filter_comparison", index))
+							.width(0.0)
+							.selected_text(comparison.symbol())
+							.show_ui(ui, |ui| {
+								for option in Comparison::ALL {
+									ui.selectable_value(comparison, option, option.symbol());
+								}
+							});
+						ui.add(egui::TextEdit::singleline(value).desired_width(60.0));
+					}
+				}
+			});
+		}
+		if let Some(index) = removed {
+			self.filters.remove(index);
+		}
+	}
+}
+
+// Returns whether the choice changed
+fn pick(
+	ui: &mut egui::Ui,
+	id: impl egui::AsIdSalt,
+	choice: &mut &'static str,
+	options: &[&'static str],
+) -> bool {
+	let previous = *choice;
+	egui::ComboBox::from_id_salt(id)
+		.selected_text(*choice)
+		.show_ui(ui, |ui| {
+			for option in options {
+				ui.selectable_value(choice, *option, *option);
+			}
+		});
+	*choice != previous
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{history::registry, scene::create_world};
+
+	#[test]
+	fn entities_are_found_by_name_component_and_field() {
+		let world = create_world();
+		let registry = registry(&world);
+		let names = |query: &EntityQuery| {
+			query
+				.results(&world, &registry)
+				.into_iter()
+				.filter_map(|entity| hierarchy::name(&world, entity))
+				.collect::<Vec<_>>()
+		};
+
+		let mut query = EntityQuery {
+			name: "enemy".to_string(),
+			filters: Vec::new(),
+		};
+		assert_eq!(names(&query), ["Enemy 0", "Enemy 1", "Enemy 2"]);
+
+		query.filters.push(Filter::Field {
+			component: "Transform",
+			field: "x",
+			comparison: Comparison::Greater,
+			value: "1".to_string(),
+		});
+		assert_eq!(names(&query), ["Enemy 1", "Enemy 2"]);
+
+		query.name.clear();
+		query.filters = vec![Filter::Has("Velocity")];
+		assert_eq!(names(&query), ["Player"]);
+		assert!(Comparison::Contains.compare(&Value::Text("Sword".to_string()), "wor"));
+		assert!(!Comparison::Less.compare(&Value::Integer(3), "three"));
+	}
+}
@@ -0,0 +1,96 @@
+use hourglass::ecs::{
+	hierarchy,
+	world::{Entity, World},
+};
+
+/// The selected entities in the order they were picked. The last one picked is
+/// the primary selection, which the gizmo follows and the inspector names.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Selection(Vec<Entity>);
+
+impl Selection {
+	pub fn primary(&self) -> Option<Entity> {
+		self.0.last().copied()
+	}
+
+	pub fn entities(&self) -> &[Entity] {
+		&self.0
+	}
+
+	pub fn contains(&self, entity: Entity) -> bool {
+		self.0.contains(&entity)
+	}
+
+	pub fn select(&mut self, entity: Entity) {
+		self.0 = vec![entity];
+	}
+
+	pub fn set(&mut self, entities: impl IntoIterator<Item = Entity>) {
+		self.0 = entities.into_iter().collect();
+	}
+
+	pub fn clear(&mut self) {
+		self.0.clear();
+	}
+
+	/// Selects only the entity, or adds or removes it with Ctrl held
+	pub fn click(&mut self, entity: Entity, modifiers: egui::Modifiers) {
+		if !modifiers.command {
+			self.select(entity);
+		} else if let Some(index) = self.0.iter().position(|selected| *selected == entity) {
+			self.0.remove(index);
+		} else {
+			self.0.push(entity);
+		}
+	}
+
+	/// Forgets entities that no longer exist
+	pub fn retain_existing(&mut self, world: &World) {
+		self.0.retain(|entity| world.entity_exists(*entity));
+	}
+
+	/// The selected entities without a selected ancestor, so edits that take
+	/// descendants along, like deleting or reparenting, reach each entity once
+	pub fn roots(&self, world: &World) -> Vec<Entity> {
+		self.0
+			.iter()
+			.copied()
+			.filter(|entity| {
+				!self
+					.0
+					.iter()
+					.any(|other| hierarchy::is_descendant(world, *entity, *other))
+			})
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use hourglass::ecs::hierarchy::Parent;
+
+	#[test]
+	fn ctrl_clicks_toggle_and_roots_skip_descendants() -> hourglass::ecs::error::Result<()> {
+		let mut world = World::new();
+		let [parent, child, other] = [(); 3].map(|_| world.create_entity());
+		world.add_component(child, Parent(parent))?;
+
+		let mut selection = Selection::default();
+		selection.click(child, egui::Modifiers::NONE);
+		selection.click(parent, egui::Modifiers::COMMAND);
+		selection.click(other, egui::Modifiers::COMMAND);
+		assert_eq!(selection.entities(), [child, parent, other]);
+		assert_eq!(selection.roots(&world), [parent, other]);
+
+		selection.click(parent, egui::Modifiers::COMMAND);
+		assert_eq!(selection.primary(), Some(other));
+		world.remove_entity(other);
+		selection.retain_existing(&world);
+		assert_eq!(selection.entities(), [child]);
+
+		selection.click(parent, egui::Modifiers::NONE);
+		assert_eq!(selection.entities(), [parent]);
+		Ok(())
+	}
+}
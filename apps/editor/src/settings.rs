@@ -0,0 +1,240 @@
+use crate::layout::Layout;
+use egui::{Key, KeyboardShortcut, Modifiers};
+use hourglass::app::{log, StateError, StateResult};
+use serde::{Deserialize, Serialize};
+use std::{
+	fs,
+	path::{Path, PathBuf},
+};
+
+const SETTINGS_FILE: &str = "editor_settings.ron";
+
+/// A file in the user's config directory for the editor
+pub fn config_path(file: &str) -> Option<PathBuf> {
+	Some(dirs::config_dir()?.join("hourglass").join(file))
+}
+
+/// The shortcuts for the editor's commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keybindings {
+	pub undo: KeyboardShortcut,
+	pub redo: KeyboardShortcut,
+	pub translate: KeyboardShortcut,
+	pub rotate: KeyboardShortcut,
+	pub scale: KeyboardShortcut,
+	pub focus: KeyboardShortcut,
+}
+
+impl Default for Keybindings {
+	fn default() -> Self {
+		let key = |key| KeyboardShortcut::new(Modifiers::NONE, key);
+		Self {
+			undo: KeyboardShortcut::new(Modifiers::COMMAND, Key::Z),
+			redo: KeyboardShortcut::new(Modifiers::COMMAND, Key::Y),
+			translate: key(Key::W),
+			rotate: key(Key::E),
+			scale: key(Key::R),
+			focus: key(Key::F),
+		}
+	}
+}
+
+impl Keybindings {
+	fn entries(&mut self) -> [(&'static str, &mut KeyboardShortcut); 6] {
+		[
+			("Undo", &mut self.undo),
+			("Redo", &mut self.redo),
+			("Move", &mut self.translate),
+			("Rotate", &mut self.rotate),
+			("Scale", &mut self.scale),
+			("Focus selection", &mut self.focus),
+		]
+	}
+}
+
+/// The user's editor preferences, kept in their config directory across projects
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+	pub theme: egui::ThemePreference,
+	pub keybindings: Keybindings,
+	/// How often the project and these settings are saved, or never when zero
+	pub autosave_minutes: u32,
+	pub layout: Layout,
+}
+
+impl Default for Settings {
+	fn default() -> Self {
+		Self {
+			theme: egui::ThemePreference::default(),
+			keybindings: Keybindings::default(),
+			autosave_minutes: 5,
+			layout: Layout::default(),
+		}
+	}
+}
+
+impl Settings {
+	/// The saved settings, or the defaults when there are none
+	pub fn load() -> Self {
+		let Some(path) = config_path(SETTINGS_FILE).filter(|path| path.exists()) else {
+			return Self::default();
+		};
+		Self::load_from(&path).unwrap_or_else(|error| {
+			log::warn!("Failed to read {}: {error}", path.display());
+			Self::default()
+		})
+	}
+
+	pub fn save(&self) -> StateResult<()> {
+		self.save_to(config_path(SETTINGS_FILE).ok_or("There is no config directory")?)
+	}
+
+	pub fn load_from(path: impl AsRef<Path>) -> StateResult<Self> {
+		Ok(ron::from_str(&fs::read_to_string(path)?)?)
+	}
+
+	pub fn save_to(&self, path: impl AsRef<Path>) -> StateResult<()> {
+		if let Some(directory) = path.as_ref().parent() {
+			fs::create_dir_all(directory)?;
+		}
+		let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+			.map_err(Box::<StateError>::from)?;
+		Ok(fs::write(path, contents)?)
+	}
+}
+
+/// A window editing the settings. A shortcut is changed by clicking it and
+/// pressing the new one, or Escape to keep the old one.
+#[derive(Default)]
+pub struct Preferences {
+	pub open: bool,
+	rebinding: Option<usize>,
+}
+
+impl Preferences {
+	/// Whether the next key press is being taken for a shortcut
+	pub fn is_rebinding(&self) -> bool {
+		self.rebinding.is_some()
+	}
+
+	/// Returns whether the window was closed, so the settings can be saved
+	pub fn show(&mut self, context: &egui::Context, settings: &mut Settings) -> bool {
+		let was_open = self.open;
+		let mut open = self.open;
+		egui::Window::new("Preferences")
+			.open(&mut open)
+			.resizable(false)
+			.collapsible(false)
+			.show(context, |ui| self.contents(ui, settings));
+		self.open = open;
+		if !open {
+			self.rebinding = None;
+		}
+		was_open && !open
+	}
+
+	fn contents(&mut self, ui: &mut egui::Ui, settings: &mut Settings) {
+		egui::Grid::new("preferences").show(ui, |ui| {
+			ui.label("Theme");
+			ui.horizontal(|ui| settings.theme.radio_buttons(ui));
+			ui.end_row();
+			ui.label("Autosave");
+			ui.add(
+				egui::DragValue::new(&mut settings.autosave_minutes)
+					.range(0..=120)
+					.custom_formatter(|minutes, _| match minutes as u32 {
+						0 => "Off".to_string(),
+						1 => "Every minute".to_string(),
+						minutes => format!("Every {minutes} minutes"),
+					}),
+			);
+			ui.end_row();
+		});
+		ui.separator();
+
+		ui.strong("Keybindings");
+		// Keys pressed while rebinding are kept from the rest of the editor
+		let pressed = if self.is_rebinding() {
+			ui.input_mut(|input| {
+				let pressed = input.events.iter().find_map(|event| match event {
+					egui::Event::Key {
+						key,
+						pressed: true,
+						modifiers,
+						..
+					} => Some(KeyboardShortcut::new(*modifiers, *key)),
+					_ => None,
+				});
+				input
+					.events
+					.retain(|event| !matches!(event, egui::Event::Key { .. }));
+				pressed
+			})
+		} else {
+			None
+		};
+		egui::Grid::new("keybindings").show(ui, |ui| {
+			for (index, (action, shortcut)) in
+				settings.keybindings.entries().into_iter().enumerate()
+			{
+				ui.label(action);
+				let text = if self.rebinding == Some(index) {
+					"Press a shortcut".to_string()
+				} else {
+					ui.ctx().format_shortcut(shortcut)
+				};
+				if ui
+					.selectable_label(self.rebinding == Some(index), text)
+					.clicked()
+				{
+					self.rebinding = Some(index);
+				} else if let (Some(pressed), true) = (pressed, self.rebinding == Some(index)) {
+					if pressed.logical_key != Key::Escape {
+						*shortcut = pressed;
+					}
+					self.rebinding = None;
+				}
+				ui.end_row();
+			}
+		});
+		if ui.button("Reset keybindings").clicked() {
+			settings.keybindings = Keybindings::default();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn settings_round_trip() -> StateResult<()> {
+		let path = std::env::temp_dir()
+			.join("hourglass_editor_settings")
+			.join(SETTINGS_FILE);
+		let settings = Settings {
+			theme: egui::ThemePreference::Light,
+			keybindings: Keybindings {
+				focus: KeyboardShortcut::new(Modifiers::SHIFT, Key::G),
+				..Keybindings::default()
+			},
+			autosave_minutes: 0,
+			layout: Layout::default(),
+		};
+		settings.save_to(&path)?;
+		let loaded = Settings::load_from(&path)?;
+		assert_eq!(loaded.theme, settings.theme);
+		assert_eq!(loaded.keybindings, settings.keybindings);
+		assert_eq!(loaded.autosave_minutes, 0);
+
+		// Settings saved by older editors fill in what they lack
+		fs::write(&path, "(theme: Dark)")?;
+		let partial = Settings::load_from(&path)?;
+		assert_eq!(partial.theme, egui::ThemePreference::Dark);
+		assert_eq!(partial.keybindings, Keybindings::default());
+		fs::remove_file(path)?;
+		Ok(())
+	}
+}
@@ -0,0 +1,224 @@
+use crate::{
+	assets::AssetPath,
+	camera::{rotate, CameraController, EditorCamera},
+	gizmo::{Gizmo, Target},
+	hierarchy::entity_label,
+	history::{Edit, History},
+	scene::{world_transform, Sprite},
+	selection::Selection,
+	settings::Keybindings,
+};
+use hourglass::{
+	app::log,
+	ecs::{hierarchy, reflect::Reflect, world::World},
+	math::{Quat, Transform, Vec2, Vec3},
+	renderer::{Camera, CameraView},
+};
+use std::{
+	collections::HashMap,
+	path::{Path, PathBuf},
+};
+
+const PIXELS_PER_UNIT: f32 = 32.0;
+const RADIUS: f32 = 6.0;
+
+/// Converts between world units, with y pointing up, and points on screen
+pub struct View {
+	/// The screen point the camera's center is drawn at
+	pub origin: egui::Pos2,
+	pub camera: EditorCamera,
+}
+
+impl View {
+	pub fn to_screen(&self, transform: &Transform) -> egui::Pos2 {
+		let point = self
+			.camera_view()
+			.world_to_viewport(transform.translation.truncate().extend(0.0))
+			.unwrap_or_default();
+		self.origin + egui::vec2(point.x - 0.5, point.y - 0.5)
+	}
+
+	/// The point on the xy plane under a point on screen
+	pub fn to_world(&self, point: egui::Pos2) -> egui::Vec2 {
+		let offset = point - self.origin;
+		let hit = self
+			.camera_view()
+			.viewport_to_world(Vec2::new(offset.x + 0.5, offset.y + 0.5))
+			.and_then(|ray| Some(ray.at(ray.intersect_plane(Vec3::ZERO, Vec3::Z)?)))
+			.unwrap_or_default();
+		egui::vec2(hit.x, hit.y)
+	}
+
+	// An orthographic view doesn't depend on the target's size, so a single pixel
+	// centered on the origin stands in for the viewport
+	fn camera_view(&self) -> CameraView {
+		let transform = Transform::from_xyz(self.camera.center.x, self.camera.center.y, 0.0)
+			.with_rotation(Quat::from_rotation_z(self.camera.rotation.to_radians()));
+		CameraView::new(&Camera::orthographic(self.scale()), &transform.into(), 1, 1)
+	}
+
+	/// A direction in the world as it points on screen, where y points down
+	pub fn to_screen_direction(&self, direction: egui::Vec2) -> egui::Vec2 {
+		let turned = rotate(direction, -self.camera.rotation);
+		egui::vec2(turned.x, -turned.y)
+	}
+
+	/// Pixels per world unit
+	pub fn scale(&self) -> f32 {
+		PIXELS_PER_UNIT * self.camera.zoom
+	}
+}
+
+/// A top-down view of the entities that have a `Transform`, seen through the editor camera.
+/// Entities with a `Sprite` are drawn with its image, one unit across. Clicking with Ctrl
+/// held selects several entities, and the focus key centers the primary selection.
+#[derive(Default)]
+pub struct Viewport {
+	pub selection: Selection,
+	/// The imported images by path, kept up to date by the asset browser
+	pub textures: HashMap<PathBuf, egui::TextureHandle>,
+	/// An asset dropped from the asset browser and where it landed
+	pub dropped: Option<(PathBuf, Transform)>,
+	pub camera: CameraController,
+	pub keybindings: Keybindings,
+	gizmo: Gizmo,
+}
+
+impl Viewport {
+	pub fn show(&mut self, ui: &mut egui::Ui, world: &mut World, history: &mut History) {
+		ui.horizontal(|ui| {
+			self.camera.toolbar(ui, world);
+			ui.separator();
+			self.gizmo.toolbar(ui, &self.keybindings);
+		});
+		let (response, painter) =
+			ui.allocate_painter(ui.available_size(), egui::Sense::click_and_drag());
+		let rect = response.rect;
+		self.camera.interact(ui, &response);
+		if let Some(target) = self.target(world).filter(|_| {
+			let focus = &self.keybindings.focus;
+			!ui.ctx().egui_wants_keyboard_input()
+				&& ui.input_mut(|input| input.consume_shortcut(focus))
+		}) {
+			let focused = target.parent.mul_transform(&target.local).translation;
+			self.camera.focus(egui::vec2(focused.x, focused.y));
+		}
+		painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+		let view = self.camera.camera.view(rect.center());
+		// The axes reach past any corner of the viewport
+		let reach = rect.size().length() / view.scale() + view.camera.center.length();
+		let axis = egui::Stroke::new(1.0, ui.visuals().weak_text_color());
+		for (x, y) in [(reach, 0.0), (0.0, reach)] {
+			painter.line_segment(
+				[
+					view.to_screen(&Transform::from_xyz(-x, -y, 0.0)),
+					view.to_screen(&Transform::from_xyz(x, y, 0.0)),
+				],
+				axis,
+			);
+		}
+
+		// A drag on the gizmo is undone as one edit
+		if let (Some(entity), Some(target)) = (self.selection.primary(), self.target(world)) {
+			let was_dragging = self.gizmo.is_dragging();
+			if let Some(transform) = self.gizmo.interact(&response, &view, &target) {
+				let edit = Edit::SetComponent {
+					entity,
+					component: "Transform",
+					old: target.local.fields(),
+					new: transform.fields(),
+				};
+				if let Err(error) = history.merge(world, edit) {
+					log::warn!("Failed to transform the entity: {error}");
+				}
+			}
+			if was_dragging && !self.gizmo.is_dragging() {
+				history.seal();
+			}
+		}
+
+		let mut clicked = None;
+		for entity in world.entities() {
+			if world.get_component::<Transform>(entity).is_none() {
+				continue;
+			}
+			let transform = world_transform(world, entity);
+			let point = view.to_screen(&transform);
+			let color = if self.selection.contains(entity) {
+				ui.visuals().selection.stroke.color
+			} else {
+				ui.visuals().text_color()
+			};
+			let texture = world
+				.get_component::<Sprite>(entity)
+				.and_then(|sprite| self.textures.get(Path::new(&sprite.path)));
+			match texture {
+				Some(texture) => {
+					let size = texture.size_vec2() / texture.size_vec2().max_elem()
+						* egui::vec2(transform.scale.x, transform.scale.y)
+						* view.scale();
+					let rotation = transform.angle_z().to_degrees() - view.camera.rotation;
+					egui::Image::new(texture)
+						.rotate(-rotation.to_radians(), egui::Vec2::splat(0.5))
+						.paint_at(ui, egui::Rect::from_center_size(point, size.abs()));
+				}
+				None => {
+					painter.circle_filled(point, RADIUS, color);
+				}
+			}
+			painter.text(
+				point + egui::vec2(0.0, RADIUS + 2.0),
+				egui::Align2::CENTER_TOP,
+				entity_label(world, entity),
+				egui::FontId::proportional(12.0),
+				color,
+			);
+			if response
+				.interact_pointer_pos()
+				.is_some_and(|pointer| pointer.distance(point) <= RADIUS * 2.0)
+			{
+				clicked = Some(entity);
+			}
+		}
+		if response.clicked() {
+			match clicked {
+				Some(entity) => self
+					.selection
+					.click(entity, ui.input(|input| input.modifiers)),
+				None => self.selection.clear(),
+			}
+		}
+		if let Some(target) = self.target(world) {
+			self.gizmo.paint(&painter, &view, &target);
+		}
+
+		if response.dnd_hover_payload::<AssetPath>().is_some() {
+			painter.rect_stroke(
+				rect,
+				0.0,
+				ui.visuals().selection.stroke,
+				egui::StrokeKind::Inside,
+			);
+		}
+		if let (Some(asset), Some(pointer)) = (
+			response.dnd_release_payload::<AssetPath>(),
+			response.hover_pos(),
+		) {
+			let position = view.to_world(pointer);
+			self.dropped = Some((
+				asset.0.clone(),
+				Transform::from_xyz(position.x, position.y, 0.0),
+			));
+		}
+	}
+
+	fn target(&self, world: &World) -> Option<Target> {
+		let entity = self.selection.primary()?;
+		Some(Target {
+			parent: hierarchy::parent(world, entity)
+				.map(|parent| world_transform(world, parent))
+				.unwrap_or_default(),
+			local: *world.get_component::<Transform>(entity)?,
+		})
+	}
+}
@@ -0,0 +1,148 @@
+use math::{Quat, Transform, Vec3};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+	#[default]
+	Linear,
+	/// Holds each keyframe until the next one
+	Step,
+}
+
+/// The values a channel moves through, one per keyframe time
+#[derive(Debug, Clone, PartialEq)]
+pub enum Keyframes {
+	Translation(Vec<Vec3>),
+	Rotation(Vec<Quat>),
+	Scale(Vec<Vec3>),
+}
+
+impl Keyframes {
+	fn len(&self) -> usize {
+		match self {
+			Self::Translation(values) | Self::Scale(values) => values.len(),
+			Self::Rotation(values) => values.len(),
+		}
+	}
+}
+
+/// Animates one part of the transform of the entity named by the target
+#[derive(Debug, Clone, PartialEq)]
+pub struct Channel {
+	pub target: String,
+	/// When each keyframe is reached, in seconds and in order
+	pub times: Vec<f32>,
+	pub keyframes: Keyframes,
+	pub interpolation: Interpolation,
+}
+
+impl Channel {
+	/// The transform with the channel's part of it replaced by its value at the time
+	pub fn sample(&self, time: f32, mut transform: Transform) -> Transform {
+		let count = self.times.len().min(self.keyframes.len());
+		if count == 0 {
+			return transform;
+		}
+		let next = self.times[..count].partition_point(|keyframe| *keyframe <= time);
+		let (from, to, amount) = match next {
+			0 => (0, 0, 0.0),
+			next if next == count => (count - 1, count - 1, 0.0),
+			next => {
+				let (start, end) = (self.times[next - 1], self.times[next]);
+				let amount = match self.interpolation {
+					Interpolation::Linear if end > start => (time - start) / (end - start),
+					_ => 0.0,
+				};
+				(next - 1, next, amount)
+			}
+		};
+		match &self.keyframes {
+			Keyframes::Translation(values) => {
+				transform.translation = values[from].lerp(values[to], amount)
+			}
+			Keyframes::Rotation(values) => {
+				transform.rotation = values[from].slerp(values[to], amount)
+			}
+			Keyframes::Scale(values) => transform.scale = values[from].lerp(values[to], amount),
+		}
+		transform
+	}
+}
+
+/// Keyframed transforms for a set of named entities, such as the joints of a skeleton
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AnimationClip {
+	pub name: String,
+	pub channels: Vec<Channel>,
+}
+
+impl AnimationClip {
+	/// When the last keyframe is reached, in seconds
+	pub fn duration(&self) -> f32 {
+		self.channels
+			.iter()
+			.filter_map(|channel| channel.times.last().copied())
+			.fold(0.0, f32::max)
+	}
+
+	/// The names of the entities the clip animates
+	pub fn targets(&self) -> impl Iterator<Item = &str> {
+		let mut targets = self
+			.channels
+			.iter()
+			.map(|channel| channel.target.as_str())
+			.collect::<Vec<_>>();
+		targets.sort_unstable();
+		targets.dedup();
+		targets.into_iter()
+	}
+
+	/// A target's transform at the time, keeping the parts of it the clip doesn't animate
+	pub fn sample(&self, target: &str, time: f32, transform: Transform) -> Transform {
+		self.channels
+			.iter()
+			.filter(|channel| channel.target == target)
+			.fold(transform, |transform, channel| {
+				channel.sample(time, transform)
+			})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn channels_interpolate_between_keyframes() {
+		let clip = AnimationClip {
+			name: "wave".to_string(),
+			channels: vec![
+				Channel {
+					target: "arm".to_string(),
+					times: vec![0.0, 1.0, 2.0],
+					keyframes: Keyframes::Translation(vec![Vec3::ZERO, Vec3::X, Vec3::Y]),
+					interpolation: Interpolation::Linear,
+				},
+				Channel {
+					target: "arm".to_string(),
+					times: vec![0.0, 2.0],
+					keyframes: Keyframes::Scale(vec![Vec3::ONE, Vec3::splat(3.0)]),
+					interpolation: Interpolation::Step,
+				},
+			],
+		};
+		assert_eq!(clip.duration(), 2.0);
+		assert_eq!(clip.targets().collect::<Vec<_>>(), ["arm"]);
+
+		let base = Transform::from_rotation(Quat::from_rotation_z(1.0));
+		let halfway = clip.sample("arm", 0.5, base);
+		assert_eq!(halfway.translation, Vec3::new(0.5, 0.0, 0.0));
+		assert_eq!(halfway.scale, Vec3::ONE);
+		// Parts the clip doesn't animate are left alone
+		assert_eq!(halfway.rotation, base.rotation);
+		// Times past either end hold the first or last keyframe
+		assert_eq!(clip.sample("arm", -1.0, base).translation, Vec3::ZERO);
+		let end = clip.sample("arm", 5.0, base);
+		assert_eq!((end.translation, end.scale), (Vec3::Y, Vec3::splat(3.0)));
+		assert_eq!(clip.sample("leg", 1.0, base), base);
+	}
+}
@@ -0,0 +1,534 @@
+use crate::{AnimationClip, Channel, Interpolation, Keyframes, SkinnedMesh};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ecs::{
+	hierarchy::{self, Name},
+	world::{Entity, World},
+};
+use math::{Mat4, Quat, Transform, Vec3};
+use serde::Deserialize;
+use std::sync::Arc;
+use thiserror::Error;
+
+const GLB_MAGIC: &[u8] = b"glTF";
+const GLB_BIN_CHUNK: u32 = 0x004E_4942;
+
+#[derive(Error, Debug)]
+pub enum GltfError {
+	#[error("Invalid glTF json: {0}")]
+	Json(#[from] serde_json::Error),
+
+	#[error("Invalid glb container")]
+	Glb,
+
+	#[error("Buffer {0} isn't embedded in the file")]
+	ExternalBuffer(usize),
+
+	#[error("Invalid embedded buffer: {0}")]
+	Base64(#[from] base64::DecodeError),
+
+	#[error("Accessor {0} is missing, out of bounds or of an unsupported type")]
+	Accessor(usize),
+}
+
+/// A node of the file's scene graph, named `node<index>` when the file leaves it unnamed
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfNode {
+	pub name: String,
+	pub transform: Transform,
+	pub children: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GltfSkin {
+	pub name: String,
+	/// The nodes the skin's joints are, in joint order
+	pub joints: Vec<usize>,
+	pub inverse_bind_matrices: Vec<Mat4>,
+}
+
+/// The nodes, skins and animations of a glTF file, with its buffers embedded
+/// in it either as a `.glb` or as base64 data uris. Animation channels target
+/// nodes by name. Cubic spline keyframes are played as linear ones and morph
+/// target weights aren't read.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Gltf {
+	pub nodes: Vec<GltfNode>,
+	pub skins: Vec<GltfSkin>,
+	pub animations: Vec<Arc<AnimationClip>>,
+}
+
+impl Gltf {
+	pub fn from_slice(bytes: &[u8]) -> Result<Self, GltfError> {
+		let (json, binary) = if bytes.starts_with(GLB_MAGIC) {
+			glb_chunks(bytes).ok_or(GltfError::Glb)?
+		} else {
+			(bytes, None)
+		};
+		let document = serde_json::from_slice::<Document>(json)?;
+		let buffers = document
+			.buffers
+			.iter()
+			.enumerate()
+			.map(|(index, buffer)| match (&buffer.uri, binary) {
+				(Some(uri), _) => {
+					let data = uri
+						.strip_prefix("data:")
+						.and_then(|uri| uri.split_once(";base64,"))
+						.ok_or(GltfError::ExternalBuffer(index))?
+						.1;
+					Ok(STANDARD.decode(data)?)
+				}
+				(None, Some(binary)) => Ok(binary.to_vec()),
+				(None, None) => Err(GltfError::ExternalBuffer(index)),
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+		let reader = Reader {
+			document: &document,
+			buffers: &buffers,
+		};
+
+		let nodes = document
+			.nodes
+			.iter()
+			.enumerate()
+			.map(|(index, node)| GltfNode {
+				name: node_name(&document, index),
+				transform: node.transform(),
+				children: node.children.clone(),
+			})
+			.collect();
+		let skins = document
+			.skins
+			.iter()
+			.enumerate()
+			.map(|(index, skin)| {
+				let inverse_bind_matrices = match skin.inverse_bind_matrices {
+					Some(accessor) => reader
+						.floats(accessor)?
+						.chunks_exact(16)
+						.map(|columns| {
+							let mut matrix = [[0.0; 4]; 4];
+							for (column, values) in matrix.iter_mut().zip(columns.chunks_exact(4)) {
+								column.copy_from_slice(values);
+							}
+							Mat4::from_cols_array_2d(&matrix)
+						})
+						.collect(),
+					None => vec![Mat4::IDENTITY; skin.joints.len()],
+				};
+				Ok(GltfSkin {
+					name: skin.name.clone().unwrap_or_else(|| format!("skin{index}")),
+					joints: skin.joints.clone(),
+					inverse_bind_matrices,
+				})
+			})
+			.collect::<Result<_, GltfError>>()?;
+		let animations = document
+			.animations
+			.iter()
+			.enumerate()
+			.map(|(index, animation)| reader.animation(index, animation).map(Arc::new))
+			.collect::<Result<_, _>>()?;
+		Ok(Self {
+			nodes,
+			skins,
+			animations,
+		})
+	}
+
+	pub fn animation(&self, name: &str) -> Option<&Arc<AnimationClip>> {
+		self.animations.iter().find(|clip| clip.name == name)
+	}
+
+	/// Creates an entity for each node, named, placed and parented as in the file,
+	/// returned in node order
+	pub fn spawn_nodes(&self, world: &mut World) -> ecs::error::Result<Vec<Entity>> {
+		let entities = world.create_entities(self.nodes.len());
+		for (node, entity) in self.nodes.iter().zip(&entities) {
+			world.add_component(*entity, Name(node.name.clone()))?;
+			world.add_component(*entity, node.transform)?;
+		}
+		for (node, entity) in self.nodes.iter().zip(&entities) {
+			for child in &node.children {
+				if let Some(child) = entities.get(*child) {
+					hierarchy::set_parent(world, *child, Some(*entity))?;
+				}
+			}
+		}
+		Ok(entities)
+	}
+
+	/// The skin bound to the entities spawned for the file's nodes
+	pub fn skinned_mesh(&self, skin: usize, nodes: &[Entity]) -> Option<SkinnedMesh> {
+		let skin = self.skins.get(skin)?;
+		Some(SkinnedMesh {
+			joints: skin
+				.joints
+				.iter()
+				.map(|joint| nodes.get(*joint).copied())
+				.collect::<Option<_>>()?,
+			inverse_bind_matrices: skin.inverse_bind_matrices.clone(),
+		})
+	}
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl assets::Asset for Gltf {
+	fn load(
+		bytes: Vec<u8>,
+		_context: &mut assets::LoadContext,
+	) -> Result<Self, assets::AssetError> {
+		Self::from_slice(&bytes).map_err(assets::AssetError::invalid)
+	}
+}
+
+// The json chunk and the binary chunk, if there is one
+fn glb_chunks(bytes: &[u8]) -> Option<(&[u8], Option<&[u8]>)> {
+	let word = |offset: usize| -> Option<u32> {
+		Some(u32::from_le_bytes(
+			bytes.get(offset..offset + 4)?.try_into().ok()?,
+		))
+	};
+	let json_length = word(12)? as usize;
+	let json = bytes.get(20..20 + json_length)?;
+	let binary_start = 20 + json_length;
+	let binary = match (word(binary_start), word(binary_start + 4)) {
+		(Some(length), Some(GLB_BIN_CHUNK)) => {
+			Some(bytes.get(binary_start + 8..binary_start + 8 + length as usize)?)
+		}
+		_ => None,
+	};
+	Some((json, binary))
+}
+
+fn node_name(document: &Document, index: usize) -> String {
+	document
+		.nodes
+		.get(index)
+		.and_then(|node| node.name.clone())
+		.unwrap_or_else(|| format!("node{index}"))
+}
+
+struct Reader<'a> {
+	document: &'a Document,
+	buffers: &'a [Vec<u8>],
+}
+
+impl Reader<'_> {
+	// Every component of every element, with normalized integers read as floats
+	fn floats(&self, index: usize) -> Result<Vec<f32>, GltfError> {
+		self.try_floats(index).ok_or(GltfError::Accessor(index))
+	}
+
+	fn try_floats(&self, index: usize) -> Option<Vec<f32>> {
+		let accessor = self.document.accessors.get(index)?;
+		let view = self.document.buffer_views.get(accessor.buffer_view?)?;
+		let buffer = self.buffers.get(view.buffer)?;
+		let data = buffer.get(view.byte_offset..view.byte_offset + view.byte_length)?;
+		let components = match accessor.kind.as_str() {
+			"SCALAR" => 1,
+			"VEC2" => 2,
+			"VEC3" => 3,
+			"VEC4" => 4,
+			"MAT4" => 16,
+			_ => return None,
+		};
+		let (size, read): (usize, fn(&[u8]) -> f32) = match accessor.component_type {
+			5126 => (4, |bytes| {
+				f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+			}),
+			5120 => (1, |bytes| (bytes[0] as i8 as f32 / 127.0).max(-1.0)),
+			5121 => (1, |bytes| bytes[0] as f32 / 255.0),
+			5122 => (2, |bytes| {
+				(i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 32767.0).max(-1.0)
+			}),
+			5123 => (2, |bytes| {
+				u16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 65535.0
+			}),
+			_ => return None,
+		};
+		let stride = view.byte_stride.unwrap_or(components * size);
+		(0..accessor.count)
+			.flat_map(|element| {
+				(0..components).map(move |component| {
+					accessor.byte_offset + element * stride + component * size
+				})
+			})
+			.map(|offset| data.get(offset..offset + size).map(read))
+			.collect()
+	}
+
+	fn animation(
+		&self,
+		index: usize,
+		animation: &RawAnimation,
+	) -> Result<AnimationClip, GltfError> {
+		let mut channels = Vec::new();
+		for channel in &animation.channels {
+			let (Some(node), Some(sampler)) =
+				(channel.target.node, animation.samplers.get(channel.sampler))
+			else {
+				continue;
+			};
+			let times = self.floats(sampler.input)?;
+			let values = self.floats(sampler.output)?;
+			let interpolation = match sampler.interpolation.as_deref() {
+				Some("STEP") => Interpolation::Step,
+				_ => Interpolation::Linear,
+			};
+			let cubic = sampler.interpolation.as_deref() == Some("CUBICSPLINE");
+			let keyframes = match channel.target.path.as_str() {
+				"translation" => Keyframes::Translation(
+					keyframe_values(&values, 3, cubic)
+						.map(|value| Vec3::new(value[0], value[1], value[2]))
+						.collect(),
+				),
+				"rotation" => Keyframes::Rotation(
+					keyframe_values(&values, 4, cubic)
+						.map(|value| {
+							Quat::from_xyzw(value[0], value[1], value[2], value[3]).normalize()
+						})
+						.collect(),
+				),
+				"scale" => Keyframes::Scale(
+					keyframe_values(&values, 3, cubic)
+						.map(|value| Vec3::new(value[0], value[1], value[2]))
+						.collect(),
+				),
+				_ => continue,
+			};
+			channels.push(Channel {
+				target: node_name(self.document, node),
+				times,
+				keyframes,
+				interpolation,
+			});
+		}
+		Ok(AnimationClip {
+			name: animation
+				.name
+				.clone()
+				.unwrap_or_else(|| format!("animation{index}")),
+			channels,
+		})
+	}
+}
+
+// Cubic spline keyframes come as in tangent, value and out tangent, of which only the value is kept
+fn keyframe_values(values: &[f32], width: usize, cubic: bool) -> impl Iterator<Item = &[f32]> {
+	let (step, skip) = if cubic { (3, 1) } else { (1, 0) };
+	values.chunks_exact(width).skip(skip).step_by(step)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Document {
+	#[serde(default)]
+	buffers: Vec<RawBuffer>,
+	#[serde(default)]
+	buffer_views: Vec<RawView>,
+	#[serde(default)]
+	accessors: Vec<RawAccessor>,
+	#[serde(default)]
+	nodes: Vec<RawNode>,
+	#[serde(default)]
+	skins: Vec<RawSkin>,
+	#[serde(default)]
+	animations: Vec<RawAnimation>,
+}
+
+#[derive(Deserialize)]
+struct RawBuffer {
+	uri: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawView {
+	buffer: usize,
+	#[serde(default)]
+	byte_offset: usize,
+	byte_length: usize,
+	byte_stride: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawAccessor {
+	buffer_view: Option<usize>,
+	#[serde(default)]
+	byte_offset: usize,
+	component_type: u32,
+	count: usize,
+	#[serde(rename = "type")]
+	kind: String,
+}
+
+#[derive(Deserialize)]
+struct RawNode {
+	name: Option<String>,
+	#[serde(default)]
+	children: Vec<usize>,
+	matrix: Option<[f32; 16]>,
+	translation: Option<[f32; 3]>,
+	rotation: Option<[f32; 4]>,
+	scale: Option<[f32; 3]>,
+}
+
+impl RawNode {
+	fn transform(&self) -> Transform {
+		if let Some(matrix) = self.matrix {
+			let (scale, rotation, translation) =
+				Mat4::from_cols_array(&matrix).to_scale_rotation_translation();
+			return Transform {
+				translation,
+				rotation,
+				scale,
+			};
+		}
+		Transform {
+			translation: self.translation.map_or(Vec3::ZERO, Vec3::from),
+			rotation: self
+				.rotation
+				.map_or(Quat::IDENTITY, |[x, y, z, w]| Quat::from_xyzw(x, y, z, w)),
+			scale: self.scale.map_or(Vec3::ONE, Vec3::from),
+		}
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSkin {
+	name: Option<String>,
+	inverse_bind_matrices: Option<usize>,
+	joints: Vec<usize>,
+}
+
+#[derive(Deserialize)]
+struct RawAnimation {
+	name: Option<String>,
+	channels: Vec<RawChannel>,
+	samplers: Vec<RawSampler>,
+}
+
+#[derive(Deserialize)]
+struct RawChannel {
+	sampler: usize,
+	target: RawTarget,
+}
+
+#[derive(Deserialize)]
+struct RawTarget {
+	node: Option<usize>,
+	path: String,
+}
+
+#[derive(Deserialize)]
+struct RawSampler {
+	input: usize,
+	output: usize,
+	interpolation: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// One clip sliding the spine, and a skin over the hips and spine
+	fn document(uri: Option<String>, length: usize) -> String {
+		let buffer = match uri {
+			Some(uri) => format!(r#"{{"uri": "{uri}", "byteLength": {length}}}"#),
+			None => format!(r#"{{"byteLength": {length}}}"#),
+		};
+		format!(
+			r#"{{
+				"asset": {{"version": "2.0"}},
+				"buffers": [{buffer}],
+				"bufferViews": [
+					{{"buffer": 0, "byteLength": 8}},
+					{{"buffer": 0, "byteOffset": 8, "byteLength": 24}},
+					{{"buffer": 0, "byteOffset": 32, "byteLength": 128}}
+				],
+				"accessors": [
+					{{"bufferView": 0, "componentType": 5126, "count": 2, "type": "SCALAR"}},
+					{{"bufferView": 1, "componentType": 5126, "count": 2, "type": "VEC3"}},
+					{{"bufferView": 2, "componentType": 5126, "count": 2, "type": "MAT4"}}
+				],
+				"nodes": [
+					{{"name": "hips", "children": [1], "translation": [0, 1, 0]}},
+					{{"matrix": [2, 0, 0, 0, 0, 2, 0, 0, 0, 0, 2, 0, 0, 3, 0, 1]}}
+				],
+				"skins": [{{"joints": [0, 1], "inverseBindMatrices": 2}}],
+				"animations": [{{
+					"name": "slide",
+					"channels": [{{"sampler": 0, "target": {{"node": 1, "path": "translation"}}}}],
+					"samplers": [{{"input": 0, "output": 1}}]
+				}}]
+			}}"#
+		)
+	}
+
+	fn buffer() -> Vec<u8> {
+		let identity = Mat4::IDENTITY.to_cols_array_2d().concat();
+		[0.0, 1.0, 0.0, 3.0, 0.0, 1.0, 2.0, 3.0]
+			.into_iter()
+			.chain(identity.iter().chain(&identity).copied())
+			.flat_map(f32::to_le_bytes)
+			.collect()
+	}
+
+	fn glb(json: &str, binary: &[u8]) -> Vec<u8> {
+		let chunk = |kind: &[u8], data: &[u8]| {
+			let mut chunk = (data.len() as u32).to_le_bytes().to_vec();
+			chunk.extend_from_slice(kind);
+			chunk.extend_from_slice(data);
+			chunk
+		};
+		let json = chunk(b"JSON", json.as_bytes());
+		let binary = chunk(b"BIN\0", binary);
+		let mut glb = GLB_MAGIC.to_vec();
+		glb.extend(2_u32.to_le_bytes());
+		glb.extend(((12 + json.len() + binary.len()) as u32).to_le_bytes());
+		glb.extend(json);
+		glb.extend(binary);
+		glb
+	}
+
+	#[test]
+	fn embedded_files_load_and_spawn() -> ecs::error::Result<()> {
+		let buffer = buffer();
+		let uri = format!(
+			"data:application/octet-stream;base64,{}",
+			STANDARD.encode(&buffer)
+		);
+		let gltf = Gltf::from_slice(document(Some(uri), buffer.len()).as_bytes())?;
+		assert_eq!(
+			gltf,
+			Gltf::from_slice(&glb(&document(None, buffer.len()), &buffer))?
+		);
+		assert!(matches!(
+			Gltf::from_slice(document(Some("skin.bin".to_string()), 0).as_bytes()),
+			Err(GltfError::ExternalBuffer(0))
+		));
+
+		assert_eq!(gltf.nodes[1].name, "node1");
+		assert_eq!(gltf.nodes[1].transform.scale, Vec3::splat(2.0));
+		assert_eq!(
+			gltf.nodes[1].transform.translation,
+			Vec3::new(0.0, 3.0, 0.0)
+		);
+		let clip = gltf.animation("slide").unwrap();
+		assert_eq!(clip.duration(), 1.0);
+		let halfway = clip.sample("node1", 0.5, Transform::default());
+		assert_eq!(halfway.translation, Vec3::new(0.5, 2.5, 1.5));
+
+		let mut world = World::new();
+		let nodes = gltf.spawn_nodes(&mut world)?;
+		assert_eq!(hierarchy::parent(&world, nodes[1]), Some(nodes[0]));
+		assert_eq!(hierarchy::name(&world, nodes[0]).as_deref(), Some("hips"));
+		let skin = gltf.skinned_mesh(0, &nodes).unwrap();
+		assert_eq!(skin.joints, nodes);
+		assert_eq!(skin.inverse_bind_matrices, vec![Mat4::IDENTITY; 2]);
+		Ok(())
+	}
+}
@@ -0,0 +1,13 @@
+#![forbid(unsafe_code)]
+
+mod clip;
+mod gltf;
+mod player;
+mod skin;
+
+pub use self::{
+	clip::{AnimationClip, Channel, Interpolation, Keyframes},
+	gltf::{Gltf, GltfError, GltfNode, GltfSkin},
+	player::{animate, AnimationPlayer},
+	skin::{update_joint_matrices, JointMatrices, SkinnedMesh},
+};
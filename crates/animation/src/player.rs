@@ -0,0 +1,296 @@
+use crate::{update_joint_matrices, AnimationClip};
+use ecs::{
+	error::Result,
+	hierarchy::{self, Name},
+	transform::propagate_transforms,
+	world::{Entity, World},
+};
+use math::Transform;
+use std::{
+	collections::{HashMap, HashSet, VecDeque},
+	sync::Arc,
+};
+
+#[derive(Debug, Clone)]
+struct Playback {
+	clip: Arc<AnimationClip>,
+	time: f32,
+}
+
+impl Playback {
+	fn advance(&mut self, delta: f32, repeat: bool) {
+		let duration = self.clip.duration();
+		self.time += delta;
+		self.time = if repeat && duration > 0.0 {
+			self.time.rem_euclid(duration)
+		} else {
+			self.time.clamp(0.0, duration)
+		};
+	}
+}
+
+/// Plays clips on the entity and its descendants, matching each channel's target
+/// to the `Name` of an entity in the hierarchy. Switching clips can crossfade
+/// between them so the change isn't a snap.
+#[derive(Debug, Clone)]
+pub struct AnimationPlayer {
+	current: Option<Playback>,
+	// The clip fading out, how long it has been fading and how long it fades for
+	fading: Option<(Playback, f32, f32)>,
+	pub speed: f32,
+	/// Starts the clip over when it ends, rather than holding its last pose
+	pub repeat: bool,
+	pub paused: bool,
+}
+
+impl Default for AnimationPlayer {
+	fn default() -> Self {
+		Self {
+			current: None,
+			fading: None,
+			speed: 1.0,
+			repeat: true,
+			paused: false,
+		}
+	}
+}
+
+impl AnimationPlayer {
+	pub fn new(clip: Arc<AnimationClip>) -> Self {
+		let mut player = Self::default();
+		player.play(clip);
+		player
+	}
+
+	/// Switches to the clip from its start straight away
+	pub fn play(&mut self, clip: Arc<AnimationClip>) {
+		self.fading = None;
+		self.current = Some(Playback { clip, time: 0.0 });
+	}
+
+	/// Switches to the clip from its start, blending in from the playing clip over some seconds
+	pub fn crossfade(&mut self, clip: Arc<AnimationClip>, seconds: f32) {
+		if seconds <= 0.0 {
+			return self.play(clip);
+		}
+		self.fading = self.current.take().map(|playback| (playback, 0.0, seconds));
+		self.current = Some(Playback { clip, time: 0.0 });
+	}
+
+	/// Leaves the entities in the pose they were last given
+	pub fn stop(&mut self) {
+		self.current = None;
+		self.fading = None;
+	}
+
+	pub fn clip(&self) -> Option<&Arc<AnimationClip>> {
+		self.current.as_ref().map(|playback| &playback.clip)
+	}
+
+	/// How far into the clip playback is, in seconds
+	pub fn elapsed(&self) -> f32 {
+		self.current.as_ref().map_or(0.0, |playback| playback.time)
+	}
+
+	pub fn seek(&mut self, seconds: f32) {
+		if let Some(playback) = self.current.as_mut() {
+			playback.time = 0.0;
+			playback.advance(seconds, self.repeat);
+		}
+	}
+
+	pub fn is_fading(&self) -> bool {
+		self.fading.is_some()
+	}
+
+	/// Whether a clip that doesn't repeat has played to its end
+	pub fn is_finished(&self) -> bool {
+		!self.repeat
+			&& self
+				.current
+				.as_ref()
+				.is_some_and(|playback| playback.time >= playback.clip.duration())
+	}
+
+	fn advance(&mut self, delta: f32) {
+		if self.paused {
+			return;
+		}
+		let repeat = self.repeat;
+		if let Some(playback) = self.current.as_mut() {
+			playback.advance(delta * self.speed, repeat);
+		}
+		if let Some((playback, elapsed, duration)) = self.fading.as_mut() {
+			playback.advance(delta * self.speed, repeat);
+			*elapsed += delta;
+			if *elapsed >= *duration {
+				self.fading = None;
+			}
+		}
+	}
+
+	fn pose(&self, target: &str, transform: Transform) -> Transform {
+		let Some(current) = self.current.as_ref() else {
+			return transform;
+		};
+		let pose = current.clip.sample(target, current.time, transform);
+		match &self.fading {
+			Some((fading, elapsed, duration)) => {
+				let from = fading.clip.sample(target, fading.time, transform);
+				blend(&from, &pose, elapsed / duration)
+			}
+			None => pose,
+		}
+	}
+
+	fn targets(&self) -> Vec<String> {
+		let mut targets = self
+			.current
+			.iter()
+			.chain(self.fading.iter().map(|(playback, ..)| playback))
+			.flat_map(|playback| playback.clip.targets().map(str::to_string))
+			.collect::<Vec<_>>();
+		targets.sort_unstable();
+		targets.dedup();
+		targets
+	}
+}
+
+fn blend(from: &Transform, to: &Transform, amount: f32) -> Transform {
+	Transform {
+		translation: from.translation.lerp(to.translation, amount),
+		rotation: from.rotation.slerp(to.rotation, amount),
+		scale: from.scale.lerp(to.scale, amount),
+	}
+}
+
+/// Advances every `AnimationPlayer` by the seconds passed and poses what it animates,
+/// then propagates transforms and updates the joint matrices of skinned meshes
+pub fn animate(world: &mut World, delta: f32) -> Result<()> {
+	let players = world
+		.entities()
+		.into_iter()
+		.filter(|entity| world.get_component::<AnimationPlayer>(*entity).is_some())
+		.collect::<Vec<_>>();
+	let mut poses = Vec::new();
+	for entity in players {
+		let Some(mut player) = world.get_component_mut::<AnimationPlayer>(entity) else {
+			continue;
+		};
+		player.advance(delta);
+		let named = named_descendants(world, entity);
+		for name in player.targets() {
+			let Some(target) = named.get(&name).copied() else {
+				continue;
+			};
+			let transform = world
+				.get_component::<Transform>(target)
+				.map(|transform| *transform)
+				.unwrap_or_default();
+			poses.push((target, player.pose(&name, transform)));
+		}
+	}
+	for (entity, transform) in poses {
+		world.add_component(entity, transform)?;
+	}
+	propagate_transforms(world)?;
+	update_joint_matrices(world)
+}
+
+// The entity and its descendants by name, with the nearest taking precedence
+fn named_descendants(world: &World, root: Entity) -> HashMap<String, Entity> {
+	let mut named = HashMap::new();
+	let mut visited = HashSet::new();
+	let mut queue = VecDeque::from([root]);
+	while let Some(entity) = queue.pop_front() {
+		if !visited.insert(entity) {
+			continue;
+		}
+		if let Some(Name(name)) = world.get_component::<Name>(entity).as_deref() {
+			named.entry(name.clone()).or_insert(entity);
+		}
+		queue.extend(hierarchy::children(world, entity));
+	}
+	named
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Channel, Interpolation, JointMatrices, Keyframes, SkinnedMesh};
+	use math::{Mat4, Vec3};
+
+	fn slide(to: Vec3) -> Arc<AnimationClip> {
+		Arc::new(AnimationClip {
+			name: String::new(),
+			channels: vec![Channel {
+				target: "bone".to_string(),
+				times: vec![0.0, 2.0],
+				keyframes: Keyframes::Translation(vec![Vec3::ZERO, to * 2.0]),
+				interpolation: Interpolation::Linear,
+			}],
+		})
+	}
+
+	fn close(a: Vec3, b: Vec3) -> bool {
+		a.distance(b) < 1e-4
+	}
+
+	#[test]
+	fn players_crossfade_and_pose_skeletons() -> Result<()> {
+		let mut world = World::new();
+		let [root, bone, mesh] = [(); 3].map(|_| world.create_entity());
+		world.add_component(root, Transform::from_xyz(0.0, 0.0, 5.0))?;
+		world.add_component(root, AnimationPlayer::new(slide(Vec3::X)))?;
+		world.add_component(bone, Name("bone".to_string()))?;
+		world.add_component(bone, Transform::default())?;
+		hierarchy::set_parent(&mut world, bone, Some(root))?;
+		world.add_component(mesh, Transform::from_xyz(0.0, 0.0, 5.0))?;
+		world.add_component(
+			mesh,
+			SkinnedMesh {
+				joints: vec![bone],
+				inverse_bind_matrices: vec![Mat4::IDENTITY],
+			},
+		)?;
+		let bone_at = |world: &World| world.get_component::<Transform>(bone).unwrap().translation;
+
+		animate(&mut world, 0.5)?;
+		assert!(close(bone_at(&world), Vec3::new(0.5, 0.0, 0.0)));
+		// Joints move the mesh relative to itself
+		let matrices = world
+			.get_component::<JointMatrices>(mesh)
+			.unwrap()
+			.0
+			.clone();
+		assert!(close(
+			matrices[0].w_axis.truncate(),
+			Vec3::new(0.5, 0.0, 0.0)
+		));
+
+		let mut player = world.get_component_mut::<AnimationPlayer>(root).unwrap();
+		player.crossfade(slide(Vec3::Y), 1.0);
+		drop(player);
+		animate(&mut world, 0.5)?;
+		// Halfway between the old clip at one second and the new one at half a second
+		assert!(close(bone_at(&world), Vec3::new(0.5, 0.25, 0.0)));
+		animate(&mut world, 0.5)?;
+		assert!(close(bone_at(&world), Vec3::new(0.0, 1.0, 0.0)));
+		assert!(!world
+			.get_component::<AnimationPlayer>(root)
+			.unwrap()
+			.is_fading());
+
+		// Clips that don't repeat hold their last pose
+		let mut player = world.get_component_mut::<AnimationPlayer>(root).unwrap();
+		player.repeat = false;
+		drop(player);
+		animate(&mut world, 5.0)?;
+		assert!(close(bone_at(&world), Vec3::new(0.0, 2.0, 0.0)));
+		assert!(world
+			.get_component::<AnimationPlayer>(root)
+			.unwrap()
+			.is_finished());
+		Ok(())
+	}
+}
@@ -0,0 +1,56 @@
+use ecs::{
+	error::Result,
+	world::{Entity, World},
+};
+use math::{GlobalTransform, Mat4};
+
+/// A mesh bent by a skeleton, with each of its vertices weighted to some of the joints
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SkinnedMesh {
+	pub joints: Vec<Entity>,
+	/// Take the mesh's space to each joint's space in the pose it was modeled in
+	pub inverse_bind_matrices: Vec<Mat4>,
+}
+
+/// What each joint moves the mesh's vertices by, in the mesh's space,
+/// ready to be uploaded for skinning
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct JointMatrices(pub Vec<Mat4>);
+
+/// Gives every `SkinnedMesh` the `JointMatrices` for where its joints are now.
+/// Run after `propagate_transforms` so joints' global transforms are current.
+pub fn update_joint_matrices(world: &mut World) -> Result<()> {
+	let skinned = world
+		.entities()
+		.into_iter()
+		.filter_map(|entity| {
+			let skin = world.get_component::<SkinnedMesh>(entity)?;
+			let mesh = global(world, entity).inverse();
+			let matrices = skin
+				.joints
+				.iter()
+				.enumerate()
+				.map(|(index, joint)| {
+					let bind = skin
+						.inverse_bind_matrices
+						.get(index)
+						.copied()
+						.unwrap_or_default();
+					mesh * global(world, *joint).matrix() * bind
+				})
+				.collect();
+			Some((entity, JointMatrices(matrices)))
+		})
+		.collect::<Vec<_>>();
+	for (entity, matrices) in skinned {
+		world.add_component(entity, matrices)?;
+	}
+	Ok(())
+}
+
+fn global(world: &World, entity: Entity) -> GlobalTransform {
+	world
+		.get_component::<GlobalTransform>(entity)
+		.map(|global| *global)
+		.unwrap_or_default()
+}
@@ -1,15 +1,47 @@
-use crate::state::{State, StateMachine};
-use image::io::Reader;
-use std::io;
+#[cfg(feature = "tray")]
+use crate::tray::{Tray, TrayConfig};
+use crate::{
+	config::{AppConfig, ConfigError, PresentMode, UpdateMode},
+	crash::CrashReporter,
+	input::{GamepadAxis, GamepadButton, GamepadId, Input},
+	job::{JobHandle, JobId, JobOutput, JobProgress, Jobs},
+	logging::{LogBuffer, Logger},
+	metrics::{AppMetrics, BusTraffic, SystemTiming, SystemTimings},
+	monitor::{self, MonitorInfo, WindowMode},
+	platform::{self, Instant},
+	plugin::{AppBuilder, BusCounter, EventHook, Extensions, Resources, System},
+	replay::{EventRecording, EventSource},
+	request::{CustomRequest, RequestContext, RequestHandlers},
+	router::EventRouter,
+	state::{Frame, State, StateError, StateHistory, StateMachine, Transition},
+	time::{rate_to_duration, FrameLoop, FrameTiming},
+	timer::{TimerId, Timers},
+	window::{
+		self, CursorMode, Resizes, WindowConfig, WindowHandles, WindowId, WindowLevel,
+		WindowRequest, Windows,
+	},
+	world::SharedWorld,
+};
+use bus::{EventBus, Publisher};
+use ecs::{time::advance_time, world::World};
+use image::{io::Reader, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::{any::Any, future::Future, io, path::PathBuf, sync::Arc, time::Duration};
 use thiserror::Error;
-use tokio::{sync::mpsc, task};
+use tokio::sync::mpsc;
 use winit::{
 	self,
+	application::ApplicationHandler,
 	dpi::PhysicalSize,
-	error::OsError,
-	event::{Event, WindowEvent},
-	event_loop::{ControlFlow, EventLoop, EventLoopBuilder, EventLoopProxy},
-	window::{Icon, WindowBuilder},
+	error::EventLoopError,
+	event::{
+		DeviceEvent, DeviceId, ElementState, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent,
+	},
+	event_loop::{
+		ActiveEventLoop, ControlFlow, DeviceEvents, EventLoop, EventLoopClosed, EventLoopProxy,
+	},
+	keyboard::KeyCode,
+	window::{Icon, Window, WindowAttributes},
 };
 
 #[derive(Error, Debug)]
@@ -17,8 +49,20 @@ pub enum Error {
 	#[error("Failed to create icon file!")]
 	CreateIcon(#[source] winit::window::BadIcon),
 
-	#[error("Failed to create a window!")]
-	CreateWindow(#[source] OsError),
+	#[error("Failed to open event recording at path: {1}")]
+	OpenRecording(#[source] io::Error, String),
+
+	#[error("Invalid app config!")]
+	InvalidConfig(#[source] ConfigError),
+
+	#[error("Failed to create the event loop!")]
+	CreateEventLoop(#[source] EventLoopError),
+
+	#[error("Failed to open log file at path: {1}")]
+	OpenLogFile(#[source] io::Error, String),
+
+	#[error("Failed to decode embedded icon!")]
+	DecodeIconBytes(#[source] image::ImageError),
 
 	#[error("Failed to decode icon file at path: {1}")]
 	DecodeIconFile(#[source] image::ImageError, String),
@@ -29,143 +73,1390 @@ pub enum Error {
 
 type Result<T, E = Error> = std::result::Result<T, E>;
 
+pub type TaskResult<T = ()> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+// How long stacked states get to finish their `on_stop` work when the app exits
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+const METRICS_INTERVAL: Duration = Duration::from_secs(1);
+
 #[derive(Debug)]
-pub struct AppConfig {
-	pub width: u32,
-	pub height: u32,
-	pub is_fullscreen: bool,
-	pub title: String,
-	pub icon: Option<String>,
+pub enum WorkerRequest {
+	/// Sent by the worker once the state machine has shut down
+	Exit(i32),
+	CreateWindow(WindowId, WindowConfig),
+	CloseWindow(WindowId),
+	Window(WindowId, WindowRequest),
+	/// Sent by the worker when `Context::set_update_rate` changes the frame rate
+	SetUpdateRate(Option<f64>),
+	QueryMonitors,
+	Custom(CustomRequest),
 }
 
-impl Default for AppConfig {
-	fn default() -> Self {
-		Self {
-			width: 1920,
-			height: 1080,
-			is_fullscreen: false,
-			title: "Hourglass App".to_string(),
-			icon: None,
-		}
-	}
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextComposition {
+	/// The text is final and should be inserted
+	Committed,
+	/// The text is an in-progress IME composition that replaces the previous one,
+	/// with the byte range of the composition cursor
+	Composing { cursor: Option<(usize, usize)> },
 }
 
-pub type TaskResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
-
-#[derive(Debug, Clone)]
-pub enum WorkerRequest {
-	Exit,
+/// Input straight from a device, regardless of which window has focus
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RawDeviceEvent {
+	MouseMotion {
+		delta_x: f64,
+		delta_y: f64,
+	},
+	MouseWheel {
+		delta: MouseScrollDelta,
+	},
+	Button {
+		button: u32,
+		state: ElementState,
+	},
+	Key {
+		keycode: Option<KeyCode>,
+		state: ElementState,
+	},
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AppEvent {
-	Resized { width: u32, height: u32 },
-	Exit,
+	Resized {
+		window: WindowId,
+		width: u32,
+		height: u32,
+	},
+	FocusChanged {
+		window: WindowId,
+		focused: bool,
+	},
+	/// The window was minimized or fully hidden behind other windows, or shown again
+	Occluded {
+		window: WindowId,
+		occluded: bool,
+	},
+	/// The primary window lost focus or was minimized, or came back to the foreground.
+	/// Updates follow `AppConfig::background_mode` while in the background.
+	BackgroundChanged {
+		background: bool,
+	},
+	/// The user started resizing the window. `AppEvent::Resized` follows with the final size
+	/// once it stops changing, when `AppConfig::resize_debounce_ms` is set.
+	ResizeStarted {
+		window: WindowId,
+	},
+	/// The window moved to a monitor with a different DPI, or the system scale changed
+	ScaleFactorChanged {
+		window: WindowId,
+		scale: f64,
+		width: u32,
+		height: u32,
+	},
+	KeyboardInput {
+		window: WindowId,
+		keycode: Option<KeyCode>,
+		state: ElementState,
+	},
+	TextInput {
+		window: WindowId,
+		text: String,
+		composition: TextComposition,
+	},
+	MouseButton {
+		window: WindowId,
+		button: MouseButton,
+		state: ElementState,
+	},
+	/// The cursor moved within the window, in physical pixels from the top left corner.
+	/// The logical position is divided by the window's scale factor, for UI laid out in points.
+	CursorMoved {
+		window: WindowId,
+		x: f64,
+		y: f64,
+		logical_x: f64,
+		logical_y: f64,
+	},
+	/// Line deltas are in lines, and pixel deltas in physical pixels
+	MouseWheel {
+		window: WindowId,
+		delta: MouseScrollDelta,
+	},
+	/// Raw mouse movement, unaffected by cursor acceleration or the window edges
+	MouseMotion {
+		delta_x: f64,
+		delta_y: f64,
+	},
+	/// A window requested with `Context::create_window` was opened
+	WindowCreated {
+		window: WindowId,
+	},
+	/// The platform asked for the window to be drawn, such as after it was uncovered
+	/// or after `Context::request_redraw`. Tools that only draw on demand draw here.
+	RedrawRequested {
+		window: WindowId,
+	},
+	/// Sent when `AppConfig::raw_device_events` is set, with an id that
+	/// stays the same for each device while the app runs
+	RawDevice {
+		device: u64,
+		event: RawDeviceEvent,
+	},
+	/// A secondary window was closed with `Context::close_window` or `Context::confirm_close`
+	WindowClosed {
+		window: WindowId,
+	},
+	Monitors {
+		monitors: Vec<MonitorInfo>,
+	},
+	GamepadConnected {
+		gamepad: GamepadId,
+	},
+	GamepadDisconnected {
+		gamepad: GamepadId,
+	},
+	GamepadButton {
+		gamepad: GamepadId,
+		button: GamepadButton,
+		state: ElementState,
+	},
+	GamepadAxis {
+		gamepad: GamepadId,
+		axis: GamepadAxis,
+		value: f32,
+	},
+	/// A finger touched, moved on, or left a touchscreen, with force from 0.0 to 1.0 when supported
+	Touch {
+		window: WindowId,
+		id: u64,
+		phase: TouchPhase,
+		x: f64,
+		y: f64,
+		force: Option<f64>,
+	},
+	/// An item of the menu added with `AppBuilder::with_tray` was selected, with the item's id
+	TrayMenu(String),
+	/// The platform suspended the app, mainly on mobile.
+	/// On Android the native surface is destroyed, so renderers must drop their surfaces.
+	Suspended,
+	/// The platform resumed the app, also sent once at startup.
+	/// On Android renderers must recreate their surfaces for the windows.
+	Resumed,
+	/// A job spawned with `Context::spawn_job` finished, with its output or error message
+	#[serde(skip)]
+	JobCompleted {
+		job: JobId,
+		result: Result<JobOutput, String>,
+	},
+	/// A timer started with `Context::after` or `Context::every` fired
+	#[serde(skip)]
+	Timer {
+		timer: TimerId,
+		tag: String,
+	},
+	/// A reply from a custom request handler
+	#[serde(skip)]
+	Custom(Arc<dyn Any + Send + Sync>),
+	/// The user asked to close a window. It closes once the states have seen the event,
+	/// unless one calls `Context::intercept_close`, such as to ask about unsaved changes
+	/// first, and later `Context::confirm_close`. Closing the primary window shuts the app down.
+	CloseRequested {
+		window: WindowId,
+	},
+}
+
+pub type ErrorHandler =
+	Box<dyn FnMut(&mut Context, &StateError) -> Transition<Context, AppEvent> + Send>;
+
+/// Decides what the worker does when a state returns an error. The transitions
+/// `PopState` and `Custom` apply bypass the active state's `can_transition` guard,
+/// since the state that failed shouldn't be able to block its own recovery.
+#[derive(Default)]
+pub enum ErrorPolicy {
+	/// Logs the error and keeps running
+	#[default]
+	Continue,
+	/// Logs the error and pops the active state
+	PopState,
+	/// Logs the error and exits the app with code 1
+	Quit,
+	/// Returns a transition to apply, such as pushing an error dialog state
+	Custom(ErrorHandler),
+}
+
+impl ErrorPolicy {
+	async fn handle(
+		&mut self,
+		state_machine: &mut StateMachine<Context, AppEvent>,
+		context: &mut Context,
+		result: TaskResult,
+	) -> TaskResult {
+		let Err(error) = result else {
+			return Ok(());
+		};
+		let transition = match self {
+			Self::Continue => {
+				log::warn!("{error}");
+				return Ok(());
+			}
+			Self::PopState => {
+				log::error!("{error}, popping the active state");
+				Transition::Pop
+			}
+			Self::Quit => {
+				log::error!("{error}, exiting");
+				context.exit(1);
+				return Ok(());
+			}
+			Self::Custom(handler) => {
+				log::error!("{error}");
+				handler(context, error.as_ref())
+			}
+		};
+		state_machine.force_transition(transition, context).await
+	}
 }
 
 pub struct Context {
-	pub app_proxy: EventLoopProxy<WorkerRequest>,
+	pub(crate) app_proxy: AppProxy,
+	pub input: Input,
+	pub timing: FrameTiming,
+	next_window_id: u64,
+	exit_code: Option<i32>,
+	close_intercepted: bool,
+	update_requested: bool,
+	update_rate_request: Option<Option<f64>>,
+	present_mode: PresentMode,
+	background: Background,
+	window_handles: WindowHandles,
+	logs: LogBuffer,
+	state_history: StateHistory,
+	metrics: AppMetrics,
+	/// Shared values registered by plugins with `AppBuilder::insert_resource`
+	pub resources: Resources,
+	systems: Vec<System>,
+	system_timings: SystemTimings,
+	bus_counters: Vec<BusCounter>,
+	world: Option<SharedWorld>,
+	jobs: Jobs,
+	timers: Timers,
+}
+
+type RequestResult<T = ()> = Result<T, EventLoopClosed<WorkerRequest>>;
+
+// Sends requests from the worker to the main thread. Tests record them instead,
+// since an event loop can only be created on the main thread.
+#[derive(Clone)]
+pub(crate) enum AppProxy {
+	EventLoop(EventLoopProxy<WorkerRequest>),
+	#[cfg(test)]
+	Recorder(std::sync::mpsc::Sender<WorkerRequest>),
+}
+
+impl AppProxy {
+	pub(crate) fn send_event(&self, request: WorkerRequest) -> RequestResult {
+		match self {
+			Self::EventLoop(proxy) => proxy.send_event(request),
+			#[cfg(test)]
+			Self::Recorder(sender) => sender
+				.send(request)
+				.map_err(|error| EventLoopClosed(error.0)),
+		}
+	}
+}
+
+impl Context {
+	/// Shuts the app down after the current frame, stopping every state
+	/// on the stack before the process exits with the given code
+	pub fn exit(&mut self, exit_code: i32) {
+		self.exit_code = Some(exit_code);
+	}
+
+	/// Runs another update even if no events arrive, when using `UpdateMode::Reactive`
+	pub fn request_update(&mut self) {
+		self.update_requested = true;
+	}
+
+	/// Runs a future on the async runtime, reporting its result with `AppEvent::JobCompleted`
+	pub fn spawn_job<T, F>(&mut self, job: impl FnOnce(JobProgress) -> F) -> JobHandle
+	where
+		T: Any + Send + Sync,
+		F: Future<Output = TaskResult<T>> + Send + 'static,
+	{
+		self.jobs.spawn(job)
+	}
+
+	/// Sends `AppEvent::Timer` with the tag once the delay has passed
+	pub fn after(&mut self, delay: Duration, tag: impl Into<String>) -> TimerId {
+		self.timers.after(delay, tag.into())
+	}
+
+	/// Sends `AppEvent::Timer` with the tag every period until cancelled. Periods shorter
+	/// than a millisecond are raised to one.
+	pub fn every(&mut self, period: Duration, tag: impl Into<String>) -> TimerId {
+		self.timers.every(period, tag.into())
+	}
+
+	pub fn cancel_timer(&mut self, timer: TimerId) {
+		self.timers.cancel(timer);
+	}
+
+	/// Runs the systems registered with `AppBuilder::add_system` on a world
+	pub fn run_systems(&self, world: &mut World) -> ecs::error::Result<()> {
+		self.system_timings.run(&self.systems, world)
+	}
+
+	/// How long each system took in its latest and all previous runs, in registration order
+	pub fn system_timings(&self) -> Vec<SystemTiming> {
+		self.system_timings.snapshot()
+	}
+
+	/// How many messages each `EventBus` resource created by the `AppBuilder` has published
+	pub fn bus_traffic(&self) -> Vec<BusTraffic> {
+		self.bus_counters
+			.iter()
+			.map(|(name, published)| BusTraffic {
+				name,
+				published: published(),
+			})
+			.collect()
+	}
+
+	/// The world created with `AppBuilder::with_world`
+	pub fn world(&self) -> Option<&SharedWorld> {
+		self.world.as_ref()
+	}
+
+	/// Runs the systems registered with `AppBuilder::add_system` on the shared world.
+	/// The future doesn't borrow the context, so states can await it.
+	pub fn run_world_systems(&self) -> impl Future<Output = TaskResult> + 'static {
+		let world = self.world.clone();
+		let systems = self.systems.clone();
+		let timings = self.system_timings.clone();
+		async move {
+			let Some(world) = world else {
+				return Ok(());
+			};
+			world
+				.with(move |world| {
+					timings
+						.run(&systems, world)
+						.map_err(|error| error.to_string())
+				})
+				.await?
+				.map_err(Into::into)
+		}
+	}
+
+	/// The most recent log records, kept when the app installed its own logger
+	pub fn logs(&self) -> &LogBuffer {
+		&self.logs
+	}
+
+	pub fn metrics(&self) -> &AppMetrics {
+		&self.metrics
+	}
+
+	/// The most recent changes to the state stack, the last one holding the current stack
+	pub fn state_history(&self) -> &StateHistory {
+		&self.state_history
+	}
+
+	/// Changes the maximum frame rate at the start of the next frame, uncapped when `None`
+	pub fn set_update_rate(&mut self, update_rate: impl Into<Option<f64>>) {
+		self.update_rate_request = Some(update_rate.into());
+	}
+
+	/// Whether the primary window is unfocused or minimized
+	pub fn is_in_background(&self) -> bool {
+		self.background.active()
+	}
+
+	/// The preferred present mode. Renderers should reconfigure their surfaces when it changes.
+	pub fn present_mode(&self) -> PresentMode {
+		self.present_mode
+	}
+
+	pub fn set_present_mode(&mut self, present_mode: PresentMode) {
+		self.present_mode = present_mode;
+	}
+
+	/// Opens a new native window, returning its id immediately
+	pub fn create_window(&mut self, config: WindowConfig) -> RequestResult<WindowId> {
+		self.next_window_id += 1;
+		let window = WindowId(self.next_window_id);
+		self.app_proxy
+			.send_event(WorkerRequest::CreateWindow(window, config))?;
+		Ok(window)
+	}
+
+	/// The native window, such as for creating a rendering surface or reading its scale factor.
+	/// Windows opened with `Context::create_window` are available after `AppEvent::WindowCreated`.
+	pub fn window(&self, window: WindowId) -> Option<Arc<Window>> {
+		self.window_handles.get(window)
+	}
+
+	pub fn close_window(&self, window: WindowId) -> RequestResult {
+		self.app_proxy
+			.send_event(WorkerRequest::CloseWindow(window))
+	}
+
+	/// Keeps the window open while handling `AppEvent::CloseRequested`,
+	/// until `Context::confirm_close` is called
+	pub fn intercept_close(&mut self) {
+		self.close_intercepted = true;
+	}
+
+	/// Lets a window close after `AppEvent::CloseRequested`,
+	/// exiting with code 0 if it is the primary window
+	pub fn confirm_close(&mut self, window: WindowId) -> RequestResult {
+		if window == WindowId::PRIMARY {
+			self.exit(0);
+			return Ok(());
+		}
+		self.close_window(window)
+	}
+
+	pub fn set_title(&self, window: WindowId, title: impl Into<String>) -> RequestResult {
+		self.window_request(window, WindowRequest::SetTitle(title.into()))
+	}
+
+	pub fn set_inner_size(&self, window: WindowId, width: u32, height: u32) -> RequestResult {
+		self.window_request(window, WindowRequest::SetInnerSize { width, height })
+	}
+
+	pub fn set_window_mode(&self, window: WindowId, mode: WindowMode) -> RequestResult {
+		self.window_request(window, WindowRequest::SetWindowMode(mode))
+	}
+
+	/// Requests the connected monitors, answered with `AppEvent::Monitors`
+	pub fn request_monitors(&self) -> RequestResult {
+		self.app_proxy.send_event(WorkerRequest::QueryMonitors)
+	}
+
+	pub fn set_decorations(&self, window: WindowId, decorations: bool) -> RequestResult {
+		self.window_request(window, WindowRequest::SetDecorations(decorations))
+	}
+
+	pub fn set_cursor_visible(&self, window: WindowId, visible: bool) -> RequestResult {
+		self.window_request(window, WindowRequest::SetCursorVisible(visible))
+	}
+
+	pub fn set_cursor_mode(&self, window: WindowId, mode: CursorMode) -> RequestResult {
+		self.window_request(window, WindowRequest::SetCursorMode(mode))
+	}
+
+	/// Keeps the window above or below other windows, such as for an overlay
+	pub fn set_window_level(&self, window: WindowId, level: WindowLevel) -> RequestResult {
+		self.window_request(window, WindowRequest::SetWindowLevel(level))
+	}
+
+	/// Passes mouse input through the window to the windows beneath it.
+	/// Not supported on the web, Android or iOS.
+	pub fn set_click_through(&self, window: WindowId, click_through: bool) -> RequestResult {
+		self.window_request(window, WindowRequest::SetClickThrough(click_through))
+	}
+
+	/// Enables IME composition, required for text input in languages such as Chinese or Japanese
+	pub fn set_ime_allowed(&self, window: WindowId, allowed: bool) -> RequestResult {
+		self.window_request(window, WindowRequest::SetImeAllowed(allowed))
+	}
+
+	/// Sends a request to the handler registered with `App::add_request_handler`
+	pub fn send_request(&self, request: impl Any + Send + Sync) -> RequestResult {
+		self.app_proxy
+			.send_event(WorkerRequest::Custom(CustomRequest::new(request)))
+	}
+
+	pub fn focus_window(&self, window: WindowId) -> RequestResult {
+		self.window_request(window, WindowRequest::Focus)
+	}
+
+	/// Asks the platform to send `AppEvent::RedrawRequested` when the window should be drawn
+	pub fn request_redraw(&self, window: WindowId) -> RequestResult {
+		self.window_request(window, WindowRequest::RequestRedraw)
+	}
+
+	fn window_request(&self, window: WindowId, request: WindowRequest) -> RequestResult {
+		self.app_proxy
+			.send_event(WorkerRequest::Window(window, request))
+	}
 }
 
 pub struct App {
 	event_loop: EventLoop<WorkerRequest>,
-	window: winit::window::Window,
+	window_attributes: WindowAttributes,
+	window_mode: WindowMode,
+	frame_loop: FrameLoop,
+	request_handlers: RequestHandlers,
+	extensions: Extensions,
+	event_source: EventSource,
+	logs: LogBuffer,
+	error_policy: ErrorPolicy,
+	raw_device_events: bool,
+	resizes: Option<Resizes>,
+	#[cfg(feature = "tray")]
+	tray: Option<(TrayConfig, Option<RgbaImage>)>,
 }
 
 impl App {
 	pub fn new(config: &AppConfig) -> Result<Self> {
-		let event_loop = EventLoopBuilder::<WorkerRequest>::with_user_event().build();
+		Self::create(config, RequestHandlers::default(), Extensions::default())
+	}
 
-		let mut window_builder = WindowBuilder::new()
-			.with_title(config.title.to_string())
-			.with_inner_size(PhysicalSize::new(config.width, config.height));
+	pub fn builder(config: AppConfig) -> AppBuilder {
+		AppBuilder::new(config)
+	}
+
+	/// Sets what happens when a state returns an error, logging and continuing by default
+	pub fn with_error_policy(mut self, error_policy: ErrorPolicy) -> Self {
+		self.error_policy = error_policy;
+		self
+	}
+
+	pub(crate) fn create(
+		config: &AppConfig,
+		request_handlers: RequestHandlers,
+		extensions: Extensions,
+	) -> Result<Self> {
+		config.validate().map_err(Error::InvalidConfig)?;
 
-		if let Some(icon_path) = config.icon.as_ref() {
-			let icon = load_icon(icon_path)?;
-			window_builder = window_builder.with_window_icon(Some(icon));
+		let mut log_config = config.logging.clone();
+		if let Ok(filter) = std::env::var("RUST_LOG") {
+			log_config.apply_filter(&filter);
+		}
+		let logger = Logger::new(&log_config).map_err(|error| {
+			Error::OpenLogFile(error, log_config.file.clone().unwrap_or_default())
+		})?;
+		let logs = logger.buffer();
+		if !logger.install() {
+			log::debug!("A logger is already installed, ignoring the logging config");
 		}
 
-		let window = window_builder
-			.build(&event_loop)
-			.map_err(Error::CreateWindow)?;
+		CrashReporter {
+			title: config.title.to_string(),
+			crash_dir: config.crash_dir.as_ref().map(PathBuf::from),
+		}
+		.install();
 
-		Ok(Self { window, event_loop })
+		let event_recording = config.event_recording.as_ref();
+		let event_source = EventSource::open(event_recording).map_err(|error| {
+			let path = event_recording
+				.map(EventRecording::path)
+				.unwrap_or_default();
+			Error::OpenRecording(error, path.to_string())
+		})?;
+
+		let mut event_loop_builder = EventLoop::<WorkerRequest>::with_user_event();
+		#[cfg(target_os = "android")]
+		if let Some(android_app) = extensions.android_app.clone() {
+			use winit::platform::android::EventLoopBuilderExtAndroid;
+			event_loop_builder.with_android_app(android_app);
+		}
+		let event_loop = event_loop_builder.build().map_err(Error::CreateEventLoop)?;
+		if config.raw_device_events {
+			event_loop.listen_device_events(DeviceEvents::Always);
+		}
+
+		let icon_image = match (config.icon_bytes, config.icon.as_ref()) {
+			(Some(icon_bytes), _) => Some(load_icon_bytes(icon_bytes)?),
+			(None, Some(icon_path)) => Some(load_icon(icon_path)?),
+			(None, None) => None,
+		};
+		#[cfg(feature = "tray")]
+		let mut extensions = extensions;
+		#[cfg(feature = "tray")]
+		let tray = extensions
+			.tray
+			.take()
+			.map(|tray_config| (tray_config, icon_image.clone()));
+		let icon = icon_image.map(create_icon).transpose()?;
+
+		// The primary window is created once the event loop resumes
+		let window_attributes = Window::default_attributes()
+			.with_title(config.title.to_string())
+			.with_inner_size(PhysicalSize::new(config.width, config.height))
+			.with_window_icon(icon);
+		let window_attributes = window::with_canvas(window_attributes, config.canvas.as_deref());
+
+		Ok(Self {
+			event_loop,
+			window_attributes,
+			window_mode: config.window_mode,
+			frame_loop: FrameLoop::new(config),
+			request_handlers,
+			extensions,
+			event_source,
+			logs,
+			error_policy: ErrorPolicy::default(),
+			raw_device_events: config.raw_device_events,
+			resizes: config
+				.resize_debounce_ms
+				.map(|millis| Resizes::new(Duration::from_millis(millis))),
+			#[cfg(feature = "tray")]
+			tray,
+		})
 	}
 
 	pub fn run(self, initial_state: impl State<Context, AppEvent>) {
-		let Self { event_loop, window } = self;
+		let Self {
+			event_loop,
+			window_attributes,
+			window_mode,
+			frame_loop,
+			request_handlers,
+			extensions,
+			event_source,
+			logs,
+			error_policy,
+			raw_device_events,
+			resizes,
+			#[cfg(feature = "tray")]
+			tray,
+		} = self;
 
 		let (worker_sender, worker_receiver) = mpsc::unbounded_channel();
-		let proxy = event_loop.create_proxy();
-		task::spawn(worker(proxy, worker_receiver, initial_state));
-
-		event_loop.run(move |event, _, control_flow| {
-			*control_flow = ControlFlow::Poll;
-
-			let process_event = || -> Result<(), Box<dyn std::error::Error>> {
-				match event {
-					// Respond to winit events by notifying the background worker
-					Event::WindowEvent { window_id, event } if window_id == window.id() => {
-						match event {
-							WindowEvent::CloseRequested => {
-								worker_sender.send(AppEvent::Exit)?;
-							}
-							WindowEvent::Resized(PhysicalSize { width, height }) => {
-								worker_sender.send(AppEvent::Resized { width, height })?
-							}
-							_ => {}
-						}
-					}
+		let windows = Windows::default();
+		let window_handles = windows.handles();
+		#[cfg_attr(target_arch = "wasm32", allow(unused_mut))]
+		let mut handler = AppHandler {
+			windows,
+			window_attributes,
+			window_mode,
+			update_mode: frame_loop.update_mode(),
+			frame_duration: frame_loop.frame_duration(),
+			router: EventRouter::new(
+				resizes,
+				raw_device_events,
+				window_handles.clone(),
+				worker_sender.clone(),
+			),
+			worker_sender: worker_sender.clone(),
+			request_handlers,
+			#[cfg(feature = "gamepad")]
+			gamepads: crate::gamepad::Gamepads::new(),
+			#[cfg(feature = "tray")]
+			tray_setup: tray,
+			#[cfg(feature = "tray")]
+			tray: None,
+			worker: Some((
+				WorkerSetup {
+					app_proxy: event_loop.create_proxy(),
+					worker_sender,
+					worker_receiver,
+					frame_loop,
+					extensions,
+					event_source,
+					logs,
+					error_policy,
+					window_handles,
+				},
+				initial_state,
+			)),
+			exit_code: 0,
+		};
 
-					// These events are sent the background worker
-					Event::UserEvent(message) => match message {
-						WorkerRequest::Exit => {
-							*control_flow = ControlFlow::Exit;
-						}
-					},
-					_ => {}
+		#[cfg(not(target_arch = "wasm32"))]
+		{
+			if let Err(error) = event_loop.run_app(&mut handler) {
+				log::error!("Event loop failed: {error}");
+				handler.exit_code = 1;
+			}
+			std::process::exit(handler.exit_code);
+		}
+
+		// The browser owns the event loop, so this returns immediately
+		#[cfg(target_arch = "wasm32")]
+		{
+			use winit::platform::web::EventLoopExtWebSys;
+			event_loop.spawn_app(handler);
+		}
+	}
+}
+
+// Runs on the event loop thread, forwarding winit events to the worker
+// and carrying out the requests it sends back
+struct AppHandler<S> {
+	windows: Windows,
+	window_attributes: WindowAttributes,
+	window_mode: WindowMode,
+	update_mode: UpdateMode,
+	frame_duration: Option<Duration>,
+	router: EventRouter,
+	worker_sender: mpsc::UnboundedSender<AppEvent>,
+	request_handlers: RequestHandlers,
+	#[cfg(feature = "gamepad")]
+	gamepads: crate::gamepad::Gamepads,
+	/// Taken with the worker, since the tray icon can't be created before the event loop runs
+	#[cfg(feature = "tray")]
+	tray_setup: Option<(TrayConfig, Option<RgbaImage>)>,
+	#[cfg(feature = "tray")]
+	tray: Option<Tray>,
+	/// Taken when the primary window exists and the worker starts
+	worker: Option<(WorkerSetup, S)>,
+	exit_code: i32,
+}
+
+impl<S: State<Context, AppEvent>> AppHandler<S> {
+	fn create_primary_window(&mut self, event_loop: &ActiveEventLoop) -> TaskResult {
+		if self.windows.get(WindowId::PRIMARY).is_some() {
+			return Ok(());
+		}
+		let window = event_loop.create_window(self.window_attributes.clone())?;
+		window.set_fullscreen(monitor::fullscreen(&window, self.window_mode));
+		self.windows.insert(WindowId::PRIMARY, window);
+
+		if let Some((setup, initial_state)) = self.worker.take() {
+			#[cfg(feature = "tray")]
+			if let Some((tray_config, icon)) = self.tray_setup.take() {
+				self.tray = Some(Tray::new(
+					tray_config,
+					icon,
+					self.worker_sender.clone(),
+					setup.app_proxy.clone(),
+				));
+			}
+			let proxy = setup.app_proxy.clone();
+			platform::spawn(async move {
+				let exit_code = worker(setup, initial_state).await.unwrap_or_else(|error| {
+					log::error!("Worker failed: {error}");
+					1
+				});
+				let _ = proxy.send_event(WorkerRequest::Exit(exit_code));
+			});
+		}
+		Ok(())
+	}
+
+	fn process_window_event(&mut self, window: WindowId, event: WindowEvent) -> TaskResult {
+		#[cfg(feature = "tray")]
+		if window == WindowId::PRIMARY
+			&& matches!(event, WindowEvent::Resized(_) | WindowEvent::Occluded(_))
+		{
+			if let (Some(tray), Some(primary)) = (self.tray.as_ref(), self.windows.get(window)) {
+				tray.hide_if_minimized(primary);
+			}
+		}
+		self.router.window_event(window, event)
+	}
+
+	fn process_request(
+		&mut self,
+		event_loop: &ActiveEventLoop,
+		request: WorkerRequest,
+	) -> TaskResult {
+		match request {
+			WorkerRequest::Exit(exit_code) => {
+				self.exit_code = exit_code;
+				event_loop.exit();
+			}
+			WorkerRequest::CreateWindow(window, config) => {
+				self.windows.create(window, &config, event_loop)?;
+				self.worker_sender
+					.send(AppEvent::WindowCreated { window })?;
+			}
+			WorkerRequest::CloseWindow(window) => {
+				if self.windows.close(window) {
+					self.worker_sender.send(AppEvent::WindowClosed { window })?;
 				}
+			}
+			WorkerRequest::Window(window, request) => {
+				self.windows.handle_request(window, request)?
+			}
+			WorkerRequest::SetUpdateRate(update_rate) => {
+				self.frame_duration = update_rate.map(rate_to_duration);
+			}
+			WorkerRequest::QueryMonitors => self.worker_sender.send(AppEvent::Monitors {
+				monitors: self.windows.monitors(),
+			})?,
+			WorkerRequest::Custom(request) => self.request_handlers.handle(
+				request,
+				&RequestContext {
+					window: self.windows.get(WindowId::PRIMARY),
+					worker_sender: &self.worker_sender,
+				},
+			),
+		}
+		Ok(())
+	}
+}
 
-				Ok(())
-			};
+impl<S: State<Context, AppEvent>> ApplicationHandler<WorkerRequest> for AppHandler<S> {
+	fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+		if let Err(error) = self.create_primary_window(event_loop) {
+			log::error!("Failed to create the primary window: {error}");
+			self.exit_code = 1;
+			event_loop.exit();
+			return;
+		}
+		log_error(self.worker_sender.send(AppEvent::Resumed));
+	}
+
+	fn suspended(&mut self, _event_loop: &ActiveEventLoop) {
+		log_error(self.worker_sender.send(AppEvent::Suspended));
+	}
+
+	fn window_event(
+		&mut self,
+		_event_loop: &ActiveEventLoop,
+		window_id: winit::window::WindowId,
+		event: WindowEvent,
+	) {
+		if let Some(window) = self.windows.find(window_id) {
+			log_error(self.process_window_event(window, event));
+		}
+	}
+
+	fn device_event(
+		&mut self,
+		_event_loop: &ActiveEventLoop,
+		device_id: DeviceId,
+		event: DeviceEvent,
+	) {
+		log_error(self.router.device_event(device_number(device_id), event));
+	}
+
+	fn user_event(&mut self, event_loop: &ActiveEventLoop, request: WorkerRequest) {
+		log_error(self.process_request(event_loop, request));
+	}
+
+	fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+		#[cfg(feature = "gamepad")]
+		log_error(self.gamepads.poll(&self.worker_sender));
+
+		let now = Instant::now();
+		log_error(self.router.flush_resizes(now));
 
-			if let Err(error) = process_event() {
-				log::error!("Error: {error}");
+		// Wake up once per frame to poll gamepads without spinning the CPU
+		let control_flow = match (self.update_mode, self.frame_duration) {
+			(UpdateMode::Reactive, _) => ControlFlow::Wait,
+			(UpdateMode::Continuous, Some(frame_duration)) => {
+				ControlFlow::WaitUntil(now + frame_duration)
 			}
+			(UpdateMode::Continuous, None) => ControlFlow::Poll,
+		};
+		let resize_deadline = self.router.next_resize_deadline();
+		event_loop.set_control_flow(match (control_flow, resize_deadline) {
+			(ControlFlow::Wait, Some(deadline)) => ControlFlow::WaitUntil(deadline),
+			(ControlFlow::WaitUntil(wake), Some(deadline)) => {
+				ControlFlow::WaitUntil(wake.min(deadline))
+			}
+			(control_flow, _) => control_flow,
 		});
 	}
 }
 
-fn load_icon(icon_path: &String) -> Result<Icon, Error> {
+// Device ids are opaque, so hash them into a number that can be recorded and replayed
+fn device_number(device_id: DeviceId) -> u64 {
+	use std::hash::{BuildHasher, BuildHasherDefault, DefaultHasher};
+	BuildHasherDefault::<DefaultHasher>::default().hash_one(device_id)
+}
+
+fn log_error<E: std::fmt::Display>(result: Result<(), E>) {
+	if let Err(error) = result {
+		log::error!("Error: {error}");
+	}
+}
+
+fn load_icon(icon_path: &String) -> Result<RgbaImage, Error> {
 	let image = Reader::open(icon_path)
 		.map_err(|error| Error::OpenIconFile(error, icon_path.to_string()))?
 		.decode()
-		.map_err(|error| Error::DecodeIconFile(error, icon_path.to_string()))?
-		.into_rgba8();
+		.map_err(|error| Error::DecodeIconFile(error, icon_path.to_string()))?;
+	Ok(image.into_rgba8())
+}
+
+fn load_icon_bytes(icon_bytes: &[u8]) -> Result<RgbaImage, Error> {
+	let image = image::load_from_memory(icon_bytes).map_err(Error::DecodeIconBytes)?;
+	Ok(image.into_rgba8())
+}
+
+fn create_icon(image: RgbaImage) -> Result<Icon, Error> {
 	let (width, height) = image.dimensions();
 	let icon = Icon::from_rgba(image.into_raw(), width, height).map_err(Error::CreateIcon)?;
 	Ok(icon)
 }
 
-async fn worker(
+// Everything the worker task takes ownership of when the app starts
+struct WorkerSetup {
 	app_proxy: EventLoopProxy<WorkerRequest>,
-	mut worker_receiver: mpsc::UnboundedReceiver<AppEvent>,
+	worker_sender: mpsc::UnboundedSender<AppEvent>,
+	worker_receiver: mpsc::UnboundedReceiver<AppEvent>,
+	frame_loop: FrameLoop,
+	extensions: Extensions,
+	event_source: EventSource,
+	logs: LogBuffer,
+	error_policy: ErrorPolicy,
+	window_handles: WindowHandles,
+}
+
+async fn worker(
+	setup: WorkerSetup,
 	initial_state: impl State<Context, AppEvent>,
-) -> TaskResult {
-	let mut state_machine = StateMachine::new(initial_state);
+) -> TaskResult<i32> {
+	let WorkerSetup {
+		app_proxy,
+		worker_sender,
+		mut worker_receiver,
+		mut frame_loop,
+		mut event_source,
+		logs,
+		mut error_policy,
+		window_handles,
+		extensions:
+			Extensions {
+				resources,
+				systems,
+				bus_counters,
+				mut event_hooks,
+				states,
+				overlays,
+				state_observers,
+				mut pools,
+				world,
+				metrics_channel,
+				..
+			},
+	} = setup;
+	let metrics_publisher = metrics_channel.and_then(|channel_name| {
+		let event_bus = resources.get::<Arc<EventBus<AppMetrics>>>()?.clone();
+		Some(Publisher::new(event_bus, channel_name))
+	});
+	let mut next_metrics = Instant::now() + METRICS_INTERVAL;
+	let world = world.map(SharedWorld::spawn).transpose()?;
+	let present_mode = frame_loop.present_mode();
+	let state_history = StateHistory::default();
+	let mut state_machine = StateMachine::new(initial_state).with_history(state_history.clone());
+	for observer in state_observers {
+		state_machine.observe(observer);
+	}
 
-	let mut context = Context { app_proxy };
+	let mut context = Context {
+		app_proxy: AppProxy::EventLoop(app_proxy),
+		input: Input::default(),
+		timing: FrameTiming::default(),
+		next_window_id: WindowId::PRIMARY.0,
+		exit_code: None,
+		close_intercepted: false,
+		update_requested: false,
+		update_rate_request: None,
+		present_mode,
+		background: Background::default(),
+		window_handles,
+		logs,
+		state_history,
+		metrics: AppMetrics::default(),
+		resources,
+		system_timings: SystemTimings::new(&systems),
+		systems,
+		bus_counters,
+		world,
+		jobs: Jobs::new(worker_sender.clone()),
+		timers: Timers::new(worker_sender),
+	};
 	state_machine.start(&mut context).await?;
+	for state in states {
+		state_machine.push(state, &mut context).await?;
+	}
+	for overlay in overlays {
+		state_machine.add_overlay(overlay, &mut context).await?;
+	}
+
+	for pool in pools.iter_mut() {
+		pool.start()?;
+	}
+
+	let fixed_delta = frame_loop.fixed_timestep().as_secs_f64();
+	event_source.start();
+	while context.exit_code.is_none() && state_machine.is_running() {
+		if frame_loop.waits_for_events() && !context.update_requested {
+			// Wake up for the next live event, or the next replayed one
+			let received = match event_source.next_replay_deadline() {
+				Some(deadline) => tokio::select! {
+					received = worker_receiver.recv() => Some(received),
+					_ = platform::sleep_until(deadline) => None,
+				},
+				None => Some(worker_receiver.recv().await),
+			};
+			if let Some(received) = received {
+				let Some(event) = received else {
+					break;
+				};
+				match event_source.live(event) {
+					Some(event) => {
+						let result =
+							handle_event(&mut state_machine, &mut context, &mut event_hooks, event)
+								.await;
+						error_policy
+							.handle(&mut state_machine, &mut context, result)
+							.await?;
+					}
+					None => context.metrics.dropped_events += 1,
+				}
+			}
+			frame_loop.reset();
+		}
+		context.update_requested = false;
+
+		if let Some(update_rate) = context.update_rate_request.take() {
+			frame_loop.set_update_rate(update_rate);
+			context
+				.app_proxy
+				.send_event(WorkerRequest::SetUpdateRate(update_rate))?;
+		}
 
-	loop {
-		while let Ok(mut event) = worker_receiver.try_recv() {
-			state_machine.on_event(&mut context, &mut event).await?;
+		let fixed_steps = frame_loop.begin_frame();
+		context.timing = frame_loop.timing();
+		if let Some(world) = context.world.as_ref() {
+			let delta = context.timing.delta_seconds() as f32;
+			// A closed world is reported to the states that reach for it
+			let _ = world.post(move |world| advance_time(world, delta));
+		}
+		context.metrics.record_frame(
+			Duration::from_secs_f64(context.timing.delta_seconds()),
+			frame_loop.lateness(),
+		);
+		context.metrics.record_queue_depth(worker_receiver.len());
+		if let Some(publisher) = metrics_publisher.as_ref() {
+			let now = Instant::now();
+			if now >= next_metrics {
+				next_metrics = now + METRICS_INTERVAL;
+				if let Err(error) =
+					publisher.publish_sync("metrics".to_string(), context.metrics.clone())
+				{
+					log::warn!("Failed to publish metrics: {error}");
+				}
+			}
 		}
 
-		if let Err(error) = state_machine.update(&mut context).await {
-			log::warn!("{error}");
+		let events_span = diagnostics::span("events");
+		while let Ok(event) = worker_receiver.try_recv() {
+			let Some(event) = event_source.live(event) else {
+				context.metrics.dropped_events += 1;
+				continue;
+			};
+			let result =
+				handle_event(&mut state_machine, &mut context, &mut event_hooks, event).await;
+			error_policy
+				.handle(&mut state_machine, &mut context, result)
+				.await?;
+		}
+		while let Some(event) = event_source.replayed() {
+			let result =
+				handle_event(&mut state_machine, &mut context, &mut event_hooks, event).await;
+			error_policy
+				.handle(&mut state_machine, &mut context, result)
+				.await?;
 		}
 
-		tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+		drop(events_span);
+
+		for _ in 0..fixed_steps {
+			let _span = diagnostics::span("fixed update");
+			let result = state_machine.fixed_update(&mut context, fixed_delta).await;
+			error_policy
+				.handle(&mut state_machine, &mut context, result)
+				.await?;
+		}
+
+		let update_span = diagnostics::span("update");
+		let result = state_machine.update(&mut context).await;
+		error_policy
+			.handle(&mut state_machine, &mut context, result)
+			.await?;
+		drop(update_span);
+
+		let render_span = diagnostics::span("render");
+		let frame = Frame {
+			index: context.timing.frame_index(),
+			alpha: frame_loop.fixed_alpha(),
+		};
+		let result = state_machine.render(&mut context, frame).await;
+		error_policy
+			.handle(&mut state_machine, &mut context, result)
+			.await?;
+		drop(render_span);
+		context.input.end_frame();
+
+		diagnostics::frame_mark();
+		frame_loop.set_in_background(context.is_in_background());
+		frame_loop.end_frame().await;
+	}
+
+	for pool in pools.iter_mut() {
+		pool.stop();
+	}
+	match platform::timeout(SHUTDOWN_TIMEOUT, state_machine.stop(&mut context)).await {
+		Some(result) => result?,
+		None => log::warn!("States did not stop within {SHUTDOWN_TIMEOUT:?}, exiting anyway"),
+	}
+
+	Ok(context.exit_code.unwrap_or_default())
+}
+
+async fn handle_event(
+	state_machine: &mut StateMachine<Context, AppEvent>,
+	context: &mut Context,
+	event_hooks: &mut [EventHook],
+	mut event: AppEvent,
+) -> TaskResult {
+	context.input.handle_event(&event);
+	for hook in event_hooks.iter_mut() {
+		hook(context, &event);
+	}
+	match event {
+		AppEvent::Suspended => state_machine.on_suspend_app(context).await?,
+		AppEvent::Resumed => state_machine.on_resume_app(context).await?,
+		_ => {}
+	}
+	let background_changed = context.background.track(&event);
+	context.close_intercepted = false;
+	state_machine.on_event(context, &mut event).await?;
+	if let AppEvent::CloseRequested { window } = event {
+		if !std::mem::take(&mut context.close_intercepted) {
+			context.confirm_close(window)?;
+		}
+	}
+
+	if let Some(background) = background_changed {
+		let mut event = AppEvent::BackgroundChanged { background };
+		for hook in event_hooks.iter_mut() {
+			hook(context, &event);
+		}
+		state_machine.on_event(context, &mut event).await?;
+	}
+	Ok(())
+}
+
+// Tracks whether the primary window is out of focus or minimized
+#[derive(Default)]
+struct Background {
+	unfocused: bool,
+	occluded: bool,
+	minimized: bool,
+}
+
+impl Background {
+	fn active(&self) -> bool {
+		self.unfocused || self.occluded || self.minimized
+	}
+
+	/// Returns the new background state when the event changed it
+	fn track(&mut self, event: &AppEvent) -> Option<bool> {
+		let was_active = self.active();
+		match *event {
+			AppEvent::FocusChanged {
+				window: WindowId::PRIMARY,
+				focused,
+			} => self.unfocused = !focused,
+			AppEvent::Occluded {
+				window: WindowId::PRIMARY,
+				occluded,
+			} => self.occluded = occluded,
+			// Some platforms report minimizing as resizing to zero
+			AppEvent::Resized {
+				window: WindowId::PRIMARY,
+				width,
+				height,
+			} => self.minimized = width == 0 || height == 0,
+			_ => return None,
+		}
+		let active = self.active();
+		(active != was_active).then_some(active)
+	}
+}
+
+#[cfg(test)]
+type RecordedRequests = std::sync::mpsc::Receiver<WorkerRequest>;
+
+#[cfg(test)]
+impl Context {
+	// A context outside of a running app, with the requests it sends to the main thread
+	// and the events its jobs and timers send to the worker
+	pub(crate) fn recorded() -> (Self, RecordedRequests, mpsc::UnboundedReceiver<AppEvent>) {
+		let (app_sender, app_receiver) = std::sync::mpsc::channel();
+		let (worker_sender, worker_receiver) = mpsc::unbounded_channel();
+		let context = Self {
+			app_proxy: AppProxy::Recorder(app_sender),
+			input: Input::default(),
+			timing: FrameTiming::default(),
+			next_window_id: WindowId::PRIMARY.0,
+			exit_code: None,
+			close_intercepted: false,
+			update_requested: false,
+			update_rate_request: None,
+			present_mode: PresentMode::default(),
+			background: Background::default(),
+			window_handles: WindowHandles::default(),
+			logs: LogBuffer::default(),
+			state_history: StateHistory::default(),
+			metrics: AppMetrics::default(),
+			resources: Resources::default(),
+			systems: Vec::new(),
+			system_timings: SystemTimings::default(),
+			bus_counters: Vec::new(),
+			world: None,
+			jobs: Jobs::new(worker_sender.clone()),
+			timers: Timers::new(worker_sender),
+		};
+		(context, app_receiver, worker_receiver)
+	}
+
+	pub(crate) fn exit_code(&self) -> Option<i32> {
+		self.exit_code
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::state::StateResult;
+	use async_trait::async_trait;
+	use image::{ImageOutputFormat, RgbaImage};
+	use std::io::Cursor;
+
+	#[test]
+	fn icon_from_bytes() {
+		let mut icon_bytes = Vec::new();
+		RgbaImage::new(2, 2)
+			.write_to(&mut Cursor::new(&mut icon_bytes), ImageOutputFormat::Png)
+			.unwrap();
+		assert!(load_icon_bytes(&icon_bytes).is_ok());
+		assert!(matches!(
+			load_icon_bytes(b"not an image"),
+			Err(Error::DecodeIconBytes(_))
+		));
+	}
+
+	#[test]
+	fn background_tracks_primary_window() {
+		let mut background = Background::default();
+		let focus = |window, focused| AppEvent::FocusChanged { window, focused };
+		assert_eq!(
+			background.track(&focus(WindowId::PRIMARY, false)),
+			Some(true)
+		);
+		assert_eq!(background.track(&focus(WindowId(1), true)), None);
+		let minimize = AppEvent::Resized {
+			window: WindowId::PRIMARY,
+			width: 0,
+			height: 0,
+		};
+		assert_eq!(background.track(&minimize), None);
+		assert_eq!(background.track(&focus(WindowId::PRIMARY, true)), None);
+		assert!(background.active());
+	}
+
+	struct Named(&'static str);
+
+	#[async_trait]
+	impl State<Context, AppEvent> for Named {
+		fn label(&self) -> String {
+			self.0.to_string()
+		}
+	}
+
+	// Blocks every transition, like a state with unsaved changes
+	struct Stubborn;
+
+	#[async_trait]
+	impl State<Context, AppEvent> for Stubborn {
+		fn label(&self) -> String {
+			"Stubborn".to_string()
+		}
+
+		fn can_transition(&self, _transition: &Transition<Context, AppEvent>) -> bool {
+			false
+		}
+	}
+
+	async fn handle_error(policy: &mut ErrorPolicy) -> (Vec<String>, Option<i32>) {
+		let (mut context, _requests, _events) = Context::recorded();
+		let mut state_machine = StateMachine::new(Named("Game"));
+		state_machine.start(&mut context).await.unwrap();
+		state_machine
+			.push(Box::new(Stubborn), &mut context)
+			.await
+			.unwrap();
+		policy
+			.handle(&mut state_machine, &mut context, Err("boom".into()))
+			.await
+			.unwrap();
+		(state_machine.stack_labels(), context.exit_code())
+	}
+
+	#[tokio::test]
+	async fn error_policies() {
+		assert_eq!(
+			handle_error(&mut ErrorPolicy::Continue).await,
+			(vec!["Game".to_string(), "Stubborn".to_string()], None)
+		);
+		// The failed state's guard doesn't stop its recovery
+		assert_eq!(
+			handle_error(&mut ErrorPolicy::PopState).await,
+			(vec!["Game".to_string()], None)
+		);
+		assert_eq!(
+			handle_error(&mut ErrorPolicy::Quit).await,
+			(vec!["Game".to_string(), "Stubborn".to_string()], Some(1))
+		);
+		let mut custom = ErrorPolicy::Custom(Box::new(|_context, error| {
+			assert_eq!(error.to_string(), "boom");
+			Transition::Switch(Box::new(Named("ErrorDialog")))
+		}));
+		assert_eq!(
+			handle_error(&mut custom).await,
+			(vec!["Game".to_string(), "ErrorDialog".to_string()], None)
+		);
+	}
+
+	// Asks about unsaved changes before letting the window close
+	struct UnsavedChanges;
+
+	#[async_trait]
+	impl State<Context, AppEvent> for UnsavedChanges {
+		async fn on_event(
+			&mut self,
+			context: &mut Context,
+			event: &mut AppEvent,
+		) -> StateResult<Transition<Context, AppEvent>> {
+			if matches!(event, AppEvent::CloseRequested { .. }) {
+				context.intercept_close();
+			}
+			Ok(Transition::None)
+		}
+	}
+
+	async fn request_close(
+		state: impl State<Context, AppEvent>,
+		window: WindowId,
+	) -> (Context, Vec<WorkerRequest>) {
+		let (mut context, requests, _events) = Context::recorded();
+		let mut state_machine = StateMachine::new(state);
+		state_machine.start(&mut context).await.unwrap();
+		let event = AppEvent::CloseRequested { window };
+		handle_event(&mut state_machine, &mut context, &mut [], event)
+			.await
+			.unwrap();
+		(context, requests.try_iter().collect())
+	}
+
+	#[tokio::test]
+	async fn close_requests_close_unless_intercepted() {
+		let (context, _) = request_close(Named("Game"), WindowId::PRIMARY).await;
+		assert_eq!(context.exit_code(), Some(0));
+
+		let (context, requests) = request_close(Named("Game"), WindowId(1)).await;
+		assert_eq!(context.exit_code(), None);
+		assert!(matches!(
+			requests[..],
+			[WorkerRequest::CloseWindow(WindowId(1))]
+		));
+
+		let (context, requests) = request_close(UnsavedChanges, WindowId::PRIMARY).await;
+		assert_eq!(context.exit_code(), None);
+		assert!(requests.is_empty());
 	}
 }
@@ -0,0 +1,426 @@
+use crate::{logging::LogConfig, monitor::WindowMode, replay::EventRecording};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::Path};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+	#[error("Failed to read config file at path: {1}")]
+	ReadConfig(#[source] io::Error, String),
+
+	#[error("Unsupported config file format at path: {0}")]
+	UnsupportedFormat(String),
+
+	#[error("Failed to parse TOML config!")]
+	ParseToml(#[source] toml::de::Error),
+
+	#[error("Failed to parse RON config!")]
+	ParseRon(#[source] ron::error::SpannedError),
+
+	#[error("Window size must be non-zero, got {0}x{1}")]
+	InvalidSize(u32, u32),
+
+	#[error("{0} must be a positive number, got {1}")]
+	InvalidRate(&'static str, f64),
+
+	#[error("Missing value for command-line flag --{0}")]
+	MissingArgument(String),
+
+	#[error("Invalid value '{1}' for command-line flag --{0}")]
+	InvalidArgument(String, String),
+}
+
+type Result<T, E = ConfigError> = std::result::Result<T, E>;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UpdateMode {
+	/// Update at the configured rate, for games and anything animated
+	#[default]
+	Continuous,
+	/// Only update after an event or `Context::request_update`, for editors and tools
+	Reactive,
+}
+
+/// What the worker does while the primary window is unfocused or minimized
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BackgroundMode {
+	/// Keep updating as if the window was in the foreground
+	#[default]
+	Run,
+	/// Update at most this many times per second
+	Throttle(f64),
+	/// Only update after an event or `Context::request_update`
+	Pause,
+}
+
+/// Presentation preference for renderers, named after the equivalent wgpu present modes
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PresentMode {
+	#[default]
+	AutoVsync,
+	AutoNoVsync,
+	Fifo,
+	Mailbox,
+	Immediate,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+	pub width: u32,
+	pub height: u32,
+	pub window_mode: WindowMode,
+	pub title: String,
+	pub icon: Option<String>,
+	/// Encoded image data, such as from `include_bytes!`. Takes priority over `icon`.
+	#[serde(skip)]
+	pub icon_bytes: Option<&'static [u8]>,
+	/// Maximum frames per second the worker runs `update` at, uncapped when `None`
+	pub update_rate: Option<f64>,
+	pub present_mode: PresentMode,
+	/// Simulation steps per second, independent of the frame rate
+	pub fixed_update_rate: f64,
+	pub update_mode: UpdateMode,
+	pub background_mode: BackgroundMode,
+	/// Sends `AppEvent::RawDevice` for keyboard and mouse input even while unfocused,
+	/// such as for global hotkeys. Not supported on every platform.
+	pub raw_device_events: bool,
+	/// Holds back `AppEvent::Resized` until the size has stopped changing for this many
+	/// milliseconds, sending `AppEvent::ResizeStarted` right away instead.
+	/// Stops drag-resizing from reconfiguring surfaces every frame.
+	pub resize_debounce_ms: Option<u64>,
+	pub event_recording: Option<EventRecording>,
+	/// On the web, the id of the canvas element to draw into.
+	/// A new canvas is appended to the page when unset.
+	pub canvas: Option<String>,
+	/// Directory a crash report with the backtrace is written to when the app panics.
+	/// Not supported on the web.
+	pub crash_dir: Option<String>,
+	/// Overridden by the `RUST_LOG` environment variable, such as `RUST_LOG=info,wgpu=warn`
+	pub logging: LogConfig,
+}
+
+impl Default for AppConfig {
+	fn default() -> Self {
+		Self {
+			width: 1920,
+			height: 1080,
+			window_mode: WindowMode::Windowed,
+			title: "Hourglass App".to_string(),
+			icon: None,
+			icon_bytes: None,
+			update_rate: Some(60.0),
+			present_mode: PresentMode::AutoVsync,
+			fixed_update_rate: 60.0,
+			update_mode: UpdateMode::Continuous,
+			background_mode: BackgroundMode::Run,
+			raw_device_events: false,
+			resize_debounce_ms: None,
+			event_recording: None,
+			canvas: None,
+			crash_dir: None,
+			logging: LogConfig::default(),
+		}
+	}
+}
+
+impl AppConfig {
+	pub fn builder() -> AppConfigBuilder {
+		AppConfigBuilder::default()
+	}
+
+	/// Loads a config from a `.toml` or `.ron` file. Missing fields keep their defaults.
+	pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+		let path = path.as_ref();
+		let contents = fs::read_to_string(path)
+			.map_err(|error| ConfigError::ReadConfig(error, path.display().to_string()))?;
+		let config = match path.extension().and_then(|extension| extension.to_str()) {
+			Some("toml") => Self::from_toml(&contents)?,
+			Some("ron") => Self::from_ron(&contents)?,
+			_ => return Err(ConfigError::UnsupportedFormat(path.display().to_string())),
+		};
+		config.validate()?;
+		Ok(config)
+	}
+
+	/// The default config with the process's command-line flags applied
+	pub fn from_args() -> Result<Self> {
+		let mut config = Self::default();
+		config.apply_args(std::env::args().skip(1))?;
+		Ok(config)
+	}
+
+	/// Overrides the config with `--width`, `--height`, `--title`, `--log-level`
+	/// and `--fullscreen` flags, such as after loading a config file.
+	/// Values can follow the flag or be joined with `=`, except for `--fullscreen`,
+	/// which only takes a joined `true`, `false`, `1` or `0`. Other arguments are ignored.
+	pub fn apply_args(&mut self, args: impl IntoIterator<Item = String>) -> Result<()> {
+		let mut args = args.into_iter();
+		while let Some(arg) = args.next() {
+			let Some(flag) = arg.strip_prefix("--") else {
+				continue;
+			};
+			let (flag, inline_value) = match flag.split_once('=') {
+				Some((flag, value)) => (flag, Some(value.to_string())),
+				None => (flag, None),
+			};
+			if flag == "fullscreen" {
+				let fullscreen = match inline_value.as_deref() {
+					None | Some("true" | "1") => true,
+					Some("false" | "0") => false,
+					Some(value) => {
+						return Err(ConfigError::InvalidArgument(
+							flag.to_string(),
+							value.to_string(),
+						))
+					}
+				};
+				self.window_mode = if fullscreen {
+					WindowMode::BorderlessFullscreen { monitor: None }
+				} else {
+					WindowMode::Windowed
+				};
+				continue;
+			}
+			if !matches!(flag, "width" | "height" | "title" | "log-level") {
+				continue;
+			}
+			let value = inline_value
+				.or_else(|| args.next())
+				.ok_or_else(|| ConfigError::MissingArgument(flag.to_string()))?;
+			let invalid = || ConfigError::InvalidArgument(flag.to_string(), value.clone());
+			match flag {
+				"width" => self.width = value.parse().map_err(|_| invalid())?,
+				"height" => self.height = value.parse().map_err(|_| invalid())?,
+				"log-level" => self.logging.level = value.parse().map_err(|_| invalid())?,
+				_ => self.title = value,
+			}
+		}
+		self.validate()
+	}
+
+	pub fn from_toml(contents: &str) -> Result<Self> {
+		toml::from_str(contents).map_err(ConfigError::ParseToml)
+	}
+
+	pub fn from_ron(contents: &str) -> Result<Self> {
+		ron::from_str(contents).map_err(ConfigError::ParseRon)
+	}
+
+	pub fn validate(&self) -> Result<()> {
+		if self.width == 0 || self.height == 0 {
+			return Err(ConfigError::InvalidSize(self.width, self.height));
+		}
+		let background_rate = match self.background_mode {
+			BackgroundMode::Throttle(rate) => Some(rate),
+			BackgroundMode::Run | BackgroundMode::Pause => None,
+		};
+		for (name, rate) in [
+			("update_rate", self.update_rate),
+			("fixed_update_rate", Some(self.fixed_update_rate)),
+			("background_mode", background_rate),
+		] {
+			let Some(rate) = rate else {
+				continue;
+			};
+			if !(rate.is_finite() && rate > 0.0) {
+				return Err(ConfigError::InvalidRate(name, rate));
+			}
+		}
+		Ok(())
+	}
+}
+
+#[derive(Default)]
+pub struct AppConfigBuilder {
+	config: AppConfig,
+}
+
+impl AppConfigBuilder {
+	pub fn title(mut self, title: impl Into<String>) -> Self {
+		self.config.title = title.into();
+		self
+	}
+
+	pub fn size(mut self, width: u32, height: u32) -> Self {
+		self.config.width = width;
+		self.config.height = height;
+		self
+	}
+
+	pub fn window_mode(mut self, window_mode: WindowMode) -> Self {
+		self.config.window_mode = window_mode;
+		self
+	}
+
+	pub fn icon(mut self, icon: impl Into<String>) -> Self {
+		self.config.icon = Some(icon.into());
+		self
+	}
+
+	pub fn icon_bytes(mut self, icon_bytes: &'static [u8]) -> Self {
+		self.config.icon_bytes = Some(icon_bytes);
+		self
+	}
+
+	/// Caps the frame rate, or leaves it uncapped when given `None`
+	pub fn update_rate(mut self, update_rate: impl Into<Option<f64>>) -> Self {
+		self.config.update_rate = update_rate.into();
+		self
+	}
+
+	pub fn present_mode(mut self, present_mode: PresentMode) -> Self {
+		self.config.present_mode = present_mode;
+		self
+	}
+
+	pub fn fixed_update_rate(mut self, fixed_update_rate: f64) -> Self {
+		self.config.fixed_update_rate = fixed_update_rate;
+		self
+	}
+
+	pub fn update_mode(mut self, update_mode: UpdateMode) -> Self {
+		self.config.update_mode = update_mode;
+		self
+	}
+
+	pub fn background_mode(mut self, background_mode: BackgroundMode) -> Self {
+		self.config.background_mode = background_mode;
+		self
+	}
+
+	pub fn raw_device_events(mut self, raw_device_events: bool) -> Self {
+		self.config.raw_device_events = raw_device_events;
+		self
+	}
+
+	pub fn resize_debounce_ms(mut self, resize_debounce_ms: Option<u64>) -> Self {
+		self.config.resize_debounce_ms = resize_debounce_ms;
+		self
+	}
+
+	pub fn event_recording(mut self, event_recording: EventRecording) -> Self {
+		self.config.event_recording = Some(event_recording);
+		self
+	}
+
+	pub fn canvas(mut self, canvas: impl Into<String>) -> Self {
+		self.config.canvas = Some(canvas.into());
+		self
+	}
+
+	pub fn crash_dir(mut self, crash_dir: impl Into<String>) -> Self {
+		self.config.crash_dir = Some(crash_dir.into());
+		self
+	}
+
+	pub fn logging(mut self, logging: LogConfig) -> Self {
+		self.config.logging = logging;
+		self
+	}
+
+	pub fn build(self) -> Result<AppConfig> {
+		self.config.validate()?;
+		Ok(self.config)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn builder_validation() {
+		let config = AppConfig::builder()
+			.title("Editor")
+			.size(800, 600)
+			.build()
+			.unwrap();
+		assert_eq!(config.title, "Editor");
+		assert_eq!((config.width, config.height), (800, 600));
+
+		assert!(AppConfig::builder().update_rate(None).build().is_ok());
+		assert!(matches!(
+			AppConfig::builder().size(0, 600).build(),
+			Err(ConfigError::InvalidSize(0, 600))
+		));
+		assert!(matches!(
+			AppConfig::builder().update_rate(0.0).build(),
+			Err(ConfigError::InvalidRate("update_rate", _))
+		));
+		assert!(matches!(
+			AppConfig::builder()
+				.background_mode(BackgroundMode::Throttle(f64::NAN))
+				.build(),
+			Err(ConfigError::InvalidRate("background_mode", _))
+		));
+		assert!(AppConfig::builder()
+			.update_rate(0.5)
+			.background_mode(BackgroundMode::Throttle(0.2))
+			.build()
+			.is_ok());
+	}
+
+	#[test]
+	fn partial_config_files() -> Result<()> {
+		let config = AppConfig::from_toml("title = \"Editor\"\nwidth = 800")?;
+		assert_eq!(config.title, "Editor");
+		assert_eq!(config.width, 800);
+		assert_eq!(config.height, AppConfig::default().height);
+
+		let config = AppConfig::from_ron(
+			"(title: \"Editor\", window_mode: BorderlessFullscreen(monitor: Some(1)))",
+		)?;
+		assert_eq!(config.title, "Editor");
+		assert_eq!(
+			config.window_mode,
+			WindowMode::BorderlessFullscreen { monitor: Some(1) }
+		);
+
+		Ok(())
+	}
+
+	#[test]
+	fn command_line_overrides() -> Result<()> {
+		let args = |args: &str| args.split(' ').map(str::to_string).collect::<Vec<_>>();
+		let mut config = AppConfig::from_toml("title = \"Editor\"\nwidth = 800")?;
+		config.apply_args(args(
+			"--height 600 --level 3 --fullscreen --log-level=debug --title=Tester",
+		))?;
+		assert_eq!((config.width, config.height), (800, 600));
+		assert_eq!(config.title, "Tester");
+		assert_eq!(config.logging.level, log::LevelFilter::Debug);
+		assert_eq!(
+			config.window_mode,
+			WindowMode::BorderlessFullscreen { monitor: None }
+		);
+
+		assert!(matches!(
+			config.apply_args(args("--width")),
+			Err(ConfigError::MissingArgument(flag)) if flag == "width"
+		));
+		assert!(matches!(
+			config.apply_args(args("--width wide")),
+			Err(ConfigError::InvalidArgument(_, value)) if value == "wide"
+		));
+		config.apply_args(args("--fullscreen=false"))?;
+		assert_eq!(config.window_mode, WindowMode::Windowed);
+		config.apply_args(args("--fullscreen=1"))?;
+		assert_eq!(
+			config.window_mode,
+			WindowMode::BorderlessFullscreen { monitor: None }
+		);
+		config.apply_args(args("--fullscreen=0"))?;
+		assert_eq!(config.window_mode, WindowMode::Windowed);
+		assert!(matches!(
+			config.apply_args(args("--fullscreen=yes")),
+			Err(ConfigError::InvalidArgument(flag, value)) if flag == "fullscreen" && value == "yes"
+		));
+
+		assert!(matches!(
+			config.apply_args(args("--width 0")),
+			Err(ConfigError::InvalidSize(0, 600))
+		));
+		Ok(())
+	}
+}
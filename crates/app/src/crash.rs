@@ -0,0 +1,92 @@
+use std::{
+	backtrace::Backtrace,
+	fs, io,
+	panic::{self, PanicHookInfo},
+	path::{Path, PathBuf},
+	sync::Once,
+	thread,
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+static INSTALL: Once = Once::new();
+
+// Where crash reports go when the app panics
+pub(crate) struct CrashReporter {
+	pub(crate) title: String,
+	pub(crate) crash_dir: Option<PathBuf>,
+}
+
+impl CrashReporter {
+	/// Replaces the panic hook with one that reports the crash and exits the process,
+	/// so a panic on any thread can't leave the event loop running without a worker.
+	/// Only the first app created installs its hook.
+	pub(crate) fn install(self) {
+		INSTALL.call_once(|| {
+			let default_hook = panic::take_hook();
+			panic::set_hook(Box::new(move |info| {
+				default_hook(info);
+				self.report(info);
+				#[cfg(not(target_arch = "wasm32"))]
+				std::process::exit(101);
+			}));
+		});
+	}
+
+	fn report(&self, info: &PanicHookInfo) {
+		let report = crash_report(&self.title, info, &Backtrace::force_capture());
+		log::error!("{report}");
+		log::logger().flush();
+
+		if let Some(crash_dir) = self.crash_dir.as_ref() {
+			match write_crash_dump(crash_dir, &report) {
+				Ok(path) => log::error!("Crash report written to {}", path.display()),
+				Err(error) => log::error!("Failed to write crash report: {error}"),
+			}
+			log::logger().flush();
+		}
+
+		#[cfg(feature = "crash-dialog")]
+		rfd::MessageDialog::new()
+			.set_level(rfd::MessageLevel::Error)
+			.set_title(format!("{} crashed", self.title))
+			.set_description(info.to_string())
+			.set_buttons(rfd::MessageButtons::Ok)
+			.show();
+	}
+}
+
+fn crash_report(title: &str, info: &PanicHookInfo, backtrace: &Backtrace) -> String {
+	let message = info.payload_as_str().unwrap_or("Box<dyn Any>");
+	let location = info
+		.location()
+		.map(ToString::to_string)
+		.unwrap_or_else(|| "unknown".to_string());
+	let thread = thread::current();
+	let thread = thread.name().unwrap_or("<unnamed>");
+	format!("{title} panicked on thread '{thread}' at {location}:\n{message}\n\nBacktrace:\n{backtrace}")
+}
+
+fn write_crash_dump(crash_dir: &Path, report: &str) -> io::Result<PathBuf> {
+	fs::create_dir_all(crash_dir)?;
+	let timestamp = SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.unwrap_or_default()
+		.as_millis();
+	let path = crash_dir.join(format!("crash-{timestamp}.log"));
+	fs::write(&path, report)?;
+	Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn crash_dump_is_written() {
+		let crash_dir =
+			std::env::temp_dir().join(format!("hourglass-crash-{}", std::process::id()));
+		let path = write_crash_dump(&crash_dir, "The app panicked").unwrap();
+		assert_eq!(fs::read_to_string(&path).unwrap(), "The app panicked");
+		fs::remove_dir_all(crash_dir).unwrap();
+	}
+}
@@ -0,0 +1,98 @@
+use crate::{
+	app::AppEvent,
+	input::{GamepadAxis, GamepadButton},
+};
+use gilrs::{Axis, Button, EventType, Gilrs};
+use tokio::sync::mpsc::{error::SendError, UnboundedSender};
+use winit::event::ElementState;
+
+// Polls connected gamepads from the main thread and forwards their events to the worker
+pub(crate) struct Gamepads {
+	gilrs: Option<Gilrs>,
+}
+
+impl Gamepads {
+	pub(crate) fn new() -> Self {
+		let gilrs = Gilrs::new()
+			.map_err(|error| log::warn!("Gamepad support is unavailable: {error}"))
+			.ok();
+		Self { gilrs }
+	}
+
+	pub(crate) fn poll(
+		&mut self,
+		worker_sender: &UnboundedSender<AppEvent>,
+	) -> Result<(), SendError<AppEvent>> {
+		let Some(gilrs) = self.gilrs.as_mut() else {
+			return Ok(());
+		};
+		while let Some(gilrs::Event { id, event, .. }) = gilrs.next_event() {
+			let gamepad = usize::from(id);
+			let app_event = match event {
+				EventType::Connected => Some(AppEvent::GamepadConnected { gamepad }),
+				EventType::Disconnected => Some(AppEvent::GamepadDisconnected { gamepad }),
+				EventType::ButtonPressed(button, _) => {
+					map_button(button).map(|button| AppEvent::GamepadButton {
+						gamepad,
+						button,
+						state: ElementState::Pressed,
+					})
+				}
+				EventType::ButtonReleased(button, _) => {
+					map_button(button).map(|button| AppEvent::GamepadButton {
+						gamepad,
+						button,
+						state: ElementState::Released,
+					})
+				}
+				EventType::AxisChanged(axis, value, _) => {
+					map_axis(axis).map(|axis| AppEvent::GamepadAxis {
+						gamepad,
+						axis,
+						value,
+					})
+				}
+				_ => None,
+			};
+			if let Some(app_event) = app_event {
+				worker_sender.send(app_event)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+fn map_button(button: Button) -> Option<GamepadButton> {
+	Some(match button {
+		Button::South => GamepadButton::South,
+		Button::East => GamepadButton::East,
+		Button::North => GamepadButton::North,
+		Button::West => GamepadButton::West,
+		Button::LeftTrigger => GamepadButton::LeftTrigger,
+		Button::LeftTrigger2 => GamepadButton::LeftTrigger2,
+		Button::RightTrigger => GamepadButton::RightTrigger,
+		Button::RightTrigger2 => GamepadButton::RightTrigger2,
+		Button::Select => GamepadButton::Select,
+		Button::Start => GamepadButton::Start,
+		Button::Mode => GamepadButton::Mode,
+		Button::LeftThumb => GamepadButton::LeftThumb,
+		Button::RightThumb => GamepadButton::RightThumb,
+		Button::DPadUp => GamepadButton::DPadUp,
+		Button::DPadDown => GamepadButton::DPadDown,
+		Button::DPadLeft => GamepadButton::DPadLeft,
+		Button::DPadRight => GamepadButton::DPadRight,
+		_ => return None,
+	})
+}
+
+fn map_axis(axis: Axis) -> Option<GamepadAxis> {
+	Some(match axis {
+		Axis::LeftStickX => GamepadAxis::LeftStickX,
+		Axis::LeftStickY => GamepadAxis::LeftStickY,
+		Axis::LeftZ => GamepadAxis::LeftZ,
+		Axis::RightStickX => GamepadAxis::RightStickX,
+		Axis::RightStickY => GamepadAxis::RightStickY,
+		Axis::RightZ => GamepadAxis::RightZ,
+		_ => return None,
+	})
+}
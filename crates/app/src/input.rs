@@ -0,0 +1,576 @@
+use crate::app::AppEvent;
+use ecs::world::World;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::{HashMap, HashSet},
+	fs, io,
+	path::Path,
+};
+use thiserror::Error;
+use winit::{
+	event::{ElementState, MouseButton, MouseScrollDelta, TouchPhase},
+	keyboard::KeyCode,
+};
+
+#[derive(Error, Debug)]
+pub enum InputError {
+	#[error("Failed to read input bindings file at path: {1}")]
+	ReadBindings(#[source] io::Error, String),
+
+	#[error("Failed to write input bindings file at path: {1}")]
+	WriteBindings(#[source] io::Error, String),
+
+	#[error("Failed to parse input bindings!")]
+	ParseBindings(#[source] ron::error::SpannedError),
+
+	#[error("Failed to serialize input bindings!")]
+	SerializeBindings(#[source] ron::Error),
+}
+
+type Result<T, E = InputError> = std::result::Result<T, E>;
+
+const DEFAULT_DEAD_ZONE: f32 = 0.15;
+
+/// Identifies a connected gamepad, stable until it disconnects
+pub type GamepadId = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadButton {
+	South,
+	East,
+	North,
+	West,
+	LeftTrigger,
+	LeftTrigger2,
+	RightTrigger,
+	RightTrigger2,
+	Select,
+	Start,
+	Mode,
+	LeftThumb,
+	RightThumb,
+	DPadUp,
+	DPadDown,
+	DPadLeft,
+	DPadRight,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadAxis {
+	LeftStickX,
+	LeftStickY,
+	LeftZ,
+	RightStickX,
+	RightStickY,
+	RightZ,
+}
+
+/// A physical input that can be bound to an action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Button {
+	Key(KeyCode),
+	Mouse(MouseButton),
+	Gamepad(GamepadId, GamepadButton),
+}
+
+/// A physical input that produces a value in the range -1.0 to 1.0
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AxisBinding {
+	Buttons { negative: Button, positive: Button },
+	MouseWheel,
+	Gamepad(GamepadId, GamepadAxis),
+}
+
+/// Maps named actions and axes to physical inputs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputMap {
+	actions: HashMap<String, Vec<Button>>,
+	axes: HashMap<String, Vec<AxisBinding>>,
+	#[serde(default = "default_dead_zone")]
+	dead_zone: f32,
+}
+
+fn default_dead_zone() -> f32 {
+	DEFAULT_DEAD_ZONE
+}
+
+impl Default for InputMap {
+	fn default() -> Self {
+		Self {
+			actions: HashMap::new(),
+			axes: HashMap::new(),
+			dead_zone: DEFAULT_DEAD_ZONE,
+		}
+	}
+}
+
+impl InputMap {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn dead_zone(&self) -> f32 {
+		self.dead_zone
+	}
+
+	/// Sets the range around the center of gamepad axes that is treated as zero
+	pub fn set_dead_zone(&mut self, dead_zone: f32) {
+		self.dead_zone = dead_zone.clamp(0.0, 0.99);
+	}
+
+	pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+		let path = path.as_ref();
+		let contents = fs::read_to_string(path)
+			.map_err(|error| InputError::ReadBindings(error, path.display().to_string()))?;
+		Self::from_ron(&contents)
+	}
+
+	pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+		let path = path.as_ref();
+		fs::write(path, self.to_ron()?)
+			.map_err(|error| InputError::WriteBindings(error, path.display().to_string()))
+	}
+
+	pub fn from_ron(contents: &str) -> Result<Self> {
+		ron::from_str(contents).map_err(InputError::ParseBindings)
+	}
+
+	pub fn to_ron(&self) -> Result<String> {
+		ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+			.map_err(InputError::SerializeBindings)
+	}
+
+	pub fn bind_action(&mut self, action: &str, button: Button) {
+		let buttons = self.actions.entry(action.to_string()).or_default();
+		if !buttons.contains(&button) {
+			buttons.push(button);
+		}
+	}
+
+	pub fn unbind_action(&mut self, action: &str, button: Button) {
+		if let Some(buttons) = self.actions.get_mut(action) {
+			buttons.retain(|bound| *bound != button);
+		}
+	}
+
+	/// Replaces every binding of an action with a single button
+	pub fn rebind_action(&mut self, action: &str, button: Button) {
+		self.actions.insert(action.to_string(), vec![button]);
+	}
+
+	pub fn bind_axis(&mut self, axis: &str, binding: AxisBinding) {
+		let bindings = self.axes.entry(axis.to_string()).or_default();
+		if !bindings.contains(&binding) {
+			bindings.push(binding);
+		}
+	}
+
+	/// Replaces every binding of an axis with a single binding
+	pub fn rebind_axis(&mut self, axis: &str, binding: AxisBinding) {
+		self.axes.insert(axis.to_string(), vec![binding]);
+	}
+
+	pub fn action_bindings(&self, action: &str) -> &[Button] {
+		self.actions.get(action).map_or(&[], Vec::as_slice)
+	}
+
+	pub fn axis_bindings(&self, axis: &str) -> &[AxisBinding] {
+		self.axes.get(axis).map_or(&[], Vec::as_slice)
+	}
+}
+
+/// Tracks the state of physical inputs and resolves actions and axes through an [`InputMap`].
+///
+/// The worker feeds it every [`AppEvent`] and ends the frame after each update,
+/// so "just pressed" and "just released" are true for exactly one update.
+#[derive(Debug, Default, Clone)]
+pub struct Input {
+	map: InputMap,
+	pressed: HashSet<Button>,
+	just_pressed: HashSet<Button>,
+	just_released: HashSet<Button>,
+	wheel_delta: f32,
+	cursor_position: (f64, f64),
+	logical_cursor_position: (f64, f64),
+	mouse_motion: (f64, f64),
+	gamepad_axes: HashMap<(GamepadId, GamepadAxis), f32>,
+	touches: HashMap<u64, (f64, f64)>,
+}
+
+impl Input {
+	pub fn new(map: InputMap) -> Self {
+		Self {
+			map,
+			..Default::default()
+		}
+	}
+
+	pub fn map(&self) -> &InputMap {
+		&self.map
+	}
+
+	pub fn map_mut(&mut self) -> &mut InputMap {
+		&mut self.map
+	}
+
+	pub fn handle_event(&mut self, event: &AppEvent) {
+		match event {
+			AppEvent::KeyboardInput {
+				keycode: Some(keycode),
+				state,
+				..
+			} => self.set_button(Button::Key(*keycode), *state),
+			AppEvent::MouseButton { button, state, .. } => {
+				self.set_button(Button::Mouse(*button), *state)
+			}
+			AppEvent::MouseWheel { delta, .. } => {
+				self.wheel_delta += match delta {
+					MouseScrollDelta::LineDelta(_, y) => *y,
+					MouseScrollDelta::PixelDelta(position) => position.y as f32,
+				}
+			}
+			AppEvent::CursorMoved {
+				x,
+				y,
+				logical_x,
+				logical_y,
+				..
+			} => {
+				self.cursor_position = (*x, *y);
+				self.logical_cursor_position = (*logical_x, *logical_y);
+			}
+			AppEvent::MouseMotion { delta_x, delta_y } => {
+				self.mouse_motion.0 += delta_x;
+				self.mouse_motion.1 += delta_y;
+			}
+			AppEvent::GamepadButton {
+				gamepad,
+				button,
+				state,
+			} => self.set_button(Button::Gamepad(*gamepad, *button), *state),
+			AppEvent::GamepadAxis {
+				gamepad,
+				axis,
+				value,
+			} => {
+				self.gamepad_axes.insert((*gamepad, *axis), *value);
+			}
+			// Keys released while unfocused are never reported, so drop them to avoid stuck keys
+			AppEvent::FocusChanged { focused: false, .. } => {
+				self.pressed
+					.retain(|button| matches!(button, Button::Gamepad(..)));
+			}
+			AppEvent::Touch {
+				id, phase, x, y, ..
+			} => match phase {
+				TouchPhase::Started | TouchPhase::Moved => {
+					self.touches.insert(*id, (*x, *y));
+				}
+				TouchPhase::Ended | TouchPhase::Cancelled => {
+					self.touches.remove(id);
+				}
+			},
+			// Touches that end while suspended are never reported
+			AppEvent::Suspended => self.touches.clear(),
+			AppEvent::GamepadDisconnected { gamepad } => {
+				self.pressed
+					.retain(|button| !matches!(button, Button::Gamepad(id, _) if id == gamepad));
+				self.gamepad_axes.retain(|(id, _), _| id != gamepad);
+			}
+			_ => {}
+		}
+	}
+
+	pub fn end_frame(&mut self) {
+		self.just_pressed.clear();
+		self.just_released.clear();
+		self.wheel_delta = 0.0;
+		self.mouse_motion = (0.0, 0.0);
+	}
+
+	fn set_button(&mut self, button: Button, state: ElementState) {
+		match state {
+			ElementState::Pressed => {
+				// Ignore key repeats
+				if self.pressed.insert(button) {
+					self.just_pressed.insert(button);
+				}
+			}
+			ElementState::Released => {
+				if self.pressed.remove(&button) {
+					self.just_released.insert(button);
+				}
+			}
+		}
+	}
+
+	pub fn is_button_pressed(&self, button: Button) -> bool {
+		self.pressed.contains(&button)
+	}
+
+	pub fn is_action_pressed(&self, action: &str) -> bool {
+		self.any_bound(action, &self.pressed)
+	}
+
+	pub fn is_action_just_pressed(&self, action: &str) -> bool {
+		self.any_bound(action, &self.just_pressed)
+	}
+
+	pub fn is_action_just_released(&self, action: &str) -> bool {
+		self.any_bound(action, &self.just_released)
+	}
+
+	fn any_bound(&self, action: &str, buttons: &HashSet<Button>) -> bool {
+		self.map
+			.action_bindings(action)
+			.iter()
+			.any(|button| buttons.contains(button))
+	}
+
+	/// The sum of every binding of an axis, clamped to -1.0 to 1.0
+	pub fn axis(&self, axis: &str) -> f32 {
+		self.map
+			.axis_bindings(axis)
+			.iter()
+			.map(|binding| match binding {
+				AxisBinding::Buttons { negative, positive } => {
+					let value = |button| f32::from(u8::from(self.pressed.contains(button)));
+					value(positive) - value(negative)
+				}
+				AxisBinding::MouseWheel => self.wheel_delta,
+				AxisBinding::Gamepad(gamepad, axis) => apply_dead_zone(
+					self.gamepad_axes
+						.get(&(*gamepad, *axis))
+						.copied()
+						.unwrap_or_default(),
+					self.map.dead_zone,
+				),
+			})
+			.sum::<f32>()
+			.clamp(-1.0, 1.0)
+	}
+
+	/// The cursor position in physical pixels
+	pub fn cursor_position(&self) -> (f64, f64) {
+		self.cursor_position
+	}
+
+	/// The cursor position divided by the window's scale factor
+	pub fn logical_cursor_position(&self) -> (f64, f64) {
+		self.logical_cursor_position
+	}
+
+	/// Raw mouse movement accumulated since the last frame
+	pub fn mouse_motion(&self) -> (f64, f64) {
+		self.mouse_motion
+	}
+
+	/// The positions of the fingers currently on the touchscreen, by touch id
+	pub fn touches(&self) -> impl Iterator<Item = (u64, (f64, f64))> + '_ {
+		self.touches.iter().map(|(id, position)| (*id, *position))
+	}
+
+	pub fn touch_position(&self, id: u64) -> Option<(f64, f64)> {
+		self.touches.get(&id).copied()
+	}
+
+	/// Copies the current input state into the world's resources for systems to read
+	pub fn sync_to_world(&self, world: &World) {
+		world.resources().borrow_mut().insert(self.clone());
+	}
+}
+
+// Rescales the value so the axis starts at zero just outside the dead zone
+fn apply_dead_zone(value: f32, dead_zone: f32) -> f32 {
+	if value.abs() < dead_zone {
+		return 0.0;
+	}
+	value.signum() * (value.abs() - dead_zone) / (1.0 - dead_zone)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::WindowId;
+
+	fn key(keycode: KeyCode, state: ElementState) -> AppEvent {
+		AppEvent::KeyboardInput {
+			window: WindowId::PRIMARY,
+			keycode: Some(keycode),
+			state,
+		}
+	}
+
+	fn input() -> Input {
+		let mut map = InputMap::new();
+		map.bind_action("Jump", Button::Key(KeyCode::Space));
+		map.bind_action("Jump", Button::Mouse(MouseButton::Left));
+		map.bind_axis(
+			"MoveX",
+			AxisBinding::Buttons {
+				negative: Button::Key(KeyCode::KeyA),
+				positive: Button::Key(KeyCode::KeyD),
+			},
+		);
+		Input::new(map)
+	}
+
+	#[test]
+	fn action_state() {
+		let mut input = input();
+
+		input.handle_event(&key(KeyCode::Space, ElementState::Pressed));
+		assert!(input.is_action_pressed("Jump"));
+		assert!(input.is_action_just_pressed("Jump"));
+
+		input.end_frame();
+		input.handle_event(&key(KeyCode::Space, ElementState::Pressed));
+		assert!(input.is_action_pressed("Jump"));
+		assert!(!input.is_action_just_pressed("Jump"));
+
+		input.handle_event(&key(KeyCode::Space, ElementState::Released));
+		assert!(!input.is_action_pressed("Jump"));
+		assert!(input.is_action_just_released("Jump"));
+
+		input.end_frame();
+		assert!(!input.is_action_just_released("Jump"));
+	}
+
+	#[test]
+	fn axis_value() {
+		let mut input = input();
+		assert_eq!(input.axis("MoveX"), 0.0);
+
+		input.handle_event(&key(KeyCode::KeyD, ElementState::Pressed));
+		assert_eq!(input.axis("MoveX"), 1.0);
+
+		input.handle_event(&key(KeyCode::KeyA, ElementState::Pressed));
+		assert_eq!(input.axis("MoveX"), 0.0);
+
+		input.handle_event(&key(KeyCode::KeyD, ElementState::Released));
+		assert_eq!(input.axis("MoveX"), -1.0);
+	}
+
+	#[test]
+	fn mouse_motion_accumulates() {
+		let mut input = input();
+		let motion = |delta_x, delta_y| AppEvent::MouseMotion { delta_x, delta_y };
+
+		input.handle_event(&motion(2.0, -1.0));
+		input.handle_event(&motion(3.0, -1.0));
+		assert_eq!(input.mouse_motion(), (5.0, -2.0));
+
+		input.end_frame();
+		assert_eq!(input.mouse_motion(), (0.0, 0.0));
+	}
+
+	#[test]
+	fn touches_are_tracked() {
+		let mut input = input();
+		let touch = |id, phase, x| AppEvent::Touch {
+			window: WindowId::PRIMARY,
+			id,
+			phase,
+			x,
+			y: 0.0,
+			force: None,
+		};
+
+		input.handle_event(&touch(1, TouchPhase::Started, 10.0));
+		input.handle_event(&touch(2, TouchPhase::Started, 20.0));
+		input.handle_event(&touch(1, TouchPhase::Moved, 15.0));
+		assert_eq!(input.touch_position(1), Some((15.0, 0.0)));
+		assert_eq!(input.touches().count(), 2);
+
+		input.handle_event(&touch(2, TouchPhase::Cancelled, 20.0));
+		assert_eq!(input.touch_position(2), None);
+
+		input.handle_event(&AppEvent::Suspended);
+		assert_eq!(input.touches().count(), 0);
+	}
+
+	#[test]
+	fn gamepad_axis_dead_zone() {
+		let mut input = input();
+		input
+			.map_mut()
+			.bind_axis("MoveX", AxisBinding::Gamepad(0, GamepadAxis::LeftStickX));
+		input.map_mut().set_dead_zone(0.25);
+
+		let axis = |value| AppEvent::GamepadAxis {
+			gamepad: 0,
+			axis: GamepadAxis::LeftStickX,
+			value,
+		};
+
+		input.handle_event(&axis(0.1));
+		assert_eq!(input.axis("MoveX"), 0.0);
+
+		input.handle_event(&axis(-0.625));
+		assert_eq!(input.axis("MoveX"), -0.5);
+
+		input.handle_event(&axis(1.0));
+		assert_eq!(input.axis("MoveX"), 1.0);
+	}
+
+	#[test]
+	fn gamepads_are_tracked_separately() {
+		let mut input = input();
+		input
+			.map_mut()
+			.bind_action("Jump", Button::Gamepad(1, GamepadButton::South));
+		input
+			.map_mut()
+			.rebind_axis("MoveX", AxisBinding::Gamepad(1, GamepadAxis::LeftStickX));
+
+		let button = |gamepad| AppEvent::GamepadButton {
+			gamepad,
+			button: GamepadButton::South,
+			state: ElementState::Pressed,
+		};
+		let axis = |gamepad, value| AppEvent::GamepadAxis {
+			gamepad,
+			axis: GamepadAxis::LeftStickX,
+			value,
+		};
+
+		input.handle_event(&button(0));
+		assert!(!input.is_action_pressed("Jump"));
+		input.handle_event(&button(1));
+		assert!(input.is_action_pressed("Jump"));
+
+		input.handle_event(&axis(1, 1.0));
+		input.handle_event(&axis(0, -1.0));
+		assert_eq!(input.axis("MoveX"), 1.0);
+
+		input.handle_event(&AppEvent::GamepadDisconnected { gamepad: 0 });
+		assert!(input.is_button_pressed(Button::Gamepad(1, GamepadButton::South)));
+		assert!(!input.is_button_pressed(Button::Gamepad(0, GamepadButton::South)));
+		assert_eq!(input.axis("MoveX"), 1.0);
+
+		input.handle_event(&AppEvent::GamepadDisconnected { gamepad: 1 });
+		assert!(!input.is_action_pressed("Jump"));
+		assert_eq!(input.axis("MoveX"), 0.0);
+	}
+
+	#[test]
+	fn rebinding() {
+		let mut input = input();
+		input
+			.map_mut()
+			.rebind_action("Jump", Button::Key(KeyCode::KeyW));
+
+		input.handle_event(&key(KeyCode::Space, ElementState::Pressed));
+		assert!(!input.is_action_pressed("Jump"));
+
+		input.handle_event(&key(KeyCode::KeyW, ElementState::Pressed));
+		assert!(input.is_action_pressed("Jump"));
+	}
+
+	#[test]
+	fn serialization() -> Result<()> {
+		let map = input().map().clone();
+		assert_eq!(InputMap::from_ron(&map.to_ron()?)?, map);
+		Ok(())
+	}
+}
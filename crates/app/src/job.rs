@@ -0,0 +1,219 @@
+use crate::{
+	app::{AppEvent, TaskResult},
+	platform::{self, Task},
+};
+use std::{
+	any::Any,
+	future::Future,
+	sync::{
+		atomic::{AtomicU32, AtomicU8, Ordering},
+		Arc,
+	},
+};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Identifies a job spawned with `Context::spawn_job`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct JobId(u64);
+
+/// The value a job finished with, downcast to the job's output type to read it
+pub type JobOutput = Arc<dyn Any + Send + Sync>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobStatus {
+	Running,
+	Completed,
+	Failed,
+	Cancelled,
+}
+
+impl JobStatus {
+	fn from_u8(value: u8) -> Self {
+		match value {
+			1 => Self::Completed,
+			2 => Self::Failed,
+			3 => Self::Cancelled,
+			_ => Self::Running,
+		}
+	}
+}
+
+#[derive(Default)]
+struct JobState {
+	status: AtomicU8,
+	progress: AtomicU32,
+}
+
+impl JobState {
+	// Moves a running job to the status, returning false when it had already finished
+	// or was cancelled, so a job only ever finishes once
+	fn finish(&self, status: JobStatus) -> bool {
+		self.status
+			.compare_exchange(
+				JobStatus::Running as u8,
+				status as u8,
+				Ordering::AcqRel,
+				Ordering::Acquire,
+			)
+			.is_ok()
+	}
+}
+
+/// Lets a running job report how far along it is
+#[derive(Clone)]
+pub struct JobProgress(Arc<JobState>);
+
+impl JobProgress {
+	/// Sets the progress, clamped to the range 0.0 to 1.0
+	pub fn set(&self, progress: f32) {
+		self.0
+			.progress
+			.store(progress.clamp(0.0, 1.0).to_bits(), Ordering::Release);
+	}
+}
+
+pub struct JobHandle {
+	id: JobId,
+	state: Arc<JobState>,
+	task: Task,
+}
+
+impl JobHandle {
+	pub fn id(&self) -> JobId {
+		self.id
+	}
+
+	pub fn status(&self) -> JobStatus {
+		JobStatus::from_u8(self.state.status.load(Ordering::Acquire))
+	}
+
+	pub fn progress(&self) -> f32 {
+		f32::from_bits(self.state.progress.load(Ordering::Acquire))
+	}
+
+	pub fn is_finished(&self) -> bool {
+		self.status() != JobStatus::Running
+	}
+
+	/// Stops the job at its next await point. Cancelled jobs don't send `AppEvent::JobCompleted`.
+	pub fn cancel(&self) {
+		if self.state.finish(JobStatus::Cancelled) {
+			self.task.abort();
+		}
+	}
+}
+
+// Spawns jobs onto the async runtime and reports their results to the worker
+pub(crate) struct Jobs {
+	next_id: u64,
+	worker_sender: UnboundedSender<AppEvent>,
+}
+
+impl Jobs {
+	pub(crate) fn new(worker_sender: UnboundedSender<AppEvent>) -> Self {
+		Self {
+			next_id: 0,
+			worker_sender,
+		}
+	}
+
+	pub(crate) fn spawn<T, F>(&mut self, job: impl FnOnce(JobProgress) -> F) -> JobHandle
+	where
+		T: Any + Send + Sync,
+		F: Future<Output = TaskResult<T>> + Send + 'static,
+	{
+		let id = JobId(self.next_id);
+		self.next_id += 1;
+
+		let state = Arc::new(JobState::default());
+		let future = job(JobProgress(state.clone()));
+		let worker_sender = self.worker_sender.clone();
+		let job_state = state.clone();
+		let task = platform::spawn(async move {
+			let result = future.await;
+			let status = match result {
+				Ok(_) => JobStatus::Completed,
+				Err(_) => JobStatus::Failed,
+			};
+			// A job cancelled while finishing doesn't report its result
+			if !job_state.finish(status) {
+				return;
+			}
+			let result = match result {
+				Ok(output) => {
+					job_state
+						.progress
+						.store(1.0_f32.to_bits(), Ordering::Release);
+					Ok(Arc::new(output) as JobOutput)
+				}
+				Err(error) => Err(error.to_string()),
+			};
+			let _ = worker_sender.send(AppEvent::JobCompleted { job: id, result });
+		});
+
+		JobHandle { id, state, task }
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tokio::sync::mpsc;
+
+	#[tokio::test]
+	async fn job_completion() {
+		let (worker_sender, mut worker_receiver) = mpsc::unbounded_channel();
+		let mut jobs = Jobs::new(worker_sender);
+
+		let handle = jobs.spawn(|progress| async move {
+			progress.set(0.5);
+			Ok(42_u32)
+		});
+		let Some(AppEvent::JobCompleted { job, result }) = worker_receiver.recv().await else {
+			panic!("expected a job completion");
+		};
+		assert_eq!(job, handle.id());
+		assert_eq!(result.unwrap().downcast_ref::<u32>(), Some(&42));
+		assert_eq!(handle.status(), JobStatus::Completed);
+		assert_eq!(handle.progress(), 1.0);
+
+		let handle = jobs.spawn(|_| async { Err::<(), _>("missing asset".into()) });
+		let Some(AppEvent::JobCompleted { job, result }) = worker_receiver.recv().await else {
+			panic!("expected a job completion");
+		};
+		assert_eq!(job, handle.id());
+		assert_eq!(result.err().as_deref(), Some("missing asset"));
+		assert_eq!(handle.status(), JobStatus::Failed);
+	}
+
+	#[tokio::test]
+	async fn cancelled_jobs_stay_cancelled() {
+		let (worker_sender, mut worker_receiver) = mpsc::unbounded_channel();
+		let mut jobs = Jobs::new(worker_sender);
+
+		let (finish_sender, finish_receiver) = tokio::sync::oneshot::channel::<()>();
+		let handle = jobs.spawn(|_| async move {
+			let _ = finish_receiver.await;
+			Ok(())
+		});
+		handle.cancel();
+		assert_eq!(handle.status(), JobStatus::Cancelled);
+		let _ = finish_sender.send(());
+		tokio::task::yield_now().await;
+		assert_eq!(handle.status(), JobStatus::Cancelled);
+
+		// A finished job can't be cancelled
+		let finished = jobs.spawn(|_| async { Ok(()) });
+		let Some(AppEvent::JobCompleted { job, .. }) = worker_receiver.recv().await else {
+			panic!("expected a job completion");
+		};
+		assert_eq!(job, finished.id());
+		finished.cancel();
+		assert_eq!(finished.status(), JobStatus::Completed);
+
+		// Only the finished job reported back
+		drop(jobs);
+		drop(handle);
+		assert!(worker_receiver.recv().await.is_none());
+	}
+}
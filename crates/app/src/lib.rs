@@ -1,13 +1,74 @@
 #![forbid(unsafe_code)]
 
 mod app;
+mod config;
+mod crash;
+#[cfg(feature = "gamepad")]
+mod gamepad;
+mod input;
+mod job;
+// States can't hold the context across awaits on the web, where it isn't `Send`
+#[cfg(not(target_arch = "wasm32"))]
+mod loading;
+mod logging;
+mod metrics;
+mod monitor;
+mod platform;
+mod plugin;
+mod pool;
+#[cfg(all(feature = "remote", not(target_arch = "wasm32")))]
+mod remote;
+mod replay;
+mod request;
+mod ring;
+mod router;
 mod state;
+mod time;
+mod timer;
+#[cfg(feature = "tray")]
+mod tray;
+mod window;
+mod world;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub use self::loading::{LoadedAssets, LoadingProgress, LoadingState};
+#[cfg(all(feature = "remote", not(target_arch = "wasm32")))]
+pub use self::remote::{
+	DebugClient, DebugMessage, RemoteComponent, RemoteEntity, RemoteEntityId, RemoteTiming,
+	DEBUG_EVENTS, DEBUG_REQUESTS,
+};
+#[cfg(feature = "tray")]
+pub use self::tray::{TrayConfig, TrayMenuItem};
 pub use self::{
-	app::{App, AppConfig, AppEvent, Context, WorkerRequest},
-	state::{State, StateResult, Transition},
+	app::{
+		App, AppEvent, Context, ErrorHandler, ErrorPolicy, RawDeviceEvent, TextComposition,
+		WorkerRequest,
+	},
+	config::{AppConfig, AppConfigBuilder, BackgroundMode, ConfigError, PresentMode, UpdateMode},
+	input::{
+		AxisBinding, Button, GamepadAxis, GamepadButton, GamepadId, Input, InputError, InputMap,
+	},
+	job::{JobHandle, JobId, JobOutput, JobProgress, JobStatus},
+	logging::{LogBuffer, LogConfig, LogRecord},
+	metrics::{AppMetrics, BusTraffic, SystemTiming, FRAME_TIME_BUCKETS_MS},
+	monitor::{MonitorInfo, VideoModeInfo, WindowMode},
+	plugin::{AppBuilder, Plugin, Resources, System},
+	pool::WorkerPool,
+	replay::EventRecording,
+	request::{CustomRequest, RequestContext},
+	ring::RingBuffer,
+	router::EventHarness,
+	state::{
+		Frame, Payload, State, StateChange, StateChangeKind, StateError, StateHistory,
+		StateObserver, StateResult, Timed, Transition,
+	},
+	time::FrameTiming,
+	timer::TimerId,
+	window::{CursorMode, WindowConfig, WindowId, WindowLevel, WindowRequest},
+	world::{SharedWorld, WorldClosed},
 };
 pub use async_trait;
+pub use ecs::math;
 pub use log;
 pub use tokio;
 pub use winit;
@@ -0,0 +1,345 @@
+use crate::{
+	app::{AppEvent, Context, TaskResult},
+	job::{JobHandle, JobId, JobOutput, JobProgress},
+	state::{Frame, State, StateResult, Transition},
+};
+use async_trait::async_trait;
+use std::{any::Any, collections::HashMap, future::Future, sync::Arc};
+
+type SpawnLoad = Box<dyn Fn(&mut Context, String) -> JobHandle + Send>;
+type OnFailure = Box<dyn FnOnce(String) -> Transition<Context, AppEvent> + Send>;
+
+/// How far a `LoadingState` has come, kept in `Context::resources`
+/// so its screen state can draw a progress bar
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct LoadingProgress {
+	pub loaded: usize,
+	pub total: usize,
+	/// From 0.0 to 1.0, including the progress reported by assets still loading
+	pub fraction: f32,
+}
+
+/// Assets loaded by a `LoadingState`, kept in `Context::resources` by path
+#[derive(Default, Clone)]
+pub struct LoadedAssets {
+	assets: HashMap<String, JobOutput>,
+}
+
+impl LoadedAssets {
+	/// The asset if it has loaded and has the given type
+	pub fn get<A: Any + Send + Sync>(&self, path: &str) -> Option<Arc<A>> {
+		self.assets.get(path)?.clone().downcast().ok()
+	}
+
+	pub fn contains(&self, path: &str) -> bool {
+		self.assets.contains_key(path)
+	}
+
+	/// Adds an asset loaded some other way, such as by an editor import, replacing the previous one
+	pub fn insert(&mut self, path: impl Into<String>, asset: JobOutput) {
+		self.assets.insert(path.into(), asset);
+	}
+
+	pub fn remove(&mut self, path: &str) -> Option<JobOutput> {
+		self.assets.remove(path)
+	}
+}
+
+/// Loads each asset with a job when it starts, then switches to the target state.
+/// An optional screen state is drawn meanwhile and can read `LoadingProgress`
+/// from `Context::resources`. Loaded assets are added to `LoadedAssets`.
+///
+/// A failed asset is returned as an error from the next update, or passed to the
+/// handler set with `on_failure`. Either way the target state is never switched to.
+pub struct LoadingState {
+	assets: Vec<String>,
+	spawn_load: SpawnLoad,
+	target: Option<Box<dyn State<Context, AppEvent>>>,
+	screen: Option<Box<dyn State<Context, AppEvent>>>,
+	on_failure: Option<OnFailure>,
+	jobs: HashMap<JobId, (String, JobHandle)>,
+	failure: Failure,
+}
+
+enum Failure {
+	None,
+	Failed(String),
+	// The failure was returned from an update, so later updates stay on the loading screen
+	Reported,
+}
+
+impl LoadingState {
+	pub fn new<A, F>(
+		assets: impl IntoIterator<Item = impl Into<String>>,
+		load: impl Fn(String, JobProgress) -> F + Send + Sync + 'static,
+		target: impl State<Context, AppEvent>,
+	) -> Self
+	where
+		A: Any + Send + Sync,
+		F: Future<Output = TaskResult<A>> + Send + 'static,
+	{
+		let load = Arc::new(load);
+		Self {
+			assets: assets.into_iter().map(Into::into).collect(),
+			spawn_load: Box::new(move |context, path| {
+				let load = load.clone();
+				context.spawn_job(move |progress| load(path, progress))
+			}),
+			target: Some(Box::new(target)),
+			screen: None,
+			on_failure: None,
+			jobs: HashMap::new(),
+			failure: Failure::None,
+		}
+	}
+
+	/// Draws the state while loading, such as a splash image or a progress bar
+	pub fn with_screen(mut self, screen: impl State<Context, AppEvent>) -> Self {
+		self.screen = Some(Box::new(screen));
+		self
+	}
+
+	/// Handles the first asset that fails to load with a transition, such as to an error
+	/// screen, instead of returning the failure as an error
+	pub fn on_failure(
+		mut self,
+		on_failure: impl FnOnce(String) -> Transition<Context, AppEvent> + Send + 'static,
+	) -> Self {
+		self.on_failure = Some(Box::new(on_failure));
+		self
+	}
+
+	pub fn has_failed(&self) -> bool {
+		!matches!(self.failure, Failure::None)
+	}
+
+	pub fn progress(&self) -> LoadingProgress {
+		let total = self.assets.len();
+		let loading = self
+			.jobs
+			.values()
+			.map(|(_, handle)| handle.progress())
+			.sum::<f32>();
+		let loaded = total - self.jobs.len();
+		LoadingProgress {
+			loaded,
+			total,
+			fraction: if total == 0 {
+				1.0
+			} else {
+				(loaded as f32 + loading) / total as f32
+			},
+		}
+	}
+}
+
+#[async_trait]
+impl State<Context, AppEvent> for LoadingState {
+	fn label(&self) -> String {
+		"Loading".to_string()
+	}
+
+	async fn on_start(&mut self, context: &mut Context) -> StateResult<()> {
+		context.resources.insert(LoadedAssets::default());
+		for path in &self.assets {
+			let handle = (self.spawn_load)(context, path.clone());
+			self.jobs.insert(handle.id(), (path.clone(), handle));
+		}
+		context.resources.insert(self.progress());
+		match self.screen.as_mut() {
+			Some(screen) => screen.on_start(context).await,
+			None => Ok(()),
+		}
+	}
+
+	async fn on_stop(&mut self, context: &mut Context) -> StateResult<()> {
+		for (_, handle) in self.jobs.values() {
+			handle.cancel();
+		}
+		context.resources.remove::<LoadingProgress>();
+		match self.screen.as_mut() {
+			Some(screen) => screen.on_stop(context).await,
+			None => Ok(()),
+		}
+	}
+
+	async fn on_suspend_app(&mut self, context: &mut Context) -> StateResult<()> {
+		match self.screen.as_mut() {
+			Some(screen) => screen.on_suspend_app(context).await,
+			None => Ok(()),
+		}
+	}
+
+	async fn on_resume_app(&mut self, context: &mut Context) -> StateResult<()> {
+		match self.screen.as_mut() {
+			Some(screen) => screen.on_resume_app(context).await,
+			None => Ok(()),
+		}
+	}
+
+	async fn on_render(&mut self, context: &mut Context, frame: Frame) -> StateResult<()> {
+		match self.screen.as_mut() {
+			Some(screen) => screen.on_render(context, frame).await,
+			None => Ok(()),
+		}
+	}
+
+	async fn update(
+		&mut self,
+		context: &mut Context,
+	) -> StateResult<Transition<Context, AppEvent>> {
+		context.resources.insert(self.progress());
+		if let Failure::Failed(failure) = &mut self.failure {
+			let failure = std::mem::take(failure);
+			self.failure = Failure::Reported;
+			return match self.on_failure.take() {
+				Some(on_failure) => Ok(on_failure(failure)),
+				None => Err(failure.into()),
+			};
+		}
+		if let Some(screen) = self.screen.as_mut() {
+			let transition = screen.update(context).await?;
+			if !matches!(transition, Transition::None) {
+				return Ok(transition);
+			}
+		}
+		if self.has_failed() {
+			return Ok(Transition::None);
+		}
+		if !self.jobs.is_empty() {
+			// Keeps the progress moving in `UpdateMode::Reactive`
+			context.request_update();
+			return Ok(Transition::None);
+		}
+		Ok(self
+			.target
+			.take()
+			.map_or(Transition::None, Transition::Switch))
+	}
+
+	async fn on_event(
+		&mut self,
+		context: &mut Context,
+		event: &mut AppEvent,
+	) -> StateResult<Transition<Context, AppEvent>> {
+		if let AppEvent::JobCompleted { job, result } = event {
+			if let Some((path, _)) = self.jobs.remove(job) {
+				match result {
+					Ok(asset) => {
+						if let Some(loaded) = context.resources.get_mut::<LoadedAssets>() {
+							loaded.assets.insert(path, asset.clone());
+						}
+					}
+					Err(error) if !self.has_failed() => {
+						self.failure = Failure::Failed(format!("Failed to load {path}: {error}"));
+					}
+					Err(_) => {}
+				}
+			}
+		}
+		match self.screen.as_mut() {
+			Some(screen) => screen.on_event(context, event).await,
+			None => Ok(Transition::None),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::state::StateMachine;
+
+	#[test]
+	fn loaded_assets_downcast_by_type() {
+		let mut loaded = LoadedAssets::default();
+		loaded
+			.assets
+			.insert("level.ron".to_string(), Arc::new(vec![1_u8, 2, 3]));
+
+		assert_eq!(
+			loaded.get::<Vec<u8>>("level.ron").as_deref(),
+			Some(&vec![1, 2, 3])
+		);
+		assert!(loaded.get::<String>("level.ron").is_none());
+		assert!(!loaded.contains("missing.ron"));
+
+		loaded.insert("level.ron", Arc::new("reimported".to_string()));
+		assert!(loaded.get::<Vec<u8>>("level.ron").is_none());
+		assert!(loaded.remove("level.ron").is_some());
+		assert!(!loaded.contains("level.ron"));
+	}
+
+	struct Named(&'static str);
+
+	#[async_trait]
+	impl State<Context, AppEvent> for Named {
+		fn label(&self) -> String {
+			self.0.to_string()
+		}
+	}
+
+	fn loading(assets: &[&str]) -> LoadingState {
+		let load = |path: String, _progress| async move {
+			match path.strip_suffix(".missing") {
+				Some(_) => Err(format!("{path} not found").into()),
+				None => Ok(path.len()),
+			}
+		};
+		LoadingState::new(assets.to_vec(), load, Named("Level"))
+	}
+
+	// Starts the loading state, finishing its jobs one at a time and updating after each
+	async fn run(loading: LoadingState) -> (StateMachine<Context, AppEvent>, Context, TaskResult) {
+		let (mut context, _requests, mut events) = Context::recorded();
+		let total = loading.assets.len();
+		let mut state_machine = StateMachine::new(loading);
+		state_machine.start(&mut context).await.unwrap();
+		let progress = context.resources.get::<LoadingProgress>().copied();
+		assert_eq!(progress.map(|progress| progress.fraction), Some(0.0));
+		let mut result = Ok(());
+		for loaded in 1..=total {
+			let mut event = events.recv().await.unwrap();
+			state_machine
+				.on_event(&mut context, &mut event)
+				.await
+				.unwrap();
+			result = result.and(state_machine.update(&mut context).await);
+			let progress = context.resources.get::<LoadingProgress>().copied();
+			if let Some(progress) = progress {
+				assert_eq!((progress.loaded, progress.total), (loaded, total));
+			}
+		}
+		(state_machine, context, result)
+	}
+
+	#[tokio::test]
+	async fn loading_switches_to_the_target() {
+		let (state_machine, context, result) = run(loading(&["level.ron", "player.png"])).await;
+		assert!(result.is_ok());
+		assert_eq!(state_machine.stack_labels(), ["Level"]);
+		let loaded = context.resources.get::<LoadedAssets>().unwrap();
+		assert_eq!(loaded.get::<usize>("player.png").as_deref(), Some(&10));
+		assert!(context.resources.get::<LoadingProgress>().is_none());
+	}
+
+	#[tokio::test]
+	async fn failed_loads_stay_failed() {
+		let (mut state_machine, mut context, result) =
+			run(loading(&["level.ron", "boss.missing"])).await;
+		assert_eq!(
+			result.unwrap_err().to_string(),
+			"Failed to load boss.missing: boss.missing not found"
+		);
+		// Later updates neither repeat the error nor switch to the target
+		state_machine.update(&mut context).await.unwrap();
+		assert_eq!(state_machine.stack_labels(), ["Loading"]);
+
+		let loading = loading(&["boss.missing"]).on_failure(|failure| {
+			assert!(failure.starts_with("Failed to load boss.missing"));
+			Transition::Switch(Box::new(Named("Error")))
+		});
+		let (state_machine, _, result) = run(loading).await;
+		assert!(result.is_ok());
+		assert_eq!(state_machine.stack_labels(), ["Error"]);
+	}
+}
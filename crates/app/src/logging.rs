@@ -0,0 +1,305 @@
+use crate::{platform::Instant, ring::RingBuffer};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::HashMap,
+	fs::{self, File, OpenOptions},
+	io::{self, Write},
+	path::PathBuf,
+	sync::Mutex,
+	time::Duration,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+	/// Level for modules without their own entry in `modules`
+	pub level: LevelFilter,
+	/// Levels by module path, such as `wgpu_core = "Warn"`. The longest matching path wins.
+	pub modules: HashMap<String, LevelFilter>,
+	/// File the log is also written to, rotated once it grows past `max_file_size` bytes
+	pub file: Option<String>,
+	pub max_file_size: u64,
+	/// How many rotated files are kept, with `<file>.1` being the newest
+	pub max_files: usize,
+	/// How many recent records `Context::logs` keeps in memory
+	pub buffer_capacity: usize,
+}
+
+impl Default for LogConfig {
+	fn default() -> Self {
+		Self {
+			level: LevelFilter::Info,
+			modules: HashMap::new(),
+			file: None,
+			max_file_size: 5 * 1024 * 1024,
+			max_files: 3,
+			buffer_capacity: 1000,
+		}
+	}
+}
+
+impl LogConfig {
+	/// Applies a `RUST_LOG` style filter such as `info,wgpu_core=warn` on top of the config
+	pub fn apply_filter(&mut self, filter: &str) {
+		for directive in filter.split(',').map(str::trim) {
+			match directive.split_once('=') {
+				Some((module, level)) => {
+					if let Ok(level) = level.parse() {
+						self.modules.insert(module.to_string(), level);
+					}
+				}
+				None => {
+					if let Ok(level) = directive.parse() {
+						self.level = level;
+					}
+				}
+			}
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogRecord {
+	pub level: Level,
+	pub target: String,
+	pub message: String,
+	/// Time since logging started
+	pub elapsed: Duration,
+}
+
+/// The most recent log records, for console panels and debug overlays
+pub type LogBuffer = RingBuffer<LogRecord>;
+
+// Appends to a log file, shifting it to `<path>.1`, `<path>.2` and so on when it gets too big
+struct RotatingFile {
+	path: PathBuf,
+	file: File,
+	size: u64,
+	max_size: u64,
+	max_files: usize,
+}
+
+impl RotatingFile {
+	fn open(path: PathBuf, max_size: u64, max_files: usize) -> io::Result<Self> {
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+		let file = OpenOptions::new().create(true).append(true).open(&path)?;
+		let size = file.metadata()?.len();
+		Ok(Self {
+			path,
+			file,
+			size,
+			max_size,
+			max_files,
+		})
+	}
+
+	fn write_line(&mut self, line: &str) -> io::Result<()> {
+		let length = line.len() as u64 + 1;
+		if self.size > 0 && self.size + length > self.max_size {
+			self.rotate()?;
+		}
+		writeln!(self.file, "{line}")?;
+		self.size += length;
+		Ok(())
+	}
+
+	fn rotate(&mut self) -> io::Result<()> {
+		if self.max_files == 0 {
+			self.file = File::create(&self.path)?;
+		} else {
+			for index in (1..self.max_files).rev() {
+				let from = self.rotated_path(index);
+				if from.exists() {
+					fs::rename(from, self.rotated_path(index + 1))?;
+				}
+			}
+			fs::rename(&self.path, self.rotated_path(1))?;
+			self.file = File::create(&self.path)?;
+		}
+		self.size = 0;
+		Ok(())
+	}
+
+	fn rotated_path(&self, index: usize) -> PathBuf {
+		let mut path = self.path.clone().into_os_string();
+		path.push(format!(".{index}"));
+		path.into()
+	}
+}
+
+pub(crate) struct Logger {
+	level: LevelFilter,
+	modules: Vec<(String, LevelFilter)>,
+	start: Instant,
+	file: Option<Mutex<RotatingFile>>,
+	buffer: LogBuffer,
+}
+
+impl Logger {
+	pub(crate) fn new(config: &LogConfig) -> io::Result<Self> {
+		let file = config
+			.file
+			.as_ref()
+			.map(|path| RotatingFile::open(path.into(), config.max_file_size, config.max_files))
+			.transpose()?
+			.map(Mutex::new);
+		let mut modules = config
+			.modules
+			.iter()
+			.map(|(module, level)| (module.to_string(), *level))
+			.collect::<Vec<_>>();
+		modules.sort_by_key(|(module, _)| std::cmp::Reverse(module.len()));
+		Ok(Self {
+			level: config.level,
+			modules,
+			start: Instant::now(),
+			file,
+			buffer: LogBuffer::new(config.buffer_capacity),
+		})
+	}
+
+	pub(crate) fn buffer(&self) -> LogBuffer {
+		self.buffer.clone()
+	}
+
+	/// Installs the logger, returning false if another logger was already installed
+	pub(crate) fn install(self) -> bool {
+		let max_level = self
+			.modules
+			.iter()
+			.map(|(_, level)| *level)
+			.fold(self.level, Ord::max);
+		if log::set_boxed_logger(Box::new(self)).is_err() {
+			return false;
+		}
+		log::set_max_level(max_level);
+		true
+	}
+
+	fn level_for(&self, target: &str) -> LevelFilter {
+		self.modules
+			.iter()
+			.find(|(module, _)| {
+				target
+					.strip_prefix(module.as_str())
+					.is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+			})
+			.map_or(self.level, |(_, level)| *level)
+	}
+}
+
+impl Log for Logger {
+	fn enabled(&self, metadata: &Metadata) -> bool {
+		metadata.level() <= self.level_for(metadata.target())
+	}
+
+	fn log(&self, record: &Record) {
+		if !self.enabled(record.metadata()) {
+			return;
+		}
+		let record = LogRecord {
+			level: record.level(),
+			target: record.target().to_string(),
+			message: record.args().to_string(),
+			elapsed: self.start.elapsed(),
+		};
+		let line = format!(
+			"[{:>10.3} {:<5} {}] {}",
+			record.elapsed.as_secs_f64(),
+			record.level,
+			record.target,
+			record.message
+		);
+
+		#[cfg(not(target_arch = "wasm32"))]
+		eprintln!("{line}");
+		#[cfg(target_arch = "wasm32")]
+		web_sys::console::log_1(&line.as_str().into());
+
+		if let Some(file) = self.file.as_ref() {
+			let mut file = file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+			// Logging about a failure to log would recurse, so report it directly
+			if let Err(error) = file.write_line(&line) {
+				eprintln!("Failed to write to log file: {error}");
+			}
+		}
+		self.buffer.push(record);
+	}
+
+	fn flush(&self) {
+		if let Some(file) = self.file.as_ref() {
+			let mut file = file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+			let _ = file.file.flush();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn log(logger: &Logger, level: Level, target: &str, message: &str) {
+		logger.log(
+			&Record::builder()
+				.args(format_args!("{message}"))
+				.level(level)
+				.target(target)
+				.build(),
+		);
+	}
+
+	#[test]
+	fn module_levels() {
+		let mut config = LogConfig::default();
+		config.apply_filter("warn,game=debug,game::ai=error");
+		let logger = Logger::new(&config).unwrap();
+
+		assert_eq!(logger.level_for("wgpu"), LevelFilter::Warn);
+		assert_eq!(logger.level_for("game"), LevelFilter::Debug);
+		assert_eq!(logger.level_for("game::physics"), LevelFilter::Debug);
+		assert_eq!(logger.level_for("game::ai::planner"), LevelFilter::Error);
+		assert_eq!(logger.level_for("gameplay"), LevelFilter::Warn);
+	}
+
+	#[test]
+	fn buffer_keeps_recent_records() {
+		let config = LogConfig {
+			buffer_capacity: 2,
+			..Default::default()
+		};
+		let logger = Logger::new(&config).unwrap();
+		let buffer = logger.buffer();
+
+		log(&logger, Level::Info, "game", "first");
+		log(&logger, Level::Debug, "game", "filtered");
+		log(&logger, Level::Warn, "game", "second");
+		log(&logger, Level::Error, "game", "third");
+
+		let messages = buffer
+			.items()
+			.into_iter()
+			.map(|record| record.message)
+			.collect::<Vec<_>>();
+		assert_eq!(messages, ["second", "third"]);
+	}
+
+	#[test]
+	fn log_files_rotate() {
+		let directory = std::env::temp_dir().join(format!("hourglass-logs-{}", std::process::id()));
+		let path = directory.join("app.log");
+		let mut file = RotatingFile::open(path.clone(), 10, 2).unwrap();
+		for line in ["first", "second", "third", "fourth"] {
+			file.write_line(line).unwrap();
+		}
+
+		let read = |path: &PathBuf| fs::read_to_string(path).unwrap();
+		assert_eq!(read(&path), "fourth\n");
+		assert_eq!(read(&file.rotated_path(1)), "third\n");
+		assert_eq!(read(&file.rotated_path(2)), "second\n");
+		assert!(!file.rotated_path(3).exists());
+		fs::remove_dir_all(directory).unwrap();
+	}
+}
@@ -0,0 +1,183 @@
+use crate::{platform::Instant, plugin::System};
+use ecs::world::World;
+use std::{
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+
+/// Upper bounds of the frame time histogram buckets in milliseconds.
+/// Frames slower than the last bound are counted in the final bucket.
+pub const FRAME_TIME_BUCKETS_MS: [f64; 6] = [4.0, 8.0, 16.7, 33.3, 50.0, 100.0];
+
+/// Counters describing how the worker loop is keeping up, read with `Context::metrics`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AppMetrics {
+	pub frames: u64,
+	/// Frames counted by how long they took, bucketed by `FRAME_TIME_BUCKETS_MS`
+	pub frame_time_histogram: [u64; FRAME_TIME_BUCKETS_MS.len() + 1],
+	/// Events waiting for the worker when the latest frame started
+	pub event_queue_depth: usize,
+	pub max_event_queue_depth: usize,
+	/// How much later than scheduled the latest frame started
+	pub worker_latency: Duration,
+	pub max_worker_latency: Duration,
+	/// Live events ignored while replaying a recording
+	pub dropped_events: u64,
+}
+
+impl AppMetrics {
+	pub(crate) fn record_frame(&mut self, frame_time: Duration, latency: Duration) {
+		let millis = frame_time.as_secs_f64() * 1000.0;
+		let bucket = FRAME_TIME_BUCKETS_MS
+			.iter()
+			.position(|bound| millis <= *bound)
+			.unwrap_or(FRAME_TIME_BUCKETS_MS.len());
+		self.frame_time_histogram[bucket] += 1;
+		self.frames += 1;
+		self.worker_latency = latency;
+		self.max_worker_latency = self.max_worker_latency.max(latency);
+	}
+
+	pub(crate) fn record_queue_depth(&mut self, depth: usize) {
+		self.event_queue_depth = depth;
+		self.max_event_queue_depth = self.max_event_queue_depth.max(depth);
+	}
+
+	/// The frame time below which the given fraction of frames fell, such as 0.99,
+	/// as the upper bound of its histogram bucket. `None` for the final bucket.
+	pub fn frame_time_percentile(&self, fraction: f64) -> Option<f64> {
+		let target = (self.frames as f64 * fraction.clamp(0.0, 1.0)).ceil() as u64;
+		let mut seen = 0;
+		for (bucket, count) in self.frame_time_histogram.iter().enumerate() {
+			seen += count;
+			if seen >= target {
+				return FRAME_TIME_BUCKETS_MS.get(bucket).copied();
+			}
+		}
+		None
+	}
+}
+
+/// How long a system registered with `AppBuilder::add_system` has taken,
+/// read with `Context::system_timings`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SystemTiming {
+	pub name: &'static str,
+	pub last: Duration,
+	pub total: Duration,
+	pub runs: u64,
+}
+
+impl SystemTiming {
+	pub fn average(&self) -> Duration {
+		if self.runs == 0 {
+			Duration::ZERO
+		} else {
+			self.total.div_f64(self.runs as f64)
+		}
+	}
+}
+
+/// How many messages an `EventBus` resource created by the `AppBuilder` has published,
+/// named after its message type and read with `Context::bus_traffic`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BusTraffic {
+	pub name: &'static str,
+	pub published: u64,
+}
+
+// Shared with the world thread, which records the timings when running the world systems
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SystemTimings(Arc<Mutex<Vec<SystemTiming>>>);
+
+impl SystemTimings {
+	pub(crate) fn new(systems: &[System]) -> Self {
+		Self(Arc::new(Mutex::new(
+			systems
+				.iter()
+				.map(|system| SystemTiming {
+					name: system.name(),
+					last: Duration::ZERO,
+					total: Duration::ZERO,
+					runs: 0,
+				})
+				.collect(),
+		)))
+	}
+
+	/// Runs the systems in order, stopping at the first error
+	pub(crate) fn run(&self, systems: &[System], world: &mut World) -> ecs::error::Result<()> {
+		for (index, system) in systems.iter().enumerate() {
+			let started = Instant::now();
+			let result = {
+				let _span = diagnostics::span(system.name());
+				system.run(world)
+			};
+			let elapsed = started.elapsed();
+			if let Some(timing) = self.lock().get_mut(index) {
+				timing.last = elapsed;
+				timing.total += elapsed;
+				timing.runs += 1;
+			}
+			result?;
+		}
+		Ok(())
+	}
+
+	pub(crate) fn snapshot(&self) -> Vec<SystemTiming> {
+		self.lock().clone()
+	}
+
+	fn lock(&self) -> std::sync::MutexGuard<'_, Vec<SystemTiming>> {
+		self.0
+			.lock()
+			.unwrap_or_else(|poisoned| poisoned.into_inner())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn frame_times_are_bucketed() {
+		let mut metrics = AppMetrics::default();
+		for millis in [3, 7, 7, 16, 16, 16, 16, 16, 16, 250] {
+			metrics.record_frame(Duration::from_millis(millis), Duration::ZERO);
+		}
+		assert_eq!(metrics.frames, 10);
+		assert_eq!(metrics.frame_time_histogram, [1, 2, 6, 0, 0, 0, 1]);
+		assert_eq!(metrics.frame_time_percentile(0.5), Some(16.7));
+		assert_eq!(metrics.frame_time_percentile(1.0), None);
+
+		metrics.record_queue_depth(12);
+		metrics.record_queue_depth(3);
+		assert_eq!(
+			(metrics.event_queue_depth, metrics.max_event_queue_depth),
+			(3, 12)
+		);
+	}
+
+	fn idle(_world: &mut World) -> ecs::error::Result<()> {
+		Ok(())
+	}
+
+	#[test]
+	fn systems_are_timed_by_name() {
+		let systems = [
+			System::new(idle),
+			System::named("failing", |_world| Err("failed".into())),
+			System::new(idle),
+		];
+		let timings = SystemTimings::new(&systems);
+		let mut world = World::new();
+		assert!(timings.run(&systems, &mut world).is_err());
+
+		let runs = timings
+			.snapshot()
+			.into_iter()
+			.map(|timing| (timing.name, timing.runs))
+			.collect::<Vec<_>>();
+		assert_eq!(runs, [("idle", 1), ("failing", 1), ("idle", 0)]);
+	}
+}
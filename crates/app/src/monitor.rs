@@ -0,0 +1,97 @@
+use serde::{Deserialize, Serialize};
+use winit::{
+	monitor::MonitorHandle,
+	window::{Fullscreen, Window},
+};
+
+/// How a window is presented. Monitors are indices into `Context::request_monitors`,
+/// with `None` meaning the monitor the window is currently on.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WindowMode {
+	#[default]
+	Windowed,
+	BorderlessFullscreen {
+		monitor: Option<usize>,
+	},
+	/// Changes the monitor's video mode, preferring one matching the window size
+	ExclusiveFullscreen {
+		monitor: Option<usize>,
+	},
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonitorInfo {
+	pub name: Option<String>,
+	pub width: u32,
+	pub height: u32,
+	pub scale_factor: f64,
+	pub video_modes: Vec<VideoModeInfo>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VideoModeInfo {
+	pub width: u32,
+	pub height: u32,
+	pub bit_depth: u16,
+	pub refresh_rate_millihertz: u32,
+}
+
+pub(crate) fn monitors(window: &Window) -> Vec<MonitorInfo> {
+	window
+		.available_monitors()
+		.map(|monitor| MonitorInfo {
+			name: monitor.name(),
+			width: monitor.size().width,
+			height: monitor.size().height,
+			scale_factor: monitor.scale_factor(),
+			video_modes: monitor
+				.video_modes()
+				.map(|video_mode| VideoModeInfo {
+					width: video_mode.size().width,
+					height: video_mode.size().height,
+					bit_depth: video_mode.bit_depth(),
+					refresh_rate_millihertz: video_mode.refresh_rate_millihertz(),
+				})
+				.collect(),
+		})
+		.collect()
+}
+
+pub(crate) fn fullscreen(window: &Window, mode: WindowMode) -> Option<Fullscreen> {
+	match mode {
+		WindowMode::Windowed => None,
+		WindowMode::BorderlessFullscreen { monitor } => Some(Fullscreen::Borderless(
+			monitor.and_then(|index| window.available_monitors().nth(index)),
+		)),
+		WindowMode::ExclusiveFullscreen { monitor } => {
+			let Some(monitor) = find_monitor(window, monitor) else {
+				log::warn!("Monitor {monitor:?} not found, staying windowed");
+				return None;
+			};
+			let size = window.inner_size();
+			let video_mode = monitor.video_modes().max_by_key(|video_mode| {
+				(
+					video_mode.size() == size,
+					video_mode.size().width * video_mode.size().height,
+					video_mode.refresh_rate_millihertz(),
+				)
+			});
+			match video_mode {
+				Some(video_mode) => Some(Fullscreen::Exclusive(video_mode)),
+				None => {
+					log::warn!("No video modes available, falling back to borderless fullscreen");
+					Some(Fullscreen::Borderless(Some(monitor)))
+				}
+			}
+		}
+	}
+}
+
+fn find_monitor(window: &Window, monitor: Option<usize>) -> Option<MonitorHandle> {
+	match monitor {
+		Some(index) => window.available_monitors().nth(index),
+		None => window
+			.current_monitor()
+			.or_else(|| window.primary_monitor()),
+	}
+}
@@ -0,0 +1,135 @@
+// Abstracts over tokio natively and the browser's event loop on the web,
+// where there is no multithreaded runtime and `std::time::Instant` panics
+use std::{future::Future, time::Duration};
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use std::time::{Instant, SystemTime};
+#[cfg(target_arch = "wasm32")]
+pub(crate) use web_time::{Instant, SystemTime};
+
+/// A spawned task that can be stopped at its next await point
+pub(crate) struct Task {
+	#[cfg(not(target_arch = "wasm32"))]
+	abort_handle: tokio::task::AbortHandle,
+	#[cfg(target_arch = "wasm32")]
+	abort_handle: futures::future::AbortHandle,
+	#[cfg(target_arch = "wasm32")]
+	finished: std::rc::Rc<std::cell::Cell<bool>>,
+}
+
+impl Task {
+	pub(crate) fn abort(&self) {
+		self.abort_handle.abort();
+	}
+
+	pub(crate) fn is_finished(&self) -> bool {
+		#[cfg(not(target_arch = "wasm32"))]
+		return self.abort_handle.is_finished();
+		#[cfg(target_arch = "wasm32")]
+		return self.finished.get() || self.abort_handle.is_aborted();
+	}
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn spawn(future: impl Future<Output = ()> + Send + 'static) -> Task {
+	Task {
+		abort_handle: tokio::spawn(future).abort_handle(),
+	}
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn spawn(future: impl Future<Output = ()> + 'static) -> Task {
+	use futures::future::{AbortHandle, Abortable};
+	let (abort_handle, registration) = AbortHandle::new_pair();
+	let finished = std::rc::Rc::new(std::cell::Cell::new(false));
+	let task_finished = finished.clone();
+	wasm_bindgen_futures::spawn_local(async move {
+		let _ = Abortable::new(future, registration).await;
+		task_finished.set(true);
+	});
+	Task {
+		abort_handle,
+		finished,
+	}
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn sleep_until(deadline: Instant) {
+	tokio::time::sleep_until(deadline.into()).await
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn sleep_until(deadline: Instant) {
+	let remaining = deadline.saturating_duration_since(Instant::now());
+	if !remaining.is_zero() {
+		gloo_timers::future::sleep(remaining).await
+	}
+}
+
+/// Runs a future to completion, or returns `None` if it takes longer than the duration
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn timeout<T>(duration: Duration, future: impl Future<Output = T>) -> Option<T> {
+	tokio::time::timeout(duration, future).await.ok()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn timeout<T>(duration: Duration, future: impl Future<Output = T>) -> Option<T> {
+	tokio::select! {
+		output = future => Some(output),
+		_ = gloo_timers::future::sleep(duration) => None,
+	}
+}
+
+/// Ticks every period, delaying the following ticks when one is missed
+pub(crate) struct Interval {
+	#[cfg(not(target_arch = "wasm32"))]
+	interval: tokio::time::Interval,
+	#[cfg(target_arch = "wasm32")]
+	period: Duration,
+	#[cfg(target_arch = "wasm32")]
+	next_tick: Instant,
+}
+
+impl Interval {
+	/// The first tick happens after one period
+	pub(crate) fn new(period: Duration) -> Self {
+		#[cfg(not(target_arch = "wasm32"))]
+		{
+			use tokio::time::{self, MissedTickBehavior};
+			let mut interval = time::interval_at(time::Instant::now() + period, period);
+			interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+			Self { interval }
+		}
+		#[cfg(target_arch = "wasm32")]
+		Self {
+			period,
+			next_tick: Instant::now() + period,
+		}
+	}
+
+	pub(crate) async fn tick(&mut self) {
+		#[cfg(not(target_arch = "wasm32"))]
+		self.interval.tick().await;
+		#[cfg(target_arch = "wasm32")]
+		{
+			sleep_until(self.next_tick).await;
+			let now = Instant::now();
+			self.next_tick += self.period;
+			if self.next_tick < now {
+				self.next_tick = now + self.period;
+			}
+		}
+	}
+}
+
+/// Waits for the browser to request the next frame, which it pauses while the page is hidden
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn next_animation_frame() {
+	let Some(window) = web_sys::window() else {
+		return;
+	};
+	let frame = js_sys::Promise::new(&mut |resolve, _| {
+		let _ = window.request_animation_frame(&resolve);
+	});
+	let _ = wasm_bindgen_futures::JsFuture::from(frame).await;
+}
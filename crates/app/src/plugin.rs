@@ -0,0 +1,347 @@
+use crate::{
+	app::{App, AppEvent, Context, Error},
+	config::AppConfig,
+	metrics::AppMetrics,
+	pool::{Pool, WorkerPool},
+	request::{RequestContext, RequestHandlers},
+	state::{State, StateChange, StateObserver},
+};
+use bus::{EventBus, EventBusError};
+use ecs::world::World;
+use std::{
+	any::{Any, TypeId},
+	collections::HashMap,
+	sync::Arc,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+const ASSET_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+#[cfg(not(target_arch = "wasm32"))]
+const ASSET_TIMER: &str = "assets";
+
+type SystemFn = dyn Fn(&mut World) -> ecs::error::Result<()> + Send + Sync;
+
+/// An ECS system registered with `AppBuilder::add_system`, named after its function
+#[derive(Clone)]
+pub struct System {
+	name: &'static str,
+	run: Arc<SystemFn>,
+}
+
+impl System {
+	pub fn new<S>(system: S) -> Self
+	where
+		S: Fn(&mut World) -> ecs::error::Result<()> + Send + Sync + 'static,
+	{
+		Self::named(function_name(std::any::type_name::<S>()), system)
+	}
+
+	pub fn named(
+		name: &'static str,
+		system: impl Fn(&mut World) -> ecs::error::Result<()> + Send + Sync + 'static,
+	) -> Self {
+		Self {
+			name,
+			run: Arc::new(system),
+		}
+	}
+
+	pub fn name(&self) -> &'static str {
+		self.name
+	}
+
+	pub fn run(&self, world: &mut World) -> ecs::error::Result<()> {
+		(self.run)(world)
+	}
+}
+
+// Turns `game::systems::movement` into `movement`, skipping closure segments
+fn function_name(type_name: &'static str) -> &'static str {
+	type_name
+		.split("::")
+		.filter(|segment| !segment.starts_with('{'))
+		.last()
+		.unwrap_or(type_name)
+}
+
+// Reads how many messages an `EventBus` resource has published, whatever its message type
+pub(crate) type BusCounter = (&'static str, Arc<dyn Fn() -> u64 + Send + Sync>);
+
+pub(crate) type EventHook = Box<dyn FnMut(&mut Context, &AppEvent) + Send>;
+
+/// Typed values shared between plugins and states through `Context::resources`
+#[derive(Default)]
+pub struct Resources {
+	data: HashMap<TypeId, Box<dyn Any + Send>>,
+}
+
+impl Resources {
+	pub fn get<T: Send + 'static>(&self) -> Option<&T> {
+		self.data
+			.get(&TypeId::of::<T>())
+			.and_then(|any| any.downcast_ref())
+	}
+
+	pub fn get_mut<T: Send + 'static>(&mut self) -> Option<&mut T> {
+		self.data
+			.get_mut(&TypeId::of::<T>())
+			.and_then(|any| any.downcast_mut())
+	}
+
+	/// Replaces any previous value of the same type
+	pub fn insert<T: Send + 'static>(&mut self, value: T) {
+		self.data.insert(TypeId::of::<T>(), Box::new(value));
+	}
+
+	pub fn remove<T: Send + 'static>(&mut self) {
+		self.data.remove(&TypeId::of::<T>());
+	}
+}
+
+/// A modular piece of app setup, such as a renderer or audio integration
+pub trait Plugin {
+	fn build(&self, app: &mut AppBuilder);
+}
+
+// Everything registered by plugins that the worker needs when it starts
+#[derive(Default)]
+pub(crate) struct Extensions {
+	pub(crate) resources: Resources,
+	pub(crate) systems: Vec<System>,
+	pub(crate) bus_counters: Vec<BusCounter>,
+	pub(crate) event_hooks: Vec<EventHook>,
+	pub(crate) states: Vec<Box<dyn State<Context, AppEvent>>>,
+	pub(crate) overlays: Vec<Box<dyn State<Context, AppEvent>>>,
+	pub(crate) state_observers: Vec<StateObserver>,
+	pub(crate) pools: Vec<Box<dyn Pool>>,
+	pub(crate) world: Option<Box<dyn FnOnce() -> World + Send>>,
+	pub(crate) metrics_channel: Option<String>,
+	#[cfg(feature = "tray")]
+	pub(crate) tray: Option<crate::tray::TrayConfig>,
+	#[cfg(target_os = "android")]
+	pub(crate) android_app: Option<winit::platform::android::activity::AndroidApp>,
+}
+
+pub struct AppBuilder {
+	config: AppConfig,
+	request_handlers: RequestHandlers,
+	extensions: Extensions,
+}
+
+impl AppBuilder {
+	pub fn new(config: AppConfig) -> Self {
+		Self {
+			config,
+			request_handlers: RequestHandlers::default(),
+			extensions: Extensions::default(),
+		}
+	}
+
+	pub fn config_mut(&mut self) -> &mut AppConfig {
+		&mut self.config
+	}
+
+	/// Runs the plugin's setup, taking and returning the builder so it chains into `build`
+	pub fn add_plugin(mut self, plugin: impl Plugin) -> Self {
+		plugin.build(&mut self);
+		self
+	}
+
+	/// Pushes a state on top of the initial state when the app starts
+	pub fn add_state(&mut self, state: impl State<Context, AppEvent>) -> &mut Self {
+		self.extensions.states.push(Box::new(state));
+		self
+	}
+
+	/// Runs a state alongside the stack for the whole app, such as a debug console or toasts
+	pub fn add_overlay(&mut self, overlay: impl State<Context, AppEvent>) -> &mut Self {
+		self.extensions.overlays.push(Box::new(overlay));
+		self
+	}
+
+	/// Observes every change to the state stack, such as to record analytics
+	/// or forward it to an `EventBus` for the editor
+	pub fn add_state_observer(
+		&mut self,
+		observer: impl FnMut(&StateChange) + Send + 'static,
+	) -> &mut Self {
+		self.extensions.state_observers.push(Box::new(observer));
+		self
+	}
+
+	pub fn insert_resource<T: Send + 'static>(&mut self, resource: T) -> &mut Self {
+		self.extensions.resources.insert(resource);
+		self
+	}
+
+	pub fn resources_mut(&mut self) -> &mut Resources {
+		&mut self.extensions.resources
+	}
+
+	/// Adds a channel to the `Arc<EventBus<T>>` resource, creating the bus if needed
+	pub fn add_bus_channel<T: Clone + Send + Sync + 'static>(
+		&mut self,
+		name: &str,
+	) -> Result<&mut Self, EventBusError> {
+		self.event_bus::<T>().add_channel(name)?;
+		Ok(self)
+	}
+
+	/// Publishes `Context::metrics` to a channel of the `Arc<EventBus<AppMetrics>>` resource
+	/// once a second under the `metrics` topic, such as for a dashboard
+	pub fn publish_metrics(&mut self, channel_name: &str) -> Result<&mut Self, EventBusError> {
+		self.add_bus_channel::<AppMetrics>(channel_name)?;
+		self.extensions.metrics_channel = Some(channel_name.to_string());
+		Ok(self)
+	}
+
+	/// Listens on the address for a debugger such as the editor, which can inspect and edit
+	/// the world the systems run on and receives the app's logs and system timings.
+	/// Requests are answered when the systems run.
+	#[cfg(all(feature = "remote", not(target_arch = "wasm32")))]
+	pub fn serve_debugger(
+		&mut self,
+		address: &str,
+	) -> Result<&mut Self, bus::remote::RemoteBridgeError> {
+		use crate::remote::{DebugServer, DebugStream};
+		let server = Arc::new(DebugServer::bind(self.event_bus(), address)?);
+		let requests = server.clone();
+		self.extensions
+			.systems
+			.push(System::named("debugger", move |world| {
+				requests.handle_requests(world);
+				Ok(())
+			}));
+		let mut stream = DebugStream::new(server);
+		Ok(self.add_event_hook(move |context, event| stream.on_event(context, event)))
+	}
+
+	/// Shares the server with states through `Context::resources`, and updates it on
+	/// a timer so unused assets are unloaded and changed ones hot reloaded
+	#[cfg(not(target_arch = "wasm32"))]
+	pub fn with_assets(&mut self, server: assets::AssetServer) -> &mut Self {
+		self.extensions.resources.insert(server.clone());
+		let mut timer = None;
+		self.add_event_hook(move |context, event| {
+			let timer = *timer.get_or_insert_with(|| context.every(ASSET_INTERVAL, ASSET_TIMER));
+			if matches!(event, AppEvent::Timer { timer: fired, .. } if *fired == timer) {
+				server.update();
+			}
+		})
+	}
+
+	/// Starts the pool's workers with the app, taking jobs from the `Arc<EventBus<J>>`
+	/// resource and publishing results to the `Arc<EventBus<R>>` resource
+	pub fn add_worker_pool<J: Clone + Send + Sync + 'static, R: Clone + Send + Sync + 'static>(
+		&mut self,
+		pool: WorkerPool<J, R>,
+	) -> &mut Self {
+		let pool = pool.with_buses(self.event_bus(), self.event_bus());
+		self.extensions.pools.push(Box::new(pool));
+		self
+	}
+
+	fn event_bus<T: Clone + Send + Sync + 'static>(&mut self) -> Arc<EventBus<T>> {
+		let resources = &mut self.extensions.resources;
+		if resources.get::<Arc<EventBus<T>>>().is_none() {
+			let event_bus = Arc::new(EventBus::<T>::new());
+			resources.insert(event_bus.clone());
+			self.extensions.bus_counters.push((
+				function_name(std::any::type_name::<T>()),
+				Arc::new(move || event_bus.published_count()),
+			));
+		}
+		resources
+			.get::<Arc<EventBus<T>>>()
+			.expect("The event bus was just inserted")
+			.clone()
+	}
+
+	/// Creates a world shared by every state through `Context::world` when the app starts
+	pub fn with_world(&mut self, create: impl FnOnce() -> World + Send + 'static) -> &mut Self {
+		self.extensions.world = Some(Box::new(create));
+		self
+	}
+
+	/// Shows a system tray icon once the primary window opens
+	#[cfg(feature = "tray")]
+	pub fn with_tray(&mut self, tray: crate::tray::TrayConfig) -> &mut Self {
+		self.extensions.tray = Some(tray);
+		self
+	}
+
+	/// Adds a system run by `Context::run_systems`, in registration order
+	pub fn add_system<S>(&mut self, system: S) -> &mut Self
+	where
+		S: Fn(&mut World) -> ecs::error::Result<()> + Send + Sync + 'static,
+	{
+		self.extensions.systems.push(System::new(system));
+		self
+	}
+
+	/// Observes every event before it reaches the states
+	pub fn add_event_hook(
+		&mut self,
+		hook: impl FnMut(&mut Context, &AppEvent) + Send + 'static,
+	) -> &mut Self {
+		self.extensions.event_hooks.push(Box::new(hook));
+		self
+	}
+
+	/// Handles requests of type `R` sent with `Context::send_request` on the main thread
+	pub fn add_request_handler<R: Any + Send + Sync>(
+		&mut self,
+		handler: impl FnMut(R, &RequestContext) + 'static,
+	) -> &mut Self {
+		self.request_handlers.insert(handler);
+		self
+	}
+
+	/// Attaches the app to the Android activity passed to `android_main`, required on Android
+	#[cfg(target_os = "android")]
+	pub fn android_app(
+		&mut self,
+		android_app: winit::platform::android::activity::AndroidApp,
+	) -> &mut Self {
+		self.extensions.android_app = Some(android_app);
+		self
+	}
+
+	pub fn build(self) -> Result<App, Error> {
+		App::create(&self.config, self.request_handlers, self.extensions)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	struct CounterPlugin;
+
+	struct Counter(u32);
+
+	impl Plugin for CounterPlugin {
+		fn build(&self, app: &mut AppBuilder) {
+			app.insert_resource(Counter(0))
+				.add_event_hook(|context, _event| {
+					if let Some(counter) = context.resources.get_mut::<Counter>() {
+						counter.0 += 1;
+					}
+				})
+				.add_bus_channel::<String>("gameplay/events")
+				.unwrap();
+		}
+	}
+
+	#[test]
+	fn plugins_register_extensions() {
+		let builder = AppBuilder::new(AppConfig::default()).add_plugin(CounterPlugin);
+
+		let resources = &builder.extensions.resources;
+		assert_eq!(resources.get::<Counter>().map(|counter| counter.0), Some(0));
+		assert!(resources
+			.get::<Arc<EventBus<String>>>()
+			.is_some_and(|bus| bus.channels_in_namespace("gameplay") == ["gameplay/events"]));
+		assert_eq!(builder.extensions.event_hooks.len(), 1);
+	}
+}
@@ -0,0 +1,166 @@
+use async_channel::Receiver;
+use bus::{EventBus, EventBusError, Message, Publisher, Subscriber};
+use std::sync::Arc;
+
+// Lets the app start and stop pools without knowing their job and result types
+pub(crate) trait Pool: Send {
+	fn start(&mut self) -> Result<(), EventBusError>;
+	fn stop(&mut self);
+}
+
+/// Background workers that take jobs published to the `<name>/jobs` channel and publish
+/// what the handler returns to `<name>/results` under the job's topic, so CPU-heavy work
+/// such as pathfinding doesn't hold up the state machine. On the web, where there are
+/// no threads, jobs run on the browser's event loop between frames.
+pub struct WorkerPool<J: Clone + Send + 'static, R: Clone + Send + 'static> {
+	name: String,
+	workers: usize,
+	jobs: Arc<EventBus<J>>,
+	results: Arc<EventBus<R>>,
+	handler: Handler<J, R>,
+}
+
+impl<J: Clone + Send + 'static, R: Clone + Send + 'static> WorkerPool<J, R> {
+	/// Uses one worker per available CPU when `workers` is zero
+	pub fn new(
+		name: impl Into<String>,
+		workers: usize,
+		handler: impl Fn(J) -> R + Send + Sync + 'static,
+	) -> Self {
+		Self {
+			name: name.into(),
+			workers,
+			jobs: Arc::new(EventBus::new()),
+			results: Arc::new(EventBus::new()),
+			handler: Arc::new(handler),
+		}
+	}
+
+	pub(crate) fn with_buses(mut self, jobs: Arc<EventBus<J>>, results: Arc<EventBus<R>>) -> Self {
+		self.jobs = jobs;
+		self.results = results;
+		self
+	}
+
+	pub fn jobs_channel(&self) -> String {
+		format!("{}/jobs", self.name)
+	}
+
+	pub fn results_channel(&self) -> String {
+		format!("{}/results", self.name)
+	}
+
+	fn worker_count(&self) -> usize {
+		if self.workers > 0 {
+			return self.workers;
+		}
+		std::thread::available_parallelism().map_or(1, usize::from)
+	}
+}
+
+impl<J: Clone + Send + 'static, R: Clone + Send + 'static> Pool for WorkerPool<J, R> {
+	fn start(&mut self) -> Result<(), EventBusError> {
+		// The channels may already exist when added with `AppBuilder::add_bus_channel`
+		let _ = self.jobs.add_channel(&self.jobs_channel());
+		let _ = self.results.add_channel(&self.results_channel());
+		let receiver = Subscriber::new(self.jobs.clone(), vec![self.jobs_channel()])
+			.subscribe(None)?
+			.remove(0);
+
+		for index in 0..self.worker_count() {
+			spawn_worker(
+				format!("{}-{index}", self.name),
+				receiver.clone(),
+				Publisher::new(self.results.clone(), self.results_channel()),
+				self.handler.clone(),
+			);
+		}
+		Ok(())
+	}
+
+	fn stop(&mut self) {
+		let _ = self.jobs.remove_channel(&self.jobs_channel());
+	}
+}
+
+type Handler<J, R> = Arc<dyn Fn(J) -> R + Send + Sync>;
+
+// Workers stop once the jobs channel is removed and its queue is empty
+#[cfg(not(target_arch = "wasm32"))]
+fn spawn_worker<J: Clone + Send + 'static, R: Clone + Send + 'static>(
+	name: String,
+	receiver: Receiver<Message<J>>,
+	publisher: Publisher<R>,
+	handler: Handler<J, R>,
+) {
+	let spawned = std::thread::Builder::new()
+		.name(name.clone())
+		.spawn(move || {
+			while let Ok(message) = receiver.recv_blocking() {
+				let (topic, job) = message.into_parts();
+				if publisher.publish_sync(topic, handler(job)).is_err() {
+					break;
+				}
+			}
+		});
+	if let Err(error) = spawned {
+		log::error!("Failed to start worker {name}: {error}");
+	}
+}
+
+#[cfg(target_arch = "wasm32")]
+fn spawn_worker<J: Clone + Send + 'static, R: Clone + Send + 'static>(
+	_name: String,
+	receiver: Receiver<Message<J>>,
+	publisher: Publisher<R>,
+	handler: Handler<J, R>,
+) {
+	crate::platform::spawn(async move {
+		while let Ok(message) = receiver.recv().await {
+			let (topic, job) = message.into_parts();
+			if publisher.publish(topic, handler(job)).await.is_err() {
+				break;
+			}
+		}
+	});
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn workers_publish_results() -> Result<(), EventBusError> {
+		let jobs = Arc::new(EventBus::<u64>::new());
+		let results = Arc::new(EventBus::<u64>::new());
+		let mut pool = WorkerPool::new("squares", 2, |value: u64| value * value)
+			.with_buses(jobs.clone(), results.clone());
+		pool.start()?;
+
+		let receiver = Subscriber::new(results, vec![pool.results_channel()])
+			.subscribe(None)?
+			.remove(0);
+		let publisher = Publisher::new(jobs, pool.jobs_channel());
+		for value in 1..=3 {
+			publisher.publish(format!("job-{value}"), value).await?;
+		}
+
+		let mut squares = Vec::new();
+		for _ in 0..3 {
+			let message = receiver.recv().await.unwrap();
+			squares.push(Message::into_parts(message));
+		}
+		squares.sort();
+		assert_eq!(
+			squares,
+			[
+				("job-1".to_string(), 1),
+				("job-2".to_string(), 4),
+				("job-3".to_string(), 9)
+			]
+		);
+
+		pool.stop();
+		Ok(())
+	}
+}
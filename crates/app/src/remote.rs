@@ -0,0 +1,412 @@
+use crate::{
+	app::{AppEvent, Context},
+	logging::LogRecord,
+	metrics::SystemTiming,
+	timer::TimerId,
+};
+use async_channel::Receiver;
+use bus::{
+	remote::{RemoteBridge, RemoteBridgeError},
+	EventBus, Message, Publisher, Subscriber,
+};
+use ecs::{
+	hierarchy,
+	reflect::{ComponentRegistry, Field, Value},
+	world::{Entity, World},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc, Mutex,
+	},
+	time::Duration,
+};
+
+/// Carries requests from a debugger to the app
+pub const DEBUG_REQUESTS: &str = "debug/requests";
+/// Carries the app's replies, logs and timings to a debugger
+pub const DEBUG_EVENTS: &str = "debug/events";
+const STREAM_INTERVAL: Duration = Duration::from_millis(500);
+const STREAM_TIMER: &str = "debugger";
+
+/// An entity in another process, which can't be turned back into an `Entity` directly
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RemoteEntityId {
+	pub index: usize,
+	pub generation: usize,
+}
+
+impl RemoteEntityId {
+	pub fn of(entity: Entity) -> Self {
+		Self {
+			index: *entity.index(),
+			generation: *entity.generation(),
+		}
+	}
+
+	pub fn find(self, world: &World) -> Option<Entity> {
+		world
+			.entities()
+			.into_iter()
+			.find(|entity| Self::of(*entity) == self)
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteComponent {
+	pub name: String,
+	pub fields: Vec<(String, Value)>,
+}
+
+/// An entity with its registered components, as sent to a debugger
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteEntity {
+	pub id: RemoteEntityId,
+	pub name: Option<String>,
+	pub parent: Option<RemoteEntityId>,
+	pub components: Vec<RemoteComponent>,
+}
+
+impl RemoteEntity {
+	fn take(world: &World, registry: &ComponentRegistry, entity: Entity) -> Self {
+		Self {
+			id: RemoteEntityId::of(entity),
+			name: hierarchy::name(world, entity),
+			parent: hierarchy::parent(world, entity).map(RemoteEntityId::of),
+			components: registry
+				.components_of(world, entity)
+				.map(|info| RemoteComponent {
+					name: info.name.to_string(),
+					fields: info
+						.fields(world, entity)
+						.unwrap_or_default()
+						.into_iter()
+						.map(|field| (field.name.to_string(), field.value))
+						.collect(),
+				})
+				.collect(),
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteTiming {
+	pub name: String,
+	pub last: Duration,
+	pub average: Duration,
+	pub runs: u64,
+}
+
+impl From<&SystemTiming> for RemoteTiming {
+	fn from(timing: &SystemTiming) -> Self {
+		Self {
+			name: timing.name.to_string(),
+			last: timing.last,
+			average: timing.average(),
+			runs: timing.runs,
+		}
+	}
+}
+
+/// What a debugger and an app served with `AppBuilder::serve_debugger` send each other
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DebugMessage {
+	/// Asks for the entities of the world the app's systems run on
+	Inspect,
+	SetField {
+		entity: RemoteEntityId,
+		component: String,
+		field: String,
+		value: Value,
+	},
+	World(Vec<RemoteEntity>),
+	/// Records logged since the last batch
+	Logs(Vec<LogRecord>),
+	Timings(Vec<RemoteTiming>),
+}
+
+/// The app's side of a debugger connection, serving one debugger at a time
+pub(crate) struct DebugServer {
+	requests: Receiver<Message<DebugMessage>>,
+	events: Publisher<DebugMessage>,
+	// Events are only published while a debugger is attached, so they don't pile up
+	attached: Arc<AtomicBool>,
+}
+
+impl DebugServer {
+	pub(crate) fn bind(
+		event_bus: Arc<EventBus<DebugMessage>>,
+		address: &str,
+	) -> Result<Self, RemoteBridgeError> {
+		event_bus.add_channel(DEBUG_REQUESTS)?;
+		event_bus.add_channel(DEBUG_EVENTS)?;
+		let listener = std::net::TcpListener::bind(address)?;
+		let requests = Subscriber::new(event_bus.clone(), vec![DEBUG_REQUESTS.to_string()])
+			.subscribe(None)?
+			.remove(0);
+		let attached = Arc::new(AtomicBool::new(false));
+		let bridge = RemoteBridge::new(
+			event_bus.clone(),
+			vec![DEBUG_EVENTS.to_string()],
+			vec![DEBUG_REQUESTS.to_string()],
+		);
+		let connection = attached.clone();
+		async_std::task::spawn(async move {
+			let listener = async_std::net::TcpListener::from(listener);
+			loop {
+				let stream = match listener.accept().await {
+					Ok((stream, address)) => {
+						log::info!("Debugger attached from {address}");
+						stream
+					}
+					Err(error) => {
+						log::warn!("Failed to accept a debugger: {error}");
+						continue;
+					}
+				};
+				connection.store(true, Ordering::Relaxed);
+				match bridge.run_tcp(stream).await {
+					Ok(()) => log::info!("Debugger detached"),
+					Err(error) => log::warn!("Debugger detached: {error}"),
+				}
+				connection.store(false, Ordering::Relaxed);
+			}
+		});
+		Ok(Self {
+			requests,
+			events: Publisher::new(event_bus, DEBUG_EVENTS.to_string()),
+			attached,
+		})
+	}
+
+	fn publish(&self, message: DebugMessage) {
+		if self.attached.load(Ordering::Relaxed) {
+			if let Err(error) = self.events.publish_sync("debug".to_string(), message) {
+				log::warn!("Failed to publish to the debugger: {error}");
+			}
+		}
+	}
+
+	/// Answers the requests received since the last run, from a system.
+	/// A bad request from the debugger is logged rather than failing the system.
+	pub(crate) fn handle_requests(&self, world: &mut World) {
+		let registry = world
+			.resources()
+			.borrow()
+			.get::<ComponentRegistry>()
+			.cloned()
+			.unwrap_or_default();
+		while let Ok(request) = self.requests.try_recv() {
+			match respond(world, &registry, request.payload) {
+				Ok(Some(reply)) => self.publish(reply),
+				Ok(None) => {}
+				Err(error) => log::warn!("Failed to answer the debugger: {error}"),
+			}
+		}
+	}
+}
+
+/// Streams new logs and the system timings to the debugger, from an event hook
+pub(crate) struct DebugStream {
+	server: Arc<DebugServer>,
+	timer: Option<TimerId>,
+	logged: Option<Duration>,
+}
+
+impl DebugStream {
+	pub(crate) fn new(server: Arc<DebugServer>) -> Self {
+		Self {
+			server,
+			timer: None,
+			logged: None,
+		}
+	}
+
+	pub(crate) fn on_event(&mut self, context: &mut Context, event: &AppEvent) {
+		if self.timer.is_none() {
+			self.timer = Some(context.every(STREAM_INTERVAL, STREAM_TIMER));
+		}
+		let AppEvent::Timer { timer, .. } = event else {
+			return;
+		};
+		if Some(*timer) != self.timer || !self.server.attached.load(Ordering::Relaxed) {
+			return;
+		}
+		let logs = context
+			.logs()
+			.items()
+			.into_iter()
+			.filter(|record| self.logged.is_none_or(|logged| record.elapsed > logged))
+			.collect::<Vec<_>>();
+		if let Some(last) = logs.last() {
+			self.logged = Some(last.elapsed);
+			self.server.publish(DebugMessage::Logs(logs));
+		}
+		let timings = context.system_timings().iter().map(Into::into).collect();
+		self.server.publish(DebugMessage::Timings(timings));
+	}
+}
+
+fn respond(
+	world: &mut World,
+	registry: &ComponentRegistry,
+	request: DebugMessage,
+) -> ecs::error::Result<Option<DebugMessage>> {
+	match request {
+		DebugMessage::Inspect => {
+			let entities = world
+				.entities()
+				.into_iter()
+				.map(|entity| RemoteEntity::take(world, registry, entity))
+				.collect();
+			Ok(Some(DebugMessage::World(entities)))
+		}
+		DebugMessage::SetField {
+			entity,
+			component,
+			field,
+			value,
+		} => {
+			let entity = entity.find(world).ok_or("The entity no longer exists")?;
+			let info = registry
+				.get(&component)
+				.ok_or_else(|| format!("{component} isn't registered"))?;
+			let name = info
+				.fields(world, entity)
+				.unwrap_or_default()
+				.into_iter()
+				.find(|current| current.name == field)
+				.ok_or_else(|| format!("{component} has no field {field}"))?
+				.name;
+			info.set_field(world, entity, Field { name, value })?;
+			Ok(None)
+		}
+		_ => Ok(None),
+	}
+}
+
+/// The debugger's side of a connection to an app served with `AppBuilder::serve_debugger`.
+/// Dropping the client disconnects it.
+pub struct DebugClient {
+	event_bus: Arc<EventBus<DebugMessage>>,
+	requests: Publisher<DebugMessage>,
+	events: Receiver<Message<DebugMessage>>,
+	closed: Arc<Mutex<Option<String>>>,
+}
+
+impl DebugClient {
+	pub fn connect(address: impl Into<String>) -> Result<Self, RemoteBridgeError> {
+		let address = address.into();
+		let event_bus = Arc::new(EventBus::new());
+		event_bus.add_channel(DEBUG_REQUESTS)?;
+		event_bus.add_channel(DEBUG_EVENTS)?;
+		let events = Subscriber::new(event_bus.clone(), vec![DEBUG_EVENTS.to_string()])
+			.subscribe(None)?
+			.remove(0);
+		let bridge = RemoteBridge::new(
+			event_bus.clone(),
+			vec![DEBUG_REQUESTS.to_string()],
+			vec![DEBUG_EVENTS.to_string()],
+		);
+		let closed = Arc::new(Mutex::new(None));
+		let reason = closed.clone();
+		async_std::task::spawn(async move {
+			let message = match bridge.connect_tcp(&address).await {
+				Ok(()) => format!("{address} closed the connection"),
+				Err(error) => error.to_string(),
+			};
+			*reason
+				.lock()
+				.unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(message);
+		});
+		Ok(Self {
+			requests: Publisher::new(event_bus.clone(), DEBUG_REQUESTS.to_string()),
+			event_bus,
+			events,
+			closed,
+		})
+	}
+
+	pub fn send(&self, request: DebugMessage) {
+		if let Err(error) = self.requests.publish_sync("debug".to_string(), request) {
+			log::warn!("Failed to send to the app: {error}");
+		}
+	}
+
+	/// The next message from the app, if one has arrived
+	pub fn try_recv(&self) -> Option<DebugMessage> {
+		self.events.try_recv().ok().map(|message| message.payload)
+	}
+
+	/// Why the connection ended, once it has
+	pub fn closed(&self) -> Option<String> {
+		self.closed
+			.lock()
+			.unwrap_or_else(|poisoned| poisoned.into_inner())
+			.clone()
+	}
+}
+
+impl Drop for DebugClient {
+	// Removing the outgoing channel ends the bridge, which closes the connection
+	fn drop(&mut self) {
+		let _ = self.event_bus.remove_channel(DEBUG_REQUESTS);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ecs::{hierarchy::Name, reflect};
+
+	#[derive(Debug, Default)]
+	struct Health {
+		value: u32,
+	}
+
+	reflect!(Health { value });
+
+	#[test]
+	fn debugger_inspects_and_edits_over_tcp() -> Result<(), Box<dyn std::error::Error>> {
+		let mut world = World::new();
+		let mut registry = ComponentRegistry::new();
+		registry.register::<Health>("Health");
+		world.resources().borrow_mut().insert(registry);
+		let player = world.create_entity();
+		world.add_component(player, Name("Player".to_string()))?;
+		world.add_component(player, Health { value: 100 })?;
+
+		let address = std::net::TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+		let server = DebugServer::bind(Arc::new(EventBus::new()), &address.to_string())?;
+		let client = DebugClient::connect(address.to_string())?;
+		while !server.attached.load(Ordering::Relaxed) {
+			std::thread::sleep(Duration::from_millis(1));
+		}
+
+		client.send(DebugMessage::SetField {
+			entity: RemoteEntityId::of(player),
+			component: "Health".to_string(),
+			field: "value".to_string(),
+			value: Value::Integer(25),
+		});
+		client.send(DebugMessage::Inspect);
+		let reply = loop {
+			server.handle_requests(&mut world);
+			if let Some(reply) = client.try_recv() {
+				break reply;
+			}
+			std::thread::sleep(Duration::from_millis(1));
+		};
+		let DebugMessage::World(entities) = reply else {
+			panic!("Expected the world, got {reply:?}");
+		};
+		assert_eq!(entities[0].name.as_deref(), Some("Player"));
+		assert_eq!(
+			entities[0].components[0].fields,
+			[("value".to_string(), Value::Integer(25))]
+		);
+		assert_eq!(world.get_component::<Health>(player).unwrap().value, 25);
+		Ok(())
+	}
+}
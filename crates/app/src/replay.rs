@@ -0,0 +1,209 @@
+use crate::{app::AppEvent, platform::Instant};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::VecDeque,
+	fs::File,
+	io::{self, BufWriter, Write},
+	path::Path,
+	time::Duration,
+};
+
+/// Records events to a file, or replays a recording instead of live input
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EventRecording {
+	Record { path: String },
+	Replay { path: String },
+}
+
+impl EventRecording {
+	pub fn path(&self) -> &str {
+		match self {
+			Self::Record { path } | Self::Replay { path } => path,
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+struct RecordedEvent {
+	seconds: f64,
+	event: AppEvent,
+}
+
+// Events produced by the app itself happen again on replay, so they are not recorded
+fn is_recordable(event: &AppEvent) -> bool {
+	!matches!(
+		event,
+		AppEvent::JobCompleted { .. } | AppEvent::Timer { .. } | AppEvent::Custom(_)
+	)
+}
+
+// Writes one RON encoded event per line so a crash keeps everything recorded so far
+pub(crate) struct Recorder<W: Write> {
+	writer: W,
+}
+
+impl Recorder<BufWriter<File>> {
+	pub(crate) fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+		Ok(Self {
+			writer: BufWriter::new(File::create(path)?),
+		})
+	}
+}
+
+impl<W: Write> Recorder<W> {
+	pub(crate) fn record(&mut self, elapsed: Duration, event: &AppEvent) -> io::Result<()> {
+		if !is_recordable(event) {
+			return Ok(());
+		}
+		let recorded = RecordedEvent {
+			seconds: elapsed.as_secs_f64(),
+			event: event.clone(),
+		};
+		let line = ron::to_string(&recorded)
+			.map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+		writeln!(self.writer, "{line}")?;
+		self.writer.flush()
+	}
+}
+
+pub(crate) struct Replayer {
+	events: VecDeque<RecordedEvent>,
+}
+
+impl Replayer {
+	pub(crate) fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+		Self::from_ron_lines(&std::fs::read_to_string(path)?)
+	}
+
+	fn from_ron_lines(contents: &str) -> io::Result<Self> {
+		let events = contents
+			.lines()
+			.filter(|line| !line.trim().is_empty())
+			.map(ron::from_str)
+			.collect::<Result<_, _>>()
+			.map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+		Ok(Self { events })
+	}
+
+	/// Time since the start of the replay at which the next event is due
+	pub(crate) fn next_event_time(&self) -> Option<Duration> {
+		self.events
+			.front()
+			.map(|recorded| Duration::from_secs_f64(recorded.seconds))
+	}
+
+	pub(crate) fn pop_due(&mut self, elapsed: Duration) -> Option<AppEvent> {
+		if self.next_event_time()? > elapsed {
+			return None;
+		}
+		self.events.pop_front().map(|recorded| recorded.event)
+	}
+}
+
+enum Recording {
+	Record(Recorder<BufWriter<File>>),
+	Replay(Replayer),
+}
+
+// Sits between the event loop and the worker, recording live events or replacing them with a replay
+pub(crate) struct EventSource {
+	recording: Option<Recording>,
+	start: Instant,
+}
+
+impl EventSource {
+	pub(crate) fn open(recording: Option<&EventRecording>) -> io::Result<Self> {
+		let recording = match recording {
+			Some(EventRecording::Record { path }) => {
+				Some(Recording::Record(Recorder::create(path)?))
+			}
+			Some(EventRecording::Replay { path }) => Some(Recording::Replay(Replayer::load(path)?)),
+			None => None,
+		};
+		Ok(Self {
+			recording,
+			start: Instant::now(),
+		})
+	}
+
+	/// Starts the recording or replay clock
+	pub(crate) fn start(&mut self) {
+		self.start = Instant::now();
+	}
+
+	/// Passes a live event through, dropping everything but close requests while replaying
+	pub(crate) fn live(&mut self, event: AppEvent) -> Option<AppEvent> {
+		match self.recording.as_mut() {
+			Some(Recording::Record(recorder)) => {
+				if let Err(error) = recorder.record(self.start.elapsed(), &event) {
+					log::warn!("Stopped recording events: {error}");
+					self.recording = None;
+				}
+				Some(event)
+			}
+			Some(Recording::Replay(_)) if is_recordable(&event) => {
+				matches!(event, AppEvent::CloseRequested { .. }).then_some(event)
+			}
+			_ => Some(event),
+		}
+	}
+
+	pub(crate) fn replayed(&mut self) -> Option<AppEvent> {
+		match self.recording.as_mut() {
+			Some(Recording::Replay(replayer)) => replayer.pop_due(self.start.elapsed()),
+			_ => None,
+		}
+	}
+
+	pub(crate) fn next_replay_deadline(&self) -> Option<Instant> {
+		match self.recording.as_ref() {
+			Some(Recording::Replay(replayer)) => replayer
+				.next_event_time()
+				.map(|event_time| self.start + event_time),
+			_ => None,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::WindowId;
+
+	#[test]
+	fn record_and_replay() -> io::Result<()> {
+		let mut recording = Vec::new();
+		let mut recorder = Recorder {
+			writer: &mut recording,
+		};
+		let resized = AppEvent::Resized {
+			window: WindowId::PRIMARY,
+			width: 800,
+			height: 600,
+		};
+		recorder.record(Duration::from_millis(100), &resized)?;
+		recorder.record(
+			Duration::from_millis(150),
+			&AppEvent::Custom(std::sync::Arc::new(())),
+		)?;
+		let close = AppEvent::CloseRequested {
+			window: WindowId::PRIMARY,
+		};
+		recorder.record(Duration::from_millis(200), &close)?;
+
+		let mut replayer = Replayer::from_ron_lines(std::str::from_utf8(&recording).unwrap())?;
+		assert!(replayer.pop_due(Duration::from_millis(50)).is_none());
+		assert!(matches!(
+			replayer.pop_due(Duration::from_millis(120)),
+			Some(AppEvent::Resized { width: 800, .. })
+		));
+		assert!(replayer.pop_due(Duration::from_millis(120)).is_none());
+		assert!(matches!(
+			replayer.pop_due(Duration::from_millis(250)),
+			Some(AppEvent::CloseRequested { .. })
+		));
+		assert_eq!(replayer.next_event_time(), None);
+
+		Ok(())
+	}
+}
@@ -0,0 +1,148 @@
+use crate::app::AppEvent;
+use std::{
+	any::{self, Any, TypeId},
+	collections::HashMap,
+	fmt,
+	sync::Arc,
+};
+use tokio::sync::mpsc::{error::SendError, UnboundedSender};
+use winit::window::Window;
+
+/// An application-defined request sent from a state to the main thread
+pub struct CustomRequest {
+	type_name: &'static str,
+	request: Box<dyn Any + Send + Sync>,
+}
+
+impl CustomRequest {
+	pub(crate) fn new<R: Any + Send + Sync>(request: R) -> Self {
+		Self {
+			type_name: any::type_name::<R>(),
+			request: Box::new(request),
+		}
+	}
+}
+
+impl fmt::Debug for CustomRequest {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_tuple("CustomRequest")
+			.field(&self.type_name)
+			.finish()
+	}
+}
+
+/// Main thread access for custom request handlers
+pub struct RequestContext<'a> {
+	pub(crate) window: Option<&'a Window>,
+	pub(crate) worker_sender: &'a UnboundedSender<AppEvent>,
+}
+
+impl RequestContext<'_> {
+	/// The primary window, for example to parent native dialogs
+	pub fn window(&self) -> Option<&Window> {
+		self.window
+	}
+
+	/// Replies to the states with an `AppEvent::Custom`
+	pub fn send_event(&self, event: impl Any + Send + Sync) -> Result<(), SendError<AppEvent>> {
+		self.worker_sender.send(AppEvent::Custom(Arc::new(event)))
+	}
+}
+
+type RequestHandler = Box<dyn FnMut(Box<dyn Any + Send + Sync>, &RequestContext)>;
+
+#[derive(Default)]
+pub(crate) struct RequestHandlers {
+	handlers: HashMap<TypeId, RequestHandler>,
+}
+
+impl RequestHandlers {
+	pub(crate) fn insert<R: Any + Send + Sync>(
+		&mut self,
+		mut handler: impl FnMut(R, &RequestContext) + 'static,
+	) {
+		self.handlers.insert(
+			TypeId::of::<R>(),
+			Box::new(move |request, context| {
+				if let Ok(request) = request.downcast::<R>() {
+					handler(*request, context);
+				}
+			}),
+		);
+	}
+
+	pub(crate) fn handle(&mut self, request: CustomRequest, context: &RequestContext) {
+		let CustomRequest { type_name, request } = request;
+		match self.handlers.get_mut(&(*request).type_id()) {
+			Some(handler) => handler(request, context),
+			None => log::warn!("No handler registered for custom request {type_name}"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		state::{State, StateMachine, StateResult, Transition},
+		WorkerRequest,
+	};
+	use async_trait::async_trait;
+	use tokio::sync::mpsc;
+	use winit::event_loop::EventLoopClosed;
+
+	struct OpenFile(String);
+
+	#[test]
+	fn dispatch_by_type() {
+		let (worker_sender, mut worker_receiver) = mpsc::unbounded_channel();
+		let context = RequestContext {
+			window: None,
+			worker_sender: &worker_sender,
+		};
+
+		let mut handlers = RequestHandlers::default();
+		handlers.insert(|OpenFile(path), context: &RequestContext| {
+			context.send_event(format!("opened {path}")).unwrap();
+		});
+		handlers.handle(
+			CustomRequest::new(OpenFile("scene.ron".to_string())),
+			&context,
+		);
+		handlers.handle(CustomRequest::new(42_u32), &context);
+
+		let Ok(AppEvent::Custom(reply)) = worker_receiver.try_recv() else {
+			panic!("expected a custom reply");
+		};
+		assert_eq!(
+			reply.downcast_ref::<String>().map(String::as_str),
+			Some("opened scene.ron")
+		);
+		assert!(worker_receiver.try_recv().is_err());
+	}
+
+	struct Requester;
+
+	#[async_trait]
+	impl State<(), ()> for Requester {
+		async fn update(&mut self, _context: &mut ()) -> StateResult<Transition<(), ()>> {
+			// What `Context::send_request` returns once the event loop has closed
+			let closed: Result<(), EventLoopClosed<WorkerRequest>> = Err(EventLoopClosed(
+				WorkerRequest::Custom(CustomRequest::new(OpenFile("scene.ron".to_string()))),
+			));
+			closed?;
+			Ok(Transition::None)
+		}
+	}
+
+	#[tokio::test]
+	async fn request_errors_propagate_from_states() {
+		let mut state_machine = StateMachine::new(Requester);
+		state_machine.start(&mut ()).await.unwrap();
+		let error = state_machine.update(&mut ()).await.unwrap_err();
+		let closed = error
+			.downcast_ref::<EventLoopClosed<WorkerRequest>>()
+			.expect("the request error should reach the state machine");
+		assert!(matches!(closed.0, WorkerRequest::Custom(_)));
+	}
+}
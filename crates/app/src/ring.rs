@@ -0,0 +1,102 @@
+use std::{
+	collections::VecDeque,
+	sync::{Arc, Mutex, MutexGuard},
+};
+
+const DEFAULT_CAPACITY: usize = 64;
+
+/// The most recent items pushed to it, dropping the oldest once it holds `capacity` items.
+/// Clones share the same items, so a buffer filled on one thread can be read on another.
+pub struct RingBuffer<T> {
+	items: Arc<Mutex<VecDeque<T>>>,
+	capacity: usize,
+}
+
+impl<T> RingBuffer<T> {
+	pub fn new(capacity: usize) -> Self {
+		Self {
+			items: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+			capacity,
+		}
+	}
+
+	pub fn capacity(&self) -> usize {
+		self.capacity
+	}
+
+	pub fn len(&self) -> usize {
+		self.lock().len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.lock().is_empty()
+	}
+
+	pub fn clear(&self) {
+		self.lock().clear();
+	}
+
+	pub(crate) fn push(&self, item: T) {
+		if self.capacity == 0 {
+			return;
+		}
+		let mut items = self.lock();
+		if items.len() == self.capacity {
+			items.pop_front();
+		}
+		items.push_back(item);
+	}
+
+	// A panic while pushing, such as in a logger, shouldn't disable the buffer for the rest of the app
+	fn lock(&self) -> MutexGuard<'_, VecDeque<T>> {
+		self.items
+			.lock()
+			.unwrap_or_else(|poisoned| poisoned.into_inner())
+	}
+}
+
+impl<T: Clone> RingBuffer<T> {
+	/// The buffered items, oldest first
+	pub fn items(&self) -> Vec<T> {
+		self.lock().iter().cloned().collect()
+	}
+
+	pub fn last(&self) -> Option<T> {
+		self.lock().back().cloned()
+	}
+}
+
+impl<T> Clone for RingBuffer<T> {
+	fn clone(&self) -> Self {
+		Self {
+			items: self.items.clone(),
+			capacity: self.capacity,
+		}
+	}
+}
+
+impl<T> Default for RingBuffer<T> {
+	fn default() -> Self {
+		Self::new(DEFAULT_CAPACITY)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn oldest_items_are_dropped() {
+		let buffer = RingBuffer::new(2);
+		let shared = buffer.clone();
+		for item in 1..=3 {
+			buffer.push(item);
+		}
+		assert_eq!(shared.items(), [2, 3]);
+		assert_eq!(shared.last(), Some(3));
+
+		let empty = RingBuffer::new(0);
+		empty.push(1);
+		assert!(empty.is_empty());
+	}
+}
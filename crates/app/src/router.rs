@@ -0,0 +1,314 @@
+use crate::{
+	app::{AppEvent, RawDeviceEvent, TaskResult, TextComposition},
+	config::AppConfig,
+	platform::Instant,
+	window::{Resizes, WindowHandles, WindowId},
+};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use winit::{
+	event::{DeviceEvent, ElementState, Ime, KeyEvent, RawKeyEvent, Touch, WindowEvent},
+	keyboard::PhysicalKey,
+};
+
+// Turns winit events into the `AppEvent`s sent to the worker
+pub(crate) struct EventRouter {
+	window_handles: WindowHandles,
+	resizes: Option<Resizes>,
+	raw_device_events: bool,
+	worker_sender: UnboundedSender<AppEvent>,
+}
+
+impl EventRouter {
+	pub(crate) fn new(
+		resizes: Option<Resizes>,
+		raw_device_events: bool,
+		window_handles: WindowHandles,
+		worker_sender: UnboundedSender<AppEvent>,
+	) -> Self {
+		Self {
+			window_handles,
+			resizes,
+			raw_device_events,
+			worker_sender,
+		}
+	}
+
+	pub(crate) fn window_event(&mut self, window: WindowId, event: WindowEvent) -> TaskResult {
+		let worker_sender = &self.worker_sender;
+		match event {
+			WindowEvent::CloseRequested => {
+				worker_sender.send(AppEvent::CloseRequested { window })?;
+			}
+			WindowEvent::Resized(size) => match self.resizes.as_mut() {
+				Some(resizes) => {
+					if resizes.push(window, size, Instant::now()) {
+						worker_sender.send(AppEvent::ResizeStarted { window })?;
+					}
+				}
+				None => worker_sender.send(AppEvent::Resized {
+					window,
+					width: size.width,
+					height: size.height,
+				})?,
+			},
+			WindowEvent::Focused(focused) => {
+				worker_sender.send(AppEvent::FocusChanged { window, focused })?
+			}
+			WindowEvent::Occluded(occluded) => {
+				worker_sender.send(AppEvent::Occluded { window, occluded })?
+			}
+			WindowEvent::RedrawRequested => {
+				worker_sender.send(AppEvent::RedrawRequested { window })?
+			}
+			WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+				let size = self
+					.window_handles
+					.get(window)
+					.map(|window| window.inner_size())
+					.unwrap_or_default();
+				worker_sender.send(AppEvent::ScaleFactorChanged {
+					window,
+					scale: scale_factor,
+					width: size.width,
+					height: size.height,
+				})?
+			}
+			WindowEvent::KeyboardInput {
+				event: KeyEvent {
+					physical_key,
+					text,
+					state,
+					..
+				},
+				..
+			} => {
+				let keycode = match physical_key {
+					PhysicalKey::Code(keycode) => Some(keycode),
+					PhysicalKey::Unidentified(_) => None,
+				};
+				worker_sender.send(AppEvent::KeyboardInput {
+					window,
+					keycode,
+					state,
+				})?;
+				if let Some(text) = text.filter(|text| {
+					state == ElementState::Pressed && !text.chars().any(char::is_control)
+				}) {
+					worker_sender.send(AppEvent::TextInput {
+						window,
+						text: text.to_string(),
+						composition: TextComposition::Committed,
+					})?
+				}
+			}
+			WindowEvent::Ime(Ime::Commit(text)) => worker_sender.send(AppEvent::TextInput {
+				window,
+				text,
+				composition: TextComposition::Committed,
+			})?,
+			WindowEvent::Ime(Ime::Preedit(text, cursor)) => {
+				worker_sender.send(AppEvent::TextInput {
+					window,
+					text,
+					composition: TextComposition::Composing { cursor },
+				})?
+			}
+			WindowEvent::MouseInput { button, state, .. } => {
+				worker_sender.send(AppEvent::MouseButton {
+					window,
+					button,
+					state,
+				})?
+			}
+			WindowEvent::CursorMoved { position, .. } => {
+				let scale = self
+					.window_handles
+					.get(window)
+					.map_or(1.0, |window| window.scale_factor());
+				let logical = position.to_logical::<f64>(scale);
+				worker_sender.send(AppEvent::CursorMoved {
+					window,
+					x: position.x,
+					y: position.y,
+					logical_x: logical.x,
+					logical_y: logical.y,
+				})?
+			}
+			WindowEvent::MouseWheel { delta, .. } => {
+				worker_sender.send(AppEvent::MouseWheel { window, delta })?
+			}
+			WindowEvent::Touch(Touch {
+				id,
+				phase,
+				location,
+				force,
+				..
+			}) => worker_sender.send(AppEvent::Touch {
+				window,
+				id,
+				phase,
+				x: location.x,
+				y: location.y,
+				force: force.map(|force| force.normalized()),
+			})?,
+			_ => {}
+		}
+		Ok(())
+	}
+
+	pub(crate) fn device_event(&mut self, device: u64, event: DeviceEvent) -> TaskResult {
+		if let DeviceEvent::MouseMotion { delta } = event {
+			self.worker_sender.send(AppEvent::MouseMotion {
+				delta_x: delta.0,
+				delta_y: delta.1,
+			})?;
+		}
+		if !self.raw_device_events {
+			return Ok(());
+		}
+		let event = match event {
+			DeviceEvent::MouseMotion { delta } => RawDeviceEvent::MouseMotion {
+				delta_x: delta.0,
+				delta_y: delta.1,
+			},
+			DeviceEvent::MouseWheel { delta } => RawDeviceEvent::MouseWheel { delta },
+			DeviceEvent::Button { button, state } => RawDeviceEvent::Button { button, state },
+			DeviceEvent::Key(RawKeyEvent {
+				physical_key,
+				state,
+			}) => RawDeviceEvent::Key {
+				keycode: match physical_key {
+					PhysicalKey::Code(keycode) => Some(keycode),
+					PhysicalKey::Unidentified(_) => None,
+				},
+				state,
+			},
+			_ => return Ok(()),
+		};
+		self.worker_sender
+			.send(AppEvent::RawDevice { device, event })?;
+		Ok(())
+	}
+
+	/// Sends the debounced resizes that have settled by now
+	pub(crate) fn flush_resizes(&mut self, now: Instant) -> TaskResult {
+		if let Some(resizes) = self.resizes.as_mut() {
+			for (window, size) in resizes.settled(now) {
+				self.worker_sender.send(AppEvent::Resized {
+					window,
+					width: size.width,
+					height: size.height,
+				})?;
+			}
+		}
+		Ok(())
+	}
+
+	pub(crate) fn next_resize_deadline(&self) -> Option<Instant> {
+		self.resizes.as_ref().and_then(Resizes::next_deadline)
+	}
+}
+
+/// Runs the app's event routing on synthetic winit events, on any thread and without
+/// an event loop or real windows, so tests can check the `AppEvent`s states would receive.
+/// Windows are treated as having a scale factor of 1.0.
+pub struct EventHarness {
+	router: EventRouter,
+	receiver: UnboundedReceiver<AppEvent>,
+}
+
+impl EventHarness {
+	pub fn new(config: &AppConfig) -> Self {
+		let (worker_sender, receiver) = mpsc::unbounded_channel();
+		Self {
+			router: EventRouter::new(
+				config
+					.resize_debounce_ms
+					.map(|millis| Resizes::new(Duration::from_millis(millis))),
+				config.raw_device_events,
+				WindowHandles::default(),
+				worker_sender,
+			),
+			receiver,
+		}
+	}
+
+	// The harness owns the receiver, so sending to it can't fail
+	pub fn window_event(&mut self, window: WindowId, event: WindowEvent) -> &mut Self {
+		let _ = self.router.window_event(window, event);
+		self
+	}
+
+	/// Device ids can't be created outside winit, so devices are numbered as in `AppEvent::RawDevice`
+	pub fn device_event(&mut self, device: u64, event: DeviceEvent) -> &mut Self {
+		let _ = self.router.device_event(device, event);
+		self
+	}
+
+	/// Delivers the resizes held back by `AppConfig::resize_debounce_ms` that have settled by `now`
+	pub fn settle_resizes(&mut self, now: Instant) -> &mut Self {
+		let _ = self.router.flush_resizes(now);
+		self
+	}
+
+	/// Takes the events routed so far
+	pub fn events(&mut self) -> Vec<AppEvent> {
+		let mut events = Vec::new();
+		while let Ok(event) = self.receiver.try_recv() {
+			events.push(event);
+		}
+		events
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::config::ConfigError;
+	use winit::dpi::PhysicalSize;
+
+	#[test]
+	fn window_events_are_routed() -> Result<(), ConfigError> {
+		let config = AppConfig::builder().resize_debounce_ms(Some(100)).build()?;
+		let mut harness = EventHarness::new(&config);
+		let start = Instant::now();
+		harness
+			.window_event(
+				WindowId::PRIMARY,
+				WindowEvent::Resized(PhysicalSize::new(640, 480)),
+			)
+			.window_event(
+				WindowId::PRIMARY,
+				WindowEvent::Resized(PhysicalSize::new(800, 600)),
+			)
+			.window_event(
+				WindowId::PRIMARY,
+				WindowEvent::Ime(Ime::Commit("é".to_string())),
+			)
+			.window_event(WindowId::PRIMARY, WindowEvent::CloseRequested)
+			.device_event(7, DeviceEvent::MouseMotion { delta: (2.0, -1.0) });
+
+		let events = harness.events();
+		assert!(matches!(
+			events.as_slice(),
+			[
+				AppEvent::ResizeStarted { .. },
+				AppEvent::TextInput { text, composition: TextComposition::Committed, .. },
+				AppEvent::CloseRequested { .. },
+				AppEvent::MouseMotion { delta_x: 2.0, delta_y: -1.0 },
+			] if text == "é"
+		));
+
+		harness.settle_resizes(start + Duration::from_secs(1));
+		assert!(matches!(
+			harness.events().as_slice(),
+			[AppEvent::Resized {
+				width: 800,
+				height: 600,
+				..
+			}]
+		));
+		Ok(())
+	}
+}
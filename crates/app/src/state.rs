@@ -1,6 +1,11 @@
 #![allow(dead_code)]
 
+use crate::{
+	platform::{Instant, SystemTime},
+	ring::RingBuffer,
+};
 use async_trait::async_trait;
+use std::{any::Any, collections::VecDeque, time::Duration};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -10,10 +15,16 @@ pub enum StateMachineError {
 }
 
 type Result<T, E = StateMachineError> = std::result::Result<T, E>;
-pub type StateResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+pub type StateError = dyn std::error::Error + Send + Sync;
+pub type StateResult<T> = Result<T, Box<StateError>>;
+
+/// A value handed back by a state popped with `Transition::PopWith`, downcast to read it
+pub type Payload = Box<dyn Any + Send>;
+
+pub type StateObserver = Box<dyn FnMut(&StateChange) + Send>;
 
 #[async_trait]
-pub trait State<T, E>: Send + 'static {
+pub trait State<T: 'static, E: 'static>: Send + 'static {
 	fn label(&self) -> String {
 		"Unlabeled State".to_string()
 	}
@@ -38,6 +49,54 @@ pub trait State<T, E>: Send + 'static {
 		Ok(())
 	}
 
+	// A state stacked on top of this state was popped with `Transition::PopWith`,
+	// handing back a payload such as a chosen file path. Called after `on_resume`.
+	async fn on_resume_with(&mut self, _context: &mut T, _payload: Payload) -> StateResult<()> {
+		Ok(())
+	}
+
+	// The app was suspended by the platform and its window surfaces are no longer valid
+	async fn on_suspend_app(&mut self, _context: &mut T) -> StateResult<()> {
+		Ok(())
+	}
+
+	// The app was resumed by the platform, also called once at startup.
+	// Window surfaces should be (re)created here.
+	async fn on_resume_app(&mut self, _context: &mut T) -> StateResult<()> {
+		Ok(())
+	}
+
+	// Called at the fixed update rate with the fixed timestep in seconds,
+	// for simulation that must not depend on the frame rate
+	async fn on_fixed_update(
+		&mut self,
+		_context: &mut T,
+		_delta: f64,
+	) -> StateResult<Transition<T, E>> {
+		Ok(Transition::None)
+	}
+
+	// Checked before a transition this state requested is applied.
+	// Return false to block it, such as a `Pop` or `Quit` while there are unsaved changes.
+	fn can_transition(&self, _transition: &Transition<T, E>) -> bool {
+		true
+	}
+
+	// A transition was blocked by `can_transition`. The returned transition is applied
+	// instead without being checked again, such as pushing a confirmation dialog state.
+	async fn on_transition_blocked(
+		&mut self,
+		_context: &mut T,
+		_transition: Transition<T, E>,
+	) -> StateResult<Transition<T, E>> {
+		Ok(Transition::None)
+	}
+
+	// Called once per frame after the updates, for drawing
+	async fn on_render(&mut self, _context: &mut T, _frame: Frame) -> StateResult<()> {
+		Ok(())
+	}
+
 	// Main function for states, called every loop
 	async fn update(&mut self, _context: &mut T) -> StateResult<Transition<T, E>> {
 		Ok(Transition::None)
@@ -54,17 +113,184 @@ pub trait State<T, E>: Send + 'static {
 	}
 }
 
+/// Passed to `State::on_render` each frame
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Frame {
+	pub index: u64,
+	/// How far the simulation is between the last fixed update and the next, from 0.0 to 1.0.
+	/// Renderers interpolate between the last two simulated states with it.
+	pub alpha: f64,
+}
+
 pub enum Transition<T, E> {
 	None,
 	Pop,
+	/// Pops the state and passes the payload to `State::on_resume_with` of the state below
+	PopWith(Payload),
 	Push(Box<dyn State<T, E>>),
 	Switch(Box<dyn State<T, E>>),
+	/// Stops every state on the stack and starts this one, such as when returning to a main menu
+	ReplaceAll(Box<dyn State<T, E>>),
+	/// Applies each transition in order before the next update
+	Multi(Vec<Transition<T, E>>),
 	Quit,
 }
 
+impl<T, E> Transition<T, E> {
+	pub fn pop_with(payload: impl Any + Send) -> Self {
+		Self::PopWith(Box::new(payload))
+	}
+}
+
+/// Wraps a state so it requests a transition once it has been active for a duration,
+/// such as a splash screen switching to the main menu. Time spent suspended under
+/// another state doesn't count. The duration is checked on update, so states using
+/// `UpdateMode::Reactive` should request updates while they wait.
+pub struct Timed<T, E> {
+	state: Box<dyn State<T, E>>,
+	duration: Duration,
+	then: Option<Transition<T, E>>,
+	elapsed: Duration,
+	resumed_at: Option<Instant>,
+}
+
+impl<T: 'static, E: 'static> Timed<T, E> {
+	pub fn new(state: impl State<T, E>, duration: Duration, then: Transition<T, E>) -> Self {
+		Self {
+			state: Box::new(state),
+			duration,
+			then: Some(then),
+			elapsed: Duration::ZERO,
+			resumed_at: None,
+		}
+	}
+
+	/// How long the state has been active
+	pub fn elapsed(&self) -> Duration {
+		self.elapsed
+			+ self
+				.resumed_at
+				.map_or(Duration::ZERO, |resumed_at| resumed_at.elapsed())
+	}
+
+	fn pause(&mut self) {
+		if let Some(resumed_at) = self.resumed_at.take() {
+			self.elapsed += resumed_at.elapsed();
+		}
+	}
+}
+
+#[async_trait]
+impl<T: Send + 'static, E: Send + 'static> State<T, E> for Timed<T, E> {
+	fn label(&self) -> String {
+		self.state.label()
+	}
+
+	async fn on_start(&mut self, context: &mut T) -> StateResult<()> {
+		self.elapsed = Duration::ZERO;
+		self.resumed_at = Some(Instant::now());
+		self.state.on_start(context).await
+	}
+
+	async fn on_suspend(&mut self, context: &mut T) -> StateResult<()> {
+		self.pause();
+		self.state.on_suspend(context).await
+	}
+
+	async fn on_stop(&mut self, context: &mut T) -> StateResult<()> {
+		self.pause();
+		self.state.on_stop(context).await
+	}
+
+	async fn on_resume(&mut self, context: &mut T) -> StateResult<()> {
+		self.resumed_at = Some(Instant::now());
+		self.state.on_resume(context).await
+	}
+
+	async fn on_resume_with(&mut self, context: &mut T, payload: Payload) -> StateResult<()> {
+		self.state.on_resume_with(context, payload).await
+	}
+
+	async fn on_suspend_app(&mut self, context: &mut T) -> StateResult<()> {
+		self.state.on_suspend_app(context).await
+	}
+
+	async fn on_resume_app(&mut self, context: &mut T) -> StateResult<()> {
+		self.state.on_resume_app(context).await
+	}
+
+	async fn on_fixed_update(
+		&mut self,
+		context: &mut T,
+		delta: f64,
+	) -> StateResult<Transition<T, E>> {
+		self.state.on_fixed_update(context, delta).await
+	}
+
+	fn can_transition(&self, transition: &Transition<T, E>) -> bool {
+		self.state.can_transition(transition)
+	}
+
+	async fn on_transition_blocked(
+		&mut self,
+		context: &mut T,
+		transition: Transition<T, E>,
+	) -> StateResult<Transition<T, E>> {
+		self.state.on_transition_blocked(context, transition).await
+	}
+
+	async fn on_render(&mut self, context: &mut T, frame: Frame) -> StateResult<()> {
+		self.state.on_render(context, frame).await
+	}
+
+	async fn update(&mut self, context: &mut T) -> StateResult<Transition<T, E>> {
+		let transition = self.state.update(context).await?;
+		if !matches!(transition, Transition::None) {
+			return Ok(transition);
+		}
+		if self.elapsed() < self.duration {
+			return Ok(Transition::None);
+		}
+		Ok(self.then.take().unwrap_or(Transition::None))
+	}
+
+	async fn on_event(&mut self, context: &mut T, event: &mut E) -> StateResult<Transition<T, E>> {
+		self.state.on_event(context, event).await
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateChangeKind {
+	Start,
+	Push,
+	Pop,
+	Switch,
+	ReplaceAll,
+	Stop,
+}
+
+/// Describes a change to the state stack, passed to observers added with `StateMachine::observe`
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateChange {
+	pub kind: StateChangeKind,
+	/// Label of the state that was active before the change
+	pub from: Option<String>,
+	/// Label of the state that is active after the change
+	pub to: Option<String>,
+	/// Labels of every state on the stack after the change, bottom first
+	pub stack: Vec<String>,
+	pub timestamp: SystemTime,
+}
+
+/// The most recent changes to the state stack, shared with debug overlays through `Context`
+pub type StateHistory = RingBuffer<StateChange>;
+
 pub struct StateMachine<T, E> {
 	running: bool,
 	states: Vec<Box<dyn State<T, E>>>,
+	overlays: Vec<Box<dyn State<T, E>>>,
+	observers: Vec<StateObserver>,
+	history: StateHistory,
 }
 
 impl<T: 'static, E: 'static> StateMachine<T, E> {
@@ -72,9 +298,67 @@ impl<T: 'static, E: 'static> StateMachine<T, E> {
 		Self {
 			running: false,
 			states: vec![Box::new(initial_state)],
+			overlays: Vec::new(),
+			observers: Vec::new(),
+			history: StateHistory::default(),
 		}
 	}
 
+	/// Replaces the history, such as with one of a different capacity
+	pub fn with_history(mut self, history: StateHistory) -> Self {
+		self.history = history;
+		self
+	}
+
+	pub fn history(&self) -> &StateHistory {
+		&self.history
+	}
+
+	/// Labels of every state on the stack, bottom first
+	pub fn stack_labels(&self) -> Vec<String> {
+		self.states.iter().map(|state| state.label()).collect()
+	}
+
+	/// How many states are on the stack, not counting overlays
+	pub fn depth(&self) -> usize {
+		self.states.len()
+	}
+
+	/// Calls the observer whenever states are started, pushed, popped, switched or stopped
+	pub fn observe(&mut self, observer: impl FnMut(&StateChange) + Send + 'static) {
+		self.observers.push(Box::new(observer));
+	}
+
+	/// Starts a state that runs alongside the stack no matter which state is active,
+	/// such as a debug console. Overlays get events before the active state and
+	/// are updated and rendered after it, in the order they were added.
+	/// Their transitions apply to the stack.
+	pub async fn add_overlay(
+		&mut self,
+		mut overlay: Box<dyn State<T, E>>,
+		context: &mut T,
+	) -> StateResult<()> {
+		if !self.running {
+			return Ok(());
+		}
+		overlay.on_start(context).await?;
+		self.overlays.push(overlay);
+		Ok(())
+	}
+
+	/// Stops the first overlay with the label, returning whether one was found
+	pub async fn remove_overlay(&mut self, label: &str, context: &mut T) -> StateResult<bool> {
+		let Some(index) = self
+			.overlays
+			.iter()
+			.position(|overlay| overlay.label() == label)
+		else {
+			return Ok(false);
+		};
+		self.overlays.remove(index).on_stop(context).await?;
+		Ok(true)
+	}
+
 	pub async fn active_state_label(&self) -> Option<String> {
 		if !self.running {
 			return None;
@@ -82,7 +366,7 @@ impl<T: 'static, E: 'static> StateMachine<T, E> {
 		self.states.last().map(|state| state.label())
 	}
 
-	pub async fn is_running(&self) -> bool {
+	pub fn is_running(&self) -> bool {
 		self.running
 	}
 
@@ -91,10 +375,19 @@ impl<T: 'static, E: 'static> StateMachine<T, E> {
 			return Ok(());
 		}
 		self.running = true;
-		self.active_state_mut()?.on_start(context).await
+		self.active_state_mut()?.on_start(context).await?;
+		self.notify(StateChangeKind::Start, None);
+		Ok(())
 	}
 
 	pub async fn on_event(&mut self, context: &mut T, event: &mut E) -> StateResult<()> {
+		for index in 0..self.overlays.len() {
+			if !self.running {
+				return Ok(());
+			}
+			let transition = self.overlays[index].on_event(context, event).await?;
+			self.transition(transition, context).await?;
+		}
 		if !self.running {
 			return Ok(());
 		}
@@ -107,23 +400,112 @@ impl<T: 'static, E: 'static> StateMachine<T, E> {
 			return Ok(());
 		}
 		let transition = self.active_state_mut()?.update(context).await?;
+		self.transition(transition, context).await?;
+		for index in 0..self.overlays.len() {
+			if !self.running {
+				return Ok(());
+			}
+			let transition = self.overlays[index].update(context).await?;
+			self.transition(transition, context).await?;
+		}
+		Ok(())
+	}
+
+	// Every state on the stack is notified since any of them may own a surface
+	pub async fn on_suspend_app(&mut self, context: &mut T) -> StateResult<()> {
+		for state in self.states.iter_mut().chain(self.overlays.iter_mut()) {
+			state.on_suspend_app(context).await?;
+		}
+		Ok(())
+	}
+
+	pub async fn on_resume_app(&mut self, context: &mut T) -> StateResult<()> {
+		for state in self.states.iter_mut().chain(self.overlays.iter_mut()) {
+			state.on_resume_app(context).await?;
+		}
+		Ok(())
+	}
+
+	pub async fn fixed_update(&mut self, context: &mut T, delta: f64) -> StateResult<()> {
+		if !self.running {
+			return Ok(());
+		}
+		let transition = self
+			.active_state_mut()?
+			.on_fixed_update(context, delta)
+			.await?;
 		self.transition(transition, context).await
 	}
 
-	async fn transition(&mut self, request: Transition<T, E>, context: &mut T) -> StateResult<()> {
+	pub async fn render(&mut self, context: &mut T, frame: Frame) -> StateResult<()> {
 		if !self.running {
 			return Ok(());
 		}
-		match request {
-			Transition::None => Ok(()),
-			Transition::Pop => self.pop(context).await,
-			Transition::Push(state) => self.push(state, context).await,
-			Transition::Switch(state) => self.switch(state, context).await,
-			Transition::Quit => self.stop(context).await,
+		self.active_state_mut()?.on_render(context, frame).await?;
+		for overlay in self.overlays.iter_mut() {
+			overlay.on_render(context, frame).await?;
+		}
+		Ok(())
+	}
+
+	pub(crate) async fn transition(
+		&mut self,
+		request: Transition<T, E>,
+		context: &mut T,
+	) -> StateResult<()> {
+		self.apply(request, context, true).await
+	}
+
+	// Applies a transition the active state didn't request, such as an error recovery,
+	// without checking it against the state's `can_transition` guard
+	pub(crate) async fn force_transition(
+		&mut self,
+		request: Transition<T, E>,
+		context: &mut T,
+	) -> StateResult<()> {
+		self.apply(request, context, false).await
+	}
+
+	async fn apply(
+		&mut self,
+		request: Transition<T, E>,
+		context: &mut T,
+		guarded: bool,
+	) -> StateResult<()> {
+		// Transitions are paired with whether the active state's guard should check them
+		let mut pending = VecDeque::from([(request, guarded)]);
+		while let Some((request, guarded)) = pending.pop_front() {
+			if !self.running {
+				return Ok(());
+			}
+			let blockable = !matches!(request, Transition::None | Transition::Multi(_));
+			if guarded && blockable && !self.active_state_mut()?.can_transition(&request) {
+				let replacement = self
+					.active_state_mut()?
+					.on_transition_blocked(context, request)
+					.await?;
+				pending.push_front((replacement, false));
+				continue;
+			}
+			match request {
+				Transition::None => {}
+				Transition::Pop => self.pop(context, None).await?,
+				Transition::PopWith(payload) => self.pop(context, Some(payload)).await?,
+				Transition::Push(state) => self.push(state, context).await?,
+				Transition::Switch(state) => self.switch(state, context).await?,
+				Transition::ReplaceAll(state) => self.replace_all(state, context).await?,
+				Transition::Multi(transitions) => {
+					for transition in transitions.into_iter().rev() {
+						pending.push_front((transition, guarded));
+					}
+				}
+				Transition::Quit => self.stop(context).await?,
+			}
 		}
+		Ok(())
 	}
 
-	fn active_state_mut(&mut self) -> Result<&mut Box<(dyn State<T, E> + 'static)>> {
+	fn active_state_mut(&mut self) -> Result<&mut Box<dyn State<T, E> + 'static>> {
 		self.states
 			.last_mut()
 			.ok_or(StateMachineError::NoStatesPresent)
@@ -133,49 +515,106 @@ impl<T: 'static, E: 'static> StateMachine<T, E> {
 		if !self.running {
 			return Ok(());
 		}
+		let from = self.top_label();
 		if let Some(mut state) = self.states.pop() {
 			state.on_stop(context).await?;
 		}
 		self.states.push(state);
-		self.active_state_mut()?.on_start(context).await
+		self.active_state_mut()?.on_start(context).await?;
+		self.notify(StateChangeKind::Switch, from);
+		Ok(())
 	}
 
-	async fn push(&mut self, state: Box<dyn State<T, E>>, context: &mut T) -> StateResult<()> {
+	async fn replace_all(
+		&mut self,
+		state: Box<dyn State<T, E>>,
+		context: &mut T,
+	) -> StateResult<()> {
+		let from = self.top_label();
+		while let Some(mut state) = self.states.pop() {
+			state.on_stop(context).await?;
+		}
+		self.states.push(state);
+		self.active_state_mut()?.on_start(context).await?;
+		self.notify(StateChangeKind::ReplaceAll, from);
+		Ok(())
+	}
+
+	pub(crate) async fn push(
+		&mut self,
+		state: Box<dyn State<T, E>>,
+		context: &mut T,
+	) -> StateResult<()> {
 		if !self.running {
 			return Ok(());
 		}
+		let from = self.top_label();
 		if let Ok(state) = self.active_state_mut() {
 			state.on_suspend(context).await?;
 		}
 		self.states.push(state);
-		self.active_state_mut()?.on_start(context).await
+		self.active_state_mut()?.on_start(context).await?;
+		self.notify(StateChangeKind::Push, from);
+		Ok(())
 	}
 
-	async fn pop(&mut self, context: &mut T) -> StateResult<()> {
+	async fn pop(&mut self, context: &mut T, payload: Option<Payload>) -> StateResult<()> {
 		if !self.running {
 			return Ok(());
 		}
 
+		let from = self.top_label();
 		if let Some(mut state) = self.states.pop() {
 			state.on_stop(context).await?;
 		}
 
-		if let Some(state) = self.states.last_mut() {
-			state.on_resume(context).await
-		} else {
-			self.running = false;
-			Ok(())
+		let Some(state) = self.states.last_mut() else {
+			self.notify(StateChangeKind::Pop, from);
+			return self.stop_overlays(context).await;
+		};
+		state.on_resume(context).await?;
+		if let Some(payload) = payload {
+			state.on_resume_with(context, payload).await?;
 		}
+		self.notify(StateChangeKind::Pop, from);
+		Ok(())
 	}
 
 	pub async fn stop(&mut self, context: &mut T) -> StateResult<()> {
 		if !self.running {
 			return Ok(());
 		}
+		let from = self.top_label();
 		while let Some(mut state) = self.states.pop() {
 			state.on_stop(context).await?;
 		}
+		self.notify(StateChangeKind::Stop, from);
+		self.stop_overlays(context).await
+	}
+
+	fn top_label(&self) -> Option<String> {
+		self.states.last().map(|state| state.label())
+	}
+
+	fn notify(&mut self, kind: StateChangeKind, from: Option<String>) {
+		let change = StateChange {
+			kind,
+			from,
+			to: self.top_label(),
+			stack: self.stack_labels(),
+			timestamp: SystemTime::now(),
+		};
+		for observer in self.observers.iter_mut() {
+			observer(&change);
+		}
+		self.history.push(change);
+	}
+
+	async fn stop_overlays(&mut self, context: &mut T) -> StateResult<()> {
 		self.running = false;
+		while let Some(mut overlay) = self.overlays.pop() {
+			overlay.on_stop(context).await?;
+		}
 		Ok(())
 	}
 }
@@ -211,6 +650,12 @@ mod tests {
 			*counter += 1;
 			Ok(())
 		}
+
+		async fn on_resume_app(&mut self, _context: &mut ()) -> StateResult<()> {
+			let mut counter = self.counter.lock().await;
+			*counter += 10;
+			Ok(())
+		}
 	}
 
 	#[tokio::test]
@@ -219,7 +664,7 @@ mod tests {
 		let state = MockState::new("TestState", counter.clone());
 		let state_machine = StateMachine::new(state);
 
-		assert!(!state_machine.is_running().await);
+		assert!(!state_machine.is_running());
 		assert_eq!(state_machine.active_state_label().await, None);
 	}
 
@@ -231,7 +676,7 @@ mod tests {
 
 		state_machine.start(&mut ()).await.unwrap();
 
-		assert!(state_machine.is_running().await);
+		assert!(state_machine.is_running());
 		assert_eq!(
 			state_machine.active_state_label().await,
 			Some("TestState".to_string())
@@ -278,7 +723,290 @@ mod tests {
 		state_machine.start(&mut ()).await.unwrap();
 		state_machine.stop(&mut ()).await.unwrap();
 
-		assert!(!state_machine.is_running().await);
+		assert!(!state_machine.is_running());
 		assert_eq!(state_machine.active_state_label().await, None);
 	}
+
+	#[tokio::test]
+	async fn test_replace_all_clears_the_stack() {
+		let counter = Arc::new(Mutex::new(0));
+		let mut state_machine = StateMachine::new(MockState::new("Menu", counter.clone()));
+		state_machine.start(&mut ()).await.unwrap();
+		state_machine
+			.push(Box::new(MockState::new("Game", counter.clone())), &mut ())
+			.await
+			.unwrap();
+
+		let menu = MockState::new("NewMenu", counter.clone());
+		state_machine
+			.transition(Transition::ReplaceAll(Box::new(menu)), &mut ())
+			.await
+			.unwrap();
+		assert_eq!(state_machine.states.len(), 1);
+		assert_eq!(
+			state_machine.active_state_label().await,
+			Some("NewMenu".to_string())
+		);
+
+		state_machine
+			.transition(Transition::Pop, &mut ())
+			.await
+			.unwrap();
+		assert!(!state_machine.is_running());
+	}
+
+	#[tokio::test]
+	async fn test_multi_transition_applies_in_order() {
+		let counter = Arc::new(Mutex::new(0));
+		let mut state_machine = StateMachine::new(MockState::new("Base", counter.clone()));
+		state_machine.start(&mut ()).await.unwrap();
+		state_machine
+			.push(Box::new(MockState::new("Picker", counter.clone())), &mut ())
+			.await
+			.unwrap();
+
+		let editor = MockState::new("Editor", counter.clone());
+		state_machine
+			.transition(
+				Transition::Multi(vec![Transition::Pop, Transition::Push(Box::new(editor))]),
+				&mut (),
+			)
+			.await
+			.unwrap();
+		assert_eq!(state_machine.stack_labels(), ["Base", "Editor"]);
+		assert_eq!(state_machine.depth(), 2);
+	}
+
+	#[tokio::test]
+	async fn test_history_keeps_recent_changes() {
+		let counter = Arc::new(Mutex::new(0));
+		let mut state_machine = StateMachine::new(MockState::new("Menu", counter.clone()))
+			.with_history(StateHistory::new(2));
+		state_machine.start(&mut ()).await.unwrap();
+		for label in ["Game", "Pause"] {
+			let state = MockState::new(label, counter.clone());
+			state_machine
+				.transition(Transition::Push(Box::new(state)), &mut ())
+				.await
+				.unwrap();
+		}
+
+		let kinds = state_machine
+			.history()
+			.items()
+			.iter()
+			.map(|change| (change.kind, change.to.clone()))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			kinds,
+			[
+				(StateChangeKind::Push, Some("Game".to_string())),
+				(StateChangeKind::Push, Some("Pause".to_string())),
+			]
+		);
+	}
+
+	#[tokio::test]
+	async fn test_observers_see_stack_changes() {
+		let counter = Arc::new(Mutex::new(0));
+		let mut state_machine = StateMachine::new(MockState::new("Menu", counter.clone()));
+		let changes = Arc::new(std::sync::Mutex::new(Vec::new()));
+		let observed = changes.clone();
+		state_machine.observe(move |change| observed.lock().unwrap().push(change.clone()));
+
+		state_machine.start(&mut ()).await.unwrap();
+		let game = MockState::new("Game", counter.clone());
+		state_machine
+			.transition(
+				Transition::Multi(vec![Transition::Push(Box::new(game)), Transition::Pop]),
+				&mut (),
+			)
+			.await
+			.unwrap();
+		state_machine.stop(&mut ()).await.unwrap();
+
+		let changes = changes.lock().unwrap();
+		let summary = changes
+			.iter()
+			.map(|change| (change.kind, change.from.as_deref(), change.to.as_deref()))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			summary,
+			[
+				(StateChangeKind::Start, None, Some("Menu")),
+				(StateChangeKind::Push, Some("Menu"), Some("Game")),
+				(StateChangeKind::Pop, Some("Game"), Some("Menu")),
+				(StateChangeKind::Stop, Some("Menu"), None),
+			]
+		);
+		assert_eq!(changes[1].stack, ["Menu", "Game"]);
+	}
+
+	#[tokio::test]
+	async fn test_timed_state_transitions_after_duration() {
+		let counter = Arc::new(Mutex::new(0));
+		let mut state_machine = StateMachine::new(MockState::new("Menu", counter.clone()));
+		state_machine.start(&mut ()).await.unwrap();
+
+		let splash = Timed::new(
+			MockState::new("Splash", counter.clone()),
+			Duration::from_secs(60),
+			Transition::Pop,
+		);
+		state_machine.push(Box::new(splash), &mut ()).await.unwrap();
+		state_machine.update(&mut ()).await.unwrap();
+		assert_eq!(state_machine.stack_labels(), ["Menu", "Splash"]);
+
+		let toast = Timed::new(
+			MockState::new("Toast", counter.clone()),
+			Duration::ZERO,
+			Transition::Pop,
+		);
+		state_machine
+			.transition(Transition::Switch(Box::new(toast)), &mut ())
+			.await
+			.unwrap();
+		state_machine.update(&mut ()).await.unwrap();
+		assert_eq!(state_machine.stack_labels(), ["Menu"]);
+	}
+
+	struct FileBrowser {
+		chosen_path: Arc<Mutex<Option<String>>>,
+	}
+
+	#[async_trait]
+	impl State<(), ()> for FileBrowser {
+		async fn on_resume_with(&mut self, _context: &mut (), payload: Payload) -> StateResult<()> {
+			*self.chosen_path.lock().await = payload.downcast::<String>().ok().map(|path| *path);
+			Ok(())
+		}
+	}
+
+	#[tokio::test]
+	async fn test_pop_with_payload() {
+		let chosen_path = Arc::new(Mutex::new(None));
+		let mut state_machine = StateMachine::new(FileBrowser {
+			chosen_path: chosen_path.clone(),
+		});
+		state_machine.start(&mut ()).await.unwrap();
+		state_machine
+			.push(
+				Box::new(MockState::new("Picker", Arc::new(Mutex::new(0)))),
+				&mut (),
+			)
+			.await
+			.unwrap();
+
+		state_machine
+			.transition(Transition::pop_with("level.ron".to_string()), &mut ())
+			.await
+			.unwrap();
+		assert_eq!(chosen_path.lock().await.as_deref(), Some("level.ron"));
+	}
+
+	struct Document {
+		unsaved_changes: bool,
+	}
+
+	#[async_trait]
+	impl State<(), ()> for Document {
+		fn label(&self) -> String {
+			"Document".to_string()
+		}
+
+		fn can_transition(&self, transition: &Transition<(), ()>) -> bool {
+			!(self.unsaved_changes && matches!(transition, Transition::Pop | Transition::Quit))
+		}
+
+		async fn on_transition_blocked(
+			&mut self,
+			_context: &mut (),
+			_transition: Transition<(), ()>,
+		) -> StateResult<Transition<(), ()>> {
+			let dialog = MockState::new("ConfirmDialog", Arc::new(Mutex::new(0)));
+			Ok(Transition::Push(Box::new(dialog)))
+		}
+	}
+
+	#[tokio::test]
+	async fn test_guard_blocks_transition() {
+		let mut state_machine = StateMachine::new(Document {
+			unsaved_changes: true,
+		});
+		state_machine.start(&mut ()).await.unwrap();
+
+		state_machine
+			.transition(Transition::Quit, &mut ())
+			.await
+			.unwrap();
+		assert!(state_machine.is_running());
+		assert_eq!(
+			state_machine.active_state_label().await,
+			Some("ConfirmDialog".to_string())
+		);
+	}
+
+	struct Console {
+		updates: Arc<Mutex<Vec<String>>>,
+	}
+
+	#[async_trait]
+	impl State<(), ()> for Console {
+		fn label(&self) -> String {
+			"Console".to_string()
+		}
+
+		async fn update(&mut self, _context: &mut ()) -> StateResult<Transition<(), ()>> {
+			self.updates.lock().await.push("Console".to_string());
+			Ok(Transition::None)
+		}
+	}
+
+	#[tokio::test]
+	async fn test_overlays_update_after_active_state() {
+		let counter = Arc::new(Mutex::new(0));
+		let updates = Arc::new(Mutex::new(Vec::new()));
+		let mut state_machine = StateMachine::new(MockState::new("Game", counter.clone()));
+		state_machine.start(&mut ()).await.unwrap();
+		state_machine
+			.add_overlay(
+				Box::new(Console {
+					updates: updates.clone(),
+				}),
+				&mut (),
+			)
+			.await
+			.unwrap();
+		state_machine
+			.push(Box::new(MockState::new("Pause", counter)), &mut ())
+			.await
+			.unwrap();
+
+		state_machine.update(&mut ()).await.unwrap();
+		assert_eq!(*updates.lock().await, ["Console"]);
+
+		assert!(state_machine
+			.remove_overlay("Console", &mut ())
+			.await
+			.unwrap());
+		state_machine.update(&mut ()).await.unwrap();
+		assert_eq!(updates.lock().await.len(), 1);
+	}
+
+	#[tokio::test]
+	async fn test_resume_app_notifies_all_states() {
+		let counter = Arc::new(Mutex::new(0));
+		let state = MockState::new("TestState", counter.clone());
+		let mut state_machine = StateMachine::new(state);
+
+		state_machine.start(&mut ()).await.unwrap();
+		let state2 = MockState::new("TestState2", counter.clone());
+		state_machine
+			.transition(Transition::Push(Box::new(state2)), &mut ())
+			.await
+			.unwrap();
+
+		state_machine.on_resume_app(&mut ()).await.unwrap();
+		assert_eq!(*counter.lock().await, 22);
+	}
 }
@@ -0,0 +1,278 @@
+use crate::config::{AppConfig, BackgroundMode, PresentMode, UpdateMode};
+use crate::platform::{self, Instant};
+use std::time::Duration;
+
+// Weight of the newest frame in the smoothed frame rate
+const FPS_SMOOTHING: f64 = 0.1;
+
+// Fixed steps beyond this are dropped so a slow frame can't snowball into slower ones
+const MAX_FIXED_STEPS_PER_FRAME: u32 = 8;
+
+/// Timing of the current frame, updated by the worker before states are updated
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct FrameTiming {
+	delta_seconds: f64,
+	smoothed_delta_seconds: f64,
+	frame_index: u64,
+}
+
+impl FrameTiming {
+	/// Seconds elapsed since the previous frame started
+	pub fn delta_seconds(&self) -> f64 {
+		self.delta_seconds
+	}
+
+	/// Frames per second averaged over recent frames
+	pub fn fps(&self) -> f64 {
+		if self.smoothed_delta_seconds > 0.0 {
+			1.0 / self.smoothed_delta_seconds
+		} else {
+			0.0
+		}
+	}
+
+	/// Number of frames started before this one
+	pub fn frame_index(&self) -> u64 {
+		self.frame_index
+	}
+
+	fn advance(&mut self, elapsed: Duration) {
+		let delta_seconds = elapsed.as_secs_f64();
+		if self.delta_seconds > 0.0 {
+			self.frame_index += 1;
+			self.smoothed_delta_seconds +=
+				(delta_seconds - self.smoothed_delta_seconds) * FPS_SMOOTHING;
+		} else {
+			self.smoothed_delta_seconds = delta_seconds;
+		}
+		self.delta_seconds = delta_seconds;
+	}
+}
+
+// Paces the worker loop and decides how many fixed updates each frame runs
+pub(crate) struct FrameLoop {
+	update_mode: UpdateMode,
+	present_mode: PresentMode,
+	background_mode: BackgroundMode,
+	in_background: bool,
+	frame_duration: Option<Duration>,
+	fixed_timestep: Duration,
+	accumulator: Duration,
+	frame_start: Instant,
+	lateness: Duration,
+	timing: FrameTiming,
+}
+
+impl FrameLoop {
+	pub(crate) fn new(config: &AppConfig) -> Self {
+		Self {
+			update_mode: config.update_mode,
+			present_mode: config.present_mode,
+			background_mode: config.background_mode,
+			in_background: false,
+			frame_duration: config.update_rate.map(rate_to_duration),
+			fixed_timestep: rate_to_duration(config.fixed_update_rate),
+			accumulator: Duration::ZERO,
+			frame_start: Instant::now(),
+			lateness: Duration::ZERO,
+			timing: FrameTiming::default(),
+		}
+	}
+
+	pub(crate) fn fixed_timestep(&self) -> Duration {
+		self.fixed_timestep
+	}
+
+	pub(crate) fn frame_duration(&self) -> Option<Duration> {
+		self.frame_duration
+	}
+
+	pub(crate) fn set_update_rate(&mut self, update_rate: Option<f64>) {
+		self.frame_duration = update_rate.map(rate_to_duration);
+	}
+
+	pub(crate) fn update_mode(&self) -> UpdateMode {
+		self.update_mode
+	}
+
+	pub(crate) fn set_in_background(&mut self, in_background: bool) {
+		self.in_background = in_background;
+	}
+
+	/// Whether the worker should idle until an event arrives before the next frame
+	pub(crate) fn waits_for_events(&self) -> bool {
+		self.update_mode == UpdateMode::Reactive
+			|| (self.in_background && self.background_mode == BackgroundMode::Pause)
+	}
+
+	// The frame limit, lowered while throttled in the background
+	fn paced_frame_duration(&self) -> Option<Duration> {
+		match self.background_mode {
+			BackgroundMode::Throttle(rate) if self.in_background => {
+				let throttled = rate_to_duration(rate);
+				Some(
+					self.frame_duration
+						.map_or(throttled, |duration| duration.max(throttled)),
+				)
+			}
+			_ => self.frame_duration,
+		}
+	}
+
+	pub(crate) fn present_mode(&self) -> PresentMode {
+		self.present_mode
+	}
+
+	/// Restarts timing after idling so the wait isn't simulated as elapsed time
+	pub(crate) fn reset(&mut self) {
+		self.frame_start = Instant::now();
+		self.accumulator = Duration::ZERO;
+	}
+
+	pub(crate) fn timing(&self) -> FrameTiming {
+		self.timing
+	}
+
+	/// How much longer than its frame limit the previous frame took
+	pub(crate) fn lateness(&self) -> Duration {
+		self.lateness
+	}
+
+	/// The fraction of a fixed timestep left over after this frame's fixed updates
+	pub(crate) fn fixed_alpha(&self) -> f64 {
+		self.accumulator.as_secs_f64() / self.fixed_timestep.as_secs_f64()
+	}
+
+	/// Starts a new frame, returning the number of fixed updates to run
+	pub(crate) fn begin_frame(&mut self) -> u32 {
+		let now = Instant::now();
+		let elapsed = now - self.frame_start;
+		self.frame_start = now;
+		self.lateness = elapsed.saturating_sub(self.paced_frame_duration().unwrap_or_default());
+		self.timing.advance(elapsed);
+		self.accumulate(elapsed)
+	}
+
+	/// Waits out the remainder of the frame, or just yields when uncapped
+	#[cfg(not(target_arch = "wasm32"))]
+	pub(crate) async fn end_frame(&self) {
+		match self.paced_frame_duration() {
+			Some(frame_duration) => platform::sleep_until(self.frame_start + frame_duration).await,
+			None => tokio::task::yield_now().await,
+		}
+	}
+
+	/// Waits for animation frames until the remainder of the frame has passed
+	#[cfg(target_arch = "wasm32")]
+	pub(crate) async fn end_frame(&self) {
+		let frame_end = self.frame_start + self.paced_frame_duration().unwrap_or_default();
+		loop {
+			platform::next_animation_frame().await;
+			if Instant::now() >= frame_end {
+				break;
+			}
+		}
+	}
+
+	fn accumulate(&mut self, elapsed: Duration) -> u32 {
+		self.accumulator += elapsed;
+		let mut steps = 0;
+		while self.accumulator >= self.fixed_timestep {
+			if steps == MAX_FIXED_STEPS_PER_FRAME {
+				self.accumulator = Duration::ZERO;
+				break;
+			}
+			self.accumulator -= self.fixed_timestep;
+			steps += 1;
+		}
+		steps
+	}
+}
+
+// Config validation rejects zero, negative and non-finite rates, but `Context::set_update_rate`
+// doesn't go through it, so those and rates too small to represent fall back to one second
+pub(crate) fn rate_to_duration(rate: f64) -> Duration {
+	if !(rate.is_finite() && rate > 0.0) {
+		return Duration::from_secs(1);
+	}
+	Duration::try_from_secs_f64(rate.recip()).unwrap_or(Duration::from_secs(1))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn frame_timing() {
+		let mut timing = FrameTiming::default();
+		assert_eq!(timing.fps(), 0.0);
+
+		timing.advance(Duration::from_millis(20));
+		assert_eq!(timing.frame_index(), 0);
+		assert_eq!(timing.delta_seconds(), 0.02);
+		assert_eq!(timing.fps(), 50.0);
+
+		timing.advance(Duration::from_millis(40));
+		assert_eq!(timing.frame_index(), 1);
+		assert_eq!(timing.delta_seconds(), 0.04);
+		assert!(timing.fps() < 50.0 && timing.fps() > 25.0);
+	}
+
+	#[test]
+	fn rates_below_one_hertz() {
+		assert_eq!(rate_to_duration(0.5), Duration::from_secs(2));
+		assert_eq!(rate_to_duration(60.0), Duration::from_secs_f64(1.0 / 60.0));
+		for rate in [0.0, -1.0, f64::NAN, f64::INFINITY, f64::MIN_POSITIVE] {
+			assert_eq!(rate_to_duration(rate), Duration::from_secs(1));
+		}
+	}
+
+	#[test]
+	fn fixed_steps_accumulate() {
+		let mut frame_loop = FrameLoop::new(&AppConfig {
+			fixed_update_rate: 64.0,
+			..Default::default()
+		});
+		assert_eq!(frame_loop.accumulate(Duration::from_millis(10)), 0);
+		assert_eq!(frame_loop.accumulate(Duration::from_millis(10)), 1);
+		assert_eq!(frame_loop.accumulate(Duration::from_millis(30)), 2);
+		assert_eq!(frame_loop.accumulator, Duration::from_micros(3125));
+		assert_eq!(frame_loop.fixed_alpha(), 0.2);
+
+		assert_eq!(
+			frame_loop.accumulate(Duration::from_secs(10)),
+			MAX_FIXED_STEPS_PER_FRAME
+		);
+		assert_eq!(frame_loop.accumulator, Duration::ZERO);
+	}
+
+	#[test]
+	fn background_throttles_and_pauses() {
+		let mut frame_loop = FrameLoop::new(&AppConfig {
+			update_rate: Some(100.0),
+			background_mode: BackgroundMode::Throttle(10.0),
+			..Default::default()
+		});
+		assert_eq!(
+			frame_loop.paced_frame_duration(),
+			Some(Duration::from_millis(10))
+		);
+		frame_loop.set_in_background(true);
+		assert_eq!(
+			frame_loop.paced_frame_duration(),
+			Some(Duration::from_millis(100))
+		);
+		assert!(!frame_loop.waits_for_events());
+
+		frame_loop.background_mode = BackgroundMode::Throttle(0.2);
+		assert_eq!(
+			frame_loop.paced_frame_duration(),
+			Some(Duration::from_secs(5))
+		);
+
+		frame_loop.background_mode = BackgroundMode::Pause;
+		assert!(frame_loop.waits_for_events());
+		frame_loop.set_in_background(false);
+		assert!(!frame_loop.waits_for_events());
+	}
+}
@@ -0,0 +1,120 @@
+use crate::{
+	app::AppEvent,
+	platform::{self, Instant, Interval, Task},
+};
+use std::{collections::HashMap, time::Duration};
+use tokio::sync::mpsc::UnboundedSender;
+
+// The shortest period, since a zero one can't be ticked
+const MIN_PERIOD: Duration = Duration::from_millis(1);
+
+/// Identifies a timer started with `Context::after` or `Context::every`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimerId(u64);
+
+// Runs each timer as a task that sends `AppEvent::Timer` to the worker when it fires
+pub(crate) struct Timers {
+	next_id: u64,
+	worker_sender: UnboundedSender<AppEvent>,
+	timers: HashMap<TimerId, Task>,
+}
+
+impl Timers {
+	pub(crate) fn new(worker_sender: UnboundedSender<AppEvent>) -> Self {
+		Self {
+			next_id: 0,
+			worker_sender,
+			timers: HashMap::new(),
+		}
+	}
+
+	pub(crate) fn after(&mut self, delay: Duration, tag: String) -> TimerId {
+		let (id, worker_sender) = self.next_timer();
+		let task = platform::spawn(async move {
+			platform::sleep_until(Instant::now() + delay).await;
+			let _ = worker_sender.send(AppEvent::Timer { timer: id, tag });
+		});
+		self.timers.insert(id, task);
+		id
+	}
+
+	pub(crate) fn every(&mut self, period: Duration, tag: String) -> TimerId {
+		let (id, worker_sender) = self.next_timer();
+		let task = platform::spawn(async move {
+			let mut interval = Interval::new(period.max(MIN_PERIOD));
+			loop {
+				interval.tick().await;
+				let event = AppEvent::Timer {
+					timer: id,
+					tag: tag.clone(),
+				};
+				if worker_sender.send(event).is_err() {
+					return;
+				}
+			}
+		});
+		self.timers.insert(id, task);
+		id
+	}
+
+	pub(crate) fn cancel(&mut self, id: TimerId) {
+		if let Some(timer) = self.timers.remove(&id) {
+			timer.abort();
+		}
+	}
+
+	fn next_timer(&mut self) -> (TimerId, UnboundedSender<AppEvent>) {
+		self.timers.retain(|_, timer| !timer.is_finished());
+		let id = TimerId(self.next_id);
+		self.next_id += 1;
+		(id, self.worker_sender.clone())
+	}
+}
+
+impl Drop for Timers {
+	fn drop(&mut self) {
+		self.timers.values().for_each(Task::abort);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use tokio::{sync::mpsc, time};
+
+	fn tag(event: Option<AppEvent>) -> Option<String> {
+		match event {
+			Some(AppEvent::Timer { tag, .. }) => Some(tag),
+			_ => None,
+		}
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn timers_fire_and_cancel() {
+		let (worker_sender, mut worker_receiver) = mpsc::unbounded_channel();
+		let mut timers = Timers::new(worker_sender);
+
+		let repeating = timers.every(Duration::from_secs(1), "tick".to_string());
+		timers.after(Duration::from_millis(1500), "once".to_string());
+
+		assert_eq!(tag(worker_receiver.recv().await).as_deref(), Some("tick"));
+		assert_eq!(tag(worker_receiver.recv().await).as_deref(), Some("once"));
+		assert_eq!(tag(worker_receiver.recv().await).as_deref(), Some("tick"));
+
+		timers.cancel(repeating);
+		time::sleep(Duration::from_secs(5)).await;
+		assert!(worker_receiver.try_recv().is_err());
+	}
+
+	#[tokio::test(start_paused = true)]
+	async fn zero_periods_still_tick() {
+		let (worker_sender, mut worker_receiver) = mpsc::unbounded_channel();
+		let mut timers = Timers::new(worker_sender);
+
+		let repeating = timers.every(Duration::ZERO, "tick".to_string());
+		for _ in 0..3 {
+			assert_eq!(tag(worker_receiver.recv().await).as_deref(), Some("tick"));
+		}
+		timers.cancel(repeating);
+	}
+}
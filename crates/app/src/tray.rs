@@ -0,0 +1,156 @@
+use crate::{
+	app::{AppEvent, TaskResult, WorkerRequest},
+	window::{WindowId, WindowRequest},
+};
+use image::RgbaImage;
+use std::sync::Mutex;
+use tokio::sync::mpsc::UnboundedSender;
+use tray_icon::{
+	menu::{Menu, MenuEvent, MenuItem},
+	Icon, MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent,
+};
+use winit::{event_loop::EventLoopProxy, window::Window};
+
+/// A system tray icon added with `AppBuilder::with_tray`, showing the app's icon.
+/// Selecting a menu item sends `AppEvent::TrayMenu` with the item's id,
+/// and clicking the icon brings back the primary window.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrayConfig {
+	pub tooltip: String,
+	pub menu: Vec<TrayMenuItem>,
+	/// Hides the primary window while it is minimized, until the tray icon is clicked
+	pub minimize_to_tray: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrayMenuItem {
+	pub id: String,
+	pub label: String,
+}
+
+impl TrayConfig {
+	pub fn new(tooltip: impl Into<String>) -> Self {
+		Self {
+			tooltip: tooltip.into(),
+			..Default::default()
+		}
+	}
+
+	pub fn with_item(mut self, id: impl Into<String>, label: impl Into<String>) -> Self {
+		self.menu.push(TrayMenuItem {
+			id: id.into(),
+			label: label.into(),
+		});
+		self
+	}
+
+	pub fn with_minimize_to_tray(mut self, minimize_to_tray: bool) -> Self {
+		self.minimize_to_tray = minimize_to_tray;
+		self
+	}
+}
+
+// Owns the tray icon on the event loop thread. On Linux the icon needs a gtk
+// event loop, so it lives on a thread of its own instead.
+pub(crate) struct Tray {
+	minimize_to_tray: bool,
+	#[cfg(not(target_os = "linux"))]
+	_icon: Option<TrayIcon>,
+}
+
+impl Tray {
+	pub(crate) fn new(
+		config: TrayConfig,
+		icon: Option<RgbaImage>,
+		worker_sender: UnboundedSender<AppEvent>,
+		app_proxy: EventLoopProxy<WorkerRequest>,
+	) -> Self {
+		MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
+			let _ = worker_sender.send(AppEvent::TrayMenu(event.id.0));
+		}));
+		let app_proxy = Mutex::new(app_proxy);
+		TrayIconEvent::set_event_handler(Some(move |event| {
+			if let TrayIconEvent::Click {
+				button: MouseButton::Left,
+				button_state: MouseButtonState::Up,
+				..
+			} = event
+			{
+				restore_primary_window(&app_proxy);
+			}
+		}));
+
+		let minimize_to_tray = config.minimize_to_tray;
+		#[cfg(target_os = "linux")]
+		{
+			spawn_gtk(config, icon);
+			Self { minimize_to_tray }
+		}
+		#[cfg(not(target_os = "linux"))]
+		Self {
+			minimize_to_tray,
+			_icon: build(&config, icon)
+				.map_err(|error| log::warn!("Failed to create the tray icon: {error}"))
+				.ok(),
+		}
+	}
+
+	/// Called when the primary window is resized or occluded, which is when minimizing shows up
+	pub(crate) fn hide_if_minimized(&self, window: &Window) {
+		if self.minimize_to_tray && window.is_minimized() == Some(true) {
+			window.set_visible(false);
+		}
+	}
+}
+
+fn restore_primary_window(app_proxy: &Mutex<EventLoopProxy<WorkerRequest>>) {
+	let app_proxy = app_proxy
+		.lock()
+		.unwrap_or_else(|poisoned| poisoned.into_inner());
+	for request in [
+		WindowRequest::SetVisible(true),
+		WindowRequest::SetMinimized(false),
+		WindowRequest::Focus,
+	] {
+		let _ = app_proxy.send_event(WorkerRequest::Window(WindowId::PRIMARY, request));
+	}
+}
+
+#[cfg(target_os = "linux")]
+fn spawn_gtk(config: TrayConfig, icon: Option<RgbaImage>) {
+	let spawned = std::thread::Builder::new()
+		.name("tray".to_string())
+		.spawn(move || {
+			if let Err(error) = gtk::init() {
+				log::warn!("Failed to start gtk for the tray icon: {error}");
+				return;
+			}
+			match build(&config, icon) {
+				Ok(_icon) => gtk::main(),
+				Err(error) => log::warn!("Failed to create the tray icon: {error}"),
+			}
+		});
+	if let Err(error) = spawned {
+		log::warn!("Failed to start the tray thread: {error}");
+	}
+}
+
+fn build(config: &TrayConfig, icon: Option<RgbaImage>) -> TaskResult<TrayIcon> {
+	let menu = Menu::new();
+	for item in &config.menu {
+		menu.append(&MenuItem::with_id(
+			item.id.as_str(),
+			&item.label,
+			true,
+			None,
+		))?;
+	}
+	let mut builder = TrayIconBuilder::new()
+		.with_tooltip(&config.tooltip)
+		.with_menu(Box::new(menu));
+	if let Some(icon) = icon {
+		let (width, height) = icon.dimensions();
+		builder = builder.with_icon(Icon::from_rgba(icon.into_raw(), width, height)?);
+	}
+	Ok(builder.build()?)
+}
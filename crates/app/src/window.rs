@@ -0,0 +1,303 @@
+use crate::{
+	monitor::{self, MonitorInfo, WindowMode},
+	platform::Instant,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::HashMap,
+	sync::{Arc, RwLock},
+	time::Duration,
+};
+use winit::{
+	dpi::PhysicalSize,
+	error::{ExternalError, OsError},
+	event_loop::ActiveEventLoop,
+	window::{self as winit_window, CursorGrabMode, Window, WindowAttributes},
+};
+
+/// Identifies a window created by the app.
+///
+/// Ids are assigned by the worker so a window can be addressed
+/// before the event loop has finished creating it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct WindowId(pub(crate) u64);
+
+impl WindowId {
+	/// The window created by `App::run` when the event loop starts
+	pub const PRIMARY: Self = Self(0);
+}
+
+#[derive(Debug, Clone)]
+pub struct WindowConfig {
+	pub width: u32,
+	pub height: u32,
+	pub title: String,
+}
+
+impl Default for WindowConfig {
+	fn default() -> Self {
+		Self {
+			width: 800,
+			height: 600,
+			title: "Hourglass Window".to_string(),
+		}
+	}
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CursorMode {
+	#[default]
+	Free,
+	/// The cursor can move but not leave the window
+	Confined,
+	/// The cursor stays in place, use `AppEvent::MouseMotion` for movement
+	Locked,
+}
+
+/// Where a window stays relative to other windows, such as above them for overlays
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WindowLevel {
+	AlwaysOnBottom,
+	#[default]
+	Normal,
+	AlwaysOnTop,
+}
+
+#[derive(Debug, Clone)]
+pub enum WindowRequest {
+	SetTitle(String),
+	SetInnerSize {
+		width: u32,
+		height: u32,
+	},
+	SetWindowMode(WindowMode),
+	SetDecorations(bool),
+	SetCursorVisible(bool),
+	SetCursorMode(CursorMode),
+	SetImeAllowed(bool),
+	Focus,
+	RequestRedraw,
+	SetVisible(bool),
+	SetMinimized(bool),
+	SetWindowLevel(WindowLevel),
+	/// Lets mouse input pass through the window to whatever is beneath it
+	SetClickThrough(bool),
+}
+
+// Shares the open windows with the worker so states can create surfaces for them
+#[derive(Clone, Default)]
+pub(crate) struct WindowHandles(Arc<RwLock<HashMap<WindowId, Arc<Window>>>>);
+
+impl WindowHandles {
+	pub(crate) fn get(&self, id: WindowId) -> Option<Arc<Window>> {
+		self.0
+			.read()
+			.unwrap_or_else(|poisoned| poisoned.into_inner())
+			.get(&id)
+			.cloned()
+	}
+
+	fn update(&self, update: impl FnOnce(&mut HashMap<WindowId, Arc<Window>>)) {
+		update(
+			&mut self
+				.0
+				.write()
+				.unwrap_or_else(|poisoned| poisoned.into_inner()),
+		);
+	}
+}
+
+// Owns the native windows on the event loop thread
+#[derive(Default)]
+pub(crate) struct Windows {
+	windows: HashMap<WindowId, Arc<Window>>,
+	handles: WindowHandles,
+}
+
+impl Windows {
+	pub(crate) fn handles(&self) -> WindowHandles {
+		self.handles.clone()
+	}
+
+	pub(crate) fn insert(&mut self, id: WindowId, window: Window) {
+		let window = Arc::new(window);
+		self.handles.update(|handles| {
+			handles.insert(id, window.clone());
+		});
+		self.windows.insert(id, window);
+	}
+
+	pub(crate) fn get(&self, id: WindowId) -> Option<&Window> {
+		self.windows.get(&id).map(Arc::as_ref)
+	}
+
+	pub(crate) fn find(&self, native_id: winit::window::WindowId) -> Option<WindowId> {
+		self.windows
+			.iter()
+			.find(|(_, window)| window.id() == native_id)
+			.map(|(id, _)| *id)
+	}
+
+	pub(crate) fn monitors(&self) -> Vec<MonitorInfo> {
+		self.get(WindowId::PRIMARY)
+			.map(monitor::monitors)
+			.unwrap_or_default()
+	}
+
+	pub(crate) fn create(
+		&mut self,
+		id: WindowId,
+		config: &WindowConfig,
+		event_loop: &ActiveEventLoop,
+	) -> Result<(), OsError> {
+		let attributes = Window::default_attributes()
+			.with_title(config.title.to_string())
+			.with_inner_size(PhysicalSize::new(config.width, config.height));
+		let window = event_loop.create_window(with_canvas(attributes, None))?;
+		self.insert(id, window);
+		Ok(())
+	}
+
+	pub(crate) fn close(&mut self, id: WindowId) -> bool {
+		self.handles.update(|handles| {
+			handles.remove(&id);
+		});
+		self.windows.remove(&id).is_some()
+	}
+
+	pub(crate) fn handle_request(
+		&self,
+		id: WindowId,
+		request: WindowRequest,
+	) -> Result<(), ExternalError> {
+		let Some(window) = self.windows.get(&id) else {
+			log::warn!("Ignoring request for closed window {id:?}");
+			return Ok(());
+		};
+		match request {
+			WindowRequest::SetTitle(title) => window.set_title(&title),
+			WindowRequest::SetInnerSize { width, height } => {
+				// The platform may apply the size later, reported with `AppEvent::Resized`
+				let _ = window.request_inner_size(PhysicalSize::new(width, height));
+			}
+			WindowRequest::SetWindowMode(mode) => {
+				window.set_fullscreen(monitor::fullscreen(window, mode))
+			}
+			WindowRequest::SetDecorations(decorations) => window.set_decorations(decorations),
+			WindowRequest::SetCursorVisible(visible) => window.set_cursor_visible(visible),
+			WindowRequest::SetCursorMode(mode) => set_cursor_mode(window, mode)?,
+			WindowRequest::SetImeAllowed(allowed) => window.set_ime_allowed(allowed),
+			WindowRequest::Focus => window.focus_window(),
+			WindowRequest::RequestRedraw => window.request_redraw(),
+			WindowRequest::SetVisible(visible) => window.set_visible(visible),
+			WindowRequest::SetMinimized(minimized) => window.set_minimized(minimized),
+			WindowRequest::SetWindowLevel(level) => window.set_window_level(match level {
+				WindowLevel::AlwaysOnBottom => winit_window::WindowLevel::AlwaysOnBottom,
+				WindowLevel::Normal => winit_window::WindowLevel::Normal,
+				WindowLevel::AlwaysOnTop => winit_window::WindowLevel::AlwaysOnTop,
+			}),
+			WindowRequest::SetClickThrough(click_through) => {
+				window.set_cursor_hittest(!click_through)?
+			}
+		}
+		Ok(())
+	}
+}
+
+// Holds back resizes until a window's size stops changing for the quiet period
+pub(crate) struct Resizes {
+	quiet_period: Duration,
+	pending: HashMap<WindowId, (PhysicalSize<u32>, Instant)>,
+}
+
+impl Resizes {
+	pub(crate) fn new(quiet_period: Duration) -> Self {
+		Self {
+			quiet_period,
+			pending: HashMap::new(),
+		}
+	}
+
+	/// Returns true when the window wasn't already being resized
+	pub(crate) fn push(&mut self, id: WindowId, size: PhysicalSize<u32>, now: Instant) -> bool {
+		self.pending
+			.insert(id, (size, now + self.quiet_period))
+			.is_none()
+	}
+
+	/// Removes the resizes whose quiet period has passed
+	pub(crate) fn settled(&mut self, now: Instant) -> Vec<(WindowId, PhysicalSize<u32>)> {
+		let settled = self
+			.pending
+			.iter()
+			.filter(|(_, (_, deadline))| *deadline <= now)
+			.map(|(id, (size, _))| (*id, *size))
+			.collect::<Vec<_>>();
+		for (id, _) in settled.iter() {
+			self.pending.remove(id);
+		}
+		settled
+	}
+
+	pub(crate) fn next_deadline(&self) -> Option<Instant> {
+		self.pending.values().map(|(_, deadline)| *deadline).min()
+	}
+}
+
+/// Draws into the canvas with the given element id on the web,
+/// or into a new canvas appended to the page
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn with_canvas(attributes: WindowAttributes, canvas: Option<&str>) -> WindowAttributes {
+	use wasm_bindgen::JsCast;
+	use winit::platform::web::WindowAttributesExtWebSys;
+
+	let element = canvas.and_then(|id| {
+		let element = web_sys::window()?.document()?.get_element_by_id(id);
+		if element.is_none() {
+			log::warn!("Canvas element '{id}' not found, appending a new canvas");
+		}
+		element?.dyn_into::<web_sys::HtmlCanvasElement>().ok()
+	});
+	let append = element.is_none();
+	attributes.with_canvas(element).with_append(append)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn with_canvas(attributes: WindowAttributes, _canvas: Option<&str>) -> WindowAttributes {
+	attributes
+}
+
+// Platforms support only one of confining or locking, so fall back to the other
+fn set_cursor_mode(window: &Window, mode: CursorMode) -> Result<(), ExternalError> {
+	let (preferred, fallback) = match mode {
+		CursorMode::Free => return window.set_cursor_grab(CursorGrabMode::None),
+		CursorMode::Confined => (CursorGrabMode::Confined, CursorGrabMode::Locked),
+		CursorMode::Locked => (CursorGrabMode::Locked, CursorGrabMode::Confined),
+	};
+	window
+		.set_cursor_grab(preferred)
+		.or_else(|_| window.set_cursor_grab(fallback))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn resizes_settle_after_quiet_period() {
+		let mut resizes = Resizes::new(Duration::from_millis(100));
+		let start = Instant::now();
+		let at = |millis| start + Duration::from_millis(millis);
+
+		assert!(resizes.push(WindowId::PRIMARY, PhysicalSize::new(800, 600), start));
+		assert!(!resizes.push(WindowId::PRIMARY, PhysicalSize::new(900, 700), at(50)));
+		assert!(resizes.settled(at(120)).is_empty());
+		assert_eq!(resizes.next_deadline(), Some(at(150)));
+
+		assert_eq!(
+			resizes.settled(at(150)),
+			[(WindowId::PRIMARY, PhysicalSize::new(900, 700))]
+		);
+		assert_eq!(resizes.next_deadline(), None);
+	}
+}
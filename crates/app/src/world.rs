@@ -0,0 +1,102 @@
+use ecs::world::World;
+use thiserror::Error;
+use tokio::sync::oneshot;
+
+#[derive(Error, Debug)]
+#[error("The shared world has shut down")]
+pub struct WorldClosed;
+
+type WorldAccess = Box<dyn FnOnce(&mut World) + Send>;
+
+/// An ECS world owned by the app and shared by every state, created with
+/// `AppBuilder::with_world`. `World` can't move between threads, so it lives
+/// on a thread of its own and is reached with closures sent to `SharedWorld::with`.
+/// On the web it lives on the browser's thread instead.
+#[derive(Clone)]
+pub struct SharedWorld {
+	#[cfg(not(target_arch = "wasm32"))]
+	sender: std::sync::mpsc::Sender<WorldAccess>,
+}
+
+#[cfg(target_arch = "wasm32")]
+thread_local! {
+	static WORLD: std::cell::RefCell<Option<World>> = const { std::cell::RefCell::new(None) };
+}
+
+impl SharedWorld {
+	#[cfg(not(target_arch = "wasm32"))]
+	pub(crate) fn spawn(create: impl FnOnce() -> World + Send + 'static) -> std::io::Result<Self> {
+		let (sender, receiver) = std::sync::mpsc::channel::<WorldAccess>();
+		std::thread::Builder::new()
+			.name("world".to_string())
+			.spawn(move || {
+				let mut world = create();
+				for access in receiver {
+					access(&mut world);
+				}
+			})?;
+		Ok(Self { sender })
+	}
+
+	#[cfg(target_arch = "wasm32")]
+	pub(crate) fn spawn(create: impl FnOnce() -> World + Send + 'static) -> std::io::Result<Self> {
+		WORLD.set(Some(create()));
+		Ok(Self {})
+	}
+
+	/// Runs the closure with the world, returning what it returns
+	pub async fn with<R: Send + 'static>(
+		&self,
+		access: impl FnOnce(&mut World) -> R + Send + 'static,
+	) -> Result<R, WorldClosed> {
+		let (reply, result) = oneshot::channel();
+		self.send(Box::new(move |world| {
+			let _ = reply.send(access(world));
+		}))?;
+		result.await.map_err(|_| WorldClosed)
+	}
+
+	/// Runs the closure with the world without waiting for it, after any sent before it
+	pub(crate) fn post(
+		&self,
+		access: impl FnOnce(&mut World) + Send + 'static,
+	) -> Result<(), WorldClosed> {
+		self.send(Box::new(access))
+	}
+
+	#[cfg(not(target_arch = "wasm32"))]
+	fn send(&self, access: WorldAccess) -> Result<(), WorldClosed> {
+		self.sender.send(access).map_err(|_| WorldClosed)
+	}
+
+	#[cfg(target_arch = "wasm32")]
+	fn send(&self, access: WorldAccess) -> Result<(), WorldClosed> {
+		WORLD.with_borrow_mut(|world| world.as_mut().map(access).ok_or(WorldClosed))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	struct Health(u32);
+
+	#[tokio::test]
+	async fn closures_share_the_world() -> Result<(), WorldClosed> {
+		let world = SharedWorld::spawn(World::new).unwrap();
+		let entity = world
+			.with(|world| {
+				let entity = world.create_entity();
+				world.add_component(entity, Health(10)).unwrap();
+				entity
+			})
+			.await?;
+
+		let health = world
+			.clone()
+			.with(move |world| world.get_component::<Health>(entity).map(|health| health.0))
+			.await?;
+		assert_eq!(health, Some(10));
+		Ok(())
+	}
+}
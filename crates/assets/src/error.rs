@@ -0,0 +1,21 @@
+use std::{fmt::Display, io};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum AssetError {
+	#[error("Failed to read {path}: {source}")]
+	Read { path: String, source: io::Error },
+
+	#[error("{0}")]
+	Invalid(String),
+
+	#[error("The loader for {0} panicked")]
+	Panicked(String),
+}
+
+impl AssetError {
+	/// Wraps any error a loader runs into
+	pub fn invalid(error: impl Display) -> Self {
+		Self::Invalid(error.to_string())
+	}
+}
@@ -0,0 +1,79 @@
+use std::{
+	fmt,
+	marker::PhantomData,
+	sync::{mpsc::Sender, Arc},
+};
+
+/// Names an asset while it is loaded. Ids aren't reused, so a stale one never names another asset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AssetId(pub(crate) u64);
+
+// Shared by every handle to an asset, reporting when the last one is dropped
+pub(crate) struct HandleInner {
+	pub(crate) id: AssetId,
+	pub(crate) path: String,
+	unused: Sender<AssetId>,
+}
+
+impl HandleInner {
+	pub(crate) fn new(id: AssetId, path: String, unused: Sender<AssetId>) -> Self {
+		Self { id, path, unused }
+	}
+}
+
+impl Drop for HandleInner {
+	fn drop(&mut self) {
+		// The server is gone when nothing receives this
+		let _ = self.unused.send(self.id);
+	}
+}
+
+/// An asset of type `T`, kept loaded while any clone of its handle lives
+pub struct Handle<T> {
+	inner: Arc<HandleInner>,
+	marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+	pub(crate) fn new(inner: Arc<HandleInner>) -> Self {
+		Self {
+			inner,
+			marker: PhantomData,
+		}
+	}
+
+	pub(crate) fn inner(&self) -> &Arc<HandleInner> {
+		&self.inner
+	}
+
+	pub fn id(&self) -> AssetId {
+		self.inner.id
+	}
+
+	pub fn path(&self) -> &str {
+		&self.inner.path
+	}
+}
+
+impl<T> Clone for Handle<T> {
+	fn clone(&self) -> Self {
+		Self::new(self.inner.clone())
+	}
+}
+
+impl<T> PartialEq for Handle<T> {
+	fn eq(&self, other: &Self) -> bool {
+		self.id() == other.id()
+	}
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> fmt::Debug for Handle<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Handle")
+			.field("id", &self.id())
+			.field("path", &self.path())
+			.finish()
+	}
+}
@@ -0,0 +1,14 @@
+#![forbid(unsafe_code)]
+
+mod error;
+mod handle;
+mod server;
+mod source;
+
+pub use self::{
+	error::AssetError,
+	handle::{AssetId, Handle},
+	server::{Asset, AssetEvent, AssetServer, LoadContext, LoadState},
+	source::{AssetSource, FileSource, MemorySource},
+};
+pub use async_trait;
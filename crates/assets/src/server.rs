@@ -0,0 +1,577 @@
+use crate::{
+	error::AssetError,
+	handle::{AssetId, Handle, HandleInner},
+	source::AssetSource,
+};
+use std::{
+	any::{Any, TypeId},
+	collections::{HashMap, HashSet},
+	sync::{
+		mpsc::{self, Receiver, Sender},
+		Arc, Mutex, MutexGuard, Weak,
+	},
+	time::{Duration, Instant},
+};
+
+type Value = Arc<dyn Any + Send + Sync>;
+type Loaded = (Value, Vec<Arc<HandleInner>>);
+type LoadFn = fn(Vec<u8>, &mut LoadContext) -> Result<Value, AssetError>;
+
+// What a load hands back to the server
+struct Finished {
+	id: AssetId,
+	generation: u64,
+	version: Option<u64>,
+	result: Result<Loaded, AssetError>,
+}
+
+/// A type that can be loaded from bytes by an `AssetServer`.
+/// Loading runs on a blocking thread, so it may be slow.
+pub trait Asset: Send + Sync + Sized + 'static {
+	fn load(bytes: Vec<u8>, context: &mut LoadContext) -> Result<Self, AssetError>;
+}
+
+impl Asset for Vec<u8> {
+	fn load(bytes: Vec<u8>, _context: &mut LoadContext) -> Result<Self, AssetError> {
+		Ok(bytes)
+	}
+}
+
+impl Asset for String {
+	fn load(bytes: Vec<u8>, _context: &mut LoadContext) -> Result<Self, AssetError> {
+		String::from_utf8(bytes).map_err(AssetError::invalid)
+	}
+}
+
+fn load_value<T: Asset>(bytes: Vec<u8>, context: &mut LoadContext) -> Result<Value, AssetError> {
+	Ok(Arc::new(T::load(bytes, context)?))
+}
+
+/// Given to `Asset::load` with the path being loaded, to load the assets it depends on
+pub struct LoadContext<'a> {
+	path: &'a str,
+	server: &'a AssetServer,
+	dependencies: Vec<Arc<HandleInner>>,
+}
+
+impl LoadContext<'_> {
+	pub fn path(&self) -> &str {
+		self.path
+	}
+
+	/// Loads an asset this one depends on. It stays loaded as long as this one,
+	/// and this one is reloaded whenever it is.
+	pub fn load<T: Asset>(&mut self, path: &str) -> Handle<T> {
+		let handle = self.server.load(path);
+		self.dependencies.push(handle.inner().clone());
+		handle
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadState {
+	/// The asset was unloaded, or the handle came from another server
+	NotLoaded,
+	Loading,
+	Loaded,
+	Failed(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetEvent {
+	Loaded {
+		id: AssetId,
+		path: String,
+	},
+	/// The asset changed and was loaded again
+	Reloaded {
+		id: AssetId,
+		path: String,
+	},
+	/// Loading failed. A reloaded asset keeps its previous value.
+	Failed {
+		id: AssetId,
+		path: String,
+		error: String,
+	},
+	/// Every handle was dropped
+	Unloaded {
+		id: AssetId,
+		path: String,
+	},
+}
+
+struct Entry {
+	path: String,
+	type_id: TypeId,
+	handle: Weak<HandleInner>,
+	load: LoadFn,
+	state: LoadState,
+	value: Option<Value>,
+	dependencies: Vec<Arc<HandleInner>>,
+	version: Option<u64>,
+	// Bumped by each load, so a slower earlier one can't overwrite it
+	generation: u64,
+}
+
+#[derive(Default)]
+struct Assets {
+	entries: HashMap<AssetId, Entry>,
+	ids: HashMap<(String, TypeId), AssetId>,
+	events: Vec<AssetEvent>,
+	next_id: u64,
+	hot_reload: Option<Duration>,
+	polled: Option<Instant>,
+}
+
+impl Assets {
+	fn remove_unused(&mut self, id: AssetId) -> Option<Entry> {
+		if self.entries.get(&id)?.handle.strong_count() > 0 {
+			return None;
+		}
+		let entry = self.entries.remove(&id)?;
+		let key = (entry.path.clone(), entry.type_id);
+		if self.ids.get(&key) == Some(&id) {
+			self.ids.remove(&key);
+		}
+		self.events.push(AssetEvent::Unloaded {
+			id,
+			path: entry.path.clone(),
+		});
+		Some(entry)
+	}
+
+	// The assets and every asset depending on them, directly or not
+	fn with_dependents(&self, mut ids: Vec<AssetId>) -> Vec<AssetId> {
+		let mut index = 0;
+		while let Some(id) = ids.get(index).copied() {
+			for (dependent, entry) in &self.entries {
+				if !ids.contains(dependent)
+					&& entry
+						.dependencies
+						.iter()
+						.any(|dependency| dependency.id == id)
+				{
+					ids.push(*dependent);
+				}
+			}
+			index += 1;
+		}
+		ids
+	}
+
+	fn dependency_state(&self, id: AssetId, visited: &mut HashSet<AssetId>) -> LoadState {
+		if !visited.insert(id) {
+			return LoadState::Loaded;
+		}
+		let Some(entry) = self.entries.get(&id) else {
+			return LoadState::NotLoaded;
+		};
+		if entry.state != LoadState::Loaded {
+			return entry.state.clone();
+		}
+		let mut state = LoadState::Loaded;
+		for dependency in &entry.dependencies {
+			match self.dependency_state(dependency.id, visited) {
+				LoadState::Loaded => {}
+				failed @ LoadState::Failed(_) => return failed,
+				other => state = other,
+			}
+		}
+		state
+	}
+}
+
+struct Shared {
+	source: Box<dyn AssetSource>,
+	assets: Mutex<Assets>,
+	unused_sender: Sender<AssetId>,
+	unused: Mutex<Receiver<AssetId>>,
+}
+
+/// Loads assets from a source on tokio tasks, sharing one copy of each between its
+/// handles and unloading it once they are all dropped. `update` should be called
+/// regularly to unload assets and, when enabled, hot reload the ones that changed.
+/// Clones share the same assets.
+#[derive(Clone)]
+pub struct AssetServer {
+	shared: Arc<Shared>,
+}
+
+impl AssetServer {
+	pub fn new(source: impl AssetSource) -> Self {
+		let (unused_sender, unused) = mpsc::channel();
+		Self {
+			shared: Arc::new(Shared {
+				source: Box::new(source),
+				assets: Mutex::new(Assets::default()),
+				unused_sender,
+				unused: Mutex::new(unused),
+			}),
+		}
+	}
+
+	/// Checks the source for changed assets this often in `update`, reloading
+	/// them and the assets that depend on them
+	pub fn with_hot_reload(self, interval: Duration) -> Self {
+		self.lock().hot_reload = Some(interval);
+		self
+	}
+
+	/// Starts loading the asset, or returns another handle to it when it is already
+	/// loaded as this type. Must be called within a tokio runtime.
+	pub fn load<T: Asset>(&self, path: &str) -> Handle<T> {
+		let path = path.replace('\\', "/");
+		let key = (path.clone(), TypeId::of::<T>());
+		let mut assets = self.lock();
+		if let Some(inner) = assets
+			.ids
+			.get(&key)
+			.and_then(|id| assets.entries.get(id))
+			.and_then(|entry| entry.handle.upgrade())
+		{
+			return Handle::new(inner);
+		}
+		let id = AssetId(assets.next_id);
+		assets.next_id += 1;
+		let inner = Arc::new(HandleInner::new(
+			id,
+			path.clone(),
+			self.shared.unused_sender.clone(),
+		));
+		assets.entries.insert(
+			id,
+			Entry {
+				path,
+				type_id: key.1,
+				handle: Arc::downgrade(&inner),
+				load: load_value::<T>,
+				state: LoadState::Loading,
+				value: None,
+				dependencies: Vec::new(),
+				version: None,
+				generation: 0,
+			},
+		);
+		assets.ids.insert(key, id);
+		drop(assets);
+		self.start(id);
+		Handle::new(inner)
+	}
+
+	/// The asset once it has loaded. A reloaded asset is replaced, so the value
+	/// should be fetched again rather than kept.
+	pub fn get<T: Asset>(&self, handle: &Handle<T>) -> Option<Arc<T>> {
+		let value = self.lock().entries.get(&handle.id())?.value.clone()?;
+		value.downcast().ok()
+	}
+
+	pub fn load_state<T>(&self, handle: &Handle<T>) -> LoadState {
+		self.lock()
+			.entries
+			.get(&handle.id())
+			.map_or(LoadState::NotLoaded, |entry| entry.state.clone())
+	}
+
+	/// Like `load_state`, but only `Loaded` once every asset it depends on has too,
+	/// and `Failed` when any of them failed
+	pub fn dependency_load_state<T>(&self, handle: &Handle<T>) -> LoadState {
+		self.lock()
+			.dependency_state(handle.id(), &mut HashSet::new())
+	}
+
+	/// The paths of the assets this one loaded while loading
+	pub fn dependencies<T>(&self, handle: &Handle<T>) -> Vec<String> {
+		self.lock()
+			.entries
+			.get(&handle.id())
+			.map(|entry| {
+				entry
+					.dependencies
+					.iter()
+					.map(|dependency| dependency.path.clone())
+					.collect()
+			})
+			.unwrap_or_default()
+	}
+
+	/// How many assets are still loading, such as for a loading screen
+	pub fn loading(&self) -> usize {
+		self.lock()
+			.entries
+			.values()
+			.filter(|entry| entry.state == LoadState::Loading)
+			.count()
+	}
+
+	/// Loads the assets at the path again, along with the assets that depend on them
+	pub fn reload(&self, path: &str) {
+		let path = path.replace('\\', "/");
+		let ids = self
+			.lock()
+			.entries
+			.iter()
+			.filter(|(_, entry)| entry.path == path)
+			.map(|(id, _)| *id)
+			.collect();
+		self.reload_ids(ids);
+	}
+
+	/// Unloads assets whose handles were all dropped, and reloads changed assets when due
+	pub fn update(&self) {
+		loop {
+			let unused = self
+				.shared
+				.unused
+				.lock()
+				.unwrap()
+				.try_iter()
+				.collect::<Vec<_>>();
+			if unused.is_empty() {
+				break;
+			}
+			let removed = {
+				let mut assets = self.lock();
+				unused
+					.into_iter()
+					.filter_map(|id| assets.remove_unused(id))
+					.collect::<Vec<_>>()
+			};
+			// Unloading an asset can leave the assets it depends on unused too
+			drop(removed);
+		}
+
+		let polled = {
+			let mut assets = self.lock();
+			let Some(interval) = assets.hot_reload else {
+				return;
+			};
+			if assets
+				.polled
+				.is_some_and(|polled| polled.elapsed() < interval)
+			{
+				return;
+			}
+			assets.polled = Some(Instant::now());
+			assets
+				.entries
+				.iter()
+				.filter(|(_, entry)| entry.state != LoadState::Loading)
+				.map(|(id, entry)| (*id, entry.path.clone(), entry.version))
+				.collect::<Vec<_>>()
+		};
+		let changed = polled
+			.into_iter()
+			.filter(|(_, path, version)| self.shared.source.version(path) != *version)
+			.map(|(id, ..)| id)
+			.collect();
+		self.reload_ids(changed);
+	}
+
+	/// The events since the last call, for whoever keeps the assets up to date
+	pub fn take_events(&self) -> Vec<AssetEvent> {
+		std::mem::take(&mut self.lock().events)
+	}
+
+	fn lock(&self) -> MutexGuard<'_, Assets> {
+		self.shared.assets.lock().unwrap()
+	}
+
+	fn reload_ids(&self, ids: Vec<AssetId>) {
+		if ids.is_empty() {
+			return;
+		}
+		let ids = self.lock().with_dependents(ids);
+		for id in ids {
+			self.start(id);
+		}
+	}
+
+	fn start(&self, id: AssetId) {
+		let (path, load, generation) = {
+			let mut assets = self.lock();
+			let Some(entry) = assets.entries.get_mut(&id) else {
+				return;
+			};
+			entry.state = LoadState::Loading;
+			entry.generation += 1;
+			(entry.path.clone(), entry.load, entry.generation)
+		};
+		let server = self.clone();
+		tokio::spawn(async move {
+			// Read before the bytes, so a change made meanwhile is seen by the next poll
+			let version = server.shared.source.version(&path);
+			let result = match server.shared.source.read(&path).await {
+				Ok(bytes) => {
+					let loader = server.clone();
+					let loading = path.clone();
+					tokio::task::spawn_blocking(move || {
+						let mut context = LoadContext {
+							path: &loading,
+							server: &loader,
+							dependencies: Vec::new(),
+						};
+						let value = load(bytes, &mut context)?;
+						Ok((value, context.dependencies))
+					})
+					.await
+					.unwrap_or_else(|_| Err(AssetError::Panicked(path.clone())))
+				}
+				Err(error) => Err(error),
+			};
+			server.finish(Finished {
+				id,
+				generation,
+				version,
+				result,
+			});
+		});
+	}
+
+	fn finish(&self, finished: Finished) {
+		let Finished {
+			id,
+			generation,
+			version,
+			result,
+		} = finished;
+		let mut assets = self.lock();
+		let Some(entry) = assets
+			.entries
+			.get_mut(&id)
+			.filter(|entry| entry.generation == generation)
+		else {
+			return;
+		};
+		entry.version = version;
+		let path = entry.path.clone();
+		let event = match result {
+			Ok((value, dependencies)) => {
+				entry.state = LoadState::Loaded;
+				entry.dependencies = dependencies;
+				match entry.value.replace(value) {
+					Some(_) => AssetEvent::Reloaded { id, path },
+					None => AssetEvent::Loaded { id, path },
+				}
+			}
+			Err(error) => {
+				entry.state = LoadState::Failed(error.to_string());
+				AssetEvent::Failed {
+					id,
+					path,
+					error: error.to_string(),
+				}
+			}
+		};
+		assets.events.push(event);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::source::MemorySource;
+
+	// Lists the paths of other text assets, one per line
+	struct Manifest(Vec<Handle<String>>);
+
+	impl Asset for Manifest {
+		fn load(bytes: Vec<u8>, context: &mut LoadContext) -> Result<Self, AssetError> {
+			let text = String::load(bytes, context)?;
+			Ok(Self(text.lines().map(|line| context.load(line)).collect()))
+		}
+	}
+
+	async fn settle(server: &AssetServer) {
+		for _ in 0..1000 {
+			server.update();
+			if server.loading() == 0 {
+				return;
+			}
+			tokio::time::sleep(Duration::from_millis(1)).await;
+		}
+		panic!("Assets are still loading");
+	}
+
+	#[tokio::test]
+	async fn handles_share_an_asset_until_dropped() {
+		let source = MemorySource::default();
+		source.insert("greeting.txt", "hello");
+		let server = AssetServer::new(source);
+		let greeting = server.load::<String>("greeting.txt");
+		let again = server.load::<String>("greeting.txt");
+		let missing = server.load::<String>("missing.txt");
+		assert_eq!(greeting, again);
+		assert_eq!(server.load_state(&greeting), LoadState::Loading);
+
+		settle(&server).await;
+		assert_eq!(
+			server.get(&again).as_deref().map(String::as_str),
+			Some("hello")
+		);
+		assert!(matches!(server.load_state(&missing), LoadState::Failed(_)));
+		assert!(server
+			.get::<Vec<u8>>(&Handle::new(again.inner().clone()))
+			.is_none());
+
+		let id = greeting.id();
+		drop(greeting);
+		server.update();
+		assert_eq!(server.load_state(&again), LoadState::Loaded);
+		drop(again);
+		server.update();
+		assert!(server.take_events().contains(&AssetEvent::Unloaded {
+			id,
+			path: "greeting.txt".to_string()
+		}));
+		assert_ne!(server.load::<String>("greeting.txt").id(), id);
+	}
+
+	#[tokio::test]
+	async fn dependencies_load_reload_and_unload_together() {
+		let source = MemorySource::default();
+		source.insert("level.txt", "a.txt\nb.txt");
+		source.insert("a.txt", "a");
+		let server = AssetServer::new(source.clone()).with_hot_reload(Duration::ZERO);
+		let level = server.load::<Manifest>("level.txt");
+		settle(&server).await;
+		assert_eq!(server.load_state(&level), LoadState::Loaded);
+		assert!(matches!(
+			server.dependency_load_state(&level),
+			LoadState::Failed(_)
+		));
+		assert_eq!(server.dependencies(&level), ["a.txt", "b.txt"]);
+
+		source.insert("b.txt", "b");
+		server.take_events();
+		settle(&server).await;
+		assert_eq!(server.dependency_load_state(&level), LoadState::Loaded);
+		let events = server.take_events();
+		assert!(events.contains(&AssetEvent::Reloaded {
+			id: level.id(),
+			path: "level.txt".to_string()
+		}));
+		let b = server.get(&level).map(|level| level.0[1].clone());
+		assert_eq!(
+			b.and_then(|b| server.get(&b))
+				.as_deref()
+				.map(String::as_str),
+			Some("b")
+		);
+
+		drop(level);
+		server.update();
+		let unloaded = server
+			.take_events()
+			.into_iter()
+			.filter_map(|event| match event {
+				AssetEvent::Unloaded { path, .. } => Some(path),
+				_ => None,
+			})
+			.collect::<HashSet<_>>();
+		assert_eq!(
+			unloaded,
+			HashSet::from(["level.txt", "a.txt", "b.txt"].map(String::from))
+		);
+	}
+}
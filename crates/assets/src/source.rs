@@ -0,0 +1,124 @@
+use crate::error::AssetError;
+use async_trait::async_trait;
+use std::{
+	collections::{hash_map::DefaultHasher, HashMap},
+	hash::{Hash, Hasher},
+	io,
+	path::PathBuf,
+	sync::{Arc, Mutex},
+};
+
+/// Where an `AssetServer` reads assets from, by paths with forward slashes
+#[async_trait]
+pub trait AssetSource: Send + Sync + 'static {
+	async fn read(&self, path: &str) -> Result<Vec<u8>, AssetError>;
+
+	/// A value that changes whenever the asset does, or `None` when it doesn't exist.
+	/// Polled to hot reload assets, so it should be cheap.
+	fn version(&self, path: &str) -> Option<u64>;
+}
+
+/// Reads assets from files under a directory, versioned by their modification time and size
+pub struct FileSource {
+	root: PathBuf,
+}
+
+impl FileSource {
+	pub fn new(root: impl Into<PathBuf>) -> Self {
+		Self { root: root.into() }
+	}
+}
+
+#[async_trait]
+impl AssetSource for FileSource {
+	async fn read(&self, path: &str) -> Result<Vec<u8>, AssetError> {
+		tokio::fs::read(self.root.join(path))
+			.await
+			.map_err(|source| AssetError::Read {
+				path: path.to_string(),
+				source,
+			})
+	}
+
+	fn version(&self, path: &str) -> Option<u64> {
+		let metadata = std::fs::metadata(self.root.join(path)).ok()?;
+		let mut hasher = DefaultHasher::new();
+		metadata.modified().ok()?.hash(&mut hasher);
+		metadata.len().hash(&mut hasher);
+		Some(hasher.finish())
+	}
+}
+
+#[derive(Default)]
+struct MemoryAssets {
+	assets: HashMap<String, (Vec<u8>, u64)>,
+	version: u64,
+}
+
+/// Assets held in memory, such as ones embedded in the binary. Clones share the
+/// same assets, and inserting over a path hot reloads it.
+#[derive(Clone, Default)]
+pub struct MemorySource(Arc<Mutex<MemoryAssets>>);
+
+impl MemorySource {
+	pub fn insert(&self, path: impl Into<String>, bytes: impl Into<Vec<u8>>) {
+		let mut memory = self.0.lock().unwrap();
+		memory.version += 1;
+		let version = memory.version;
+		memory.assets.insert(path.into(), (bytes.into(), version));
+	}
+
+	pub fn remove(&self, path: &str) {
+		self.0.lock().unwrap().assets.remove(path);
+	}
+}
+
+#[async_trait]
+impl AssetSource for MemorySource {
+	async fn read(&self, path: &str) -> Result<Vec<u8>, AssetError> {
+		self.0
+			.lock()
+			.unwrap()
+			.assets
+			.get(path)
+			.map(|(bytes, _)| bytes.clone())
+			.ok_or_else(|| AssetError::Read {
+				path: path.to_string(),
+				source: io::ErrorKind::NotFound.into(),
+			})
+	}
+
+	fn version(&self, path: &str) -> Option<u64> {
+		self.0
+			.lock()
+			.unwrap()
+			.assets
+			.get(path)
+			.map(|(_, version)| *version)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::{error::Error, fs};
+
+	#[tokio::test]
+	async fn files_are_read_and_versioned() -> Result<(), Box<dyn Error>> {
+		let root = std::env::temp_dir().join("hourglass_asset_source");
+		fs::create_dir_all(root.join("text"))?;
+		fs::write(root.join("text/note.txt"), "one")?;
+
+		let source = FileSource::new(&root);
+		assert_eq!(source.read("text/note.txt").await?, b"one");
+		let version = source.version("text/note.txt");
+		assert!(version.is_some());
+		fs::write(root.join("text/note.txt"), "three")?;
+		assert_ne!(source.version("text/note.txt"), version);
+
+		assert!(source.read("missing.txt").await.is_err());
+		assert_eq!(source.version("missing.txt"), None);
+		fs::remove_dir_all(root)?;
+		Ok(())
+	}
+}
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum BehaviorError {
+	#[error("Failed to parse behavior tree: {0}")]
+	Parse(#[from] ron::error::SpannedError),
+
+	#[error("No action named {0} is registered")]
+	UnknownAction(String),
+
+	#[error("No condition named {0} is registered")]
+	UnknownCondition(String),
+}
+
+/// A behavior tree as written, to build `BehaviorTree` components from.
+/// Trees can be authored in RON and loaded as assets:
+///
+/// ```ron
+/// Selector([
+///     Sequence([Condition("sees_player"), Action("chase")]),
+///     Sequence([Action("wander"), Wait(2.0)]),
+/// ])
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Behavior {
+	/// Runs its children in order, failing as soon as one fails
+	Sequence(Vec<Behavior>),
+	/// Runs its children in order, succeeding as soon as one succeeds
+	Selector(Vec<Behavior>),
+	/// Runs every child each tick, failing as soon as one fails and succeeding once all have
+	Parallel(Vec<Behavior>),
+	/// Fails when its child succeeds and succeeds when it fails
+	Invert(Box<Behavior>),
+	/// Succeeds when its child finishes, whether it succeeded or failed
+	Succeed(Box<Behavior>),
+	/// Runs its child again each time it finishes, this many times or forever
+	Repeat(Option<u32>, Box<Behavior>),
+	/// Runs for this many seconds of the world's `Time`, then succeeds
+	Wait(f32),
+	/// Runs the action registered by this name
+	Action(String),
+	/// Succeeds when the condition registered by this name holds, and fails otherwise
+	Condition(String),
+}
+
+impl Behavior {
+	pub fn from_ron(source: &str) -> Result<Self, BehaviorError> {
+		Ok(ron::from_str(source)?)
+	}
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl assets::Asset for Behavior {
+	fn load(
+		bytes: Vec<u8>,
+		_context: &mut assets::LoadContext,
+	) -> Result<Self, assets::AssetError> {
+		ron::de::from_bytes(&bytes).map_err(assets::AssetError::invalid)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn trees_are_read_from_ron() -> Result<(), BehaviorError> {
+		let behavior = Behavior::from_ron(
+			r#"
+			Selector([
+				Sequence([Condition("sees_player"), Action("chase")]),
+				Repeat(Some(3), Invert(Action("wander"))),
+				Wait(2.0),
+			])
+			"#,
+		)?;
+		assert_eq!(
+			behavior,
+			Behavior::Selector(vec![
+				Behavior::Sequence(vec![
+					Behavior::Condition("sees_player".to_string()),
+					Behavior::Action("chase".to_string()),
+				]),
+				Behavior::Repeat(
+					Some(3),
+					Box::new(Behavior::Invert(Box::new(Behavior::Action(
+						"wander".to_string()
+					))))
+				),
+				Behavior::Wait(2.0),
+			])
+		);
+		assert!(matches!(
+			Behavior::from_ron("Sequence(["),
+			Err(BehaviorError::Parse(_))
+		));
+		Ok(())
+	}
+}
@@ -0,0 +1,11 @@
+#![forbid(unsafe_code)]
+
+mod behavior;
+mod system;
+mod tree;
+
+pub use self::{
+	behavior::{Behavior, BehaviorError},
+	system::{tick_behaviors, BehaviorRegistry},
+	tree::{BehaviorTree, Status},
+};
@@ -0,0 +1,167 @@
+use crate::{
+	behavior::BehaviorError,
+	tree::{BehaviorTree, Leaf, Status},
+};
+use ecs::{
+	error::Result,
+	time::delta_seconds,
+	world::{Entity, World},
+};
+use std::{collections::HashMap, rc::Rc};
+
+type Action = Rc<dyn Fn(&mut World, Entity) -> Status>;
+type Condition = Rc<dyn Fn(&World, Entity) -> bool>;
+
+/// The actions and conditions behavior trees run by name, kept as a world resource
+#[derive(Default, Clone)]
+pub struct BehaviorRegistry {
+	actions: HashMap<String, Action>,
+	conditions: HashMap<String, Condition>,
+}
+
+impl BehaviorRegistry {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Runs for the entity each tick its tree reaches the action, until it stops running
+	pub fn register_action(
+		&mut self,
+		name: impl Into<String>,
+		action: impl Fn(&mut World, Entity) -> Status + 'static,
+	) {
+		self.actions.insert(name.into(), Rc::new(action));
+	}
+
+	pub fn register_condition(
+		&mut self,
+		name: impl Into<String>,
+		condition: impl Fn(&World, Entity) -> bool + 'static,
+	) {
+		self.conditions.insert(name.into(), Rc::new(condition));
+	}
+}
+
+/// Ticks the `BehaviorTree` of every entity that has one, running its leaves from the
+/// `BehaviorRegistry` resource. Waits count down by the `Time` resource's delta.
+pub fn tick_behaviors(world: &mut World) -> Result<()> {
+	let delta = delta_seconds(world);
+	// Cloned out of the resources, so leaves are free to use them
+	let registry = world
+		.resources()
+		.borrow()
+		.get::<BehaviorRegistry>()
+		.cloned()
+		.unwrap_or_default();
+	let trees = world
+		.entities()
+		.into_iter()
+		.filter(|entity| world.get_component::<BehaviorTree>(*entity).is_some())
+		.collect::<Vec<_>>();
+	for entity in trees {
+		// Copied while it runs so leaves can use the entity's components, then written back
+		let Some(mut tree) = world
+			.get_component::<BehaviorTree>(entity)
+			.map(|tree| tree.clone())
+		else {
+			continue;
+		};
+		tree.tick(delta, &mut |leaf| match leaf {
+			Leaf::Action(name) => {
+				let action = registry
+					.actions
+					.get(name)
+					.ok_or_else(|| BehaviorError::UnknownAction(name.to_string()))?;
+				Ok(action(world, entity))
+			}
+			Leaf::Condition(name) => {
+				let condition = registry
+					.conditions
+					.get(name)
+					.ok_or_else(|| BehaviorError::UnknownCondition(name.to_string()))?;
+				Ok(if condition(world, entity) {
+					Status::Success
+				} else {
+					Status::Failure
+				})
+			}
+		})?;
+		// Unless a leaf removed it or the entity
+		if let Some(mut component) = world.get_component_mut::<BehaviorTree>(entity) {
+			*component = tree;
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::behavior::Behavior;
+	use ecs::time::advance_time;
+
+	struct Health(u32);
+
+	#[test]
+	fn trees_run_registered_leaves_on_their_entities() -> Result<()> {
+		let mut world = World::new();
+		let mut registry = BehaviorRegistry::new();
+		registry.register_condition("hurt", |world, entity| {
+			world
+				.get_component::<Health>(entity)
+				.is_some_and(|health| health.0 < 50)
+		});
+		registry.register_action("heal", |world, entity| {
+			let Some(mut health) = world.get_component_mut::<Health>(entity) else {
+				return Status::Failure;
+			};
+			health.0 += 10;
+			Status::Success
+		});
+		world.resources().borrow_mut().insert(registry);
+
+		let behavior =
+			Behavior::from_ron(r#"Sequence([Condition("hurt"), Action("heal"), Wait(1.0)])"#)?;
+		let patient = world.create_entity();
+		world.add_component(patient, Health(30))?;
+		world.add_component(patient, BehaviorTree::new(&behavior))?;
+
+		advance_time(&mut world, 0.5);
+		tick_behaviors(&mut world)?;
+		assert_eq!(
+			world
+				.get_component::<Health>(patient)
+				.map(|health| health.0),
+			Some(40)
+		);
+		let status = |world: &World| {
+			world
+				.get_component::<BehaviorTree>(patient)
+				.and_then(|tree| tree.status())
+		};
+		assert_eq!(status(&world), Some(Status::Running));
+
+		// Heals again after the wait, then is no longer hurt, so the next run fails at the condition
+		for _ in 0..2 {
+			tick_behaviors(&mut world)?;
+		}
+		assert_eq!(status(&world), Some(Status::Running));
+		for _ in 0..2 {
+			tick_behaviors(&mut world)?;
+		}
+		assert_eq!(
+			world
+				.get_component::<Health>(patient)
+				.map(|health| health.0),
+			Some(50)
+		);
+		assert_eq!(status(&world), Some(Status::Failure));
+
+		world.add_component(
+			patient,
+			BehaviorTree::new(&Behavior::Action("dance".to_string())),
+		)?;
+		assert!(tick_behaviors(&mut world).is_err());
+		Ok(())
+	}
+}
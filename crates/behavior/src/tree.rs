@@ -0,0 +1,285 @@
+use crate::behavior::{Behavior, BehaviorError};
+use graph::{Graph, NodeId};
+use std::{collections::HashMap, sync::Arc};
+
+/// How a behavior went when ticked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+	Success,
+	Failure,
+	/// Not finished yet, so it's ticked again next time
+	Running,
+}
+
+// A behavior without its children, which are the node's edges in order
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+	Sequence,
+	Selector,
+	Parallel,
+	Invert,
+	Succeed,
+	Repeat(Option<u32>),
+	Wait(f32),
+	Action(String),
+	Condition(String),
+}
+
+// What a node left off at while running
+#[derive(Debug, Clone, PartialEq)]
+enum NodeState {
+	Child(usize),
+	Finished(Vec<bool>),
+	Count(u32),
+	Elapsed(f32),
+}
+
+/// A leaf of the tree, run by whatever ticks it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Leaf<'a> {
+	Action(&'a str),
+	Condition(&'a str),
+}
+
+type Leaves<'a> = dyn FnMut(Leaf) -> Result<Status, BehaviorError> + 'a;
+
+/// Runs a behavior for its entity, ticked each frame by `tick_behaviors`. The tree is kept
+/// in a graph shared between clones, with an edge from each node to each of its children.
+#[derive(Clone)]
+pub struct BehaviorTree {
+	graph: Arc<Graph<Node, ()>>,
+	states: HashMap<NodeId, NodeState>,
+	status: Option<Status>,
+}
+
+impl BehaviorTree {
+	pub fn new(behavior: &Behavior) -> Self {
+		let mut graph = Graph::new();
+		add_behavior(&mut graph, behavior);
+		Self {
+			graph: Arc::new(graph),
+			states: HashMap::new(),
+			status: None,
+		}
+	}
+
+	/// How the tree went the last time it was ticked. Finished trees start over on the next tick.
+	pub fn status(&self) -> Option<Status> {
+		self.status
+	}
+
+	/// Starts the tree over from the top
+	pub fn reset(&mut self) {
+		self.states.clear();
+		self.status = None;
+	}
+
+	pub(crate) fn tick(
+		&mut self,
+		delta: f32,
+		leaves: &mut Leaves,
+	) -> Result<Status, BehaviorError> {
+		let graph = self.graph.clone();
+		let status = Ticker {
+			graph: &graph,
+			states: &mut self.states,
+			delta,
+		}
+		.tick(0, leaves)?;
+		self.status = Some(status);
+		Ok(status)
+	}
+}
+
+fn add_behavior(graph: &mut Graph<Node, ()>, behavior: &Behavior) -> NodeId {
+	let (node, children) = match behavior {
+		Behavior::Sequence(children) => (Node::Sequence, children.iter().collect()),
+		Behavior::Selector(children) => (Node::Selector, children.iter().collect()),
+		Behavior::Parallel(children) => (Node::Parallel, children.iter().collect()),
+		Behavior::Invert(child) => (Node::Invert, vec![child.as_ref()]),
+		Behavior::Succeed(child) => (Node::Succeed, vec![child.as_ref()]),
+		Behavior::Repeat(times, child) => (Node::Repeat(*times), vec![child.as_ref()]),
+		Behavior::Wait(seconds) => (Node::Wait(*seconds), Vec::new()),
+		Behavior::Action(name) => (Node::Action(name.clone()), Vec::new()),
+		Behavior::Condition(name) => (Node::Condition(name.clone()), Vec::new()),
+	};
+	let id = graph.add_node(node);
+	for child in children {
+		let child = add_behavior(graph, child);
+		// The child was just added, so it can't already be linked or be its parent
+		let _ = graph.add_edge(id, child, ());
+	}
+	id
+}
+
+struct Ticker<'a> {
+	graph: &'a Graph<Node, ()>,
+	states: &'a mut HashMap<NodeId, NodeState>,
+	delta: f32,
+}
+
+impl Ticker<'_> {
+	fn children(&self, id: NodeId) -> Vec<NodeId> {
+		self.graph
+			.neighbors(id)
+			.map(|children| children.iter().map(|(child, _)| *child).collect())
+			.unwrap_or_default()
+	}
+
+	// Forgets where the node and everything under it left off, once it's finished
+	fn reset(&mut self, id: NodeId) {
+		for node in self.graph.dfs(id).unwrap_or_default() {
+			self.states.remove(&node);
+		}
+	}
+
+	fn tick(&mut self, id: NodeId, leaves: &mut Leaves) -> Result<Status, BehaviorError> {
+		let Some(node) = self.graph.get_node(id).map(|node| node.data.clone()) else {
+			return Ok(Status::Failure);
+		};
+		let children = self.children(id);
+		let status = match node {
+			Node::Sequence | Node::Selector => {
+				// A sequence goes on while its children succeed, and a selector while they fail
+				let next = if node == Node::Sequence {
+					Status::Success
+				} else {
+					Status::Failure
+				};
+				let start = match self.states.get(&id) {
+					Some(NodeState::Child(index)) => *index,
+					_ => 0,
+				};
+				let mut status = next;
+				for (index, child) in children.iter().enumerate().skip(start) {
+					status = self.tick(*child, leaves)?;
+					if status == Status::Running {
+						self.states.insert(id, NodeState::Child(index));
+						break;
+					}
+					if status != next {
+						break;
+					}
+				}
+				status
+			}
+			Node::Parallel => {
+				let mut finished = match self.states.remove(&id) {
+					Some(NodeState::Finished(finished)) => finished,
+					_ => vec![false; children.len()],
+				};
+				let mut status = Status::Success;
+				for (index, child) in children.iter().enumerate() {
+					if finished[index] {
+						continue;
+					}
+					match self.tick(*child, leaves)? {
+						Status::Success => finished[index] = true,
+						Status::Failure => {
+							status = Status::Failure;
+							break;
+						}
+						Status::Running => status = Status::Running,
+					}
+				}
+				if status == Status::Running {
+					self.states.insert(id, NodeState::Finished(finished));
+				}
+				status
+			}
+			Node::Invert => match self.tick(children[0], leaves)? {
+				Status::Success => Status::Failure,
+				Status::Failure => Status::Success,
+				Status::Running => Status::Running,
+			},
+			Node::Succeed => match self.tick(children[0], leaves)? {
+				Status::Running => Status::Running,
+				_ => Status::Success,
+			},
+			Node::Repeat(times) => {
+				let count = match self.states.get(&id) {
+					Some(NodeState::Count(count)) => *count,
+					_ => 0,
+				};
+				match self.tick(children[0], leaves)? {
+					Status::Running => Status::Running,
+					_ if times.is_some_and(|times| count + 1 >= times) => Status::Success,
+					_ => {
+						// The child starts over on the next tick
+						self.reset(children[0]);
+						self.states.insert(id, NodeState::Count(count + 1));
+						Status::Running
+					}
+				}
+			}
+			Node::Wait(seconds) => {
+				let elapsed = match self.states.get(&id) {
+					Some(NodeState::Elapsed(elapsed)) => *elapsed,
+					_ => 0.0,
+				} + self.delta;
+				if elapsed >= seconds {
+					Status::Success
+				} else {
+					self.states.insert(id, NodeState::Elapsed(elapsed));
+					Status::Running
+				}
+			}
+			Node::Action(name) => leaves(Leaf::Action(&name))?,
+			Node::Condition(name) => leaves(Leaf::Condition(&name))?,
+		};
+		if status != Status::Running {
+			self.reset(id);
+		}
+		Ok(status)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn composites_resume_where_they_left_off() -> Result<(), BehaviorError> {
+		let behavior = Behavior::from_ron(
+			r#"
+			Selector([
+				Sequence([Condition("hungry"), Action("eat")]),
+				Repeat(Some(2), Sequence([Action("walk"), Wait(1.0)])),
+			])
+			"#,
+		)?;
+		let mut tree = BehaviorTree::new(&behavior);
+		let mut ran = Vec::new();
+		let tick = |tree: &mut BehaviorTree, ran: &mut Vec<String>| {
+			tree.tick(0.6, &mut |leaf| {
+				Ok(match leaf {
+					Leaf::Condition(name) => {
+						ran.push(name.to_string());
+						Status::Failure
+					}
+					Leaf::Action(name) => {
+						ran.push(name.to_string());
+						Status::Success
+					}
+				})
+			})
+		};
+
+		// Not hungry, so it walks and waits twice over before finishing
+		assert_eq!(tick(&mut tree, &mut ran)?, Status::Running);
+		assert_eq!(ran, ["hungry", "walk"]);
+		// The selector goes straight back to the wait it left running
+		assert_eq!(tick(&mut tree, &mut ran)?, Status::Running);
+		assert_eq!(ran, ["hungry", "walk"]);
+		assert_eq!(tick(&mut tree, &mut ran)?, Status::Running);
+		assert_eq!(ran, ["hungry", "walk", "walk"]);
+		assert_eq!(tick(&mut tree, &mut ran)?, Status::Success);
+		assert_eq!(tree.status(), Some(Status::Success));
+
+		// Finished, it starts over from the top
+		ran.clear();
+		tick(&mut tree, &mut ran)?;
+		assert_eq!(ran, ["hungry", "walk"]);
+		Ok(())
+	}
+}
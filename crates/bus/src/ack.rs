@@ -0,0 +1,290 @@
+use crate::{EventBus, EventBusError, Message};
+use futures::future::{self, Either};
+use std::{
+	collections::HashMap,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc, Mutex,
+	},
+	time::Duration,
+};
+
+struct PendingDelivery<T> {
+	message: Message<T>,
+	attempt: u32,
+	// On the bus's clock
+	deadline: Duration,
+}
+
+type PendingDeliveries<T> = Arc<Mutex<HashMap<u64, PendingDelivery<T>>>>;
+
+/// A message that must be acknowledged before the subscriber's ack timeout,
+/// otherwise it is delivered again
+pub struct Delivery<T> {
+	id: u64,
+	attempt: u32,
+	message: Message<T>,
+	pending: PendingDeliveries<T>,
+}
+
+impl<T> Delivery<T> {
+	pub fn message(&self) -> &Message<T> {
+		&self.message
+	}
+
+	/// How many times this message has been delivered, starting at 1
+	pub fn attempt(&self) -> u32 {
+		self.attempt
+	}
+
+	pub fn ack(self) {
+		self.pending.lock().unwrap().remove(&self.id);
+		self.message.record("acknowledged");
+	}
+}
+
+/// Subscribes to a channel with at-least-once delivery.
+///
+/// Messages that are not acknowledged within the ack timeout are redelivered,
+/// or moved to a dead letter channel once they reach the maximum number of deliveries.
+/// Unacknowledged messages are returned to the channel when the subscriber is dropped.
+/// The ack timeout is measured on the bus's clock, set with `EventBus::with_clock`.
+pub struct AckSubscriber<T: Clone + Send + 'static> {
+	event_bus: Arc<EventBus<T>>,
+	channel_name: String,
+	ack_timeout: Duration,
+	dead_letter: Option<(String, u32)>,
+	pending: PendingDeliveries<T>,
+	next_id: AtomicU64,
+}
+
+impl<T: Clone + Send + 'static> AckSubscriber<T> {
+	pub fn new(event_bus: Arc<EventBus<T>>, channel_name: String, ack_timeout: Duration) -> Self {
+		Self {
+			event_bus,
+			channel_name,
+			ack_timeout,
+			dead_letter: None,
+			pending: Arc::new(Mutex::new(HashMap::new())),
+			next_id: AtomicU64::new(0),
+		}
+	}
+
+	/// Moves messages that were delivered `max_deliveries` times without
+	/// being acknowledged to the given channel instead of redelivering them
+	pub fn with_dead_letter_channel(mut self, channel_name: String, max_deliveries: u32) -> Self {
+		self.dead_letter = Some((channel_name, max_deliveries.max(1)));
+		self
+	}
+
+	/// Waits for the next message, redelivering expired unacknowledged messages first
+	pub async fn next(&self) -> Result<Delivery<T>, EventBusError> {
+		let receiver = self
+			.event_bus
+			.get_channel(&self.channel_name)
+			.ok_or(EventBusError::ChannelRemovalFailed)?
+			.receiver();
+
+		loop {
+			if let Some(delivery) = self.redeliver_expired()? {
+				return Ok(delivery);
+			}
+
+			let next_deadline = self
+				.pending
+				.lock()
+				.unwrap()
+				.values()
+				.map(|pending| pending.deadline)
+				.min();
+
+			let received = match next_deadline {
+				Some(deadline) => {
+					let sleep = self.event_bus.scheduler().clock().sleep_until(deadline);
+					let recv = receiver.recv();
+					futures::pin_mut!(sleep, recv);
+					match future::select(recv, sleep).await {
+						Either::Left((received, _)) => Some(received),
+						Either::Right(_) => None,
+					}
+				}
+				None => Some(receiver.recv().await),
+			};
+
+			if let Some(received) = received {
+				let message = received.map_err(|_| EventBusError::ChannelRemovalFailed)?;
+				message.record("delivered awaiting ack");
+				return Ok(self.deliver(message, 1));
+			}
+		}
+	}
+
+	fn redeliver_expired(&self) -> Result<Option<Delivery<T>>, EventBusError> {
+		let now = self.event_bus.scheduler().clock().now();
+		loop {
+			let expired = {
+				let mut pending = self.pending.lock().unwrap();
+				let id = pending
+					.iter()
+					.filter(|(_, pending)| pending.deadline <= now)
+					.min_by_key(|(_, pending)| pending.deadline)
+					.map(|(id, _)| *id);
+				id.and_then(|id| pending.remove(&id))
+			};
+			let Some(expired) = expired else {
+				return Ok(None);
+			};
+
+			match self.dead_letter.as_ref() {
+				Some((channel_name, max_deliveries)) if expired.attempt >= *max_deliveries => {
+					expired.message.record("dead lettered");
+					let (topic, payload) = expired.message.into_parts();
+					self.event_bus
+						.get_channel(channel_name)
+						.ok_or(EventBusError::ChannelRemovalFailed)?
+						.try_send(topic, payload)?;
+				}
+				_ => {
+					expired.message.record("redelivered");
+					return Ok(Some(self.deliver(expired.message, expired.attempt + 1)));
+				}
+			}
+		}
+	}
+
+	fn deliver(&self, message: Message<T>, attempt: u32) -> Delivery<T> {
+		let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+		self.pending.lock().unwrap().insert(
+			id,
+			PendingDelivery {
+				message: message.clone(),
+				attempt,
+				deadline: self.event_bus.scheduler().clock().now() + self.ack_timeout,
+			},
+		);
+		Delivery {
+			id,
+			attempt,
+			message,
+			pending: self.pending.clone(),
+		}
+	}
+}
+
+impl<T: Clone + Send + 'static> Drop for AckSubscriber<T> {
+	fn drop(&mut self) {
+		let Some(channel) = self.event_bus.get_channel(&self.channel_name) else {
+			return;
+		};
+		for (_, pending) in self.pending.lock().unwrap().drain() {
+			let (topic, payload) = pending.message.into_parts();
+			let _ = channel.try_send(topic, payload);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{ManualClock, Publisher};
+
+	const ACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+	fn setup_event_bus() -> (Arc<EventBus<String>>, Arc<ManualClock>) {
+		let clock = Arc::new(ManualClock::default());
+		let event_bus = Arc::new(EventBus::<String>::new().with_clock(clock.clone()));
+		event_bus.add_channel("commands").unwrap();
+		event_bus.add_channel("dead_letters").unwrap();
+		(event_bus, clock)
+	}
+
+	#[async_std::test]
+	async fn unacknowledged_messages_are_redelivered() -> Result<(), EventBusError> {
+		let (event_bus, clock) = setup_event_bus();
+		let publisher = Publisher::new(event_bus.clone(), "commands".to_string());
+		publisher
+			.publish("save".to_string(), "slot1".to_string())
+			.await?;
+
+		let subscriber = AckSubscriber::new(event_bus.clone(), "commands".to_string(), ACK_TIMEOUT);
+
+		let delivery = subscriber.next().await?;
+		assert_eq!(delivery.attempt(), 1);
+		drop(delivery);
+
+		// Not yet expired, so newer messages come first
+		clock.advance(ACK_TIMEOUT - Duration::from_millis(1));
+		publisher
+			.publish("quit".to_string(), "".to_string())
+			.await?;
+		let delivery = subscriber.next().await?;
+		assert_eq!(
+			(delivery.attempt(), delivery.message().topic.as_str()),
+			(1, "quit")
+		);
+		delivery.ack();
+
+		clock.advance(Duration::from_millis(1));
+		let delivery = subscriber.next().await?;
+		assert_eq!(delivery.attempt(), 2);
+		assert_eq!(delivery.message().topic, "save");
+		delivery.ack();
+
+		publisher
+			.publish("load".to_string(), "slot1".to_string())
+			.await?;
+		let delivery = subscriber.next().await?;
+		assert_eq!(delivery.attempt(), 1);
+		assert_eq!(delivery.message().topic, "load");
+
+		Ok(())
+	}
+
+	#[async_std::test]
+	async fn expired_messages_are_dead_lettered() -> Result<(), EventBusError> {
+		let (event_bus, clock) = setup_event_bus();
+		let publisher = Publisher::new(event_bus.clone(), "commands".to_string());
+		publisher
+			.publish("save".to_string(), "slot1".to_string())
+			.await?;
+
+		let subscriber = AckSubscriber::new(event_bus.clone(), "commands".to_string(), ACK_TIMEOUT)
+			.with_dead_letter_channel("dead_letters".to_string(), 1);
+
+		let _delivery = subscriber.next().await?;
+
+		// The expired message is dead lettered while waiting for the next message
+		clock.advance(ACK_TIMEOUT);
+		publisher
+			.publish("load".to_string(), "slot1".to_string())
+			.await?;
+		let delivery = subscriber.next().await?;
+		assert_eq!(delivery.message().topic, "load");
+
+		let dead_letters = event_bus.get_channel("dead_letters").unwrap().receiver();
+		assert_eq!(
+			dead_letters.try_recv().unwrap().into_parts(),
+			("save".to_string(), "slot1".to_string())
+		);
+
+		Ok(())
+	}
+
+	#[async_std::test]
+	async fn pending_messages_are_requeued_on_drop() -> Result<(), EventBusError> {
+		let (event_bus, _) = setup_event_bus();
+		let publisher = Publisher::new(event_bus.clone(), "commands".to_string());
+		publisher
+			.publish("save".to_string(), "slot1".to_string())
+			.await?;
+
+		let subscriber = AckSubscriber::new(event_bus.clone(), "commands".to_string(), ACK_TIMEOUT);
+		let _delivery = subscriber.next().await?;
+		drop(subscriber);
+
+		let subscriber = AckSubscriber::new(event_bus.clone(), "commands".to_string(), ACK_TIMEOUT);
+		assert_eq!(subscriber.next().await?.message().topic, "save");
+
+		Ok(())
+	}
+}
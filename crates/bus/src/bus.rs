@@ -1,12 +1,18 @@
+use crate::{
+	cron::Cron,
+	schedule::{Clock, Schedule, ScheduleId, Scheduler},
+};
 use async_channel::{Receiver, Sender};
 use std::{
-	collections::HashMap,
+	collections::{hash_map::RandomState, HashMap},
 	error::Error,
 	fmt::Debug,
+	hash::BuildHasher,
 	sync::{
-		atomic::{AtomicUsize, Ordering},
+		atomic::{AtomicU64, AtomicUsize, Ordering},
 		Arc, RwLock,
 	},
+	time::Duration,
 };
 
 #[derive(Debug, PartialEq)]
@@ -26,11 +32,173 @@ impl std::fmt::Display for EventBusError {
 
 impl Error for EventBusError {}
 
-type Channel<T> = (Sender<(String, T)>, Receiver<(String, T)>);
+type Predicate<T> = dyn Fn(&str, &T) -> bool + Send + Sync;
+
+/// A predicate evaluated on the topic and payload of each message published to a channel
+pub struct Filter<T>(Arc<Predicate<T>>);
+
+impl<T> Filter<T> {
+	pub fn new(predicate: impl Fn(&str, &T) -> bool + Send + Sync + 'static) -> Self {
+		Self(Arc::new(predicate))
+	}
+
+	pub fn matches(&self, topic: &str, payload: &T) -> bool {
+		(self.0)(topic, payload)
+	}
+}
+
+impl<T> Clone for Filter<T> {
+	fn clone(&self) -> Self {
+		Self(self.0.clone())
+	}
+}
+
+/// A message published on a channel
+#[derive(Debug, Clone)]
+pub struct Message<T> {
+	pub topic: String,
+	pub payload: T,
+	/// Span created when the message was published, used to follow it through the bus
+	#[cfg(feature = "tracing")]
+	pub span: tracing::Span,
+}
+
+impl<T> Message<T> {
+	pub fn into_parts(self) -> (String, T) {
+		(self.topic, self.payload)
+	}
+
+	// Records a step of the message's delivery within its span
+	pub(crate) fn record(&self, _step: &str) {
+		#[cfg(feature = "tracing")]
+		tracing::trace!(parent: &self.span, "{}", _step);
+	}
+}
+
+impl<T: PartialEq> PartialEq for Message<T> {
+	fn eq(&self, other: &Self) -> bool {
+		self.topic == other.topic && self.payload == other.payload
+	}
+}
+
+struct FilteredSubscription<T> {
+	filter: Filter<T>,
+	sender: Sender<Message<T>>,
+}
+
+pub(crate) struct Channel<T> {
+	#[cfg(feature = "tracing")]
+	name: Arc<str>,
+	sender: Sender<Message<T>>,
+	receiver: Receiver<Message<T>>,
+	filtered_subscriptions: Arc<RwLock<Vec<FilteredSubscription<T>>>>,
+	published: Arc<AtomicU64>,
+}
+
+impl<T> Clone for Channel<T> {
+	fn clone(&self) -> Self {
+		Self {
+			#[cfg(feature = "tracing")]
+			name: self.name.clone(),
+			sender: self.sender.clone(),
+			receiver: self.receiver.clone(),
+			filtered_subscriptions: self.filtered_subscriptions.clone(),
+			published: self.published.clone(),
+		}
+	}
+}
+
+impl<T: Clone + Send + 'static> Channel<T> {
+	fn new(_name: &str, published: Arc<AtomicU64>) -> Self {
+		let (sender, receiver) = async_channel::unbounded();
+		Self {
+			#[cfg(feature = "tracing")]
+			name: Arc::from(_name),
+			sender,
+			receiver,
+			filtered_subscriptions: Arc::new(RwLock::new(Vec::new())),
+			published,
+		}
+	}
+
+	pub(crate) fn receiver(&self) -> Receiver<Message<T>> {
+		self.receiver.clone()
+	}
+
+	// Filtered subscriptions get their own queue so they are only woken for matching messages
+	fn subscribe_filtered(&self, filter: Filter<T>) -> Receiver<Message<T>> {
+		let (sender, receiver) = async_channel::unbounded();
+		let mut subscriptions = self.filtered_subscriptions.write().unwrap();
+		subscriptions.retain(|subscription| !subscription.sender.is_closed());
+		subscriptions.push(FilteredSubscription { filter, sender });
+		receiver
+	}
+
+	pub(crate) async fn send(&self, topic: String, payload: T) -> Result<(), EventBusError> {
+		let message = self.message(topic, payload);
+		self.send_filtered(&message);
+		self.sender
+			.send(message)
+			.await
+			.map_err(|_| EventBusError::ChannelRemovalFailed)
+	}
+
+	// Channels are unbounded, so sending without awaiting only fails if the channel is closed
+	pub(crate) fn try_send(&self, topic: String, payload: T) -> Result<(), EventBusError> {
+		let message = self.message(topic, payload);
+		self.send_filtered(&message);
+		self.sender
+			.try_send(message)
+			.map_err(|_| EventBusError::ChannelRemovalFailed)
+	}
+
+	fn message(&self, topic: String, payload: T) -> Message<T> {
+		let message = Message {
+			#[cfg(feature = "tracing")]
+			span: tracing::debug_span!("bus_message", channel = %self.name, topic = %topic),
+			topic,
+			payload,
+		};
+		message.record("published");
+		self.published.fetch_add(1, Ordering::Relaxed);
+		diagnostics::count("bus messages", 1);
+		message
+	}
+
+	// Publishers only share a read lock, and subscriptions whose receivers were dropped are
+	// removed when a send to one fails or another filtered subscription is added
+	fn send_filtered(&self, message: &Message<T>) {
+		let _span = diagnostics::span("bus filters");
+		let mut closed = false;
+		for subscription in self.filtered_subscriptions.read().unwrap().iter() {
+			if subscription
+				.filter
+				.matches(&message.topic, &message.payload)
+			{
+				message.record("delivered to filtered subscription");
+				closed |= subscription.sender.try_send(message.clone()).is_err();
+			}
+		}
+		if closed {
+			self.filtered_subscriptions
+				.write()
+				.unwrap()
+				.retain(|subscription| !subscription.sender.is_closed());
+		}
+	}
+}
+
 type Channels<T> = HashMap<String, Channel<T>>;
 
+const DEFAULT_SHARD_COUNT: usize = 16;
+
+// Channels are spread across independently locked shards so that publishers
+// on different channels rarely contend for the same lock
 pub struct EventBus<T: Clone + Send + 'static> {
-	channels: RwLock<Channels<T>>,
+	shards: Vec<RwLock<Channels<T>>>,
+	hasher: RandomState,
+	scheduler: Scheduler<T>,
+	published: Arc<AtomicU64>,
 }
 
 impl<T: Clone + Send + 'static> Default for EventBus<T> {
@@ -41,22 +209,43 @@ impl<T: Clone + Send + 'static> Default for EventBus<T> {
 
 impl<T: Clone + Send + 'static> EventBus<T> {
 	pub fn new() -> Self {
-		Self::default()
+		Self::with_shards(DEFAULT_SHARD_COUNT)
+	}
+
+	/// Creates a bus whose channel registry is split into `shard_count` shards.
+	/// Buses with many channels benefit from more shards.
+	pub fn with_shards(shard_count: usize) -> Self {
+		Self {
+			shards: (0..shard_count.max(1))
+				.map(|_| RwLock::new(HashMap::new()))
+				.collect(),
+			hasher: RandomState::new(),
+			scheduler: Scheduler::new(),
+			published: Arc::new(AtomicU64::new(0)),
+		}
+	}
+
+	/// Uses the clock for delayed, repeating and cron publishes instead of the system's
+	pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+		self.scheduler.set_clock(clock);
+		self
 	}
 
 	pub fn add_channel(&self, channel_name: &str) -> Result<(), EventBusError> {
-		let mut channels = self.channels.write().unwrap();
+		let mut channels = self.shard(channel_name).write().unwrap();
 		if channels.contains_key(channel_name) {
 			Err(EventBusError::ChannelCreationFailed)
 		} else {
-			let (sender, receiver) = async_channel::unbounded();
-			channels.insert(channel_name.to_string(), (sender, receiver));
+			channels.insert(
+				channel_name.to_string(),
+				Channel::new(channel_name, self.published.clone()),
+			);
 			Ok(())
 		}
 	}
 
 	pub fn remove_channel(&self, channel_name: &str) -> Result<(), EventBusError> {
-		let mut channels = self.channels.write().unwrap();
+		let mut channels = self.shard(channel_name).write().unwrap();
 		if channels.contains_key(channel_name) {
 			channels.remove(channel_name);
 			Ok(())
@@ -65,10 +254,65 @@ impl<T: Clone + Send + 'static> EventBus<T> {
 		}
 	}
 
-	fn get_channel(&self, channel_name: &str) -> Option<Channel<T>> {
-		let channels = self.channels.read().unwrap();
+	/// Lists the channels under a namespace such as `ui` or `ui/*`, sorted by name.
+	/// Channel names are hierarchical, separated by `/`.
+	pub fn channels_in_namespace(&self, namespace: &str) -> Vec<String> {
+		let prefix = namespace_prefix(namespace);
+		let mut channel_names = self
+			.shards
+			.iter()
+			.flat_map(|shard| {
+				shard
+					.read()
+					.unwrap()
+					.keys()
+					.filter(|channel_name| channel_name.starts_with(&prefix))
+					.cloned()
+					.collect::<Vec<_>>()
+			})
+			.collect::<Vec<_>>();
+		channel_names.sort();
+		channel_names
+	}
+
+	/// Removes every channel under a namespace, returning how many were removed
+	pub fn remove_namespace(&self, namespace: &str) -> usize {
+		let prefix = namespace_prefix(namespace);
+		self.shards
+			.iter()
+			.map(|shard| {
+				let mut channels = shard.write().unwrap();
+				let count = channels.len();
+				channels.retain(|channel_name, _| !channel_name.starts_with(&prefix));
+				count - channels.len()
+			})
+			.sum()
+	}
+
+	/// How many messages have been published on any channel, such as to measure throughput
+	pub fn published_count(&self) -> u64 {
+		self.published.load(Ordering::Relaxed)
+	}
+
+	pub(crate) fn get_channel(&self, channel_name: &str) -> Option<Channel<T>> {
+		let channels = self.shard(channel_name).read().unwrap();
 		channels.get(channel_name).cloned()
 	}
+
+	pub(crate) fn scheduler(&self) -> &Scheduler<T> {
+		&self.scheduler
+	}
+
+	fn shard(&self, channel_name: &str) -> &RwLock<Channels<T>> {
+		let index = self.hasher.hash_one(channel_name) as usize % self.shards.len();
+		&self.shards[index]
+	}
+}
+
+// Accepts `ui`, `ui/` and `ui/*` as the same namespace
+fn namespace_prefix(namespace: &str) -> String {
+	let namespace = namespace.trim_end_matches('*').trim_end_matches('/');
+	format!("{namespace}/")
 }
 
 pub struct Publisher<T: Clone + Send + 'static> {
@@ -85,15 +329,44 @@ impl<T: Clone + Send + 'static> Publisher<T> {
 	}
 
 	pub async fn publish(&self, topic: String, payload: T) -> Result<(), EventBusError> {
-		if let Some((sender, _)) = self.event_bus.get_channel(&self.channel_name) {
-			sender
-				.send((topic, payload))
-				.await
-				.map_err(|_| EventBusError::ChannelRemovalFailed)
+		if let Some(channel) = self.event_bus.get_channel(&self.channel_name) {
+			channel.send(topic, payload).await
 		} else {
 			Err(EventBusError::ChannelRemovalFailed)
 		}
 	}
+
+	/// Publishes without awaiting, for callers that are not async
+	pub fn publish_sync(&self, topic: String, payload: T) -> Result<(), EventBusError> {
+		self.event_bus
+			.get_channel(&self.channel_name)
+			.ok_or(EventBusError::ChannelRemovalFailed)?
+			.try_send(topic, payload)
+	}
+
+	/// Publishes once after a delay, without spawning a task per publish
+	pub fn publish_after(&self, delay: Duration, topic: String, payload: T) -> ScheduleId {
+		self.event_bus
+			.schedule(&self.channel_name, Schedule::Once(delay), (topic, payload))
+	}
+
+	/// Publishes repeatedly at a fixed interval until cancelled with
+	/// [`EventBus::cancel_scheduled`], starting one interval from now. Intervals shorter
+	/// than a millisecond are raised to one.
+	pub fn publish_every(&self, interval: Duration, topic: String, payload: T) -> ScheduleId {
+		self.event_bus.schedule(
+			&self.channel_name,
+			Schedule::Every(interval),
+			(topic, payload),
+		)
+	}
+
+	/// Publishes each time the cron schedule fires until cancelled with
+	/// [`EventBus::cancel_scheduled`]
+	pub fn publish_cron(&self, cron: Cron, topic: String, payload: T) -> ScheduleId {
+		self.event_bus
+			.schedule(&self.channel_name, Schedule::Cron(cron), (topic, payload))
+	}
 }
 
 #[derive(Debug, PartialEq)]
@@ -132,31 +405,51 @@ impl<T: Clone + Send + 'static> Subscriber<T> {
 		}
 	}
 
-	pub fn subscribe(&self) -> Result<Vec<Receiver<(String, T)>>, EventBusError> {
+	/// Subscribes to every channel currently under a namespace
+	pub fn for_namespace(event_bus: Arc<EventBus<T>>, namespace: &str) -> Self {
+		let channel_names = event_bus.channels_in_namespace(namespace);
+		Self::new(event_bus, channel_names)
+	}
+
+	/// Returns a receiver for each subscribed channel.
+	///
+	/// Without a filter, the receivers share each channel's queue with all other
+	/// unfiltered subscribers. With a filter, each receiver gets its own copy of
+	/// only the messages the filter accepts.
+	pub fn subscribe(
+		&self,
+		filter: Option<Filter<T>>,
+	) -> Result<Vec<Receiver<Message<T>>>, EventBusError> {
 		self.channel_names
 			.iter()
 			.map(|channel_name| {
 				self.event_bus
 					.get_channel(channel_name)
-					.map(|(_, receiver)| receiver)
+					.map(|channel| match filter.as_ref() {
+						Some(filter) => channel.subscribe_filtered(filter.clone()),
+						None => channel.receiver(),
+					})
 					.ok_or(EventBusError::ChannelRemovalFailed)
 			})
 			.collect()
 	}
 
-	pub async fn try_next_message(&self) -> Option<(String, T)> {
+	pub async fn try_next_message(&self) -> Option<Message<T>> {
 		let index = self.current_channel_index.load(Ordering::Relaxed);
 		let channel_name = self.channel_names.get(index)?;
-		let (_, receiver) = self.event_bus.get_channel(channel_name)?;
+		let channel = self.event_bus.get_channel(channel_name)?;
 		self.current_channel_index
 			.store((index + 1) % self.channel_names.len(), Ordering::Relaxed);
-		receiver.try_recv().ok()
+		let message = channel.receiver().try_recv().ok()?;
+		message.record("delivered");
+		Some(message)
 	}
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::ManualClock;
 
 	fn setup_event_bus() -> Arc<EventBus<String>> {
 		let event_bus = Arc::new(EventBus::<String>::new());
@@ -202,10 +495,241 @@ mod tests {
 		);
 
 		let subscriber = Subscriber::new(event_bus.clone(), vec!["channel1".to_string()]);
-		let receivers = subscriber.subscribe().unwrap();
+		let receivers = subscriber.subscribe(None).unwrap();
 
 		let received_messages: Vec<(String, String)> =
 			vec![("topic1".to_string(), "Hello, world!".to_string())];
-		assert_eq!(receivers[0].recv().await.unwrap(), received_messages[0]);
+		assert_eq!(
+			receivers[0].recv().await.unwrap().into_parts(),
+			received_messages[0]
+		);
+		assert_eq!(event_bus.published_count(), 1);
+	}
+
+	#[async_std::test]
+	async fn filtered_subscription() {
+		let event_bus = setup_event_bus();
+
+		let subscriber = Subscriber::new(event_bus.clone(), vec!["channel1".to_string()]);
+		let receivers = subscriber
+			.subscribe(Some(Filter::new(|topic, _payload| topic == "door")))
+			.unwrap();
+
+		let publisher = Publisher::new(event_bus.clone(), "channel1".to_string());
+		publisher
+			.publish("window".to_string(), "opened".to_string())
+			.await
+			.unwrap();
+		publisher
+			.publish("door".to_string(), "opened".to_string())
+			.await
+			.unwrap();
+
+		assert_eq!(
+			receivers[0].recv().await.unwrap().into_parts(),
+			("door".to_string(), "opened".to_string())
+		);
+		assert!(receivers[0].try_recv().is_err());
+
+		// Unfiltered subscribers still receive every message
+		let receivers = subscriber.subscribe(None).unwrap();
+		assert_eq!(receivers[0].len(), 2);
+	}
+
+	#[async_std::test]
+	async fn dropped_filtered_subscriptions_are_pruned() {
+		let event_bus = setup_event_bus();
+		let subscriber = Subscriber::new(event_bus.clone(), vec!["channel1".to_string()]);
+		let receivers = subscriber
+			.subscribe(Some(Filter::new(|topic, _payload| topic == "door")))
+			.unwrap();
+		drop(receivers);
+
+		let publisher = Publisher::new(event_bus.clone(), "channel1".to_string());
+		publisher
+			.publish("window".to_string(), "opened".to_string())
+			.await
+			.unwrap();
+		let channel = event_bus.get_channel("channel1").unwrap();
+		assert_eq!(channel.filtered_subscriptions.read().unwrap().len(), 1);
+
+		publisher
+			.publish("door".to_string(), "opened".to_string())
+			.await
+			.unwrap();
+		assert!(channel.filtered_subscriptions.read().unwrap().is_empty());
+	}
+
+	#[async_std::test]
+	async fn channels_across_shards() {
+		let event_bus = Arc::new(EventBus::<String>::with_shards(4));
+
+		let channel_names = (0..64)
+			.map(|index| format!("entity{index}"))
+			.collect::<Vec<_>>();
+		for channel_name in channel_names.iter() {
+			assert_eq!(event_bus.add_channel(channel_name), Ok(()));
+		}
+		for channel_name in channel_names.iter() {
+			assert!(event_bus.get_channel(channel_name).is_some());
+			assert_eq!(event_bus.remove_channel(channel_name), Ok(()));
+			assert!(event_bus.get_channel(channel_name).is_none());
+		}
+	}
+
+	// A bus on a clock that only moves when advanced, starting at 2024-02-28 23:30 UTC
+	fn scheduled_bus() -> (Arc<EventBus<String>>, Arc<ManualClock>, Publisher<String>) {
+		let clock = Arc::new(ManualClock::new(Duration::from_secs(1_709_163_000)));
+		let event_bus = Arc::new(EventBus::<String>::new().with_clock(clock.clone()));
+		event_bus.add_channel("channel1").unwrap();
+		let publisher = Publisher::new(event_bus.clone(), "channel1".to_string());
+		(event_bus, clock, publisher)
+	}
+
+	// Publishes a marker after the delay, so once it's received everything due before
+	// it has been published too
+	async fn advance(
+		clock: &ManualClock,
+		publisher: &Publisher<String>,
+		delay: Duration,
+		receiver: &Receiver<Message<String>>,
+	) -> Vec<String> {
+		publisher.publish_after(delay, "marker".to_string(), "".to_string());
+		clock.advance(delay);
+		let mut topics = Vec::new();
+		loop {
+			let topic = receiver.recv().await.unwrap().topic;
+			if topic == "marker" {
+				return topics;
+			}
+			topics.push(topic);
+		}
+	}
+
+	#[async_std::test]
+	async fn delayed_publish() {
+		let (event_bus, clock, publisher) = scheduled_bus();
+		let subscriber = Subscriber::new(event_bus, vec!["channel1".to_string()]);
+		let receiver = subscriber.subscribe(None).unwrap().remove(0);
+
+		publisher.publish_after(
+			Duration::from_millis(20),
+			"autosave".to_string(),
+			"now".to_string(),
+		);
+		let millisecond = Duration::from_millis(1);
+		assert!(advance(&clock, &publisher, millisecond * 19, &receiver)
+			.await
+			.is_empty());
+		assert_eq!(
+			advance(&clock, &publisher, millisecond, &receiver).await,
+			["autosave"]
+		);
+	}
+
+	#[async_std::test]
+	async fn repeating_publish() {
+		let (event_bus, clock, publisher) = scheduled_bus();
+		let subscriber = Subscriber::new(event_bus.clone(), vec!["channel1".to_string()]);
+		let receiver = subscriber.subscribe(None).unwrap().remove(0);
+
+		let interval = Duration::from_millis(5);
+		let id = publisher.publish_every(interval, "tick".to_string(), "".to_string());
+		assert_eq!(
+			advance(&clock, &publisher, interval * 3, &receiver).await,
+			["tick", "tick", "tick"]
+		);
+
+		event_bus.cancel_scheduled(id);
+		assert!(advance(&clock, &publisher, interval * 4, &receiver)
+			.await
+			.is_empty());
+	}
+
+	#[async_std::test]
+	async fn repeats_survive_missed_publishes() {
+		let (event_bus, clock, publisher) = scheduled_bus();
+		let subscriber = Subscriber::new(event_bus.clone(), vec!["channel1".to_string()]);
+		let receiver = subscriber.subscribe(None).unwrap().remove(0);
+		event_bus.add_channel("channel2").unwrap();
+		let other = Publisher::new(event_bus.clone(), "channel2".to_string());
+
+		// Zero intervals are raised to a millisecond rather than publishing forever
+		let millisecond = Duration::from_millis(1);
+		let id = publisher.publish_every(Duration::ZERO, "tick".to_string(), "".to_string());
+		assert_eq!(
+			advance(&clock, &publisher, millisecond * 2, &receiver).await,
+			["tick", "tick"]
+		);
+		event_bus.cancel_scheduled(id);
+
+		// Keeps repeating after its channel was missing for a while
+		other.publish_every(millisecond * 5, "tock".to_string(), "".to_string());
+		event_bus.remove_channel("channel2").unwrap();
+		advance(&clock, &publisher, millisecond * 10, &receiver).await;
+		event_bus.add_channel("channel2").unwrap();
+		let subscriber = Subscriber::new(event_bus.clone(), vec!["channel2".to_string()]);
+		let other_receiver = subscriber.subscribe(None).unwrap().remove(0);
+		advance(&clock, &publisher, millisecond * 5, &receiver).await;
+		assert_eq!(other_receiver.try_recv().unwrap().topic, "tock");
+
+		// Cancelling a publish that already went out isn't remembered
+		let once = publisher.publish_after(millisecond, "once".to_string(), "".to_string());
+		advance(&clock, &publisher, millisecond, &receiver).await;
+		event_bus.cancel_scheduled(once);
+		// The pass that publishes a marker has finished once the next marker is received
+		advance(&clock, &publisher, millisecond, &receiver).await;
+		advance(&clock, &publisher, millisecond, &receiver).await;
+		assert!(event_bus.scheduler().cancelled.lock().unwrap().is_empty());
+	}
+
+	#[async_std::test]
+	async fn cron_publish() {
+		let (event_bus, clock, publisher) = scheduled_bus();
+		let subscriber = Subscriber::new(event_bus, vec!["channel1".to_string()]);
+		let receiver = subscriber.subscribe(None).unwrap().remove(0);
+
+		publisher.publish_cron(
+			Cron::parse("0 0 * * *").unwrap(),
+			"midnight".to_string(),
+			"".to_string(),
+		);
+		let minute = Duration::from_secs(60);
+		assert!(advance(&clock, &publisher, minute * 29, &receiver)
+			.await
+			.is_empty());
+		assert_eq!(
+			advance(&clock, &publisher, minute, &receiver).await,
+			["midnight"]
+		);
+		assert_eq!(
+			advance(&clock, &publisher, minute * 60 * 48, &receiver).await,
+			["midnight", "midnight"]
+		);
+	}
+
+	#[async_std::test]
+	async fn namespaces() {
+		let event_bus = Arc::new(EventBus::<String>::new());
+		for channel_name in ["ui/menu", "ui/hud/health", "uikit", "audio/music"] {
+			event_bus.add_channel(channel_name).unwrap();
+		}
+
+		assert_eq!(
+			event_bus.channels_in_namespace("ui/*"),
+			vec!["ui/hud/health".to_string(), "ui/menu".to_string()]
+		);
+		assert_eq!(
+			event_bus.channels_in_namespace("ui/hud"),
+			vec!["ui/hud/health".to_string()]
+		);
+
+		let subscriber = Subscriber::for_namespace(event_bus.clone(), "ui");
+		assert_eq!(subscriber.subscribe(None).unwrap().len(), 2);
+
+		assert_eq!(event_bus.remove_namespace("ui/*"), 2);
+		assert!(event_bus.channels_in_namespace("ui").is_empty());
+		assert!(event_bus.get_channel("uikit").is_some());
+		assert!(event_bus.get_channel("audio/music").is_some());
 	}
 }
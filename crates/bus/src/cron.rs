@@ -0,0 +1,197 @@
+use std::time::Duration;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+// Far enough ahead to find the next 29th of February, plus the odd impossible date
+const MAX_DAYS_AHEAD: u64 = 366 * 8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CronError {
+	FieldCount(usize),
+	InvalidField(String),
+}
+
+impl std::fmt::Display for CronError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			CronError::FieldCount(count) => {
+				write!(f, "Expected 5 cron fields, found {}", count)
+			}
+			CronError::InvalidField(field) => write!(f, "Invalid cron field: {}", field),
+		}
+	}
+}
+
+impl std::error::Error for CronError {}
+
+/// A calendar schedule in cron's `minute hour day-of-month month day-of-week` form,
+/// evaluated in UTC. Each field is `*`, a number, a range like `1-5`, a step like `*/15`
+/// or `8-18/2`, or a comma separated list of those. Days of the week run from 0 for
+/// Sunday to 6, with 7 also meaning Sunday.
+///
+/// As in cron, when both the day of the month and the day of the week are restricted,
+/// a day matching either one fires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cron {
+	minutes: u64,
+	hours: u64,
+	days: u64,
+	months: u64,
+	weekdays: u64,
+	any_day: bool,
+	any_weekday: bool,
+}
+
+impl Cron {
+	pub fn parse(expression: &str) -> Result<Self, CronError> {
+		let fields = expression.split_whitespace().collect::<Vec<_>>();
+		let [minutes, hours, days, months, weekdays] = fields[..] else {
+			return Err(CronError::FieldCount(fields.len()));
+		};
+		let mut weekday_bits = parse_field(weekdays, 0, 7)?;
+		// Both 0 and 7 are Sunday
+		if weekday_bits & (1 << 7) != 0 {
+			weekday_bits |= 1;
+		}
+		Ok(Self {
+			minutes: parse_field(minutes, 0, 59)?,
+			hours: parse_field(hours, 0, 23)?,
+			days: parse_field(days, 1, 31)?,
+			months: parse_field(months, 1, 12)?,
+			weekdays: weekday_bits,
+			any_day: days.starts_with('*'),
+			any_weekday: weekdays.starts_with('*'),
+		})
+	}
+
+	/// The first time it fires after the given time, both since the Unix epoch
+	pub fn next_after(&self, time: Duration) -> Option<Duration> {
+		let start = time.as_secs() / 60 + 1;
+		let first_day = start * 60 / SECONDS_PER_DAY;
+		let first_minute = start % (24 * 60);
+		(first_day..first_day + MAX_DAYS_AHEAD)
+			.filter(|day| self.matches_day(*day))
+			.find_map(|day| {
+				let from = if day == first_day { first_minute } else { 0 };
+				(from..24 * 60)
+					.find(|minute| {
+						self.hours & (1 << (minute / 60)) != 0
+							&& self.minutes & (1 << (minute % 60)) != 0
+					})
+					.map(|minute| Duration::from_secs(day * SECONDS_PER_DAY + minute * 60))
+			})
+	}
+
+	fn matches_day(&self, day: u64) -> bool {
+		let (_, month, day_of_month) = civil_from_days(day);
+		if self.months & (1 << month) == 0 {
+			return false;
+		}
+		let weekday = (day + 4) % 7;
+		let on_day = self.days & (1 << day_of_month) != 0;
+		let on_weekday = self.weekdays & (1 << weekday) != 0;
+		match (self.any_day, self.any_weekday) {
+			(false, false) => on_day || on_weekday,
+			_ => on_day && on_weekday,
+		}
+	}
+}
+
+impl std::str::FromStr for Cron {
+	type Err = CronError;
+
+	fn from_str(expression: &str) -> Result<Self, Self::Err> {
+		Self::parse(expression)
+	}
+}
+
+// The values a field allows, as a bit per value
+fn parse_field(field: &str, min: u64, max: u64) -> Result<u64, CronError> {
+	let invalid = || CronError::InvalidField(field.to_string());
+	let mut bits = 0;
+	for part in field.split(',') {
+		let (range, step) = match part.split_once('/') {
+			Some((range, step)) => (range, step.parse::<u64>().map_err(|_| invalid())?),
+			None => (part, 1),
+		};
+		let (start, end) = match range {
+			"*" => (min, max),
+			range => match range.split_once('-') {
+				Some((start, end)) => (
+					start.parse().map_err(|_| invalid())?,
+					end.parse().map_err(|_| invalid())?,
+				),
+				None => {
+					let value = range.parse().map_err(|_| invalid())?;
+					// A single value with a step runs to the end, like `5/15`
+					(value, if part.contains('/') { max } else { value })
+				}
+			},
+		};
+		if step == 0 || start < min || end > max || start > end {
+			return Err(invalid());
+		}
+		for value in (start..=end).step_by(step as usize) {
+			bits |= 1 << value;
+		}
+	}
+	Ok(bits)
+}
+
+// The year, month and day of a day since the Unix epoch, from Howard Hinnant's algorithm
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+	let days = days + 719_468;
+	let era = days / 146_097;
+	let day_of_era = days % 146_097;
+	let year_of_era =
+		(day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+	let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+	let shifted_month = (5 * day_of_year + 2) / 153;
+	let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+	let month = if shifted_month < 10 {
+		shifted_month + 3
+	} else {
+		shifted_month - 9
+	};
+	let year = year_of_era + era * 400 + u64::from(month <= 2);
+	(year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// 2024-02-28 23:30:00 UTC, a Wednesday
+	const START: Duration = Duration::from_secs(1_709_163_000);
+
+	fn next(expression: &str) -> Option<u64> {
+		let next = Cron::parse(expression).unwrap().next_after(START)?;
+		Some(next.as_secs() - START.as_secs())
+	}
+
+	#[test]
+	fn next_fire_times() {
+		assert_eq!(
+			civil_from_days(START.as_secs() / SECONDS_PER_DAY),
+			(2024, 2, 28)
+		);
+		assert_eq!(next("* * * * *"), Some(60));
+		assert_eq!(next("*/15 * * * *"), Some(15 * 60));
+		assert_eq!(next("0 0 * * *"), Some(30 * 60));
+		// The leap day, then the first of March
+		assert_eq!(next("0 12 29 * *"), Some(12 * 3600 + 30 * 60));
+		assert_eq!(next("0 0 1 3 *"), Some(24 * 3600 + 30 * 60));
+		// The next Sunday is the 3rd of March, and a restricted day or weekday fires
+		assert_eq!(next("0 0 * * 0"), Some(3 * 86_400 + 30 * 60));
+		assert_eq!(next("0 0 * * 7"), next("0 0 * * 0"));
+		assert_eq!(next("0 0 1 * 0"), Some(24 * 3600 + 30 * 60));
+		assert_eq!(next("30 9-17/4 * * 1-5"), Some(10 * 3600));
+		assert_eq!(next("0 0 31 2 *"), None);
+
+		assert_eq!(Cron::parse("* * *"), Err(CronError::FieldCount(3)));
+		assert_eq!(
+			Cron::parse("60 * * * *"),
+			Err(CronError::InvalidField("60".to_string()))
+		);
+		assert!(Cron::parse("*/0 * * * *").is_err());
+	}
+}
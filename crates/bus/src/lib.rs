@@ -1,3 +1,20 @@
+mod ack;
 mod bus;
+mod cron;
+mod schedule;
 
-pub use self::bus::*;
+#[cfg(feature = "ecs")]
+pub mod resource;
+
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+
+#[cfg(feature = "remote")]
+pub mod remote;
+
+pub use self::{
+	ack::{AckSubscriber, Delivery},
+	bus::*,
+	cron::{Cron, CronError},
+	schedule::{Clock, ManualClock, ScheduleId, Sleep, SystemClock},
+};
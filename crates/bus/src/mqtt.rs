@@ -0,0 +1,206 @@
+use crate::{EventBus, EventBusError};
+use futures::{
+	future::{self, Either},
+	stream, StreamExt,
+};
+use rumqttc::{
+	AsyncClient, ClientError, ConnectionError, Event, EventLoop, MqttOptions, Packet, QoS,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{error::Error, sync::Arc};
+
+#[derive(Debug)]
+pub enum MqttBridgeError {
+	Client(ClientError),
+	Connection(Box<ConnectionError>),
+	EventBus(EventBusError),
+	Serialization(serde_json::Error),
+}
+
+impl std::fmt::Display for MqttBridgeError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			MqttBridgeError::Client(error) => write!(f, "MQTT client error: {}", error),
+			MqttBridgeError::Connection(error) => write!(f, "MQTT connection error: {}", error),
+			MqttBridgeError::EventBus(error) => write!(f, "Event bus error: {}", error),
+			MqttBridgeError::Serialization(error) => {
+				write!(f, "Message serialization failed: {}", error)
+			}
+		}
+	}
+}
+
+impl Error for MqttBridgeError {}
+
+impl From<ClientError> for MqttBridgeError {
+	fn from(error: ClientError) -> Self {
+		MqttBridgeError::Client(error)
+	}
+}
+
+impl From<ConnectionError> for MqttBridgeError {
+	fn from(error: ConnectionError) -> Self {
+		MqttBridgeError::Connection(Box::new(error))
+	}
+}
+
+impl From<EventBusError> for MqttBridgeError {
+	fn from(error: EventBusError) -> Self {
+		MqttBridgeError::EventBus(error)
+	}
+}
+
+impl From<serde_json::Error> for MqttBridgeError {
+	fn from(error: serde_json::Error) -> Self {
+		MqttBridgeError::Serialization(error)
+	}
+}
+
+/// Maps event bus channels onto an MQTT broker.
+///
+/// A message published on bus channel `channel` with topic `topic` is published
+/// to the MQTT topic `channel/topic` with a JSON payload, and the reverse
+/// mapping is applied to messages received for the incoming channels.
+pub struct MqttBridge<T: Clone + Send + 'static> {
+	event_bus: Arc<EventBus<T>>,
+	client: AsyncClient,
+	event_loop: EventLoop,
+	outgoing_channels: Vec<String>,
+	incoming_channels: Vec<String>,
+}
+
+impl<T> MqttBridge<T>
+where
+	T: Clone + Send + Serialize + DeserializeOwned + 'static,
+{
+	pub fn new(
+		event_bus: Arc<EventBus<T>>,
+		options: MqttOptions,
+		outgoing_channels: Vec<String>,
+		incoming_channels: Vec<String>,
+	) -> Self {
+		let (client, event_loop) = AsyncClient::new(options, 64);
+		MqttBridge {
+			event_bus,
+			client,
+			event_loop,
+			outgoing_channels,
+			incoming_channels,
+		}
+	}
+
+	/// Runs the bridge until the broker connection fails.
+	/// The bridge must be driven by a tokio runtime.
+	pub async fn run(self) -> Result<(), MqttBridgeError> {
+		let Self {
+			event_bus,
+			client,
+			mut event_loop,
+			outgoing_channels,
+			incoming_channels,
+		} = self;
+
+		for channel_name in incoming_channels.iter() {
+			client
+				.subscribe(format!("{channel_name}/#"), QoS::AtLeastOnce)
+				.await?;
+		}
+
+		let forward = forward(&event_bus, &client, &outgoing_channels);
+		let receive = async {
+			loop {
+				if let Event::Incoming(Packet::Publish(publish)) = event_loop.poll().await? {
+					let Some((channel_name, topic)) =
+						split_topic(&incoming_channels, &publish.topic)
+					else {
+						continue;
+					};
+					let payload: T = serde_json::from_slice(&publish.payload)?;
+					event_bus
+						.get_channel(channel_name)
+						.ok_or(EventBusError::ChannelRemovalFailed)?
+						.send(topic.to_string(), payload)
+						.await?;
+				}
+			}
+		};
+
+		futures::pin_mut!(forward, receive);
+		match future::select(forward, receive).await {
+			Either::Left((result, _)) | Either::Right((result, _)) => result,
+		}
+	}
+}
+
+async fn forward<T>(
+	event_bus: &EventBus<T>,
+	client: &AsyncClient,
+	channel_names: &[String],
+) -> Result<(), MqttBridgeError>
+where
+	T: Clone + Send + Serialize + 'static,
+{
+	let receivers = channel_names
+		.iter()
+		.map(|channel_name| {
+			let receiver = event_bus
+				.get_channel(channel_name)
+				.ok_or(EventBusError::ChannelRemovalFailed)?
+				.receiver();
+			let channel_name = channel_name.to_string();
+			Ok(receiver.map(move |message| {
+				message.record("forwarded to mqtt broker");
+				let (topic, payload) = message.into_parts();
+				(format!("{channel_name}/{topic}"), payload)
+			}))
+		})
+		.collect::<Result<Vec<_>, MqttBridgeError>>()?;
+
+	if receivers.is_empty() {
+		// Nothing to forward, so only the receiving side can end the bridge
+		return future::pending().await;
+	}
+
+	let mut messages = stream::select_all(receivers);
+	while let Some((topic, payload)) = messages.next().await {
+		let payload = serde_json::to_vec(&payload)?;
+		client
+			.publish(topic, QoS::AtLeastOnce, false, payload)
+			.await?;
+	}
+	Ok(())
+}
+
+// Finds the longest incoming channel name that prefixes the mqtt topic
+fn split_topic<'a>(channel_names: &'a [String], topic: &'a str) -> Option<(&'a str, &'a str)> {
+	channel_names
+		.iter()
+		.filter_map(|channel_name| {
+			topic
+				.strip_prefix(channel_name.as_str())
+				.and_then(|rest| rest.strip_prefix('/'))
+				.map(|rest| (channel_name.as_str(), rest))
+		})
+		.max_by_key(|(channel_name, _)| channel_name.len())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn split_mqtt_topic() {
+		let channel_names = vec!["game".to_string(), "game/ui".to_string()];
+
+		assert_eq!(
+			split_topic(&channel_names, "game/door"),
+			Some(("game", "door"))
+		);
+		assert_eq!(
+			split_topic(&channel_names, "game/ui/click"),
+			Some(("game/ui", "click"))
+		);
+		assert_eq!(split_topic(&channel_names, "audio/play"), None);
+		assert_eq!(split_topic(&channel_names, "gameplay/door"), None);
+	}
+}
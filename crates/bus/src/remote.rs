@@ -0,0 +1,344 @@
+use crate::{EventBus, EventBusError};
+use async_std::{
+	io::{prelude::BufReadExt, BufReader, WriteExt},
+	net::TcpStream,
+};
+use futures::{
+	future::{self, Either},
+	stream, Sink, Stream, StreamExt, TryStreamExt,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+	error::Error,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc,
+	},
+};
+
+#[cfg(feature = "websocket")]
+use async_tungstenite::{
+	tungstenite::{self, Message},
+	WebSocketStream,
+};
+#[cfg(feature = "websocket")]
+use futures::{AsyncRead, AsyncWrite, SinkExt};
+
+#[derive(Debug)]
+pub enum RemoteBridgeError {
+	ChannelNotBridged(String),
+	EventBus(EventBusError),
+	Io(std::io::Error),
+	Serialization(serde_json::Error),
+	#[cfg(feature = "websocket")]
+	WebSocket(Box<tungstenite::Error>),
+}
+
+impl std::fmt::Display for RemoteBridgeError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			RemoteBridgeError::ChannelNotBridged(channel_name) => {
+				write!(f, "Channel {} is not bridged", channel_name)
+			}
+			RemoteBridgeError::EventBus(error) => write!(f, "Event bus error: {}", error),
+			RemoteBridgeError::Io(error) => write!(f, "Connection error: {}", error),
+			RemoteBridgeError::Serialization(error) => {
+				write!(f, "Message serialization failed: {}", error)
+			}
+			#[cfg(feature = "websocket")]
+			RemoteBridgeError::WebSocket(error) => write!(f, "WebSocket error: {}", error),
+		}
+	}
+}
+
+impl Error for RemoteBridgeError {}
+
+impl From<EventBusError> for RemoteBridgeError {
+	fn from(error: EventBusError) -> Self {
+		RemoteBridgeError::EventBus(error)
+	}
+}
+
+impl From<std::io::Error> for RemoteBridgeError {
+	fn from(error: std::io::Error) -> Self {
+		RemoteBridgeError::Io(error)
+	}
+}
+
+impl From<serde_json::Error> for RemoteBridgeError {
+	fn from(error: serde_json::Error) -> Self {
+		RemoteBridgeError::Serialization(error)
+	}
+}
+
+#[cfg(feature = "websocket")]
+impl From<tungstenite::Error> for RemoteBridgeError {
+	fn from(error: tungstenite::Error) -> Self {
+		RemoteBridgeError::WebSocket(Box::new(error))
+	}
+}
+
+/// A bus message as it is sent over the wire
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteMessage<T> {
+	pub channel: String,
+	pub topic: String,
+	pub payload: T,
+}
+
+/// Connects an event bus to an event bus in another process.
+///
+/// Messages published on the outgoing channels are forwarded to the remote side,
+/// and messages received from the remote side are published on the incoming channels.
+/// Keep the two sets disjoint, otherwise messages will be echoed back and forth.
+/// Received messages that can't be read or are for a channel that isn't incoming are
+/// skipped and counted, rather than ending the bridge.
+pub struct RemoteBridge<T: Clone + Send + 'static> {
+	event_bus: Arc<EventBus<T>>,
+	outgoing_channels: Vec<String>,
+	incoming_channels: Vec<String>,
+	rejected: AtomicU64,
+}
+
+impl<T> RemoteBridge<T>
+where
+	T: Clone + Send + Serialize + DeserializeOwned + 'static,
+{
+	pub fn new(
+		event_bus: Arc<EventBus<T>>,
+		outgoing_channels: Vec<String>,
+		incoming_channels: Vec<String>,
+	) -> Self {
+		RemoteBridge {
+			event_bus,
+			outgoing_channels,
+			incoming_channels,
+			rejected: AtomicU64::new(0),
+		}
+	}
+
+	/// How many received messages were skipped
+	pub fn rejected(&self) -> u64 {
+		self.rejected.load(Ordering::Relaxed)
+	}
+
+	fn reject(&self, _error: RemoteBridgeError) {
+		self.rejected.fetch_add(1, Ordering::Relaxed);
+		diagnostics::count("bus rejected messages", 1);
+		#[cfg(feature = "tracing")]
+		tracing::warn!("Skipped a remote message: {}", _error);
+	}
+
+	/// Bridges the bus over a TCP connection using newline delimited JSON
+	/// until the connection is closed or an error occurs.
+	pub async fn run_tcp(&self, stream: TcpStream) -> Result<(), RemoteBridgeError> {
+		let sink = futures::sink::unfold(stream.clone(), |mut writer, line: String| async move {
+			writer.write_all(line.as_bytes()).await?;
+			writer.write_all(b"\n").await?;
+			Ok::<_, RemoteBridgeError>(writer)
+		});
+		let lines = BufReader::new(stream)
+			.lines()
+			.map_err(RemoteBridgeError::from);
+		self.run(sink, lines).await
+	}
+
+	/// Connects to a bridge listening at the address, then bridges the bus over
+	/// the connection like `run_tcp`.
+	pub async fn connect_tcp(&self, address: &str) -> Result<(), RemoteBridgeError> {
+		self.run_tcp(TcpStream::connect(address).await?).await
+	}
+
+	/// Bridges the bus over a WebSocket connection using JSON text messages
+	/// until the connection is closed or an error occurs.
+	#[cfg(feature = "websocket")]
+	pub async fn run_websocket<S>(
+		&self,
+		websocket: WebSocketStream<S>,
+	) -> Result<(), RemoteBridgeError>
+	where
+		S: AsyncRead + AsyncWrite + Unpin,
+	{
+		let (sink, stream) = websocket.split();
+		let sink = sink
+			.sink_map_err(RemoteBridgeError::from)
+			.with(|line: String| async move { Ok::<_, RemoteBridgeError>(Message::text(line)) });
+		let stream = stream
+			.map_err(RemoteBridgeError::from)
+			.try_filter_map(|message| async move {
+				match message {
+					Message::Text(text) => Ok(Some(text.to_string())),
+					_ => Ok(None),
+				}
+			});
+		self.run(sink, stream).await
+	}
+
+	async fn run<Si, St>(&self, sink: Si, stream: St) -> Result<(), RemoteBridgeError>
+	where
+		Si: Sink<String, Error = RemoteBridgeError>,
+		St: Stream<Item = Result<String, RemoteBridgeError>>,
+	{
+		let forward = self.forward(sink);
+		let receive = self.receive(stream);
+		futures::pin_mut!(forward, receive);
+		match future::select(forward, receive).await {
+			Either::Left((result, _)) | Either::Right((result, _)) => result,
+		}
+	}
+
+	async fn forward<S>(&self, sink: S) -> Result<(), RemoteBridgeError>
+	where
+		S: Sink<String, Error = RemoteBridgeError>,
+	{
+		let receivers = self
+			.outgoing_channels
+			.iter()
+			.map(|channel_name| {
+				let receiver = self
+					.event_bus
+					.get_channel(channel_name)
+					.ok_or(EventBusError::ChannelRemovalFailed)?
+					.receiver();
+				let channel_name = channel_name.to_string();
+				Ok(receiver.map(move |message| {
+					message.record("forwarded to remote bus");
+					let (topic, payload) = message.into_parts();
+					RemoteMessage {
+						channel: channel_name.clone(),
+						topic,
+						payload,
+					}
+				}))
+			})
+			.collect::<Result<Vec<_>, RemoteBridgeError>>()?;
+
+		if receivers.is_empty() {
+			// Nothing to forward, so only the receiving side can end the bridge
+			return future::pending().await;
+		}
+
+		stream::select_all(receivers)
+			.map(|message| serde_json::to_string(&message).map_err(RemoteBridgeError::from))
+			.forward(sink)
+			.await
+	}
+
+	async fn receive<S>(&self, stream: S) -> Result<(), RemoteBridgeError>
+	where
+		S: Stream<Item = Result<String, RemoteBridgeError>>,
+	{
+		futures::pin_mut!(stream);
+		while let Some(line) = stream.try_next().await? {
+			let message: RemoteMessage<T> = match serde_json::from_str(&line) {
+				Ok(message) => message,
+				Err(error) => {
+					self.reject(error.into());
+					continue;
+				}
+			};
+			if !self.incoming_channels.contains(&message.channel) {
+				self.reject(RemoteBridgeError::ChannelNotBridged(message.channel));
+				continue;
+			}
+			self.event_bus
+				.get_channel(&message.channel)
+				.ok_or(EventBusError::ChannelRemovalFailed)?
+				.send(message.topic, message.payload)
+				.await?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Publisher, Subscriber};
+	use async_std::net::TcpListener;
+
+	#[async_std::test]
+	async fn bridge_over_tcp() -> Result<(), Box<dyn Error>> {
+		let local_bus = Arc::new(EventBus::<String>::new());
+		local_bus.add_channel("telemetry")?;
+		let remote_bus = Arc::new(EventBus::<String>::new());
+		remote_bus.add_channel("telemetry")?;
+
+		let listener = TcpListener::bind("127.0.0.1:0").await?;
+		let address = listener.local_addr()?;
+
+		let remote_bridge = RemoteBridge::new(
+			remote_bus.clone(),
+			Vec::new(),
+			vec!["telemetry".to_string()],
+		);
+		async_std::task::spawn(async move {
+			let (stream, _) = listener.accept().await?;
+			remote_bridge.run_tcp(stream).await
+		});
+
+		let local_bridge =
+			RemoteBridge::new(local_bus.clone(), vec!["telemetry".to_string()], Vec::new());
+		let stream = TcpStream::connect(address).await?;
+		async_std::task::spawn(async move { local_bridge.run_tcp(stream).await });
+
+		let publisher = Publisher::new(local_bus, "telemetry".to_string());
+		publisher
+			.publish("door".to_string(), "opened".to_string())
+			.await?;
+
+		let subscriber = Subscriber::new(remote_bus, vec!["telemetry".to_string()]);
+		let receivers = subscriber.subscribe(None)?;
+		assert_eq!(
+			receivers[0].recv().await?.into_parts(),
+			("door".to_string(), "opened".to_string())
+		);
+
+		Ok(())
+	}
+
+	#[async_std::test]
+	async fn bad_messages_are_skipped() -> Result<(), Box<dyn Error>> {
+		let bus = Arc::new(EventBus::<String>::new());
+		bus.add_channel("telemetry")?;
+		let listener = TcpListener::bind("127.0.0.1:0").await?;
+		let address = listener.local_addr()?;
+		let bridge = Arc::new(RemoteBridge::new(
+			bus.clone(),
+			Vec::new(),
+			vec!["telemetry".to_string()],
+		));
+		let running = bridge.clone();
+		async_std::task::spawn(async move {
+			let (stream, _) = listener.accept().await?;
+			running.run_tcp(stream).await
+		});
+		let subscriber = Subscriber::new(bus, vec!["telemetry".to_string()]);
+		let receivers = subscriber.subscribe(None)?;
+
+		let mut stream = TcpStream::connect(address).await?;
+		let lines = [
+			"not json".to_string(),
+			serde_json::to_string(&RemoteMessage {
+				channel: "secrets".to_string(),
+				topic: "door".to_string(),
+				payload: "ignored".to_string(),
+			})?,
+			serde_json::to_string(&RemoteMessage {
+				channel: "telemetry".to_string(),
+				topic: "door".to_string(),
+				payload: "opened".to_string(),
+			})?,
+		];
+		stream
+			.write_all(format!("{}\n", lines.join("\n")).as_bytes())
+			.await?;
+
+		assert_eq!(
+			receivers[0].recv().await?.into_parts(),
+			("door".to_string(), "opened".to_string())
+		);
+		assert_eq!(bridge.rejected(), 2);
+		Ok(())
+	}
+}
@@ -0,0 +1,98 @@
+use crate::{EventBus, EventBusError, Message};
+use ecs::events::Events;
+use std::sync::Arc;
+
+/// Shares an event bus with ECS systems when inserted into the world's resources
+pub struct BusResource<T: Clone + Send + 'static> {
+	event_bus: Arc<EventBus<T>>,
+}
+
+impl<T: Clone + Send + 'static> BusResource<T> {
+	pub fn new(event_bus: Arc<EventBus<T>>) -> Self {
+		Self { event_bus }
+	}
+
+	pub fn event_bus(&self) -> &Arc<EventBus<T>> {
+		&self.event_bus
+	}
+
+	/// Publishes a message without awaiting, for use in synchronous systems
+	pub fn publish_sync(
+		&self,
+		channel_name: &str,
+		topic: &str,
+		payload: T,
+	) -> Result<(), EventBusError> {
+		self.event_bus
+			.get_channel(channel_name)
+			.ok_or(EventBusError::ChannelRemovalFailed)?
+			.try_send(topic.to_string(), payload)
+	}
+
+	/// Moves all pending messages on a channel into an event queue,
+	/// returning the number of messages moved
+	pub fn drain_into(
+		&self,
+		channel_name: &str,
+		events: &mut Events<Message<T>>,
+	) -> Result<usize, EventBusError> {
+		let receiver = self
+			.event_bus
+			.get_channel(channel_name)
+			.ok_or(EventBusError::ChannelRemovalFailed)?
+			.receiver();
+		let count = events.len();
+		events.extend(std::iter::from_fn(|| receiver.try_recv().ok()));
+		Ok(events.len() - count)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ecs::{error::Result, system, world::World};
+
+	#[derive(Default)]
+	struct Door {
+		open: bool,
+	}
+
+	system!(door_system, [resources, _entity], (), (door: Door) -> Result<()> {
+		if door.open {
+			resources
+				.borrow()
+				.get::<BusResource<String>>()
+				.unwrap()
+				.publish_sync("gameplay", "door", "opened".to_string())?;
+		}
+		Ok(())
+	});
+
+	#[test]
+	fn publish_from_system() -> Result<()> {
+		let event_bus = Arc::new(EventBus::<String>::new());
+		event_bus.add_channel("gameplay")?;
+
+		let mut world = World::new();
+		world
+			.resources()
+			.borrow_mut()
+			.insert(BusResource::new(event_bus.clone()));
+
+		let entity = world.create_entity();
+		world.add_component(entity, Door { open: true })?;
+		door_system(&mut world)?;
+
+		let mut events = Events::new();
+		let resources = world.resources().borrow();
+		let bus = resources.get::<BusResource<String>>().unwrap();
+		assert_eq!(bus.drain_into("gameplay", &mut events)?, 1);
+		assert_eq!(
+			events.drain().map(Message::into_parts).collect::<Vec<_>>(),
+			vec![("door".to_string(), "opened".to_string())]
+		);
+		assert_eq!(bus.drain_into("gameplay", &mut events)?, 0);
+
+		Ok(())
+	}
+}
@@ -0,0 +1,363 @@
+use crate::{cron::Cron, EventBus};
+use async_channel::{Receiver, Sender};
+use futures::future::{self, Either, Future, FutureExt};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::{
+	cmp::{Ordering, Reverse},
+	collections::{BinaryHeap, HashSet},
+	sync::{
+		atomic::{AtomicBool, AtomicU64, Ordering as AtomicOrdering},
+		Arc, Mutex, Weak,
+	},
+	time::Duration,
+};
+#[cfg(target_arch = "wasm32")]
+use web_time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Waits for a `Clock`, and is only `Send` where tasks can move between threads
+#[cfg(not(target_arch = "wasm32"))]
+pub type Sleep = futures::future::BoxFuture<'static, ()>;
+#[cfg(target_arch = "wasm32")]
+pub type Sleep = futures::future::LocalBoxFuture<'static, ()>;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn sleep(future: impl Future<Output = ()> + Send + 'static) -> Sleep {
+	future.boxed()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn sleep(future: impl Future<Output = ()> + 'static) -> Sleep {
+	future.boxed_local()
+}
+
+/// Where a bus's scheduled publishes get the time from. Times are since the Unix epoch,
+/// so cron schedules fire at the right time of day.
+pub trait Clock: Send + Sync + 'static {
+	fn now(&self) -> Duration;
+
+	/// Completes once the time reaches the deadline. Completing early is fine, since the
+	/// scheduler checks the time again.
+	fn sleep_until(&self, deadline: Duration) -> Sleep;
+}
+
+/// The time on the system's clock when it was created, moving forward monotonically from
+/// there so changes to the system's clock don't bunch up or skip publishes
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+	started: Instant,
+	epoch_time: Duration,
+}
+
+impl Default for SystemClock {
+	fn default() -> Self {
+		Self {
+			started: Instant::now(),
+			epoch_time: SystemTime::now()
+				.duration_since(UNIX_EPOCH)
+				.unwrap_or_default(),
+		}
+	}
+}
+
+impl Clock for SystemClock {
+	fn now(&self) -> Duration {
+		self.epoch_time + self.started.elapsed()
+	}
+
+	fn sleep_until(&self, deadline: Duration) -> Sleep {
+		sleep(async_std::task::sleep(deadline.saturating_sub(self.now())))
+	}
+}
+
+/// A clock that only moves when advanced, so scheduled publishes can be tested without
+/// waiting on real time
+#[derive(Default)]
+pub struct ManualClock {
+	now: Mutex<Duration>,
+	sleepers: Mutex<Vec<(Duration, Sender<()>)>>,
+}
+
+impl ManualClock {
+	pub fn new(now: Duration) -> Self {
+		Self {
+			now: Mutex::new(now),
+			sleepers: Mutex::new(Vec::new()),
+		}
+	}
+
+	pub fn advance(&self, duration: Duration) {
+		let now = {
+			let mut now = self.now.lock().unwrap();
+			*now += duration;
+			*now
+		};
+		self.sleepers.lock().unwrap().retain(|(deadline, sender)| {
+			if *deadline <= now {
+				let _ = sender.try_send(());
+				return false;
+			}
+			!sender.is_closed()
+		});
+	}
+}
+
+impl Clock for ManualClock {
+	fn now(&self) -> Duration {
+		*self.now.lock().unwrap()
+	}
+
+	fn sleep_until(&self, deadline: Duration) -> Sleep {
+		let (sender, receiver) = async_channel::bounded(1);
+		let mut sleepers = self.sleepers.lock().unwrap();
+		// Checked with the sleepers locked so an advance can't slip in between
+		if deadline <= self.now() {
+			return sleep(future::ready(()));
+		}
+		sleepers.push((deadline, sender));
+		sleep(async move {
+			let _ = receiver.recv().await;
+		})
+	}
+}
+
+// The shortest repeat, so a zero interval can't keep the scheduler publishing forever
+const MIN_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Identifies a scheduled publish so it can be cancelled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScheduleId(u64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Schedule {
+	Once(Duration),
+	Every(Duration),
+	Cron(Cron),
+}
+
+impl Schedule {
+	fn first(&self, now: Duration) -> Option<Duration> {
+		match self {
+			Schedule::Once(delay) | Schedule::Every(delay) => Some(now + *delay),
+			Schedule::Cron(cron) => cron.next_after(now),
+		}
+	}
+
+	// The deadline after one that was just published, if it repeats
+	fn next(&self, deadline: Duration) -> Option<Duration> {
+		match self {
+			Schedule::Once(_) => None,
+			Schedule::Every(interval) => Some(deadline + *interval),
+			Schedule::Cron(cron) => cron.next_after(deadline),
+		}
+	}
+}
+
+struct ScheduledPublish<T> {
+	id: ScheduleId,
+	// Since the Unix epoch, on the scheduler's clock
+	deadline: Duration,
+	schedule: Schedule,
+	channel_name: String,
+	topic: String,
+	payload: T,
+}
+
+impl<T> PartialEq for ScheduledPublish<T> {
+	fn eq(&self, other: &Self) -> bool {
+		self.deadline == other.deadline && self.id == other.id
+	}
+}
+
+impl<T> Eq for ScheduledPublish<T> {}
+
+impl<T> PartialOrd for ScheduledPublish<T> {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl<T> Ord for ScheduledPublish<T> {
+	fn cmp(&self, other: &Self) -> Ordering {
+		self.deadline
+			.cmp(&other.deadline)
+			.then(self.id.0.cmp(&other.id.0))
+	}
+}
+
+// Holds every pending publish of a bus, driven by a single background task
+pub(crate) struct Scheduler<T> {
+	queue: Mutex<BinaryHeap<Reverse<ScheduledPublish<T>>>>,
+	pub(crate) cancelled: Mutex<HashSet<ScheduleId>>,
+	next_id: AtomicU64,
+	started: AtomicBool,
+	wakeup_sender: Sender<()>,
+	wakeup_receiver: Receiver<()>,
+	clock: Arc<dyn Clock>,
+}
+
+impl<T: Clone + Send + 'static> Scheduler<T> {
+	pub(crate) fn new() -> Self {
+		let (wakeup_sender, wakeup_receiver) = async_channel::unbounded();
+		Self {
+			queue: Mutex::new(BinaryHeap::new()),
+			cancelled: Mutex::new(HashSet::new()),
+			next_id: AtomicU64::new(0),
+			started: AtomicBool::new(false),
+			wakeup_sender,
+			wakeup_receiver,
+			clock: Arc::new(SystemClock::default()),
+		}
+	}
+
+	pub(crate) fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+		self.clock = clock;
+	}
+
+	pub(crate) fn clock(&self) -> &Arc<dyn Clock> {
+		&self.clock
+	}
+
+	// Drops the publish if it's queued. Otherwise it may be in the middle of publishing, so
+	// it's remembered until the scheduler next finishes publishing.
+	pub(crate) fn cancel(&self, id: ScheduleId) {
+		let mut queue = self.queue.lock().unwrap();
+		let queued = queue.len();
+		queue.retain(|Reverse(scheduled)| scheduled.id != id);
+		if queue.len() == queued {
+			self.cancelled.lock().unwrap().insert(id);
+			let _ = self.wakeup_sender.try_send(());
+		}
+	}
+
+	fn next_deadline(&self) -> Option<Duration> {
+		self.queue
+			.lock()
+			.unwrap()
+			.peek()
+			.map(|Reverse(scheduled)| scheduled.deadline)
+	}
+
+	// One at a time, so repeats that fall due while catching up go out in order
+	fn pop_due(&self, now: Duration) -> Option<ScheduledPublish<T>> {
+		let mut queue = self.queue.lock().unwrap();
+		if queue
+			.peek()
+			.is_some_and(|Reverse(scheduled)| scheduled.deadline <= now)
+		{
+			return queue.pop().map(|Reverse(scheduled)| scheduled);
+		}
+		None
+	}
+
+	fn push(&self, scheduled: ScheduledPublish<T>) {
+		self.queue.lock().unwrap().push(Reverse(scheduled));
+	}
+
+	// Returns true when the publish was cancelled and should be dropped
+	fn take_cancelled(&self, id: ScheduleId) -> bool {
+		self.cancelled.lock().unwrap().remove(&id)
+	}
+
+	// Forgets cancellations of publishes that are no longer queued, such as one that
+	// already went out, since they can't fire again
+	fn forget_cancelled(&self) {
+		let queue = self.queue.lock().unwrap();
+		self.cancelled
+			.lock()
+			.unwrap()
+			.retain(|id| queue.iter().any(|Reverse(scheduled)| scheduled.id == *id));
+	}
+}
+
+impl<T: Clone + Send + 'static> EventBus<T> {
+	pub(crate) fn schedule(
+		self: &Arc<Self>,
+		channel_name: &str,
+		schedule: Schedule,
+		message: (String, T),
+	) -> ScheduleId {
+		let scheduler = self.scheduler();
+		let id = ScheduleId(scheduler.next_id.fetch_add(1, AtomicOrdering::Relaxed));
+		let (topic, payload) = message;
+		let schedule = match schedule {
+			Schedule::Every(interval) => Schedule::Every(interval.max(MIN_INTERVAL)),
+			schedule => schedule,
+		};
+		// A cron schedule that never fires is never queued
+		let Some(deadline) = schedule.first(scheduler.clock.now()) else {
+			return id;
+		};
+		scheduler.push(ScheduledPublish {
+			id,
+			deadline,
+			schedule,
+			channel_name: channel_name.to_string(),
+			topic,
+			payload,
+		});
+
+		if !scheduler.started.swap(true, AtomicOrdering::SeqCst) {
+			#[cfg(not(target_arch = "wasm32"))]
+			async_std::task::spawn(run_scheduler(Arc::downgrade(self)));
+			#[cfg(target_arch = "wasm32")]
+			async_std::task::spawn_local(run_scheduler(Arc::downgrade(self)));
+		}
+		let _ = scheduler.wakeup_sender.try_send(());
+
+		id
+	}
+
+	/// Cancels a delayed or repeating publish
+	pub fn cancel_scheduled(&self, id: ScheduleId) {
+		self.scheduler().cancel(id);
+	}
+}
+
+async fn run_scheduler<T: Clone + Send + 'static>(event_bus: Weak<EventBus<T>>) {
+	loop {
+		// Only hold a strong reference while publishing so the task ends with the bus
+		let (next_deadline, wakeup, clock) = {
+			let Some(event_bus) = event_bus.upgrade() else {
+				return;
+			};
+			let scheduler = event_bus.scheduler();
+			let now = scheduler.clock.now();
+			while let Some(mut scheduled) = scheduler.pop_due(now) {
+				if scheduler.take_cancelled(scheduled.id) {
+					continue;
+				}
+				// Missed while its channel is missing or full, but repeats still go out later
+				if let Some(channel) = event_bus.get_channel(&scheduled.channel_name) {
+					let _ = channel.try_send(scheduled.topic.clone(), scheduled.payload.clone());
+				}
+				if let Some(deadline) = scheduled.schedule.next(scheduled.deadline) {
+					scheduled.deadline = deadline;
+					scheduler.push(scheduled);
+				}
+			}
+			scheduler.forget_cancelled();
+			(
+				scheduler.next_deadline(),
+				scheduler.wakeup_receiver.clone(),
+				scheduler.clock.clone(),
+			)
+		};
+
+		let woken = match next_deadline {
+			Some(deadline) => {
+				let sleep = clock.sleep_until(deadline);
+				let wakeup = wakeup.recv();
+				futures::pin_mut!(sleep, wakeup);
+				match future::select(sleep, wakeup).await {
+					Either::Left(_) => Ok(()),
+					Either::Right((result, _)) => result,
+				}
+			}
+			None => wakeup.recv().await,
+		};
+		if woken.is_err() {
+			return;
+		}
+	}
+}
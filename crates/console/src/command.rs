@@ -0,0 +1,193 @@
+use ecs::world::World;
+use std::{any::type_name, rc::Rc, str::FromStr};
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ConsoleError {
+	#[error("Unknown command or variable: {0}")]
+	Unknown(String),
+
+	#[error("Missing argument {}", .0 + 1)]
+	MissingArgument(usize),
+
+	#[error("Argument {} should be {expected}, not '{value}'", .index + 1)]
+	InvalidArgument {
+		index: usize,
+		value: String,
+		expected: &'static str,
+	},
+
+	#[error("A quote was left open")]
+	UnclosedQuote,
+
+	#[error("Unknown variable: {0}")]
+	UnknownVariable(String),
+
+	#[error("'{value}' isn't a valid value for {name}")]
+	InvalidValue { name: String, value: String },
+
+	#[error("{0}")]
+	Failed(String),
+}
+
+/// What a command is run with. Handlers return the text to print, which can be empty.
+pub type Handler = Rc<dyn Fn(&mut World, &Args) -> Result<String, ConsoleError>>;
+
+/// A line typed in the console, split into its command name and arguments. Arguments
+/// are separated by spaces, and can be quoted to include spaces.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Args {
+	name: String,
+	values: Vec<String>,
+	// The text after the name, as typed
+	text: String,
+}
+
+impl Args {
+	pub fn parse(line: &str) -> Result<Self, ConsoleError> {
+		let line = line.trim();
+		let (name, text) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+		Ok(Self {
+			name: name.to_string(),
+			values: split(text)?,
+			text: text.trim_start().to_string(),
+		})
+	}
+
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	pub fn len(&self) -> usize {
+		self.values.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.values.is_empty()
+	}
+
+	/// The argument as typed, without its quotes
+	pub fn text(&self, index: usize) -> Result<&str, ConsoleError> {
+		self.values
+			.get(index)
+			.map(String::as_str)
+			.ok_or(ConsoleError::MissingArgument(index))
+	}
+
+	/// The argument read as a number, bool or anything else that parses from text
+	pub fn get<T: FromStr>(&self, index: usize) -> Result<T, ConsoleError> {
+		let value = self.text(index)?;
+		value.parse().map_err(|_| ConsoleError::InvalidArgument {
+			index,
+			value: value.to_string(),
+			expected: short_type_name::<T>(),
+		})
+	}
+
+	/// The argument, or the default when it wasn't given
+	pub fn get_or<T: FromStr>(&self, index: usize, default: T) -> Result<T, ConsoleError> {
+		match self.values.get(index) {
+			Some(_) => self.get(index),
+			None => Ok(default),
+		}
+	}
+
+	/// Everything after the name as typed, quotes included
+	pub fn rest(&self) -> &str {
+		&self.text
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = &str> {
+		self.values.iter().map(String::as_str)
+	}
+}
+
+fn split(text: &str) -> Result<Vec<String>, ConsoleError> {
+	let mut values = Vec::new();
+	let mut value: Option<String> = None;
+	let mut quoted = false;
+	for character in text.chars() {
+		match character {
+			'"' => {
+				quoted = !quoted;
+				value.get_or_insert_with(String::new);
+			}
+			character if character.is_whitespace() && !quoted => values.extend(value.take()),
+			character => value.get_or_insert_with(String::new).push(character),
+		}
+	}
+	if quoted {
+		return Err(ConsoleError::UnclosedQuote);
+	}
+	values.extend(value);
+	Ok(values)
+}
+
+fn short_type_name<T>() -> &'static str {
+	let name = type_name::<T>();
+	name.rsplit("::").next().unwrap_or(name)
+}
+
+/// A command added with `Console::register_command`
+#[derive(Clone)]
+pub struct Command {
+	pub(crate) handler: Handler,
+	pub description: String,
+}
+
+impl Command {
+	pub(crate) fn new(handler: Handler) -> Self {
+		Self {
+			handler,
+			description: String::new(),
+		}
+	}
+
+	/// Sets what `help` shows for the command
+	pub fn describe(&mut self, description: impl Into<String>) -> &mut Self {
+		self.description = description.into();
+		self
+	}
+}
+
+impl std::fmt::Debug for Command {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Command")
+			.field("description", &self.description)
+			.finish_non_exhaustive()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn arguments_are_split_and_parsed() -> Result<(), ConsoleError> {
+		let args = Args::parse("  spawn goblin 3 \"Big Bob\" \"\"  ")?;
+		assert_eq!(args.name(), "spawn");
+		assert_eq!(
+			args.iter().collect::<Vec<_>>(),
+			["goblin", "3", "Big Bob", ""]
+		);
+		assert_eq!(args.rest(), "goblin 3 \"Big Bob\" \"\"");
+		assert_eq!(args.get::<u32>(1)?, 3);
+		assert_eq!(args.get_or::<f32>(4, 1.5)?, 1.5);
+		assert_eq!(
+			args.get::<u32>(0),
+			Err(ConsoleError::InvalidArgument {
+				index: 0,
+				value: "goblin".to_string(),
+				expected: "u32",
+			})
+		);
+		assert_eq!(args.text(4), Err(ConsoleError::MissingArgument(4)));
+		assert_eq!(
+			ConsoleError::MissingArgument(4).to_string(),
+			"Missing argument 5"
+		);
+		assert_eq!(Args::parse("say \"hi"), Err(ConsoleError::UnclosedQuote));
+		assert!(Args::parse("clear")?.is_empty());
+		Ok(())
+	}
+}
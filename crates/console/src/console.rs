@@ -0,0 +1,520 @@
+use crate::{
+	command::{Args, Command, ConsoleError},
+	cvar::{display, CVars},
+};
+use app::{
+	winit::{event::ElementState, keyboard::KeyCode},
+	AppEvent, TextComposition,
+};
+use ecs::world::World;
+use std::{
+	collections::{BTreeMap, VecDeque},
+	rc::Rc,
+};
+
+const MAX_HISTORY: usize = 100;
+const MAX_OUTPUT: usize = 200;
+const BUILTINS: [&str; 3] = ["clear", "help", "reset"];
+
+/// A drop-down console for running commands and changing `CVars` while the game runs,
+/// usually kept as a world resource and fed events with `handle_console_event`
+#[derive(Debug)]
+pub struct Console {
+	commands: BTreeMap<String, Command>,
+	open: bool,
+	input: String,
+	// A byte offset into the input, always on a character boundary
+	cursor: usize,
+	history: Vec<String>,
+	// The line of history being shown, while going back through it
+	history_index: Option<usize>,
+	output: VecDeque<String>,
+	toggle_key: KeyCode,
+}
+
+impl Default for Console {
+	fn default() -> Self {
+		Self {
+			commands: BTreeMap::new(),
+			open: false,
+			input: String::new(),
+			cursor: 0,
+			history: Vec::new(),
+			history_index: None,
+			output: VecDeque::new(),
+			toggle_key: KeyCode::Backquote,
+		}
+	}
+}
+
+impl Console {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// The key that opens and closes the console, the backquote by default
+	pub fn with_toggle_key(mut self, key: KeyCode) -> Self {
+		self.toggle_key = key;
+		self
+	}
+
+	/// Adds a command, replacing any with the same name:
+	///
+	/// ```
+	/// let mut console = console::Console::new();
+	/// console.register_command("spawn", |world, args| {
+	///     let name = args.text(0)?;
+	///     let count = args.get_or::<u32>(1, 1)?;
+	///     for _ in 0..count {
+	///         world.create_entity();
+	///     }
+	///     Ok(format!("Spawned {count} {name}"))
+	/// });
+	/// ```
+	pub fn register_command(
+		&mut self,
+		name: impl Into<String>,
+		handler: impl Fn(&mut World, &Args) -> Result<String, ConsoleError> + 'static,
+	) -> &mut Command {
+		let command = Command::new(Rc::new(handler));
+		let name = name.into();
+		self.commands.insert(name.clone(), command);
+		self.commands
+			.get_mut(&name)
+			.expect("the command was just added")
+	}
+
+	pub fn is_open(&self) -> bool {
+		self.open
+	}
+
+	pub fn set_open(&mut self, open: bool) {
+		self.open = open;
+	}
+
+	pub fn input(&self) -> &str {
+		&self.input
+	}
+
+	/// Where the cursor is in the input, in bytes
+	pub fn cursor(&self) -> usize {
+		self.cursor
+	}
+
+	pub fn set_input(&mut self, input: impl Into<String>) {
+		self.input = input.into();
+		self.cursor = self.input.len();
+	}
+
+	/// The lines typed, oldest first
+	pub fn history(&self) -> &[String] {
+		&self.history
+	}
+
+	/// The latest lines printed, oldest first
+	pub fn output(&self) -> impl Iterator<Item = &str> {
+		self.output.iter().map(String::as_str)
+	}
+
+	/// Adds text to the output, a line at a time
+	pub fn print(&mut self, text: impl AsRef<str>) {
+		for line in text.as_ref().lines() {
+			self.output.push_back(line.to_string());
+		}
+		while self.output.len() > MAX_OUTPUT {
+			self.output.pop_front();
+		}
+	}
+
+	/// Opens and closes the console with its toggle key, and while it's open types into it,
+	/// returning whether the event was used so the game can ignore it
+	pub fn handle_event(&mut self, world: &mut World, event: &AppEvent) -> bool {
+		match event {
+			AppEvent::KeyboardInput {
+				keycode: Some(keycode),
+				state: ElementState::Pressed,
+				..
+			} if *keycode == self.toggle_key => {
+				self.open = !self.open;
+				true
+			}
+			AppEvent::KeyboardInput {
+				keycode: Some(keycode),
+				state: ElementState::Pressed,
+				..
+			} if self.open => {
+				self.press(world, *keycode);
+				true
+			}
+			AppEvent::TextInput {
+				text,
+				composition: TextComposition::Committed,
+				..
+			} if self.open => {
+				// The toggle key's own character shouldn't be typed as it closes the console
+				let text = text
+					.chars()
+					.filter(|character| !character.is_control() && !matches!(character, '`' | '~'))
+					.collect::<String>();
+				self.input.insert_str(self.cursor, &text);
+				self.cursor += text.len();
+				true
+			}
+			AppEvent::KeyboardInput { .. } | AppEvent::TextInput { .. } => self.open,
+			_ => false,
+		}
+	}
+
+	fn press(&mut self, world: &mut World, keycode: KeyCode) {
+		match keycode {
+			KeyCode::Enter | KeyCode::NumpadEnter => {
+				let line = std::mem::take(&mut self.input);
+				self.cursor = 0;
+				self.submit(world, &line);
+			}
+			KeyCode::Backspace => {
+				if let Some(previous) = self.previous_boundary() {
+					self.input.replace_range(previous..self.cursor, "");
+					self.cursor = previous;
+				}
+			}
+			KeyCode::Delete => {
+				if let Some(next) = self.next_boundary() {
+					self.input.replace_range(self.cursor..next, "");
+				}
+			}
+			KeyCode::ArrowLeft => self.cursor = self.previous_boundary().unwrap_or(self.cursor),
+			KeyCode::ArrowRight => self.cursor = self.next_boundary().unwrap_or(self.cursor),
+			KeyCode::Home => self.cursor = 0,
+			KeyCode::End => self.cursor = self.input.len(),
+			KeyCode::ArrowUp => self.browse_history(true),
+			KeyCode::ArrowDown => self.browse_history(false),
+			KeyCode::Tab => self.complete(world),
+			KeyCode::Escape => self.open = false,
+			_ => {}
+		}
+	}
+
+	fn previous_boundary(&self) -> Option<usize> {
+		self.input[..self.cursor]
+			.char_indices()
+			.next_back()
+			.map(|(index, _)| index)
+	}
+
+	fn next_boundary(&self) -> Option<usize> {
+		self.input[self.cursor..]
+			.chars()
+			.next()
+			.map(|character| self.cursor + character.len_utf8())
+	}
+
+	fn browse_history(&mut self, back: bool) {
+		let index = match (self.history_index, back) {
+			(None, true) => self.history.len().checked_sub(1),
+			(Some(index), true) => Some(index.saturating_sub(1)),
+			(Some(index), false) => Some(index + 1).filter(|index| *index < self.history.len()),
+			(None, false) => None,
+		};
+		self.history_index = index;
+		let line = index.map_or_else(String::new, |index| self.history[index].clone());
+		self.set_input(line);
+	}
+
+	// Completes the command or variable being typed, or prints the choices when there are
+	// several
+	fn complete(&mut self, world: &World) {
+		if self.input.contains(char::is_whitespace) {
+			return;
+		}
+		let completions = self.completions(world, &self.input);
+		match completions.as_slice() {
+			[] => {}
+			[completion] => self.set_input(format!("{completion} ")),
+			[first, rest @ ..] => {
+				let mut common = first.clone();
+				for completion in rest {
+					let shared = common
+						.char_indices()
+						.zip(completion.chars())
+						.find(|((_, first), other)| first != other)
+						.map_or(common.len().min(completion.len()), |((index, _), _)| index);
+					common.truncate(shared);
+				}
+				self.set_input(common);
+				self.print(completions.join("  "));
+			}
+		}
+	}
+
+	/// The commands and variables starting with the prefix, in order
+	pub fn completions(&self, world: &World, prefix: &str) -> Vec<String> {
+		let resources = world.resources().borrow();
+		let variables = resources
+			.get::<CVars>()
+			.into_iter()
+			.flat_map(CVars::iter)
+			.map(|(name, _)| name);
+		let mut completions = BUILTINS
+			.into_iter()
+			.chain(self.commands.keys().map(String::as_str))
+			.chain(variables)
+			.filter(|name| name.starts_with(prefix))
+			.map(str::to_string)
+			.collect::<Vec<_>>();
+		completions.sort();
+		completions.dedup();
+		completions
+	}
+
+	/// Runs the line and prints it with what it returned, adding it to the history
+	pub fn submit(&mut self, world: &mut World, line: &str) {
+		let line = line.trim();
+		self.history_index = None;
+		if line.is_empty() {
+			return;
+		}
+		if self.history.last().map(String::as_str) != Some(line) {
+			self.history.push(line.to_string());
+			if self.history.len() > MAX_HISTORY {
+				self.history.remove(0);
+			}
+		}
+		self.print(format!("> {line}"));
+		match self.execute(world, line) {
+			Ok(output) => self.print(output),
+			Err(error) => self.print(error.to_string()),
+		}
+	}
+
+	/// Runs a command, or shows or sets a variable when given its name and a value
+	pub fn execute(&mut self, world: &mut World, line: &str) -> Result<String, ConsoleError> {
+		let args = Args::parse(line)?;
+		match args.name() {
+			"" => Ok(String::new()),
+			"help" => Ok(self.help(world, &args)),
+			"clear" => {
+				self.output.clear();
+				Ok(String::new())
+			}
+			"reset" => {
+				let name = args.text(0)?;
+				let mut resources = world.resources().borrow_mut();
+				let cvars = resources
+					.get_mut::<CVars>()
+					.ok_or_else(|| ConsoleError::UnknownVariable(name.to_string()))?;
+				cvars.reset(name)?;
+				Ok(show(cvars, name))
+			}
+			name => match self.commands.get(name) {
+				Some(command) => {
+					let handler = command.handler.clone();
+					handler(world, &args)
+				}
+				None => set_variable(world, &args),
+			},
+		}
+	}
+
+	fn help(&self, world: &World, args: &Args) -> String {
+		let resources = world.resources().borrow();
+		let cvars = resources.get::<CVars>();
+		if let Ok(name) = args.text(0) {
+			if let Some(command) = self.commands.get(name) {
+				return format!("{name}: {}", command.description);
+			}
+			return match cvars.and_then(|cvars| cvars.variable(name)) {
+				Some(variable) => format!(
+					"{name} = {} (default {}): {}",
+					display(&variable.value),
+					display(&variable.default),
+					variable.description
+				),
+				None => ConsoleError::Unknown(name.to_string()).to_string(),
+			};
+		}
+		let mut lines = vec!["Commands: clear, help [name], reset <variable>".to_string()];
+		lines.extend(self.commands.iter().map(|(name, command)| {
+			format!("  {name}  {}", command.description)
+				.trim_end()
+				.to_string()
+		}));
+		if let Some(cvars) = cvars {
+			lines.push("Variables:".to_string());
+			lines.extend(
+				cvars
+					.iter()
+					.map(|(name, variable)| format!("  {name} = {}", display(&variable.value))),
+			);
+		}
+		lines.join("\n")
+	}
+}
+
+fn set_variable(world: &mut World, args: &Args) -> Result<String, ConsoleError> {
+	let name = args.name();
+	let mut resources = world.resources().borrow_mut();
+	let cvars = resources
+		.get_mut::<CVars>()
+		.filter(|cvars| cvars.variable(name).is_some())
+		.ok_or_else(|| ConsoleError::Unknown(name.to_string()))?;
+	match args.len() {
+		0 => {}
+		1 => cvars.set_text(name, args.text(0)?)?,
+		_ => cvars.set_text(name, args.rest())?,
+	}
+	Ok(show(cvars, name))
+}
+
+fn show(cvars: &CVars, name: &str) -> String {
+	cvars.variable(name).map_or_else(String::new, |variable| {
+		format!("{name} = {}", display(&variable.value))
+	})
+}
+
+/// Passes the event to the world's `Console` resource, if it has one, returning whether
+/// the console used it. The console is taken out of the world while its commands run.
+pub fn handle_console_event(world: &mut World, event: &AppEvent) -> bool {
+	let Some(mut console) = world
+		.resources()
+		.borrow_mut()
+		.get_mut::<Console>()
+		.map(std::mem::take)
+	else {
+		return false;
+	};
+	let handled = console.handle_event(world, event);
+	world.resources().borrow_mut().insert(console);
+	handled
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use app::WindowId;
+
+	fn key(keycode: KeyCode) -> AppEvent {
+		AppEvent::KeyboardInput {
+			window: WindowId::PRIMARY,
+			keycode: Some(keycode),
+			state: ElementState::Pressed,
+		}
+	}
+
+	fn text(text: &str) -> AppEvent {
+		AppEvent::TextInput {
+			window: WindowId::PRIMARY,
+			text: text.to_string(),
+			composition: TextComposition::Committed,
+		}
+	}
+
+	struct Spawned(u32);
+
+	fn world() -> World {
+		let world = World::new();
+		let mut cvars = CVars::new();
+		cvars.register("god_mode", false, "Takes no damage");
+		cvars.register("gravity", 9.8, "");
+		let mut console = Console::new();
+		console
+			.register_command("spawn", |world, args| {
+				let count = args.get_or::<u32>(1, 1)?;
+				let name = args.text(0)?;
+				let mut resources = world.resources().borrow_mut();
+				resources.insert(Spawned(count));
+				Ok(format!("Spawned {count} {name}"))
+			})
+			.describe("spawn <name> [count]");
+		let mut resources = world.resources().borrow_mut();
+		resources.insert(cvars);
+		resources.insert(console);
+		drop(resources);
+		world
+	}
+
+	fn type_line(world: &mut World, line: &str) {
+		handle_console_event(world, &text(line));
+		handle_console_event(world, &key(KeyCode::Enter));
+	}
+
+	#[test]
+	fn typed_lines_run_commands_and_set_variables() {
+		let mut world = world();
+		assert!(!handle_console_event(&mut world, &key(KeyCode::KeyW)));
+		assert!(handle_console_event(&mut world, &key(KeyCode::Backquote)));
+		assert!(handle_console_event(&mut world, &text("`")));
+
+		type_line(&mut world, "spawn goblin 3");
+		type_line(&mut world, "god_mode on");
+		type_line(&mut world, "gravity");
+		type_line(&mut world, "spawn goblin many");
+		type_line(&mut world, "fly");
+		let resources = world.resources().borrow();
+		assert_eq!(resources.get::<Spawned>().map(|spawned| spawned.0), Some(3));
+		assert_eq!(
+			resources
+				.get::<CVars>()
+				.and_then(|cvars| cvars.get("god_mode")),
+			Some(true)
+		);
+		let console = resources.get::<Console>().unwrap();
+		assert_eq!(
+			console.output().collect::<Vec<_>>(),
+			[
+				"> spawn goblin 3",
+				"Spawned 3 goblin",
+				"> god_mode on",
+				"god_mode = true",
+				"> gravity",
+				"gravity = 9.8",
+				"> spawn goblin many",
+				"Argument 2 should be u32, not 'many'",
+				"> fly",
+				"Unknown command or variable: fly",
+			]
+		);
+		assert_eq!(console.history().len(), 5);
+		assert_eq!(console.input(), "");
+	}
+
+	#[test]
+	fn history_editing_and_completion() {
+		let mut world = world();
+		let mut console = Console::new();
+		console.register_command("spawn", |_, _| Ok(String::new()));
+		console.register_command("speed", |_, _| Ok(String::new()));
+		console.set_open(true);
+		console.submit(&mut world, "spawn a");
+		console.submit(&mut world, "speed 2");
+
+		console.handle_event(&mut world, &key(KeyCode::ArrowUp));
+		assert_eq!(console.input(), "speed 2");
+		console.handle_event(&mut world, &key(KeyCode::ArrowUp));
+		console.handle_event(&mut world, &key(KeyCode::ArrowUp));
+		assert_eq!(console.input(), "spawn a");
+		console.handle_event(&mut world, &key(KeyCode::ArrowDown));
+		console.handle_event(&mut world, &key(KeyCode::ArrowDown));
+		assert_eq!(console.input(), "");
+
+		// Editing works on characters rather than bytes
+		console.handle_event(&mut world, &text("sé"));
+		console.handle_event(&mut world, &key(KeyCode::ArrowLeft));
+		console.handle_event(&mut world, &key(KeyCode::Backspace));
+		console.handle_event(&mut world, &key(KeyCode::Delete));
+		assert_eq!((console.input(), console.cursor()), ("", 0));
+
+		console.handle_event(&mut world, &text("sp"));
+		console.handle_event(&mut world, &key(KeyCode::Tab));
+		assert_eq!(console.input(), "sp");
+		assert_eq!(console.output().last(), Some("spawn  speed"));
+		console.handle_event(&mut world, &text("a"));
+		console.handle_event(&mut world, &key(KeyCode::Tab));
+		assert_eq!(console.input(), "spawn ");
+		assert_eq!(console.completions(&world, "g"), ["god_mode", "gravity"]);
+
+		console.handle_event(&mut world, &key(KeyCode::Escape));
+		assert!(!console.is_open());
+		assert!(!console.handle_event(&mut world, &text("x")));
+	}
+}
@@ -0,0 +1,188 @@
+use crate::command::ConsoleError;
+use ecs::{
+	reflect::{FieldValue, Value},
+	world::World,
+};
+use std::collections::BTreeMap;
+
+/// A variable that can be read by the game and changed from the console
+#[derive(Debug, Clone, PartialEq)]
+pub struct CVar {
+	pub value: Value,
+	pub default: Value,
+	pub description: String,
+}
+
+/// The console's variables, kept as a world resource. Typing a variable's name in the
+/// console shows its value, and typing its name followed by a value sets it.
+#[derive(Debug, Default, Clone)]
+pub struct CVars {
+	variables: BTreeMap<String, CVar>,
+}
+
+impl CVars {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Adds a variable, or replaces the default and description of one already added
+	pub fn register(
+		&mut self,
+		name: impl Into<String>,
+		default: impl FieldValue,
+		description: impl Into<String>,
+	) {
+		let default = default.to_value();
+		let description = description.into();
+		let variable = self.variables.entry(name.into()).or_insert_with(|| CVar {
+			value: default.clone(),
+			default: default.clone(),
+			description: String::new(),
+		});
+		variable.default = default;
+		variable.description = description;
+	}
+
+	pub fn get<T: FieldValue>(&self, name: &str) -> Option<T> {
+		T::from_value(self.variables.get(name)?.value.clone())
+	}
+
+	pub fn variable(&self, name: &str) -> Option<&CVar> {
+		self.variables.get(name)
+	}
+
+	/// Sets a variable to a value of the type it was registered with
+	pub fn set(&mut self, name: &str, value: impl FieldValue) -> Result<(), ConsoleError> {
+		self.set_value(name, value.to_value())
+	}
+
+	pub fn set_value(&mut self, name: &str, value: Value) -> Result<(), ConsoleError> {
+		let variable = self
+			.variables
+			.get_mut(name)
+			.ok_or_else(|| ConsoleError::UnknownVariable(name.to_string()))?;
+		let value = match (&variable.default, value) {
+			(Value::Float(_), Value::Integer(integer)) => Value::Float(integer as f64),
+			(Value::Float(_), Value::Unsigned(integer)) => Value::Float(integer as f64),
+			(
+				Value::Integer(_) | Value::Unsigned(_),
+				value @ (Value::Integer(_) | Value::Unsigned(_)),
+			) => value,
+			(default, value)
+				if std::mem::discriminant(default) == std::mem::discriminant(&value) =>
+			{
+				value
+			}
+			(_, value) => {
+				return Err(ConsoleError::InvalidValue {
+					name: name.to_string(),
+					value: display(&value),
+				})
+			}
+		};
+		variable.value = value;
+		Ok(())
+	}
+
+	/// Sets a variable from text typed in the console, read as the variable's type
+	pub fn set_text(&mut self, name: &str, text: &str) -> Result<(), ConsoleError> {
+		let variable = self
+			.variables
+			.get(name)
+			.ok_or_else(|| ConsoleError::UnknownVariable(name.to_string()))?;
+		let invalid = || ConsoleError::InvalidValue {
+			name: name.to_string(),
+			value: text.to_string(),
+		};
+		let value = match variable.default {
+			Value::Bool(_) => match text.to_lowercase().as_str() {
+				"true" | "on" | "1" => Value::Bool(true),
+				"false" | "off" | "0" => Value::Bool(false),
+				_ => return Err(invalid()),
+			},
+			Value::Integer(_) | Value::Unsigned(_) => match text.parse() {
+				Ok(integer) => Value::Integer(integer),
+				Err(_) => Value::Unsigned(text.parse().map_err(|_| invalid())?),
+			},
+			Value::Float(_) => Value::Float(text.parse().map_err(|_| invalid())?),
+			Value::Text(_) => Value::Text(text.to_string()),
+		};
+		self.set_value(name, value)
+	}
+
+	pub fn reset(&mut self, name: &str) -> Result<(), ConsoleError> {
+		let variable = self
+			.variables
+			.get_mut(name)
+			.ok_or_else(|| ConsoleError::UnknownVariable(name.to_string()))?;
+		variable.value = variable.default.clone();
+		Ok(())
+	}
+
+	/// Every variable, ordered by name
+	pub fn iter(&self) -> impl Iterator<Item = (&str, &CVar)> {
+		self.variables
+			.iter()
+			.map(|(name, variable)| (name.as_str(), variable))
+	}
+}
+
+/// A variable from the world's `CVars`
+pub fn cvar<T: FieldValue>(world: &World, name: &str) -> Option<T> {
+	world.resources().borrow().get::<CVars>()?.get(name)
+}
+
+pub(crate) fn display(value: &Value) -> String {
+	match value {
+		Value::Bool(value) => value.to_string(),
+		Value::Integer(value) => value.to_string(),
+		Value::Unsigned(value) => value.to_string(),
+		Value::Float(value) => value.to_string(),
+		Value::Text(value) => format!("\"{value}\""),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn variables_keep_their_types() -> Result<(), ConsoleError> {
+		let mut cvars = CVars::new();
+		cvars.register("god_mode", false, "Takes no damage");
+		cvars.register("gravity", 9.8, "Downward acceleration");
+		cvars.register("max_enemies", 12, "");
+		cvars.register("seed", u64::MAX, "");
+
+		cvars.set_text("god_mode", "on")?;
+		cvars.set_text("gravity", "4.5")?;
+		cvars.set("max_enemies", 20)?;
+		assert_eq!(cvars.get::<bool>("god_mode"), Some(true));
+		assert_eq!(cvars.get::<f32>("gravity"), Some(4.5));
+		assert_eq!(cvars.get::<u32>("max_enemies"), Some(20));
+
+		// Integers are taken for floats, but nothing else changes a variable's type
+		cvars.set("gravity", 3)?;
+		assert_eq!(cvars.get::<f64>("gravity"), Some(3.0));
+		assert!(matches!(
+			cvars.set_text("max_enemies", "lots"),
+			Err(ConsoleError::InvalidValue { .. })
+		));
+		assert!(cvars.set("god_mode", 1).is_err());
+		assert!(matches!(
+			cvars.set("fov", 90),
+			Err(ConsoleError::UnknownVariable(_))
+		));
+
+		cvars.set_text("seed", "18446744073709551614")?;
+		assert_eq!(cvars.get::<u64>("seed"), Some(u64::MAX - 1));
+		cvars.set("seed", 7_u64)?;
+		assert_eq!(cvars.get::<u64>("seed"), Some(7));
+
+		cvars.reset("gravity")?;
+		assert_eq!(cvars.get::<f64>("gravity"), Some(9.8));
+		let names = cvars.iter().map(|(name, _)| name).collect::<Vec<_>>();
+		assert_eq!(names, ["god_mode", "gravity", "max_enemies", "seed"]);
+		Ok(())
+	}
+}
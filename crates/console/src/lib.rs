@@ -0,0 +1,13 @@
+#![forbid(unsafe_code)]
+
+mod command;
+mod console;
+mod cvar;
+mod view;
+
+pub use self::{
+	command::{Args, Command, ConsoleError, Handler},
+	console::{handle_console_event, Console},
+	cvar::{cvar, CVar, CVars},
+	view::ConsoleView,
+};
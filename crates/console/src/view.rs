@@ -0,0 +1,99 @@
+use crate::console::Console;
+use math::Color;
+use ui::{Dimension, Edges, Style, Ui, UiError, Widget, WidgetId};
+
+const BACKGROUND: Color = Color::rgba(0.05, 0.05, 0.08, 0.9);
+const INPUT_COLOR: Color = Color::rgb(1.0, 0.85, 0.4);
+const MAX_LINES: usize = 20;
+
+/// The console drawn as a panel across the top of a `Ui`, showing its latest output over
+/// the line being typed. Spawn it before the rest of the ui so it drops down from the top.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConsoleView {
+	pub panel: WidgetId,
+	pub output: WidgetId,
+	pub input: WidgetId,
+}
+
+impl ConsoleView {
+	pub fn new(ui: &mut Ui, font: &str) -> Result<Self, UiError> {
+		let panel = ui.spawn(
+			ui.root(),
+			Widget::Panel,
+			Style::default()
+				.with_size(Dimension::Percent(100.0), Dimension::Auto)
+				.with_padding(Edges::symmetric(8.0, 6.0))
+				.with_gap(4.0)
+				.with_background(BACKGROUND),
+		)?;
+		let output = ui.spawn(panel, Widget::label("", font), Style::default())?;
+		let mut input = Widget::label("", font);
+		if let Some(text) = input.text_mut() {
+			text.color = INPUT_COLOR;
+		}
+		let input = ui.spawn(panel, input, Style::default())?;
+		if let Some(style) = ui.style_mut(panel) {
+			style.visible = false;
+		}
+		Ok(Self {
+			panel,
+			output,
+			input,
+		})
+	}
+
+	/// Shows the panel while the console is open, with its output and input
+	pub fn update(&self, ui: &mut Ui, console: &Console) {
+		if let Some(style) = ui.style_mut(self.panel) {
+			style.visible = console.is_open();
+		}
+		if !console.is_open() {
+			return;
+		}
+		let lines = console.output().collect::<Vec<_>>();
+		let output = lines[lines.len().saturating_sub(MAX_LINES)..].join("\n");
+		set_text(ui, self.output, output);
+		let (before, after) = console.input().split_at(console.cursor());
+		set_text(ui, self.input, format!("> {before}_{after}"));
+	}
+}
+
+fn set_text(ui: &mut Ui, widget: WidgetId, value: String) {
+	if let Some(text) = ui.widget_mut(widget).and_then(Widget::text_mut) {
+		if text.value != value {
+			text.value = value;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ecs::world::World;
+
+	#[test]
+	fn the_panel_shows_the_open_console() -> Result<(), UiError> {
+		let mut world = World::new();
+		let mut ui = Ui::new();
+		let view = ConsoleView::new(&mut ui, "mono")?;
+		let mut console = Console::new();
+		view.update(&mut ui, &console);
+		assert_eq!(ui.style(view.panel).map(|style| style.visible), Some(false));
+
+		console.set_open(true);
+		console.submit(&mut world, "help");
+		console.set_input("spwn");
+		view.update(&mut ui, &console);
+		assert_eq!(ui.style(view.panel).map(|style| style.visible), Some(true));
+		let text = |widget| {
+			ui.widget(widget)
+				.and_then(Widget::text)
+				.unwrap()
+				.value
+				.clone()
+		};
+		assert!(text(view.output).starts_with("> help\nCommands:"));
+		assert_eq!(text(view.input), "> spwn_");
+		Ok(())
+	}
+}
@@ -0,0 +1,100 @@
+use crate::store::{Frame, Sink};
+use std::io::Write;
+
+/// Writes frames as Chrome trace events, which Perfetto and `chrome://tracing` can open.
+/// The closing bracket is left off as the format allows, so the trace can be opened even
+/// when the game doesn't exit cleanly. Write errors are ignored, since profiling shouldn't
+/// stop the game.
+pub struct ChromeTrace<W> {
+	writer: W,
+	started: bool,
+}
+
+impl<W: Write + Send> ChromeTrace<W> {
+	pub fn new(writer: W) -> Self {
+		Self {
+			writer,
+			started: false,
+		}
+	}
+
+	fn write_event(&mut self, event: &str) {
+		let prefix = if self.started { ",\n" } else { "[\n" };
+		self.started = true;
+		let _ = write!(self.writer, "{prefix}{event}");
+	}
+}
+
+impl<W: Write + Send> Sink for ChromeTrace<W> {
+	fn frame(&mut self, frame: &Frame) {
+		let start = frame.start.as_micros();
+		self.write_event(&format!(
+			r#"{{"name":"frame {}","ph":"i","s":"g","ts":{start},"pid":0,"tid":0}}"#,
+			frame.index
+		));
+		for span in &frame.spans {
+			self.write_event(&format!(
+				r#"{{"name":"{}","ph":"X","ts":{},"dur":{},"pid":0,"tid":{}}}"#,
+				escape(span.name),
+				start + span.start.as_micros(),
+				span.duration.as_micros(),
+				span.thread
+			));
+		}
+		for (name, amount) in &frame.counters {
+			let name = escape(name);
+			self.write_event(&format!(
+				r#"{{"name":"{name}","ph":"C","ts":{start},"pid":0,"args":{{"{name}":{amount}}}}}"#
+			));
+		}
+		let _ = self.writer.flush();
+	}
+}
+
+fn escape(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len());
+	for character in text.chars() {
+		match character {
+			'"' => escaped.push_str("\\\""),
+			'\\' => escaped.push_str("\\\\"),
+			character if character.is_control() => {
+				escaped.push_str(&format!("\\u{:04x}", character as u32));
+			}
+			character => escaped.push(character),
+		}
+	}
+	escaped
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::store::Span;
+	use std::time::Duration;
+
+	#[test]
+	fn frames_are_written_as_trace_events() {
+		let mut trace = ChromeTrace::new(Vec::new());
+		let frame = Frame {
+			index: 4,
+			start: Duration::from_millis(2),
+			duration: Duration::from_millis(16),
+			spans: vec![Span {
+				name: "load \"level\"",
+				thread: 1,
+				depth: 0,
+				start: Duration::from_micros(5),
+				duration: Duration::from_micros(40),
+			}],
+			counters: vec![("draws", 12)],
+		};
+		trace.frame(&frame);
+		trace.frame(&Frame::default());
+		let written = String::from_utf8(trace.writer).unwrap();
+		assert!(written.starts_with("[\n{\"name\":\"frame 4\""));
+		assert!(written
+			.contains(r#"{"name":"load \"level\"","ph":"X","ts":2005,"dur":40,"pid":0,"tid":1}"#));
+		assert!(written.contains(r#""args":{"draws":12}"#));
+		assert_eq!(written.matches("\"frame ").count(), 2);
+	}
+}
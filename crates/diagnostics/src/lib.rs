@@ -0,0 +1,20 @@
+#![forbid(unsafe_code)]
+
+mod chrome;
+#[cfg(feature = "puffin")]
+mod puffin;
+mod store;
+#[cfg(all(feature = "tracy", not(target_arch = "wasm32")))]
+mod tracy;
+
+#[cfg(feature = "puffin")]
+pub use self::puffin::PuffinSink;
+#[cfg(all(feature = "tracy", not(target_arch = "wasm32")))]
+pub use self::tracy::TracySink;
+pub use self::{
+	chrome::ChromeTrace,
+	store::{
+		add_sink, clear, count, enable, frame_mark, frames, is_enabled, latest_frame, set_capacity,
+		span, Edge, Frame, Sink, Span, SpanGuard,
+	},
+};
@@ -0,0 +1,119 @@
+use crate::store::{Edge, Frame, Sink};
+use puffin::{GlobalProfiler, ScopeDetails, ScopeId, StreamInfo, ThreadInfo};
+use std::{
+	collections::{BTreeMap, HashMap},
+	time::Duration,
+};
+
+/// Reports frames to puffin's global profiler, such as for `puffin_http` to serve to
+/// `puffin_viewer`. Spans become scopes on a puffin thread per diagnostics thread, and a puffin
+/// frame is started after each one. Counters are left out, since puffin has nothing to show
+/// them in.
+#[derive(Default)]
+pub struct PuffinSink {
+	scopes: HashMap<&'static str, ScopeId>,
+}
+
+impl PuffinSink {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	fn scope_id(&mut self, profiler: &mut GlobalProfiler, name: &'static str) -> ScopeId {
+		*self.scopes.entry(name).or_insert_with(|| {
+			profiler.register_user_scopes(&[ScopeDetails::from_scope_name(name)])[0]
+		})
+	}
+}
+
+impl Sink for PuffinSink {
+	fn frame(&mut self, frame: &Frame) {
+		// Sinks are called as the frame ends, which lines its start up with puffin's clock
+		let frame_start = puffin::now_ns() - nanos(frame.duration);
+		let mut profiler = GlobalProfiler::lock();
+		let mut streams: BTreeMap<u64, StreamInfo> = BTreeMap::new();
+		let mut open = Vec::new();
+		for edge in frame.edges() {
+			match edge {
+				Edge::Begin(span) => {
+					let scope_id = self.scope_id(&mut profiler, span.name);
+					let start = frame_start + nanos(span.start);
+					let info = streams.entry(span.thread).or_default();
+					let (offset, _) = info.stream.begin_scope(|| start, scope_id, "");
+					open.push(offset);
+					info.num_scopes += 1;
+					info.depth = info.depth.max(span.depth + 1);
+					info.range_ns.0 = info.range_ns.0.min(start);
+				}
+				Edge::End(span) => {
+					let end = frame_start + nanos(span.start + span.duration);
+					let info = streams.entry(span.thread).or_default();
+					if let Some(offset) = open.pop() {
+						info.stream.end_scope(offset, end);
+					}
+					info.range_ns.1 = info.range_ns.1.max(end);
+				}
+			}
+		}
+		for (thread, info) in &streams {
+			let thread = ThreadInfo {
+				start_time_ns: None,
+				name: format!("thread {thread}"),
+			};
+			profiler.report_user_scopes(thread, &info.as_stream_into_ref());
+		}
+		profiler.new_frame();
+	}
+}
+
+fn nanos(duration: Duration) -> i64 {
+	i64::try_from(duration.as_nanos()).unwrap_or(i64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::store::Span;
+	use puffin::{FrameData, Reader};
+	use std::sync::{Arc, Mutex};
+
+	#[test]
+	fn spans_are_reported_as_scopes() {
+		let reported: Arc<Mutex<Vec<Arc<FrameData>>>> = Arc::default();
+		let frames = reported.clone();
+		GlobalProfiler::lock().add_sink(Box::new(move |frame| frames.lock().unwrap().push(frame)));
+
+		let span = |name, depth, start, duration| Span {
+			name,
+			thread: 2,
+			depth,
+			start: Duration::from_micros(start),
+			duration: Duration::from_micros(duration),
+		};
+		PuffinSink::new().frame(&Frame {
+			duration: Duration::from_millis(16),
+			spans: vec![span("update", 0, 0, 100), span("physics", 1, 10, 40)],
+			..Default::default()
+		});
+
+		let reported = reported.lock().unwrap();
+		let Ok(frame) = reported[0].unpacked();
+		let (thread, info) = frame.thread_streams.iter().next().unwrap();
+		assert_eq!(thread.name, "thread 2");
+		assert_eq!((info.num_scopes, info.depth), (2, 2));
+
+		let update = Reader::from_start(&info.stream).read_top_scopes().unwrap();
+		assert_eq!(update.len(), 1);
+		assert_eq!(update[0].record.duration_ns, 100_000);
+		let physics = Reader::with_offset(&info.stream, update[0].child_begin_position)
+			.unwrap()
+			.read_top_scopes()
+			.unwrap();
+		assert_eq!(physics.len(), 1);
+		assert_eq!(
+			physics[0].record.start_ns - update[0].record.start_ns,
+			10_000
+		);
+		assert_eq!(physics[0].record.duration_ns, 40_000);
+	}
+}
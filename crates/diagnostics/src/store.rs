@@ -0,0 +1,378 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+use std::{
+	collections::VecDeque,
+	sync::{
+		atomic::{AtomicBool, AtomicU64, Ordering},
+		Mutex, MutexGuard,
+	},
+	time::Duration,
+};
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+const DEFAULT_CAPACITY: usize = 300;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STORE: Mutex<Store> = Mutex::new(Store::new());
+static NEXT_THREAD: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+	static THREAD: u64 = NEXT_THREAD.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Time spent on something during a frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+	pub name: &'static str,
+	/// The thread it started on, numbered in the order threads first recorded a span
+	pub thread: u64,
+	/// How many spans on the same thread it's inside of
+	pub depth: usize,
+	/// From the start of the frame
+	pub start: Duration,
+	pub duration: Duration,
+}
+
+/// Everything recorded between two `frame_mark`s
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Frame {
+	pub index: u64,
+	/// From when diagnostics were first recorded
+	pub start: Duration,
+	pub duration: Duration,
+	/// Ordered by thread, then by when they started
+	pub spans: Vec<Span>,
+	/// The amounts counted during the frame, in the order they were first counted
+	pub counters: Vec<(&'static str, i64)>,
+}
+
+impl Frame {
+	/// The time spent in spans with the name
+	pub fn total(&self, name: &str) -> Duration {
+		self.spans
+			.iter()
+			.filter(|span| span.name == name)
+			.map(|span| span.duration)
+			.sum()
+	}
+
+	pub fn counter(&self, name: &str) -> i64 {
+		self.counters
+			.iter()
+			.find(|(counter, _)| *counter == name)
+			.map_or(0, |(_, amount)| *amount)
+	}
+
+	/// The spans as their threads entered and left them, one thread at a time, for profilers
+	/// that expect scopes to be opened and closed in order
+	pub fn edges(&self) -> Vec<Edge<'_>> {
+		let mut edges = Vec::with_capacity(self.spans.len() * 2);
+		let mut open: Vec<&Span> = Vec::new();
+		for span in &self.spans {
+			while let Some(&last) = open.last() {
+				if last.thread == span.thread && open.len() <= span.depth {
+					break;
+				}
+				edges.push(Edge::End(last));
+				open.pop();
+			}
+			edges.push(Edge::Begin(span));
+			open.push(span);
+		}
+		edges.extend(open.into_iter().rev().map(Edge::End));
+		edges
+	}
+}
+
+/// A span being entered or left
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Edge<'a> {
+	Begin(&'a Span),
+	End(&'a Span),
+}
+
+/// Receives each frame as it ends, such as to write it to a file or forward it to an
+/// external profiler like Tracy or puffin
+pub trait Sink: Send {
+	fn frame(&mut self, frame: &Frame);
+}
+
+struct Store {
+	// When diagnostics were first recorded, which times are measured from
+	origin: Option<Instant>,
+	frame_start: Option<Instant>,
+	index: u64,
+	// Spans of the current frame, started from the origin
+	spans: Vec<Span>,
+	counters: Vec<(&'static str, i64)>,
+	frames: VecDeque<Frame>,
+	capacity: usize,
+	sinks: Vec<Box<dyn Sink>>,
+}
+
+impl Store {
+	const fn new() -> Self {
+		Self {
+			origin: None,
+			frame_start: None,
+			index: 0,
+			spans: Vec::new(),
+			counters: Vec::new(),
+			frames: VecDeque::new(),
+			capacity: DEFAULT_CAPACITY,
+			sinks: Vec::new(),
+		}
+	}
+
+	fn since_origin(&mut self, instant: Instant) -> Duration {
+		instant.saturating_duration_since(*self.origin.get_or_insert(instant))
+	}
+}
+
+fn lock() -> MutexGuard<'static, Store> {
+	STORE
+		.lock()
+		.unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Starts or stops recording. Diagnostics are off until enabled, and cost an atomic load
+/// per span and counter while off.
+pub fn enable(enabled: bool) {
+	ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+	ENABLED.load(Ordering::Relaxed)
+}
+
+/// Ends when dropped, recording the time since `span` was called
+#[must_use = "the span ends when this is dropped"]
+#[derive(Debug)]
+pub struct SpanGuard {
+	name: &'static str,
+	thread: u64,
+	start: Option<Instant>,
+}
+
+impl Drop for SpanGuard {
+	fn drop(&mut self) {
+		let Some(start) = self.start else {
+			return;
+		};
+		let duration = start.elapsed();
+		let mut store = lock();
+		let start = store.since_origin(start);
+		store.spans.push(Span {
+			name: self.name,
+			thread: self.thread,
+			depth: 0,
+			start,
+			duration,
+		});
+	}
+}
+
+/// Records the time until the returned guard is dropped:
+///
+/// ```
+/// let _span = diagnostics::span("physics");
+/// ```
+pub fn span(name: &'static str) -> SpanGuard {
+	SpanGuard {
+		name,
+		thread: THREAD.with(|thread| *thread),
+		start: is_enabled().then(Instant::now),
+	}
+}
+
+/// Adds to the counter for the current frame
+pub fn count(name: &'static str, amount: i64) {
+	if !is_enabled() {
+		return;
+	}
+	let mut store = lock();
+	match store
+		.counters
+		.iter_mut()
+		.find(|(counter, _)| *counter == name)
+	{
+		Some((_, total)) => *total += amount,
+		None => store.counters.push((name, amount)),
+	}
+}
+
+/// Ends the current frame, keeping it with the latest frames and passing it to the sinks.
+/// Spans still open carry over into the next frame.
+pub fn frame_mark() {
+	if !is_enabled() {
+		return;
+	}
+	let now = Instant::now();
+	let mut store = lock();
+	let end = store.since_origin(now);
+	let start = store
+		.frame_start
+		.replace(now)
+		.map_or(Duration::ZERO, |start| store.since_origin(start));
+	let mut spans = std::mem::take(&mut store.spans);
+	for span in &mut spans {
+		span.start = span.start.saturating_sub(start);
+	}
+	nest(&mut spans);
+	let frame = Frame {
+		index: store.index,
+		start,
+		duration: end - start,
+		spans,
+		counters: std::mem::take(&mut store.counters),
+	};
+	store.index += 1;
+
+	// Sinks are called without the lock held, so they can record spans of their own
+	let mut sinks = std::mem::take(&mut store.sinks);
+	drop(store);
+	for sink in &mut sinks {
+		sink.frame(&frame);
+	}
+	let mut store = lock();
+	sinks.append(&mut store.sinks);
+	store.sinks = sinks;
+	store.frames.push_back(frame);
+	while store.frames.len() > store.capacity {
+		store.frames.pop_front();
+	}
+}
+
+// Orders spans by thread and start, setting how deeply each is nested
+fn nest(spans: &mut [Span]) {
+	spans.sort_by(|first, second| {
+		(first.thread, first.start)
+			.cmp(&(second.thread, second.start))
+			.then(second.duration.cmp(&first.duration))
+	});
+	let mut open: Vec<(u64, Duration)> = Vec::new();
+	for span in spans {
+		open.retain(|(thread, end)| *thread == span.thread && *end > span.start);
+		span.depth = open.len();
+		open.push((span.thread, span.start + span.duration));
+	}
+}
+
+/// The latest frames, oldest first
+pub fn frames() -> Vec<Frame> {
+	lock().frames.iter().cloned().collect()
+}
+
+pub fn latest_frame() -> Option<Frame> {
+	lock().frames.back().cloned()
+}
+
+/// How many of the latest frames are kept, 300 by default
+pub fn set_capacity(frames: usize) {
+	let mut store = lock();
+	store.capacity = frames;
+	while store.frames.len() > frames {
+		store.frames.pop_front();
+	}
+}
+
+pub fn add_sink(sink: impl Sink + 'static) {
+	lock().sinks.push(Box::new(sink));
+}
+
+/// Forgets the kept frames and what's been recorded for the current one
+pub fn clear() {
+	let mut store = lock();
+	store.frames.clear();
+	store.spans.clear();
+	store.counters.clear();
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::{mpsc, mpsc::Sender};
+
+	struct Forward(Sender<u64>);
+
+	impl Sink for Forward {
+		fn frame(&mut self, frame: &Frame) {
+			let _ = self.0.send(frame.index);
+		}
+	}
+
+	#[test]
+	fn frames_hold_nested_spans_and_counters() {
+		let _ignored = span("before enabling");
+		enable(true);
+		let (sender, receiver) = mpsc::channel();
+		add_sink(Forward(sender));
+		frame_mark();
+		{
+			let _update = span("update");
+			{
+				let _physics = span("physics");
+				count("contacts", 2);
+			}
+			count("contacts", 3);
+			let _ai = span("ai");
+		}
+		frame_mark();
+
+		let frame = latest_frame().unwrap();
+		let spans = frame
+			.spans
+			.iter()
+			.map(|span| (span.name, span.depth))
+			.collect::<Vec<_>>();
+		assert_eq!(spans, [("update", 0), ("physics", 1), ("ai", 1)]);
+		assert!(frame.total("update") >= frame.total("physics") + frame.total("ai"));
+		assert_eq!(frame.counter("contacts"), 5);
+		assert_eq!(receiver.try_iter().collect::<Vec<_>>(), [0, 1]);
+
+		set_capacity(1);
+		frame_mark();
+		assert_eq!(frames().len(), 1);
+		assert_eq!(frames()[0].counter("contacts"), 0);
+		enable(false);
+		frame_mark();
+		assert_eq!(latest_frame().map(|frame| frame.index), Some(2));
+	}
+
+	#[test]
+	fn edges_close_nested_spans_first() {
+		let span = |name, thread, depth, start, duration| Span {
+			name,
+			thread,
+			depth,
+			start: Duration::from_millis(start),
+			duration: Duration::from_millis(duration),
+		};
+		let frame = Frame {
+			spans: vec![
+				span("update", 0, 0, 0, 10),
+				span("physics", 0, 1, 1, 4),
+				span("ai", 0, 1, 5, 4),
+				span("render", 0, 0, 10, 5),
+				span("load", 1, 0, 2, 20),
+			],
+			..Default::default()
+		};
+		let edges = frame
+			.edges()
+			.into_iter()
+			.map(|edge| match edge {
+				Edge::Begin(span) => format!("+{}", span.name),
+				Edge::End(span) => format!("-{}", span.name),
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(
+			edges,
+			[
+				"+update", "+physics", "-physics", "+ai", "-ai", "-update", "+render", "-render",
+				"+load", "-load"
+			]
+		);
+	}
+}
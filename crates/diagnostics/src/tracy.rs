@@ -0,0 +1,85 @@
+use crate::store::{Edge, Frame, Sink};
+use std::{collections::HashMap, time::Duration};
+use tracy_client::{Client, GpuContext, GpuContextType, GpuSpan, PlotName};
+
+/// Reports frames to Tracy. Tracy only records zones as they happen on the calling thread, so
+/// spans are replayed onto a timeline per diagnostics thread, which Tracy shows next to its
+/// GPU timelines. Counters become plots, and each frame ends with a frame mark.
+pub struct TracySink {
+	client: Client,
+	timelines: HashMap<u64, Option<GpuContext>>,
+	plots: HashMap<&'static str, PlotName>,
+}
+
+impl Default for TracySink {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl TracySink {
+	/// Starts the Tracy client if it isn't running yet
+	pub fn new() -> Self {
+		Self {
+			client: Client::start(),
+			timelines: HashMap::new(),
+			plots: HashMap::new(),
+		}
+	}
+
+	// Tracy allows 255 timelines, so spans on threads past that are dropped
+	fn timeline(&mut self, thread: u64, now: i64) -> Option<&GpuContext> {
+		self.timelines
+			.entry(thread)
+			.or_insert_with(|| {
+				self.client
+					.clone()
+					.new_gpu_context(
+						Some(&format!("thread {thread}")),
+						GpuContextType::Invalid,
+						now,
+						1.0,
+					)
+					.ok()
+			})
+			.as_ref()
+	}
+}
+
+impl Sink for TracySink {
+	fn frame(&mut self, frame: &Frame) {
+		let now = nanos(frame.start + frame.duration);
+		let mut open: Vec<Option<GpuSpan>> = Vec::new();
+		for edge in frame.edges() {
+			match edge {
+				Edge::Begin(span) => {
+					let zone = self
+						.timeline(span.thread, now)
+						.and_then(|timeline| timeline.span_alloc(span.name, "", "", 0).ok());
+					if let Some(zone) = &zone {
+						zone.upload_timestamp_start(nanos(frame.start + span.start));
+					}
+					open.push(zone);
+				}
+				Edge::End(span) => {
+					if let Some(mut zone) = open.pop().flatten() {
+						zone.end_zone();
+						zone.upload_timestamp_end(nanos(frame.start + span.start + span.duration));
+					}
+				}
+			}
+		}
+		for (name, amount) in &frame.counters {
+			let plot = *self
+				.plots
+				.entry(name)
+				.or_insert_with(|| PlotName::new_leak(name.to_string()));
+			self.client.plot(plot, *amount as f64);
+		}
+		self.client.frame_mark();
+	}
+}
+
+fn nanos(duration: Duration) -> i64 {
+	i64::try_from(duration.as_nanos()).unwrap_or(i64::MAX)
+}
@@ -28,12 +28,15 @@ fn reinsertion(c: &mut Criterion) {
 	});
 }
 
+#[allow(dead_code)]
 #[derive(Default)]
 struct Position(f32, f32);
 
+#[allow(dead_code)]
 #[derive(Default)]
 struct Health(u8);
 
+#[allow(dead_code)]
 #[derive(Default)]
 struct Name(String);
 
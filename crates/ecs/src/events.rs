@@ -0,0 +1,72 @@
+use std::vec::Drain;
+
+/// A queue of events stored as a world resource, written by one system and drained by another.
+#[derive(Debug)]
+pub struct Events<T> {
+	events: Vec<T>,
+}
+
+impl<T> Default for Events<T> {
+	fn default() -> Self {
+		Self { events: Vec::new() }
+	}
+}
+
+impl<T> Events<T> {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn send(&mut self, event: T) {
+		self.events.push(event);
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = &T> {
+		self.events.iter()
+	}
+
+	pub fn drain(&mut self) -> Drain<'_, T> {
+		self.events.drain(..)
+	}
+
+	pub fn clear(&mut self) {
+		self.events.clear();
+	}
+
+	pub fn len(&self) -> usize {
+		self.events.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.events.is_empty()
+	}
+}
+
+impl<T> Extend<T> for Events<T> {
+	fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+		self.events.extend(iter);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Events;
+
+	#[derive(Debug, PartialEq)]
+	struct Collision(u32, u32);
+
+	#[test]
+	fn send_and_drain() {
+		let mut events = Events::new();
+		assert!(events.is_empty());
+
+		events.send(Collision(0, 1));
+		events.extend([Collision(1, 2), Collision(2, 3)]);
+		assert_eq!(events.len(), 3);
+		assert_eq!(events.iter().next(), Some(&Collision(0, 1)));
+
+		let drained = events.drain().collect::<Vec<_>>();
+		assert_eq!(drained.len(), 3);
+		assert!(events.is_empty());
+	}
+}
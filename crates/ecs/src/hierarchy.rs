@@ -0,0 +1,122 @@
+use crate::{
+	error::Result,
+	world::{Entity, World},
+};
+use genvec::error::HandleNotFoundError;
+use std::fmt;
+
+/// A display name for an entity
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Name(pub String);
+
+/// Makes an entity the child of another entity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Parent(pub Entity);
+
+#[derive(Debug)]
+pub struct HierarchyCycleError {
+	pub entity: Entity,
+	pub parent: Entity,
+}
+
+impl std::error::Error for HierarchyCycleError {}
+
+impl fmt::Display for HierarchyCycleError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"Entity '{:?}' can't be a child of itself or its descendant '{:?}'",
+			self.entity, self.parent
+		)
+	}
+}
+
+pub fn name(world: &World, entity: Entity) -> Option<String> {
+	world
+		.get_component::<Name>(entity)
+		.map(|name| name.0.clone())
+}
+
+/// The entity's parent, if it has one that still exists
+pub fn parent(world: &World, entity: Entity) -> Option<Entity> {
+	world
+		.get_component::<Parent>(entity)
+		.map(|parent| parent.0)
+		.filter(|parent| world.entity_exists(*parent))
+}
+
+pub fn children(world: &World, entity: Entity) -> Vec<Entity> {
+	world
+		.entities()
+		.into_iter()
+		.filter(|child| parent(world, *child) == Some(entity))
+		.collect()
+}
+
+/// Entities without a parent
+pub fn roots(world: &World) -> Vec<Entity> {
+	world
+		.entities()
+		.into_iter()
+		.filter(|entity| parent(world, *entity).is_none())
+		.collect()
+}
+
+pub fn is_descendant(world: &World, entity: Entity, ancestor: Entity) -> bool {
+	// Bounded in case a cycle was made by adding `Parent` components directly
+	std::iter::successors(parent(world, entity), |entity| parent(world, *entity))
+		.take(world.entities().len())
+		.any(|entity| entity == ancestor)
+}
+
+/// Moves the entity under a new parent, or to the root with `None`
+pub fn set_parent(world: &mut World, entity: Entity, parent: Option<Entity>) -> Result<()> {
+	let Some(parent) = parent else {
+		return world.remove_component::<Parent>(entity);
+	};
+	if !world.entity_exists(parent) {
+		return Err(Box::new(HandleNotFoundError { handle: parent }));
+	}
+	if parent == entity || is_descendant(world, parent, entity) {
+		return Err(Box::new(HierarchyCycleError { entity, parent }));
+	}
+	world.add_component(entity, Parent(parent))
+}
+
+/// Removes the entity along with all of its descendants
+pub fn remove_recursive(world: &mut World, entity: Entity) {
+	for child in children(world, entity) {
+		remove_recursive(world, child);
+	}
+	world.remove_entity(entity);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn reparenting() -> Result<()> {
+		let mut world = World::new();
+		let [root, child, grandchild] = [(); 3].map(|_| world.create_entity());
+		world.add_component(root, Name("Root".to_string()))?;
+		set_parent(&mut world, child, Some(root))?;
+		set_parent(&mut world, grandchild, Some(child))?;
+
+		assert_eq!(name(&world, root).as_deref(), Some("Root"));
+		assert_eq!(roots(&world), [root]);
+		assert_eq!(children(&world, root), [child]);
+		assert!(is_descendant(&world, grandchild, root));
+		assert!(set_parent(&mut world, root, Some(grandchild)).is_err());
+		assert!(set_parent(&mut world, root, Some(root)).is_err());
+
+		set_parent(&mut world, grandchild, None)?;
+		assert_eq!(roots(&world), [root, grandchild]);
+
+		set_parent(&mut world, grandchild, Some(child))?;
+		remove_recursive(&mut world, child);
+		assert!(!world.entity_exists(grandchild));
+		assert_eq!(world.entities(), [root]);
+		Ok(())
+	}
+}
@@ -1,7 +1,16 @@
 #![forbid(unsafe_code)]
 
+pub mod events;
+pub mod hierarchy;
+pub mod reflect;
+pub mod rng;
+pub mod time;
+pub mod transform;
+pub mod tween;
 pub mod world;
 
+pub use math;
+
 pub mod error {
 	pub type Result<T, E = Box<dyn std::error::Error>> = std::result::Result<T, E>;
 }
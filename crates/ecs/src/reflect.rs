@@ -0,0 +1,373 @@
+use crate::{
+	error::Result,
+	world::{Entity, World},
+};
+use std::{any::TypeId, fmt};
+
+/// A component field value that tools such as the editor can display and edit
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Value {
+	Bool(bool),
+	Integer(i64),
+	/// An unsigned integer too big for `Integer`, such as a `u64` above `i64::MAX`
+	Unsigned(u64),
+	Float(f64),
+	Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+	pub name: &'static str,
+	pub value: Value,
+}
+
+#[derive(Debug)]
+pub enum ReflectError {
+	UnknownField(String),
+	WrongType(String),
+	MissingComponent(&'static str),
+}
+
+impl std::error::Error for ReflectError {}
+
+impl fmt::Display for ReflectError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::UnknownField(field) => write!(f, "No field named '{field}'"),
+			Self::WrongType(field) => write!(f, "Wrong value type for field '{field}'"),
+			Self::MissingComponent(name) => write!(f, "Entity has no '{name}' component"),
+		}
+	}
+}
+
+/// Lists and sets the fields of a component by name, usually implemented with `reflect!`
+pub trait Reflect: 'static {
+	fn fields(&self) -> Vec<Field>;
+	fn set_field(&mut self, name: &str, value: Value) -> std::result::Result<(), ReflectError>;
+}
+
+/// Converts a field to and from a `Value`
+pub trait FieldValue: Sized {
+	fn to_value(&self) -> Value;
+	fn from_value(value: Value) -> Option<Self>;
+}
+
+impl FieldValue for bool {
+	fn to_value(&self) -> Value {
+		Value::Bool(*self)
+	}
+
+	fn from_value(value: Value) -> Option<Self> {
+		match value {
+			Value::Bool(value) => Some(value),
+			_ => None,
+		}
+	}
+}
+
+impl FieldValue for String {
+	fn to_value(&self) -> Value {
+		Value::Text(self.clone())
+	}
+
+	fn from_value(value: Value) -> Option<Self> {
+		match value {
+			Value::Text(value) => Some(value),
+			_ => None,
+		}
+	}
+}
+
+macro_rules! float_value {
+	($($type:ty),*) => {
+		$(
+			impl FieldValue for $type {
+				fn to_value(&self) -> Value {
+					Value::Float(f64::from(*self))
+				}
+
+				fn from_value(value: Value) -> Option<Self> {
+					match value {
+						Value::Float(value) => Some(value as $type),
+						Value::Integer(value) => Some(value as $type),
+						Value::Unsigned(value) => Some(value as $type),
+						_ => None,
+					}
+				}
+			}
+		)*
+	};
+}
+
+// Integers that fit in an `i64` are `Value::Integer`, and larger ones `Value::Unsigned`.
+// Values that don't fit the field's type are rejected rather than clamped.
+macro_rules! integer_value {
+	($($type:ty),*) => {
+		$(
+			impl FieldValue for $type {
+				fn to_value(&self) -> Value {
+					match i64::try_from(*self) {
+						Ok(value) => Value::Integer(value),
+						Err(_) => Value::Unsigned(*self as u64),
+					}
+				}
+
+				fn from_value(value: Value) -> Option<Self> {
+					match value {
+						Value::Integer(value) => <$type>::try_from(value).ok(),
+						Value::Unsigned(value) => <$type>::try_from(value).ok(),
+						_ => None,
+					}
+				}
+			}
+		)*
+	};
+}
+
+float_value!(f32, f64);
+integer_value!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+/// Implements `Reflect` for a struct with named fields that implement `FieldValue`,
+/// such as `reflect!(Position { x, y });`
+#[macro_export]
+macro_rules! reflect {
+	($type:ty { $($field:ident),* $(,)? }) => {
+		impl $crate::reflect::Reflect for $type {
+			fn fields(&self) -> Vec<$crate::reflect::Field> {
+				vec![$(
+					$crate::reflect::Field {
+						name: stringify!($field),
+						value: $crate::reflect::FieldValue::to_value(&self.$field),
+					},
+				)*]
+			}
+
+			fn set_field(
+				&mut self,
+				name: &str,
+				value: $crate::reflect::Value,
+			) -> std::result::Result<(), $crate::reflect::ReflectError> {
+				match name {
+					$(
+						stringify!($field) => {
+							self.$field = $crate::reflect::FieldValue::from_value(value)
+								.ok_or_else(|| $crate::reflect::ReflectError::WrongType(name.to_string()))?;
+							Ok(())
+						}
+					)*
+					_ => Err($crate::reflect::ReflectError::UnknownField(name.to_string())),
+				}
+			}
+		}
+	};
+}
+
+/// A registered component type, with functions to reach it in a world without knowing the type
+#[derive(Clone, Copy)]
+pub struct ComponentInfo {
+	pub name: &'static str,
+	pub type_id: TypeId,
+	default_fields: fn() -> Vec<Field>,
+	fields: fn(&World, Entity) -> Option<Vec<Field>>,
+	set_field: fn(&World, Entity, Field) -> std::result::Result<(), ReflectError>,
+	add_default: fn(&mut World, Entity) -> Result<()>,
+	remove: fn(&mut World, Entity) -> Result<()>,
+}
+
+impl ComponentInfo {
+	pub fn is_on(&self, world: &World, entity: Entity) -> bool {
+		self.fields(world, entity).is_some()
+	}
+
+	/// The fields of the component's default value, such as to list its field names
+	pub fn default_fields(&self) -> Vec<Field> {
+		(self.default_fields)()
+	}
+
+	/// The component's fields, or `None` if the entity doesn't have it
+	pub fn fields(&self, world: &World, entity: Entity) -> Option<Vec<Field>> {
+		(self.fields)(world, entity)
+	}
+
+	pub fn set_field(
+		&self,
+		world: &World,
+		entity: Entity,
+		field: Field,
+	) -> std::result::Result<(), ReflectError> {
+		(self.set_field)(world, entity, field)
+	}
+
+	pub fn add_default(&self, world: &mut World, entity: Entity) -> Result<()> {
+		(self.add_default)(world, entity)
+	}
+
+	pub fn remove(&self, world: &mut World, entity: Entity) -> Result<()> {
+		(self.remove)(world, entity)
+	}
+}
+
+/// The component types tools can inspect, usually stored as a world resource
+#[derive(Default, Clone)]
+pub struct ComponentRegistry {
+	components: Vec<ComponentInfo>,
+}
+
+impl ComponentRegistry {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Registering a type again replaces its name
+	pub fn register<T: Reflect + Default>(&mut self, name: &'static str) -> &mut Self {
+		self.components
+			.retain(|info| info.type_id != TypeId::of::<T>());
+		self.components.push(ComponentInfo {
+			name,
+			type_id: TypeId::of::<T>(),
+			default_fields: || T::default().fields(),
+			fields: |world, entity| {
+				world
+					.get_component::<T>(entity)
+					.map(|component| component.fields())
+			},
+			set_field: |world, entity, field| {
+				world
+					.get_component_mut::<T>(entity)
+					.ok_or(ReflectError::MissingComponent(std::any::type_name::<T>()))?
+					.set_field(field.name, field.value)
+			},
+			add_default: |world, entity| world.add_component(entity, T::default()),
+			remove: |world, entity| world.remove_component::<T>(entity),
+		});
+		self
+	}
+
+	pub fn get(&self, name: &str) -> Option<&ComponentInfo> {
+		self.components.iter().find(|info| info.name == name)
+	}
+
+	/// Components in registration order
+	pub fn iter(&self) -> impl Iterator<Item = &ComponentInfo> {
+		self.components.iter()
+	}
+
+	/// The registered components the entity has
+	pub fn components_of<'a>(
+		&'a self,
+		world: &'a World,
+		entity: Entity,
+	) -> impl Iterator<Item = &'a ComponentInfo> {
+		self.components
+			.iter()
+			.filter(move |info| info.is_on(world, entity))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[derive(Debug, Default, PartialEq)]
+	struct Position {
+		x: f32,
+		y: f32,
+	}
+
+	reflect!(Position { x, y });
+
+	#[derive(Debug, Default, PartialEq)]
+	struct Label {
+		text: String,
+		visible: bool,
+	}
+
+	reflect!(Label { text, visible });
+
+	#[test]
+	fn integers_are_not_clamped() {
+		assert_eq!(7_u64.to_value(), Value::Integer(7));
+		assert_eq!(u64::MAX.to_value(), Value::Unsigned(u64::MAX));
+		assert_eq!(u64::from_value(u64::MAX.to_value()), Some(u64::MAX));
+		assert_eq!(i64::from_value(Value::Unsigned(u64::MAX)), None);
+		assert_eq!(u32::from_value(Value::Integer(-1)), None);
+	}
+
+	#[test]
+	fn registered_fields_are_edited() -> Result<()> {
+		let mut registry = ComponentRegistry::new();
+		registry
+			.register::<Position>("Position")
+			.register::<Label>("Label");
+
+		let mut world = World::new();
+		let entity = world.create_entity();
+		world.add_component(entity, Position { x: 1.0, y: 2.0 })?;
+
+		let names = registry
+			.components_of(&world, entity)
+			.map(|info| info.name)
+			.collect::<Vec<_>>();
+		assert_eq!(names, ["Position"]);
+
+		let position = registry.get("Position").unwrap();
+		position.set_field(
+			&world,
+			entity,
+			Field {
+				name: "y",
+				value: Value::Float(5.0),
+			},
+		)?;
+		assert_eq!(
+			position.fields(&world, entity),
+			Some(vec![
+				Field {
+					name: "x",
+					value: Value::Float(1.0)
+				},
+				Field {
+					name: "y",
+					value: Value::Float(5.0)
+				},
+			])
+		);
+		assert!(matches!(
+			position.set_field(
+				&world,
+				entity,
+				Field {
+					name: "z",
+					value: Value::Float(0.0)
+				}
+			),
+			Err(ReflectError::UnknownField(_))
+		));
+
+		let label = registry.get("Label").unwrap();
+		assert_eq!(
+			label
+				.default_fields()
+				.iter()
+				.map(|field| field.name)
+				.collect::<Vec<_>>(),
+			["text", "visible"]
+		);
+		label.add_default(&mut world, entity)?;
+		assert!(matches!(
+			label.set_field(
+				&world,
+				entity,
+				Field {
+					name: "visible",
+					value: Value::Integer(1)
+				}
+			),
+			Err(ReflectError::WrongType(_))
+		));
+		label.remove(&mut world, entity)?;
+		assert!(!label.is_on(&world, entity));
+		Ok(())
+	}
+}
@@ -0,0 +1,253 @@
+use crate::reflect::{Field, FieldValue, Reflect, ReflectError, Value};
+use rand_chacha::{
+	rand_core::{RngCore, SeedableRng},
+	ChaCha8Rng,
+};
+use std::ops::Range;
+
+/// A seeded random number generator, so a game given the same seed plays out the same way
+/// in replays and lockstep netplay. Kept as a world resource for shared randomness, and as
+/// a component for an entity's own, usually `split` from the resource when it's spawned.
+///
+/// Its whole state can be saved and restored through `Reflect`, and serde with the `serde`
+/// feature, so it's part of the world's snapshots when registered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+	feature = "serde",
+	derive(serde::Serialize, serde::Deserialize),
+	serde(from = "RngState", into = "RngState")
+)]
+pub struct Rng(ChaCha8Rng);
+
+impl Default for Rng {
+	fn default() -> Self {
+		Self::new(0)
+	}
+}
+
+impl Rng {
+	pub fn new(seed: u64) -> Self {
+		Self(ChaCha8Rng::seed_from_u64(seed))
+	}
+
+	pub fn from_seed(seed: [u8; 32]) -> Self {
+		Self(ChaCha8Rng::from_seed(seed))
+	}
+
+	/// A new generator seeded from this one's numbers, which draws its own without changing
+	/// this one's after
+	pub fn split(&mut self) -> Self {
+		let mut seed = [0; 32];
+		self.0.fill_bytes(&mut seed);
+		Self::from_seed(seed)
+	}
+
+	/// A generator with the same seed drawing from another of its independent streams,
+	/// from the start. The same seed and stream always give the same numbers.
+	pub fn stream(&self, stream: u64) -> Self {
+		let mut rng = ChaCha8Rng::from_seed(self.0.get_seed());
+		rng.set_stream(stream);
+		Self(rng)
+	}
+
+	pub fn u32(&mut self) -> u32 {
+		self.0.next_u32()
+	}
+
+	pub fn u64(&mut self) -> u64 {
+		self.0.next_u64()
+	}
+
+	/// From zero up to but not including one
+	pub fn f32(&mut self) -> f32 {
+		(self.0.next_u32() >> 8) as f32 / (1 << 24) as f32
+	}
+
+	/// From zero up to but not including one
+	pub fn f64(&mut self) -> f64 {
+		(self.0.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+	}
+
+	/// True with the probability from zero to one
+	pub fn chance(&mut self, probability: f64) -> bool {
+		self.f64() < probability
+	}
+
+	/// A number in the range, or its start when it's empty
+	pub fn range(&mut self, range: Range<i64>) -> i64 {
+		if range.is_empty() {
+			return range.start;
+		}
+		let width = range.end.wrapping_sub(range.start) as u64;
+		range.start.wrapping_add(self.below(width) as i64)
+	}
+
+	pub fn range_f32(&mut self, range: Range<f32>) -> f32 {
+		range.start + (range.end - range.start) * self.f32()
+	}
+
+	pub fn pick<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+		if items.is_empty() {
+			return None;
+		}
+		items.get(self.below(items.len() as u64) as usize)
+	}
+
+	pub fn shuffle<T>(&mut self, items: &mut [T]) {
+		for index in (1..items.len()).rev() {
+			items.swap(index, self.below(index as u64 + 1) as usize);
+		}
+	}
+
+	// Below a bound above zero, rejecting the few numbers that would make some likelier
+	fn below(&mut self, bound: u64) -> u64 {
+		let threshold = bound.wrapping_neg() % bound;
+		loop {
+			let product = u128::from(self.0.next_u64()) * u128::from(bound);
+			if product as u64 >= threshold {
+				return (product >> 64) as u64;
+			}
+		}
+	}
+}
+
+impl RngCore for Rng {
+	fn next_u32(&mut self) -> u32 {
+		self.0.next_u32()
+	}
+
+	fn next_u64(&mut self) -> u64 {
+		self.0.next_u64()
+	}
+
+	fn fill_bytes(&mut self, destination: &mut [u8]) {
+		self.0.fill_bytes(destination);
+	}
+}
+
+// Its seed as hex, with the stream and how far through it the generator is
+impl Reflect for Rng {
+	fn fields(&self) -> Vec<Field> {
+		let seed = self
+			.0
+			.get_seed()
+			.iter()
+			.map(|byte| format!("{byte:02x}"))
+			.collect();
+		vec![
+			Field {
+				name: "seed",
+				value: Value::Text(seed),
+			},
+			Field {
+				name: "stream",
+				value: self.0.get_stream().to_value(),
+			},
+			Field {
+				name: "position",
+				value: (self.0.get_word_pos() as u64).to_value(),
+			},
+		]
+	}
+
+	fn set_field(&mut self, name: &str, value: Value) -> Result<(), ReflectError> {
+		let wrong_type = || ReflectError::WrongType(name.to_string());
+		match (name, value) {
+			("seed", Value::Text(text)) => {
+				let mut seed = [0; 32];
+				if text.len() != 64 {
+					return Err(wrong_type());
+				}
+				for (byte, digits) in seed.iter_mut().zip(text.as_bytes().chunks(2)) {
+					let digits = std::str::from_utf8(digits).map_err(|_| wrong_type())?;
+					*byte = u8::from_str_radix(digits, 16).map_err(|_| wrong_type())?;
+				}
+				let (stream, position) = (self.0.get_stream(), self.0.get_word_pos());
+				self.0 = ChaCha8Rng::from_seed(seed);
+				self.0.set_stream(stream);
+				self.0.set_word_pos(position);
+			}
+			("stream", value) => self
+				.0
+				.set_stream(u64::from_value(value).ok_or_else(wrong_type)?),
+			("position", value) => self
+				.0
+				.set_word_pos(u64::from_value(value).ok_or_else(wrong_type)?.into()),
+			("seed", _) => return Err(wrong_type()),
+			_ => return Err(ReflectError::UnknownField(name.to_string())),
+		}
+		Ok(())
+	}
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct RngState {
+	seed: [u8; 32],
+	stream: u64,
+	position: u128,
+}
+
+#[cfg(feature = "serde")]
+impl From<RngState> for Rng {
+	fn from(state: RngState) -> Self {
+		let mut rng = ChaCha8Rng::from_seed(state.seed);
+		rng.set_stream(state.stream);
+		rng.set_word_pos(state.position);
+		Self(rng)
+	}
+}
+
+#[cfg(feature = "serde")]
+impl From<Rng> for RngState {
+	fn from(rng: Rng) -> Self {
+		Self {
+			seed: rng.0.get_seed(),
+			stream: rng.0.get_stream(),
+			position: rng.0.get_word_pos(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn seeded_generators_repeat_and_restore() -> Result<(), ReflectError> {
+		let mut first = Rng::new(7);
+		let mut second = Rng::new(7);
+		let numbers = (0..8).map(|_| first.range(-3..4)).collect::<Vec<_>>();
+		assert_eq!(
+			numbers,
+			(0..8).map(|_| second.range(-3..4)).collect::<Vec<_>>()
+		);
+		assert!(numbers.iter().all(|number| (-3..4).contains(number)));
+		assert_ne!(Rng::new(8).u64(), Rng::new(7).u64());
+
+		// Splits and streams draw their own numbers without disturbing each other
+		let mut split = first.split();
+		assert_ne!(split.u64(), first.clone().u64());
+		let mut stream = first.stream(3);
+		assert_eq!(stream.u64(), first.stream(3).u64());
+		assert_ne!(first.stream(4).u64(), first.stream(3).u64());
+
+		// A restored generator carries on where the saved one was
+		let mut restored = Rng::new(0);
+		for field in first.fields() {
+			restored.set_field(field.name, field.value)?;
+		}
+		assert_eq!(restored.u64(), first.u64());
+		assert!(restored
+			.set_field("seed", Value::Text("zz".into()))
+			.is_err());
+
+		let mut items = [1, 2, 3, 4, 5];
+		first.shuffle(&mut items);
+		items.sort_unstable();
+		assert_eq!(items, [1, 2, 3, 4, 5]);
+		assert_eq!(first.pick::<i32>(&[]), None);
+		assert!((0.0..1.0).contains(&first.f32()));
+		Ok(())
+	}
+}
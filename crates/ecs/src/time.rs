@@ -0,0 +1,55 @@
+use crate::world::World;
+
+/// The world's clock, stored as a resource. Apps advance it once per frame
+/// before their states update, so systems can read how much time passed.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Time {
+	delta: f32,
+	elapsed: f64,
+	frame: u64,
+}
+
+impl Time {
+	/// Seconds since the previous frame
+	pub fn delta_seconds(&self) -> f32 {
+		self.delta
+	}
+
+	/// Seconds since the clock started
+	pub fn elapsed_seconds(&self) -> f64 {
+		self.elapsed
+	}
+
+	/// Number of times the clock has been advanced
+	pub fn frame(&self) -> u64 {
+		self.frame
+	}
+
+	pub fn advance(&mut self, delta: f32) {
+		self.delta = delta.max(0.0);
+		self.elapsed += f64::from(self.delta);
+		self.frame += 1;
+	}
+}
+
+/// Advances the world's `Time`, adding it if the world has none yet
+pub fn advance_time(world: &mut World, delta: f32) {
+	let mut resources = world.resources().borrow_mut();
+	match resources.get_mut::<Time>() {
+		Some(time) => time.advance(delta),
+		None => {
+			let mut time = Time::default();
+			time.advance(delta);
+			resources.insert(time);
+		}
+	}
+}
+
+/// The seconds since the previous frame, or zero if the world has no `Time`
+pub fn delta_seconds(world: &World) -> f32 {
+	world
+		.resources()
+		.borrow()
+		.get::<Time>()
+		.map_or(0.0, Time::delta_seconds)
+}
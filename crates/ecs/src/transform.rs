@@ -0,0 +1,151 @@
+use crate::{
+	error::Result,
+	hierarchy,
+	reflect::{Field, FieldValue, Reflect, ReflectError, Value},
+	world::{Entity, World},
+};
+use math::{glam::EulerRot, GlobalTransform, Quat, Transform};
+use std::collections::HashMap;
+
+const FIELDS: [&str; 9] = [
+	"x",
+	"y",
+	"z",
+	"rotation_x",
+	"rotation_y",
+	"rotation_z",
+	"scale_x",
+	"scale_y",
+	"scale_z",
+];
+
+// Flattened into numbers tools can edit, with the rotation as euler angles in degrees
+impl Reflect for Transform {
+	fn fields(&self) -> Vec<Field> {
+		let (x, y, z) = self.rotation.to_euler(EulerRot::XYZ);
+		let rotation = [x, y, z].map(f32::to_degrees);
+		let values = self
+			.translation
+			.to_array()
+			.into_iter()
+			.chain(rotation)
+			.chain(self.scale.to_array());
+		FIELDS
+			.into_iter()
+			.zip(values)
+			.map(|(name, value)| Field {
+				name,
+				value: value.to_value(),
+			})
+			.collect()
+	}
+
+	fn set_field(&mut self, name: &str, value: Value) -> std::result::Result<(), ReflectError> {
+		let index = FIELDS
+			.iter()
+			.position(|field| *field == name)
+			.ok_or_else(|| ReflectError::UnknownField(name.to_string()))?;
+		let value =
+			f32::from_value(value).ok_or_else(|| ReflectError::WrongType(name.to_string()))?;
+		match index {
+			0..=2 => self.translation[index] = value,
+			3..=5 => {
+				let (x, y, z) = self.rotation.to_euler(EulerRot::XYZ);
+				let mut rotation = [x, y, z];
+				rotation[index - 3] = value.to_radians();
+				let [x, y, z] = rotation;
+				self.rotation = Quat::from_euler(EulerRot::XYZ, x, y, z);
+			}
+			_ => self.scale[index - 6] = value,
+		}
+		Ok(())
+	}
+}
+
+/// Gives every entity with a `Transform` a `GlobalTransform` placing it in the world
+/// through its parents. Parents without a `Transform` count as the identity.
+pub fn propagate_transforms(world: &mut World) -> Result<()> {
+	let mut globals = HashMap::new();
+	let entities = world
+		.entities()
+		.into_iter()
+		.filter(|entity| world.get_component::<Transform>(*entity).is_some())
+		.collect::<Vec<_>>();
+	for entity in &entities {
+		global_transform(world, *entity, &mut globals);
+	}
+	for entity in entities {
+		world.add_component(entity, globals[&entity])?;
+	}
+	Ok(())
+}
+
+fn global_transform(
+	world: &World,
+	entity: Entity,
+	globals: &mut HashMap<Entity, GlobalTransform>,
+) -> GlobalTransform {
+	if let Some(global) = globals.get(&entity) {
+		return *global;
+	}
+	let local = world
+		.get_component::<Transform>(entity)
+		.map_or_else(Transform::default, |transform| *transform);
+	// Cycles made by adding `Parent` components directly are cut where they close
+	globals.insert(entity, GlobalTransform::from(local));
+	let global = match hierarchy::parent(world, entity) {
+		Some(parent) => global_transform(world, parent, globals).mul_transform(&local),
+		None => GlobalTransform::from(local),
+	};
+	globals.insert(entity, global);
+	global
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use math::Vec3;
+
+	#[test]
+	fn children_follow_their_parents() -> Result<()> {
+		let mut world = World::new();
+		let [root, group, child] = [(); 3].map(|_| world.create_entity());
+		world.add_component(root, Transform::from_xyz(1.0, 2.0, 3.0))?;
+		world.add_component(child, Transform::from_xyz(0.0, 1.0, 0.0))?;
+		hierarchy::set_parent(&mut world, group, Some(root))?;
+		hierarchy::set_parent(&mut world, child, Some(group))?;
+
+		propagate_transforms(&mut world)?;
+		let translation = |entity| {
+			world
+				.get_component::<GlobalTransform>(entity)
+				.map(|global| global.translation())
+		};
+		assert_eq!(translation(root), Some(Vec3::new(1.0, 2.0, 3.0)));
+		assert_eq!(translation(child), Some(Vec3::new(1.0, 3.0, 3.0)));
+		// Without a transform of its own it has no place in the world
+		assert_eq!(translation(group), None);
+		Ok(())
+	}
+
+	#[test]
+	fn transforms_reflect_as_numbers() -> std::result::Result<(), ReflectError> {
+		let mut transform = Transform::from_xyz(1.0, 2.0, 3.0);
+		transform.set_field("rotation_z", Value::Float(90.0))?;
+		transform.set_field("scale_y", Value::Integer(2))?;
+		assert!((transform.angle_z() - 90_f32.to_radians()).abs() < 1e-5);
+		assert_eq!(transform.scale, Vec3::new(1.0, 2.0, 1.0));
+		let fields = transform.fields();
+		assert_eq!(fields.len(), 9);
+		assert_eq!(fields[0].value, Value::Float(1.0));
+		let Value::Float(degrees) = fields[5].value else {
+			panic!("rotation should be a float");
+		};
+		assert!((degrees - 90.0).abs() < 1e-4);
+		assert!(matches!(
+			transform.set_field("w", Value::Float(0.0)),
+			Err(ReflectError::UnknownField(_))
+		));
+		Ok(())
+	}
+}
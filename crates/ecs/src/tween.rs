@@ -0,0 +1,429 @@
+use crate::{
+	error::Result,
+	events::Events,
+	time,
+	world::{Entity, World},
+};
+use math::{Color, Quat, Vec2, Vec3};
+use std::f32::consts::PI;
+
+/// A value that can be blended from one to another
+pub trait Tweenable: Copy + 'static {
+	fn lerp(self, to: Self, amount: f32) -> Self;
+}
+
+impl Tweenable for f32 {
+	fn lerp(self, to: Self, amount: f32) -> Self {
+		self + (to - self) * amount
+	}
+}
+
+impl Tweenable for f64 {
+	fn lerp(self, to: Self, amount: f32) -> Self {
+		self + (to - self) * f64::from(amount)
+	}
+}
+
+impl Tweenable for Vec2 {
+	fn lerp(self, to: Self, amount: f32) -> Self {
+		Vec2::lerp(self, to, amount)
+	}
+}
+
+impl Tweenable for Vec3 {
+	fn lerp(self, to: Self, amount: f32) -> Self {
+		Vec3::lerp(self, to, amount)
+	}
+}
+
+impl Tweenable for Quat {
+	fn lerp(self, to: Self, amount: f32) -> Self {
+		self.slerp(to, amount)
+	}
+}
+
+impl Tweenable for Color {
+	fn lerp(self, to: Self, amount: f32) -> Self {
+		Color::lerp(self, to, amount)
+	}
+}
+
+/// How a tween's progress is curved over its duration
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Ease {
+	#[default]
+	Linear,
+	QuadIn,
+	QuadOut,
+	QuadInOut,
+	CubicIn,
+	CubicOut,
+	CubicInOut,
+	SineIn,
+	SineOut,
+	SineInOut,
+	/// Pulls back before setting off
+	BackIn,
+	/// Overshoots before settling
+	BackOut,
+	/// Springs past the end and wobbles into place
+	ElasticOut,
+	BounceOut,
+}
+
+impl Ease {
+	/// The eased progress for progress from 0.0 to 1.0
+	pub fn apply(self, t: f32) -> f32 {
+		const BACK: f32 = 1.70158;
+		let t = t.clamp(0.0, 1.0);
+		match self {
+			Self::Linear => t,
+			Self::QuadIn => t * t,
+			Self::QuadOut => 1.0 - (1.0 - t).powi(2),
+			Self::QuadInOut if t < 0.5 => 2.0 * t * t,
+			Self::QuadInOut => 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0,
+			Self::CubicIn => t.powi(3),
+			Self::CubicOut => 1.0 - (1.0 - t).powi(3),
+			Self::CubicInOut if t < 0.5 => 4.0 * t.powi(3),
+			Self::CubicInOut => 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0,
+			Self::SineIn => 1.0 - (t * PI / 2.0).cos(),
+			Self::SineOut => (t * PI / 2.0).sin(),
+			Self::SineInOut => -((t * PI).cos() - 1.0) / 2.0,
+			Self::BackIn => (BACK + 1.0) * t.powi(3) - BACK * t * t,
+			Self::BackOut => 1.0 + (BACK + 1.0) * (t - 1.0).powi(3) + BACK * (t - 1.0).powi(2),
+			Self::ElasticOut if t == 0.0 || t == 1.0 => t,
+			Self::ElasticOut => {
+				2_f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0
+			}
+			Self::BounceOut => bounce_out(t),
+		}
+	}
+}
+
+fn bounce_out(t: f32) -> f32 {
+	const N: f32 = 7.5625;
+	const D: f32 = 2.75;
+	if t < 1.0 / D {
+		N * t * t
+	} else if t < 2.0 / D {
+		let t = t - 1.5 / D;
+		N * t * t + 0.75
+	} else if t < 2.5 / D {
+		let t = t - 2.25 / D;
+		N * t * t + 0.9375
+	} else {
+		let t = t - 2.625 / D;
+		N * t * t + 0.984375
+	}
+}
+
+/// Identifies a started tween in its `TweenFinished` event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TweenId(u64);
+
+/// Sent to the world's `Events<TweenFinished>` when a tween reaches its end
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TweenFinished {
+	pub id: TweenId,
+	pub entity: Entity,
+}
+
+/// Something `Tweens` can play, made with `Tween::new(entity).field(...)`
+pub trait Animate: 'static {
+	fn entity(&self) -> Entity;
+
+	/// Moves on by the seconds passed, returning whether it has finished,
+	/// or none if what it animates is gone
+	fn advance(&mut self, world: &World, delta: f32) -> Option<bool>;
+
+	/// The tweens to start once this one finishes
+	fn take_next(&mut self) -> Vec<Box<dyn Animate>>;
+}
+
+/// Starts building a tween of one of the entity's components
+pub struct Tween {
+	entity: Entity,
+}
+
+impl Tween {
+	pub fn new(entity: Entity) -> Self {
+		Self { entity }
+	}
+
+	/// Animates the field of the component the closure picks out,
+	/// such as `|transform: &mut Transform| &mut transform.translation`
+	pub fn field<C, V, F>(self, field: F) -> FieldTween<C, V>
+	where
+		C: 'static,
+		V: Tweenable,
+		F: Fn(&mut C) -> &mut V + 'static,
+	{
+		FieldTween {
+			entity: self.entity,
+			field: Box::new(field),
+			from: None,
+			to: None,
+			duration: 1.0,
+			delay: 0.0,
+			ease: Ease::Linear,
+			elapsed: 0.0,
+			next: Vec::new(),
+		}
+	}
+}
+
+type Field<C, V> = Box<dyn Fn(&mut C) -> &mut V>;
+
+/// Blends a component's field to a value over some seconds
+pub struct FieldTween<C, V> {
+	entity: Entity,
+	field: Field<C, V>,
+	from: Option<V>,
+	to: Option<V>,
+	duration: f32,
+	delay: f32,
+	ease: Ease,
+	elapsed: f32,
+	next: Vec<Box<dyn Animate>>,
+}
+
+impl<C: 'static, V: Tweenable> FieldTween<C, V> {
+	/// Starts from this value instead of the field's value when the tween begins
+	pub fn from(self, from: V) -> Self {
+		Self {
+			from: Some(from),
+			..self
+		}
+	}
+
+	pub fn to(self, to: V) -> Self {
+		Self {
+			to: Some(to),
+			..self
+		}
+	}
+
+	/// How many seconds the tween takes, one by default
+	pub fn over(self, seconds: f32) -> Self {
+		Self {
+			duration: seconds.max(0.0),
+			..self
+		}
+	}
+
+	/// Waits this many seconds before starting
+	pub fn delay(self, seconds: f32) -> Self {
+		Self {
+			delay: seconds.max(0.0),
+			..self
+		}
+	}
+
+	pub fn ease(self, ease: Ease) -> Self {
+		Self { ease, ..self }
+	}
+
+	/// Starts another tween once this one finishes
+	pub fn then(mut self, next: impl Animate) -> Self {
+		self.next.push(Box::new(next));
+		self
+	}
+}
+
+impl<C: 'static, V: Tweenable> Animate for FieldTween<C, V> {
+	fn entity(&self) -> Entity {
+		self.entity
+	}
+
+	fn advance(&mut self, world: &World, delta: f32) -> Option<bool> {
+		let mut component = world.get_component_mut::<C>(self.entity)?;
+		self.elapsed += delta;
+		let elapsed = self.elapsed - self.delay;
+		if elapsed < 0.0 {
+			return Some(false);
+		}
+		let field = (self.field)(&mut *component);
+		let from = *self.from.get_or_insert(*field);
+		let to = self.to.unwrap_or(from);
+		let progress = if self.duration > 0.0 {
+			elapsed / self.duration
+		} else {
+			1.0
+		};
+		*field = from.lerp(to, self.ease.apply(progress));
+		Some(progress >= 1.0)
+	}
+
+	fn take_next(&mut self) -> Vec<Box<dyn Animate>> {
+		std::mem::take(&mut self.next)
+	}
+}
+
+/// The playing tweens, stored as a world resource and advanced by `update_tweens`
+#[derive(Default)]
+pub struct Tweens {
+	playing: Vec<(TweenId, Box<dyn Animate>)>,
+	next_id: u64,
+}
+
+impl Tweens {
+	pub fn start(&mut self, tween: impl Animate) -> TweenId {
+		self.start_boxed(Box::new(tween))
+	}
+
+	fn start_boxed(&mut self, tween: Box<dyn Animate>) -> TweenId {
+		let id = TweenId(self.next_id);
+		self.next_id += 1;
+		self.playing.push((id, tween));
+		id
+	}
+
+	/// Stops a tween where it is, without sending its `TweenFinished` or starting what follows it
+	pub fn cancel(&mut self, id: TweenId) {
+		self.playing.retain(|(playing, _)| *playing != id);
+	}
+
+	/// Stops every tween of the entity's
+	pub fn cancel_entity(&mut self, entity: Entity) {
+		self.playing.retain(|(_, tween)| tween.entity() != entity);
+	}
+
+	pub fn is_playing(&self, id: TweenId) -> bool {
+		self.playing.iter().any(|(playing, _)| *playing == id)
+	}
+
+	pub fn len(&self) -> usize {
+		self.playing.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.playing.is_empty()
+	}
+}
+
+/// Starts a tween on the world, adding its `Tweens` if it has none yet
+pub fn start_tween(world: &World, tween: impl Animate) -> TweenId {
+	let mut resources = world.resources().borrow_mut();
+	if resources.get::<Tweens>().is_none() {
+		resources.insert(Tweens::default());
+	}
+	resources.get_mut::<Tweens>().unwrap().start(tween)
+}
+
+/// Advances every tween by the world's `Time`, sending `TweenFinished` events
+/// for those that end and starting the tweens chained after them. Tweens whose
+/// entity or component is gone are dropped.
+pub fn update_tweens(world: &mut World) -> Result<()> {
+	let delta = time::delta_seconds(world);
+	let Some(mut tweens) = world
+		.resources()
+		.borrow_mut()
+		.get_mut::<Tweens>()
+		.map(std::mem::take)
+	else {
+		return Ok(());
+	};
+	let mut finished = Vec::new();
+	let mut chained = Vec::new();
+	tweens
+		.playing
+		.retain_mut(|(id, tween)| match tween.advance(world, delta) {
+			Some(false) => true,
+			Some(true) => {
+				finished.push(TweenFinished {
+					id: *id,
+					entity: tween.entity(),
+				});
+				chained.extend(tween.take_next());
+				false
+			}
+			None => false,
+		});
+	for tween in chained {
+		tweens.start_boxed(tween);
+	}
+
+	let mut resources = world.resources().borrow_mut();
+	resources.insert(tweens);
+	match resources.get_mut::<Events<TweenFinished>>() {
+		Some(events) => events.extend(finished),
+		None => {
+			let mut events = Events::new();
+			events.extend(finished);
+			resources.insert(events);
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::time::advance_time;
+	use math::Transform;
+
+	struct Opacity(f32);
+
+	fn finished(world: &World) -> Vec<TweenFinished> {
+		let mut resources = world.resources().borrow_mut();
+		let events = resources.get_mut::<Events<TweenFinished>>().unwrap();
+		events.drain().collect()
+	}
+
+	#[test]
+	fn fields_tween_and_chain_by_time() -> Result<()> {
+		assert_eq!(Ease::QuadIn.apply(0.5), 0.25);
+		assert_eq!(Ease::BounceOut.apply(1.0), 1.0);
+		assert!(Ease::BackOut.apply(0.8) > 1.0);
+
+		let mut world = World::new();
+		let entity = world.create_entity();
+		world.add_component(entity, Transform::default())?;
+		world.add_component(entity, Opacity(1.0))?;
+		let slide = Tween::new(entity)
+			.field(|transform: &mut Transform| &mut transform.translation)
+			.to(Vec3::new(4.0, 0.0, 0.0))
+			.over(2.0)
+			.then(
+				Tween::new(entity)
+					.field(|opacity: &mut Opacity| &mut opacity.0)
+					.to(0.0)
+					.over(1.0),
+			);
+		let id = start_tween(&world, slide);
+		let translation = |world: &World| {
+			world
+				.get_component::<Transform>(entity)
+				.unwrap()
+				.translation
+		};
+		let opacity = |world: &World| world.get_component::<Opacity>(entity).unwrap().0;
+
+		advance_time(&mut world, 0.5);
+		update_tweens(&mut world)?;
+		assert_eq!(translation(&world), Vec3::new(1.0, 0.0, 0.0));
+		assert!(finished(&world).is_empty());
+
+		advance_time(&mut world, 1.5);
+		update_tweens(&mut world)?;
+		assert_eq!(translation(&world), Vec3::new(4.0, 0.0, 0.0));
+		assert_eq!(finished(&world), [TweenFinished { id, entity }]);
+		// The fade chained after the slide begins on the next update
+		assert_eq!(opacity(&world), 1.0);
+		advance_time(&mut world, 0.25);
+		update_tweens(&mut world)?;
+		assert_eq!(opacity(&world), 0.75);
+
+		// Tweens of removed components stop without finishing
+		world.remove_component::<Opacity>(entity)?;
+		update_tweens(&mut world)?;
+		assert!(finished(&world).is_empty());
+		assert!(world
+			.resources()
+			.borrow()
+			.get::<Tweens>()
+			.unwrap()
+			.is_empty());
+		Ok(())
+	}
+}
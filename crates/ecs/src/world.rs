@@ -138,6 +138,7 @@ macro_rules! system {
 pub struct World {
 	resources: Rc<RefCell<AnyMap>>,
 	components: ComponentMap,
+	component_names: HashMap<TypeId, &'static str>,
 	allocator: HandleAllocator,
 }
 
@@ -168,6 +169,17 @@ impl World {
 			.for_each(|entity| self.allocator.deallocate(entity))
 	}
 
+	/// Brings back a removed entity with the same handle and no components
+	pub fn restore_entity(&mut self, entity: Entity) -> Result<()> {
+		if !self.allocator.restore(&entity) {
+			return Err(Box::new(HandleNotFoundError { handle: entity }));
+		}
+		for components in self.components.values() {
+			components.borrow_mut().remove(entity);
+		}
+		Ok(())
+	}
+
 	pub fn add_component<T: 'static>(&mut self, entity: Entity, component: T) -> Result<()> {
 		self.assign_component::<T>(entity, Some(Box::new(component)))
 	}
@@ -191,6 +203,8 @@ impl World {
 			);
 		}
 
+		self.component_names
+			.insert(TypeId::of::<T>(), std::any::type_name::<T>());
 		let mut components = self
 			.components
 			.entry(TypeId::of::<T>())
@@ -214,7 +228,7 @@ impl World {
 	}
 
 	#[must_use]
-	pub fn get_component<T: 'static>(&self, entity: Entity) -> Option<Ref<T>> {
+	pub fn get_component<T: 'static>(&self, entity: Entity) -> Option<Ref<'_, T>> {
 		if !self.entity_exists(entity) {
 			return None;
 		}
@@ -233,7 +247,7 @@ impl World {
 	}
 
 	#[must_use]
-	pub fn get_component_mut<T: 'static>(&self, entity: Entity) -> Option<RefMut<T>> {
+	pub fn get_component_mut<T: 'static>(&self, entity: Entity) -> Option<RefMut<'_, T>> {
 		if !self.entity_exists(entity) {
 			return None;
 		}
@@ -251,19 +265,21 @@ impl World {
 			})
 	}
 
-	pub fn get_component_vec<T: 'static>(&self) -> Option<Ref<ComponentVec>> {
+	pub fn get_component_vec<T: 'static>(&self) -> Option<Ref<'_, ComponentVec>> {
 		self.components
 			.get(&TypeId::of::<T>())
 			.map(|component_vec| component_vec.deref().borrow())
 	}
 
-	pub fn get_component_vec_mut<T: 'static>(&self) -> Option<RefMut<ComponentVec>> {
+	pub fn get_component_vec_mut<T: 'static>(&self) -> Option<RefMut<'_, ComponentVec>> {
 		self.components
 			.get(&TypeId::of::<T>())
 			.map(|component_vec| component_vec.deref().borrow_mut())
 	}
 
 	pub fn register_component<T: 'static>(&mut self) {
+		self.component_names
+			.insert(TypeId::of::<T>(), std::any::type_name::<T>());
 		self.components
 			.entry(TypeId::of::<T>())
 			.or_insert(component_vec!());
@@ -272,6 +288,26 @@ impl World {
 	pub fn entity_exists(&self, entity: Entity) -> bool {
 		self.allocator.is_allocated(&entity)
 	}
+
+	/// Every entity in the world, in creation order of their slots
+	pub fn entities(&self) -> Vec<Entity> {
+		self.allocator.allocated_handles()
+	}
+
+	/// The types of every component on the entity, with their type names
+	pub fn component_types(&self, entity: Entity) -> Vec<(TypeId, &'static str)> {
+		if !self.entity_exists(entity) {
+			return Vec::new();
+		}
+		self.components
+			.iter()
+			.filter(|(_, components)| entity_has_component(entity, components))
+			.map(|(type_id, _)| {
+				let name = self.component_names.get(type_id).copied();
+				(*type_id, name.unwrap_or("unknown"))
+			})
+			.collect()
+	}
 }
 
 pub fn entity_has_component(entity: Entity, components: &ComponentVecHandle) -> bool {
@@ -294,6 +330,7 @@ mod tests {
 		value: u8,
 	}
 
+	#[allow(dead_code)]
 	struct Name(String);
 
 	// Translate only named entities
@@ -323,6 +360,35 @@ mod tests {
 		Ok(())
 	}
 
+	#[test]
+	fn restore_entity() -> Result<()> {
+		let mut world = World::new();
+		let entity = world.create_entity();
+		world.add_component(entity, Position::default())?;
+		assert!(world.restore_entity(entity).is_err());
+
+		world.remove_entity(entity);
+		let reused = world.create_entity();
+		world.add_component(reused, Health { value: 1 })?;
+		world.remove_entity(reused);
+
+		world.restore_entity(entity)?;
+		assert!(world.entity_exists(entity));
+		assert!(!world.entity_exists(reused));
+		assert!(world.get_component::<Position>(entity).is_none());
+		world.add_component(entity, Health { value: 2 })?;
+		assert_ne!(world.create_entity(), entity);
+
+		// The slot never hands out a generation again, so old handles stay stale
+		world.remove_entity(entity);
+		let newest = world.create_entity();
+		assert_eq!(newest.index(), entity.index());
+		assert_ne!(newest, entity);
+		assert_ne!(newest, reused);
+		assert!(!world.entity_exists(reused));
+		Ok(())
+	}
+
 	#[test]
 	fn add_component() -> Result<()> {
 		let mut world = World::default();
@@ -358,6 +424,20 @@ mod tests {
 		Ok(())
 	}
 
+	#[test]
+	fn component_types() -> Result<()> {
+		let mut world = World::new();
+		let entity = world.create_entity();
+		world.add_component(entity, Position { x: 0.0, y: 0.0 })?;
+		world.add_component(entity, Health { value: 10 })?;
+		world.remove_component::<Health>(entity)?;
+		assert_eq!(
+			world.component_types(entity),
+			[(TypeId::of::<Position>(), std::any::type_name::<Position>())]
+		);
+		Ok(())
+	}
+
 	#[test]
 	fn get_component() -> Result<()> {
 		let mut world = World::default();
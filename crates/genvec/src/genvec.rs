@@ -155,6 +155,9 @@ impl<T> DerefMut for Slot<T> {
 pub struct Allocation {
 	allocated: bool,
 	generation: usize,
+	// The newest generation the slot has handed out, which a restored older handle can be
+	// behind, so allocating never reuses a generation
+	newest: usize,
 }
 
 #[derive(Default)]
@@ -171,17 +174,20 @@ impl HandleAllocator {
 	pub fn allocate(&mut self) -> Handle {
 		match self.available_handles.pop() {
 			Some(index) => {
-				self.allocations[index].generation += 1;
-				self.allocations[index].allocated = true;
+				let allocation = &mut self.allocations[index];
+				allocation.newest += 1;
+				allocation.generation = allocation.newest;
+				allocation.allocated = true;
 				Handle {
 					index,
-					generation: self.allocations[index].generation,
+					generation: allocation.generation,
 				}
 			}
 			None => {
 				self.allocations.push(Allocation {
 					allocated: true,
 					generation: 0,
+					newest: 0,
 				});
 				Handle {
 					index: self.allocations.len() - 1,
@@ -199,6 +205,21 @@ impl HandleAllocator {
 		self.available_handles.push(handle.index);
 	}
 
+	/// Allocates a deallocated handle again, keeping its generation. Handles allocated
+	/// in the slot since then stay stale. Returns false if the handle's slot is in use.
+	pub fn restore(&mut self, handle: &Handle) -> bool {
+		if !self.handle_exists(handle) || self.allocations[handle.index].allocated {
+			return false;
+		}
+		let allocation = &mut self.allocations[handle.index];
+		allocation.allocated = true;
+		allocation.generation = handle.generation;
+		allocation.newest = allocation.newest.max(handle.generation);
+		self.available_handles
+			.retain(|index| *index != handle.index);
+		true
+	}
+
 	pub fn is_allocated(&self, handle: &Handle) -> bool {
 		self.handle_exists(handle)
 			&& self.allocations[handle.index].generation == handle.generation
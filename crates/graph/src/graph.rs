@@ -46,7 +46,10 @@ impl<T, E> Default for Graph<T, E> {
 
 impl<T, E> Graph<T, E> {
 	pub fn new() -> Self {
-		Self::default()
+		Self {
+			nodes: HashMap::new(),
+			adjacency_list: HashMap::new(),
+		}
 	}
 
 	pub fn add_node(&mut self, data: T) -> NodeId {
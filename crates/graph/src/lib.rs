@@ -1,3 +1,3 @@
 mod graph;
 
-pub use self::graph::Graph;
+pub use self::graph::{Graph, GraphError, Node, NodeId};
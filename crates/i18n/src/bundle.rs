@@ -0,0 +1,198 @@
+use crate::localization::Argument;
+use fluent_bundle::{concurrent::FluentBundle, FluentArgs, FluentResource, FluentValue};
+use fluent_syntax::ast::Entry;
+use std::sync::Arc;
+use thiserror::Error;
+use unic_langid::LanguageIdentifier;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum LocalizationError {
+	#[error("Syntax error on line {line}: {message}")]
+	Syntax { line: usize, message: String },
+}
+
+/// Formats the messages of a bundle in one language
+pub(crate) type Formatter = FluentBundle<Arc<FluentResource>>;
+
+/// The translations for one language, parsed from Fluent (`.ftl`) source:
+///
+/// ```text
+/// # Terms start with a dash and can only be used in other messages
+/// -brand = Hourglass
+///
+/// welcome = Welcome to { -brand }, { $name }!
+/// items = { $count ->
+///     [0] No items
+///     [one] One item
+///    *[other] { $count } items
+/// }
+/// play = Play
+///     .tooltip = Start a new game
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Bundle {
+	resources: Vec<Arc<FluentResource>>,
+}
+
+impl Bundle {
+	/// Fails on the first part of the source that isn't valid Fluent
+	pub fn parse(source: &str) -> Result<Self, LocalizationError> {
+		let resource = FluentResource::try_new(source.to_string()).map_err(|(_, errors)| {
+			let (start, message) = errors.first().map_or((0, String::new()), |error| {
+				(error.pos.start, error.to_string())
+			});
+			let before = source.get(..start).unwrap_or(source);
+			LocalizationError::Syntax {
+				line: before.matches('\n').count() + 1,
+				message,
+			}
+		})?;
+		Ok(Self {
+			resources: vec![Arc::new(resource)],
+		})
+	}
+
+	pub fn has_message(&self, id: &str) -> bool {
+		self.message_ids().any(|message| message == id)
+	}
+
+	pub fn message_ids(&self) -> impl Iterator<Item = &str> {
+		self.resources
+			.iter()
+			.flat_map(|resource| resource.entries())
+			.filter_map(|entry| match entry {
+				Entry::Message(message) => Some(message.id.name),
+				_ => None,
+			})
+	}
+
+	/// Adds the other bundle's messages, replacing any with the same names
+	pub fn extend(&mut self, other: Bundle) {
+		self.resources.extend(other.resources);
+	}
+
+	/// The message's value, or one of its attributes with `message.attribute`, with the
+	/// arguments filled in. Missing arguments and references are written as they appear in
+	/// the source. The language picks the plural variants of numbers.
+	pub fn format(&self, key: &str, args: &[(&str, Argument)], language: &str) -> Option<String> {
+		format(&self.formatter(language), key, args)
+	}
+
+	pub(crate) fn formatter(&self, language: &str) -> Formatter {
+		let language = language.parse::<LanguageIdentifier>().unwrap_or_default();
+		let mut formatter = FluentBundle::new_concurrent(vec![language]);
+		// Fluent otherwise marks where arguments start and end, for text that runs right to left
+		formatter.set_use_isolating(false);
+		for resource in &self.resources {
+			formatter.add_resource_overriding(resource.clone());
+		}
+		formatter
+	}
+}
+
+// A key's message and attribute
+pub(crate) fn split(key: &str) -> (&str, Option<&str>) {
+	match key.split_once('.') {
+		Some((id, attribute)) => (id, Some(attribute)),
+		None => (key, None),
+	}
+}
+
+pub(crate) fn format(
+	formatter: &Formatter,
+	key: &str,
+	args: &[(&str, Argument)],
+) -> Option<String> {
+	let (id, attribute) = split(key);
+	let message = formatter.get_message(id)?;
+	let pattern = match attribute {
+		Some(attribute) => message.get_attribute(attribute)?.value(),
+		None => message.value()?,
+	};
+	let args = args
+		.iter()
+		.map(|(name, argument)| (*name, FluentValue::from(argument)))
+		.collect::<FluentArgs>();
+	// What went wrong is already written into the text
+	let mut errors = Vec::new();
+	Some(
+		formatter
+			.format_pattern(pattern, Some(&args), &mut errors)
+			.into_owned(),
+	)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl assets::Asset for Bundle {
+	fn load(
+		bytes: Vec<u8>,
+		_context: &mut assets::LoadContext,
+	) -> Result<Self, assets::AssetError> {
+		let source = String::from_utf8(bytes).map_err(assets::AssetError::invalid)?;
+		Self::parse(&source).map_err(assets::AssetError::invalid)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const SOURCE: &str = r#"
+# Terms can only be used in other messages
+-brand = Hourglass
+
+welcome = Welcome to { -brand }, { $name }!
+items = { $count ->
+    [0] No items
+    [one] One item
+   *[other] { $count } items
+}
+play = Play
+    .tooltip = Start a new
+        { -brand } game
+loop = { loop }
+"#;
+
+	fn format(bundle: &Bundle, key: &str, args: &[(&str, Argument)]) -> Option<String> {
+		bundle.format(key, args, "en")
+	}
+
+	#[test]
+	fn messages_are_formatted_with_their_arguments() -> Result<(), LocalizationError> {
+		let bundle = Bundle::parse(SOURCE)?;
+		assert!(!bundle.has_message("brand"));
+		assert_eq!(
+			format(&bundle, "welcome", &[("name", "Ada".into())]).as_deref(),
+			Some("Welcome to Hourglass, Ada!")
+		);
+		for (count, expected) in [(0, "No items"), (1, "One item"), (7, "7 items")] {
+			assert_eq!(
+				format(&bundle, "items", &[("count", count.into())]).as_deref(),
+				Some(expected)
+			);
+		}
+		assert_eq!(format(&bundle, "play", &[]).as_deref(), Some("Play"));
+		assert_eq!(
+			format(&bundle, "play.tooltip", &[]).as_deref(),
+			Some("Start a new\nHourglass game")
+		);
+		// Missing arguments and references that never end are left as they're written
+		assert_eq!(
+			format(&bundle, "welcome", &[]).as_deref(),
+			Some("Welcome to Hourglass, {$name}!")
+		);
+		assert_eq!(format(&bundle, "loop", &[]).as_deref(), Some("{loop}"));
+		assert_eq!(format(&bundle, "missing", &[]), None);
+
+		assert_eq!(
+			Bundle::parse("a = 1\nb = { $count ->\n[one] x\n}").unwrap_err(),
+			LocalizationError::Syntax {
+				line: 4,
+				message: "The select expression must have a default variant".to_string(),
+			}
+		);
+		assert!(Bundle::parse("a = {").is_err());
+		assert!(Bundle::parse("no value").is_err());
+		Ok(())
+	}
+}
@@ -0,0 +1,11 @@
+#![forbid(unsafe_code)]
+
+mod bundle;
+mod localization;
+mod system;
+
+pub use self::{
+	bundle::{Bundle, LocalizationError},
+	localization::{Argument, LanguageChanged, Localization},
+	system::{localize, Localized, LocalizedWidgets},
+};
@@ -0,0 +1,210 @@
+use crate::bundle::{format, split, Bundle, Formatter};
+use ecs::events::Events;
+use fluent_bundle::FluentValue;
+use std::{collections::HashMap, fmt};
+
+/// A value filled into a message's `{ $variable }`. Numbers also pick plural variants.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Argument {
+	Number(f64),
+	Text(String),
+}
+
+impl fmt::Display for Argument {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			// Whole numbers are written without a fraction
+			Self::Number(number) if number.fract() == 0.0 && number.abs() < 1e15 => {
+				write!(f, "{}", *number as i64)
+			}
+			Self::Number(number) => write!(f, "{number}"),
+			Self::Text(text) => write!(f, "{text}"),
+		}
+	}
+}
+
+macro_rules! number_arguments {
+	($($number:ty),*) => {
+		$(impl From<$number> for Argument {
+			fn from(number: $number) -> Self {
+				Self::Number(number as f64)
+			}
+		})*
+	};
+}
+
+number_arguments!(i32, i64, u32, u64, usize, f32, f64);
+
+impl<'a> From<&'a Argument> for FluentValue<'a> {
+	fn from(argument: &'a Argument) -> Self {
+		match argument {
+			Argument::Number(number) => Self::from(*number),
+			Argument::Text(text) => Self::from(text.as_str()),
+		}
+	}
+}
+
+impl From<&str> for Argument {
+	fn from(text: &str) -> Self {
+		Self::Text(text.to_string())
+	}
+}
+
+impl From<String> for Argument {
+	fn from(text: String) -> Self {
+		Self::Text(text)
+	}
+}
+
+/// Sent when `Localization::set_language` switches to another language
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageChanged {
+	pub previous: String,
+	pub language: String,
+}
+
+/// The game's translations, kept as a world resource. Languages are named by their tags,
+/// like `en-US`, and messages missing from the current language are looked up in the
+/// fallback language.
+pub struct Localization {
+	bundles: HashMap<String, Bundle>,
+	// Rebuilt when a language's bundle changes, rather than for every message
+	formatters: HashMap<String, Formatter>,
+	language: String,
+	fallback: Option<String>,
+	events: Events<LanguageChanged>,
+}
+
+impl Localization {
+	pub fn new(language: impl Into<String>) -> Self {
+		Self {
+			bundles: HashMap::new(),
+			formatters: HashMap::new(),
+			language: language.into(),
+			fallback: None,
+			events: Events::new(),
+		}
+	}
+
+	pub fn with_fallback(self, language: impl Into<String>) -> Self {
+		Self {
+			fallback: Some(language.into()),
+			..self
+		}
+	}
+
+	/// Adds the language's messages to any it already has
+	pub fn add_bundle(&mut self, language: impl Into<String>, bundle: Bundle) {
+		let language = language.into();
+		let added = self.bundles.entry(language.clone()).or_default();
+		added.extend(bundle);
+		let formatter = added.formatter(&language);
+		self.formatters.insert(language, formatter);
+	}
+
+	pub fn bundle(&self, language: &str) -> Option<&Bundle> {
+		self.bundles.get(language)
+	}
+
+	pub fn language(&self) -> &str {
+		&self.language
+	}
+
+	/// The languages with bundles
+	pub fn languages(&self) -> impl Iterator<Item = &str> {
+		self.bundles.keys().map(String::as_str)
+	}
+
+	pub fn set_language(&mut self, language: impl Into<String>) {
+		let language = language.into();
+		if language != self.language {
+			let previous = std::mem::replace(&mut self.language, language.clone());
+			self.events.send(LanguageChanged { previous, language });
+		}
+	}
+
+	/// Whether the current or fallback language has the message
+	pub fn has(&self, key: &str) -> bool {
+		let (id, _) = split(key);
+		self.searched().any(|formatter| formatter.has_message(id))
+	}
+
+	/// The message with the arguments filled in, from the current language or else the
+	/// fallback. Attributes are looked up with `message.attribute`, and missing messages
+	/// are returned as their key so they stand out.
+	pub fn t(&self, key: &str, args: &[(&str, Argument)]) -> String {
+		self.searched()
+			.find_map(|formatter| format(formatter, key, args))
+			.unwrap_or_else(|| key.to_string())
+	}
+
+	/// The language changes since the events were last drained
+	pub fn drain_events(&mut self) -> impl Iterator<Item = LanguageChanged> + '_ {
+		self.events.drain()
+	}
+
+	// The current language's formatter, then the fallback's
+	fn searched(&self) -> impl Iterator<Item = &Formatter> {
+		[Some(&self.language), self.fallback.as_ref()]
+			.into_iter()
+			.flatten()
+			.filter_map(|language| self.formatters.get(language))
+	}
+}
+
+impl fmt::Debug for Localization {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Localization")
+			.field("bundles", &self.bundles)
+			.field("language", &self.language)
+			.field("fallback", &self.fallback)
+			.field("events", &self.events)
+			.finish_non_exhaustive()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::bundle::LocalizationError;
+
+	#[test]
+	fn missing_messages_fall_back() -> Result<(), LocalizationError> {
+		let mut localization = Localization::new("ru").with_fallback("en");
+		localization.add_bundle(
+			"en",
+			Bundle::parse("quit = Quit\n    .confirm = Are you sure?\nlives = { $count } lives")?,
+		);
+		localization.add_bundle(
+			"ru",
+			Bundle::parse(
+				"lives = { $count ->\n  [one] { $count } жизнь\n  [few] { $count } жизни\n *[many] { $count } жизней\n}",
+			)?,
+		);
+		assert_eq!(localization.t("lives", &[("count", 21.into())]), "21 жизнь");
+		assert_eq!(localization.t("lives", &[("count", 3.into())]), "3 жизни");
+		assert_eq!(
+			localization.t("lives", &[("count", 11.into())]),
+			"11 жизней"
+		);
+		assert_eq!(localization.t("quit.confirm", &[]), "Are you sure?");
+		assert!(localization.has("quit"));
+		assert_eq!(localization.t("credits", &[]), "credits");
+
+		localization.set_language("ru");
+		assert_eq!(localization.drain_events().count(), 0);
+		localization.set_language("en");
+		assert_eq!(
+			localization.t("lives", &[("count", 1.5.into())]),
+			"1.5 lives"
+		);
+		assert_eq!(
+			localization.drain_events().collect::<Vec<_>>(),
+			[LanguageChanged {
+				previous: "ru".to_string(),
+				language: "en".to_string(),
+			}]
+		);
+		Ok(())
+	}
+}
@@ -0,0 +1,202 @@
+use crate::localization::{Argument, LanguageChanged, Localization};
+use ecs::{error::Result, events::Events, world::World};
+use renderer::Text;
+use std::collections::HashMap;
+use ui::{Ui, WidgetId};
+
+/// A message shown by the entity's `Text`, or by a widget through `LocalizedWidgets`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Localized {
+	pub key: String,
+	pub args: Vec<(String, Argument)>,
+}
+
+impl Localized {
+	pub fn new(key: impl Into<String>) -> Self {
+		Self {
+			key: key.into(),
+			args: Vec::new(),
+		}
+	}
+
+	pub fn with_arg(mut self, name: impl Into<String>, value: impl Into<Argument>) -> Self {
+		self.set_arg(name, value);
+		self
+	}
+
+	/// Replaces the argument's value, so the text is updated the next time it's localized
+	pub fn set_arg(&mut self, name: impl Into<String>, value: impl Into<Argument>) {
+		let name = name.into();
+		let value = value.into();
+		match self.args.iter_mut().find(|(argument, _)| *argument == name) {
+			Some((_, argument)) => *argument = value,
+			None => self.args.push((name, value)),
+		}
+	}
+
+	pub fn text(&self, localization: &Localization) -> String {
+		let args = self
+			.args
+			.iter()
+			.map(|(name, value)| (name.as_str(), value.clone()))
+			.collect::<Vec<_>>();
+		localization.t(&self.key, &args)
+	}
+}
+
+/// Sets the value of every `Text` with a `Localized` to its message in the `Localization`
+/// resource's language, so switching languages or changing arguments shows up the same
+/// frame. Language changes are sent as `Events<LanguageChanged>`.
+pub fn localize(world: &mut World) -> Result<()> {
+	let (texts, changes) = {
+		let mut resources = world.resources().borrow_mut();
+		let Some(localization) = resources.get_mut::<Localization>() else {
+			return Ok(());
+		};
+		let changes = localization.drain_events().collect::<Vec<_>>();
+		let texts = world
+			.entities()
+			.into_iter()
+			.filter_map(|entity| {
+				let localized = world.get_component::<Localized>(entity)?;
+				Some((entity, localized.text(localization)))
+			})
+			.collect::<Vec<_>>();
+		(texts, changes)
+	};
+	for (entity, value) in texts {
+		if let Some(mut text) = world.get_component_mut::<Text>(entity) {
+			if text.value != value {
+				text.value = value;
+			}
+		}
+	}
+	if !changes.is_empty() {
+		let mut resources = world.resources().borrow_mut();
+		match resources.get_mut::<Events<LanguageChanged>>() {
+			Some(events) => events.extend(changes),
+			None => {
+				let mut events = Events::new();
+				events.extend(changes);
+				resources.insert(events);
+			}
+		}
+	}
+	Ok(())
+}
+
+/// The messages shown by labels and buttons in a `Ui`. Widgets are measured again by every
+/// `Ui::layout`, so text that grows or shrinks in another language is laid out to fit.
+#[derive(Debug, Default, Clone)]
+pub struct LocalizedWidgets {
+	widgets: HashMap<WidgetId, Localized>,
+}
+
+impl LocalizedWidgets {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn insert(&mut self, widget: WidgetId, localized: Localized) {
+		self.widgets.insert(widget, localized);
+	}
+
+	pub fn get_mut(&mut self, widget: WidgetId) -> Option<&mut Localized> {
+		self.widgets.get_mut(&widget)
+	}
+
+	pub fn remove(&mut self, widget: WidgetId) -> Option<Localized> {
+		self.widgets.remove(&widget)
+	}
+
+	/// Sets each widget's text to its message, forgetting widgets that were despawned
+	pub fn apply(&mut self, ui: &mut Ui, localization: &Localization) {
+		self.widgets.retain(|widget, localized| {
+			let Some(text) = ui.widget_mut(*widget).and_then(|widget| widget.text_mut()) else {
+				return ui.contains(*widget);
+			};
+			let value = localized.text(localization);
+			if text.value != value {
+				text.value = value;
+			}
+			true
+		});
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::bundle::Bundle;
+	use ui::{Style, Widget};
+
+	fn localization() -> std::result::Result<Localization, crate::bundle::LocalizationError> {
+		let mut localization = Localization::new("en");
+		localization.add_bundle(
+			"en",
+			Bundle::parse("score = Score: { $points }\nplay = Play")?,
+		);
+		localization.add_bundle(
+			"de",
+			Bundle::parse("score = Punkte: { $points }\nplay = Spielen")?,
+		);
+		Ok(localization)
+	}
+
+	#[test]
+	fn texts_and_widgets_follow_the_language() -> Result<()> {
+		let mut world = World::new();
+		let entity = world.create_entity();
+		world.add_component(entity, Text::new("", "body"))?;
+		world.add_component(entity, Localized::new("score").with_arg("points", 3))?;
+		world.resources().borrow_mut().insert(localization()?);
+		localize(&mut world)?;
+		assert_eq!(
+			world.get_component::<Text>(entity).unwrap().value,
+			"Score: 3"
+		);
+
+		let mut ui = Ui::new();
+		let button = ui.spawn(ui.root(), Widget::button("", "body"), Style::default())?;
+		let mut widgets = LocalizedWidgets::new();
+		widgets.insert(button, Localized::new("play"));
+
+		world
+			.resources()
+			.borrow_mut()
+			.get_mut::<Localization>()
+			.unwrap()
+			.set_language("de");
+		world
+			.get_component_mut::<Localized>(entity)
+			.unwrap()
+			.set_arg("points", 4);
+		localize(&mut world)?;
+		assert_eq!(
+			world.get_component::<Text>(entity).unwrap().value,
+			"Punkte: 4"
+		);
+		let resources = world.resources().borrow();
+		let localization = resources.get::<Localization>().unwrap();
+		widgets.apply(&mut ui, localization);
+		let label = ui.widget(button).and_then(Widget::text).unwrap();
+		assert_eq!(label.value, "Spielen");
+		assert_eq!(
+			resources
+				.get::<Events<LanguageChanged>>()
+				.unwrap()
+				.iter()
+				.collect::<Vec<_>>(),
+			[&LanguageChanged {
+				previous: "en".to_string(),
+				language: "de".to_string(),
+			}]
+		);
+
+		// Despawned widgets are forgotten
+		ui.despawn(button)?;
+		widgets.apply(&mut ui, localization);
+		assert!(widgets.get_mut(button).is_none());
+		Ok(())
+	}
+}
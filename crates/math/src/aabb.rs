@@ -0,0 +1,79 @@
+use crate::{Mat4, Vec3};
+
+/// An axis aligned bounding box
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Aabb {
+	pub min: Vec3,
+	pub max: Vec3,
+}
+
+impl Aabb {
+	pub fn new(min: Vec3, max: Vec3) -> Self {
+		Self { min, max }
+	}
+
+	pub fn from_center_half_extents(center: Vec3, half_extents: Vec3) -> Self {
+		Self::new(center - half_extents, center + half_extents)
+	}
+
+	/// The smallest box around the points, or none without any
+	pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Self> {
+		let mut points = points.into_iter();
+		let first = points.next()?;
+		Some(points.fold(Self::new(first, first), |aabb, point| {
+			Self::new(aabb.min.min(point), aabb.max.max(point))
+		}))
+	}
+
+	pub fn center(&self) -> Vec3 {
+		(self.min + self.max) / 2.0
+	}
+
+	pub fn half_extents(&self) -> Vec3 {
+		(self.max - self.min) / 2.0
+	}
+
+	pub fn size(&self) -> Vec3 {
+		self.max - self.min
+	}
+
+	/// The radius of a sphere around the box sharing its center
+	pub fn radius(&self) -> f32 {
+		self.half_extents().length()
+	}
+
+	pub fn contains(&self, point: Vec3) -> bool {
+		point.min(self.min) == self.min && point.max(self.max) == self.max
+	}
+
+	pub fn intersects(&self, other: &Self) -> bool {
+		self.min.x <= other.max.x
+			&& self.max.x >= other.min.x
+			&& self.min.y <= other.max.y
+			&& self.max.y >= other.min.y
+			&& self.min.z <= other.max.z
+			&& self.max.z >= other.min.z
+	}
+
+	pub fn union(&self, other: &Self) -> Self {
+		Self::new(self.min.min(other.min), self.max.max(other.max))
+	}
+
+	pub fn corners(&self) -> [Vec3; 8] {
+		let (min, max) = (self.min, self.max);
+		[0, 1, 2, 3, 4, 5, 6, 7].map(|corner| {
+			Vec3::new(
+				if corner & 1 == 0 { min.x } else { max.x },
+				if corner & 2 == 0 { min.y } else { max.y },
+				if corner & 4 == 0 { min.z } else { max.z },
+			)
+		})
+	}
+
+	/// The box around this one after it's moved by the matrix
+	pub fn transformed(&self, matrix: &Mat4) -> Self {
+		Self::from_points(self.corners().map(|corner| matrix.transform_point3(corner)))
+			.unwrap_or_default()
+	}
+}
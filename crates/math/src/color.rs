@@ -0,0 +1,121 @@
+/// A color in linear RGBA, the space lighting and blending are done in
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Color {
+	pub r: f32,
+	pub g: f32,
+	pub b: f32,
+	pub a: f32,
+}
+
+impl Default for Color {
+	fn default() -> Self {
+		Self::WHITE
+	}
+}
+
+impl Color {
+	pub const WHITE: Self = Self::rgb(1.0, 1.0, 1.0);
+	pub const BLACK: Self = Self::rgb(0.0, 0.0, 0.0);
+	pub const RED: Self = Self::rgb(1.0, 0.0, 0.0);
+	pub const GREEN: Self = Self::rgb(0.0, 1.0, 0.0);
+	pub const BLUE: Self = Self::rgb(0.0, 0.0, 1.0);
+	pub const TRANSPARENT: Self = Self::rgba(0.0, 0.0, 0.0, 0.0);
+
+	pub const fn rgb(r: f32, g: f32, b: f32) -> Self {
+		Self::rgba(r, g, b, 1.0)
+	}
+
+	pub const fn rgba(r: f32, g: f32, b: f32, a: f32) -> Self {
+		Self { r, g, b, a }
+	}
+
+	/// From sRGB channels between 0 and 255, as color pickers and image editors show them
+	pub fn srgb_u8(r: u8, g: u8, b: u8, a: u8) -> Self {
+		let channel = |value: u8| srgb_to_linear(value as f32 / 255.0);
+		Self::rgba(channel(r), channel(g), channel(b), a as f32 / 255.0)
+	}
+
+	/// Back to sRGB channels between 0 and 255
+	pub fn to_srgb_u8(self) -> [u8; 4] {
+		let channel = |value: f32| (linear_to_srgb(value).clamp(0.0, 1.0) * 255.0).round() as u8;
+		[
+			channel(self.r),
+			channel(self.g),
+			channel(self.b),
+			(self.a.clamp(0.0, 1.0) * 255.0).round() as u8,
+		]
+	}
+
+	pub fn with_alpha(self, a: f32) -> Self {
+		Self { a, ..self }
+	}
+
+	pub fn is_translucent(&self) -> bool {
+		self.a < 1.0
+	}
+
+	pub fn lerp(self, other: Self, amount: f32) -> Self {
+		let mix = |from: f32, to: f32| from + (to - from) * amount;
+		Self::rgba(
+			mix(self.r, other.r),
+			mix(self.g, other.g),
+			mix(self.b, other.b),
+			mix(self.a, other.a),
+		)
+	}
+
+	/// Every channel multiplied by the scale, leaving the alpha alone, as for light intensity
+	pub fn scaled(self, scale: f32) -> Self {
+		Self::rgba(self.r * scale, self.g * scale, self.b * scale, self.a)
+	}
+
+	pub fn to_array(self) -> [f32; 4] {
+		[self.r, self.g, self.b, self.a]
+	}
+}
+
+impl From<[f32; 4]> for Color {
+	fn from([r, g, b, a]: [f32; 4]) -> Self {
+		Self::rgba(r, g, b, a)
+	}
+}
+
+impl From<[f32; 3]> for Color {
+	fn from([r, g, b]: [f32; 3]) -> Self {
+		Self::rgb(r, g, b)
+	}
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+	if value <= 0.04045 {
+		value / 12.92
+	} else {
+		((value + 0.055) / 1.055).powf(2.4)
+	}
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+	if value <= 0.0031308 {
+		value * 12.92
+	} else {
+		1.055 * value.powf(1.0 / 2.4) - 0.055
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn colors_round_trip_through_srgb() {
+		let color = Color::srgb_u8(255, 128, 0, 255);
+		assert!((color.g - 0.2158).abs() < 1e-3);
+		assert_eq!(color.to_srgb_u8(), [255, 128, 0, 255]);
+		assert_eq!(
+			Color::BLACK.lerp(Color::WHITE, 0.5),
+			Color::rgb(0.5, 0.5, 0.5)
+		);
+		assert!(Color::WHITE.with_alpha(0.5).is_translucent());
+	}
+}
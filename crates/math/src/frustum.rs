@@ -0,0 +1,56 @@
+use crate::{Mat4, Vec3, Vec4};
+
+/// The planes bounding what a view projection matrix can see, facing inwards
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+	planes: [Vec4; 6],
+}
+
+impl Frustum {
+	pub fn new(view_projection: &Mat4) -> Self {
+		let [x, y, z, w] = [0, 1, 2, 3].map(|index| view_projection.row(index));
+		let planes =
+			[w + x, w - x, w + y, w - y, z, w - z].map(|plane| plane / plane.truncate().length());
+		Self { planes }
+	}
+
+	pub fn intersects_sphere(&self, center: Vec3, radius: f32) -> bool {
+		self.planes
+			.iter()
+			.all(|plane| plane.truncate().dot(center) + plane.w >= -radius)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Aabb;
+	use glam::camera::rh::proj::directx::perspective;
+
+	fn close(a: Vec3, b: Vec3) -> bool {
+		a.distance(b) < 1e-4
+	}
+
+	#[test]
+	fn frustums_cull_and_boxes_transform() {
+		let projection = perspective(90_f32.to_radians(), 1.0, 1.0, 100.0);
+		assert!(close(
+			projection.project_point3(Vec3::new(0.0, 0.0, -1.0)),
+			Vec3::ZERO
+		));
+		assert!(close(
+			projection.project_point3(Vec3::new(100.0, 0.0, -100.0)),
+			Vec3::new(1.0, 0.0, 1.0)
+		));
+		let frustum = Frustum::new(&projection);
+		assert!(frustum.intersects_sphere(Vec3::new(0.0, 0.0, -50.0), 1.0));
+		assert!(!frustum.intersects_sphere(Vec3::new(0.0, 0.0, 5.0), 1.0));
+		assert!(!frustum.intersects_sphere(Vec3::new(-80.0, 0.0, -50.0), 1.0));
+
+		let aabb = Aabb::new(Vec3::splat(-1.0), Vec3::ONE);
+		let moved = aabb.transformed(&Mat4::from_translation(Vec3::X * 10.0));
+		assert!(moved.contains(Vec3::new(10.5, 0.0, 0.0)));
+		assert!(!moved.intersects(&aabb));
+		assert_eq!(moved.union(&aabb).size(), Vec3::new(12.0, 2.0, 2.0));
+	}
+}
@@ -0,0 +1,16 @@
+#![forbid(unsafe_code)]
+
+mod aabb;
+mod color;
+mod frustum;
+mod ray;
+mod transform;
+
+pub use self::{
+	aabb::Aabb,
+	color::Color,
+	frustum::Frustum,
+	ray::Ray,
+	transform::{GlobalTransform, Transform},
+};
+pub use glam::{self, Mat3, Mat4, Quat, Vec2, Vec3, Vec4};
@@ -0,0 +1,54 @@
+use crate::{Aabb, Vec3};
+
+/// A half line from an origin, as cast from a camera through a point on screen
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ray {
+	pub origin: Vec3,
+	/// A unit vector
+	pub direction: Vec3,
+}
+
+impl Ray {
+	pub fn new(origin: Vec3, direction: Vec3) -> Self {
+		Self {
+			origin,
+			direction: direction.normalize_or_zero(),
+		}
+	}
+
+	pub fn at(&self, distance: f32) -> Vec3 {
+		self.origin + self.direction * distance
+	}
+
+	/// How far along the ray it meets the plane through the point, if it does
+	pub fn intersect_plane(&self, point: Vec3, normal: Vec3) -> Option<f32> {
+		let facing = self.direction.dot(normal);
+		if facing.abs() < f32::EPSILON {
+			return None;
+		}
+		let distance = (point - self.origin).dot(normal) / facing;
+		(distance >= 0.0).then_some(distance)
+	}
+
+	/// How far along the ray it enters the box, or zero when it starts inside
+	pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+		let (mut near, mut far) = (0.0_f32, f32::INFINITY);
+		for (origin, direction, min, max) in [
+			(self.origin.x, self.direction.x, aabb.min.x, aabb.max.x),
+			(self.origin.y, self.direction.y, aabb.min.y, aabb.max.y),
+			(self.origin.z, self.direction.z, aabb.min.z, aabb.max.z),
+		] {
+			if direction == 0.0 {
+				if origin < min || origin > max {
+					return None;
+				}
+				continue;
+			}
+			let (entry, exit) = ((min - origin) / direction, (max - origin) / direction);
+			near = near.max(entry.min(exit));
+			far = far.min(entry.max(exit));
+		}
+		(near <= far).then_some(near)
+	}
+}
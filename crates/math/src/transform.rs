@@ -0,0 +1,232 @@
+use crate::{Mat3, Mat4, Quat, Vec3};
+use std::ops::Mul;
+
+/// Where an entity is relative to its parent, or to the world without one
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transform {
+	pub translation: Vec3,
+	pub rotation: Quat,
+	pub scale: Vec3,
+}
+
+impl Default for Transform {
+	fn default() -> Self {
+		Self::IDENTITY
+	}
+}
+
+impl Transform {
+	pub const IDENTITY: Self = Self {
+		translation: Vec3::ZERO,
+		rotation: Quat::IDENTITY,
+		scale: Vec3::ONE,
+	};
+
+	pub fn from_xyz(x: f32, y: f32, z: f32) -> Self {
+		Self::from_translation(Vec3::new(x, y, z))
+	}
+
+	pub fn from_translation(translation: Vec3) -> Self {
+		Self {
+			translation,
+			..Self::IDENTITY
+		}
+	}
+
+	pub fn from_rotation(rotation: Quat) -> Self {
+		Self {
+			rotation,
+			..Self::IDENTITY
+		}
+	}
+
+	pub fn from_scale(scale: Vec3) -> Self {
+		Self {
+			scale,
+			..Self::IDENTITY
+		}
+	}
+
+	pub fn with_rotation(self, rotation: Quat) -> Self {
+		Self { rotation, ..self }
+	}
+
+	pub fn with_scale(self, scale: Vec3) -> Self {
+		Self { scale, ..self }
+	}
+
+	/// Turns it to face the target, the way cameras look down their negative z axis
+	pub fn looking_at(self, target: Vec3, up: Vec3) -> Self {
+		let forward = (target - self.translation).normalize_or_zero();
+		let mut right = forward.cross(up);
+		if right.length_squared() < 1e-6 {
+			right = forward.cross(Vec3::Z);
+		}
+		let right = right.normalize_or_zero();
+		let axes = Mat3::from_cols(right, right.cross(forward), -forward);
+		self.with_rotation(Quat::from_mat3(&axes))
+	}
+
+	/// Turns it further by the rotation
+	pub fn rotate(&mut self, rotation: Quat) {
+		self.rotation = (rotation * self.rotation).normalize();
+	}
+
+	/// The direction its negative z axis points
+	pub fn forward(&self) -> Vec3 {
+		-(self.rotation * Vec3::Z)
+	}
+
+	pub fn right(&self) -> Vec3 {
+		self.rotation * Vec3::X
+	}
+
+	pub fn up(&self) -> Vec3 {
+		self.rotation * Vec3::Y
+	}
+
+	/// Its counterclockwise turn around the z axis in radians, as 2D scenes use
+	pub fn angle_z(&self) -> f32 {
+		let x_axis = self.right();
+		x_axis.y.atan2(x_axis.x)
+	}
+
+	pub fn matrix(&self) -> Mat4 {
+		Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+	}
+
+	/// Moves a point relative to this transform into its parent space
+	pub fn transform_point(&self, point: Vec3) -> Vec3 {
+		self.translation + self.rotation * (point * self.scale)
+	}
+
+	/// Moves a point in the parent space to be relative to this transform,
+	/// leaving axes with no scale alone
+	pub fn inverse_transform_point(&self, point: Vec3) -> Vec3 {
+		let local = self.rotation.inverse() * (point - self.translation);
+		Vec3::select(self.scale.cmpeq(Vec3::ZERO), local, local / self.scale)
+	}
+
+	/// Places a child's transform, which is relative to this one, in this one's parent space.
+	/// Scaling unevenly and then rotating can't be kept exactly, which `GlobalTransform` can.
+	pub fn mul_transform(&self, child: &Self) -> Self {
+		Self {
+			translation: self.transform_point(child.translation),
+			rotation: (self.rotation * child.rotation).normalize(),
+			scale: self.scale * child.scale,
+		}
+	}
+}
+
+impl Mul for Transform {
+	type Output = Self;
+
+	fn mul(self, child: Self) -> Self {
+		self.mul_transform(&child)
+	}
+}
+
+/// Where an entity is relative to the world, composed from its `Transform` and those of
+/// its parents. Kept up to date by `ecs::transform::propagate_transforms`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GlobalTransform(pub Mat4);
+
+impl From<Transform> for GlobalTransform {
+	fn from(transform: Transform) -> Self {
+		Self(transform.matrix())
+	}
+}
+
+impl GlobalTransform {
+	pub fn matrix(&self) -> Mat4 {
+		self.0
+	}
+
+	pub fn translation(&self) -> Vec3 {
+		self.0.w_axis.truncate()
+	}
+
+	/// The direction its negative z axis points
+	pub fn forward(&self) -> Vec3 {
+		-self.0.z_axis.truncate().normalize_or_zero()
+	}
+
+	/// How far it stretches along its longest axis
+	pub fn max_scale(&self) -> f32 {
+		(0..3)
+			.map(|axis| self.0.col(axis).truncate().length())
+			.fold(0.0, f32::max)
+	}
+
+	pub fn transform_point(&self, point: Vec3) -> Vec3 {
+		self.0.transform_point3(point)
+	}
+
+	/// Places a child's transform, which is relative to this one, in the world
+	pub fn mul_transform(&self, child: &Transform) -> Self {
+		Self(self.0 * child.matrix())
+	}
+
+	/// The matrix taking world positions to be relative to this transform, as a camera's view does
+	/// or the identity when it flattens space and has none
+	pub fn inverse(&self) -> Mat4 {
+		if self.0.determinant().abs() < f32::EPSILON {
+			return Mat4::IDENTITY;
+		}
+		self.0.inverse()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn close(a: Vec3, b: Vec3) -> bool {
+		a.distance(b) < 1e-4
+	}
+
+	#[test]
+	fn transforms_compose_through_parents() {
+		let parent = Transform::from_xyz(10.0, 0.0, 0.0)
+			.with_rotation(Quat::from_rotation_z(90_f32.to_radians()))
+			.with_scale(Vec3::splat(2.0));
+		let child = Transform::from_xyz(1.0, 0.0, 0.0);
+		// Turned to point up and doubled
+		assert!(close(
+			parent.mul_transform(&child).translation,
+			Vec3::new(10.0, 2.0, 0.0)
+		));
+		let global = GlobalTransform::from(parent).mul_transform(&child);
+		assert!(close(global.translation(), Vec3::new(10.0, 2.0, 0.0)));
+		assert!((parent.angle_z() - 90_f32.to_radians()).abs() < 1e-5);
+		assert!((global.max_scale() - 2.0).abs() < 1e-5);
+
+		let point = Vec3::new(1.0, 2.0, 3.0);
+		assert!(close(
+			parent.inverse_transform_point(parent.transform_point(point)),
+			point
+		));
+		let flat = parent.with_scale(Vec3::new(2.0, 2.0, 0.0));
+		assert!(close(
+			flat.inverse_transform_point(Vec3::new(10.0, 2.0, 3.0)),
+			Vec3::new(1.0, 0.0, 3.0)
+		));
+
+		let world_point = Vec3::new(3.0, -4.0, 5.0);
+		assert!(close(
+			global
+				.inverse()
+				.transform_point3(global.transform_point(world_point)),
+			world_point
+		));
+
+		let camera = Transform::from_xyz(3.0, 4.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y);
+		assert!(close(
+			camera.forward(),
+			Vec3::new(-3.0, -4.0, -5.0).normalize()
+		));
+		assert!(camera.up().y > 0.0);
+	}
+}
@@ -0,0 +1,185 @@
+use crate::path::Pathfinder;
+use ecs::{error::Result, world::World};
+use math::{Transform, Vec3};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NavStatus {
+	/// Without a destination
+	#[default]
+	Idle,
+	/// Following a path to its destination
+	Moving,
+	/// Reached its destination
+	Arrived,
+	/// No path was found to its destination
+	Unreachable,
+}
+
+/// Moves the entity's transform along a path to its destination, found by `follow_paths`
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavAgent {
+	/// In units per second
+	pub speed: f32,
+	/// How close to a point on its path the agent has to get to have reached it
+	pub arrival_distance: f32,
+	destination: Option<Vec3>,
+	path: Vec<Vec3>,
+	status: NavStatus,
+}
+
+impl NavAgent {
+	pub fn new(speed: f32) -> Self {
+		Self {
+			speed,
+			arrival_distance: 0.01,
+			destination: None,
+			path: Vec::new(),
+			status: NavStatus::Idle,
+		}
+	}
+
+	/// Sends the agent somewhere, finding its path the next time `follow_paths` runs
+	pub fn set_destination(&mut self, destination: Vec3) {
+		self.destination = Some(destination);
+		self.path.clear();
+		self.status = NavStatus::Moving;
+	}
+
+	/// Stops where the agent is and forgets its destination
+	pub fn stop(&mut self) {
+		self.destination = None;
+		self.path.clear();
+		self.status = NavStatus::Idle;
+	}
+
+	pub fn destination(&self) -> Option<Vec3> {
+		self.destination
+	}
+
+	/// The points left to walk through, the destination last
+	pub fn path(&self) -> &[Vec3] {
+		&self.path
+	}
+
+	pub fn status(&self) -> NavStatus {
+		self.status
+	}
+}
+
+/// Finds paths for agents sent somewhere new with the `P` resource, then moves every
+/// moving agent along its path by how far it goes in `delta` seconds
+pub fn follow_paths<P: Pathfinder + 'static>(world: &mut World, delta: f32) -> Result<()> {
+	let agents = world
+		.entities()
+		.into_iter()
+		.filter(|entity| world.get_component::<NavAgent>(*entity).is_some())
+		.collect::<Vec<_>>();
+	let resources = world.resources();
+	let resources = resources.borrow();
+	let pathfinder = resources.get::<P>();
+
+	for entity in agents {
+		let (Some(mut agent), Some(mut transform)) = (
+			world.get_component_mut::<NavAgent>(entity),
+			world.get_component_mut::<Transform>(entity),
+		) else {
+			continue;
+		};
+		if agent.status != NavStatus::Moving {
+			continue;
+		}
+		let Some(destination) = agent.destination else {
+			continue;
+		};
+		if agent.path.is_empty() {
+			let Some(pathfinder) = pathfinder else {
+				continue;
+			};
+			match pathfinder.find_path(transform.translation, destination) {
+				// Already at the start of the path
+				Some(path) => agent.path = path.into_iter().skip(1).collect(),
+				None => {
+					agent.status = NavStatus::Unreachable;
+					continue;
+				}
+			}
+		}
+
+		let mut travel = agent.speed * delta;
+		while let Some(next) = agent.path.first().copied() {
+			let offset = next - transform.translation;
+			let distance = offset.length();
+			if distance > travel.max(agent.arrival_distance) {
+				transform.translation += offset / distance * travel;
+				break;
+			}
+			transform.translation = next;
+			travel -= distance;
+			agent.path.remove(0);
+		}
+		if agent.path.is_empty() {
+			agent.status = NavStatus::Arrived;
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::grid::{GridPlane, NavGridBuilder};
+
+	#[test]
+	fn agents_walk_their_paths_to_their_destinations() -> Result<()> {
+		let mut world = World::new();
+		let grid = NavGridBuilder::new(4, 4, 1.0)
+			.with_plane(GridPlane::XY)
+			.block_area((1, 0), (1, 2))
+			.build();
+		world.resources().borrow_mut().insert(grid);
+
+		let agent = world.create_entity();
+		world.add_component(agent, NavAgent::new(2.0))?;
+		world.add_component(agent, Transform::from_xyz(0.5, 0.5, 0.0))?;
+		let stuck = world.create_entity();
+		world.add_component(stuck, NavAgent::new(2.0))?;
+		world.add_component(stuck, Transform::from_xyz(0.5, 0.5, 0.0))?;
+
+		let destination = Vec3::new(2.5, 0.5, 0.0);
+		world
+			.get_component_mut::<NavAgent>(agent)
+			.unwrap()
+			.set_destination(destination);
+		world
+			.get_component_mut::<NavAgent>(stuck)
+			.unwrap()
+			.set_destination(Vec3::new(1.5, 0.5, 0.0));
+
+		follow_paths::<crate::grid::NavGrid>(&mut world, 0.5)?;
+		assert_eq!(
+			world.get_component::<NavAgent>(stuck).unwrap().status(),
+			NavStatus::Unreachable
+		);
+		{
+			let walker = world.get_component::<NavAgent>(agent).unwrap();
+			assert_eq!(walker.status(), NavStatus::Moving);
+			assert_eq!(walker.path().last(), Some(&destination));
+		}
+		// A step of one unit around the wall, which is in the way of a straight line
+		let position = world.get_component::<Transform>(agent).unwrap().translation;
+		assert!((position.distance(Vec3::new(0.5, 0.5, 0.0)) - 1.0).abs() < 1e-4);
+
+		for _ in 0..10 {
+			follow_paths::<crate::grid::NavGrid>(&mut world, 0.5)?;
+		}
+		assert_eq!(
+			world.get_component::<NavAgent>(agent).unwrap().status(),
+			NavStatus::Arrived
+		);
+		assert_eq!(
+			world.get_component::<Transform>(agent).unwrap().translation,
+			destination
+		);
+		Ok(())
+	}
+}
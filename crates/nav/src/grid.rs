@@ -0,0 +1,325 @@
+use crate::path::{find_node_path, NavGraph, Pathfinder};
+use graph::NodeId;
+use math::Vec3;
+use std::f32::consts::SQRT_2;
+
+/// Which plane a grid's columns and rows lie on
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GridPlane {
+	/// Columns along x and rows along y, for 2D scenes
+	XY,
+	/// Columns along x and rows along z, for the ground of 3D scenes
+	#[default]
+	XZ,
+}
+
+impl GridPlane {
+	fn axes(&self) -> (Vec3, Vec3) {
+		match self {
+			Self::XY => (Vec3::X, Vec3::Y),
+			Self::XZ => (Vec3::X, Vec3::Z),
+		}
+	}
+}
+
+/// Builds a `NavGrid` from a grid of cells, every one walkable unless blocked
+#[derive(Debug, Clone)]
+pub struct NavGridBuilder {
+	columns: usize,
+	rows: usize,
+	cell_size: f32,
+	origin: Vec3,
+	plane: GridPlane,
+	diagonals: bool,
+	walkable: Vec<bool>,
+}
+
+impl NavGridBuilder {
+	pub fn new(columns: usize, rows: usize, cell_size: f32) -> Self {
+		Self {
+			columns,
+			rows,
+			cell_size,
+			origin: Vec3::ZERO,
+			plane: GridPlane::default(),
+			diagonals: true,
+			walkable: vec![true; columns * rows],
+		}
+	}
+
+	/// Where the corner of the first cell is
+	pub fn with_origin(self, origin: Vec3) -> Self {
+		Self { origin, ..self }
+	}
+
+	pub fn with_plane(self, plane: GridPlane) -> Self {
+		Self { plane, ..self }
+	}
+
+	/// Whether agents can move diagonally between cells, which they never do past
+	/// the corner of a blocked cell
+	pub fn with_diagonals(self, diagonals: bool) -> Self {
+		Self { diagonals, ..self }
+	}
+
+	pub fn block(mut self, column: usize, row: usize) -> Self {
+		if column < self.columns && row < self.rows {
+			self.walkable[row * self.columns + column] = false;
+		}
+		self
+	}
+
+	/// Blocks every cell from the first to the last, inclusive
+	pub fn block_area(mut self, first: (usize, usize), last: (usize, usize)) -> Self {
+		for row in first.1..=last.1 {
+			for column in first.0..=last.0 {
+				self = self.block(column, row);
+			}
+		}
+		self
+	}
+
+	pub fn build(self) -> NavGrid {
+		let mut grid = NavGrid {
+			columns: self.columns,
+			rows: self.rows,
+			cell_size: self.cell_size,
+			origin: self.origin,
+			plane: self.plane,
+			walkable: self.walkable,
+			graph: NavGraph::new(),
+			nodes: Vec::new(),
+		};
+		grid.nodes = (0..grid.columns * grid.rows)
+			.map(|index| {
+				let cell = (index % grid.columns, index / grid.columns);
+				grid.walkable[index].then(|| grid.graph.add_node(grid.cell_center(cell)))
+			})
+			.collect();
+
+		let mut steps = vec![((1, 0), 1.0), ((0, 1), 1.0), ((-1, 0), 1.0), ((0, -1), 1.0)];
+		if self.diagonals {
+			steps.extend([
+				((1, 1), SQRT_2),
+				((1, -1), SQRT_2),
+				((-1, 1), SQRT_2),
+				((-1, -1), SQRT_2),
+			]);
+		}
+		for row in 0..grid.rows {
+			for column in 0..grid.columns {
+				let Some(node) = grid.node((column as isize, row as isize)) else {
+					continue;
+				};
+				for ((x, y), cost) in &steps {
+					let (column, row) = (column as isize, row as isize);
+					let Some(neighbor) = grid.node((column + x, row + y)) else {
+						continue;
+					};
+					// No cutting corners, which would clip the blocked cell
+					if grid.node((column + x, row)).is_none()
+						|| grid.node((column, row + y)).is_none()
+					{
+						continue;
+					}
+					let _ = grid.graph.add_edge(node, neighbor, cost * grid.cell_size);
+				}
+			}
+		}
+		grid
+	}
+}
+
+/// A grid of walkable and blocked cells with a node for each walkable one,
+/// linked to its walkable neighbors. Paths are found between cells, then
+/// straightened wherever there's a clear line between their points.
+pub struct NavGrid {
+	columns: usize,
+	rows: usize,
+	cell_size: f32,
+	origin: Vec3,
+	plane: GridPlane,
+	walkable: Vec<bool>,
+	graph: NavGraph,
+	nodes: Vec<Option<NodeId>>,
+}
+
+impl NavGrid {
+	pub fn graph(&self) -> &NavGraph {
+		&self.graph
+	}
+
+	pub fn columns(&self) -> usize {
+		self.columns
+	}
+
+	pub fn rows(&self) -> usize {
+		self.rows
+	}
+
+	pub fn is_walkable(&self, column: usize, row: usize) -> bool {
+		self.node((column as isize, row as isize)).is_some()
+	}
+
+	/// The column and row of the cell the point is over
+	pub fn cell_at(&self, point: Vec3) -> Option<(usize, usize)> {
+		let (column, row) = self.grid_position(point);
+		let (column, row) = (column.floor(), row.floor());
+		(column >= 0.0
+			&& row >= 0.0
+			&& (column as usize) < self.columns
+			&& (row as usize) < self.rows)
+			.then_some((column as usize, row as usize))
+	}
+
+	pub fn cell_center(&self, (column, row): (usize, usize)) -> Vec3 {
+		let (x_axis, y_axis) = self.plane.axes();
+		self.origin
+			+ x_axis * ((column as f32 + 0.5) * self.cell_size)
+			+ y_axis * ((row as f32 + 0.5) * self.cell_size)
+	}
+
+	/// The node of the cell, when it's walkable
+	pub fn node(&self, (column, row): (isize, isize)) -> Option<NodeId> {
+		if column < 0 || row < 0 || column as usize >= self.columns || row as usize >= self.rows {
+			return None;
+		}
+		self.nodes[row as usize * self.columns + column as usize]
+	}
+
+	// The point in cells from the origin, along the columns and rows
+	fn grid_position(&self, point: Vec3) -> (f32, f32) {
+		let (x_axis, y_axis) = self.plane.axes();
+		let offset = (point - self.origin) / self.cell_size;
+		(offset.dot(x_axis), offset.dot(y_axis))
+	}
+
+	/// Whether every cell the line between the points crosses is walkable
+	pub fn line_of_sight(&self, from: Vec3, to: Vec3) -> bool {
+		let (start, end) = (self.grid_position(from), self.grid_position(to));
+		let walkable = |column: f32, row: f32| {
+			column >= 0.0 && row >= 0.0 && self.node((column as isize, row as isize)).is_some()
+		};
+		let (mut column, mut row) = (start.0.floor(), start.1.floor());
+		let (end_column, end_row) = (end.0.floor(), end.1.floor());
+		let (delta_x, delta_y) = (end.0 - start.0, end.1 - start.1);
+		let (step_x, step_y) = (delta_x.signum(), delta_y.signum());
+		// How far along the line each next column or row boundary is, and the distance between them
+		let boundary = |position: f32, cell: f32, step: f32, delta: f32| {
+			if delta == 0.0 {
+				return (f32::INFINITY, f32::INFINITY);
+			}
+			let next = if step > 0.0 { cell + 1.0 } else { cell };
+			((next - position) / delta, (1.0 / delta).abs())
+		};
+		let (mut next_x, span_x) = boundary(start.0, column, step_x, delta_x);
+		let (mut next_y, span_y) = boundary(start.1, row, step_y, delta_y);
+
+		if !walkable(column, row) {
+			return false;
+		}
+		while column != end_column || row != end_row {
+			if next_x.min(next_y) > 1.0 {
+				break;
+			}
+			if (next_x - next_y).abs() < f32::EPSILON {
+				// Passing exactly through a corner touches both cells beside it
+				if !walkable(column + step_x, row) || !walkable(column, row + step_y) {
+					return false;
+				}
+				column += step_x;
+				row += step_y;
+				next_x += span_x;
+				next_y += span_y;
+			} else if next_x < next_y {
+				column += step_x;
+				next_x += span_x;
+			} else {
+				row += step_y;
+				next_y += span_y;
+			}
+			if !walkable(column, row) {
+				return false;
+			}
+		}
+		true
+	}
+
+	// Drops every point that can be skipped with a straight line past it
+	fn smooth(&self, points: Vec<Vec3>) -> Vec<Vec3> {
+		let Some(first) = points.first() else {
+			return points;
+		};
+		let mut smoothed = vec![*first];
+		let mut anchor = 0;
+		while anchor + 1 < points.len() {
+			let farthest = (anchor + 1..points.len())
+				.rev()
+				.find(|index| self.line_of_sight(points[anchor], points[*index]))
+				.unwrap_or(anchor + 1);
+			smoothed.push(points[farthest]);
+			anchor = farthest;
+		}
+		smoothed
+	}
+}
+
+impl Pathfinder for NavGrid {
+	fn find_path(&self, from: Vec3, to: Vec3) -> Option<Vec<Vec3>> {
+		let start = self.cell_at(from)?;
+		let goal = self.cell_at(to)?;
+		let cell_node = |(column, row): (usize, usize)| self.node((column as isize, row as isize));
+		let nodes = find_node_path(&self.graph, cell_node(start)?, cell_node(goal)?).ok()??;
+		let mut points = nodes
+			.iter()
+			.filter_map(|node| self.graph.get_node(*node).map(|node| node.data))
+			.collect::<Vec<_>>();
+		// The ends are the points themselves rather than the middle of their cells
+		if let Some(first) = points.first_mut() {
+			*first = from;
+		}
+		points.push(to);
+		if points.len() > 2 {
+			points.remove(points.len() - 2);
+		}
+		Some(self.smooth(points))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn paths_go_around_walls_and_are_straightened() {
+		// A wall down the middle with a gap at the top
+		let grid = NavGridBuilder::new(5, 5, 2.0)
+			.with_plane(GridPlane::XY)
+			.block_area((2, 0), (2, 3))
+			.build();
+		assert!(!grid.is_walkable(2, 1));
+		assert_eq!(grid.cell_at(Vec3::new(5.0, 9.0, 0.0)), Some((2, 4)));
+		assert_eq!(grid.cell_at(Vec3::new(-1.0, 0.0, 0.0)), None);
+
+		let from = Vec3::new(1.0, 1.0, 0.0);
+		let to = Vec3::new(9.0, 1.0, 0.0);
+		assert!(!grid.line_of_sight(from, to));
+		let path = grid.find_path(from, to).unwrap();
+		assert_eq!(path.first(), Some(&from));
+		assert_eq!(path.last(), Some(&to));
+		// Every leg is clear, and the path climbs through the gap in as few legs as it can
+		assert!(path
+			.windows(2)
+			.all(|leg| grid.line_of_sight(leg[0], leg[1])));
+		assert!(path.iter().any(|point| point.y > 8.0));
+		assert!(path.len() <= 4, "{path:?}");
+
+		// Points in blocked cells, or cut off from each other, have no path
+		assert_eq!(grid.find_path(from, Vec3::new(5.0, 1.0, 0.0)), None);
+		let sealed = NavGridBuilder::new(3, 1, 1.0).block(1, 0).build();
+		assert_eq!(
+			sealed.find_path(Vec3::new(0.5, 0.0, 0.5), Vec3::new(2.5, 0.0, 0.5)),
+			None
+		);
+	}
+}
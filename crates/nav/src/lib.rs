@@ -0,0 +1,13 @@
+#![forbid(unsafe_code)]
+
+mod agent;
+mod grid;
+mod mesh;
+mod path;
+
+pub use self::{
+	agent::{follow_paths, NavAgent, NavStatus},
+	grid::{GridPlane, NavGrid, NavGridBuilder},
+	mesh::{NavMesh, NavMeshError},
+	path::{find_node_path, NavGraph, Pathfinder},
+};
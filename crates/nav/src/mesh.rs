@@ -0,0 +1,261 @@
+use crate::path::{find_node_path, NavGraph, Pathfinder};
+use graph::NodeId;
+use math::{Vec2, Vec3};
+use std::{collections::HashMap, fmt};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NavMeshError {
+	/// A triangle has a corner past the end of the vertices
+	VertexOutOfRange { triangle: usize, vertex: usize },
+}
+
+impl std::error::Error for NavMeshError {}
+
+impl fmt::Display for NavMeshError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::VertexOutOfRange { triangle, vertex } => {
+				write!(
+					f,
+					"Triangle {triangle} uses vertex {vertex}, which does not exist"
+				)
+			}
+		}
+	}
+}
+
+// Seen from above, on the ground
+fn flat(point: Vec3) -> Vec2 {
+	Vec2::new(point.x, point.z)
+}
+
+fn cross(a: Vec2, b: Vec2) -> f32 {
+	a.x * b.y - a.y * b.x
+}
+
+/// Walkable ground made of triangles, as exported from a level editor, with a node for
+/// each triangle linked to those it shares an edge with. Points are placed on the mesh
+/// from above, and paths are pulled tight through the edges they cross.
+pub struct NavMesh {
+	vertices: Vec<Vec3>,
+	triangles: Vec<[usize; 3]>,
+	graph: NavGraph,
+	// The edge shared by each pair of neighboring triangles
+	portals: HashMap<(NodeId, NodeId), (Vec3, Vec3)>,
+}
+
+impl NavMesh {
+	pub fn from_triangles(
+		vertices: Vec<Vec3>,
+		triangles: Vec<[usize; 3]>,
+	) -> Result<Self, NavMeshError> {
+		let mut graph = NavGraph::new();
+		for (index, triangle) in triangles.iter().enumerate() {
+			if let Some(vertex) = triangle.iter().find(|vertex| **vertex >= vertices.len()) {
+				return Err(NavMeshError::VertexOutOfRange {
+					triangle: index,
+					vertex: *vertex,
+				});
+			}
+			let center = triangle
+				.iter()
+				.fold(Vec3::ZERO, |sum, vertex| sum + vertices[*vertex])
+				/ 3.0;
+			graph.add_node(center);
+		}
+
+		// Triangles sharing an edge, found by the edge's vertices lowest first
+		let mut edges = HashMap::<(usize, usize), Vec<usize>>::new();
+		for (index, triangle) in triangles.iter().enumerate() {
+			for corner in 0..3 {
+				let (a, b) = (triangle[corner], triangle[(corner + 1) % 3]);
+				edges.entry((a.min(b), a.max(b))).or_default().push(index);
+			}
+		}
+		let mut portals = HashMap::new();
+		for ((a, b), sharing) in edges {
+			for (first, second) in sharing.iter().zip(sharing.iter().skip(1)) {
+				let centers = (graph.get_node(*first), graph.get_node(*second));
+				let (Some(first_node), Some(second_node)) = centers else {
+					continue;
+				};
+				let cost = first_node.data.distance(second_node.data);
+				for (from, to) in [(*first, *second), (*second, *first)] {
+					if graph.add_edge(from, to, cost).is_ok() {
+						portals.insert((from, to), (vertices[a], vertices[b]));
+					}
+				}
+			}
+		}
+		Ok(Self {
+			vertices,
+			triangles,
+			graph,
+			portals,
+		})
+	}
+
+	pub fn graph(&self) -> &NavGraph {
+		&self.graph
+	}
+
+	pub fn vertices(&self) -> &[Vec3] {
+		&self.vertices
+	}
+
+	pub fn triangles(&self) -> &[[usize; 3]] {
+		&self.triangles
+	}
+
+	/// The node of the triangle under or over the point
+	pub fn triangle_at(&self, point: Vec3) -> Option<NodeId> {
+		let point = flat(point);
+		self.triangles.iter().position(|triangle| {
+			let [a, b, c] = triangle.map(|vertex| flat(self.vertices[vertex]));
+			let sides = [
+				cross(b - a, point - a),
+				cross(c - b, point - b),
+				cross(a - c, point - c),
+			];
+			// Inside, or on an edge, whichever way the triangle winds
+			sides.iter().all(|side| *side >= 0.0) || sides.iter().all(|side| *side <= 0.0)
+		})
+	}
+
+	// The shortest line through the edges crossed by the triangles, bending only at
+	// their corners, with the simple stupid funnel algorithm
+	fn pull(&self, from: Vec3, to: Vec3, nodes: &[NodeId]) -> Vec<Vec3> {
+		// Each edge as its left and right ends going from the one triangle into the next
+		let mut portals = vec![(from, from)];
+		for pair in nodes.windows(2) {
+			let Some((a, b)) = self.portals.get(&(pair[0], pair[1])).copied() else {
+				continue;
+			};
+			// Looking across the edge from the corner opposite it, the ends are on either side
+			let opposite = self.triangles[pair[0]]
+				.map(|vertex| self.vertices[vertex])
+				.into_iter()
+				.find(|corner| *corner != a && *corner != b)
+				.unwrap_or(from);
+			let direction = flat((a + b) / 2.0) - flat(opposite);
+			if cross(direction, flat(a) - flat(opposite)) > 0.0 {
+				portals.push((a, b));
+			} else {
+				portals.push((b, a));
+			}
+		}
+		portals.push((to, to));
+
+		let turn = |apex: Vec3, from: Vec3, to: Vec3| {
+			cross(flat(from) - flat(apex), flat(to) - flat(apex))
+		};
+		let mut path = vec![from];
+		let (mut apex, mut left, mut right) = (from, from, from);
+		let (mut left_index, mut right_index) = (0, 0);
+		let mut index = 1;
+		while index < portals.len() {
+			let (next_left, next_right) = portals[index];
+			// Narrow the funnel from the right, unless that crosses its left side
+			if turn(apex, right, next_right) >= 0.0 {
+				if apex == right || turn(apex, left, next_right) < 0.0 {
+					right = next_right;
+					right_index = index;
+				} else {
+					path.push(left);
+					apex = left;
+					right = apex;
+					right_index = left_index;
+					index = left_index + 1;
+					continue;
+				}
+			}
+			if turn(apex, left, next_left) <= 0.0 {
+				if apex == left || turn(apex, right, next_left) > 0.0 {
+					left = next_left;
+					left_index = index;
+				} else {
+					path.push(right);
+					apex = right;
+					left = apex;
+					left_index = right_index;
+					index = right_index + 1;
+					continue;
+				}
+			}
+			index += 1;
+		}
+		if path.last() != Some(&to) {
+			path.push(to);
+		}
+		path
+	}
+}
+
+impl Pathfinder for NavMesh {
+	fn find_path(&self, from: Vec3, to: Vec3) -> Option<Vec<Vec3>> {
+		let start = self.triangle_at(from)?;
+		let goal = self.triangle_at(to)?;
+		let nodes = find_node_path(&self.graph, start, goal).ok()??;
+		Some(self.pull(from, to, &nodes))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn paths_bend_around_the_inside_corner_of_an_l() -> Result<(), NavMeshError> {
+		// An L shape of three squares, each split into two triangles, with the corner at (1, 1)
+		//
+		//  z 2 +---+
+		//      |   |
+		//    1 +---+---+
+		//      |   |   |
+		//    0 +---+---+
+		//      0   1   2 x
+		let vertices = [
+			(0.0, 0.0),
+			(1.0, 0.0),
+			(2.0, 0.0),
+			(0.0, 1.0),
+			(1.0, 1.0),
+			(2.0, 1.0),
+			(0.0, 2.0),
+			(1.0, 2.0),
+		]
+		.map(|(x, z)| Vec3::new(x, 0.0, z))
+		.to_vec();
+		let triangles = vec![
+			[0, 1, 4],
+			[0, 4, 3],
+			[1, 2, 5],
+			[1, 5, 4],
+			[3, 4, 7],
+			[3, 7, 6],
+		];
+		let mesh = NavMesh::from_triangles(vertices.clone(), triangles)?;
+		assert_eq!(mesh.triangle_at(Vec3::new(0.9, 5.0, 0.1)), Some(0));
+		assert_eq!(mesh.triangle_at(Vec3::new(1.5, 0.0, 1.5)), None);
+
+		let from = Vec3::new(1.8, 0.0, 0.5);
+		let to = Vec3::new(0.5, 0.0, 1.8);
+		assert_eq!(
+			mesh.find_path(from, to),
+			Some(vec![from, Vec3::new(1.0, 0.0, 1.0), to])
+		);
+		// Within sight, the path is a straight line
+		let near = Vec3::new(0.2, 0.0, 0.2);
+		assert_eq!(mesh.find_path(from, near), Some(vec![from, near]));
+		assert_eq!(mesh.find_path(from, Vec3::new(3.0, 0.0, 3.0)), None);
+
+		assert_eq!(
+			NavMesh::from_triangles(vertices, vec![[0, 1, 8]]).err(),
+			Some(NavMeshError::VertexOutOfRange {
+				triangle: 0,
+				vertex: 8
+			})
+		);
+		Ok(())
+	}
+}
@@ -0,0 +1,125 @@
+use graph::{Graph, GraphError, NodeId};
+use math::Vec3;
+use std::{
+	cmp::Ordering,
+	collections::{BinaryHeap, HashMap},
+};
+
+/// Places to walk between, with each node's position and the cost of each edge,
+/// usually the distance it spans
+pub type NavGraph = Graph<Vec3, f32>;
+
+/// Something that can find a way between two points, kept as a world resource for
+/// `follow_paths` to send agents along
+pub trait Pathfinder {
+	/// The points to walk through from one point to the other, starting at `from`
+	/// and ending at `to`, or none when either is off the walkable area or can't be reached
+	fn find_path(&self, from: Vec3, to: Vec3) -> Option<Vec<Vec3>>;
+}
+
+// A node waiting to be visited, ordered so the heap pops the lowest estimated cost first
+#[derive(PartialEq)]
+struct Open {
+	estimate: f32,
+	node: NodeId,
+}
+
+impl Eq for Open {}
+
+impl Ord for Open {
+	fn cmp(&self, other: &Self) -> Ordering {
+		other.estimate.total_cmp(&self.estimate)
+	}
+}
+
+impl PartialOrd for Open {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+/// The cheapest nodes to go through from start to goal with A*, guided by the
+/// straight line distance to the goal
+pub fn find_node_path(
+	graph: &NavGraph,
+	start: NodeId,
+	goal: NodeId,
+) -> Result<Option<Vec<NodeId>>, GraphError> {
+	let position = |id| {
+		graph
+			.get_node(id)
+			.map(|node| node.data)
+			.ok_or(GraphError::NodeDoesNotExist(id))
+	};
+	let target = position(goal)?;
+	let mut costs = HashMap::from([(start, 0.0)]);
+	let mut came_from = HashMap::new();
+	let mut open = BinaryHeap::from([Open {
+		estimate: position(start)?.distance(target),
+		node: start,
+	}]);
+
+	while let Some(Open { node, .. }) = open.pop() {
+		if node == goal {
+			let mut path = vec![goal];
+			while let Some(previous) = came_from.get(path.last().unwrap_or(&goal)) {
+				path.push(*previous);
+			}
+			path.reverse();
+			return Ok(Some(path));
+		}
+		let cost = costs[&node];
+		for (neighbor, edge) in graph.neighbors(node)? {
+			let next = cost + edge;
+			if costs.get(neighbor).is_some_and(|known| *known <= next) {
+				continue;
+			}
+			costs.insert(*neighbor, next);
+			came_from.insert(*neighbor, node);
+			open.push(Open {
+				estimate: next + position(*neighbor)?.distance(target),
+				node: *neighbor,
+			});
+		}
+	}
+	Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn the_cheapest_path_wins_over_the_shortest() -> Result<(), GraphError> {
+		let mut graph = NavGraph::new();
+		let start = graph.add_node(Vec3::ZERO);
+		let swamp = graph.add_node(Vec3::new(1.0, 0.0, 0.0));
+		let road = [
+			graph.add_node(Vec3::new(0.0, 1.0, 0.0)),
+			graph.add_node(Vec3::new(2.0, 1.0, 0.0)),
+		];
+		let goal = graph.add_node(Vec3::new(2.0, 0.0, 0.0));
+		let island = graph.add_node(Vec3::new(9.0, 9.0, 0.0));
+		let mut connect = |a, b, cost| -> Result<(), GraphError> {
+			graph.add_edge(a, b, cost)?;
+			graph.add_edge(b, a, cost)
+		};
+		connect(start, swamp, 10.0)?;
+		connect(swamp, goal, 10.0)?;
+		connect(start, road[0], 1.0)?;
+		connect(road[0], road[1], 2.0)?;
+		connect(road[1], goal, 1.0)?;
+
+		assert_eq!(
+			find_node_path(&graph, start, goal)?,
+			Some(vec![start, road[0], road[1], goal])
+		);
+		assert_eq!(find_node_path(&graph, goal, goal)?, Some(vec![goal]));
+		assert_eq!(find_node_path(&graph, start, island)?, None);
+		assert_eq!(
+			find_node_path(&graph, start, 99),
+			Err(GraphError::NodeDoesNotExist(99))
+		);
+		Ok(())
+	}
+}
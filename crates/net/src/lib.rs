@@ -0,0 +1,13 @@
+#![forbid(unsafe_code)]
+
+mod replication;
+mod rollback;
+mod snapshot;
+mod transport;
+
+pub use self::{
+	replication::{update_client, update_server, Replica, Replicated, Replicator},
+	rollback::{checksum, Rollback, RollbackError, RollbackSession},
+	snapshot::{capture, interpolate, EntityState, NetworkId, Snapshot, WorldState},
+	transport::{ClientId, Delivery, NetClient, NetError, NetEvent, NetServer, Packet},
+};
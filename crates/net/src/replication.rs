@@ -0,0 +1,448 @@
+use crate::{
+	snapshot::{capture, interpolate, NetworkId, Snapshot, WorldState},
+	transport::{ClientId, Delivery, NetClient, NetEvent, NetServer, Packet},
+};
+use ecs::{
+	error::Result,
+	events::Events,
+	reflect::{ComponentRegistry, Field},
+	time::Time,
+	world::{Entity, World},
+};
+use std::collections::{HashMap, VecDeque};
+
+// How many past states servers keep to send deltas against, and clients keep to apply them to
+const HISTORY: usize = 32;
+
+/// Marks an entity to be replicated to clients, with the registered components to send
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Replicated {
+	pub components: Vec<String>,
+}
+
+impl Replicated {
+	pub fn new(components: impl IntoIterator<Item = impl Into<String>>) -> Self {
+		Self {
+			components: components.into_iter().map(Into::into).collect(),
+		}
+	}
+}
+
+/// Sends snapshots of the world to clients, kept as a resource on servers. Each client is
+/// sent the changes since the last snapshot it acknowledged, or everything if there's none.
+#[derive(Debug, Default)]
+pub struct Replicator {
+	tick: u32,
+	history: VecDeque<(u32, WorldState)>,
+	acknowledged: HashMap<ClientId, u32>,
+}
+
+impl Replicator {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// The tick of the latest snapshot
+	pub fn tick(&self) -> u32 {
+		self.tick
+	}
+
+	fn receive(&mut self, event: &NetEvent) {
+		match event {
+			NetEvent::Received(client, Packet::Ack(tick)) => {
+				let acknowledged = self.acknowledged.entry(*client).or_default();
+				*acknowledged = (*acknowledged).max(*tick);
+			}
+			NetEvent::Disconnected(client) => {
+				self.acknowledged.remove(client);
+			}
+			_ => {}
+		}
+	}
+
+	fn send(&mut self, server: &mut NetServer, state: WorldState, time: f64) -> Result<()> {
+		self.tick += 1;
+		for client in server.clients() {
+			let baseline = self.acknowledged.get(&client).and_then(|acknowledged| {
+				self.history
+					.iter()
+					.find(|(tick, _)| tick == acknowledged)
+					.map(|(tick, state)| (*tick, state))
+			});
+			let snapshot = Snapshot::new(self.tick, time, &state, baseline);
+			server.send(client, &Packet::Snapshot(snapshot), Delivery::Unreliable)?;
+		}
+		self.history.push_back((self.tick, state));
+		if self.history.len() > HISTORY {
+			self.history.pop_front();
+		}
+		Ok(())
+	}
+}
+
+/// Receives snapshots from the server and mirrors them onto local entities, kept as a resource
+/// on clients. With an interpolation delay, entities are shown that far behind the latest
+/// snapshot, blended between the two around them so they move smoothly.
+#[derive(Debug, Default)]
+pub struct Replica {
+	/// In seconds, or zero to show each snapshot as it arrives
+	pub interpolation_delay: f64,
+	states: VecDeque<(u32, f64, WorldState)>,
+	entities: HashMap<NetworkId, Entity>,
+	clock: Option<f64>,
+}
+
+impl Replica {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn with_interpolation_delay(self, interpolation_delay: f64) -> Self {
+		Self {
+			interpolation_delay,
+			..self
+		}
+	}
+
+	/// The local entity mirroring the server's
+	pub fn entity(&self, id: NetworkId) -> Option<Entity> {
+		self.entities.get(&id).copied()
+	}
+
+	/// The tick of the latest snapshot received
+	pub fn tick(&self) -> Option<u32> {
+		self.states.back().map(|(tick, ..)| *tick)
+	}
+
+	// Rebuilds the snapshot's state and returns its tick to acknowledge, unless it's
+	// a duplicate or its baseline is gone
+	fn receive(&mut self, snapshot: &Snapshot) -> Option<u32> {
+		if self.states.iter().any(|(tick, ..)| *tick == snapshot.tick) {
+			return None;
+		}
+		let baseline = snapshot.baseline.and_then(|baseline| {
+			self.states
+				.iter()
+				.find(|(tick, ..)| *tick == baseline)
+				.map(|(.., state)| state)
+		});
+		let state = snapshot.apply(baseline)?;
+		// Kept in order, since datagrams can arrive out of it
+		let index = self
+			.states
+			.iter()
+			.position(|(tick, ..)| *tick > snapshot.tick)
+			.unwrap_or(self.states.len());
+		self.states
+			.insert(index, (snapshot.tick, snapshot.time, state));
+		if self.states.len() > HISTORY {
+			self.states.pop_front();
+		}
+		Some(snapshot.tick)
+	}
+
+	// The state to show after the clock moves on by the delta
+	fn state(&mut self, delta: f64) -> Option<WorldState> {
+		let (_, latest, state) = self.states.back()?;
+		if self.interpolation_delay <= 0.0 {
+			return Some(state.clone());
+		}
+		// Follows the server's clock, jumping to it when it falls too far behind
+		let target = latest - self.interpolation_delay;
+		let clock = self.clock.map_or(target, |clock| clock + delta);
+		let clock = if clock < target - self.interpolation_delay {
+			target
+		} else {
+			clock.min(*latest)
+		};
+		self.clock = Some(clock);
+
+		let next = self.states.iter().position(|(_, time, _)| *time > clock);
+		Some(match next {
+			Some(0) => self.states[0].2.clone(),
+			Some(next) => {
+				let (_, from_time, from) = &self.states[next - 1];
+				let (_, to_time, to) = &self.states[next];
+				interpolate(from, to, (clock - from_time) / (to_time - from_time))
+			}
+			None => state.clone(),
+		})
+	}
+
+	// Spawns, updates and removes local entities to match the state
+	fn apply(
+		&mut self,
+		world: &mut World,
+		registry: &ComponentRegistry,
+		state: &WorldState,
+	) -> Result<()> {
+		let gone = self
+			.entities
+			.keys()
+			.filter(|id| !state.contains_key(id))
+			.copied()
+			.collect::<Vec<_>>();
+		for id in gone {
+			if let Some(entity) = self.entities.remove(&id) {
+				world.remove_entity(entity);
+			}
+		}
+		for (id, components) in state {
+			let entity = match self.entities.get(id) {
+				Some(entity) => *entity,
+				None => {
+					let entity = world.create_entity();
+					self.entities.insert(*id, entity);
+					entity
+				}
+			};
+			for (name, fields) in components {
+				let Some(info) = registry.get(name) else {
+					continue;
+				};
+				if !info.is_on(world, entity) {
+					info.add_default(world, entity)?;
+				}
+				// Set by the registry's names for the fields, which outlive the state's
+				for field in info.default_fields() {
+					if let Some(value) = fields.get(field.name) {
+						info.set_field(
+							world,
+							entity,
+							Field {
+								name: field.name,
+								value: value.clone(),
+							},
+						)?;
+					}
+				}
+			}
+		}
+		Ok(())
+	}
+}
+
+fn push_events(world: &World, events: Vec<NetEvent>) {
+	let mut resources = world.resources().borrow_mut();
+	match resources.get_mut::<Events<NetEvent>>() {
+		Some(queue) => queue.extend(events),
+		None => {
+			let mut queue = Events::new();
+			queue.extend(events);
+			resources.insert(queue);
+		}
+	}
+}
+
+/// Polls the `NetServer` resource, sending its events to the world's `Events<NetEvent>`, then
+/// sends every client a snapshot of the `Replicated` entities through the `Replicator` resource.
+/// Components are read through the `ComponentRegistry` resource.
+pub fn update_server(world: &mut World) -> Result<()> {
+	if world.resources().borrow().get::<NetServer>().is_none() {
+		return Ok(());
+	}
+	let (registry, time) = {
+		let resources = world.resources().borrow();
+		(
+			resources
+				.get::<ComponentRegistry>()
+				.cloned()
+				.unwrap_or_default(),
+			resources.get::<Time>().map_or(0.0, Time::elapsed_seconds),
+		)
+	};
+	let state = capture(world, &registry);
+
+	let mut resources = world.resources().borrow_mut();
+	let mut replicator = resources
+		.get_mut::<Replicator>()
+		.map(std::mem::take)
+		.unwrap_or_default();
+	let Some(server) = resources.get_mut::<NetServer>() else {
+		return Ok(());
+	};
+	let events = server.poll();
+	for event in &events {
+		replicator.receive(event);
+	}
+	let sent = replicator.send(server, state, time);
+	resources.insert(replicator);
+	drop(resources);
+	push_events(world, events);
+	sent
+}
+
+/// Polls the `NetClient` resource, sending its events to the world's `Events<NetEvent>`, then
+/// mirrors the server's replicated entities onto local ones through the `Replica` resource.
+/// Components are written through the `ComponentRegistry` resource.
+pub fn update_client(world: &mut World) -> Result<()> {
+	let (registry, delta) = {
+		let resources = world.resources().borrow();
+		(
+			resources
+				.get::<ComponentRegistry>()
+				.cloned()
+				.unwrap_or_default(),
+			resources.get::<Time>().map_or(0.0, Time::delta_seconds),
+		)
+	};
+	let mut resources = world.resources().borrow_mut();
+	let mut replica = resources
+		.get_mut::<Replica>()
+		.map(std::mem::take)
+		.unwrap_or_default();
+	let Some(client) = resources.get_mut::<NetClient>() else {
+		resources.insert(replica);
+		return Ok(());
+	};
+	let events = client.poll();
+	for event in &events {
+		if let NetEvent::Received(_, Packet::Snapshot(snapshot)) = event {
+			if let Some(tick) = replica.receive(snapshot) {
+				// A failed acknowledgement is no worse than one lost on the way
+				let _ = client.send(&Packet::Ack(tick), Delivery::Unreliable);
+			}
+		}
+	}
+	drop(resources);
+
+	let applied = match replica.state(delta.into()) {
+		Some(state) => replica.apply(world, &registry, &state),
+		None => Ok(()),
+	};
+	world.resources().borrow_mut().insert(replica);
+	push_events(world, events);
+	applied
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::transport::tests::poll_until;
+	use ecs::{reflect, reflect::Value};
+	use std::collections::BTreeMap;
+
+	#[derive(Debug, Default, PartialEq)]
+	struct Position {
+		x: f32,
+		y: f32,
+	}
+
+	reflect!(Position { x, y });
+
+	fn registry() -> ComponentRegistry {
+		let mut registry = ComponentRegistry::new();
+		registry.register::<Position>("Position");
+		registry
+	}
+
+	fn positions(world: &World) -> Vec<Position> {
+		world
+			.entities()
+			.into_iter()
+			.filter_map(|entity| {
+				world
+					.get_component::<Position>(entity)
+					.map(|position| Position { ..*position })
+			})
+			.collect()
+	}
+
+	#[test]
+	fn replicated_components_are_mirrored_on_clients() -> Result<()> {
+		let mut server = World::new();
+		let server_address = {
+			let net = NetServer::bind("127.0.0.1:0")?;
+			let address = net.local_address()?;
+			let mut resources = server.resources().borrow_mut();
+			resources.insert(net);
+			resources.insert(Replicator::new());
+			resources.insert(registry());
+			address
+		};
+		let mut client = World::new();
+		{
+			let mut resources = client.resources().borrow_mut();
+			resources.insert(NetClient::connect(server_address)?);
+			resources.insert(Replica::new());
+			resources.insert(registry());
+		}
+
+		let player = server.create_entity();
+		server.add_component(player, Position { x: 1.0, y: 2.0 })?;
+		server.add_component(player, Replicated::new(["Position"]))?;
+		// Not replicated
+		let hidden = server.create_entity();
+		server.add_component(hidden, Position::default())?;
+
+		let step = |server: &mut World, client: &mut World| -> bool {
+			update_server(server).unwrap();
+			update_client(client).unwrap();
+			true
+		};
+		poll_until(|| step(&mut server, &mut client) && !positions(&client).is_empty());
+		assert_eq!(positions(&client), [Position { x: 1.0, y: 2.0 }]);
+		let connected = client
+			.resources()
+			.borrow()
+			.get::<Events<NetEvent>>()
+			.is_some_and(|events| {
+				events
+					.iter()
+					.any(|event| matches!(event, NetEvent::Connected(_)))
+			});
+		assert!(connected);
+
+		server.get_component_mut::<Position>(player).unwrap().x = 5.0;
+		poll_until(|| step(&mut server, &mut client) && positions(&client)[0].x == 5.0);
+		assert_eq!(positions(&client), [Position { x: 5.0, y: 2.0 }]);
+		// Snapshots after the first acknowledged one are deltas against it
+		let acknowledged = server
+			.resources()
+			.borrow()
+			.get::<Replicator>()
+			.is_some_and(|replicator| !replicator.acknowledged.is_empty());
+		assert!(acknowledged);
+
+		server.remove_entity(player);
+		poll_until(|| step(&mut server, &mut client) && positions(&client).is_empty());
+		Ok(())
+	}
+
+	#[test]
+	fn clients_blend_between_snapshots_behind_the_latest() {
+		let id = NetworkId {
+			index: 0,
+			generation: 0,
+		};
+		let state = |x: f64| {
+			WorldState::from([(
+				id,
+				BTreeMap::from([(
+					"Position".to_string(),
+					BTreeMap::from([("x".to_string(), Value::Float(x))]),
+				)]),
+			)])
+		};
+		let x = |state: WorldState| state[&id]["Position"]["x"].clone();
+
+		let mut replica = Replica::new().with_interpolation_delay(0.1);
+		assert_eq!(
+			replica.receive(&Snapshot::new(1, 1.0, &state(0.0), None)),
+			Some(1)
+		);
+		let second = Snapshot::new(2, 1.1, &state(10.0), Some((1, &state(0.0))));
+		assert_eq!(replica.receive(&second), Some(2));
+		assert_eq!(replica.receive(&second), None);
+		// Starts a tenth of a second behind the latest, then catches up with it
+		assert_eq!(replica.state(0.0).map(x), Some(Value::Float(0.0)));
+		let Some(Value::Float(halfway)) = replica.state(0.05).map(x) else {
+			panic!("x isn't a float");
+		};
+		assert!((halfway - 5.0).abs() < 1e-6);
+		assert_eq!(replica.state(1.0).map(x), Some(Value::Float(10.0)));
+		// A delta against a state the client never had is dropped
+		let orphan = Snapshot::new(4, 1.3, &state(0.0), Some((3, &state(0.0))));
+		assert_eq!(replica.receive(&orphan), None);
+	}
+}
@@ -0,0 +1,441 @@
+use crate::transport::Packet;
+use ecs::{
+	error::Result,
+	reflect::{ComponentRegistry, Field, Reflect},
+	rng::Rng,
+	world::{Entity, World},
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum RollbackError {
+	#[error("No player {0} in the session")]
+	UnknownPlayer(usize),
+
+	#[error("Input for frame {0} arrived too late to roll back to")]
+	TooLate(u32),
+
+	#[error("Peers disagree on the state after frame {0}")]
+	Desync(u32),
+
+	#[error("Failed to read input: {0}")]
+	Decode(#[from] serde_json::Error),
+}
+
+/// Marks an entity as part of the simulation, so its registered components are saved each
+/// frame and rolled back. Entities spawned by the simulation need it too. The world's `Rng`
+/// resource is saved and rolled back along with them.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Rollback;
+
+type Components = Vec<(&'static str, Vec<Field>)>;
+
+#[derive(Debug)]
+struct Saved {
+	entities: Vec<(Entity, Components)>,
+	rng: Option<Rng>,
+}
+
+// The registered components of every `Rollback` entity, and the world's `Rng`
+fn save(world: &World, registry: &ComponentRegistry) -> Saved {
+	let entities = world
+		.entities()
+		.into_iter()
+		.filter(|entity| world.get_component::<Rollback>(*entity).is_some())
+		.map(|entity| {
+			let components = registry
+				.components_of(world, entity)
+				.filter_map(|info| Some((info.name, info.fields(world, entity)?)))
+				.collect();
+			(entity, components)
+		})
+		.collect();
+	let rng = world.resources().borrow().get::<Rng>().cloned();
+	Saved { entities, rng }
+}
+
+fn load(world: &mut World, registry: &ComponentRegistry, saved: &Saved) -> Result<()> {
+	if let Some(rng) = &saved.rng {
+		world.resources().borrow_mut().insert(rng.clone());
+	}
+	let saved = &saved.entities;
+	// Entities spawned since are removed and those removed since are brought back. The newest
+	// are removed first, so simulating again spawns into the same slots in the same order.
+	for entity in world.entities().into_iter().rev() {
+		if world.get_component::<Rollback>(entity).is_some()
+			&& !saved.iter().any(|(saved, _)| *saved == entity)
+		{
+			world.remove_entity(entity);
+		}
+	}
+	for (entity, components) in saved {
+		if !world.entity_exists(*entity) {
+			world.restore_entity(*entity)?;
+			world.add_component(*entity, Rollback)?;
+		}
+		for info in registry.iter() {
+			let fields = components
+				.iter()
+				.find(|(name, _)| *name == info.name)
+				.map(|(_, fields)| fields);
+			match fields {
+				Some(fields) => {
+					if !info.is_on(world, *entity) {
+						info.add_default(world, *entity)?;
+					}
+					for field in fields {
+						info.set_field(world, *entity, field.clone())?;
+					}
+				}
+				None if info.is_on(world, *entity) => info.remove(world, *entity)?,
+				None => {}
+			}
+		}
+	}
+	Ok(())
+}
+
+/// A CRC32 of the registered components of every `Rollback` entity and the world's `Rng`,
+/// which peers running the same inputs deterministically agree on
+pub fn checksum(world: &World, registry: &ComponentRegistry) -> u32 {
+	let mut hasher = crc32fast::Hasher::new();
+	let saved = save(world, registry);
+	// Entities are hashed in slot order without their generations, which rollbacks change
+	let components = saved
+		.entities
+		.into_iter()
+		.flat_map(|(_, components)| components)
+		.chain(saved.rng.map(|rng| ("Rng", rng.fields())));
+	for (name, fields) in components {
+		let fields = fields
+			.into_iter()
+			.map(|field| (field.name, field.value))
+			.collect::<Vec<_>>();
+		// Serializing plain names and values can't fail
+		if let Ok(bytes) = serde_json::to_vec(&(name, fields)) {
+			hasher.update(&bytes);
+		}
+	}
+	hasher.finalize()
+}
+
+/// Runs a deterministic simulation from every player's inputs, predicting the inputs that
+/// haven't arrived yet. When an input arrives that differs from its prediction, the world is
+/// rolled back to the frame it was for and simulated forward again. For lockstep instead,
+/// advance only once `is_ready` says every input for the frame has arrived.
+///
+/// The world's state is saved through its `ComponentRegistry` resource.
+pub struct RollbackSession<I> {
+	players: usize,
+	max_frames: usize,
+	frame: u32,
+	inputs: BTreeMap<u32, Vec<Option<I>>>,
+	simulated: BTreeMap<u32, Vec<I>>,
+	// The state at the start of each kept frame
+	saved: VecDeque<(u32, Saved)>,
+	checksums: BTreeMap<u32, u32>,
+	remote_checksums: BTreeMap<u32, u32>,
+	rollback: Option<u32>,
+}
+
+impl<I: Clone + Default + PartialEq> RollbackSession<I> {
+	/// Keeps the last `max_frames` frames to roll back through
+	pub fn new(players: usize, max_frames: usize) -> Self {
+		Self {
+			players,
+			max_frames: max_frames.max(1),
+			frame: 0,
+			inputs: BTreeMap::new(),
+			simulated: BTreeMap::new(),
+			saved: VecDeque::new(),
+			checksums: BTreeMap::new(),
+			remote_checksums: BTreeMap::new(),
+			rollback: None,
+		}
+	}
+
+	/// The next frame to simulate
+	pub fn frame(&self) -> u32 {
+		self.frame
+	}
+
+	fn oldest_frame(&self) -> u32 {
+		self.saved.front().map_or(self.frame, |(frame, _)| *frame)
+	}
+
+	pub fn add_input(&mut self, player: usize, frame: u32, input: I) -> Result<(), RollbackError> {
+		if player >= self.players {
+			return Err(RollbackError::UnknownPlayer(player));
+		}
+		if frame < self.oldest_frame() {
+			return Err(RollbackError::TooLate(frame));
+		}
+		let mispredicted = self
+			.simulated
+			.get(&frame)
+			.is_some_and(|inputs| inputs[player] != input);
+		if mispredicted {
+			self.rollback = Some(self.rollback.map_or(frame, |rollback| rollback.min(frame)));
+		}
+		self.inputs
+			.entry(frame)
+			.or_insert_with(|| vec![None; self.players])[player] = Some(input);
+		Ok(())
+	}
+
+	/// The player's input for the frame, or the latest one before it when it hasn't arrived
+	pub fn input(&self, player: usize, frame: u32) -> I {
+		self.inputs
+			.range(..=frame)
+			.rev()
+			.find_map(|(_, inputs)| inputs.get(player).cloned().flatten())
+			.unwrap_or_default()
+	}
+
+	/// Whether every player's input for the next frame has arrived
+	pub fn is_ready(&self) -> bool {
+		self.inputs
+			.get(&self.frame)
+			.is_some_and(|inputs| inputs.iter().all(Option::is_some))
+	}
+
+	/// The last frame whose inputs have all arrived, along with every frame before it
+	pub fn confirmed_frame(&self) -> Option<u32> {
+		let mut confirmed = None;
+		for frame in self.oldest_frame()..self.frame {
+			let complete = self
+				.inputs
+				.get(&frame)
+				.is_some_and(|inputs| inputs.iter().all(Option::is_some));
+			if !complete {
+				break;
+			}
+			confirmed = Some(frame);
+		}
+		confirmed
+	}
+
+	/// The checksum of the state after the frame, while it's kept
+	pub fn checksum(&self, frame: u32) -> Option<u32> {
+		self.checksums.get(&frame).copied()
+	}
+
+	/// Records a peer's checksum for a frame, checked once the frame is confirmed
+	pub fn add_checksum(&mut self, frame: u32, checksum: u32) {
+		self.remote_checksums.insert(frame, checksum);
+	}
+
+	/// Simulates the next frame with each player's input, first rolling back and simulating
+	/// again from the earliest mispredicted frame
+	pub fn advance(
+		&mut self,
+		world: &mut World,
+		mut simulate: impl FnMut(&mut World, &[I]) -> Result<()>,
+	) -> Result<()> {
+		let registry = world
+			.resources()
+			.borrow()
+			.get::<ComponentRegistry>()
+			.cloned()
+			.unwrap_or_default();
+		if let Some(rollback) = self.rollback.take() {
+			let target = self.frame;
+			if let Some(index) = self.saved.iter().position(|(frame, _)| *frame == rollback) {
+				load(world, &registry, &self.saved[index].1)?;
+				self.saved.truncate(index);
+				self.frame = rollback;
+			}
+			while self.frame < target {
+				self.step(world, &registry, &mut simulate)?;
+			}
+		}
+		self.step(world, &registry, &mut simulate)?;
+		self.check_desync()?;
+		Ok(())
+	}
+
+	fn step(
+		&mut self,
+		world: &mut World,
+		registry: &ComponentRegistry,
+		simulate: &mut impl FnMut(&mut World, &[I]) -> Result<()>,
+	) -> Result<()> {
+		let frame = self.frame;
+		self.saved.push_back((frame, save(world, registry)));
+		let inputs = (0..self.players)
+			.map(|player| self.input(player, frame))
+			.collect::<Vec<_>>();
+		simulate(world, &inputs)?;
+		self.simulated.insert(frame, inputs);
+		self.checksums.insert(frame, checksum(world, registry));
+		self.frame += 1;
+
+		while self.saved.len() > self.max_frames {
+			self.saved.pop_front();
+		}
+		// Inputs before the oldest frame are kept while they're still the latest for predictions
+		let oldest = self.oldest_frame();
+		self.simulated = self.simulated.split_off(&oldest);
+		self.checksums = self.checksums.split_off(&oldest);
+		let keep = self
+			.inputs
+			.range(..oldest)
+			.next_back()
+			.map_or(oldest, |(frame, _)| *frame);
+		self.inputs = self.inputs.split_off(&keep);
+		Ok(())
+	}
+
+	fn check_desync(&mut self) -> Result<(), RollbackError> {
+		let Some(confirmed) = self.confirmed_frame() else {
+			return Ok(());
+		};
+		let checked = self.remote_checksums.range(..=confirmed);
+		for (frame, remote) in checked {
+			if self
+				.checksums
+				.get(frame)
+				.is_some_and(|local| local != remote)
+			{
+				return Err(RollbackError::Desync(*frame));
+			}
+		}
+		self.remote_checksums = self.remote_checksums.split_off(&(confirmed + 1));
+		Ok(())
+	}
+}
+
+impl<I: Clone + Default + PartialEq + Serialize + DeserializeOwned> RollbackSession<I> {
+	/// A packet carrying a player's input for a frame to the other peers
+	pub fn input_packet(player: usize, frame: u32, input: &I) -> Result<Packet, RollbackError> {
+		Ok(Packet::Input {
+			player,
+			frame,
+			input: serde_json::to_string(input)?,
+		})
+	}
+
+	/// Takes the input or checksum a peer sent, returning false for other packets
+	pub fn receive(&mut self, packet: &Packet) -> Result<bool, RollbackError> {
+		match packet {
+			Packet::Input {
+				player,
+				frame,
+				input,
+			} => self.add_input(*player, *frame, serde_json::from_str(input)?)?,
+			Packet::Checksum { frame, checksum } => self.add_checksum(*frame, *checksum),
+			_ => return Ok(false),
+		}
+		Ok(true)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ecs::reflect;
+
+	#[derive(Debug, Default, PartialEq)]
+	struct Position {
+		x: i32,
+	}
+
+	reflect!(Position { x });
+
+	fn world() -> World {
+		let world = World::new();
+		let mut registry = ComponentRegistry::new();
+		registry.register::<Position>("Position");
+		world.resources().borrow_mut().insert(registry);
+		world.resources().borrow_mut().insert(Rng::new(1));
+		world
+	}
+
+	// Spawns an entity a frame until each player has one, then moves each by its player's input
+	// and a roll of the world's dice
+	fn simulate(world: &mut World, inputs: &[i32]) -> Result<()> {
+		let mut players = world
+			.entities()
+			.into_iter()
+			.filter(|entity| world.get_component::<Position>(*entity).is_some())
+			.collect::<Vec<_>>();
+		if players.len() < inputs.len() {
+			let entity = world.create_entity();
+			world.add_component(entity, Position::default())?;
+			world.add_component(entity, Rollback)?;
+			players.push(entity);
+		}
+		for (entity, input) in players.into_iter().zip(inputs) {
+			let roll = world
+				.resources()
+				.borrow_mut()
+				.get_mut::<Rng>()
+				.unwrap()
+				.range(0..3) as i32;
+			world.get_component_mut::<Position>(entity).unwrap().x += input + roll;
+		}
+		Ok(())
+	}
+
+	#[test]
+	fn late_inputs_roll_back_and_resimulate_to_the_same_state() -> Result<()> {
+		let mut local = world();
+		let mut session = RollbackSession::<i32>::new(2, 4);
+		let mut remote = world();
+		let mut lockstep = RollbackSession::<i32>::new(2, 8);
+
+		// Player 1's inputs arrive three frames late, so they're predicted until then
+		for frame in 0..6 {
+			session.add_input(0, frame, 1)?;
+			if frame >= 3 {
+				session.add_input(1, frame - 3, 2 + frame as i32)?;
+			}
+			session.advance(&mut local, simulate)?;
+
+			lockstep.add_input(0, frame, 1)?;
+			lockstep.add_input(1, frame, 5 + frame as i32)?;
+			assert!(lockstep.is_ready());
+			lockstep.advance(&mut remote, simulate)?;
+		}
+		assert_ne!(session.checksum(5), lockstep.checksum(5));
+
+		// Catches up with the last inputs
+		for frame in 3..6 {
+			session.add_input(1, frame, 5 + frame as i32)?;
+		}
+		assert!(matches!(
+			session.add_input(1, 0, 0),
+			Err(RollbackError::TooLate(0))
+		));
+		for (player, input) in [(0, 1), (1, 11)] {
+			session.add_input(player, 6, input)?;
+			lockstep.add_input(player, 6, input)?;
+		}
+		session.advance(&mut local, simulate)?;
+		lockstep.advance(&mut remote, simulate)?;
+
+		assert_eq!(session.confirmed_frame(), Some(6));
+		assert_eq!(session.checksum(5), lockstep.checksum(5));
+		let registry = local
+			.resources()
+			.borrow()
+			.get::<ComponentRegistry>()
+			.cloned()
+			.unwrap();
+		assert_eq!(checksum(&local, &registry), checksum(&remote, &registry));
+		assert_eq!(
+			local.entities().len(),
+			remote.entities().len(),
+			"The rolled back spawn is only made once"
+		);
+
+		// A peer reporting a different state for a confirmed frame is a desync
+		let packet = RollbackSession::<i32>::input_packet(0, 7, &1)?;
+		assert!(session.receive(&packet)?);
+		session.add_checksum(5, session.checksum(5).unwrap().wrapping_add(1));
+		assert!(session.advance(&mut local, simulate).is_err());
+		Ok(())
+	}
+}
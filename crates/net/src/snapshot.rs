@@ -0,0 +1,223 @@
+use crate::replication::Replicated;
+use ecs::{
+	reflect::{ComponentRegistry, Value},
+	world::{Entity, World},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// An entity on the server, which clients map to entities of their own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct NetworkId {
+	pub index: usize,
+	pub generation: usize,
+}
+
+impl NetworkId {
+	pub fn of(entity: Entity) -> Self {
+		Self {
+			index: *entity.index(),
+			generation: *entity.generation(),
+		}
+	}
+}
+
+/// The fields of an entity's replicated components, by component and then field name
+pub type EntityState = BTreeMap<String, BTreeMap<String, Value>>;
+
+/// Every replicated entity's state
+pub type WorldState = BTreeMap<NetworkId, EntityState>;
+
+/// The replicated state of a server's world at a tick, sent to clients
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+	pub tick: u32,
+	/// The server's elapsed seconds when it was taken
+	pub time: f64,
+	/// The earlier tick this only holds the changes since, or `None` when it holds everything
+	pub baseline: Option<u32>,
+	pub entities: Vec<(NetworkId, EntityState)>,
+	/// Entities in the baseline that are gone
+	pub removed: Vec<NetworkId>,
+}
+
+impl Snapshot {
+	/// A snapshot of the state, holding only the fields that changed since the baseline when given.
+	/// Components removed from an entity that's still replicated aren't sent.
+	pub fn new(
+		tick: u32,
+		time: f64,
+		state: &WorldState,
+		baseline: Option<(u32, &WorldState)>,
+	) -> Self {
+		let Some((baseline_tick, previous)) = baseline else {
+			return Self {
+				tick,
+				time,
+				baseline: None,
+				entities: state.clone().into_iter().collect(),
+				removed: Vec::new(),
+			};
+		};
+		let entities = state
+			.iter()
+			.filter_map(|(id, entity)| {
+				let Some(before) = previous.get(id) else {
+					return Some((*id, entity.clone()));
+				};
+				let changes = entity
+					.iter()
+					.filter_map(|(name, fields)| {
+						let before = before.get(name);
+						let changed = fields
+							.iter()
+							.filter(|(field, value)| {
+								before.and_then(|before| before.get(*field)) != Some(*value)
+							})
+							.map(|(field, value)| (field.clone(), value.clone()))
+							.collect::<BTreeMap<_, _>>();
+						(!changed.is_empty()).then(|| (name.clone(), changed))
+					})
+					.collect::<EntityState>();
+				(!changes.is_empty()).then_some((*id, changes))
+			})
+			.collect();
+		Self {
+			tick,
+			time,
+			baseline: Some(baseline_tick),
+			entities,
+			removed: previous
+				.keys()
+				.filter(|id| !state.contains_key(id))
+				.copied()
+				.collect(),
+		}
+	}
+
+	/// The whole state this describes, given the state at its baseline.
+	/// Returns `None` when it needs a baseline and wasn't given one.
+	pub fn apply(&self, baseline: Option<&WorldState>) -> Option<WorldState> {
+		let mut state = match self.baseline {
+			Some(_) => baseline?.clone(),
+			None => WorldState::new(),
+		};
+		for id in &self.removed {
+			state.remove(id);
+		}
+		for (id, changes) in &self.entities {
+			let entity = state.entry(*id).or_default();
+			for (name, fields) in changes {
+				entity.entry(name.clone()).or_default().extend(
+					fields
+						.iter()
+						.map(|(field, value)| (field.clone(), value.clone())),
+				);
+			}
+		}
+		Some(state)
+	}
+}
+
+/// The state of every `Replicated` entity's marked components, read through the registry
+pub fn capture(world: &World, registry: &ComponentRegistry) -> WorldState {
+	world
+		.entities()
+		.into_iter()
+		.filter_map(|entity| {
+			let replicated = world.get_component::<Replicated>(entity)?;
+			let state = replicated
+				.components
+				.iter()
+				.filter_map(|name| {
+					let fields = registry.get(name)?.fields(world, entity)?;
+					Some((
+						name.clone(),
+						fields
+							.into_iter()
+							.map(|field| (field.name.to_string(), field.value))
+							.collect(),
+					))
+				})
+				.collect();
+			Some((NetworkId::of(entity), state))
+		})
+		.collect()
+}
+
+/// The state part way from one to the other, by an amount from zero to one. Float fields are
+/// blended and the rest switch over at the end. Only entities in the first state are kept,
+/// so entities appear and disappear when the states they're added and removed in are reached.
+pub fn interpolate(from: &WorldState, to: &WorldState, amount: f64) -> WorldState {
+	let mut state = from.clone();
+	for (id, entity) in &mut state {
+		let Some(target) = to.get(id) else {
+			continue;
+		};
+		for (name, fields) in entity.iter_mut() {
+			let Some(target) = target.get(name) else {
+				continue;
+			};
+			for (field, value) in fields.iter_mut() {
+				match (&*value, target.get(field)) {
+					(Value::Float(start), Some(Value::Float(end))) => {
+						*value = Value::Float(start + (end - start) * amount);
+					}
+					(_, Some(end)) if amount >= 1.0 => *value = end.clone(),
+					_ => {}
+				}
+			}
+		}
+	}
+	state
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn entity(index: usize, x: f64, label: &str) -> (NetworkId, EntityState) {
+		(
+			NetworkId {
+				index,
+				generation: 0,
+			},
+			EntityState::from([(
+				"Position".to_string(),
+				BTreeMap::from([
+					("x".to_string(), Value::Float(x)),
+					("label".to_string(), Value::Text(label.to_string())),
+				]),
+			)]),
+		)
+	}
+
+	#[test]
+	fn deltas_hold_changes_and_rebuild_the_state() {
+		let first = WorldState::from([entity(0, 1.0, "a"), entity(1, 0.0, "b")]);
+		let second = WorldState::from([entity(0, 3.0, "a"), entity(2, 5.0, "c")]);
+
+		let full = Snapshot::new(1, 0.0, &first, None);
+		assert_eq!(full.entities.len(), 2);
+		assert_eq!(full.apply(None), Some(first.clone()));
+
+		let delta = Snapshot::new(2, 0.1, &second, Some((1, &first)));
+		assert_eq!(delta.baseline, Some(1));
+		assert_eq!(delta.removed, [entity(1, 0.0, "").0]);
+		// Only the moved field of the entity that was already there
+		assert_eq!(
+			delta.entities[0].1["Position"],
+			BTreeMap::from([("x".to_string(), Value::Float(3.0))])
+		);
+		assert_eq!(delta.entities[1], entity(2, 5.0, "c"));
+		assert_eq!(delta.apply(Some(&first)), Some(second.clone()));
+		assert_eq!(delta.apply(None), None);
+
+		let halfway = interpolate(&first, &second, 0.5);
+		let position = &halfway[&entity(0, 0.0, "").0]["Position"];
+		assert_eq!(position["x"], Value::Float(2.0));
+		// Entity 1 is kept until it's removed, and entity 2 isn't shown until it's added
+		assert_eq!(halfway.len(), 2);
+		assert!(halfway.contains_key(&entity(1, 0.0, "").0));
+	}
+}
@@ -0,0 +1,489 @@
+use crate::snapshot::Snapshot;
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::HashMap,
+	io::{self, ErrorKind, Read, Write},
+	net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket},
+};
+use thiserror::Error;
+
+// Unreliable packets bigger than this go over TCP, since large datagrams are often dropped
+const MAX_DATAGRAM: usize = 1200;
+const MAX_FRAME: usize = 16 * 1024 * 1024;
+
+pub type ClientId = u64;
+
+#[derive(Error, Debug)]
+pub enum NetError {
+	#[error("Network error: {0}")]
+	Io(#[from] io::Error),
+
+	#[error("Failed to encode packet: {0}")]
+	Encode(#[from] serde_json::Error),
+
+	#[error("No client with id {0} is connected")]
+	UnknownClient(ClientId),
+
+	#[error("Not connected to a server")]
+	NotConnected,
+}
+
+/// How a packet is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+	/// In order and never lost, over TCP
+	Reliable,
+	/// Over UDP, so it may be lost or arrive out of order but never waits on earlier packets.
+	/// Falls back to TCP until the other side's UDP address is known.
+	Unreliable,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Packet {
+	/// Sent by the server when a client connects, with the client's id
+	Welcome(ClientId),
+	/// Sent by clients over UDP once welcomed, so the server learns their address
+	Hello,
+	Snapshot(Snapshot),
+	/// Sent by clients for each snapshot they receive
+	Ack(u32),
+	/// A player's input for a frame of a `RollbackSession`
+	Input {
+		player: usize,
+		frame: u32,
+		input: String,
+	},
+	/// A peer's checksum of its world after a frame
+	Checksum {
+		frame: u32,
+		checksum: u32,
+	},
+	/// A message for the game itself
+	Message {
+		topic: String,
+		payload: String,
+	},
+}
+
+/// Connections, disconnections and packets, from polling a `NetServer` or `NetClient`.
+/// On clients, the id is the client's own.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetEvent {
+	Connected(ClientId),
+	Disconnected(ClientId),
+	Received(ClientId, Packet),
+}
+
+// A TCP stream split into frames, each prefixed with its length
+struct Stream {
+	stream: TcpStream,
+	incoming: Vec<u8>,
+	outgoing: Vec<u8>,
+}
+
+impl Stream {
+	fn new(stream: TcpStream) -> io::Result<Self> {
+		stream.set_nonblocking(true)?;
+		stream.set_nodelay(true)?;
+		Ok(Self {
+			stream,
+			incoming: Vec::new(),
+			outgoing: Vec::new(),
+		})
+	}
+
+	fn queue(&mut self, frame: &[u8]) {
+		self.outgoing
+			.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+		self.outgoing.extend_from_slice(frame);
+	}
+
+	// Writes as much of the queue as the socket takes without blocking
+	fn flush(&mut self) -> io::Result<()> {
+		while !self.outgoing.is_empty() {
+			match self.stream.write(&self.outgoing) {
+				Ok(0) => return Err(ErrorKind::WriteZero.into()),
+				Ok(written) => {
+					self.outgoing.drain(..written);
+				}
+				Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+				Err(error) if error.kind() == ErrorKind::Interrupted => {}
+				Err(error) => return Err(error),
+			}
+		}
+		Ok(())
+	}
+
+	// The whole frames that have arrived, and whether the other side closed the stream
+	fn receive(&mut self) -> io::Result<(Vec<Vec<u8>>, bool)> {
+		let mut buffer = [0; 4096];
+		let closed = loop {
+			match self.stream.read(&mut buffer) {
+				Ok(0) => break true,
+				Ok(read) => self.incoming.extend_from_slice(&buffer[..read]),
+				Err(error) if error.kind() == ErrorKind::WouldBlock => break false,
+				Err(error) if error.kind() == ErrorKind::Interrupted => {}
+				Err(error) => return Err(error),
+			}
+		};
+		let mut frames = Vec::new();
+		while let Some(header) = self.incoming.get(..4) {
+			let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+			if length > MAX_FRAME {
+				return Err(io::Error::new(ErrorKind::InvalidData, "Frame too large"));
+			}
+			if self.incoming.len() < 4 + length {
+				break;
+			}
+			frames.push(self.incoming[4..4 + length].to_vec());
+			self.incoming.drain(..4 + length);
+		}
+		Ok((frames, closed))
+	}
+}
+
+struct Connection {
+	stream: Stream,
+	// Learned from the client's first datagram
+	address: Option<SocketAddr>,
+}
+
+/// Accepts clients over TCP and exchanges datagrams with them over UDP on the same port.
+/// Nothing blocks, so it's polled once a frame.
+pub struct NetServer {
+	listener: TcpListener,
+	socket: UdpSocket,
+	clients: HashMap<ClientId, Connection>,
+	next_client: ClientId,
+}
+
+impl NetServer {
+	pub fn bind(address: impl ToSocketAddrs) -> Result<Self, NetError> {
+		let listener = TcpListener::bind(address)?;
+		let socket = UdpSocket::bind(listener.local_addr()?)?;
+		listener.set_nonblocking(true)?;
+		socket.set_nonblocking(true)?;
+		Ok(Self {
+			listener,
+			socket,
+			clients: HashMap::new(),
+			next_client: 1,
+		})
+	}
+
+	/// The address clients connect to, such as to find the port when bound to port zero
+	pub fn local_address(&self) -> Result<SocketAddr, NetError> {
+		Ok(self.listener.local_addr()?)
+	}
+
+	/// Connected clients, in the order they connected
+	pub fn clients(&self) -> Vec<ClientId> {
+		let mut clients = self.clients.keys().copied().collect::<Vec<_>>();
+		clients.sort_unstable();
+		clients
+	}
+
+	/// Accepts new clients, sends what's queued and reads what has arrived
+	pub fn poll(&mut self) -> Vec<NetEvent> {
+		let mut events = Vec::new();
+		while let Ok((stream, _)) = self.listener.accept() {
+			let Ok(mut stream) = Stream::new(stream) else {
+				continue;
+			};
+			let client = self.next_client;
+			self.next_client += 1;
+			if let Ok(welcome) = serde_json::to_vec(&Packet::Welcome(client)) {
+				stream.queue(&welcome);
+			}
+			self.clients.insert(
+				client,
+				Connection {
+					stream,
+					address: None,
+				},
+			);
+			events.push(NetEvent::Connected(client));
+		}
+
+		let mut disconnected = Vec::new();
+		for (client, connection) in &mut self.clients {
+			let received = connection
+				.stream
+				.flush()
+				.and_then(|_| connection.stream.receive());
+			let Ok((frames, closed)) = received else {
+				disconnected.push(*client);
+				continue;
+			};
+			for frame in frames {
+				// Clients sending what isn't a packet are dropped
+				match serde_json::from_slice(&frame) {
+					Ok(packet) => events.push(NetEvent::Received(*client, packet)),
+					Err(_) => {
+						disconnected.push(*client);
+						break;
+					}
+				}
+			}
+			if closed && !disconnected.contains(client) {
+				disconnected.push(*client);
+			}
+		}
+		disconnected.sort_unstable();
+		for client in disconnected {
+			self.disconnect(client);
+			events.push(NetEvent::Disconnected(client));
+		}
+
+		events.extend(self.receive_datagrams());
+		events
+	}
+
+	fn receive_datagrams(&mut self) -> Vec<NetEvent> {
+		let mut events = Vec::new();
+		let mut buffer = [0; 65536];
+		while let Ok((length, address)) = self.socket.recv_from(&mut buffer) {
+			// Datagrams carry the client's id, and are only taken from the client's own host
+			let Ok((client, packet)) =
+				serde_json::from_slice::<(ClientId, Packet)>(&buffer[..length])
+			else {
+				continue;
+			};
+			let Some(connection) = self.clients.get_mut(&client) else {
+				continue;
+			};
+			if connection
+				.stream
+				.stream
+				.peer_addr()
+				.map(|peer| peer.ip())
+				.ok() != Some(address.ip())
+			{
+				continue;
+			}
+			connection.address = Some(address);
+			if packet != Packet::Hello {
+				events.push(NetEvent::Received(client, packet));
+			}
+		}
+		events
+	}
+
+	pub fn send(
+		&mut self,
+		client: ClientId,
+		packet: &Packet,
+		delivery: Delivery,
+	) -> Result<(), NetError> {
+		let connection = self
+			.clients
+			.get_mut(&client)
+			.ok_or(NetError::UnknownClient(client))?;
+		let bytes = serde_json::to_vec(packet)?;
+		match connection.address {
+			Some(address) if delivery == Delivery::Unreliable && bytes.len() <= MAX_DATAGRAM => {
+				// A full socket buffer drops the datagram, as the network could have
+				match self.socket.send_to(&bytes, address) {
+					Err(error) if error.kind() != ErrorKind::WouldBlock => return Err(error.into()),
+					_ => {}
+				}
+			}
+			_ => connection.stream.queue(&bytes),
+		}
+		Ok(())
+	}
+
+	pub fn broadcast(&mut self, packet: &Packet, delivery: Delivery) -> Result<(), NetError> {
+		for client in self.clients() {
+			self.send(client, packet, delivery)?;
+		}
+		Ok(())
+	}
+
+	/// Closes the client's connection, without a `Disconnected` event
+	pub fn disconnect(&mut self, client: ClientId) {
+		if let Some(connection) = self.clients.remove(&client) {
+			// Sends what it can of the queue first
+			let mut stream = connection.stream;
+			let _ = stream.flush();
+			let _ = stream.stream.shutdown(Shutdown::Both);
+		}
+	}
+}
+
+/// A connection to a `NetServer`, polled once a frame
+pub struct NetClient {
+	stream: Option<Stream>,
+	socket: UdpSocket,
+	id: Option<ClientId>,
+}
+
+impl NetClient {
+	pub fn connect(address: impl ToSocketAddrs) -> Result<Self, NetError> {
+		let stream = TcpStream::connect(address)?;
+		let server = stream.peer_addr()?;
+		let local = match server {
+			SocketAddr::V4(_) => SocketAddr::from(([0, 0, 0, 0], 0)),
+			SocketAddr::V6(_) => SocketAddr::from(([0; 16], 0)),
+		};
+		let socket = UdpSocket::bind(local)?;
+		socket.connect(server)?;
+		socket.set_nonblocking(true)?;
+		Ok(Self {
+			stream: Some(Stream::new(stream)?),
+			socket,
+			id: None,
+		})
+	}
+
+	/// The id the server gave this client, once it's connected
+	pub fn id(&self) -> Option<ClientId> {
+		self.id
+	}
+
+	pub fn is_connected(&self) -> bool {
+		self.stream.is_some() && self.id.is_some()
+	}
+
+	/// Sends what's queued and reads what has arrived
+	pub fn poll(&mut self) -> Vec<NetEvent> {
+		let mut events = Vec::new();
+		let Some(stream) = &mut self.stream else {
+			return events;
+		};
+		let (frames, closed) = stream
+			.flush()
+			.and_then(|_| stream.receive())
+			.unwrap_or((Vec::new(), true));
+		for frame in frames {
+			match serde_json::from_slice(&frame) {
+				Ok(Packet::Welcome(id)) => {
+					self.id = Some(id);
+					events.push(NetEvent::Connected(id));
+					// Lets the server send datagrams back
+					let _ = self.send(&Packet::Hello, Delivery::Unreliable);
+				}
+				Ok(packet) => events.extend(self.id.map(|id| NetEvent::Received(id, packet))),
+				Err(_) => {}
+			}
+		}
+
+		let mut buffer = [0; 65536];
+		while let Ok(length) = self.socket.recv(&mut buffer) {
+			if let (Some(id), Ok(packet)) = (self.id, serde_json::from_slice(&buffer[..length])) {
+				events.push(NetEvent::Received(id, packet));
+			}
+		}
+		if closed {
+			self.stream = None;
+			events.extend(self.id.map(NetEvent::Disconnected));
+		}
+		events
+	}
+
+	pub fn send(&mut self, packet: &Packet, delivery: Delivery) -> Result<(), NetError> {
+		let stream = self.stream.as_mut().ok_or(NetError::NotConnected)?;
+		match self.id {
+			Some(id) if delivery == Delivery::Unreliable => {
+				let bytes = serde_json::to_vec(&(id, packet))?;
+				if bytes.len() <= MAX_DATAGRAM {
+					match self.socket.send(&bytes) {
+						Err(error) if error.kind() != ErrorKind::WouldBlock => {
+							return Err(error.into())
+						}
+						_ => return Ok(()),
+					}
+				}
+				stream.queue(&serde_json::to_vec(packet)?);
+			}
+			_ => stream.queue(&serde_json::to_vec(packet)?),
+		}
+		Ok(())
+	}
+
+	/// Closes the connection, sending what it can of the queue first
+	pub fn disconnect(&mut self) {
+		if let Some(mut stream) = self.stream.take() {
+			let _ = stream.flush();
+			let _ = stream.stream.shutdown(Shutdown::Both);
+		}
+	}
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use super::*;
+	use std::{
+		thread,
+		time::{Duration, Instant},
+	};
+
+	// Polls until the condition holds, giving up after a few seconds
+	pub(crate) fn poll_until(mut poll: impl FnMut() -> bool) {
+		let start = Instant::now();
+		while !poll() {
+			assert!(start.elapsed() < Duration::from_secs(5), "Timed out");
+			thread::sleep(Duration::from_millis(1));
+		}
+	}
+
+	#[test]
+	fn clients_connect_exchange_packets_and_disconnect() -> Result<(), NetError> {
+		let mut server = NetServer::bind("127.0.0.1:0")?;
+		let mut client = NetClient::connect(server.local_address()?)?;
+		let mut server_events = Vec::new();
+		let mut client_events = Vec::new();
+		poll_until(|| {
+			server_events.extend(server.poll());
+			client_events.extend(client.poll());
+			client.is_connected()
+		});
+		let id = client.id().unwrap();
+		assert_eq!(server_events, [NetEvent::Connected(id)]);
+		assert_eq!(client_events, [NetEvent::Connected(id)]);
+		assert_eq!(server.clients(), [id]);
+
+		let message = |payload: &str| Packet::Message {
+			topic: "chat".to_string(),
+			payload: payload.to_string(),
+		};
+		client.send(&message("reliable"), Delivery::Reliable)?;
+		client.send(&message("unreliable"), Delivery::Unreliable)?;
+		server_events.clear();
+		poll_until(|| {
+			client.poll();
+			server_events.extend(server.poll());
+			server_events.len() == 2
+		});
+		assert!(server_events.contains(&NetEvent::Received(id, message("reliable"))));
+		assert!(server_events.contains(&NetEvent::Received(id, message("unreliable"))));
+
+		// Too big for a datagram, so it goes over TCP
+		let large = message(&"x".repeat(MAX_DATAGRAM * 2));
+		server.broadcast(&large, Delivery::Unreliable)?;
+		client_events.clear();
+		poll_until(|| {
+			server.poll();
+			client_events.extend(client.poll());
+			!client_events.is_empty()
+		});
+		assert_eq!(client_events, [NetEvent::Received(id, large)]);
+
+		client.disconnect();
+		server_events.clear();
+		poll_until(|| {
+			server_events.extend(server.poll());
+			!server_events.is_empty()
+		});
+		assert_eq!(server_events, [NetEvent::Disconnected(id)]);
+		assert!(matches!(
+			server.send(id, &Packet::Hello, Delivery::Reliable),
+			Err(NetError::UnknownClient(_))
+		));
+		assert!(matches!(
+			client.send(&Packet::Hello, Delivery::Reliable),
+			Err(NetError::NotConnected)
+		));
+		Ok(())
+	}
+}
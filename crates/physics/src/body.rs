@@ -0,0 +1,161 @@
+use math::{Aabb, Mat4, Transform, Vec3};
+use rapier3d::{dynamics::RigidBodyType, geometry::SharedShape};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BodyKind {
+	/// Moved by gravity, its velocity and what it hits
+	#[default]
+	Dynamic,
+	/// Moved only by its velocity, pushing dynamic bodies out of its way
+	Kinematic,
+	/// Never moves, such as the ground
+	Fixed,
+}
+
+/// Makes the entity's `Transform` move under the simulation. Bodies are simulated
+/// in their parent's space, so they're best kept at the root of the hierarchy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RigidBody {
+	pub kind: BodyKind,
+	pub linear_velocity: Vec3,
+	/// Turns the body around this axis, in radians per second by its length
+	pub angular_velocity: Vec3,
+	pub mass: f32,
+	/// How much velocity is lost each second, from 0.0 for none
+	pub linear_damping: f32,
+	pub gravity_scale: f32,
+}
+
+impl Default for RigidBody {
+	fn default() -> Self {
+		Self {
+			kind: BodyKind::Dynamic,
+			linear_velocity: Vec3::ZERO,
+			angular_velocity: Vec3::ZERO,
+			mass: 1.0,
+			linear_damping: 0.0,
+			gravity_scale: 1.0,
+		}
+	}
+}
+
+impl RigidBody {
+	pub fn dynamic() -> Self {
+		Self::default()
+	}
+
+	pub fn kinematic() -> Self {
+		Self {
+			kind: BodyKind::Kinematic,
+			..Self::default()
+		}
+	}
+
+	pub fn fixed() -> Self {
+		Self {
+			kind: BodyKind::Fixed,
+			..Self::default()
+		}
+	}
+
+	pub fn with_velocity(self, linear_velocity: Vec3) -> Self {
+		Self {
+			linear_velocity,
+			..self
+		}
+	}
+
+	pub(crate) fn body_type(&self) -> RigidBodyType {
+		match self.kind {
+			BodyKind::Dynamic => RigidBodyType::Dynamic,
+			BodyKind::Kinematic => RigidBodyType::KinematicVelocityBased,
+			BodyKind::Fixed => RigidBodyType::Fixed,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shape {
+	Ball { radius: f32 },
+	Cuboid { half_extents: Vec3 },
+}
+
+/// The shape the entity collides as, centered on its `Transform` and scaled by it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Collider {
+	pub shape: Shape,
+	/// How much speed a bounce keeps, from 0.0 to 1.0
+	pub restitution: f32,
+	pub friction: f32,
+	/// Reports what overlaps it without pushing anything
+	pub sensor: bool,
+}
+
+impl Collider {
+	pub fn ball(radius: f32) -> Self {
+		Self::new(Shape::Ball { radius })
+	}
+
+	pub fn cuboid(half_x: f32, half_y: f32, half_z: f32) -> Self {
+		Self::new(Shape::Cuboid {
+			half_extents: Vec3::new(half_x, half_y, half_z),
+		})
+	}
+
+	pub fn sensor(self) -> Self {
+		Self {
+			sensor: true,
+			..self
+		}
+	}
+
+	pub fn with_restitution(self, restitution: f32) -> Self {
+		Self {
+			restitution,
+			..self
+		}
+	}
+
+	fn new(shape: Shape) -> Self {
+		Self {
+			shape,
+			restitution: 0.0,
+			friction: 0.5,
+			sensor: false,
+		}
+	}
+
+	/// The shape sized by the transform's scale
+	pub(crate) fn scaled(&self, transform: &Transform) -> Shape {
+		let scale = transform.scale;
+		let scale = Vec3::new(scale.x.abs(), scale.y.abs(), scale.z.abs());
+		match self.shape {
+			Shape::Ball { radius } => Shape::Ball {
+				radius: radius * scale.max_element(),
+			},
+			Shape::Cuboid { half_extents } => Shape::Cuboid {
+				half_extents: half_extents * scale,
+			},
+		}
+	}
+
+	/// The scaled shape as rapier builds colliders from
+	pub(crate) fn shared_shape(&self, transform: &Transform) -> SharedShape {
+		match self.scaled(transform) {
+			Shape::Ball { radius } => SharedShape::ball(radius),
+			Shape::Cuboid { half_extents } => {
+				SharedShape::cuboid(half_extents.x, half_extents.y, half_extents.z)
+			}
+		}
+	}
+
+	pub fn aabb(&self, transform: &Transform) -> Aabb {
+		let half_extents = match self.scaled(transform) {
+			Shape::Ball { radius } => Vec3::splat(radius),
+			Shape::Cuboid { half_extents } => half_extents,
+		};
+		Aabb::from_center_half_extents(Vec3::ZERO, half_extents).transformed(
+			&Mat4::from_rotation_translation(transform.rotation, transform.translation),
+		)
+	}
+}
@@ -0,0 +1,22 @@
+use math::Vec3;
+use rapier3d::geometry::ContactPair;
+
+/// Where two colliders overlap
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+	/// The direction from the first collider to the second
+	pub normal: Vec3,
+	/// How far they'd have to move apart along the normal to only touch
+	pub depth: f32,
+}
+
+impl Contact {
+	/// The deepest point of a pair rapier found touching
+	pub(crate) fn deepest(pair: &ContactPair) -> Option<Self> {
+		let (manifold, contact) = pair.find_deepest_contact()?;
+		Some(Self {
+			normal: manifold.data.normal,
+			depth: (-contact.dist).max(0.0),
+		})
+	}
+}
@@ -0,0 +1,141 @@
+use ecs::world::Entity;
+
+/// The bus channel collision events are published on, when the world has a
+/// `BusResource<CollisionEvent>`. It's added to the bus if it's missing.
+pub const COLLISION_CHANNEL: &str = "physics/collisions";
+
+/// Sent to the world's `Events<CollisionEvent>` when colliders start or stop touching
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollisionEvent {
+	Started(Entity, Entity),
+	Ended(Entity, Entity),
+	/// Something began overlapping a sensor, given as the sensor and then the other
+	TriggerEntered(Entity, Entity),
+	TriggerExited(Entity, Entity),
+}
+
+impl CollisionEvent {
+	pub fn entities(&self) -> (Entity, Entity) {
+		match *self {
+			Self::Started(a, b)
+			| Self::Ended(a, b)
+			| Self::TriggerEntered(a, b)
+			| Self::TriggerExited(a, b) => (a, b),
+		}
+	}
+
+	pub fn involves(&self, entity: Entity) -> bool {
+		let (a, b) = self.entities();
+		a == entity || b == entity
+	}
+
+	/// The topic the event is published under on the bus
+	pub fn topic(&self) -> &'static str {
+		match self {
+			Self::Started(..) => "started",
+			Self::Ended(..) => "ended",
+			Self::TriggerEntered(..) => "trigger_entered",
+			Self::TriggerExited(..) => "trigger_exited",
+		}
+	}
+}
+
+type Pairing = fn(Entity, Entity) -> CollisionEvent;
+
+/// The events for pairs that appear in one list of pairs and not the other
+pub(crate) fn changes(
+	previous: &[(Entity, Entity)],
+	current: &[(Entity, Entity)],
+	(began, ended): (Pairing, Pairing),
+) -> Vec<CollisionEvent> {
+	let missing = |pairs: &[(Entity, Entity)], (a, b): (Entity, Entity)| {
+		!pairs.iter().any(|pair| *pair == (a, b) || *pair == (b, a))
+	};
+	let ended = previous
+		.iter()
+		.filter(|pair| missing(current, **pair))
+		.map(|(a, b)| ended(*a, *b));
+	let began = current
+		.iter()
+		.filter(|pair| missing(previous, **pair))
+		.map(|(a, b)| began(*a, *b));
+	ended.chain(began).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{step_physics, Collider, RigidBody};
+	use bus::{resource::BusResource, EventBus, Message};
+	use ecs::{error::Result, events::Events, world::World};
+	use math::Transform;
+	use std::sync::Arc;
+
+	fn drain(world: &World) -> Vec<CollisionEvent> {
+		let mut resources = world.resources().borrow_mut();
+		let events = resources.get_mut::<Events<CollisionEvent>>().unwrap();
+		events.drain().collect()
+	}
+
+	#[test]
+	fn touching_starts_and_ends_on_events_and_the_bus() -> Result<()> {
+		let event_bus = Arc::new(EventBus::<CollisionEvent>::new());
+		let mut world = World::new();
+		world
+			.resources()
+			.borrow_mut()
+			.insert(BusResource::new(event_bus.clone()));
+		let [ground, ball, sensor] = [(); 3].map(|_| world.create_entity());
+		world.add_component(ground, Transform::default())?;
+		world.add_component(ground, Collider::cuboid(5.0, 0.5, 5.0))?;
+		world.add_component(ball, Transform::from_xyz(0.0, 1.5, 0.0))?;
+		world.add_component(ball, RigidBody::dynamic())?;
+		world.add_component(ball, Collider::ball(0.5))?;
+		world.add_component(sensor, Transform::from_xyz(0.0, 1.2, 0.0))?;
+		world.add_component(sensor, Collider::cuboid(1.0, 0.1, 1.0).sensor())?;
+
+		let mut sent = Vec::new();
+		for _ in 0..60 {
+			step_physics(&mut world, 1.0 / 60.0)?;
+			sent.extend(drain(&world));
+		}
+		assert!(sent.contains(&CollisionEvent::TriggerEntered(sensor, ball)));
+		assert!(sent
+			.iter()
+			.any(|event| matches!(event, CollisionEvent::Started(..)) && event.involves(ground)));
+		// Resting on the ground keeps the contact going without more events
+		assert_eq!(
+			sent.iter()
+				.filter(|event| matches!(event, CollisionEvent::Started(..)))
+				.count(),
+			1
+		);
+
+		world.remove_entity(ground);
+		step_physics(&mut world, 1.0 / 60.0)?;
+		let ended = drain(&world);
+		assert!(ended
+			.iter()
+			.any(|event| matches!(event, CollisionEvent::Ended(..))
+				&& event.involves(ground)
+				&& event.involves(ball)));
+		sent.extend(ended);
+
+		let mut published = Events::<Message<CollisionEvent>>::new();
+		world
+			.resources()
+			.borrow()
+			.get::<BusResource<CollisionEvent>>()
+			.unwrap()
+			.drain_into(COLLISION_CHANNEL, &mut published)?;
+		let published = published
+			.drain()
+			.map(|message| message.into_parts())
+			.collect::<Vec<_>>();
+		assert_eq!(published.len(), sent.len());
+		assert!(published
+			.iter()
+			.all(|(topic, event)| sent.contains(event) && topic == event.topic()));
+		Ok(())
+	}
+}
@@ -0,0 +1,14 @@
+#![forbid(unsafe_code)]
+
+mod body;
+mod contact;
+mod events;
+mod simulation;
+
+pub use self::{
+	body::{BodyKind, Collider, RigidBody, Shape},
+	contact::Contact,
+	events::{CollisionEvent, COLLISION_CHANNEL},
+	simulation::{step_physics, PhysicsConfig, PhysicsState},
+};
+pub use rapier3d;
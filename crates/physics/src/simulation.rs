@@ -0,0 +1,501 @@
+use crate::{
+	events::{changes, CollisionEvent, COLLISION_CHANNEL},
+	Collider, Contact, RigidBody,
+};
+use bus::resource::BusResource;
+use ecs::{
+	error::Result,
+	events::Events,
+	world::{Entity, World},
+};
+use math::{Transform, Vec3};
+use rapier3d::{
+	dynamics::{CoefficientCombineRule, RigidBodyBuilder, RigidBodyHandle},
+	geometry::{ActiveCollisionTypes, ColliderBuilder, ColliderHandle},
+	math::Pose,
+	pipeline::PhysicsWorld,
+};
+use std::{collections::HashMap, fmt};
+
+/// The simulation's settings, read from the world's resources or defaulted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhysicsConfig {
+	pub gravity: Vec3,
+	/// Seconds simulated by each step
+	pub timestep: f32,
+	/// The most steps taken in one call, so a slow frame can't snowball into slower ones
+	pub max_substeps: u32,
+	/// How many times contacts are solved each step, trading speed for stiffer stacks
+	pub solver_iterations: u32,
+}
+
+impl Default for PhysicsConfig {
+	fn default() -> Self {
+		Self {
+			gravity: Vec3::new(0.0, -9.81, 0.0),
+			timestep: 1.0 / 60.0,
+			max_substeps: 8,
+			solver_iterations: 4,
+		}
+	}
+}
+
+// The rapier objects mirroring an entity, with the collider and scale its collider was built from
+#[derive(Default)]
+struct Synced {
+	body: Option<RigidBodyHandle>,
+	collider: Option<(ColliderHandle, Collider, Vec3)>,
+}
+
+/// What the simulation keeps between calls, stored as a world resource. Bodies and
+/// colliders are mirrored into a rapier world, which does the simulating.
+#[derive(Default)]
+pub struct PhysicsState {
+	accumulator: f32,
+	world: PhysicsWorld,
+	synced: HashMap<Entity, Synced>,
+	entities: HashMap<ColliderHandle, Entity>,
+	contacts: Vec<(Entity, Entity, Contact)>,
+	triggers: Vec<(Entity, Entity)>,
+}
+
+impl fmt::Debug for PhysicsState {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("PhysicsState")
+			.field("accumulator", &self.accumulator)
+			.field("contacts", &self.contacts)
+			.field("triggers", &self.triggers)
+			.finish_non_exhaustive()
+	}
+}
+
+impl PhysicsState {
+	/// The pairs of solid colliders touching after the last step
+	pub fn contacts(&self) -> &[(Entity, Entity, Contact)] {
+		&self.contacts
+	}
+
+	/// The sensors and what overlapped them after the last step
+	pub fn triggers(&self) -> &[(Entity, Entity)] {
+		&self.triggers
+	}
+
+	/// Whether the colliders touched or overlapped after the last step
+	pub fn in_contact(&self, a: Entity, b: Entity) -> bool {
+		self.contacts
+			.iter()
+			.map(|(first, second, _)| (*first, *second))
+			.chain(self.triggers.iter().copied())
+			.any(|pair| pair == (a, b) || pair == (b, a))
+	}
+
+	/// The rapier world the entities are simulated in, such as for scene queries
+	pub fn rapier_world(&self) -> &PhysicsWorld {
+		&self.world
+	}
+
+	/// The entity a rapier collider stands for
+	pub fn collider_entity(&self, collider: ColliderHandle) -> Option<Entity> {
+		self.entities.get(&collider).copied()
+	}
+
+	fn update(
+		&mut self,
+		contacts: Vec<(Entity, Entity, Contact)>,
+		triggers: Vec<(Entity, Entity)>,
+	) -> Vec<CollisionEvent> {
+		let pairs = |contacts: &[(Entity, Entity, Contact)]| {
+			contacts
+				.iter()
+				.map(|(a, b, _)| (*a, *b))
+				.collect::<Vec<_>>()
+		};
+		let mut events = changes(
+			&pairs(&self.contacts),
+			&pairs(&contacts),
+			(CollisionEvent::Started, CollisionEvent::Ended),
+		);
+		events.extend(changes(
+			&self.triggers,
+			&triggers,
+			(
+				CollisionEvent::TriggerEntered,
+				CollisionEvent::TriggerExited,
+			),
+		));
+		self.contacts = contacts;
+		self.triggers = triggers;
+		events
+	}
+
+	fn configure(&mut self, config: &PhysicsConfig) {
+		self.world.gravity = config.gravity;
+		let parameters = &mut self.world.integration_parameters;
+		parameters.dt = config.timestep;
+		parameters.num_solver_iterations = config.solver_iterations.max(1) as usize;
+	}
+
+	// Brings rapier in line with the components, which systems may have changed since the last step
+	fn sync(&mut self, world: &World) {
+		let bodies = gather(world);
+		let stale = self
+			.synced
+			.iter()
+			.filter(|(entity, synced)| {
+				bodies
+					.get(entity)
+					.is_none_or(|(transform, body, collider)| {
+						let shape = synced
+							.collider
+							.map(|(_, collider, scale)| (collider, scale));
+						synced.body.is_some() != body.is_some()
+							|| shape != collider.map(|collider| (collider, transform.scale))
+					})
+			})
+			.map(|(entity, _)| *entity)
+			.collect::<Vec<_>>();
+		for entity in stale {
+			self.remove(entity);
+		}
+		for (entity, components) in bodies {
+			if !self.synced.contains_key(&entity) {
+				self.insert(entity, &components);
+			}
+			let (transform, body, _) = components;
+			let synced = &self.synced[&entity];
+			let pose = Pose::from_parts(transform.translation, transform.rotation);
+			match body {
+				Some(body) => self.update_body(entity, &body, pose),
+				None => {
+					if let Some((handle, ..)) = synced.collider {
+						let collider = &mut self.world.colliders[handle];
+						if *collider.position() != pose {
+							collider.set_position(pose);
+						}
+					}
+				}
+			}
+		}
+	}
+
+	fn insert(&mut self, entity: Entity, (transform, body, collider): &Components) {
+		let pose = Pose::from_parts(transform.translation, transform.rotation);
+		let body_handle = body.map(|body| {
+			self.world
+				.insert_body(RigidBodyBuilder::new(body.body_type()).pose(pose))
+		});
+		let collider = collider.map(|collider| {
+			let mut builder = ColliderBuilder::new(collider.shared_shape(transform))
+				.sensor(collider.sensor)
+				.friction(collider.friction)
+				.restitution(collider.restitution)
+				.restitution_combine_rule(CoefficientCombineRule::Max)
+				.active_collision_types(
+					ActiveCollisionTypes::default()
+						| ActiveCollisionTypes::KINEMATIC_KINEMATIC
+						| ActiveCollisionTypes::KINEMATIC_FIXED,
+				);
+			if body_handle.is_none() {
+				builder = builder.position(pose);
+			}
+			let handle = self.world.insert_collider(builder, body_handle);
+			self.entities.insert(handle, entity);
+			(handle, collider, transform.scale)
+		});
+		self.synced.insert(
+			entity,
+			Synced {
+				body: body_handle,
+				collider,
+			},
+		);
+	}
+
+	fn remove(&mut self, entity: Entity) {
+		let Some(synced) = self.synced.remove(&entity) else {
+			return;
+		};
+		if let Some((handle, ..)) = synced.collider {
+			self.entities.remove(&handle);
+			self.world.remove_collider(handle);
+		}
+		if let Some(handle) = synced.body {
+			self.world.remove_body(handle);
+		}
+	}
+
+	// Only what changed is set, so resting bodies are left asleep
+	fn update_body(&mut self, entity: Entity, body: &RigidBody, pose: Pose) {
+		let Some(Synced {
+			body: Some(handle),
+			collider,
+		}) = self.synced.get(&entity)
+		else {
+			return;
+		};
+		let (handle, collider) = (*handle, collider.map(|(collider, ..)| collider));
+		match collider {
+			// The collider carries the mass, so it turns with the body
+			Some(collider) => {
+				let collider = &mut self.world.colliders[collider];
+				if collider.mass() != body.mass {
+					collider.set_mass(body.mass);
+				}
+			}
+			None => {
+				let rapier_body = &mut self.world.bodies[handle];
+				if rapier_body.mass() != body.mass {
+					rapier_body.set_additional_mass(body.mass, true);
+				}
+			}
+		}
+		let rapier_body = &mut self.world.bodies[handle];
+		if rapier_body.body_type() != body.body_type() {
+			rapier_body.set_body_type(body.body_type(), true);
+		}
+		if *rapier_body.position() != pose {
+			rapier_body.set_position(pose, true);
+		}
+		if rapier_body.linvel() != body.linear_velocity {
+			rapier_body.set_linvel(body.linear_velocity, true);
+		}
+		if rapier_body.angvel() != body.angular_velocity {
+			rapier_body.set_angvel(body.angular_velocity, true);
+		}
+		if rapier_body.linear_damping() != body.linear_damping {
+			rapier_body.set_linear_damping(body.linear_damping);
+		}
+		if rapier_body.gravity_scale() != body.gravity_scale {
+			rapier_body.set_gravity_scale(body.gravity_scale, true);
+		}
+	}
+
+	// Returns the solid contacts and the sensor overlaps, sensor first
+	fn touching(&self) -> Touching {
+		let entity = |handle| self.collider_entity(handle);
+		let narrow_phase = &self.world.narrow_phase;
+		let contacts = narrow_phase
+			.contact_pairs()
+			.filter(|pair| pair.has_any_active_contact())
+			.filter_map(|pair| {
+				Some((
+					entity(pair.collider1)?,
+					entity(pair.collider2)?,
+					Contact::deepest(pair)?,
+				))
+			})
+			.collect();
+		let triggers = narrow_phase
+			.intersection_pairs()
+			.filter(|(.., intersecting)| *intersecting)
+			.filter_map(|(a, b, _)| {
+				let pair = (entity(a)?, entity(b)?);
+				Some(match self.world.colliders.get(a)?.is_sensor() {
+					true => pair,
+					false => (pair.1, pair.0),
+				})
+			})
+			.collect();
+		(contacts, triggers)
+	}
+
+	// Copies where rapier moved the bodies back onto their components
+	fn write_back(&self, world: &World) {
+		for (entity, synced) in &self.synced {
+			let Some(rapier_body) = synced.body.and_then(|handle| self.world.bodies.get(handle))
+			else {
+				continue;
+			};
+			if let Some(mut transform) = world.get_component_mut::<Transform>(*entity) {
+				transform.translation = rapier_body.translation();
+				transform.rotation = *rapier_body.rotation();
+			}
+			if let Some(mut body) = world.get_component_mut::<RigidBody>(*entity) {
+				body.linear_velocity = rapier_body.linvel();
+				body.angular_velocity = rapier_body.angvel();
+			}
+		}
+	}
+}
+
+/// Advances every `RigidBody` by as many fixed steps as fit in the time passed,
+/// carrying the remainder over to the next call. Transforms are read before
+/// stepping and written back after, so systems can move bodies directly.
+/// Colliders without a body stay where they are. Colliders starting or stopping
+/// touching are sent as `Events<CollisionEvent>`, and published on the bus too
+/// when the world has a `BusResource<CollisionEvent>`. Returns the steps taken.
+pub fn step_physics(world: &mut World, delta: f32) -> Result<u32> {
+	let config = world
+		.resources()
+		.borrow()
+		.get::<PhysicsConfig>()
+		.copied()
+		.unwrap_or_default();
+	let mut state = world
+		.resources()
+		.borrow_mut()
+		.get_mut::<PhysicsState>()
+		.map(std::mem::take)
+		.unwrap_or_default();
+	let timestep = config.timestep.max(f32::EPSILON);
+	state.accumulator += delta.max(0.0);
+	let mut steps = 0;
+	let mut events = Vec::new();
+	if state.accumulator >= timestep {
+		state.configure(&PhysicsConfig { timestep, ..config });
+		state.sync(world);
+		while state.accumulator >= timestep && steps < config.max_substeps {
+			state.world.step();
+			let (contacts, triggers) = state.touching();
+			events.extend(state.update(contacts, triggers));
+			state.accumulator -= timestep;
+			steps += 1;
+		}
+		// Time that couldn't be simulated is dropped rather than caught up on later
+		state.accumulator = state.accumulator.min(timestep);
+		state.write_back(world);
+	}
+	world.resources().borrow_mut().insert(state);
+	send_events(world, events)?;
+	Ok(steps)
+}
+
+fn send_events(world: &World, events: Vec<CollisionEvent>) -> Result<()> {
+	if events.is_empty() {
+		return Ok(());
+	}
+	let mut resources = world.resources().borrow_mut();
+	if let Some(bus) = resources.get::<BusResource<CollisionEvent>>() {
+		// Fails only when the channel already exists
+		let _ = bus.event_bus().add_channel(COLLISION_CHANNEL);
+		for event in &events {
+			bus.publish_sync(COLLISION_CHANNEL, event.topic(), *event)?;
+		}
+	}
+	match resources.get_mut::<Events<CollisionEvent>>() {
+		Some(queue) => queue.extend(events),
+		None => {
+			let mut queue = Events::new();
+			queue.extend(events);
+			resources.insert(queue);
+		}
+	}
+	Ok(())
+}
+
+type Components = (Transform, Option<RigidBody>, Option<Collider>);
+
+fn gather(world: &World) -> HashMap<Entity, Components> {
+	world
+		.entities()
+		.into_iter()
+		.filter_map(|entity| {
+			let transform = *world.get_component::<Transform>(entity)?;
+			let body = world.get_component::<RigidBody>(entity).map(|body| *body);
+			let collider = world
+				.get_component::<Collider>(entity)
+				.map(|collider| *collider);
+			(body.is_some() || collider.is_some()).then_some((entity, (transform, body, collider)))
+		})
+		.collect()
+}
+
+type Touching = (Vec<(Entity, Entity, Contact)>, Vec<(Entity, Entity)>);
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn bodies_fall_onto_colliders_in_fixed_steps() -> Result<()> {
+		let mut world = World::new();
+		let [ground, ball, sensor, ghost] = [(); 4].map(|_| world.create_entity());
+		world.add_component(ground, Transform::default())?;
+		world.add_component(ground, Collider::cuboid(5.0, 0.5, 5.0))?;
+		world.add_component(ball, Transform::from_xyz(0.0, 3.0, 0.0))?;
+		world.add_component(ball, RigidBody::dynamic())?;
+		world.add_component(ball, Collider::ball(0.5))?;
+		// A sensor sits in the ball's way without stopping it
+		world.add_component(sensor, Transform::from_xyz(0.0, 2.0, 0.0))?;
+		world.add_component(sensor, Collider::cuboid(1.0, 0.1, 1.0).sensor())?;
+		world.add_component(ghost, Transform::from_xyz(3.0, 3.0, 0.0))?;
+		world.add_component(ghost, RigidBody::dynamic().with_velocity(Vec3::X))?;
+
+		// Three steps fit and the rest carries over
+		assert_eq!(step_physics(&mut world, 0.06)?, 3);
+		assert_eq!(step_physics(&mut world, 0.01)?, 1);
+		// A long stall is capped
+		assert_eq!(step_physics(&mut world, 10.0)?, 8);
+
+		let mut touched_sensor = false;
+		for _ in 0..180 {
+			step_physics(&mut world, 1.0 / 60.0)?;
+			let resources = world.resources().borrow();
+			let state = resources.get::<PhysicsState>().unwrap();
+			touched_sensor |= state.in_contact(ball, sensor);
+		}
+		assert!(touched_sensor);
+
+		// The ball rests on top of the ground
+		let transform = *world.get_component::<Transform>(ball).unwrap();
+		assert!((transform.translation.y - 1.0).abs() < 0.02);
+		let body = *world.get_component::<RigidBody>(ball).unwrap();
+		assert!(body.linear_velocity.length() < 0.1);
+		// Bodies without colliders fall through everything
+		let transform = *world.get_component::<Transform>(ghost).unwrap();
+		assert!(transform.translation.y < -10.0);
+		assert!(transform.translation.x > 3.0);
+
+		// Gameplay can move a body between steps
+		world.add_component(ball, Transform::from_xyz(0.0, 5.0, 0.0))?;
+		step_physics(&mut world, 1.0 / 60.0)?;
+		let transform = *world.get_component::<Transform>(ball).unwrap();
+		assert!(transform.translation.y > 4.9);
+		Ok(())
+	}
+
+	#[test]
+	fn component_changes_reach_the_simulation() -> Result<()> {
+		let mut world = World::new();
+		let [ground, ball, spinner, mover] = [(); 4].map(|_| world.create_entity());
+		world.add_component(ground, Transform::default())?;
+		world.add_component(ground, Collider::cuboid(5.0, 0.5, 5.0))?;
+		world.add_component(ball, Transform::from_xyz(0.0, 1.0, 0.0))?;
+		world.add_component(ball, RigidBody::dynamic())?;
+		world.add_component(ball, Collider::ball(0.5))?;
+		world.add_component(spinner, Transform::from_xyz(10.0, 0.0, 0.0))?;
+		world.add_component(
+			spinner,
+			RigidBody {
+				angular_velocity: Vec3::Z,
+				gravity_scale: 0.0,
+				..RigidBody::dynamic()
+			},
+		)?;
+		world.add_component(mover, Transform::from_xyz(-10.0, 0.0, 0.0))?;
+		world.add_component(mover, RigidBody::kinematic().with_velocity(Vec3::X))?;
+		for _ in 0..60 {
+			step_physics(&mut world, 1.0 / 60.0)?;
+		}
+
+		{
+			let resources = world.resources().borrow();
+			let state = resources.get::<PhysicsState>().unwrap();
+			let (first, _, contact) = state.contacts()[0];
+			let upwards = if first == ground { 1.0 } else { -1.0 };
+			assert!(contact.normal.distance(Vec3::Y * upwards) < 1e-3);
+		}
+		let transform = |world: &World, entity| *world.get_component::<Transform>(entity).unwrap();
+		assert!((transform(&world, spinner).angle_z() - 1.0).abs() < 0.05);
+		// Kinematic bodies keep their velocity and ignore gravity
+		assert!((transform(&world, mover).translation - Vec3::new(-9.0, 0.0, 0.0)).length() < 0.05);
+
+		// Without its collider the ball falls through the ground
+		world.remove_component::<Collider>(ball)?;
+		for _ in 0..60 {
+			step_physics(&mut world, 1.0 / 60.0)?;
+		}
+		assert!(transform(&world, ball).translation.y < 0.0);
+		Ok(())
+	}
+}
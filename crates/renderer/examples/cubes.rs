@@ -0,0 +1,193 @@
+use app::{
+	async_trait::async_trait, tokio, winit::window::Window, App, AppConfig, AppEvent, Context,
+	Frame, State, StateResult, Transition, WindowId,
+};
+use ecs::{system, world::World};
+use renderer::{
+	extract_meshes,
+	math::{Color, Quat, Transform, Vec3},
+	wgpu, Camera, DirectionalLight, Material, Mesh, MeshData, MeshRenderer, PointLight,
+};
+use std::{error::Error, sync::Arc, time::Instant};
+
+fn create_world() -> World {
+	let mut world = World::new();
+	let camera = world.create_entity();
+	let _ = world.add_component(
+		camera,
+		Transform::from_xyz(10.0, 10.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y),
+	);
+	let _ = world.add_component(
+		camera,
+		Camera {
+			clear_color: Color::rgb(0.01, 0.01, 0.02),
+			..Camera::perspective(60.0)
+		},
+	);
+	let sun = world.create_entity();
+	let _ = world.add_component(sun, DirectionalLight::default());
+	let lamp = world.create_entity();
+	let _ = world.add_component(lamp, Transform::from_xyz(0.0, 4.0, 0.0));
+	let _ = world.add_component(
+		lamp,
+		PointLight {
+			color: Color::rgb(1.0, 0.6, 0.3),
+			intensity: 20.0,
+			..PointLight::default()
+		},
+	);
+
+	// Spread around a spiral, as the golden angle never lines them up
+	for index in 0..10 {
+		let angle = index as f32 * 2.4;
+		let radius = 1.5 + index as f32 * 0.4;
+		let cube = world.create_entity();
+		let _ = world.add_component(
+			cube,
+			Transform::from_xyz(
+				angle.cos() * radius,
+				index as f32 - 5.0,
+				angle.sin() * radius,
+			),
+		);
+		let _ = world.add_component(cube, Mesh::new("cube"));
+		let _ = world.add_component(cube, Material::color(Color::GREEN));
+	}
+	world
+}
+
+system!(rotation_system, [_resources, _entity], (degrees: f32), (transform: Transform) -> ecs::error::Result<()> {
+	transform.rotate(Quat::from_rotation_y(degrees.to_radians()));
+	Ok(())
+});
+
+system!(color_system, [_resources, _entity], (seconds: f32), (material: Material) -> ecs::error::Result<()> {
+	material.base_color = Color::rgb(seconds.sin().abs(), seconds.cos().abs(), 0.5);
+	Ok(())
+});
+
+struct Surface {
+	surface: wgpu::Surface<'static>,
+	device: wgpu::Device,
+	queue: wgpu::Queue,
+	config: wgpu::SurfaceConfiguration,
+	meshes: MeshRenderer,
+}
+
+impl Surface {
+	async fn new(window: Arc<Window>) -> StateResult<Self> {
+		let instance = wgpu::Instance::default();
+		let surface = instance.create_surface(window.clone())?;
+		let adapter = instance
+			.request_adapter(&wgpu::RequestAdapterOptions {
+				compatible_surface: Some(&surface),
+				..Default::default()
+			})
+			.await?;
+		let (device, queue) = adapter
+			.request_device(&wgpu::DeviceDescriptor::default())
+			.await?;
+		let size = window.inner_size();
+		let config = surface
+			.get_default_config(&adapter, size.width.max(1), size.height.max(1))
+			.ok_or("The window surface isn't supported by the adapter")?;
+		surface.configure(&device, &config);
+		let mut meshes = MeshRenderer::new(&device, &queue, config.format);
+		meshes.set_mesh("cube", &MeshData::cube(1.0));
+		Ok(Self {
+			surface,
+			device,
+			queue,
+			config,
+			meshes,
+		})
+	}
+}
+
+struct Cubes {
+	surface: Option<Surface>,
+	start: Instant,
+}
+
+#[async_trait]
+impl State<Context, AppEvent> for Cubes {
+	async fn on_resume_app(&mut self, context: &mut Context) -> StateResult<()> {
+		if let Some(window) = context.window(WindowId::PRIMARY) {
+			self.surface = Some(Surface::new(window).await?);
+		}
+		Ok(())
+	}
+
+	async fn on_suspend_app(&mut self, _context: &mut Context) -> StateResult<()> {
+		self.surface = None;
+		Ok(())
+	}
+
+	async fn update(
+		&mut self,
+		context: &mut Context,
+	) -> StateResult<Transition<Context, AppEvent>> {
+		let seconds = self.start.elapsed().as_secs_f32();
+		if let Some(world) = context.world() {
+			world
+				.with(move |world| {
+					rotation_system(0.8, world)
+						.and_then(|_| color_system(seconds, world))
+						.map_err(|error| error.to_string())
+				})
+				.await??;
+		}
+		Ok(Transition::None)
+	}
+
+	async fn on_event(
+		&mut self,
+		_context: &mut Context,
+		event: &mut AppEvent,
+	) -> StateResult<Transition<Context, AppEvent>> {
+		if let (AppEvent::Resized { width, height, .. }, Some(surface)) = (event, &mut self.surface)
+		{
+			if *width > 0 && *height > 0 {
+				surface.config.width = *width;
+				surface.config.height = *height;
+				surface.surface.configure(&surface.device, &surface.config);
+			}
+		}
+		Ok(Transition::None)
+	}
+
+	async fn on_render(&mut self, context: &mut Context, _frame: Frame) -> StateResult<()> {
+		let (Some(surface), Some(world)) = (self.surface.as_mut(), context.world()) else {
+			return Ok(());
+		};
+		let extracted = world.with(|world| extract_meshes(world)).await?;
+		let frame = match surface.surface.get_current_texture() {
+			wgpu::CurrentSurfaceTexture::Success(frame)
+			| wgpu::CurrentSurfaceTexture::Suboptimal(frame) => frame,
+			_ => {
+				surface.surface.configure(&surface.device, &surface.config);
+				return Ok(());
+			}
+		};
+		surface
+			.meshes
+			.prepare(&extracted, surface.config.width, surface.config.height);
+		let view = frame.texture.create_view(&Default::default());
+		let mut encoder = surface.device.create_command_encoder(&Default::default());
+		surface.meshes.render(&mut encoder, &view);
+		surface.queue.submit([encoder.finish()]);
+		surface.queue.present(frame);
+		Ok(())
+	}
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+	let mut builder = App::builder(AppConfig::from_args()?);
+	builder.with_world(create_world);
+	builder.build()?.run(Cubes {
+		surface: None,
+		start: Instant::now(),
+	});
+	Ok(())
+}
@@ -0,0 +1,227 @@
+use app::{
+	async_trait::async_trait, tokio, winit::window::Window, App, AppConfig, AppEvent, Context,
+	Frame, State, StateResult, Transition, WindowId,
+};
+use ecs::{izip, system, world::World};
+use renderer::{
+	extract_sprites,
+	math::{Color, Quat, Transform},
+	wgpu, Camera, Sprite, SpriteRenderer, SpriteSheet,
+};
+use std::{error::Error, sync::Arc};
+
+const FRAME_COLORS: [[u8; 4]; 4] = [
+	[230, 80, 80, 255],
+	[230, 180, 60, 255],
+	[90, 200, 110, 255],
+	[80, 140, 230, 255],
+];
+
+/// Turns the entity and steps through its sprite sheet every few updates
+struct Spin {
+	degrees: f32,
+	updates: u32,
+}
+
+system!(spin_system, [_resources, _entity], (), (spin: Spin, transform: Transform, sheet: SpriteSheet) -> ecs::error::Result<()> {
+	transform.rotate(Quat::from_rotation_z(spin.degrees.to_radians()));
+	spin.updates += 1;
+	if spin.updates % 15 == 0 {
+		sheet.index = (sheet.index + 1) % sheet.frames();
+	}
+	Ok(())
+});
+
+fn create_world() -> World {
+	let mut world = World::new();
+	let camera = world.create_entity();
+	let _ = world.add_component(
+		camera,
+		Camera {
+			clear_color: Color::rgb(0.02, 0.02, 0.04),
+			..Camera::orthographic(48.0)
+		},
+	);
+	for x in -4..=4 {
+		for y in -2..=2 {
+			let tile = world.create_entity();
+			let _ = world.add_component(
+				tile,
+				Transform::from_xyz(x as f32 * 2.0, y as f32 * 2.0, 0.0),
+			);
+			let _ = world.add_component(tile, Sprite::new("checker"));
+		}
+	}
+	for (index, x) in [-6.0, 0.0, 6.0].into_iter().enumerate() {
+		let spinner = world.create_entity();
+		let _ = world.add_component(spinner, Transform::from_xyz(x, 0.0, 0.0));
+		let _ = world.add_component(
+			spinner,
+			Sprite {
+				size: [1.5, 1.5],
+				layer: 1,
+				..Sprite::new("frames")
+			},
+		);
+		let _ = world.add_component(
+			spinner,
+			SpriteSheet {
+				columns: 2,
+				rows: 2,
+				index: index as u32,
+			},
+		);
+		let _ = world.add_component(
+			spinner,
+			Spin {
+				degrees: 1.0 + index as f32,
+				updates: 0,
+			},
+		);
+	}
+	world
+}
+
+fn checker() -> image::RgbaImage {
+	image::RgbaImage::from_fn(8, 8, |x, y| {
+		let shade = if (x + y) % 2 == 0 { 60 } else { 40 };
+		image::Rgba([shade, shade, shade + 10, 255])
+	})
+}
+
+// Four solid frames in a two by two grid
+fn frames() -> image::RgbaImage {
+	image::RgbaImage::from_fn(32, 32, |x, y| {
+		image::Rgba(FRAME_COLORS[(x / 16 + y / 16 * 2) as usize])
+	})
+}
+
+struct Surface {
+	surface: wgpu::Surface<'static>,
+	device: wgpu::Device,
+	queue: wgpu::Queue,
+	config: wgpu::SurfaceConfiguration,
+	sprites: SpriteRenderer,
+}
+
+impl Surface {
+	async fn new(window: Arc<Window>) -> StateResult<Self> {
+		let instance = wgpu::Instance::default();
+		let surface = instance.create_surface(window.clone())?;
+		let adapter = instance
+			.request_adapter(&wgpu::RequestAdapterOptions {
+				compatible_surface: Some(&surface),
+				..Default::default()
+			})
+			.await?;
+		let (device, queue) = adapter
+			.request_device(&wgpu::DeviceDescriptor::default())
+			.await?;
+		let size = window.inner_size();
+		let config = surface
+			.get_default_config(&adapter, size.width.max(1), size.height.max(1))
+			.ok_or("The window surface isn't supported by the adapter")?;
+		surface.configure(&device, &config);
+		let mut sprites = SpriteRenderer::new(&device, &queue, config.format);
+		sprites.set_texture("checker", &checker());
+		sprites.set_texture("frames", &frames());
+		Ok(Self {
+			surface,
+			device,
+			queue,
+			config,
+			sprites,
+		})
+	}
+}
+
+#[derive(Default)]
+struct Game {
+	surface: Option<Surface>,
+}
+
+#[async_trait]
+impl State<Context, AppEvent> for Game {
+	async fn on_resume_app(&mut self, context: &mut Context) -> StateResult<()> {
+		if let Some(window) = context.window(WindowId::PRIMARY) {
+			self.surface = Some(Surface::new(window).await?);
+		}
+		Ok(())
+	}
+
+	async fn on_suspend_app(&mut self, _context: &mut Context) -> StateResult<()> {
+		self.surface = None;
+		Ok(())
+	}
+
+	async fn update(
+		&mut self,
+		context: &mut Context,
+	) -> StateResult<Transition<Context, AppEvent>> {
+		context.run_world_systems().await?;
+		Ok(Transition::None)
+	}
+
+	async fn on_event(
+		&mut self,
+		_context: &mut Context,
+		event: &mut AppEvent,
+	) -> StateResult<Transition<Context, AppEvent>> {
+		if let (AppEvent::Resized { width, height, .. }, Some(surface)) = (event, &mut self.surface)
+		{
+			if *width > 0 && *height > 0 {
+				surface.config.width = *width;
+				surface.config.height = *height;
+				surface.surface.configure(&surface.device, &surface.config);
+			}
+		}
+		Ok(Transition::None)
+	}
+
+	async fn on_render(&mut self, context: &mut Context, _frame: Frame) -> StateResult<()> {
+		let (Some(surface), Some(world)) = (self.surface.as_mut(), context.world()) else {
+			return Ok(());
+		};
+		let extracted = world.with(|world| extract_sprites(world)).await?;
+		let frame = match surface.surface.get_current_texture() {
+			wgpu::CurrentSurfaceTexture::Success(frame)
+			| wgpu::CurrentSurfaceTexture::Suboptimal(frame) => frame,
+			_ => {
+				surface.surface.configure(&surface.device, &surface.config);
+				return Ok(());
+			}
+		};
+		surface
+			.sprites
+			.prepare(&extracted, surface.config.width, surface.config.height);
+		let view = frame.texture.create_view(&Default::default());
+		let mut encoder = surface.device.create_command_encoder(&Default::default());
+		{
+			let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+				label: Some("sprites"),
+				color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+					view: &view,
+					depth_slice: None,
+					resolve_target: None,
+					ops: wgpu::Operations {
+						load: wgpu::LoadOp::Clear(extracted.camera.clear_color()),
+						store: wgpu::StoreOp::Store,
+					},
+				})],
+				..Default::default()
+			});
+			surface.sprites.render(&mut pass);
+		}
+		surface.queue.submit([encoder.finish()]);
+		surface.queue.present(frame);
+		Ok(())
+	}
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+	let mut builder = App::builder(AppConfig::from_args()?);
+	builder.with_world(create_world).add_system(spin_system);
+	builder.build()?.run(Game::default());
+	Ok(())
+}
@@ -0,0 +1,276 @@
+use crate::extract::world_transform;
+use ecs::{error::Result, world::World};
+use math::{
+	glam::camera::rh::proj::directx::{orthographic, perspective},
+	Color, GlobalTransform, Mat4, Ray, Vec2, Vec3,
+};
+
+/// How a camera flattens the world onto the screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+	/// Farther things look smaller, as in 3D scenes
+	Perspective {
+		/// The vertical field of view, in degrees
+		fov_y: f32,
+		near: f32,
+		far: f32,
+	},
+	/// Things look the same size however far away they are, as in 2D scenes
+	Orthographic {
+		/// Pixels per world unit
+		zoom: f32,
+		near: f32,
+		far: f32,
+	},
+}
+
+impl Default for Projection {
+	fn default() -> Self {
+		Self::Perspective {
+			fov_y: 60.0,
+			near: 0.1,
+			far: 1000.0,
+		}
+	}
+}
+
+impl Projection {
+	/// The matrix taking view space to clip space for a viewport this size in pixels
+	pub fn matrix(&self, width: f32, height: f32) -> Mat4 {
+		let (width, height) = (width.max(1.0), height.max(1.0));
+		match *self {
+			Self::Perspective { fov_y, near, far } => {
+				perspective(fov_y.to_radians(), width / height, near, far)
+			}
+			Self::Orthographic { zoom, near, far } => {
+				let (half_width, half_height) = (width / zoom / 2.0, height / zoom / 2.0);
+				orthographic(
+					-half_width,
+					half_width,
+					-half_height,
+					half_height,
+					near,
+					far,
+				)
+			}
+		}
+	}
+}
+
+/// The part of the target a camera draws to, as fractions of its size from the top left
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+	pub x: f32,
+	pub y: f32,
+	pub width: f32,
+	pub height: f32,
+}
+
+impl Default for Viewport {
+	fn default() -> Self {
+		Self {
+			x: 0.0,
+			y: 0.0,
+			width: 1.0,
+			height: 1.0,
+		}
+	}
+}
+
+impl Viewport {
+	/// The left, top, width and height in pixels on a target this size
+	pub fn pixels(&self, width: u32, height: u32) -> [f32; 4] {
+		let (width, height) = (width.max(1) as f32, height.max(1) as f32);
+		[
+			self.x * width,
+			self.y * height,
+			(self.width * width).max(1.0),
+			(self.height * height).max(1.0),
+		]
+	}
+}
+
+/// Views the world from the entity's transform, looking down its negative z axis.
+/// The scene is drawn from the first camera found, or from the origin without one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+	pub projection: Projection,
+	pub viewport: Viewport,
+	/// What the target is cleared to
+	pub clear_color: Color,
+}
+
+impl Default for Camera {
+	fn default() -> Self {
+		Self {
+			projection: Projection::default(),
+			viewport: Viewport::default(),
+			clear_color: Color::BLACK,
+		}
+	}
+}
+
+impl Camera {
+	pub fn perspective(fov_y: f32) -> Self {
+		Self {
+			projection: Projection::Perspective {
+				fov_y,
+				near: 0.1,
+				far: 1000.0,
+			},
+			..Self::default()
+		}
+	}
+
+	/// Sees as far behind the camera as in front of it, so 2D scenes can be
+	/// viewed from the xy plane they're on
+	pub fn orthographic(zoom: f32) -> Self {
+		Self {
+			projection: Projection::Orthographic {
+				zoom,
+				near: -1000.0,
+				far: 1000.0,
+			},
+			..Self::default()
+		}
+	}
+
+	pub fn clear_color(&self) -> wgpu::Color {
+		let [r, g, b, a] = self.clear_color.to_array().map(f64::from);
+		wgpu::Color { r, g, b, a }
+	}
+}
+
+/// A camera's matrices for a target of some size, used to draw from it and to
+/// turn points on screen into rays for picking
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CameraView {
+	pub view: Mat4,
+	pub projection: Mat4,
+	pub position: Vec3,
+	/// The left, top, width and height drawn to, in pixels
+	pub viewport: [f32; 4],
+}
+
+impl CameraView {
+	pub fn new(camera: &Camera, transform: &GlobalTransform, width: u32, height: u32) -> Self {
+		let viewport = camera.viewport.pixels(width, height);
+		Self {
+			view: transform.inverse(),
+			projection: camera.projection.matrix(viewport[2], viewport[3]),
+			position: transform.translation(),
+			viewport,
+		}
+	}
+
+	/// The matrix taking world positions to clip space
+	pub fn view_projection(&self) -> Mat4 {
+		self.projection * self.view
+	}
+
+	/// The ray through a point on the target in pixels, with y pointing down
+	pub fn viewport_to_world(&self, point: Vec2) -> Option<Ray> {
+		let [left, top, width, height] = self.viewport;
+		let x = (point.x - left) / width * 2.0 - 1.0;
+		let y = 1.0 - (point.y - top) / height * 2.0;
+		let view_projection = self.view_projection();
+		if view_projection.determinant().abs() < f32::EPSILON {
+			return None;
+		}
+		let inverse = view_projection.inverse();
+		let near = inverse.project_point3(Vec3::new(x, y, 0.0));
+		let far = inverse.project_point3(Vec3::new(x, y, 1.0));
+		Some(Ray::new(near, far - near))
+	}
+
+	/// Where a world position lands on the target in pixels, or none behind the camera
+	pub fn world_to_viewport(&self, point: Vec3) -> Option<Vec2> {
+		let view_projection = self.view_projection();
+		let [x, y, z, w] = view_projection.row(3).to_array();
+		if x * point.x + y * point.y + z * point.z + w <= 0.0 {
+			return None;
+		}
+		let clip = view_projection.project_point3(point);
+		let [left, top, width, height] = self.viewport;
+		Some(Vec2::new(
+			left + (clip.x + 1.0) / 2.0 * width,
+			top + (1.0 - clip.y) / 2.0 * height,
+		))
+	}
+}
+
+/// Gives every `Camera` a `CameraView` for a target this size, so systems can pick with it
+pub fn update_cameras(world: &mut World, width: u32, height: u32) -> Result<()> {
+	let views = world
+		.entities()
+		.into_iter()
+		.filter_map(|entity| {
+			let camera = *world.get_component::<Camera>(entity)?;
+			let transform = world_transform(world, entity).unwrap_or_default();
+			Some((entity, CameraView::new(&camera, &transform, width, height)))
+		})
+		.collect::<Vec<_>>();
+	for (entity, view) in views {
+		world.add_component(entity, view)?;
+	}
+	Ok(())
+}
+
+/// The first camera and where it is
+pub(crate) fn first_camera(world: &World) -> Option<(Camera, GlobalTransform)> {
+	world.entities().into_iter().find_map(|entity| {
+		let camera = *world.get_component::<Camera>(entity)?;
+		Some((camera, world_transform(world, entity).unwrap_or_default()))
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use math::{Aabb, Quat, Transform};
+
+	fn close(a: Vec2, b: Vec2) -> bool {
+		a.distance(b) < 1e-3
+	}
+
+	#[test]
+	fn screen_points_and_rays_meet_in_the_world() -> Result<()> {
+		let mut world = World::new();
+		let entity = world.create_entity();
+		let camera = Camera {
+			viewport: Viewport {
+				x: 0.5,
+				width: 0.5,
+				..Viewport::default()
+			},
+			..Camera::perspective(90.0)
+		};
+		world.add_component(entity, camera)?;
+		world.add_component(entity, Transform::from_xyz(0.0, 0.0, 10.0))?;
+		update_cameras(&mut world, 800, 400)?;
+		let view = *world.get_component::<CameraView>(entity).unwrap();
+
+		// The right half of the target, looking at the origin
+		assert_eq!(view.viewport, [400.0, 0.0, 400.0, 400.0]);
+		let center = Vec2::new(600.0, 200.0);
+		assert_eq!(view.world_to_viewport(Vec3::ZERO), Some(center));
+		assert_eq!(view.world_to_viewport(Vec3::new(0.0, 0.0, 20.0)), None);
+		let ray = view.viewport_to_world(Vec2::new(800.0, 0.0)).unwrap();
+		let hit = ray.at(ray.intersect_plane(Vec3::ZERO, Vec3::Z).unwrap());
+		assert!(hit.distance(Vec3::new(10.0, 10.0, 0.0)) < 1e-3);
+		let aabb = Aabb::from_center_half_extents(Vec3::ZERO, Vec3::ONE);
+		let ray = view.viewport_to_world(center).unwrap();
+		// Starting from the near plane, it meets the box's front face
+		let hit = ray.at(ray.intersect_aabb(&aabb).unwrap());
+		assert!(hit.distance(Vec3::Z) < 1e-3);
+
+		// Turned a quarter left, what was above is on the right
+		let turned = Transform::from_rotation(Quat::from_rotation_z(90_f32.to_radians()));
+		let view = CameraView::new(&Camera::orthographic(10.0), &turned.into(), 200, 100);
+		let above = view.world_to_viewport(Vec3::new(0.0, 5.0, 0.0)).unwrap();
+		assert!(close(above, Vec2::new(150.0, 50.0)));
+		let ray = view.viewport_to_world(above).unwrap();
+		assert!(close(ray.origin.truncate(), Vec2::new(0.0, 5.0)));
+		Ok(())
+	}
+}
@@ -0,0 +1,278 @@
+use crate::{
+	camera::{first_camera, Camera},
+	mesh::{AmbientLight, DirectionalLight, Material, Mesh, PointLight},
+	sprite::{Sprite, SpriteSheet},
+	text::Text,
+};
+use ecs::world::{Entity, World};
+use math::{GlobalTransform, Mat4, Transform, Vec3};
+
+/// A sprite as the shader draws it
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct SpriteInstance {
+	pub position: [f32; 2],
+	/// Scaled by the transform
+	pub size: [f32; 2],
+	/// Counterclockwise, in radians
+	pub rotation: f32,
+	/// The left, top, right and bottom texture coordinates, swapped to flip
+	pub uv: [f32; 4],
+	pub color: [f32; 4],
+}
+
+/// Sprites sharing a texture, drawn together with one draw call
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteBatch {
+	pub texture: String,
+	pub instances: Vec<SpriteInstance>,
+}
+
+/// What the renderer draws in a frame, pulled out of the world so it can be sent
+/// to the thread that renders
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ExtractedSprites {
+	pub camera: Camera,
+	pub view: GlobalTransform,
+	/// In drawing order, lowest layer first
+	pub batches: Vec<SpriteBatch>,
+}
+
+impl ExtractedSprites {
+	pub fn len(&self) -> usize {
+		self.batches.iter().map(|batch| batch.instances.len()).sum()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.batches.is_empty()
+	}
+}
+
+/// Where the entity is in the world, from its `GlobalTransform` when transforms have been
+/// propagated and from its `Transform` otherwise
+pub fn world_transform(world: &World, entity: Entity) -> Option<GlobalTransform> {
+	world
+		.get_component::<GlobalTransform>(entity)
+		.map(|global| *global)
+		.or_else(|| {
+			world
+				.get_component::<Transform>(entity)
+				.map(|transform| GlobalTransform::from(*transform))
+		})
+}
+
+/// Collects every entity with a `Sprite` and a transform, along with the first camera,
+/// run each frame before drawing. Sprites are ordered by layer, and those on a layer are batched by texture.
+pub fn extract_sprites(world: &World) -> ExtractedSprites {
+	let entities = world.entities();
+	// Without a camera, the origin is at the center at one pixel per unit
+	let (camera, view) = first_camera(world)
+		.unwrap_or_else(|| (Camera::orthographic(1.0), GlobalTransform::default()));
+
+	let mut sprites = entities
+		.iter()
+		.filter_map(|entity| {
+			let sprite = world.get_component::<Sprite>(*entity)?;
+			let transform = world_transform(world, *entity)?.matrix();
+			let sheet = world.get_component::<SpriteSheet>(*entity);
+			let [mut left, mut top, mut right, mut bottom] =
+				sheet.map_or([0.0, 0.0, 1.0, 1.0], |sheet| sheet.uv());
+			if sprite.flip_x {
+				std::mem::swap(&mut left, &mut right);
+			}
+			if sprite.flip_y {
+				std::mem::swap(&mut top, &mut bottom);
+			}
+			let (x_axis, y_axis) = (transform.x_axis.truncate(), transform.y_axis.truncate());
+			let instance = SpriteInstance {
+				position: transform.w_axis.truncate().truncate().to_array(),
+				size: [
+					sprite.size[0] * x_axis.truncate().length(),
+					sprite.size[1] * y_axis.truncate().length(),
+				],
+				rotation: x_axis.y.atan2(x_axis.x),
+				uv: [left, top, right, bottom],
+				color: sprite.color.to_array(),
+			};
+			Some((sprite.layer, sprite.texture.clone(), instance))
+		})
+		.collect::<Vec<_>>();
+	// Stable, so sprites sharing a layer and texture keep the order they were created in
+	sprites.sort_by(|(layer, texture, _), (other_layer, other_texture, _)| {
+		(layer, texture).cmp(&(other_layer, other_texture))
+	});
+
+	let mut batches = Vec::<SpriteBatch>::new();
+	let mut layer = None;
+	for (sprite_layer, texture, instance) in sprites {
+		match batches.last_mut() {
+			Some(batch) if layer == Some(sprite_layer) && batch.texture == texture => {
+				batch.instances.push(instance);
+			}
+			_ => batches.push(SpriteBatch {
+				texture,
+				instances: vec![instance],
+			}),
+		}
+		layer = Some(sprite_layer);
+	}
+	ExtractedSprites {
+		camera,
+		view,
+		batches,
+	}
+}
+
+/// A mesh pulled out of the world with where it is and how it looks
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshInstance {
+	pub mesh: String,
+	pub model: Mat4,
+	pub material: Material,
+}
+
+/// What the mesh renderer draws in a frame, pulled out of the world like `ExtractedSprites`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ExtractedMeshes {
+	pub camera: Camera,
+	pub view: GlobalTransform,
+	pub ambient_light: AmbientLight,
+	pub meshes: Vec<MeshInstance>,
+	pub directional_lights: Vec<DirectionalLight>,
+	/// With the light's position
+	pub point_lights: Vec<(Vec3, PointLight)>,
+}
+
+/// Collects every entity with a `Mesh` and a transform, along with the lights and
+/// the first camera. Meshes without a `Material` get the default one.
+pub fn extract_meshes(world: &World) -> ExtractedMeshes {
+	let (camera, view) = first_camera(world).unwrap_or_default();
+	let mut extracted = ExtractedMeshes {
+		camera,
+		view,
+		..ExtractedMeshes::default()
+	};
+	let mut ambient_found = false;
+	for entity in world.entities() {
+		// Ambient and directional lights shine the same everywhere, so they need no transform
+		if let (Some(light), false) = (world.get_component::<AmbientLight>(entity), ambient_found) {
+			extracted.ambient_light = *light;
+			ambient_found = true;
+		}
+		if let Some(light) = world.get_component::<DirectionalLight>(entity) {
+			extracted.directional_lights.push(*light);
+		}
+		let Some(transform) = world_transform(world, entity) else {
+			continue;
+		};
+		if let Some(light) = world.get_component::<PointLight>(entity) {
+			extracted
+				.point_lights
+				.push((transform.translation(), *light));
+		}
+		if let Some(mesh) = world.get_component::<Mesh>(entity) {
+			extracted.meshes.push(MeshInstance {
+				mesh: mesh.name.clone(),
+				model: transform.matrix(),
+				material: world
+					.get_component::<Material>(entity)
+					.map_or_else(Material::default, |material| *material),
+			});
+		}
+	}
+	extracted
+}
+
+/// What the text renderer draws in a frame, pulled out of the world like `ExtractedSprites`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ExtractedText {
+	pub camera: Camera,
+	pub view: GlobalTransform,
+	/// With where each is, in creation order
+	pub texts: Vec<(Text, GlobalTransform)>,
+}
+
+/// Collects every entity with a `Text` and a transform, along with the first camera
+/// to draw world labels with
+pub fn extract_text(world: &World) -> ExtractedText {
+	let (camera, view) = first_camera(world)
+		.unwrap_or_else(|| (Camera::orthographic(1.0), GlobalTransform::default()));
+	let texts = world
+		.entities()
+		.into_iter()
+		.filter_map(|entity| {
+			let text = world.get_component::<Text>(entity)?.clone();
+			Some((text, world_transform(world, entity)?))
+		})
+		.collect();
+	ExtractedText {
+		camera,
+		view,
+		texts,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn sprites_are_batched_by_layer_then_texture() -> ecs::error::Result<()> {
+		let mut world = World::new();
+		let spawn = |world: &mut World,
+		             texture: &str,
+		             layer: i32,
+		             x: f32|
+		 -> ecs::error::Result<ecs::world::Entity> {
+			let entity = world.create_entity();
+			world.add_component(
+				entity,
+				Sprite {
+					layer,
+					..Sprite::new(texture)
+				},
+			)?;
+			world.add_component(entity, Transform::from_xyz(x, 0.0, 0.0))?;
+			Ok(entity)
+		};
+		spawn(&mut world, "tiles", 0, 0.0)?;
+		let player = spawn(&mut world, "player", 1, 1.0)?;
+		spawn(&mut world, "tiles", 0, 2.0)?;
+		spawn(&mut world, "player", 0, 3.0)?;
+		spawn(&mut world, "tiles", 1, 4.0)?;
+		let hidden = world.create_entity();
+		world.add_component(hidden, Sprite::new("tiles"))?;
+		world.add_component(
+			player,
+			SpriteSheet {
+				columns: 4,
+				rows: 2,
+				index: 5,
+			},
+		)?;
+		world.get_component_mut::<Sprite>(player).unwrap().flip_x = true;
+
+		let extracted = extract_sprites(&world);
+		let batches = extracted
+			.batches
+			.iter()
+			.map(|batch| {
+				let positions = batch.instances.iter().map(|instance| instance.position[0]);
+				(batch.texture.as_str(), positions.collect::<Vec<_>>())
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(
+			batches,
+			[
+				("player", vec![3.0]),
+				("tiles", vec![0.0, 2.0]),
+				("player", vec![1.0]),
+				("tiles", vec![4.0]),
+			]
+		);
+		assert_eq!(extracted.len(), 5);
+		assert_eq!(extracted.batches[2].instances[0].uv, [0.5, 0.5, 0.25, 1.0]);
+		assert_eq!(extracted.camera, Camera::orthographic(1.0));
+		Ok(())
+	}
+}
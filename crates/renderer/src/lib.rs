@@ -0,0 +1,26 @@
+#![forbid(unsafe_code)]
+
+mod camera;
+mod extract;
+mod mesh;
+mod mesh_renderer;
+mod sprite;
+mod sprite_renderer;
+mod text;
+mod text_renderer;
+
+pub use self::{
+	camera::{update_cameras, Camera, CameraView, Projection, Viewport},
+	extract::{
+		extract_meshes, extract_sprites, extract_text, world_transform, ExtractedMeshes,
+		ExtractedSprites, ExtractedText, MeshInstance, SpriteBatch, SpriteInstance,
+	},
+	mesh::{AmbientLight, DirectionalLight, Material, Mesh, MeshData, PointLight, Vertex},
+	mesh_renderer::MeshRenderer,
+	sprite::{Sprite, SpriteSheet},
+	sprite_renderer::SpriteRenderer,
+	text::{Font, PlacedGlyph, Text, TextAlign, TextLayout, TextSpace},
+	text_renderer::TextRenderer,
+};
+pub use math;
+pub use wgpu;
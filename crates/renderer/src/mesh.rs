@@ -0,0 +1,229 @@
+use math::{Aabb, Color, Vec3};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Vertex {
+	pub position: [f32; 3],
+	pub normal: [f32; 3],
+}
+
+/// Triangles for the GPU, indexed counterclockwise
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MeshData {
+	pub vertices: Vec<Vertex>,
+	pub indices: Vec<u32>,
+}
+
+impl MeshData {
+	/// A cube centered on the origin with sides this long
+	pub fn cube(size: f32) -> Self {
+		let half = size / 2.0;
+		let mut mesh = Self::default();
+		for (normal, up) in [
+			([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+			([-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+			([0.0, 1.0, 0.0], [0.0, 0.0, -1.0]),
+			([0.0, -1.0, 0.0], [0.0, 0.0, 1.0]),
+			([0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+			([0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+		] {
+			let right = Vec3::from(up).cross(Vec3::from(normal));
+			let start = mesh.vertices.len() as u32;
+			for (u, v) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+				let position = (Vec3::from(normal) + right * u + Vec3::from(up) * v) * half;
+				mesh.vertices.push(Vertex {
+					position: position.to_array(),
+					normal,
+				});
+			}
+			mesh.indices
+				.extend([0, 1, 2, 0, 2, 3].map(|index| start + index));
+		}
+		mesh
+	}
+
+	/// A square on the xz plane facing up, centered on the origin
+	pub fn plane(size: f32) -> Self {
+		let half = size / 2.0;
+		let normal = [0.0, 1.0, 0.0];
+		Self {
+			vertices: [(-1.0, 1.0), (1.0, 1.0), (1.0, -1.0), (-1.0, -1.0)]
+				.map(|(x, z)| Vertex {
+					position: [x * half, 0.0, z * half],
+					normal,
+				})
+				.to_vec(),
+			indices: vec![0, 1, 2, 0, 2, 3],
+		}
+	}
+
+	/// A sphere centered on the origin made of rings from pole to pole
+	pub fn sphere(radius: f32, segments: u32) -> Self {
+		let segments = segments.max(3);
+		let rings = segments / 2 + 1;
+		let mut mesh = Self::default();
+		for ring in 0..=rings {
+			let (sin_theta, cos_theta) =
+				(ring as f32 / rings as f32 * std::f32::consts::PI).sin_cos();
+			for segment in 0..=segments {
+				let (sin_phi, cos_phi) =
+					(segment as f32 / segments as f32 * std::f32::consts::TAU).sin_cos();
+				let normal = [sin_theta * cos_phi, cos_theta, -sin_theta * sin_phi];
+				mesh.vertices.push(Vertex {
+					position: normal.map(|value| value * radius),
+					normal,
+				});
+			}
+		}
+		for ring in 0..rings {
+			for segment in 0..segments {
+				let top = ring * (segments + 1) + segment;
+				let bottom = top + segments + 1;
+				mesh.indices
+					.extend([top, bottom, bottom + 1, top, bottom + 1, top + 1]);
+			}
+		}
+		mesh
+	}
+
+	/// The box around every vertex
+	pub fn bounds(&self) -> Aabb {
+		Aabb::from_points(
+			self.vertices
+				.iter()
+				.map(|vertex| Vec3::from(vertex.position)),
+		)
+		.unwrap_or_default()
+	}
+}
+
+/// Draws a mesh given to `MeshRenderer::set_mesh` at the entity's transform
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mesh {
+	pub name: String,
+}
+
+impl Mesh {
+	pub fn new(name: impl Into<String>) -> Self {
+		Self { name: name.into() }
+	}
+}
+
+/// How a mesh's surface reflects light. Meshes whose base color is translucent
+/// are drawn after the opaque ones, farthest first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Material {
+	pub base_color: Color,
+	/// From 0.0 for plastic-like surfaces to 1.0 for metals
+	pub metallic: f32,
+	/// From 0.0 for mirror-like surfaces to 1.0 for matte ones
+	pub roughness: f32,
+	/// Light given off regardless of the lights, leaving out the alpha
+	pub emissive: Color,
+}
+
+impl Default for Material {
+	fn default() -> Self {
+		Self {
+			base_color: Color::rgb(0.8, 0.8, 0.8),
+			metallic: 0.0,
+			roughness: 0.5,
+			emissive: Color::BLACK,
+		}
+	}
+}
+
+impl Material {
+	pub fn color(base_color: Color) -> Self {
+		Self {
+			base_color,
+			..Self::default()
+		}
+	}
+
+	pub fn is_translucent(&self) -> bool {
+		self.base_color.is_translucent()
+	}
+}
+
+/// Light from far away shining in one direction, such as the sun
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DirectionalLight {
+	pub direction: Vec3,
+	pub color: Color,
+	pub intensity: f32,
+}
+
+impl Default for DirectionalLight {
+	fn default() -> Self {
+		Self {
+			direction: Vec3::new(-0.3, -1.0, -0.5),
+			color: Color::WHITE,
+			intensity: 1.0,
+		}
+	}
+}
+
+/// Light shining every way from the entity's transform, fading out by `range`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PointLight {
+	pub color: Color,
+	pub intensity: f32,
+	pub range: f32,
+}
+
+impl Default for PointLight {
+	fn default() -> Self {
+		Self {
+			color: Color::WHITE,
+			intensity: 1.0,
+			range: 10.0,
+		}
+	}
+}
+
+/// Light reaching every surface evenly, standing in for light bounced around the scene.
+/// The first one found lights the scene.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AmbientLight {
+	pub color: Color,
+	pub intensity: f32,
+}
+
+impl Default for AmbientLight {
+	fn default() -> Self {
+		Self {
+			color: Color::WHITE,
+			intensity: 0.03,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::camera::{Camera, CameraView};
+	use math::{Frustum, Transform};
+
+	#[test]
+	fn cameras_see_what_they_look_at() {
+		let transform = Transform::from_xyz(3.0, 4.0, 5.0).looking_at(Vec3::ZERO, Vec3::Y);
+		let view_projection =
+			CameraView::new(&Camera::default(), &transform.into(), 800, 600).view_projection();
+		// The target lands in the middle of the screen
+		let center = view_projection.project_point3(Vec3::ZERO);
+		assert!(center.x.abs() < 1e-4 && center.y.abs() < 1e-4);
+
+		let frustum = Frustum::new(&view_projection);
+		assert!(frustum.intersects_sphere(Vec3::ZERO, 1.0));
+		assert!(!frustum.intersects_sphere(Vec3::new(6.0, 8.0, 10.0), 1.0));
+		assert!(!frustum.intersects_sphere(Vec3::new(50.0, 0.0, 0.0), 1.0));
+
+		let bounds = MeshData::cube(2.0).bounds();
+		assert_eq!(bounds, Aabb::new(Vec3::splat(-1.0), Vec3::ONE));
+		assert_eq!(
+			MeshData::plane(4.0).bounds().size(),
+			Vec3::new(4.0, 0.0, 4.0)
+		);
+	}
+}
@@ -0,0 +1,93 @@
+const PI: f32 = 3.14159265;
+
+struct Light {
+	// The direction of a directional light, or the position and range of a point light
+	vector: vec4<f32>,
+	// Scaled by the intensity
+	color: vec4<f32>,
+}
+
+struct Scene {
+	view_projection: mat4x4<f32>,
+	camera_position: vec4<f32>,
+	ambient: vec4<f32>,
+	// Directional lights, then point lights
+	light_counts: vec4<u32>,
+	directional: array<Light, 4>,
+	points: array<Light, 16>,
+}
+
+@group(0) @binding(0) var<uniform> scene: Scene;
+
+struct Vertex {
+	@location(0) position: vec3<f32>,
+	@location(1) normal: vec3<f32>,
+}
+
+struct Instance {
+	@location(2) model_0: vec4<f32>,
+	@location(3) model_1: vec4<f32>,
+	@location(4) model_2: vec4<f32>,
+	@location(5) model_3: vec4<f32>,
+	@location(6) base_color: vec4<f32>,
+	// Metallic and roughness
+	@location(7) surface: vec4<f32>,
+	@location(8) emissive: vec4<f32>,
+}
+
+struct VertexOutput {
+	@builtin(position) clip_position: vec4<f32>,
+	@location(0) world_position: vec3<f32>,
+	@location(1) normal: vec3<f32>,
+	@location(2) base_color: vec4<f32>,
+	@location(3) surface: vec4<f32>,
+	@location(4) emissive: vec4<f32>,
+}
+
+@vertex
+fn vs_main(vertex: Vertex, instance: Instance) -> VertexOutput {
+	let model = mat4x4<f32>(instance.model_0, instance.model_1, instance.model_2, instance.model_3);
+	let world_position = model * vec4<f32>(vertex.position, 1.0);
+	var out: VertexOutput;
+	out.clip_position = scene.view_projection * world_position;
+	out.world_position = world_position.xyz;
+	out.normal = (model * vec4<f32>(vertex.normal, 0.0)).xyz;
+	out.base_color = instance.base_color;
+	out.surface = instance.surface;
+	out.emissive = instance.emissive;
+	return out;
+}
+
+// Blinn-Phong with its shininess taken from the roughness, and Schlick's fresnel
+fn reflect_light(normal: vec3<f32>, view: vec3<f32>, light: vec3<f32>, radiance: vec3<f32>, base: vec3<f32>, surface: vec4<f32>) -> vec3<f32> {
+	let n_dot_l = max(dot(normal, light), 0.0);
+	let halfway = normalize(light + view);
+	let shininess = exp2(10.0 * (1.0 - surface.y) + 1.0);
+	let f0 = mix(vec3<f32>(0.04), base, surface.x);
+	let fresnel = f0 + (1.0 - f0) * pow(1.0 - max(dot(halfway, view), 0.0), 5.0);
+	let specular = fresnel * (shininess + 8.0) / (8.0 * PI) * pow(max(dot(normal, halfway), 0.0), shininess);
+	let diffuse = (1.0 - fresnel) * (1.0 - surface.x) * base;
+	return (diffuse + specular) * radiance * n_dot_l;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+	let normal = normalize(in.normal);
+	let view = normalize(scene.camera_position.xyz - in.world_position);
+	let base = in.base_color.rgb;
+	var color = scene.ambient.rgb * base + in.emissive.rgb;
+	for (var index = 0u; index < scene.light_counts.x; index++) {
+		let light = scene.directional[index];
+		color += reflect_light(normal, view, normalize(-light.vector.xyz), light.color.rgb, base, in.surface);
+	}
+	for (var index = 0u; index < scene.light_counts.y; index++) {
+		let light = scene.points[index];
+		let offset = light.vector.xyz - in.world_position;
+		let distance = max(length(offset), 0.0001);
+		// Falls off with the square of the distance, smoothly reaching zero at the range
+		let fade = saturate(1.0 - pow(distance / light.vector.w, 4.0));
+		let attenuation = fade * fade / (distance * distance + 1.0);
+		color += reflect_light(normal, view, offset / distance, light.color.rgb * attenuation, base, in.surface);
+	}
+	return vec4<f32>(color, in.base_color.a);
+}
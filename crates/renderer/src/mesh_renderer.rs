@@ -0,0 +1,497 @@
+use crate::{
+	camera::CameraView,
+	extract::ExtractedMeshes,
+	mesh::{MeshData, Vertex},
+};
+use math::{Aabb, Frustum, Mat4, Vec3};
+use std::{collections::HashMap, mem::size_of, ops::Range};
+use wgpu::util::DeviceExt;
+
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+const MAX_DIRECTIONAL_LIGHTS: usize = 4;
+const MAX_POINT_LIGHTS: usize = 16;
+
+const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+	wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3];
+
+const INSTANCE_ATTRIBUTES: [wgpu::VertexAttribute; 7] = wgpu::vertex_attr_array![
+	2 => Float32x4,
+	3 => Float32x4,
+	4 => Float32x4,
+	5 => Float32x4,
+	6 => Float32x4,
+	7 => Float32x4,
+	8 => Float32x4,
+];
+
+/// A light as the shader reads it
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Light {
+	// The direction of a directional light, or the position and range of a point light
+	vector: [f32; 4],
+	color: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Scene {
+	view_projection: [[f32; 4]; 4],
+	camera_position: [f32; 4],
+	ambient: [f32; 4],
+	light_counts: [u32; 4],
+	directional: [Light; MAX_DIRECTIONAL_LIGHTS],
+	points: [Light; MAX_POINT_LIGHTS],
+}
+
+impl Scene {
+	fn new(meshes: &ExtractedMeshes, view_projection: Mat4) -> Self {
+		let mut scene = Self {
+			view_projection: view_projection.to_cols_array_2d(),
+			camera_position: extend(meshes.view.translation(), 1.0),
+			ambient: meshes
+				.ambient_light
+				.color
+				.scaled(meshes.ambient_light.intensity)
+				.to_array(),
+			..bytemuck::Zeroable::zeroed()
+		};
+		for (slot, light) in scene.directional.iter_mut().zip(&meshes.directional_lights) {
+			*slot = Light {
+				vector: extend(light.direction, 0.0),
+				color: light.color.scaled(light.intensity).to_array(),
+			};
+		}
+		for (slot, (position, light)) in scene.points.iter_mut().zip(&meshes.point_lights) {
+			*slot = Light {
+				vector: extend(*position, light.range),
+				color: light.color.scaled(light.intensity).to_array(),
+			};
+		}
+		scene.light_counts = [
+			meshes.directional_lights.len().min(MAX_DIRECTIONAL_LIGHTS) as u32,
+			meshes.point_lights.len().min(MAX_POINT_LIGHTS) as u32,
+			0,
+			0,
+		];
+		scene
+	}
+}
+
+/// A mesh instance as the shader reads it
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct Instance {
+	model: [[f32; 4]; 4],
+	base_color: [f32; 4],
+	// Metallic and roughness
+	surface: [f32; 4],
+	emissive: [f32; 4],
+}
+
+/// Consecutive instances of one mesh, drawn in a single call
+#[derive(Debug, Clone, PartialEq)]
+struct Draw {
+	mesh: String,
+	instances: Range<u32>,
+	translucent: bool,
+}
+
+struct GpuMesh {
+	vertices: wgpu::Buffer,
+	indices: wgpu::Buffer,
+	index_count: u32,
+	bounds: Aabb,
+}
+
+/// Draws extracted meshes with wgpu, lit by their lights. Meshes outside the
+/// camera's view are skipped. Opaque meshes are drawn nearest first so hidden
+/// surfaces fail the depth test early, then translucent ones farthest first so
+/// they blend over what is behind them.
+pub struct MeshRenderer {
+	device: wgpu::Device,
+	queue: wgpu::Queue,
+	opaque: wgpu::RenderPipeline,
+	translucent: wgpu::RenderPipeline,
+	scene: wgpu::Buffer,
+	scene_group: wgpu::BindGroup,
+	meshes: HashMap<String, GpuMesh>,
+	instances: wgpu::Buffer,
+	depth: Option<(u32, u32, wgpu::TextureView)>,
+	draws: Vec<Draw>,
+	clear_color: wgpu::Color,
+	viewport: [f32; 4],
+}
+
+impl MeshRenderer {
+	pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, format: wgpu::TextureFormat) -> Self {
+		let shader = device.create_shader_module(wgpu::include_wgsl!("mesh.wgsl"));
+		let scene_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+			label: Some("mesh scene"),
+			entries: &[wgpu::BindGroupLayoutEntry {
+				binding: 0,
+				visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+				ty: wgpu::BindingType::Buffer {
+					ty: wgpu::BufferBindingType::Uniform,
+					has_dynamic_offset: false,
+					min_binding_size: None,
+				},
+				count: None,
+			}],
+		});
+		let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+			label: Some("meshes"),
+			bind_group_layouts: &[Some(&scene_layout)],
+			immediate_size: 0,
+		});
+		let pipeline = |translucent: bool| {
+			device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+				label: Some(if translucent {
+					"translucent meshes"
+				} else {
+					"opaque meshes"
+				}),
+				layout: Some(&layout),
+				vertex: wgpu::VertexState {
+					module: &shader,
+					entry_point: Some("vs_main"),
+					compilation_options: Default::default(),
+					buffers: &[
+						Some(wgpu::VertexBufferLayout {
+							array_stride: size_of::<Vertex>() as wgpu::BufferAddress,
+							step_mode: wgpu::VertexStepMode::Vertex,
+							attributes: &VERTEX_ATTRIBUTES,
+						}),
+						Some(wgpu::VertexBufferLayout {
+							array_stride: size_of::<Instance>() as wgpu::BufferAddress,
+							step_mode: wgpu::VertexStepMode::Instance,
+							attributes: &INSTANCE_ATTRIBUTES,
+						}),
+					],
+				},
+				primitive: wgpu::PrimitiveState {
+					cull_mode: Some(wgpu::Face::Back),
+					..Default::default()
+				},
+				// Translucent meshes are tested against the opaque ones but don't hide each other
+				depth_stencil: Some(wgpu::DepthStencilState {
+					format: DEPTH_FORMAT,
+					depth_write_enabled: Some(!translucent),
+					depth_compare: Some(wgpu::CompareFunction::Less),
+					stencil: Default::default(),
+					bias: Default::default(),
+				}),
+				multisample: wgpu::MultisampleState::default(),
+				fragment: Some(wgpu::FragmentState {
+					module: &shader,
+					entry_point: Some("fs_main"),
+					compilation_options: Default::default(),
+					targets: &[Some(wgpu::ColorTargetState {
+						format,
+						blend: Some(if translucent {
+							wgpu::BlendState::ALPHA_BLENDING
+						} else {
+							wgpu::BlendState::REPLACE
+						}),
+						write_mask: wgpu::ColorWrites::ALL,
+					})],
+				}),
+				multiview_mask: None,
+				cache: None,
+			})
+		};
+		let (opaque, translucent) = (pipeline(false), pipeline(true));
+
+		let scene = device.create_buffer(&wgpu::BufferDescriptor {
+			label: Some("mesh scene"),
+			size: size_of::<Scene>() as wgpu::BufferAddress,
+			usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+			mapped_at_creation: false,
+		});
+		let scene_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+			label: Some("mesh scene"),
+			layout: &scene_layout,
+			entries: &[wgpu::BindGroupEntry {
+				binding: 0,
+				resource: scene.as_entire_binding(),
+			}],
+		});
+		Self {
+			device: device.clone(),
+			queue: queue.clone(),
+			instances: instance_buffer(device, 64),
+			opaque,
+			translucent,
+			scene,
+			scene_group,
+			meshes: HashMap::new(),
+			depth: None,
+			draws: Vec::new(),
+			clear_color: wgpu::Color::BLACK,
+			viewport: [0.0, 0.0, 1.0, 1.0],
+		}
+	}
+
+	/// Uploads a mesh for entities to draw by name, replacing any with the same name
+	pub fn set_mesh(&mut self, name: impl Into<String>, data: &MeshData) {
+		let vertices = self
+			.device
+			.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+				label: Some("mesh vertices"),
+				contents: bytemuck::cast_slice(&data.vertices),
+				usage: wgpu::BufferUsages::VERTEX,
+			});
+		let indices = self
+			.device
+			.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+				label: Some("mesh indices"),
+				contents: bytemuck::cast_slice(&data.indices),
+				usage: wgpu::BufferUsages::INDEX,
+			});
+		let mesh = GpuMesh {
+			vertices,
+			indices,
+			index_count: data.indices.len() as u32,
+			bounds: data.bounds(),
+		};
+		self.meshes.insert(name.into(), mesh);
+	}
+
+	pub fn remove_mesh(&mut self, name: &str) {
+		self.meshes.remove(name);
+	}
+
+	pub fn has_mesh(&self, name: &str) -> bool {
+		self.meshes.contains_key(name)
+	}
+
+	/// How many instances survived culling in the last `prepare`
+	pub fn drawn(&self) -> u32 {
+		self.draws.last().map_or(0, |draw| draw.instances.end)
+	}
+
+	/// Culls and sorts the meshes and uploads them with the lights and camera,
+	/// for a target of this size in pixels
+	pub fn prepare(&mut self, meshes: &ExtractedMeshes, width: u32, height: u32) {
+		let view = CameraView::new(&meshes.camera, &meshes.view, width, height);
+		let view_projection = view.view_projection();
+		let scene = Scene::new(meshes, view_projection);
+		self.viewport = view.viewport;
+		self.queue
+			.write_buffer(&self.scene, 0, bytemuck::bytes_of(&scene));
+		self.clear_color = meshes.camera.clear_color();
+
+		let (instances, draws) = submission(meshes, &view_projection, |name| {
+			self.meshes.get(name).map(|mesh| mesh.bounds)
+		});
+		let needed = (instances.len() * size_of::<Instance>()) as wgpu::BufferAddress;
+		if needed > self.instances.size() {
+			self.instances = instance_buffer(&self.device, instances.len().next_power_of_two());
+		}
+		self.queue
+			.write_buffer(&self.instances, 0, bytemuck::cast_slice(&instances));
+		self.draws = draws;
+
+		let (width, height) = (width.max(1), height.max(1));
+		if !matches!(&self.depth, Some((w, h, _)) if (*w, *h) == (width, height)) {
+			self.depth = Some((width, height, depth_view(&self.device, width, height)));
+		}
+	}
+
+	/// Clears the target to the camera's clear color and draws the prepared meshes
+	/// into the camera's viewport
+	pub fn render(&self, encoder: &mut wgpu::CommandEncoder, target: &wgpu::TextureView) {
+		let Some((_, _, depth)) = &self.depth else {
+			return;
+		};
+		let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+			label: Some("meshes"),
+			color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+				view: target,
+				depth_slice: None,
+				resolve_target: None,
+				ops: wgpu::Operations {
+					load: wgpu::LoadOp::Clear(self.clear_color),
+					store: wgpu::StoreOp::Store,
+				},
+			})],
+			depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+				view: depth,
+				depth_ops: Some(wgpu::Operations {
+					load: wgpu::LoadOp::Clear(1.0),
+					store: wgpu::StoreOp::Discard,
+				}),
+				stencil_ops: None,
+			}),
+			..Default::default()
+		});
+		let [x, y, width, height] = self.viewport;
+		pass.set_viewport(x, y, width, height, 0.0, 1.0);
+		pass.set_bind_group(0, &self.scene_group, &[]);
+		pass.set_vertex_buffer(1, self.instances.slice(..));
+		let mut translucent = None;
+		for draw in &self.draws {
+			let Some(mesh) = self.meshes.get(&draw.mesh) else {
+				continue;
+			};
+			if translucent != Some(draw.translucent) {
+				pass.set_pipeline(if draw.translucent {
+					&self.translucent
+				} else {
+					&self.opaque
+				});
+				translucent = Some(draw.translucent);
+			}
+			pass.set_vertex_buffer(0, mesh.vertices.slice(..));
+			pass.set_index_buffer(mesh.indices.slice(..), wgpu::IndexFormat::Uint32);
+			pass.draw_indexed(0..mesh.index_count, 0, draw.instances.clone());
+		}
+	}
+}
+
+/// The visible instances in the order they're drawn, and the draws covering them
+fn submission(
+	meshes: &ExtractedMeshes,
+	view_projection: &Mat4,
+	bounds: impl Fn(&str) -> Option<Aabb>,
+) -> (Vec<Instance>, Vec<Draw>) {
+	let frustum = Frustum::new(view_projection);
+	let eye = meshes.view.translation();
+	let mut visible = meshes
+		.meshes
+		.iter()
+		.filter_map(|mesh| {
+			let bounds = bounds(&mesh.mesh)?.transformed(&mesh.model);
+			if !frustum.intersects_sphere(bounds.center(), bounds.radius()) {
+				return None;
+			}
+			Some((mesh, bounds.center().distance(eye)))
+		})
+		.collect::<Vec<_>>();
+	visible.sort_by(|(a, a_distance), (b, b_distance)| {
+		let (a_translucent, b_translucent) =
+			(a.material.is_translucent(), b.material.is_translucent());
+		a_translucent.cmp(&b_translucent).then_with(|| {
+			if a_translucent {
+				b_distance.total_cmp(a_distance)
+			} else {
+				a_distance.total_cmp(b_distance)
+			}
+		})
+	});
+
+	let mut instances = Vec::with_capacity(visible.len());
+	let mut draws = Vec::<Draw>::new();
+	for (index, (mesh, _)) in visible.into_iter().enumerate() {
+		let material = &mesh.material;
+		instances.push(Instance {
+			model: mesh.model.to_cols_array_2d(),
+			base_color: material.base_color.to_array(),
+			surface: [material.metallic, material.roughness, 0.0, 0.0],
+			emissive: material.emissive.with_alpha(0.0).to_array(),
+		});
+		let index = index as u32;
+		let translucent = material.is_translucent();
+		match draws.last_mut() {
+			Some(draw) if draw.mesh == mesh.mesh && draw.translucent == translucent => {
+				draw.instances.end = index + 1;
+			}
+			_ => draws.push(Draw {
+				mesh: mesh.mesh.clone(),
+				instances: index..index + 1,
+				translucent,
+			}),
+		}
+	}
+	(instances, draws)
+}
+
+fn extend(vector: Vec3, w: f32) -> [f32; 4] {
+	[vector.x, vector.y, vector.z, w]
+}
+
+fn instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+	device.create_buffer(&wgpu::BufferDescriptor {
+		label: Some("mesh instances"),
+		size: (capacity.max(1) * size_of::<Instance>()) as wgpu::BufferAddress,
+		usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+		mapped_at_creation: false,
+	})
+}
+
+fn depth_view(device: &wgpu::Device, width: u32, height: u32) -> wgpu::TextureView {
+	device
+		.create_texture(&wgpu::TextureDescriptor {
+			label: Some("mesh depth"),
+			size: wgpu::Extent3d {
+				width,
+				height,
+				depth_or_array_layers: 1,
+			},
+			mip_level_count: 1,
+			sample_count: 1,
+			dimension: wgpu::TextureDimension::D2,
+			format: DEPTH_FORMAT,
+			usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+			view_formats: &[],
+		})
+		.create_view(&Default::default())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{camera::Camera, extract::MeshInstance, mesh::Material};
+	use math::{Color, Transform};
+
+	#[test]
+	fn meshes_are_culled_and_sorted_for_drawing() {
+		let mesh = |name: &str, z: f32, alpha: f32| MeshInstance {
+			mesh: name.to_string(),
+			model: Transform::from_xyz(0.0, 0.0, z).matrix(),
+			material: Material::color(Color::WHITE.with_alpha(alpha)),
+		};
+		let meshes = ExtractedMeshes {
+			camera: Camera::default(),
+			view: Transform::from_xyz(0.0, 0.0, 10.0).into(),
+			meshes: vec![
+				mesh("glass", -5.0, 0.5),
+				mesh("cube", -5.0, 1.0),
+				mesh("glass", 0.0, 0.5),
+				mesh("cube", 0.0, 1.0),
+				// Behind the camera
+				mesh("cube", 20.0, 1.0),
+				// Not uploaded
+				mesh("missing", 0.0, 1.0),
+				mesh("cube", 5.0, 1.0),
+			],
+			..ExtractedMeshes::default()
+		};
+		let view_projection =
+			CameraView::new(&meshes.camera, &meshes.view, 800, 600).view_projection();
+		let (instances, draws) = submission(&meshes, &view_projection, |name| {
+			(name != "missing").then_some(Aabb::new(Vec3::splat(-1.0), Vec3::ONE))
+		});
+
+		let depths = instances
+			.iter()
+			.map(|instance| instance.model[3][2])
+			.collect::<Vec<_>>();
+		assert_eq!(depths, [5.0, 0.0, -5.0, -5.0, 0.0]);
+		assert_eq!(
+			draws,
+			[
+				Draw {
+					mesh: "cube".to_string(),
+					instances: 0..3,
+					translucent: false,
+				},
+				Draw {
+					mesh: "glass".to_string(),
+					instances: 3..5,
+					translucent: true,
+				},
+			]
+		);
+	}
+}
@@ -0,0 +1,85 @@
+use math::Color;
+
+/// Draws a texture centered on the entity's transform on the xy plane, or the part
+/// of it picked by a `SpriteSheet`. Without a texture the sprite is a plain rectangle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sprite {
+	/// The name the texture was given with `SpriteRenderer::set_texture`
+	pub texture: String,
+	/// In world units
+	pub size: [f32; 2],
+	/// Multiplies the texture's colors
+	pub color: Color,
+	/// Sprites on higher layers are drawn over lower ones
+	pub layer: i32,
+	pub flip_x: bool,
+	pub flip_y: bool,
+}
+
+impl Default for Sprite {
+	fn default() -> Self {
+		Self {
+			texture: String::new(),
+			size: [1.0, 1.0],
+			color: Color::WHITE,
+			layer: 0,
+			flip_x: false,
+			flip_y: false,
+		}
+	}
+}
+
+impl Sprite {
+	pub fn new(texture: impl Into<String>) -> Self {
+		Self {
+			texture: texture.into(),
+			..Self::default()
+		}
+	}
+}
+
+/// Splits a sprite's texture into a grid of equally sized frames,
+/// showing the one at `index`, counted left to right and then down
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SpriteSheet {
+	pub columns: u32,
+	pub rows: u32,
+	pub index: u32,
+}
+
+impl SpriteSheet {
+	pub fn frames(&self) -> u32 {
+		self.columns * self.rows
+	}
+
+	/// The frame's texture coordinates as the left, top, right and bottom edges
+	pub fn uv(&self) -> [f32; 4] {
+		let (columns, rows) = (self.columns.max(1), self.rows.max(1));
+		let index = self.index % (columns * rows);
+		let (column, row) = ((index % columns) as f32, (index / columns) as f32);
+		let (width, height) = (1.0 / columns as f32, 1.0 / rows as f32);
+		[
+			column * width,
+			row * height,
+			(column + 1.0) * width,
+			(row + 1.0) * height,
+		]
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn sheets_pick_frames() {
+		let sheet = SpriteSheet {
+			columns: 4,
+			rows: 2,
+			index: 6,
+		};
+		assert_eq!(sheet.frames(), 8);
+		assert_eq!(sheet.uv(), [0.5, 0.5, 0.75, 1.0]);
+		assert_eq!(SpriteSheet { index: 14, ..sheet }.uv(), sheet.uv());
+	}
+}
@@ -0,0 +1,44 @@
+struct Camera {
+	view_projection: mat4x4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+@group(1) @binding(0) var sprite_texture: texture_2d<f32>;
+@group(1) @binding(1) var sprite_sampler: sampler;
+
+struct Instance {
+	@location(0) position: vec2<f32>,
+	@location(1) size: vec2<f32>,
+	@location(2) rotation: f32,
+	@location(3) uv: vec4<f32>,
+	@location(4) color: vec4<f32>,
+}
+
+struct VertexOutput {
+	@builtin(position) clip_position: vec4<f32>,
+	@location(0) uv: vec2<f32>,
+	@location(1) color: vec4<f32>,
+}
+
+// Each sprite is a strip of four vertices, from the bottom left corner to the top right
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, instance: Instance) -> VertexOutput {
+	let corner = vec2<f32>(f32(index & 1u), f32(index >> 1u));
+	let local = (corner - vec2<f32>(0.5)) * instance.size;
+	let sin_cos = vec2<f32>(sin(instance.rotation), cos(instance.rotation));
+	let rotated = vec2<f32>(
+		local.x * sin_cos.y - local.y * sin_cos.x,
+		local.x * sin_cos.x + local.y * sin_cos.y,
+	);
+	var out: VertexOutput;
+	out.clip_position = camera.view_projection * vec4<f32>(instance.position + rotated, 0.0, 1.0);
+	// Texture coordinates grow downwards while the world's y grows upwards
+	out.uv = mix(instance.uv.xw, instance.uv.zy, corner);
+	out.color = instance.color;
+	return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+	return textureSample(sprite_texture, sprite_sampler, in.uv) * in.color;
+}
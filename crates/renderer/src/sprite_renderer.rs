@@ -0,0 +1,266 @@
+use crate::{
+	camera::CameraView,
+	extract::{ExtractedSprites, SpriteInstance},
+};
+use std::{collections::HashMap, mem::size_of, ops::Range};
+use wgpu::util::DeviceExt;
+
+const INSTANCE_ATTRIBUTES: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array![
+	0 => Float32x2,
+	1 => Float32x2,
+	2 => Float32,
+	3 => Float32x4,
+	4 => Float32x4,
+];
+
+/// Draws extracted sprites with wgpu, one instanced draw call per batch.
+/// Call `prepare` with each frame's sprites, then `render` into a pass on a
+/// target of the format the renderer was created with.
+pub struct SpriteRenderer {
+	device: wgpu::Device,
+	queue: wgpu::Queue,
+	pipeline: wgpu::RenderPipeline,
+	texture_layout: wgpu::BindGroupLayout,
+	sampler: wgpu::Sampler,
+	camera: wgpu::Buffer,
+	camera_group: wgpu::BindGroup,
+	textures: HashMap<String, wgpu::BindGroup>,
+	// Drawn for sprites without a texture, or whose texture isn't set
+	blank: wgpu::BindGroup,
+	instances: wgpu::Buffer,
+	draws: Vec<(String, Range<u32>)>,
+	viewport: [f32; 4],
+}
+
+impl SpriteRenderer {
+	pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, format: wgpu::TextureFormat) -> Self {
+		let shader = device.create_shader_module(wgpu::include_wgsl!("sprite.wgsl"));
+		let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+			label: Some("sprite camera"),
+			entries: &[wgpu::BindGroupLayoutEntry {
+				binding: 0,
+				visibility: wgpu::ShaderStages::VERTEX,
+				ty: wgpu::BindingType::Buffer {
+					ty: wgpu::BufferBindingType::Uniform,
+					has_dynamic_offset: false,
+					min_binding_size: None,
+				},
+				count: None,
+			}],
+		});
+		let texture_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+			label: Some("sprite texture"),
+			entries: &[
+				wgpu::BindGroupLayoutEntry {
+					binding: 0,
+					visibility: wgpu::ShaderStages::FRAGMENT,
+					ty: wgpu::BindingType::Texture {
+						sample_type: wgpu::TextureSampleType::Float { filterable: true },
+						view_dimension: wgpu::TextureViewDimension::D2,
+						multisampled: false,
+					},
+					count: None,
+				},
+				wgpu::BindGroupLayoutEntry {
+					binding: 1,
+					visibility: wgpu::ShaderStages::FRAGMENT,
+					ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+					count: None,
+				},
+			],
+		});
+		let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+			label: Some("sprites"),
+			bind_group_layouts: &[Some(&camera_layout), Some(&texture_layout)],
+			immediate_size: 0,
+		});
+		let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+			label: Some("sprites"),
+			layout: Some(&layout),
+			vertex: wgpu::VertexState {
+				module: &shader,
+				entry_point: Some("vs_main"),
+				compilation_options: Default::default(),
+				buffers: &[Some(wgpu::VertexBufferLayout {
+					array_stride: size_of::<SpriteInstance>() as wgpu::BufferAddress,
+					step_mode: wgpu::VertexStepMode::Instance,
+					attributes: &INSTANCE_ATTRIBUTES,
+				})],
+			},
+			primitive: wgpu::PrimitiveState {
+				topology: wgpu::PrimitiveTopology::TriangleStrip,
+				..Default::default()
+			},
+			depth_stencil: None,
+			multisample: wgpu::MultisampleState::default(),
+			fragment: Some(wgpu::FragmentState {
+				module: &shader,
+				entry_point: Some("fs_main"),
+				compilation_options: Default::default(),
+				targets: &[Some(wgpu::ColorTargetState {
+					format,
+					blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+					write_mask: wgpu::ColorWrites::ALL,
+				})],
+			}),
+			multiview_mask: None,
+			cache: None,
+		});
+
+		let camera = device.create_buffer(&wgpu::BufferDescriptor {
+			label: Some("sprite camera"),
+			size: size_of::<[[f32; 4]; 4]>() as wgpu::BufferAddress,
+			usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+			mapped_at_creation: false,
+		});
+		let camera_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+			label: Some("sprite camera"),
+			layout: &camera_layout,
+			entries: &[wgpu::BindGroupEntry {
+				binding: 0,
+				resource: camera.as_entire_binding(),
+			}],
+		});
+		// Nearest filtering keeps pixel art crisp
+		let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+			label: Some("sprites"),
+			..Default::default()
+		});
+		Self {
+			device: device.clone(),
+			queue: queue.clone(),
+			instances: instance_buffer(device, 64),
+			blank: texture_group(
+				device,
+				queue,
+				(&texture_layout, &sampler),
+				&image::RgbaImage::from_pixel(1, 1, image::Rgba([255; 4])),
+			),
+			pipeline,
+			texture_layout,
+			sampler,
+			camera,
+			camera_group,
+			textures: HashMap::new(),
+			draws: Vec::new(),
+			viewport: [0.0, 0.0, 1.0, 1.0],
+		}
+	}
+
+	/// Uploads a texture for sprites to draw by name, replacing any with the same name
+	pub fn set_texture(&mut self, name: impl Into<String>, image: &image::RgbaImage) {
+		let group = texture_group(
+			&self.device,
+			&self.queue,
+			(&self.texture_layout, &self.sampler),
+			image,
+		);
+		self.textures.insert(name.into(), group);
+	}
+
+	pub fn remove_texture(&mut self, name: &str) {
+		self.textures.remove(name);
+	}
+
+	pub fn has_texture(&self, name: &str) -> bool {
+		self.textures.contains_key(name)
+	}
+
+	/// Uploads the sprites and the camera for a target of this size in pixels
+	pub fn prepare(&mut self, sprites: &ExtractedSprites, width: u32, height: u32) {
+		let view = CameraView::new(&sprites.camera, &sprites.view, width, height);
+		self.queue.write_buffer(
+			&self.camera,
+			0,
+			bytemuck::cast_slice(&view.view_projection().to_cols_array_2d()),
+		);
+		self.viewport = view.viewport;
+		let instances = sprites
+			.batches
+			.iter()
+			.flat_map(|batch| batch.instances.iter().copied())
+			.collect::<Vec<_>>();
+		let needed = (instances.len() * size_of::<SpriteInstance>()) as wgpu::BufferAddress;
+		if needed > self.instances.size() {
+			self.instances = instance_buffer(&self.device, instances.len().next_power_of_two());
+		}
+		self.queue
+			.write_buffer(&self.instances, 0, bytemuck::cast_slice(&instances));
+
+		self.draws.clear();
+		let mut start = 0;
+		for batch in &sprites.batches {
+			let end = start + batch.instances.len() as u32;
+			self.draws.push((batch.texture.clone(), start..end));
+			start = end;
+		}
+	}
+
+	/// Draws the prepared sprites into the camera's viewport
+	pub fn render(&self, pass: &mut wgpu::RenderPass) {
+		if self.draws.is_empty() {
+			return;
+		}
+		let [x, y, width, height] = self.viewport;
+		pass.set_viewport(x, y, width, height, 0.0, 1.0);
+		pass.set_pipeline(&self.pipeline);
+		pass.set_bind_group(0, &self.camera_group, &[]);
+		pass.set_vertex_buffer(0, self.instances.slice(..));
+		for (texture, instances) in &self.draws {
+			let group = self.textures.get(texture).unwrap_or(&self.blank);
+			pass.set_bind_group(1, group, &[]);
+			pass.draw(0..4, instances.clone());
+		}
+	}
+}
+
+fn instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+	device.create_buffer(&wgpu::BufferDescriptor {
+		label: Some("sprite instances"),
+		size: (capacity.max(1) * size_of::<SpriteInstance>()) as wgpu::BufferAddress,
+		usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+		mapped_at_creation: false,
+	})
+}
+
+fn texture_group(
+	device: &wgpu::Device,
+	queue: &wgpu::Queue,
+	(layout, sampler): (&wgpu::BindGroupLayout, &wgpu::Sampler),
+	image: &image::RgbaImage,
+) -> wgpu::BindGroup {
+	let texture = device.create_texture_with_data(
+		queue,
+		&wgpu::TextureDescriptor {
+			label: Some("sprite"),
+			size: wgpu::Extent3d {
+				width: image.width(),
+				height: image.height(),
+				depth_or_array_layers: 1,
+			},
+			mip_level_count: 1,
+			sample_count: 1,
+			dimension: wgpu::TextureDimension::D2,
+			format: wgpu::TextureFormat::Rgba8UnormSrgb,
+			usage: wgpu::TextureUsages::TEXTURE_BINDING,
+			view_formats: &[],
+		},
+		wgpu::util::TextureDataOrder::LayerMajor,
+		image.as_raw(),
+	);
+	let view = texture.create_view(&Default::default());
+	device.create_bind_group(&wgpu::BindGroupDescriptor {
+		label: Some("sprite"),
+		layout,
+		entries: &[
+			wgpu::BindGroupEntry {
+				binding: 0,
+				resource: wgpu::BindingResource::TextureView(&view),
+			},
+			wgpu::BindGroupEntry {
+				binding: 1,
+				resource: wgpu::BindingResource::Sampler(sampler),
+			},
+		],
+	})
+}
@@ -0,0 +1,245 @@
+use ab_glyph::{Font as _, FontArc, GlyphId, PxScale, ScaleFont};
+use math::{Color, Vec2};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TextAlign {
+	/// Lines start at the entity
+	#[default]
+	Left,
+	/// Lines are centered on the entity
+	Center,
+	/// Lines end at the entity
+	Right,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TextSpace {
+	/// Drawn over the scene at the entity's translation in pixels from the target's top left
+	#[default]
+	Screen,
+	/// A label placed in the world by the entity's transform, drawn with the camera
+	World,
+}
+
+/// Draws a string with a font set on the `TextRenderer`, its first line's top at the entity
+#[derive(Debug, Clone, PartialEq)]
+pub struct Text {
+	pub value: String,
+	/// The name the font was given with `TextRenderer::set_font`
+	pub font: String,
+	/// How tall a line is, in pixels on screen and world units in the world
+	pub size: f32,
+	pub color: Color,
+	pub align: TextAlign,
+	/// Lines longer than this are wrapped between words, in the same units as the size
+	pub max_width: Option<f32>,
+	pub space: TextSpace,
+}
+
+impl Text {
+	pub fn new(value: impl Into<String>, font: impl Into<String>) -> Self {
+		Self {
+			value: value.into(),
+			font: font.into(),
+			size: 16.0,
+			color: Color::WHITE,
+			align: TextAlign::Left,
+			max_width: None,
+			space: TextSpace::Screen,
+		}
+	}
+
+	/// A label in the world, a line of which is this many units tall
+	pub fn label(value: impl Into<String>, font: impl Into<String>, size: f32) -> Self {
+		Self {
+			size,
+			space: TextSpace::World,
+			..Self::new(value, font)
+		}
+	}
+
+	pub fn with_size(self, size: f32) -> Self {
+		Self { size, ..self }
+	}
+
+	pub fn with_color(self, color: Color) -> Self {
+		Self { color, ..self }
+	}
+
+	pub fn with_align(self, align: TextAlign) -> Self {
+		Self { align, ..self }
+	}
+
+	pub fn with_max_width(self, max_width: f32) -> Self {
+		Self {
+			max_width: Some(max_width),
+			..self
+		}
+	}
+}
+
+/// A glyph placed by `Font::layout`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlacedGlyph {
+	pub(crate) id: GlyphId,
+	pub character: char,
+	/// Where the glyph sits on its line's baseline, right and down from the text's origin
+	pub position: Vec2,
+}
+
+/// Text laid out in the units of its size, with y growing down from the top of its first line
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TextLayout {
+	pub glyphs: Vec<PlacedGlyph>,
+	pub min: Vec2,
+	pub max: Vec2,
+}
+
+impl TextLayout {
+	pub fn size(&self) -> Vec2 {
+		self.max - self.min
+	}
+}
+
+/// A TrueType or OpenType font to lay out and draw text with
+#[derive(Debug, Clone)]
+pub struct Font(FontArc);
+
+impl Font {
+	pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, ab_glyph::InvalidFont> {
+		FontArc::try_from_vec(bytes).map(Self)
+	}
+
+	pub(crate) fn inner(&self) -> &FontArc {
+		&self.0
+	}
+
+	/// Places the text's glyphs, wrapped and aligned
+	pub fn layout(&self, text: &Text) -> TextLayout {
+		let font = self.0.as_scaled(PxScale::from(text.size.max(0.0)));
+		let advance = |line: &str| {
+			let mut previous = None;
+			line.chars()
+				.map(|character| {
+					let id = font.glyph_id(character);
+					let kern = previous.map_or(0.0, |previous| font.kern(previous, id));
+					previous = Some(id);
+					kern + font.h_advance(id)
+				})
+				.sum::<f32>()
+		};
+		let line_height = font.height() + font.line_gap();
+		let lines = lines(&text.value, text.max_width, text.align, advance);
+
+		let mut layout = TextLayout::default();
+		for (index, (line, start)) in lines.iter().enumerate() {
+			let baseline = font.ascent() + index as f32 * line_height;
+			let mut x = *start;
+			let mut previous = None;
+			for character in line.chars() {
+				let id = font.glyph_id(character);
+				x += previous.map_or(0.0, |previous| font.kern(previous, id));
+				layout.glyphs.push(PlacedGlyph {
+					id,
+					character,
+					position: Vec2::new(x, baseline),
+				});
+				x += font.h_advance(id);
+				previous = Some(id);
+			}
+			layout.min.x = layout.min.x.min(*start);
+			layout.max.x = layout.max.x.max(x);
+		}
+		layout.max.y = lines.len() as f32 * line_height;
+		layout
+	}
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl assets::Asset for Font {
+	fn load(
+		bytes: Vec<u8>,
+		_context: &mut assets::LoadContext,
+	) -> Result<Self, assets::AssetError> {
+		Self::from_bytes(bytes).map_err(assets::AssetError::invalid)
+	}
+}
+
+// Each line with where it starts, wrapping between words to fit the width
+fn lines(
+	value: &str,
+	max_width: Option<f32>,
+	align: TextAlign,
+	advance: impl Fn(&str) -> f32,
+) -> Vec<(&str, f32)> {
+	let mut lines = Vec::new();
+	for paragraph in value.lines() {
+		let mut line = None::<(usize, usize)>;
+		for word in paragraph.split_whitespace() {
+			let start = word.as_ptr() as usize - paragraph.as_ptr() as usize;
+			let end = start + word.len();
+			line = match line {
+				// A word too long for a line of its own overflows it
+				Some((line_start, line_end))
+					if max_width.is_some_and(|max_width| {
+						advance(&paragraph[line_start..end]) > max_width
+					}) =>
+				{
+					lines.push(&paragraph[line_start..line_end]);
+					Some((start, end))
+				}
+				Some((line_start, _)) => Some((line_start, end)),
+				None => Some((start, end)),
+			};
+		}
+		lines.push(line.map_or("", |(start, end)| &paragraph[start..end]));
+	}
+	lines
+		.into_iter()
+		.map(|line| {
+			let start = match align {
+				TextAlign::Left => 0.0,
+				TextAlign::Center => -advance(line) / 2.0,
+				TextAlign::Right => -advance(line),
+			};
+			(line, start)
+		})
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// Every character is one unit wide
+	fn monospace(line: &str) -> f32 {
+		line.chars().count() as f32
+	}
+
+	#[test]
+	fn lines_wrap_between_words_and_align() {
+		assert_eq!(
+			lines(
+				"the quick brown fox",
+				Some(10.0),
+				TextAlign::Left,
+				monospace
+			),
+			[("the quick", 0.0), ("brown fox", 0.0)]
+		);
+		assert_eq!(
+			lines("one\n  two three", None, TextAlign::Left, monospace),
+			[("one", 0.0), ("two three", 0.0)]
+		);
+		// Words longer than the width get a line to themselves
+		assert_eq!(
+			lines("a enormous b", Some(4.0), TextAlign::Right, monospace),
+			[("a", -1.0), ("enormous", -8.0), ("b", -1.0)]
+		);
+		assert_eq!(
+			lines("four", None, TextAlign::Center, monospace),
+			[("four", -2.0)]
+		);
+		assert!(lines("", None, TextAlign::Left, monospace).is_empty());
+	}
+}
@@ -0,0 +1,40 @@
+struct Camera {
+	view_projection: mat4x4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> camera: Camera;
+@group(1) @binding(0) var atlas: texture_2d<f32>;
+@group(1) @binding(1) var atlas_sampler: sampler;
+
+struct Instance {
+	@location(0) origin: vec3<f32>,
+	@location(1) right: vec3<f32>,
+	@location(2) down: vec3<f32>,
+	@location(3) uv: vec4<f32>,
+	@location(4) color: vec4<f32>,
+}
+
+struct VertexOutput {
+	@builtin(position) clip_position: vec4<f32>,
+	@location(0) uv: vec2<f32>,
+	@location(1) color: vec4<f32>,
+}
+
+// Each glyph is a strip of four vertices, from its top left corner to its bottom right
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, instance: Instance) -> VertexOutput {
+	let corner = vec2<f32>(f32(index & 1u), f32(index >> 1u));
+	let position = instance.origin + instance.right * corner.x + instance.down * corner.y;
+	var out: VertexOutput;
+	out.clip_position = camera.view_projection * vec4<f32>(position, 1.0);
+	out.uv = mix(instance.uv.xy, instance.uv.zw, corner);
+	out.color = instance.color;
+	return out;
+}
+
+// The atlas holds how much of each pixel the glyph covers
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+	let coverage = textureSample(atlas, atlas_sampler, in.uv).r;
+	return vec4<f32>(in.color.rgb, in.color.a * coverage);
+}
@@ -0,0 +1,440 @@
+use crate::{
+	camera::CameraView,
+	extract::ExtractedText,
+	text::{Font, TextSpace},
+};
+use ab_glyph::{Font as _, GlyphId, PxScale};
+use math::{glam::camera::rh::proj::directx::orthographic, Vec3};
+use std::{collections::HashMap, mem::size_of, ops::Range};
+
+const ATLAS_SIZE: u32 = 1024;
+// World labels are drawn from glyphs rasterized at this many pixels a line,
+// since how big they end up on screen changes as the camera moves
+const WORLD_GLYPH_PIXELS: f32 = 64.0;
+// Left empty around each glyph so filtering doesn't bleed in its neighbours
+const PADDING: u32 = 1;
+
+const INSTANCE_ATTRIBUTES: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array![
+	0 => Float32x3,
+	1 => Float32x3,
+	2 => Float32x3,
+	3 => Float32x4,
+	4 => Float32x4,
+];
+
+/// A glyph as the shader draws it, spanning `right` and `down` from its top left corner
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct GlyphInstance {
+	origin: [f32; 3],
+	right: [f32; 3],
+	down: [f32; 3],
+	uv: [f32; 4],
+	color: [f32; 4],
+}
+
+// The font, glyph and the pixels a line it was rasterized at
+type GlyphKey = (String, GlyphId, u32);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AtlasGlyph {
+	/// The left, top, right and bottom texture coordinates
+	uv: [f32; 4],
+	/// From where the glyph sits on the baseline to its top left corner, in pixels
+	offset: [f32; 2],
+	size: [f32; 2],
+}
+
+// Glyphs packed into rows of the atlas texture, tallest of each row setting its height
+#[derive(Debug, Default)]
+struct Shelves {
+	cursor: [u32; 2],
+	row_height: u32,
+}
+
+impl Shelves {
+	fn allocate(&mut self, width: u32, height: u32) -> Option<[u32; 2]> {
+		let (width, height) = (width + PADDING, height + PADDING);
+		if width > ATLAS_SIZE {
+			return None;
+		}
+		if self.cursor[0] + width > ATLAS_SIZE {
+			self.cursor = [0, self.cursor[1] + self.row_height];
+			self.row_height = 0;
+		}
+		if self.cursor[1] + height > ATLAS_SIZE {
+			return None;
+		}
+		let position = self.cursor;
+		self.cursor[0] += width;
+		self.row_height = self.row_height.max(height);
+		Some(position)
+	}
+}
+
+// Raised when the atlas has no room left for a glyph
+struct AtlasFull;
+
+/// Draws extracted text with wgpu from an atlas of the glyphs it has seen.
+/// World labels are drawn with the camera into its viewport and screen text over
+/// the whole target. Call `prepare` each frame, then `render` into a pass.
+pub struct TextRenderer {
+	device: wgpu::Device,
+	queue: wgpu::Queue,
+	pipeline: wgpu::RenderPipeline,
+	atlas: wgpu::Texture,
+	atlas_group: wgpu::BindGroup,
+	cameras: [(wgpu::Buffer, wgpu::BindGroup); 2],
+	fonts: HashMap<String, Font>,
+	// Glyphs without an outline, such as spaces, are kept as none
+	glyphs: HashMap<GlyphKey, Option<AtlasGlyph>>,
+	shelves: Shelves,
+	instances: wgpu::Buffer,
+	draws: [Range<u32>; 2],
+	viewports: [[f32; 4]; 2],
+}
+
+impl TextRenderer {
+	pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, format: wgpu::TextureFormat) -> Self {
+		let shader = device.create_shader_module(wgpu::include_wgsl!("text.wgsl"));
+		let camera_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+			label: Some("text camera"),
+			entries: &[wgpu::BindGroupLayoutEntry {
+				binding: 0,
+				visibility: wgpu::ShaderStages::VERTEX,
+				ty: wgpu::BindingType::Buffer {
+					ty: wgpu::BufferBindingType::Uniform,
+					has_dynamic_offset: false,
+					min_binding_size: None,
+				},
+				count: None,
+			}],
+		});
+		let atlas_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+			label: Some("text atlas"),
+			entries: &[
+				wgpu::BindGroupLayoutEntry {
+					binding: 0,
+					visibility: wgpu::ShaderStages::FRAGMENT,
+					ty: wgpu::BindingType::Texture {
+						sample_type: wgpu::TextureSampleType::Float { filterable: true },
+						view_dimension: wgpu::TextureViewDimension::D2,
+						multisampled: false,
+					},
+					count: None,
+				},
+				wgpu::BindGroupLayoutEntry {
+					binding: 1,
+					visibility: wgpu::ShaderStages::FRAGMENT,
+					ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+					count: None,
+				},
+			],
+		});
+		let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+			label: Some("text"),
+			bind_group_layouts: &[Some(&camera_layout), Some(&atlas_layout)],
+			immediate_size: 0,
+		});
+		let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+			label: Some("text"),
+			layout: Some(&layout),
+			vertex: wgpu::VertexState {
+				module: &shader,
+				entry_point: Some("vs_main"),
+				compilation_options: Default::default(),
+				buffers: &[Some(wgpu::VertexBufferLayout {
+					array_stride: size_of::<GlyphInstance>() as wgpu::BufferAddress,
+					step_mode: wgpu::VertexStepMode::Instance,
+					attributes: &INSTANCE_ATTRIBUTES,
+				})],
+			},
+			primitive: wgpu::PrimitiveState {
+				topology: wgpu::PrimitiveTopology::TriangleStrip,
+				..Default::default()
+			},
+			depth_stencil: None,
+			multisample: wgpu::MultisampleState::default(),
+			fragment: Some(wgpu::FragmentState {
+				module: &shader,
+				entry_point: Some("fs_main"),
+				compilation_options: Default::default(),
+				targets: &[Some(wgpu::ColorTargetState {
+					format,
+					blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+					write_mask: wgpu::ColorWrites::ALL,
+				})],
+			}),
+			multiview_mask: None,
+			cache: None,
+		});
+
+		let atlas = device.create_texture(&wgpu::TextureDescriptor {
+			label: Some("text atlas"),
+			size: wgpu::Extent3d {
+				width: ATLAS_SIZE,
+				height: ATLAS_SIZE,
+				depth_or_array_layers: 1,
+			},
+			mip_level_count: 1,
+			sample_count: 1,
+			dimension: wgpu::TextureDimension::D2,
+			format: wgpu::TextureFormat::R8Unorm,
+			usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+			view_formats: &[],
+		});
+		let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+			label: Some("text"),
+			mag_filter: wgpu::FilterMode::Linear,
+			min_filter: wgpu::FilterMode::Linear,
+			..Default::default()
+		});
+		let atlas_view = atlas.create_view(&Default::default());
+		let atlas_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+			label: Some("text atlas"),
+			layout: &atlas_layout,
+			entries: &[
+				wgpu::BindGroupEntry {
+					binding: 0,
+					resource: wgpu::BindingResource::TextureView(&atlas_view),
+				},
+				wgpu::BindGroupEntry {
+					binding: 1,
+					resource: wgpu::BindingResource::Sampler(&sampler),
+				},
+			],
+		});
+		let camera = |label| {
+			let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+				label: Some(label),
+				size: size_of::<[[f32; 4]; 4]>() as wgpu::BufferAddress,
+				usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+				mapped_at_creation: false,
+			});
+			let group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+				label: Some(label),
+				layout: &camera_layout,
+				entries: &[wgpu::BindGroupEntry {
+					binding: 0,
+					resource: buffer.as_entire_binding(),
+				}],
+			});
+			(buffer, group)
+		};
+		Self {
+			device: device.clone(),
+			queue: queue.clone(),
+			pipeline,
+			atlas,
+			atlas_group,
+			cameras: [camera("text world camera"), camera("text screen camera")],
+			fonts: HashMap::new(),
+			glyphs: HashMap::new(),
+			shelves: Shelves::default(),
+			instances: instance_buffer(device, 256),
+			draws: [0..0, 0..0],
+			viewports: [[0.0, 0.0, 1.0, 1.0]; 2],
+		}
+	}
+
+	/// Adds a font for text to draw with by name, replacing any with the same name
+	pub fn set_font(&mut self, name: impl Into<String>, font: Font) {
+		let name = name.into();
+		self.glyphs.retain(|(font, ..), _| *font != name);
+		self.fonts.insert(name, font);
+	}
+
+	pub fn remove_font(&mut self, name: &str) {
+		self.fonts.remove(name);
+	}
+
+	pub fn font(&self, name: &str) -> Option<&Font> {
+		self.fonts.get(name)
+	}
+
+	/// Lays out the text, rasterizing glyphs it hasn't drawn before, and uploads it
+	/// along with the cameras for a target of this size in pixels
+	pub fn prepare(&mut self, text: &ExtractedText, width: u32, height: u32) {
+		let view = CameraView::new(&text.camera, &text.view, width, height);
+		let (width, height) = (width.max(1) as f32, height.max(1) as f32);
+		let screen = orthographic(0.0, width, height, 0.0, -1.0, 1.0);
+		for ((buffer, _), matrix) in self.cameras.iter().zip([view.view_projection(), screen]) {
+			self.queue
+				.write_buffer(buffer, 0, bytemuck::cast_slice(&matrix.to_cols_array_2d()));
+		}
+		self.viewports = [view.viewport, [0.0, 0.0, width, height]];
+
+		// When the atlas fills up it's cleared, and the glyphs this frame needs are packed again
+		let instances = match self.instances(text) {
+			Ok(instances) => instances,
+			Err(AtlasFull) => {
+				self.glyphs.clear();
+				self.shelves = Shelves::default();
+				self.instances(text).unwrap_or_default()
+			}
+		};
+		let (world, screen): (Vec<_>, Vec<_>) = instances
+			.into_iter()
+			.partition(|(space, _)| *space == TextSpace::World);
+		let world_count = world.len() as u32;
+		let instances = world
+			.into_iter()
+			.chain(screen)
+			.map(|(_, instance)| instance)
+			.collect::<Vec<_>>();
+		self.draws = [0..world_count, world_count..instances.len() as u32];
+		let needed = (instances.len() * size_of::<GlyphInstance>()) as wgpu::BufferAddress;
+		if needed > self.instances.size() {
+			self.instances = instance_buffer(&self.device, instances.len().next_power_of_two());
+		}
+		self.queue
+			.write_buffer(&self.instances, 0, bytemuck::cast_slice(&instances));
+	}
+
+	/// Draws the prepared world labels, then the screen text over them
+	pub fn render(&self, pass: &mut wgpu::RenderPass) {
+		pass.set_pipeline(&self.pipeline);
+		pass.set_bind_group(1, &self.atlas_group, &[]);
+		pass.set_vertex_buffer(0, self.instances.slice(..));
+		for ((draw, (_, camera)), viewport) in
+			self.draws.iter().zip(&self.cameras).zip(self.viewports)
+		{
+			if draw.is_empty() {
+				continue;
+			}
+			let [x, y, width, height] = viewport;
+			pass.set_viewport(x, y, width, height, 0.0, 1.0);
+			pass.set_bind_group(0, camera, &[]);
+			pass.draw(0..4, draw.clone());
+		}
+	}
+
+	fn instances(
+		&mut self,
+		text: &ExtractedText,
+	) -> Result<Vec<(TextSpace, GlyphInstance)>, AtlasFull> {
+		let mut instances = Vec::new();
+		for (text, transform) in &text.texts {
+			let Some(font) = self.fonts.get(&text.font).cloned() else {
+				continue;
+			};
+			let pixels = match text.space {
+				TextSpace::Screen => text.size.round().max(1.0),
+				TextSpace::World => WORLD_GLYPH_PIXELS,
+			};
+			// Text units, and the pixels rasterized for them, are mapped onto the
+			// world with y up or onto the screen with y down
+			let matrix = transform.matrix();
+			let units_per_pixel = text.size / pixels;
+			let down = match text.space {
+				TextSpace::Screen => Vec3::Y,
+				TextSpace::World => -Vec3::Y,
+			};
+			let right = matrix.transform_vector3(Vec3::X) * units_per_pixel;
+			let down = matrix.transform_vector3(down) * units_per_pixel;
+			let origin = matrix.w_axis.truncate();
+
+			for glyph in font.layout(text).glyphs {
+				let Some(atlas_glyph) =
+					self.glyph(&font, (text.font.clone(), glyph.id, pixels as u32))?
+				else {
+					continue;
+				};
+				let [x, y] = (glyph.position / units_per_pixel).to_array();
+				let (left, top) = (x + atlas_glyph.offset[0], y + atlas_glyph.offset[1]);
+				instances.push((
+					text.space,
+					GlyphInstance {
+						origin: (origin + right * left + down * top).to_array(),
+						right: (right * atlas_glyph.size[0]).to_array(),
+						down: (down * atlas_glyph.size[1]).to_array(),
+						uv: atlas_glyph.uv,
+						color: text.color.to_array(),
+					},
+				));
+			}
+		}
+		Ok(instances)
+	}
+
+	// The glyph's place in the atlas, rasterizing it into the atlas the first time it's seen
+	fn glyph(&mut self, font: &Font, key: GlyphKey) -> Result<Option<AtlasGlyph>, AtlasFull> {
+		if let Some(glyph) = self.glyphs.get(&key) {
+			return Ok(*glyph);
+		}
+		let glyph = key.1.with_scale(PxScale::from(key.2 as f32));
+		let Some(outline) = font.inner().outline_glyph(glyph) else {
+			self.glyphs.insert(key, None);
+			return Ok(None);
+		};
+		let bounds = outline.px_bounds();
+		let (width, height) = (bounds.width() as u32, bounds.height() as u32);
+		let [x, y] = self.shelves.allocate(width, height).ok_or(AtlasFull)?;
+		let mut coverage = vec![0_u8; (width * height) as usize];
+		outline.draw(|column, row, amount| {
+			if let Some(pixel) = coverage.get_mut((row * width + column) as usize) {
+				*pixel = (amount.clamp(0.0, 1.0) * 255.0) as u8;
+			}
+		});
+		if width > 0 && height > 0 {
+			self.queue.write_texture(
+				wgpu::TexelCopyTextureInfo {
+					texture: &self.atlas,
+					mip_level: 0,
+					origin: wgpu::Origin3d { x, y, z: 0 },
+					aspect: wgpu::TextureAspect::All,
+				},
+				&coverage,
+				wgpu::TexelCopyBufferLayout {
+					offset: 0,
+					bytes_per_row: Some(width),
+					rows_per_image: None,
+				},
+				wgpu::Extent3d {
+					width,
+					height,
+					depth_or_array_layers: 1,
+				},
+			);
+		}
+		let size = ATLAS_SIZE as f32;
+		let atlas_glyph = AtlasGlyph {
+			uv: [
+				x as f32 / size,
+				y as f32 / size,
+				(x + width) as f32 / size,
+				(y + height) as f32 / size,
+			],
+			offset: [bounds.min.x, bounds.min.y],
+			size: [width as f32, height as f32],
+		};
+		self.glyphs.insert(key, Some(atlas_glyph));
+		Ok(Some(atlas_glyph))
+	}
+}
+
+fn instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+	device.create_buffer(&wgpu::BufferDescriptor {
+		label: Some("text instances"),
+		size: (capacity.max(1) * size_of::<GlyphInstance>()) as wgpu::BufferAddress,
+		usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+		mapped_at_creation: false,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn glyphs_pack_into_rows_until_the_atlas_is_full() {
+		let mut shelves = Shelves::default();
+		assert_eq!(shelves.allocate(500, 20), Some([0, 0]));
+		assert_eq!(shelves.allocate(500, 40), Some([501, 0]));
+		// Too wide for what's left of the row, so it starts the next one below the tallest
+		assert_eq!(shelves.allocate(100, 10), Some([0, 41]));
+		assert_eq!(shelves.allocate(ATLAS_SIZE + 1, 10), None);
+		assert_eq!(shelves.allocate(10, ATLAS_SIZE), None);
+		assert_eq!(shelves.allocate(10, 10), Some([101, 41]));
+	}
+}
@@ -0,0 +1,12 @@
+#![forbid(unsafe_code)]
+
+mod script;
+mod system;
+mod value;
+
+pub use self::{
+	script::{Script, ScriptError},
+	system::{evaluate, run_scripts, ScriptBindings},
+	value::ScriptValue,
+};
+pub use rhai;
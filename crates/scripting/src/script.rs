@@ -0,0 +1,232 @@
+use crate::value::ScriptValue;
+use ecs::{reflect::ReflectError, world::Entity};
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, Map, ParseError, Scope, AST};
+use std::sync::Arc;
+use thiserror::Error;
+
+// Stops scripts that loop forever or recurse too deep from hanging the frame
+const MAX_OPERATIONS: u64 = 1_000_000;
+const MAX_DEPTH: usize = 64;
+
+#[derive(Error, Debug)]
+pub enum ScriptError {
+	#[error("Syntax error on line {line}: {message}")]
+	Syntax { line: usize, message: String },
+
+	#[error("No variable named {0}")]
+	UnknownVariable(String),
+
+	#[error("No function named {0}")]
+	UnknownFunction(String),
+
+	#[error("Wrong arguments passed to {0}")]
+	Arguments(String),
+
+	#[error("No component named {0} is registered")]
+	UnknownComponent(String),
+
+	#[error("{0}")]
+	Runtime(String),
+
+	#[error("Failed to set component field: {0}")]
+	Reflect(#[from] ReflectError),
+
+	#[error("Failed to publish: {0}")]
+	Publish(#[from] bus::EventBusError),
+}
+
+impl From<ParseError> for ScriptError {
+	fn from(ParseError(error, position): ParseError) -> Self {
+		Self::Syntax {
+			line: position.line().unwrap_or_default(),
+			message: error.to_string(),
+		}
+	}
+}
+
+impl From<Box<EvalAltResult>> for ScriptError {
+	fn from(error: Box<EvalAltResult>) -> Self {
+		match *error {
+			// Reported as what went wrong inside the function rather than the call to it
+			EvalAltResult::ErrorInFunctionCall(_, _, error, _) => error.into(),
+			EvalAltResult::ErrorParsing(error, position) => Self::Syntax {
+				line: position.line().unwrap_or_default(),
+				message: error.to_string(),
+			},
+			EvalAltResult::ErrorVariableNotFound(name, _) => Self::UnknownVariable(name),
+			EvalAltResult::ErrorFunctionNotFound(signature, _) => Self::UnknownFunction(signature),
+			error => Self::Runtime(error.to_string()),
+		}
+	}
+}
+
+/// An engine with the limits scripts run under and entities as a type they can hold
+pub(crate) fn engine() -> Engine {
+	let mut engine = Engine::new();
+	engine
+		.set_max_operations(MAX_OPERATIONS)
+		.set_max_call_levels(MAX_DEPTH)
+		.register_type_with_name::<Entity>("Entity")
+		.register_fn("==", |a: Entity, b: Entity| a == b)
+		.register_fn("!=", |a: Entity, b: Entity| a != b)
+		.register_fn("to_string", |entity: &mut Entity| {
+			ScriptValue::Entity(*entity).to_string()
+		})
+		.register_fn("to_debug", |entity: &mut Entity| {
+			ScriptValue::Entity(*entity).to_string()
+		});
+	engine
+}
+
+/// A compiled [rhai](https://rhai.rs) script, and the component that runs it on an entity:
+///
+/// ```text
+/// let jumps = 0;
+///
+/// fn update(entity, delta) {
+///     let position = get(entity, "Position");
+///     if just_pressed("jump") {
+///         position.y += 2.0;
+///         this.jumps += 1;
+///         publish("gameplay", "jumped", #{ jumps: this.jumps });
+///     }
+///     set(entity, "Position", position);
+/// }
+/// ```
+///
+/// Variables declared at the top keep their values between frames, and functions reach
+/// them through `this`, since rhai functions can't see the variables around them. When the
+/// script first runs its top level runs once, then its `start(entity)` function if it has
+/// one, and `run_scripts` calls its `update(entity, delta)` function every frame after that.
+#[derive(Debug, Clone)]
+pub struct Script {
+	ast: Arc<AST>,
+	variables: Dynamic,
+	started: bool,
+}
+
+impl Script {
+	pub fn compile(source: &str) -> Result<Self, ScriptError> {
+		Ok(Self {
+			ast: Arc::new(engine().compile(source)?),
+			variables: Map::new().into(),
+			started: false,
+		})
+	}
+
+	pub fn has_function(&self, name: &str) -> bool {
+		self.ast
+			.iter_functions()
+			.any(|function| function.name == name)
+	}
+
+	pub fn is_started(&self) -> bool {
+		self.started
+	}
+
+	/// A variable declared at the top of the script, once it has started
+	pub fn variable(&self, name: &str) -> Option<ScriptValue> {
+		self.variables
+			.read_lock::<Map>()?
+			.get(name)
+			.map(|value| value.clone().into())
+	}
+
+	pub fn set_variable(&mut self, name: &str, value: ScriptValue) {
+		if let Some(mut variables) = self.variables.write_lock::<Map>() {
+			variables.insert(name.into(), value.into());
+		}
+	}
+
+	/// Runs the top of the script and its `start` function
+	pub(crate) fn start(&mut self, engine: &Engine, entity: Entity) -> Result<(), ScriptError> {
+		self.started = true;
+		let mut scope = Scope::new();
+		engine.run_ast_with_scope(&mut scope, &self.ast)?;
+		for (name, _, value) in scope.iter() {
+			self.set_variable(name, value.into());
+		}
+		if self.has_function("start") {
+			self.call(engine, "start", vec![ScriptValue::Entity(entity)])?;
+		}
+		Ok(())
+	}
+
+	pub(crate) fn call(
+		&mut self,
+		engine: &Engine,
+		name: &str,
+		arguments: Vec<ScriptValue>,
+	) -> Result<ScriptValue, ScriptError> {
+		// The top level already ran when the script started
+		let options = CallFnOptions::new()
+			.eval_ast(false)
+			.bind_this_ptr(&mut self.variables);
+		let arguments = arguments
+			.into_iter()
+			.map(Into::into)
+			.collect::<Vec<Dynamic>>();
+		let result = engine.call_fn_with_options::<Dynamic>(
+			options,
+			&mut Scope::new(),
+			&self.ast,
+			name,
+			arguments,
+		)?;
+		Ok(result.into())
+	}
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl assets::Asset for Script {
+	fn load(
+		bytes: Vec<u8>,
+		_context: &mut assets::LoadContext,
+	) -> Result<Self, assets::AssetError> {
+		let source = String::from_utf8(bytes).map_err(assets::AssetError::invalid)?;
+		Self::compile(&source).map_err(assets::AssetError::invalid)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn scripts_keep_their_variables_between_calls() -> Result<(), ScriptError> {
+		let engine = engine();
+		let mut script = Script::compile(
+			r#"
+			let count = 10;
+			fn start(entity) { this.count = 0; }
+			fn update(entity, delta) { this.count += 1; return this.count; }
+			"#,
+		)?;
+		assert!(!script.is_started());
+		assert_eq!(script.variable("count"), None);
+		script.start(&engine, Entity::default())?;
+		assert_eq!(script.variable("count"), Some(ScriptValue::Integer(0)));
+
+		// Copies share the compiled program but not their variables
+		let mut copy = script.clone();
+		script.call(&engine, "update", vec![ScriptValue::Unit; 2])?;
+		assert_eq!(
+			script.call(&engine, "update", vec![ScriptValue::Unit; 2])?,
+			ScriptValue::Integer(2)
+		);
+		copy.set_variable("count", ScriptValue::Integer(40));
+		assert_eq!(
+			copy.call(&engine, "update", vec![ScriptValue::Unit; 2])?,
+			ScriptValue::Integer(41)
+		);
+		assert!(matches!(
+			script.call(&engine, "update", Vec::new()),
+			Err(ScriptError::UnknownFunction(_))
+		));
+		assert!(matches!(
+			Script::compile("fn update( {"),
+			Err(ScriptError::Syntax { line: 1, .. })
+		));
+		Ok(())
+	}
+}
@@ -0,0 +1,505 @@
+use crate::{
+	script::{engine, Script, ScriptError},
+	value::ScriptValue,
+};
+use app::Input;
+use bus::resource::BusResource;
+use ecs::{
+	error::Result,
+	reflect::{ComponentRegistry, Field},
+	time::delta_seconds,
+	world::{Entity, World},
+};
+use rhai::{Dynamic, Engine, EvalAltResult, Position};
+use std::{any::TypeId, cell::RefCell, collections::HashMap, rc::Rc};
+
+type Function =
+	Rc<dyn Fn(&mut World, Option<Entity>, &[ScriptValue]) -> Result<ScriptValue, ScriptError>>;
+
+// The built in functions, by name and number of arguments
+const BUILT_INS: [(&str, usize); 12] = [
+	("spawn_entity", 0),
+	("despawn", 1),
+	("get", 2),
+	("set", 3),
+	("has", 2),
+	("remove", 2),
+	("publish", 2),
+	("publish", 3),
+	("pressed", 1),
+	("just_pressed", 1),
+	("just_released", 1),
+	("axis", 1),
+];
+
+thread_local! {
+	// Functions given to rhai have to be thread safe, so the world they work on is moved
+	// here while scripts run rather than shared with them
+	static CONTEXT: RefCell<Option<Context>> = const { RefCell::new(None) };
+}
+
+/// Functions scripts can call beyond the built in ones, kept as a world resource.
+/// Each is passed the entity whose script called it, or `None` when `evaluate` called it.
+#[derive(Default, Clone)]
+pub struct ScriptBindings {
+	functions: HashMap<String, (usize, Function)>,
+}
+
+impl ScriptBindings {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Registering a function with the name and number of arguments of a built in one
+	/// replaces it
+	pub fn register_function(
+		&mut self,
+		name: impl Into<String>,
+		arguments: usize,
+		function: impl Fn(&mut World, Option<Entity>, &[ScriptValue]) -> Result<ScriptValue, ScriptError>
+			+ 'static,
+	) {
+		self.functions
+			.insert(name.into(), (arguments, Rc::new(function)));
+	}
+}
+
+/// Runs the `Script` of every entity that has one, starting new scripts then calling their
+/// `update(entity, delta)` function with the `Time` resource's delta. Besides their own
+/// functions and those in the `ScriptBindings` resource, scripts can call:
+///
+/// - `spawn_entity()` and `despawn(entity)`
+/// - `get(entity, "Component")`, which returns a map of the fields of a component in the
+///   `ComponentRegistry` resource or `()` when the entity doesn't have it
+/// - `set(entity, "Component", #{ field: value })`, adding the component if it's missing
+/// - `has(entity, "Component")` and `remove(entity, "Component")`
+/// - `publish("channel", "topic", value)`, through the `BusResource<ScriptValue>` resource
+/// - `pressed("action")`, `just_pressed("action")`, `just_released("action")` and
+///   `axis("axis")`, from the `Input` resource
+pub fn run_scripts(world: &mut World) -> Result<()> {
+	let delta = delta_seconds(world);
+	let bindings = world
+		.resources()
+		.borrow()
+		.get::<ScriptBindings>()
+		.cloned()
+		.unwrap_or_default();
+	let scripted = world
+		.entities()
+		.into_iter()
+		.filter(|entity| world.get_component::<Script>(*entity).is_some())
+		.collect::<Vec<_>>();
+	with_context(world, &bindings, |engine| {
+		for entity in scripted {
+			// Copied while it runs so it can use the entity's components, then written back
+			let Some(mut script) = context(|context| {
+				context.entity = Some(entity);
+				context.error = None;
+				context
+					.world
+					.get_component::<Script>(entity)
+					.map(|script| script.clone())
+			})
+			.flatten() else {
+				continue;
+			};
+			if !script.is_started() {
+				script.start(engine, entity)?;
+			}
+			if script.has_function("update") {
+				let arguments = vec![
+					ScriptValue::Entity(entity),
+					ScriptValue::Float(delta.into()),
+				];
+				script.call(engine, "update", arguments)?;
+			}
+			// Unless the script removed it or the entity
+			context(|context| {
+				if let Some(mut component) = context.world.get_component_mut::<Script>(entity) {
+					*component = script;
+				}
+			});
+		}
+		Ok(())
+	})?;
+	Ok(())
+}
+
+/// Runs a snippet of script once, such as a line typed into a console, returning its value.
+/// It can call the same functions as scripts run by `run_scripts`, with `bindings` in place
+/// of the `ScriptBindings` resource.
+pub fn evaluate(
+	world: &mut World,
+	bindings: &ScriptBindings,
+	source: &str,
+) -> Result<ScriptValue, ScriptError> {
+	with_context(world, bindings, |engine| {
+		Ok(engine.eval::<Dynamic>(source)?.into())
+	})
+}
+
+fn with_context<T>(
+	world: &mut World,
+	bindings: &ScriptBindings,
+	run: impl FnOnce(&Engine) -> Result<T, ScriptError>,
+) -> Result<T, ScriptError> {
+	let mut engine = engine();
+	for (name, arguments) in BUILT_INS {
+		register(&mut engine, (name, arguments), move |context, arguments| {
+			context.call(name, arguments)
+		});
+	}
+	for (name, (arguments, _)) in &bindings.functions {
+		let binding = name.clone();
+		register(
+			&mut engine,
+			(name, *arguments),
+			move |context, arguments| context.bind(&binding, arguments),
+		);
+	}
+	// Cloned out of the resources, so bindings are free to use them
+	let components = world
+		.resources()
+		.borrow()
+		.get::<ComponentRegistry>()
+		.cloned()
+		.unwrap_or_default();
+	CONTEXT.set(Some(Context {
+		world: std::mem::take(world),
+		entity: None,
+		bindings: bindings.clone(),
+		components,
+		error: None,
+	}));
+	let result = run(&engine);
+	let Some(mut context) = CONTEXT.take() else {
+		return result;
+	};
+	*world = context.world;
+	// Rhai only carries the text of errors from functions, so the original is preferred
+	result.map_err(|error| context.error.take().unwrap_or(error))
+}
+
+// Runs something with the context scripts are running in, if they are
+fn context<T>(run: impl FnOnce(&mut Context) -> T) -> Option<T> {
+	CONTEXT.with_borrow_mut(|context| context.as_mut().map(run))
+}
+
+fn register(
+	engine: &mut Engine,
+	(name, arguments): (&str, usize),
+	function: impl Fn(&mut Context, &[ScriptValue]) -> Result<ScriptValue, ScriptError>
+		+ Send
+		+ Sync
+		+ 'static,
+) {
+	engine.register_raw_fn(
+		name,
+		vec![TypeId::of::<Dynamic>(); arguments],
+		move |_, arguments: &mut [&mut Dynamic]| -> Result<Dynamic, Box<EvalAltResult>> {
+			let arguments = arguments
+				.iter()
+				.map(|argument| ScriptValue::from((**argument).clone()))
+				.collect::<Vec<_>>();
+			let result = context(|context| {
+				function(context, &arguments).map_err(|error| {
+					let message = error.to_string();
+					context.error = Some(error);
+					message
+				})
+			})
+			.unwrap_or_else(|| Err("Scripts can only call this while they run".to_string()));
+			result.map(Into::into).map_err(|message| {
+				EvalAltResult::ErrorRuntime(message.into(), Position::NONE).into()
+			})
+		},
+	);
+}
+
+struct Context {
+	world: World,
+	entity: Option<Entity>,
+	bindings: ScriptBindings,
+	components: ComponentRegistry,
+	error: Option<ScriptError>,
+}
+
+impl Context {
+	fn call(&mut self, name: &str, arguments: &[ScriptValue]) -> Result<ScriptValue, ScriptError> {
+		match name {
+			"spawn_entity" => Ok(ScriptValue::Entity(self.world.create_entity())),
+			"despawn" => argument(name, arguments, 0, ScriptValue::as_entity).map(|entity| {
+				self.world.remove_entity(entity);
+				ScriptValue::Unit
+			}),
+			"get" | "set" | "has" | "remove" => self.component(name, arguments),
+			"publish" => publish(&self.world, arguments),
+			"pressed" | "just_pressed" | "just_released" | "axis" => {
+				input(&self.world, name, arguments)
+			}
+			_ => Err(ScriptError::UnknownFunction(name.to_string())),
+		}
+	}
+
+	fn bind(&mut self, name: &str, arguments: &[ScriptValue]) -> Result<ScriptValue, ScriptError> {
+		let (_, function) = self
+			.bindings
+			.functions
+			.get(name)
+			.ok_or_else(|| ScriptError::UnknownFunction(name.to_string()))?;
+		function(&mut self.world, self.entity, arguments)
+	}
+
+	fn component(
+		&mut self,
+		name: &str,
+		arguments: &[ScriptValue],
+	) -> Result<ScriptValue, ScriptError> {
+		let entity = argument(name, arguments, 0, ScriptValue::as_entity)?;
+		let component = argument(name, arguments, 1, ScriptValue::as_text)?;
+		let info = self
+			.components
+			.get(component)
+			.ok_or_else(|| ScriptError::UnknownComponent(component.to_string()))?;
+		let fields = info.fields(&self.world, entity);
+		let error = |error: Box<dyn std::error::Error>| ScriptError::Runtime(error.to_string());
+		Ok(match name {
+			"get" => fields.map_or(ScriptValue::Unit, |fields| {
+				ScriptValue::Map(
+					fields
+						.into_iter()
+						.map(|field| (field.name.to_string(), field.value.into()))
+						.collect(),
+				)
+			}),
+			"has" => ScriptValue::Bool(fields.is_some()),
+			"remove" => {
+				if fields.is_some() {
+					info.remove(&mut self.world, entity).map_err(error)?;
+				}
+				ScriptValue::Unit
+			}
+			_ => {
+				let Some(ScriptValue::Map(values)) = arguments.get(2) else {
+					return Err(ScriptError::Arguments(name.to_string()));
+				};
+				let fields = match fields {
+					Some(fields) => fields,
+					None => {
+						info.add_default(&mut self.world, entity).map_err(error)?;
+						info.default_fields()
+					}
+				};
+				for field in fields {
+					let Some(value) = values.get(field.name) else {
+						continue;
+					};
+					let value = value.to_field(&field.value).ok_or_else(|| {
+						ScriptError::Runtime(format!(
+							"Can't set {component}.{} to a {}",
+							field.name,
+							value.type_name()
+						))
+					})?;
+					info.set_field(
+						&self.world,
+						entity,
+						Field {
+							name: field.name,
+							value,
+						},
+					)?;
+				}
+				ScriptValue::Unit
+			}
+		})
+	}
+}
+
+fn argument<'a, T>(
+	function: &str,
+	arguments: &'a [ScriptValue],
+	index: usize,
+	convert: impl Fn(&'a ScriptValue) -> Option<T>,
+) -> Result<T, ScriptError> {
+	arguments
+		.get(index)
+		.and_then(convert)
+		.ok_or_else(|| ScriptError::Arguments(function.to_string()))
+}
+
+fn publish(world: &World, arguments: &[ScriptValue]) -> Result<ScriptValue, ScriptError> {
+	let channel = argument("publish", arguments, 0, ScriptValue::as_text)?;
+	let topic = argument("publish", arguments, 1, ScriptValue::as_text)?;
+	let payload = arguments.get(2).cloned().unwrap_or_default();
+	let resources = world.resources();
+	let resources = resources.borrow();
+	let bus = resources.get::<BusResource<ScriptValue>>().ok_or_else(|| {
+		ScriptError::Runtime("No BusResource<ScriptValue> to publish to".to_string())
+	})?;
+	bus.publish_sync(channel, topic, payload)?;
+	Ok(ScriptValue::Unit)
+}
+
+// Reads as released and centered when there's no input resource
+fn input(world: &World, name: &str, arguments: &[ScriptValue]) -> Result<ScriptValue, ScriptError> {
+	let action = argument(name, arguments, 0, ScriptValue::as_text)?;
+	let resources = world.resources();
+	let resources = resources.borrow();
+	let input = resources.get::<Input>();
+	Ok(match name {
+		"axis" => ScriptValue::Float(input.map_or(0.0, |input| input.axis(action)).into()),
+		_ => ScriptValue::Bool(input.is_some_and(|input| match name {
+			"pressed" => input.is_action_pressed(action),
+			"just_pressed" => input.is_action_just_pressed(action),
+			_ => input.is_action_just_released(action),
+		})),
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use bus::{EventBus, Message};
+	use ecs::{events::Events, reflect, time::advance_time};
+	use std::sync::Arc;
+
+	#[derive(Debug, Default, PartialEq)]
+	struct Position {
+		x: f32,
+		y: f32,
+	}
+
+	reflect!(Position { x, y });
+
+	#[derive(Debug, Default, PartialEq)]
+	struct Ammo {
+		count: u32,
+	}
+
+	reflect!(Ammo { count });
+
+	#[test]
+	fn scripts_update_components_spawn_and_publish() -> Result<()> {
+		let mut world = World::new();
+		let mut components = ComponentRegistry::new();
+		components
+			.register::<Position>("Position")
+			.register::<Ammo>("Ammo");
+		let mut bindings = ScriptBindings::new();
+		bindings.register_function("fire_rate", 0, |_, _, _| Ok(ScriptValue::Integer(2)));
+		let event_bus = Arc::new(EventBus::<ScriptValue>::new());
+		event_bus.add_channel("gameplay")?;
+		{
+			let resources = world.resources();
+			let mut resources = resources.borrow_mut();
+			resources.insert(components);
+			resources.insert(bindings);
+			resources.insert(BusResource::new(event_bus.clone()));
+		}
+		let script = Script::compile(
+			r#"
+			let speed = 4;
+			let spawned = [];
+
+			fn start(entity) {
+				set(entity, "Ammo", #{ count: 3 });
+			}
+
+			fn update(entity, delta) {
+				let position = get(entity, "Position");
+				position.x += this.speed * delta;
+				set(entity, "Position", position);
+
+				let ammo = get(entity, "Ammo");
+				if ammo.count > 0 && !pressed("hold") {
+					ammo.count -= fire_rate() - 1;
+					set(entity, "Ammo", ammo);
+					let bullet = spawn_entity();
+					set(bullet, "Position", #{ x: position.x });
+					this.spawned += [bullet];
+					publish("gameplay", "fired", #{ left: ammo.count, at: position.x });
+				}
+			}
+			"#,
+		)?;
+		let player = world.create_entity();
+		world.add_component(player, Position::default())?;
+		world.add_component(player, script)?;
+
+		advance_time(&mut world, 0.5);
+		run_scripts(&mut world)?;
+		run_scripts(&mut world)?;
+		assert_eq!(
+			world.get_component::<Position>(player).as_deref(),
+			Some(&Position { x: 4.0, y: 0.0 })
+		);
+		assert_eq!(
+			world.get_component::<Ammo>(player).as_deref(),
+			Some(&Ammo { count: 1 })
+		);
+
+		let script = world.get_component::<Script>(player).unwrap().clone();
+		let Some(ScriptValue::Array(spawned)) = script.variable("spawned") else {
+			panic!("spawned isn't an array");
+		};
+		let bullet = spawned[1].as_entity().unwrap();
+		assert_eq!(
+			world.get_component::<Position>(bullet).as_deref(),
+			Some(&Position { x: 4.0, y: 0.0 })
+		);
+		let mut events = Events::new();
+		world
+			.resources()
+			.borrow()
+			.get::<BusResource<ScriptValue>>()
+			.unwrap()
+			.drain_into("gameplay", &mut events)?;
+		let messages = events.drain().map(Message::into_parts).collect::<Vec<_>>();
+		assert_eq!(messages.len(), 2);
+		assert_eq!(messages[1].0, "fired");
+		assert_eq!(messages[1].1.to_string(), "#{ at: 4.0, left: 1 }");
+
+		world.add_component(
+			player,
+			Script::compile("fn update(entity, delta) { get(entity, \"Health\"); }")?,
+		)?;
+		assert!(run_scripts(&mut world).is_err());
+		Ok(())
+	}
+
+	#[test]
+	fn snippets_run_once_against_the_world() -> Result<()> {
+		let mut world = World::new();
+		let mut components = ComponentRegistry::new();
+		components.register::<Position>("Position");
+		world.resources().borrow_mut().insert(components);
+		let mut bindings = ScriptBindings::new();
+		bindings.register_function("double", 1, |_, entity, arguments| {
+			assert_eq!(entity, None);
+			let value = argument("double", arguments, 0, ScriptValue::as_float)?;
+			Ok(ScriptValue::Float(value * 2.0))
+		});
+
+		let value = evaluate(
+			&mut world,
+			&bindings,
+			r#"
+			let entity = spawn_entity();
+			set(entity, "Position", #{ x: double(1.5) });
+			get(entity, "Position").x
+			"#,
+		)?;
+		assert_eq!(value, ScriptValue::Float(3.0));
+		assert_eq!(world.entities().len(), 1);
+		assert!(matches!(
+			evaluate(&mut world, &bindings, "get(spawn_entity(), \"Health\")"),
+			Err(ScriptError::UnknownComponent(_))
+		));
+		assert!(matches!(
+			evaluate(&mut world, &bindings, "double()"),
+			Err(ScriptError::UnknownFunction(_))
+		));
+		Ok(())
+	}
+}
@@ -0,0 +1,212 @@
+use ecs::{reflect::Value, world::Entity};
+use rhai::{Array, Dynamic, Map};
+use std::{collections::BTreeMap, fmt};
+
+/// A value in a script, passed to and returned from bindings
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum ScriptValue {
+	#[default]
+	Unit,
+	Bool(bool),
+	Integer(i64),
+	Float(f64),
+	Text(String),
+	Array(Vec<ScriptValue>),
+	Map(BTreeMap<String, ScriptValue>),
+	Entity(Entity),
+}
+
+impl ScriptValue {
+	pub fn type_name(&self) -> &'static str {
+		match self {
+			Self::Unit => "unit",
+			Self::Bool(_) => "bool",
+			Self::Integer(_) => "integer",
+			Self::Float(_) => "float",
+			Self::Text(_) => "string",
+			Self::Array(_) => "array",
+			Self::Map(_) => "map",
+			Self::Entity(_) => "entity",
+		}
+	}
+
+	pub fn as_bool(&self) -> Option<bool> {
+		match self {
+			Self::Bool(value) => Some(*value),
+			_ => None,
+		}
+	}
+
+	pub fn as_integer(&self) -> Option<i64> {
+		match self {
+			Self::Integer(value) => Some(*value),
+			_ => None,
+		}
+	}
+
+	/// Integers are read as floats too
+	pub fn as_float(&self) -> Option<f64> {
+		match self {
+			Self::Integer(value) => Some(*value as f64),
+			Self::Float(value) => Some(*value),
+			_ => None,
+		}
+	}
+
+	pub fn as_text(&self) -> Option<&str> {
+		match self {
+			Self::Text(value) => Some(value),
+			_ => None,
+		}
+	}
+
+	pub fn as_entity(&self) -> Option<Entity> {
+		match self {
+			Self::Entity(value) => Some(*value),
+			_ => None,
+		}
+	}
+
+	/// The value as a reflected component field, shaped like the field it replaces,
+	/// so whole numbers can be written to float fields and the other way round
+	pub fn to_field(&self, current: &Value) -> Option<Value> {
+		Some(match (self, current) {
+			(Self::Bool(value), _) => Value::Bool(*value),
+			(Self::Text(value), _) => Value::Text(value.clone()),
+			(Self::Integer(value), Value::Float(_)) => Value::Float(*value as f64),
+			(Self::Integer(value), _) => Value::Integer(*value),
+			(Self::Float(value), Value::Integer(_)) => Value::Integer(value.round() as i64),
+			(Self::Float(value), Value::Unsigned(_)) => Value::Unsigned(value.round() as u64),
+			(Self::Float(value), _) => Value::Float(*value),
+			_ => return None,
+		})
+	}
+}
+
+impl From<Value> for ScriptValue {
+	fn from(value: Value) -> Self {
+		match value {
+			Value::Bool(value) => Self::Bool(value),
+			Value::Integer(value) => Self::Integer(value),
+			// Only integers above `i64::MAX` are unsigned, which scripts can't hold exactly
+			Value::Unsigned(value) => Self::Float(value as f64),
+			Value::Float(value) => Self::Float(value),
+			Value::Text(value) => Self::Text(value),
+		}
+	}
+}
+
+impl From<ScriptValue> for Dynamic {
+	fn from(value: ScriptValue) -> Self {
+		match value {
+			ScriptValue::Unit => Dynamic::UNIT,
+			ScriptValue::Bool(value) => value.into(),
+			ScriptValue::Integer(value) => value.into(),
+			ScriptValue::Float(value) => value.into(),
+			ScriptValue::Text(value) => value.into(),
+			ScriptValue::Array(items) => {
+				items.into_iter().map(Into::into).collect::<Array>().into()
+			}
+			ScriptValue::Map(entries) => entries
+				.into_iter()
+				.map(|(key, value)| (key.into(), value.into()))
+				.collect::<Map>()
+				.into(),
+			ScriptValue::Entity(entity) => Dynamic::from(entity),
+		}
+	}
+}
+
+// Anything else a script makes, such as a closure, comes across as its text
+impl From<Dynamic> for ScriptValue {
+	fn from(value: Dynamic) -> Self {
+		let value = value.flatten();
+		if value.is_unit() {
+			Self::Unit
+		} else if let Ok(value) = value.as_bool() {
+			Self::Bool(value)
+		} else if let Ok(value) = value.as_int() {
+			Self::Integer(value)
+		} else if let Ok(value) = value.as_float() {
+			Self::Float(value)
+		} else if value.is::<Entity>() {
+			Self::Entity(value.cast())
+		} else if value.is_array() {
+			Self::Array(value.cast::<Array>().into_iter().map(Self::from).collect())
+		} else if value.is_map() {
+			Self::Map(
+				value
+					.cast::<Map>()
+					.into_iter()
+					.map(|(key, value)| (key.to_string(), value.into()))
+					.collect(),
+			)
+		} else {
+			Self::Text(value.to_string())
+		}
+	}
+}
+
+impl fmt::Display for ScriptValue {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Unit => write!(f, "()"),
+			Self::Bool(value) => write!(f, "{value}"),
+			Self::Integer(value) => write!(f, "{value}"),
+			Self::Float(value) => write!(f, "{value:?}"),
+			Self::Text(value) => write!(f, "{value}"),
+			Self::Array(items) => {
+				write!(f, "[")?;
+				for (index, item) in items.iter().enumerate() {
+					let separator = if index == 0 { "" } else { ", " };
+					write!(f, "{separator}{item}")?;
+				}
+				write!(f, "]")
+			}
+			Self::Map(entries) => {
+				write!(f, "#{{")?;
+				for (index, (key, value)) in entries.iter().enumerate() {
+					let separator = if index == 0 { " " } else { ", " };
+					write!(f, "{separator}{key}: {value}")?;
+				}
+				write!(f, " }}")
+			}
+			Self::Entity(entity) => write!(f, "entity {}v{}", entity.index(), entity.generation()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn values_convert_to_the_fields_they_replace() {
+		assert_eq!(
+			ScriptValue::Integer(2).to_field(&Value::Float(0.0)),
+			Some(Value::Float(2.0))
+		);
+		assert_eq!(
+			ScriptValue::Float(2.6).to_field(&Value::Integer(0)),
+			Some(Value::Integer(3))
+		);
+		assert_eq!(ScriptValue::Unit.to_field(&Value::Bool(false)), None);
+		assert_eq!(
+			ScriptValue::from(Value::Text("hi".to_string())),
+			ScriptValue::Text("hi".to_string())
+		);
+
+		let map = ScriptValue::Map(BTreeMap::from([
+			("x".to_string(), ScriptValue::Float(1.0)),
+			(
+				"tags".to_string(),
+				ScriptValue::Array(vec![ScriptValue::Integer(1), ScriptValue::Bool(true)]),
+			),
+		]));
+		assert_eq!(map.to_string(), "#{ tags: [1, true], x: 1.0 }");
+		let dynamic: Dynamic = map.clone().into();
+		assert_eq!(ScriptValue::from(dynamic), map);
+		let entity: Dynamic = ScriptValue::Entity(Entity::default()).into();
+		assert!(entity.is::<Entity>());
+	}
+}
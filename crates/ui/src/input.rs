@@ -0,0 +1,188 @@
+use crate::tree::{Ui, WidgetId};
+use app::{
+	winit::event::{ElementState, MouseButton, TouchPhase},
+	AppEvent,
+};
+use math::Vec2;
+
+/// What happened to a button, drained from the `Ui` with `drain_events`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiEvent {
+	HoverStarted(WidgetId),
+	HoverEnded(WidgetId),
+	Pressed(WidgetId),
+	/// Sent when the button is let go, wherever the cursor is
+	Released(WidgetId),
+	/// The button was pressed and let go with the cursor still over it
+	Clicked(WidgetId),
+}
+
+impl Ui {
+	/// Updates what's hovered and pressed from the app's cursor, mouse and touch events,
+	/// returning whether the event landed on the ui so the scene behind it can ignore it.
+	/// Uses the positions from the last `layout`.
+	pub fn handle_event(&mut self, event: &AppEvent) -> bool {
+		match event {
+			AppEvent::CursorMoved { x, y, .. } => self.move_cursor(*x, *y),
+			AppEvent::MouseButton {
+				button: MouseButton::Left,
+				state,
+				..
+			} => match state {
+				ElementState::Pressed => self.press(),
+				ElementState::Released => self.release(),
+			},
+			AppEvent::MouseButton { .. } | AppEvent::MouseWheel { .. } => self.is_over_ui(),
+			AppEvent::Touch { phase, x, y, .. } => {
+				let over = self.move_cursor(*x, *y);
+				match phase {
+					TouchPhase::Started => self.press(),
+					TouchPhase::Moved => over,
+					// A finger that lifts leaves nothing hovered behind it
+					TouchPhase::Ended | TouchPhase::Cancelled => {
+						let released = self.release();
+						self.cursor = None;
+						self.update_hovered();
+						released
+					}
+				}
+			}
+			AppEvent::ScaleFactorChanged { scale, .. } => {
+				self.set_scale(*scale as f32);
+				false
+			}
+			// Buttons let go of while unfocused are never reported
+			AppEvent::FocusChanged { focused: false, .. } => {
+				if let Some(pressed) = self.pressed.take() {
+					self.events.send(UiEvent::Released(pressed));
+				}
+				false
+			}
+			_ => false,
+		}
+	}
+
+	fn is_over_ui(&self) -> bool {
+		self.cursor
+			.is_some_and(|cursor| self.hit_test(cursor).is_some())
+	}
+
+	// Positions from the app are in pixels
+	fn move_cursor(&mut self, x: f64, y: f64) -> bool {
+		self.cursor = Some(Vec2::new(x as f32, y as f32) / self.scale);
+		self.update_hovered();
+		self.is_over_ui()
+	}
+
+	fn update_hovered(&mut self) {
+		let hovered = self
+			.cursor
+			.and_then(|cursor| self.hit_test(cursor))
+			.filter(|id| {
+				self.widget(*id)
+					.is_some_and(|widget| widget.is_interactive())
+			});
+		if hovered == self.hovered {
+			return;
+		}
+		if let Some(previous) = self.hovered {
+			self.events.send(UiEvent::HoverEnded(previous));
+		}
+		if let Some(hovered) = hovered {
+			self.events.send(UiEvent::HoverStarted(hovered));
+		}
+		self.hovered = hovered;
+	}
+
+	fn press(&mut self) -> bool {
+		if let Some(hovered) = self.hovered {
+			self.pressed = Some(hovered);
+			self.events.send(UiEvent::Pressed(hovered));
+		}
+		self.is_over_ui()
+	}
+
+	fn release(&mut self) -> bool {
+		let Some(pressed) = self.pressed.take() else {
+			return self.is_over_ui();
+		};
+		self.events.send(UiEvent::Released(pressed));
+		if self.hovered == Some(pressed) {
+			self.events.send(UiEvent::Clicked(pressed));
+		}
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		style::{Dimension, Style},
+		tree::UiError,
+		widget::Widget,
+	};
+	use app::WindowId;
+
+	fn cursor(x: f64, y: f64) -> AppEvent {
+		AppEvent::CursorMoved {
+			window: WindowId::PRIMARY,
+			x,
+			y,
+			logical_x: x,
+			logical_y: y,
+		}
+	}
+
+	fn click(state: ElementState) -> AppEvent {
+		AppEvent::MouseButton {
+			window: WindowId::PRIMARY,
+			button: MouseButton::Left,
+			state,
+		}
+	}
+
+	#[test]
+	fn buttons_are_hovered_pressed_and_clicked() -> Result<(), UiError> {
+		let mut ui = Ui::new();
+		let button = ui.spawn(
+			ui.root(),
+			Widget::button("Play", "body"),
+			Style::default().with_size(Dimension::Points(100.0), Dimension::Points(40.0)),
+		)?;
+		ui.layout(800, 600);
+
+		// Clicks away from the ui are left for the scene
+		assert!(!ui.handle_event(&cursor(300.0, 300.0)));
+		assert!(!ui.handle_event(&click(ElementState::Pressed)));
+		assert!(!ui.handle_event(&click(ElementState::Released)));
+
+		assert!(ui.handle_event(&cursor(50.0, 20.0)));
+		assert!(ui.handle_event(&click(ElementState::Pressed)));
+		assert_eq!(ui.pressed(), Some(button));
+		assert!(ui.handle_event(&click(ElementState::Released)));
+		assert_eq!(
+			ui.drain_events().collect::<Vec<_>>(),
+			[
+				UiEvent::HoverStarted(button),
+				UiEvent::Pressed(button),
+				UiEvent::Released(button),
+				UiEvent::Clicked(button),
+			]
+		);
+
+		// Letting go after moving off the button doesn't click it
+		ui.handle_event(&click(ElementState::Pressed));
+		ui.handle_event(&cursor(300.0, 300.0));
+		assert!(ui.handle_event(&click(ElementState::Released)));
+		assert_eq!(
+			ui.drain_events().collect::<Vec<_>>(),
+			[
+				UiEvent::Pressed(button),
+				UiEvent::HoverEnded(button),
+				UiEvent::Released(button),
+			]
+		);
+		Ok(())
+	}
+}
@@ -0,0 +1,292 @@
+use crate::{
+	style::{Align, Dimension, Direction, Edges, Justify, Style},
+	tree::{Ui, WidgetId},
+	widget::Widget,
+};
+use math::Vec2;
+use renderer::{Font, Text};
+use std::collections::HashMap;
+use taffy::prelude::{
+	AlignItems, AvailableSpace, Display, FlexDirection, JustifyContent, LengthPercentage,
+	LengthPercentageAuto, NodeId, Size, TaffyTree,
+};
+
+/// An area of the screen in points, from its top left corner
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Rect {
+	pub position: Vec2,
+	pub size: Vec2,
+}
+
+impl Rect {
+	pub fn new(position: Vec2, size: Vec2) -> Self {
+		Self { position, size }
+	}
+
+	pub fn max(&self) -> Vec2 {
+		self.position + self.size
+	}
+
+	pub fn center(&self) -> Vec2 {
+		self.position + self.size / 2.0
+	}
+
+	pub fn contains(&self, point: Vec2) -> bool {
+		let max = self.max();
+		point.x >= self.position.x
+			&& point.y >= self.position.y
+			&& point.x < max.x
+			&& point.y < max.y
+	}
+}
+
+impl Dimension {
+	fn to_taffy(self) -> taffy::Dimension {
+		match self {
+			Self::Auto => taffy::Dimension::auto(),
+			Self::Points(points) => taffy::Dimension::length(points),
+			Self::Percent(percent) => taffy::Dimension::percent(percent / 100.0),
+		}
+	}
+}
+
+impl Edges {
+	fn to_taffy<T>(self, length: fn(f32) -> T) -> taffy::Rect<T> {
+		taffy::Rect {
+			left: length(self.left),
+			right: length(self.right),
+			top: length(self.top),
+			bottom: length(self.bottom),
+		}
+	}
+}
+
+impl Style {
+	fn to_taffy(&self) -> taffy::Style {
+		taffy::Style {
+			display: Display::Flex,
+			flex_direction: match self.direction {
+				Direction::Row => FlexDirection::Row,
+				Direction::Column => FlexDirection::Column,
+			},
+			justify_content: Some(match self.justify {
+				Justify::Start => JustifyContent::FLEX_START,
+				Justify::Center => JustifyContent::CENTER,
+				Justify::End => JustifyContent::FLEX_END,
+				Justify::SpaceBetween => JustifyContent::SPACE_BETWEEN,
+			}),
+			align_items: Some(match self.align {
+				Align::Start => AlignItems::FLEX_START,
+				Align::Center => AlignItems::CENTER,
+				Align::End => AlignItems::FLEX_END,
+				Align::Stretch => AlignItems::STRETCH,
+			}),
+			size: Size {
+				width: self.width.to_taffy(),
+				height: self.height.to_taffy(),
+			},
+			padding: self.padding.to_taffy(LengthPercentage::length),
+			margin: self.margin.to_taffy(LengthPercentageAuto::length),
+			gap: Size {
+				width: LengthPercentage::length(self.gap),
+				height: LengthPercentage::length(self.gap),
+			},
+			flex_grow: self.grow.max(0.0),
+			..taffy::Style::default()
+		}
+	}
+}
+
+// How big a label is with its text wrapped to the room it's given
+fn measure_text(
+	fonts: &HashMap<String, Font>,
+	text: Option<&mut Text>,
+	known: Size<Option<f32>>,
+	available: Size<AvailableSpace>,
+) -> Size<f32> {
+	let Some((text, font)) = text.and_then(|text| Some((&*text, fonts.get(&text.font)?))) else {
+		return Size::ZERO;
+	};
+	let max_width = known.width.or(match available.width {
+		AvailableSpace::Definite(width) => Some(width),
+		AvailableSpace::MinContent => Some(0.0),
+		AvailableSpace::MaxContent => None,
+	});
+	let text = match max_width {
+		Some(width) => text.clone().with_max_width(width),
+		None => text.clone(),
+	};
+	let size = font.layout(&text).size();
+	Size {
+		width: known.width.unwrap_or(size.x),
+		height: known.height.unwrap_or(size.y),
+	}
+}
+
+impl Ui {
+	/// Places every widget to fit a target this size in pixels, as flexbox lays them out
+	pub fn layout(&mut self, width: u32, height: u32) {
+		let size = Vec2::new(width as f32, height as f32) / self.scale;
+		let mut tree = TaffyTree::new();
+		tree.disable_rounding();
+		let root = self.root();
+		let Some(node) = self.build(&mut tree, root) else {
+			return;
+		};
+		let fonts = &self.fonts;
+		let available = Size {
+			width: AvailableSpace::Definite(size.x),
+			height: AvailableSpace::Definite(size.y),
+		};
+		let computed =
+			tree.compute_layout_with_measure(node, available, |inputs, _, text, style| {
+				taffy::compute_leaf_layout(
+					inputs,
+					style,
+					|_, _| 0.0,
+					|known, available| measure_text(fonts, text, known, available),
+				)
+			});
+		if computed.is_ok() {
+			self.place(&tree, (node, root), Vec2::ZERO);
+		}
+	}
+
+	fn visible_children(&self, id: WidgetId) -> Vec<WidgetId> {
+		self.children(id)
+			.iter()
+			.copied()
+			.filter(|child| self.style(*child).is_some_and(|style| style.visible))
+			.collect()
+	}
+
+	// Mirrors the widget and its visible children into the taffy tree. Labels
+	// without children are measured by their text.
+	fn build(&self, tree: &mut TaffyTree<Text>, id: WidgetId) -> Option<NodeId> {
+		let node = self.node(id).ok()?;
+		let style = node.style.to_taffy();
+		let children = self
+			.visible_children(id)
+			.into_iter()
+			.map(|child| self.build(tree, child))
+			.collect::<Option<Vec<_>>>()?;
+		match node.widget.text() {
+			Some(text) if children.is_empty() => tree.new_leaf_with_context(style, text.clone()),
+			_ => tree.new_with_children(style, &children),
+		}
+		.ok()
+	}
+
+	// Copies the computed layout back onto the widgets, relative to the screen
+	fn place(&mut self, tree: &TaffyTree<Text>, (node, id): (NodeId, WidgetId), origin: Vec2) {
+		let Ok(layout) = tree.layout(node) else {
+			return;
+		};
+		let rect = Rect::new(
+			origin + Vec2::new(layout.location.x, layout.location.y),
+			Vec2::new(layout.size.width, layout.size.height),
+		);
+		if let Ok(widget) = self.node_mut(id) {
+			widget.rect = rect;
+		}
+		let children = tree.children(node).unwrap_or_default();
+		for (node, child) in children.into_iter().zip(self.visible_children(id)) {
+			self.place(tree, (node, child), rect.position);
+		}
+	}
+
+	/// The topmost visible widget at the point, in points, that has a background or is a
+	/// button or an image. Other widgets are see-through.
+	pub fn hit_test(&self, point: Vec2) -> Option<WidgetId> {
+		self.hit(self.root(), point)
+	}
+
+	fn hit(&self, id: WidgetId, point: Vec2) -> Option<WidgetId> {
+		let node = self.node(id).ok()?;
+		if !node.style.visible || !node.rect.contains(point) {
+			return None;
+		}
+		// Later children are drawn over earlier ones
+		node.children
+			.iter()
+			.rev()
+			.find_map(|child| self.hit(*child, point))
+			.or_else(|| {
+				let solid = node.style.background.is_some()
+					|| matches!(node.widget, Widget::Image(_))
+					|| node.widget.is_interactive();
+				solid.then_some(id)
+			})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::style::Edges;
+	use math::Color;
+
+	fn points(width: f32, height: f32) -> Style {
+		Style::default().with_size(Dimension::Points(width), Dimension::Points(height))
+	}
+
+	#[test]
+	fn rows_grow_justify_and_stretch_children() -> Result<(), crate::tree::UiError> {
+		let mut ui = Ui::new();
+		let bar = ui.spawn(
+			ui.root(),
+			Widget::Panel,
+			Style::row()
+				.with_size(Dimension::Percent(100.0), Dimension::Points(40.0))
+				.with_padding(Edges::all(5.0))
+				.with_gap(10.0),
+		)?;
+		let icon = ui.spawn(bar, Widget::image("icon"), points(30.0, 30.0))?;
+		let spacer = ui.spawn(bar, Widget::Panel, Style::default().with_grow(1.0))?;
+		let stretched = ui.spawn(
+			bar,
+			Widget::Panel,
+			Style::default()
+				.with_size(Dimension::Points(50.0), Dimension::Auto)
+				.with_background(Color::RED),
+		)?;
+		ui.set_scale(2.0);
+		ui.layout(400, 300);
+
+		assert_eq!(
+			ui.rect(ui.root()),
+			Some(Rect::new(Vec2::ZERO, Vec2::new(200.0, 150.0)))
+		);
+		assert_eq!(
+			ui.rect(icon),
+			Some(Rect::new(Vec2::new(5.0, 5.0), Vec2::new(30.0, 30.0)))
+		);
+		// The spacer takes what's left of the row between the gaps
+		assert_eq!(
+			ui.rect(spacer),
+			Some(Rect::new(Vec2::new(45.0, 5.0), Vec2::new(90.0, 30.0)))
+		);
+		assert_eq!(
+			ui.rect(stretched),
+			Some(Rect::new(Vec2::new(145.0, 5.0), Vec2::new(50.0, 30.0)))
+		);
+		// The image and the panel with a background are solid, while the spacer,
+		// the bar and the root are see-through
+		assert_eq!(ui.hit_test(Vec2::new(20.0, 20.0)), Some(icon));
+		assert_eq!(ui.hit_test(Vec2::new(150.0, 20.0)), Some(stretched));
+		assert_eq!(ui.hit_test(Vec2::new(60.0, 20.0)), None);
+
+		let bar_style = ui.style_mut(bar).unwrap();
+		bar_style.justify = Justify::Center;
+		bar_style.align = Align::End;
+		ui.style_mut(spacer).unwrap().visible = false;
+		ui.style_mut(icon).unwrap().height = Dimension::Points(10.0);
+		ui.layout(400, 300);
+		assert_eq!(
+			ui.rect(icon),
+			Some(Rect::new(Vec2::new(55.0, 25.0), Vec2::new(30.0, 10.0)))
+		);
+		assert_eq!(ui.rect(stretched).map(|rect| rect.position.x), Some(95.0));
+		Ok(())
+	}
+}
@@ -0,0 +1,17 @@
+#![forbid(unsafe_code)]
+
+mod input;
+mod layout;
+mod render;
+mod style;
+mod tree;
+mod widget;
+
+pub use self::{
+	input::UiEvent,
+	layout::Rect,
+	render::UiRenderer,
+	style::{Align, Dimension, Direction, Edges, Justify, Style},
+	tree::{Ui, UiError, WidgetId},
+	widget::Widget,
+};
@@ -0,0 +1,188 @@
+use crate::{
+	tree::{Ui, WidgetId},
+	widget::Widget,
+};
+use math::{Color, GlobalTransform, Transform, Vec2};
+use renderer::{
+	wgpu, Camera, ExtractedSprites, ExtractedText, SpriteBatch, SpriteInstance, SpriteRenderer,
+	TextAlign, TextRenderer, TextSpace,
+};
+
+/// Draws a `Ui` over the scene with the renderer's sprite and text renderers.
+/// Backgrounds and images are drawn first in tree order, then every label over them.
+pub struct UiRenderer {
+	sprites: SpriteRenderer,
+	text: TextRenderer,
+}
+
+impl UiRenderer {
+	pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, format: wgpu::TextureFormat) -> Self {
+		Self {
+			sprites: SpriteRenderer::new(device, queue, format),
+			text: TextRenderer::new(device, queue, format),
+		}
+	}
+
+	/// Uploads a texture for image widgets to draw by name, replacing any with the same name
+	pub fn set_texture(&mut self, name: impl Into<String>, image: &image::RgbaImage) {
+		self.sprites.set_texture(name, image);
+	}
+
+	pub fn remove_texture(&mut self, name: &str) {
+		self.sprites.remove_texture(name);
+	}
+
+	/// Uploads the widgets as placed by the last `Ui::layout`, for a target of this size in pixels.
+	/// Fonts added to the ui since the last call are picked up here.
+	pub fn prepare(&mut self, ui: &Ui, width: u32, height: u32) {
+		for (name, font) in &ui.fonts {
+			if self.text.font(name).is_none() {
+				self.text.set_font(name.clone(), font.clone());
+			}
+		}
+		let (sprites, text) = extract(ui, width, height);
+		self.sprites.prepare(&sprites, width, height);
+		self.text.prepare(&text, width, height);
+	}
+
+	pub fn render(&self, pass: &mut wgpu::RenderPass) {
+		self.sprites.render(pass);
+		self.text.render(pass);
+	}
+}
+
+// The widgets as sprites seen by a camera one pixel per unit with the origin at the
+// top left, and as screen text
+fn extract(ui: &Ui, width: u32, height: u32) -> (ExtractedSprites, ExtractedText) {
+	let view = GlobalTransform::from(Transform::from_xyz(
+		width as f32 / 2.0,
+		-(height as f32) / 2.0,
+		0.0,
+	));
+	let mut sprites = ExtractedSprites {
+		camera: Camera::orthographic(1.0),
+		view,
+		batches: Vec::new(),
+	};
+	let mut text = ExtractedText::default();
+	extract_widget(ui, ui.root(), &mut sprites, &mut text);
+	(sprites, text)
+}
+
+fn extract_widget(ui: &Ui, id: WidgetId, sprites: &mut ExtractedSprites, text: &mut ExtractedText) {
+	let Ok(node) = ui.node(id) else {
+		return;
+	};
+	let style = &node.style;
+	if !style.visible {
+		return;
+	}
+	let scale = ui.scale;
+	let (position, size) = (node.rect.position * scale, node.rect.size * scale);
+	let center = position + size / 2.0;
+	let mut quad = |texture: &str, color: Color| {
+		let instance = SpriteInstance {
+			position: [center.x, -center.y],
+			size: size.to_array(),
+			rotation: 0.0,
+			uv: [0.0, 0.0, 1.0, 1.0],
+			color: color.to_array(),
+		};
+		match sprites.batches.last_mut() {
+			Some(batch) if batch.texture == texture => batch.instances.push(instance),
+			_ => sprites.batches.push(SpriteBatch {
+				texture: texture.to_string(),
+				instances: vec![instance],
+			}),
+		}
+	};
+
+	let background = if ui.pressed == Some(id) {
+		style.pressed.or(style.hovered).or(style.background)
+	} else if ui.hovered == Some(id) {
+		style.hovered.or(style.background)
+	} else {
+		style.background
+	};
+	if let Some(background) = background {
+		quad("", background);
+	}
+	if let Widget::Image(texture) = &node.widget {
+		quad(texture, Color::WHITE);
+	}
+
+	if let Some(label) = node.widget.text() {
+		// Placed as it was measured, in points inside the padding, then scaled to pixels
+		let padding = style.padding;
+		let content_width = (node.rect.size.x - padding.horizontal()).max(0.0);
+		let content_height = (node.rect.size.y - padding.vertical()).max(0.0);
+		let measured = ui.fonts.get(&label.font).map_or(0.0, |font| {
+			font.layout(&label.clone().with_max_width(content_width))
+				.size()
+				.y
+		});
+		let x = match label.align {
+			TextAlign::Left => 0.0,
+			TextAlign::Center => content_width / 2.0,
+			TextAlign::Right => content_width,
+		};
+		let y = (content_height - measured).max(0.0) / 2.0;
+		let origin = (node.rect.position + Vec2::new(padding.left + x, padding.top + y)) * scale;
+		let mut label = label.clone().with_max_width(content_width * scale);
+		label.size *= scale;
+		label.space = TextSpace::Screen;
+		text.texts.push((
+			label,
+			GlobalTransform::from(Transform::from_xyz(origin.x, origin.y, 0.0)),
+		));
+	}
+
+	for child in &node.children {
+		extract_widget(ui, *child, sprites, text);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		style::{Dimension, Edges, Style},
+		tree::UiError,
+	};
+
+	#[test]
+	fn widgets_are_drawn_in_pixels_from_the_top_left() -> Result<(), UiError> {
+		let mut ui = Ui::new();
+		let panel = ui.spawn(
+			ui.root(),
+			Widget::Panel,
+			Style::default()
+				.with_size(Dimension::Points(100.0), Dimension::Points(50.0))
+				.with_padding(Edges::all(10.0))
+				.with_background(Color::BLACK),
+		)?;
+		ui.spawn(panel, Widget::image("icon"), Style::default())?;
+		ui.spawn(panel, Widget::label("Score", "body"), Style::default())?;
+		ui.set_scale(2.0);
+		ui.layout(800, 600);
+
+		let (sprites, text) = extract(&ui, 800, 600);
+		let camera = renderer::CameraView::new(&sprites.camera, &sprites.view, 800, 600);
+		let corner = camera.view_projection().project_point3(math::Vec3::ZERO);
+		assert_eq!((corner.x, corner.y), (-1.0, 1.0));
+
+		assert_eq!(sprites.batches.len(), 2);
+		assert_eq!(sprites.batches[0].texture, "");
+		assert_eq!(sprites.batches[0].instances[0].position, [100.0, -50.0]);
+		assert_eq!(sprites.batches[0].instances[0].size, [200.0, 100.0]);
+		assert_eq!(sprites.batches[1].texture, "icon");
+
+		let (label, transform) = &text.texts[0];
+		assert_eq!(label.size, 32.0);
+		assert_eq!(label.max_width, Some(160.0));
+		assert_eq!(label.space, TextSpace::Screen);
+		// Under the image, which has no height of its own, inside the panel's padding
+		assert_eq!(transform.translation().truncate().to_array(), [20.0, 20.0]);
+		Ok(())
+	}
+}
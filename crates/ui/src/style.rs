@@ -0,0 +1,182 @@
+use math::Color;
+
+/// Which way a widget lays out its children
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+	/// Left to right
+	Row,
+	/// Top to bottom
+	#[default]
+	Column,
+}
+
+/// Where children go along the direction they're laid out in, when there's room left
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Justify {
+	#[default]
+	Start,
+	Center,
+	End,
+	/// The first and last children at the ends, with the room left shared between the rest
+	SpaceBetween,
+}
+
+/// Where children go across the direction they're laid out in
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+	Start,
+	Center,
+	End,
+	/// Children without a set size fill the widget
+	#[default]
+	Stretch,
+}
+
+/// A width or height
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Dimension {
+	/// Fits what the widget holds
+	#[default]
+	Auto,
+	Points(f32),
+	/// Of the space inside the parent's padding, from 0 to 100
+	Percent(f32),
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Edges {
+	pub left: f32,
+	pub right: f32,
+	pub top: f32,
+	pub bottom: f32,
+}
+
+impl Edges {
+	pub fn all(value: f32) -> Self {
+		Self::symmetric(value, value)
+	}
+
+	pub fn symmetric(horizontal: f32, vertical: f32) -> Self {
+		Self {
+			left: horizontal,
+			right: horizontal,
+			top: vertical,
+			bottom: vertical,
+		}
+	}
+
+	pub fn horizontal(&self) -> f32 {
+		self.left + self.right
+	}
+
+	pub fn vertical(&self) -> f32 {
+		self.top + self.bottom
+	}
+}
+
+/// How a widget is sized, lays out its children and looks, in points.
+/// A point is a pixel times the `Ui`'s scale.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Style {
+	pub direction: Direction,
+	pub justify: Justify,
+	pub align: Align,
+	pub width: Dimension,
+	pub height: Dimension,
+	pub padding: Edges,
+	pub margin: Edges,
+	/// Between each child
+	pub gap: f32,
+	/// How much of the room left in its parent the widget takes, shared by
+	/// the parent's children in proportion to their grow
+	pub grow: f32,
+	/// Without one, the widget is see-through and clicks pass through it
+	pub background: Option<Color>,
+	/// The background of a button with the cursor over it
+	pub hovered: Option<Color>,
+	/// The background of a button being held down
+	pub pressed: Option<Color>,
+	/// Hidden widgets take no room, and neither they nor their children are drawn
+	pub visible: bool,
+}
+
+impl Default for Style {
+	fn default() -> Self {
+		Self {
+			direction: Direction::Column,
+			justify: Justify::Start,
+			align: Align::Stretch,
+			width: Dimension::Auto,
+			height: Dimension::Auto,
+			padding: Edges::default(),
+			margin: Edges::default(),
+			gap: 0.0,
+			grow: 0.0,
+			background: None,
+			hovered: None,
+			pressed: None,
+			visible: true,
+		}
+	}
+}
+
+impl Style {
+	pub fn row() -> Self {
+		Self {
+			direction: Direction::Row,
+			..Self::default()
+		}
+	}
+
+	pub fn column() -> Self {
+		Self::default()
+	}
+
+	pub fn with_size(self, width: Dimension, height: Dimension) -> Self {
+		Self {
+			width,
+			height,
+			..self
+		}
+	}
+
+	pub fn with_padding(self, padding: Edges) -> Self {
+		Self { padding, ..self }
+	}
+
+	pub fn with_margin(self, margin: Edges) -> Self {
+		Self { margin, ..self }
+	}
+
+	pub fn with_gap(self, gap: f32) -> Self {
+		Self { gap, ..self }
+	}
+
+	pub fn with_grow(self, grow: f32) -> Self {
+		Self { grow, ..self }
+	}
+
+	pub fn with_justify(self, justify: Justify) -> Self {
+		Self { justify, ..self }
+	}
+
+	pub fn with_align(self, align: Align) -> Self {
+		Self { align, ..self }
+	}
+
+	pub fn with_background(self, background: Color) -> Self {
+		Self {
+			background: Some(background),
+			..self
+		}
+	}
+
+	/// Backgrounds for a button with the cursor over it and while it's held down
+	pub fn with_interaction(self, hovered: Color, pressed: Color) -> Self {
+		Self {
+			hovered: Some(hovered),
+			pressed: Some(pressed),
+			..self
+		}
+	}
+}
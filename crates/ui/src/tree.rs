@@ -0,0 +1,273 @@
+use crate::{
+	input::UiEvent,
+	layout::Rect,
+	style::{Dimension, Style},
+	widget::Widget,
+};
+use ecs::events::Events;
+use math::Vec2;
+use renderer::Font;
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UiError {
+	#[error("Widget not found: {0:?}")]
+	WidgetNotFound(WidgetId),
+
+	#[error("The root widget can't be removed")]
+	RemoveRoot,
+}
+
+type Result<T, E = UiError> = std::result::Result<T, E>;
+
+/// A widget in a `Ui`. Ids of removed widgets aren't reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WidgetId {
+	index: u32,
+	generation: u32,
+}
+
+#[derive(Debug)]
+pub(crate) struct Node {
+	pub(crate) widget: Widget,
+	pub(crate) style: Style,
+	pub(crate) parent: Option<WidgetId>,
+	pub(crate) children: Vec<WidgetId>,
+	pub(crate) rect: Rect,
+}
+
+#[derive(Debug, Default)]
+struct Slot {
+	generation: u32,
+	node: Option<Node>,
+}
+
+/// A retained tree of widgets for HUDs and menus, drawn over the scene with a `UiRenderer`.
+///
+/// Widgets are added under the root, which fills the screen, and stay until removed.
+/// Each frame, pass the app's events to `handle_event`, call `layout` with the size of
+/// the target, then read what was clicked from `drain_events`.
+#[derive(Debug)]
+pub struct Ui {
+	slots: Vec<Slot>,
+	free: Vec<u32>,
+	root: WidgetId,
+	pub(crate) fonts: HashMap<String, Font>,
+	pub(crate) scale: f32,
+	pub(crate) cursor: Option<Vec2>,
+	pub(crate) hovered: Option<WidgetId>,
+	pub(crate) pressed: Option<WidgetId>,
+	pub(crate) events: Events<UiEvent>,
+}
+
+impl Default for Ui {
+	fn default() -> Self {
+		let root = Node {
+			widget: Widget::Panel,
+			style: Style::default().with_size(Dimension::Percent(100.0), Dimension::Percent(100.0)),
+			parent: None,
+			children: Vec::new(),
+			rect: Rect::default(),
+		};
+		Self {
+			slots: vec![Slot {
+				generation: 0,
+				node: Some(root),
+			}],
+			free: Vec::new(),
+			root: WidgetId {
+				index: 0,
+				generation: 0,
+			},
+			fonts: HashMap::new(),
+			scale: 1.0,
+			cursor: None,
+			hovered: None,
+			pressed: None,
+			events: Events::new(),
+		}
+	}
+}
+
+impl Ui {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// The panel every other widget is under, filling the target
+	pub fn root(&self) -> WidgetId {
+		self.root
+	}
+
+	/// Adds a widget as the parent's last child
+	pub fn spawn(&mut self, parent: WidgetId, widget: Widget, style: Style) -> Result<WidgetId> {
+		self.node(parent)?;
+		let node = Node {
+			widget,
+			style,
+			parent: Some(parent),
+			children: Vec::new(),
+			rect: Rect::default(),
+		};
+		let id = match self.free.pop() {
+			Some(index) => {
+				let slot = &mut self.slots[index as usize];
+				slot.node = Some(node);
+				WidgetId {
+					index,
+					generation: slot.generation,
+				}
+			}
+			None => {
+				self.slots.push(Slot {
+					generation: 0,
+					node: Some(node),
+				});
+				WidgetId {
+					index: self.slots.len() as u32 - 1,
+					generation: 0,
+				}
+			}
+		};
+		self.node_mut(parent)?.children.push(id);
+		Ok(id)
+	}
+
+	/// Removes the widget along with everything under it
+	pub fn despawn(&mut self, id: WidgetId) -> Result<()> {
+		if id == self.root {
+			return Err(UiError::RemoveRoot);
+		}
+		if let Some(parent) = self.node(id)?.parent {
+			self.node_mut(parent)?.children.retain(|child| *child != id);
+		}
+		let mut removed = vec![id];
+		while let Some(id) = removed.pop() {
+			let slot = &mut self.slots[id.index as usize];
+			if let Some(node) = slot.node.take() {
+				removed.extend(node.children);
+			}
+			slot.generation += 1;
+			self.free.push(id.index);
+			if self.hovered == Some(id) {
+				self.hovered = None;
+			}
+			if self.pressed == Some(id) {
+				self.pressed = None;
+			}
+		}
+		Ok(())
+	}
+
+	pub fn contains(&self, id: WidgetId) -> bool {
+		self.node(id).is_ok()
+	}
+
+	pub fn widget(&self, id: WidgetId) -> Option<&Widget> {
+		self.node(id).ok().map(|node| &node.widget)
+	}
+
+	pub fn widget_mut(&mut self, id: WidgetId) -> Option<&mut Widget> {
+		self.node_mut(id).ok().map(|node| &mut node.widget)
+	}
+
+	pub fn style(&self, id: WidgetId) -> Option<&Style> {
+		self.node(id).ok().map(|node| &node.style)
+	}
+
+	pub fn style_mut(&mut self, id: WidgetId) -> Option<&mut Style> {
+		self.node_mut(id).ok().map(|node| &mut node.style)
+	}
+
+	pub fn parent(&self, id: WidgetId) -> Option<WidgetId> {
+		self.node(id).ok()?.parent
+	}
+
+	/// In the order they're laid out and drawn
+	pub fn children(&self, id: WidgetId) -> &[WidgetId] {
+		self.node(id).map_or(&[], |node| &node.children)
+	}
+
+	/// Where the widget was put by the last `layout`, in points from the top left
+	pub fn rect(&self, id: WidgetId) -> Option<Rect> {
+		self.node(id).ok().map(|node| node.rect)
+	}
+
+	/// Adds a font for labels to be measured and drawn with by name
+	pub fn set_font(&mut self, name: impl Into<String>, font: Font) {
+		self.fonts.insert(name.into(), font);
+	}
+
+	pub fn font(&self, name: &str) -> Option<&Font> {
+		self.fonts.get(name)
+	}
+
+	/// Pixels per point, kept in step with the window's scale factor by `handle_event`
+	pub fn scale(&self) -> f32 {
+		self.scale
+	}
+
+	pub fn set_scale(&mut self, scale: f32) {
+		self.scale = scale.max(f32::EPSILON);
+	}
+
+	/// The button under the cursor
+	pub fn hovered(&self) -> Option<WidgetId> {
+		self.hovered
+	}
+
+	/// The button being held down
+	pub fn pressed(&self) -> Option<WidgetId> {
+		self.pressed
+	}
+
+	/// What happened to buttons since the events were last drained
+	pub fn drain_events(&mut self) -> impl Iterator<Item = UiEvent> + '_ {
+		self.events.drain()
+	}
+
+	pub(crate) fn node(&self, id: WidgetId) -> Result<&Node> {
+		self.slots
+			.get(id.index as usize)
+			.filter(|slot| slot.generation == id.generation)
+			.and_then(|slot| slot.node.as_ref())
+			.ok_or(UiError::WidgetNotFound(id))
+	}
+
+	pub(crate) fn node_mut(&mut self, id: WidgetId) -> Result<&mut Node> {
+		self.slots
+			.get_mut(id.index as usize)
+			.filter(|slot| slot.generation == id.generation)
+			.and_then(|slot| slot.node.as_mut())
+			.ok_or(UiError::WidgetNotFound(id))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn despawning_removes_descendants_and_stales_ids() -> Result<()> {
+		let mut ui = Ui::new();
+		let menu = ui.spawn(ui.root(), Widget::Panel, Style::default())?;
+		let button = ui.spawn(menu, Widget::button("Play", "body"), Style::default())?;
+		let footer = ui.spawn(ui.root(), Widget::Panel, Style::default())?;
+		assert_eq!(ui.children(ui.root()), [menu, footer]);
+		assert_eq!(ui.parent(button), Some(menu));
+
+		ui.despawn(menu)?;
+		assert_eq!(ui.children(ui.root()), [footer]);
+		assert!(!ui.contains(button));
+		assert_eq!(ui.despawn(menu), Err(UiError::WidgetNotFound(menu)));
+		assert_eq!(ui.despawn(ui.root()), Err(UiError::RemoveRoot));
+
+		// A freed slot is reused, but not the ids that pointed at it
+		let reused = ui.spawn(footer, Widget::Panel, Style::default())?;
+		assert!(ui.contains(reused));
+		assert_ne!(reused, menu);
+		assert_ne!(reused, button);
+		Ok(())
+	}
+}
@@ -0,0 +1,47 @@
+use renderer::{Text, TextAlign};
+
+/// What a widget in the `Ui` is. Any widget can hold others.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Widget {
+	/// Holds other widgets, drawn as its background
+	Panel,
+	/// Text wrapped to fit the widget. Its size is in points and its space is ignored.
+	Label(Text),
+	/// A label that sends `UiEvent::Clicked` when pressed and released with the cursor over it
+	Button(Text),
+	/// The texture set with `UiRenderer::set_texture` by this name, stretched over the widget
+	Image(String),
+}
+
+impl Widget {
+	pub fn label(value: impl Into<String>, font: impl Into<String>) -> Self {
+		Self::Label(Text::new(value, font))
+	}
+
+	/// A button with its label centered in it
+	pub fn button(value: impl Into<String>, font: impl Into<String>) -> Self {
+		Self::Button(Text::new(value, font).with_align(TextAlign::Center))
+	}
+
+	pub fn image(texture: impl Into<String>) -> Self {
+		Self::Image(texture.into())
+	}
+
+	pub fn text(&self) -> Option<&Text> {
+		match self {
+			Self::Label(text) | Self::Button(text) => Some(text),
+			Self::Panel | Self::Image(_) => None,
+		}
+	}
+
+	pub fn text_mut(&mut self) -> Option<&mut Text> {
+		match self {
+			Self::Label(text) | Self::Button(text) => Some(text),
+			Self::Panel | Self::Image(_) => None,
+		}
+	}
+
+	pub fn is_interactive(&self) -> bool {
+		matches!(self, Self::Button(_))
+	}
+}
@@ -1,4 +1,19 @@
+pub use animation;
 pub use app;
+#[cfg(not(target_arch = "wasm32"))]
+pub use assets;
+pub use behavior;
 pub use bus;
+pub use console;
+pub use diagnostics;
 pub use ecs;
 pub use graph;
+pub use i18n;
+pub use math;
+pub use nav;
+pub use net;
+#[cfg(feature = "physics")]
+pub use physics;
+pub use renderer;
+pub use scripting;
+pub use ui;